@@ -7,23 +7,90 @@ use fdf_sdk::OperatorRegistry;
 #[command(name = "fdf")]
 #[command(about = "Foundation Data Factory - High-performance data pipeline")]
 struct Cli {
-    #[arg(short, long)]
-    config: String,
+    #[arg(short, long, required_unless_present = "list_operators")]
+    config: Option<String>,
+    /// Print the available operators (with plugins listed in --config, if given) and exit
+    #[arg(long)]
+    list_operators: bool,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    if cli.list_operators {
+        let mut registry = OperatorRegistry::new();
+        register_all(&mut registry)?;
+        if let Some(config) = &cli.config {
+            let spec: PipelineSpec = serde_yaml::from_str(&std::fs::read_to_string(config)?)?;
+            for path in &spec.plugins {
+                fdf_sdk::plugin::load_plugin(path, &mut registry)?;
+            }
+        }
+        print_operators(&registry);
+        return Ok(());
+    }
+
     // Load YAML spec
-    let spec: PipelineSpec = serde_yaml::from_str(&std::fs::read_to_string(&cli.config)?)?;
+    let config = cli.config.expect("required unless --list-operators");
+    let spec: PipelineSpec = serde_yaml::from_str(&std::fs::read_to_string(&config)?)?;
+
+    // With a stdout sink, stdout carries the data and messages go to stderr
+    let to_stdout = spec.sink.kind == "stdout";
 
     // Register all operators
     let mut registry = OperatorRegistry::new();
     register_all(&mut registry)?;
 
+    // Register operators from native plugins listed in the spec
+    for path in &spec.plugins {
+        fdf_sdk::plugin::load_plugin(path, &mut registry)?;
+    }
+
     // Run pipeline (statistics are printed by run_pipeline)
     fdf_engine::run_pipeline(spec, &registry)?;
 
-    println!("✓ Pipeline completed successfully");
+    if to_stdout {
+        eprintln!("✓ Pipeline completed successfully");
+    } else {
+        println!("✓ Pipeline completed successfully");
+    }
     Ok(())
 }
+
+/// Operators grouped by namespace, with their parameters and aliases
+fn print_operators(registry: &OperatorRegistry) {
+    println!(
+        "Optional operator groups: {}",
+        match fdf_operators::enabled_features().join(", ") {
+            features if features.is_empty() => "none".to_string(),
+            features => features,
+        }
+    );
+    let mut namespace = None;
+    for (name, metadata) in registry.list() {
+        let current = OperatorRegistry::namespace(name);
+        if namespace != Some(current) {
+            println!("\n[{}]", current.unwrap_or("(no namespace)"));
+            namespace = Some(current);
+        }
+        let category = metadata.category.map(|c| c.as_str()).unwrap_or("-");
+        println!("{} ({}, v{})", name, category, metadata.version);
+        if !metadata.description.is_empty() {
+            println!("    {}", metadata.description);
+        }
+        for param in &metadata.params {
+            let default = match &param.default {
+                Some(default) => format!(", default {}", default),
+                None if param.required => ", required".to_string(),
+                None => String::new(),
+            };
+            println!(
+                "    - {}: {}{} - {}",
+                param.name, param.param_type, default, param.description
+            );
+        }
+        if !metadata.aliases.is_empty() {
+            println!("    aliases: {}", metadata.aliases.join(", "));
+        }
+    }
+}
@@ -8,6 +8,15 @@ use crate::spec::{SinkSpec, SourceSpec};
 type WriterFactoryFn =
     Box<dyn Fn(&str, Arc<Schema>) -> anyhow::Result<Box<dyn Writer>> + Send + Sync>;
 
+// Arrow <-> JSON value conversions shared by readers and writers
+pub mod convert;
+
+// HuggingFace Hub dataset upload (hf:// sink uris)
+pub mod hub;
+
+// Local file / object storage destinations for writers
+pub mod output;
+
 // Reader trait and implementations
 pub mod reader;
 
@@ -16,7 +25,19 @@ pub use reader::{jsonl::JsonlReader, multi_file::MultiFileReader, parquet::Parqu
 // Writer trait and implementations
 pub mod writer;
 
-pub use writer::{jsonl::JsonlWriter, parquet::ParquetWriter, sharded::ShardedWriter, Writer};
+pub use writer::{
+    arrow_ipc::{ArrowIpcWriter, IpcFormat},
+    csv::CsvWriter,
+    elasticsearch::ElasticsearchWriter,
+    jsonl::{JsonlCompression, JsonlWriter},
+    parquet::ParquetWriter,
+    qdrant::QdrantWriter,
+    sharded::{ShardMode, ShardedWriter},
+    sqlite::SqliteWriter,
+    stdout::StdoutWriter,
+    validated::{DeclaredSchema, ValidatingWriter},
+    Writer,
+};
 
 /// Factory for creating readers based on source configuration
 pub struct ReaderFactory;
@@ -29,12 +50,29 @@ impl ReaderFactory {
     /// - Directory: specify directory path in uris (reads all matching files in the directory)
     /// - HuggingFace dataset via hf:// protocol: use hf://datasets/org/dataset/path/to/file.parquet in uris
     /// - HuggingFace dataset via kind: specify kind="huggingface" with dataset name in uris (legacy)
+    ///
+    /// Column casts (source.casts) are applied on top of whichever reader is selected.
     pub fn create(spec: &SourceSpec) -> anyhow::Result<Box<dyn Reader>> {
         // Handle HuggingFace datasets
-        if spec.kind == "huggingface" || spec.kind == "hf" {
-            return Self::create_huggingface_reader(spec);
+        let reader = if spec.kind == "huggingface" || spec.kind == "hf" {
+            Self::create_huggingface_reader(spec)?
+        } else {
+            Self::create_file_reader(spec)?
+        };
+
+        // Apply casts last so they see the renamed columns
+        if spec.casts.is_empty() {
+            Ok(reader)
+        } else {
+            Ok(Box::new(reader::cast::CastReader::new(
+                reader,
+                spec.casts.clone(),
+            )?))
         }
+    }
 
+    /// Create a reader for local files, directories and hf:// URIs
+    fn create_file_reader(spec: &SourceSpec) -> anyhow::Result<Box<dyn Reader>> {
         // Collect all file paths to read
         let mut file_paths = Vec::new();
 
@@ -107,11 +145,29 @@ impl ReaderFactory {
             readers.push(reader);
         }
 
-        // If only one reader, return it directly; otherwise wrap in MultiFileReader
+        Self::combine_readers(readers, spec)
+    }
+
+    /// Combine per-file readers according to source.read_order
+    /// If only one reader, return it directly; otherwise wrap in MultiFileReader
+    fn combine_readers(
+        readers: Vec<Box<dyn Reader>>,
+        spec: &SourceSpec,
+    ) -> anyhow::Result<Box<dyn Reader>> {
         if readers.len() == 1 {
-            Ok(readers.into_iter().next().unwrap())
-        } else {
-            Ok(Box::new(reader::multi_file::MultiFileReader::new(readers)?))
+            return Ok(readers.into_iter().next().unwrap());
+        }
+
+        match spec.read_order.as_str() {
+            "sequential" => Ok(Box::new(reader::multi_file::MultiFileReader::new(readers)?)),
+            "interleaved" => Ok(Box::new(reader::multi_file::MultiFileReader::interleaved(
+                readers,
+                spec.interleave_chunk_size,
+            )?)),
+            other => Err(anyhow::anyhow!(
+                "Unknown read_order '{}'. Expected 'sequential' or 'interleaved'",
+                other
+            )),
         }
     }
 
@@ -154,10 +210,7 @@ impl ReaderFactory {
 
         rt.block_on(async {
             // Try to get HuggingFace token from environment variable
-            let token = std::env::var("HF_TOKEN")
-                .or_else(|_| std::env::var("HUGGINGFACE_TOKEN"))
-                .or_else(|_| std::env::var("HF_API_TOKEN"))
-                .ok();
+            let token = hub::token();
 
             use hf_hub::api::tokio::ApiBuilder;
             let mut builder = ApiBuilder::new().with_progress(true);
@@ -235,11 +288,7 @@ impl ReaderFactory {
         }
 
         // Combine readers if multiple
-        let combined_reader: Box<dyn Reader> = if readers.len() > 1 {
-            Box::new(reader::multi_file::MultiFileReader::new(readers)?)
-        } else {
-            readers.into_iter().next().unwrap()
-        };
+        let combined_reader = Self::combine_readers(readers, spec)?;
 
         // Apply column filter if column mapping is specified
         if spec.columns.mapping.is_empty() {
@@ -256,68 +305,279 @@ impl ReaderFactory {
 /// Factory for creating writers based on sink configuration
 pub struct WriterFactory;
 
+/// File extensions that mark a sink uri as a single file rather than a directory
+pub(crate) const FILE_EXTENSIONS: &[&str] = &[
+    ".parquet",
+    ".jsonl",
+    ".json",
+    ".jsonl.gz",
+    ".jsonl.zst",
+    ".csv",
+    ".tsv",
+    ".arrow",
+    ".ipc",
+    ".arrows",
+    ".db",
+    ".sqlite",
+];
+
+/// Known extension (FILE_EXTENSIONS) a path ends with, the longest if several match
+/// (".jsonl.gz" rather than a shorter suffix of it)
+pub(crate) fn file_extension(path: &str) -> Option<&'static str> {
+    FILE_EXTENSIONS
+        .iter()
+        .copied()
+        .filter(|ext| path.ends_with(ext))
+        .max_by_key(|ext| ext.len())
+}
+
 impl WriterFactory {
     /// Create a writer from sink spec
     /// Automatically enables sharding if uri is a directory, disables if uri is a file
+    /// With a declared schema (sink.schema), samples are validated and only declared columns are written
     pub fn create(spec: &SinkSpec, schema: Arc<Schema>) -> anyhow::Result<Box<dyn Writer>> {
-        // Determine base writer type
-        let is_parquet = spec.kind == "parquet" || spec.uri.ends_with(".parquet");
+        if let Some(declared) = &spec.schema {
+            let declared = DeclaredSchema::new(declared)?;
+            let inner = Self::create_unchecked(spec, declared.arrow_schema().clone())?;
+            return Ok(Box::new(ValidatingWriter::new(inner, declared)));
+        }
+        Self::create_unchecked(spec, schema)
+    }
+
+    /// Create the writer for a sink without schema validation
+    fn create_unchecked(spec: &SinkSpec, schema: Arc<Schema>) -> anyhow::Result<Box<dyn Writer>> {
+        // stdout has no uri, files or sharding
+        if spec.kind == "stdout" {
+            return Ok(Box::new(StdoutWriter::new(schema)));
+        }
+        // Search clusters and vector databases: the uri is the service url
+        if spec.kind == "elasticsearch" || spec.kind == "opensearch" {
+            return Ok(Box::new(ElasticsearchWriter::new(
+                &spec.uri,
+                schema,
+                spec.elasticsearch.clone(),
+            )?));
+        }
+        if spec.kind == "qdrant" {
+            return Ok(Box::new(QdrantWriter::new(
+                &spec.uri,
+                schema,
+                spec.qdrant.clone(),
+            )?));
+        }
 
         // Check if uri is a directory or a file
         let path = Path::new(&spec.uri);
         // If uri ends with a known extension, treat as file; otherwise treat as directory
-        let is_directory = !spec.uri.ends_with(".parquet")
-            && !spec.uri.ends_with(".jsonl")
-            && !spec.uri.ends_with(".json")
+        let is_directory = file_extension(&spec.uri).is_none()
             && (path.is_dir() || !path.exists() || spec.uri.ends_with('/'));
 
+        // Determine base writer type
+        let format = Self::format(spec, is_directory);
+
+        // Handle existing output according to sink.mode
+        let append = Self::apply_mode(spec, is_directory)?;
+
         // Enable sharding if uri is a directory
         if is_directory {
-            // Create directory if it doesn't exist
-            std::fs::create_dir_all(&spec.uri)?;
+            // Create directory if it doesn't exist (no-op for object storage)
+            output::create_dir_all(&spec.uri)?;
 
-            let create_writer: WriterFactoryFn = if is_parquet {
-                Box::new(|path: &str, s: Arc<Schema>| {
-                    Ok(Box::new(ParquetWriter::new(path, s)?) as Box<dyn Writer>)
-                })
-            } else {
-                Box::new(|path: &str, s: Arc<Schema>| {
-                    Ok(Box::new(JsonlWriter::new(path, s)?) as Box<dyn Writer>)
-                })
-            };
+            let sink = spec.clone();
+            let create_writer: WriterFactoryFn = Box::new(move |path: &str, s: Arc<Schema>| {
+                Self::create_file_writer(format, path, s, &sink)
+            });
 
             // Determine default shard name pattern based on extension
-            let default_pattern = if is_parquet {
-                "part-{shard_id:08}.parquet"
-            } else {
-                "part-{shard_id:08}.jsonl"
-            };
+            let default_pattern = format!("part-{{shard_id:08}}{}", Self::extension(spec));
 
             Ok(Box::new(writer::sharded::ShardedWriter::new(
                 &spec.uri,
                 schema,
-                spec.shard_key.clone(),
-                spec.samples_per_shard,
-                spec.shard_name_pattern
-                    .clone()
-                    .or_else(|| Some(default_pattern.to_string())),
+                Self::shard_mode(spec)?,
+                spec.shard_name_pattern.clone().or(Some(default_pattern)),
                 create_writer,
+                append,
             )?) as Box<dyn Writer>)
         } else {
             // Create regular (non-sharded) writer for file path
-            let writer: Box<dyn Writer> = if is_parquet {
-                Box::new(writer::parquet::ParquetWriter::new(&spec.uri, schema)?)
-            } else if spec.kind == "jsonl"
-                || spec.kind == "json"
-                || spec.uri.ends_with(".jsonl")
+            Self::create_file_writer(format, &spec.uri, schema, spec)
+        }
+    }
+
+    /// Apply sink.mode to existing output at the sink uri; returns whether to append
+    /// - overwrite: clear the directory (a single file is replaced on close)
+    /// - append: continue shard numbering after existing shards (single files cannot be appended)
+    /// - error_if_exists: fail if the directory contains files or the file exists
+    fn apply_mode(spec: &SinkSpec, is_directory: bool) -> anyhow::Result<bool> {
+        match spec.mode.as_str() {
+            "overwrite" => {
+                if is_directory {
+                    for file in output::list_files(&spec.uri)? {
+                        output::remove(&file);
+                    }
+                }
+                Ok(false)
+            }
+            "append" => {
+                if !is_directory && output::exists(&spec.uri)? {
+                    return Err(anyhow::anyhow!(
+                        "Cannot append to existing file '{}'. Use a directory uri to append new shards",
+                        spec.uri
+                    ));
+                }
+                Ok(true)
+            }
+            "error_if_exists" => {
+                let exists = if is_directory {
+                    !output::list_files(&spec.uri)?.is_empty()
+                } else {
+                    output::exists(&spec.uri)?
+                };
+                if exists {
+                    return Err(anyhow::anyhow!(
+                        "Sink output '{}' already exists (mode: error_if_exists)",
+                        spec.uri
+                    ));
+                }
+                Ok(false)
+            }
+            other => Err(anyhow::anyhow!(
+                "Unknown sink mode '{}'. Expected: overwrite, append, error_if_exists",
+                other
+            )),
+        }
+    }
+
+    /// Whether the sink writes files under its uri (false for stdout, search and vector databases)
+    pub fn writes_files(spec: &SinkSpec) -> bool {
+        !matches!(
+            spec.kind.as_str(),
+            "stdout" | "elasticsearch" | "opensearch" | "qdrant"
+        )
+    }
+
+    /// Sharding mode: hash sharding when shard_key is set, sequential otherwise
+    fn shard_mode(spec: &SinkSpec) -> anyhow::Result<ShardMode> {
+        match (&spec.shard_key, spec.num_shards) {
+            (Some(key), Some(num_shards)) => Ok(ShardMode::Hash {
+                key: key.clone(),
+                num_shards,
+            }),
+            (Some(key), None) => Err(anyhow::anyhow!(
+                "sink.shard_key '{}' requires sink.num_shards (the fixed number of hash shards)",
+                key
+            )),
+            (None, _) => Ok(ShardMode::Sequential {
+                samples_per_shard: spec.samples_per_shard,
+            }),
+        }
+    }
+
+    /// File extension (with leading dot) for files written by this sink
+    pub fn extension(spec: &SinkSpec) -> &'static str {
+        match spec.kind.as_str() {
+            "parquet" => ".parquet",
+            "csv" => ".csv",
+            "tsv" => ".tsv",
+            "huggingface" | "hf" => ".parquet",
+            "arrow" | "ipc" => ".arrow",
+            "arrow_stream" => ".arrows",
+            "sqlite" => ".db",
+            "stdout" => ".jsonl",
+            _ => match Self::jsonl_compression(spec, &spec.uri) {
+                Ok(JsonlCompression::Gzip) => ".jsonl.gz",
+                Ok(JsonlCompression::Zstd) => ".jsonl.zst",
+                _ => ".jsonl",
+            },
+        }
+    }
+
+    /// JSONL compression from sink.compression, falling back to the path extension
+    fn jsonl_compression(spec: &SinkSpec, path: &str) -> anyhow::Result<JsonlCompression> {
+        match &spec.compression {
+            Some(name) => JsonlCompression::parse(name),
+            None => Ok(JsonlCompression::from_path(path)),
+        }
+    }
+
+    /// Resolve the output format from the sink kind, falling back to the uri extension
+    /// Unknown formats default to jsonl for directories and parquet for single files
+    fn format(spec: &SinkSpec, is_directory: bool) -> &'static str {
+        match spec.kind.as_str() {
+            "parquet" | "huggingface" | "hf" => "parquet",
+            "jsonl" | "json" | "jsonline" => "jsonl",
+            "csv" | "tsv" => "csv",
+            "arrow" | "ipc" => "arrow",
+            "arrow_stream" => "arrow_stream",
+            "sqlite" => "sqlite",
+            _ if spec.uri.ends_with(".parquet") => "parquet",
+            _ if spec.uri.ends_with(".jsonl")
                 || spec.uri.ends_with(".json")
+                || spec.uri.ends_with(".jsonl.gz")
+                || spec.uri.ends_with(".jsonl.zst") =>
             {
-                Box::new(writer::jsonl::JsonlWriter::new(&spec.uri, schema)?)
-            } else {
-                // Default to parquet
-                Box::new(writer::parquet::ParquetWriter::new(&spec.uri, schema)?)
-            };
-            Ok(writer)
+                "jsonl"
+            }
+            _ if spec.uri.ends_with(".csv") || spec.uri.ends_with(".tsv") => "csv",
+            _ if spec.uri.ends_with(".arrow") || spec.uri.ends_with(".ipc") => "arrow",
+            _ if spec.uri.ends_with(".arrows") => "arrow_stream",
+            _ if spec.uri.ends_with(".db") || spec.uri.ends_with(".sqlite") => "sqlite",
+            _ if is_directory => "jsonl",
+            _ => "parquet",
+        }
+    }
+
+    /// Create a writer for a single output file
+    fn create_file_writer(
+        format: &str,
+        path: &str,
+        schema: Arc<Schema>,
+        spec: &SinkSpec,
+    ) -> anyhow::Result<Box<dyn Writer>> {
+        // Hub outputs are written to a local staging file and uploaded on close
+        if hub::is_hub_uri(path) {
+            let staging = hub::staging_path(path)?;
+            let inner = Self::create_file_writer(format, &staging, schema, spec)?;
+            return Ok(Box::new(hub::HubWriter::new(
+                inner,
+                path,
+                &staging,
+                spec.hub.clone(),
+            )?));
         }
+
+        let writer: Box<dyn Writer> = match format {
+            "jsonl" => Box::new(JsonlWriter::with_compression(
+                path,
+                schema,
+                Self::jsonl_compression(spec, path)?,
+                spec.compression_level,
+            )?),
+            "csv" => {
+                let mut options = spec.csv.clone();
+                if options.delimiter.is_none() && (spec.kind == "tsv" || path.ends_with(".tsv")) {
+                    options.delimiter = Some("\t".to_string());
+                }
+                Box::new(CsvWriter::with_options(path, schema, options)?)
+            }
+            "arrow" => Box::new(ArrowIpcWriter::with_format(path, schema, IpcFormat::File)?),
+            "arrow_stream" => Box::new(ArrowIpcWriter::with_format(
+                path,
+                schema,
+                IpcFormat::Stream,
+            )?),
+            "sqlite" => Box::new(SqliteWriter::with_options(
+                path,
+                schema,
+                spec.sqlite.clone(),
+            )?),
+            _ => Box::new(ParquetWriter::with_properties(
+                path,
+                schema,
+                writer::parquet::writer_properties(spec)?,
+            )?),
+        };
+        Ok(writer)
     }
 }
@@ -0,0 +1,460 @@
+//! Conversions between Arrow arrays and the JSON values carried by samples
+//!
+//! Readers turn each Arrow value into JSON with `array_value_to_json`, and Arrow-based writers
+//! (parquet, IPC) rebuild batches of the original Arrow types with `samples_to_batch`, so types
+//! such as Int32, Timestamp, Decimal, List and Struct survive a round trip through the pipeline.
+//! Binary columns are carried as the samples' raw binary fields instead of JSON.
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, NaiveDate, SecondsFormat};
+use fdf_sdk::Sample;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Convert the value at `row` of an Arrow array to JSON
+///
+/// Temporal values become ISO 8601 strings and decimals become strings (no precision loss);
+/// lists become arrays and structs/maps become objects.
+pub fn array_value_to_json(array: &dyn Array, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+
+    match array.data_type() {
+        DataType::Null => Value::Null,
+        DataType::Boolean => Value::Bool(array.as_boolean().value(row)),
+        DataType::Int8 => Value::from(array.as_primitive::<Int8Type>().value(row)),
+        DataType::Int16 => Value::from(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => Value::from(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => Value::from(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => Value::from(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => Value::from(array.as_primitive::<UInt16Type>().value(row)),
+        DataType::UInt32 => Value::from(array.as_primitive::<UInt32Type>().value(row)),
+        DataType::UInt64 => Value::from(array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float16 => float(array.as_primitive::<Float16Type>().value(row).to_f64()),
+        DataType::Float32 => float(array.as_primitive::<Float32Type>().value(row) as f64),
+        DataType::Float64 => float(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => Value::String(array.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => Value::String(array.as_string::<i64>().value(row).to_string()),
+        DataType::Utf8View => Value::String(array.as_string_view().value(row).to_string()),
+        DataType::Decimal128(_, _) => {
+            Value::String(array.as_primitive::<Decimal128Type>().value_as_string(row))
+        }
+        DataType::Decimal256(_, _) => {
+            Value::String(array.as_primitive::<Decimal256Type>().value_as_string(row))
+        }
+        DataType::Date32 => {
+            let days = array.as_primitive::<Date32Type>().value(row);
+            NaiveDate::from_num_days_from_ce_opt(days + 719_163)
+                .map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+                .unwrap_or(Value::Null)
+        }
+        DataType::Timestamp(unit, tz) => {
+            let v = match unit {
+                TimeUnit::Second => array.as_primitive::<TimestampSecondType>().value(row),
+                TimeUnit::Millisecond => {
+                    array.as_primitive::<TimestampMillisecondType>().value(row)
+                }
+                TimeUnit::Microsecond => {
+                    array.as_primitive::<TimestampMicrosecondType>().value(row)
+                }
+                TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().value(row),
+            };
+            timestamp_to_json(v, unit, tz.is_some())
+        }
+        DataType::List(_) => list_to_json(array.as_list::<i32>().value(row).as_ref()),
+        DataType::LargeList(_) => list_to_json(array.as_list::<i64>().value(row).as_ref()),
+        DataType::FixedSizeList(_, _) => {
+            list_to_json(array.as_fixed_size_list().value(row).as_ref())
+        }
+        DataType::Struct(fields) => {
+            let struct_array = array.as_struct();
+            let mut map = Map::with_capacity(fields.len());
+            for (idx, field) in fields.iter().enumerate() {
+                map.insert(
+                    field.name().clone(),
+                    array_value_to_json(struct_array.column(idx).as_ref(), row),
+                );
+            }
+            Value::Object(map)
+        }
+        DataType::Map(_, _) => {
+            let entries = array.as_map().value(row);
+            let mut map = Map::with_capacity(entries.len());
+            for idx in 0..entries.len() {
+                let key = match array_value_to_json(entries.column(0).as_ref(), idx) {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                map.insert(key, array_value_to_json(entries.column(1).as_ref(), idx));
+            }
+            Value::Object(map)
+        }
+        DataType::Dictionary(_, value_type) => {
+            match arrow::compute::cast(&array.slice(row, 1), value_type) {
+                Ok(values) => array_value_to_json(values.as_ref(), 0),
+                Err(_) => Value::Null,
+            }
+        }
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => Value::Null,
+        _ => {
+            // Remaining types (time of day, durations, intervals) use Arrow's display format
+            match ArrayFormatter::try_new(array, &FormatOptions::default()) {
+                Ok(formatter) => Value::String(formatter.value(row).to_string()),
+                Err(_) => Value::Null,
+            }
+        }
+    }
+}
+
+/// Raw bytes at `row` of a binary array; None for nulls and non-binary arrays
+pub fn array_value_to_bytes(array: &dyn Array, row: usize) -> Option<Vec<u8>> {
+    if array.is_null(row) {
+        return None;
+    }
+
+    match array.data_type() {
+        DataType::Binary => Some(array.as_binary::<i32>().value(row).to_vec()),
+        DataType::LargeBinary => Some(array.as_binary::<i64>().value(row).to_vec()),
+        DataType::BinaryView => Some(array.as_binary_view().value(row).to_vec()),
+        DataType::FixedSizeBinary(_) => Some(array.as_fixed_size_binary().value(row).to_vec()),
+        _ => None,
+    }
+}
+
+/// JSON form of a sample for text outputs (JSONL, CSV, HTTP sinks): binary fields become base64 strings
+pub fn sample_to_json(sample: &Sample) -> Cow<'_, Value> {
+    use base64::Engine;
+
+    if sample.binary_fields().is_empty() {
+        return Cow::Borrowed(sample.as_value());
+    }
+    let mut value = sample.as_value().clone();
+    if let Some(map) = value.as_object_mut() {
+        for (name, bytes) in sample.binary_fields() {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+            map.insert(name.clone(), Value::String(encoded));
+        }
+    }
+    Cow::Owned(value)
+}
+
+fn float(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn list_to_json(values: &dyn Array) -> Value {
+    Value::Array(
+        (0..values.len())
+            .map(|idx| array_value_to_json(values, idx))
+            .collect(),
+    )
+}
+
+/// Timestamps with a timezone are written in UTC with a "Z" suffix, naive ones without
+fn timestamp_to_json(v: i64, unit: &TimeUnit, has_tz: bool) -> Value {
+    let dt = match unit {
+        TimeUnit::Second => DateTime::from_timestamp(v, 0),
+        TimeUnit::Millisecond => DateTime::from_timestamp_millis(v),
+        TimeUnit::Microsecond => DateTime::from_timestamp_micros(v),
+        TimeUnit::Nanosecond => Some(DateTime::from_timestamp_nanos(v)),
+    };
+    match dt {
+        Some(dt) if has_tz => Value::String(dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        Some(dt) => Value::String(dt.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+        None => Value::Null,
+    }
+}
+
+/// Build an Arrow array of `field`'s type from one JSON value per row
+///
+/// Values that don't fit the column type become nulls instead of failing the whole batch.
+fn json_to_array(field: &Field, values: &[&Value]) -> anyhow::Result<ArrayRef> {
+    let target_type = field.data_type();
+
+    // The JSON decoder has no dictionary support and only understands offset timezones:
+    // decode the value type / UTC epochs and convert afterwards
+    let decode_type = match target_type {
+        DataType::Dictionary(_, value_type) => value_type.as_ref().clone(),
+        DataType::Timestamp(unit, Some(_)) => DataType::Timestamp(*unit, None),
+        other => other.clone(),
+    };
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        field.name(),
+        decode_type.clone(),
+        true,
+    )]));
+
+    let array = match decode_rows(&schema, values) {
+        Ok(array) => array,
+        Err(_) => {
+            // Some values don't match the column type: decode row by row and null out failures
+            let arrays: Vec<ArrayRef> = values
+                .iter()
+                .map(|value| {
+                    decode_rows(&schema, std::slice::from_ref(value))
+                        .unwrap_or_else(|_| new_null_array(&decode_type, 1))
+                })
+                .collect();
+            let refs: Vec<&dyn Array> = arrays.iter().map(|a| a.as_ref()).collect();
+            arrow::compute::concat(&refs)?
+        }
+    };
+
+    match target_type {
+        // Same epoch values, only the timezone annotation differs
+        DataType::Timestamp(_, Some(_)) => Ok(make_array(
+            array
+                .into_data()
+                .into_builder()
+                .data_type(target_type.clone())
+                .build()?,
+        )),
+        _ if &decode_type != target_type => Ok(arrow::compute::cast(&array, target_type)?),
+        _ => Ok(array),
+    }
+}
+
+/// Build a binary column from the samples' binary fields (string values are taken as UTF-8 bytes)
+fn binary_to_array(samples: &[Sample], field: &Field) -> anyhow::Result<ArrayRef> {
+    let mut builder = BinaryBuilder::new();
+    for sample in samples {
+        match (sample.get_bytes(field.name()), sample.get(field.name())) {
+            (Some(bytes), _) => builder.append_value(bytes),
+            (None, Some(Value::String(s))) => builder.append_value(s.as_bytes()),
+            _ => builder.append_null(),
+        }
+    }
+    let array: ArrayRef = Arc::new(builder.finish());
+    match field.data_type() {
+        DataType::Binary => Ok(array),
+        // Values of the wrong size for FixedSizeBinary become nulls
+        other => Ok(arrow::compute::cast(&array, other)?),
+    }
+}
+
+/// Decode JSON values into a single column using the Arrow JSON decoder
+fn decode_rows(schema: &SchemaRef, values: &[&Value]) -> anyhow::Result<ArrayRef> {
+    let name = schema.field(0).name();
+    let rows: Vec<Value> = values
+        .iter()
+        .map(|value| {
+            let mut row = Map::with_capacity(1);
+            row.insert(name.clone(), (*value).clone());
+            Value::Object(row)
+        })
+        .collect();
+
+    let mut decoder = arrow::json::ReaderBuilder::new(schema.clone())
+        .with_batch_size(rows.len().max(1))
+        .build_decoder()?;
+    decoder.serialize(&rows)?;
+    let batch = decoder
+        .flush()?
+        .ok_or_else(|| anyhow::anyhow!("No rows decoded for column '{}'", name))?;
+    Ok(batch.column(0).clone())
+}
+
+/// Infer Arrow types for fields that are not part of the input schema
+/// Uses Arrow's JSON schema inference so nested annotator output becomes List/Struct columns;
+/// falls back to Utf8 (JSON text) when a field has conflicting or null-only values.
+fn infer_field_types(values: &[&Value], field_names: &[String]) -> Vec<Field> {
+    // Only look at the requested fields so conflicts elsewhere don't affect inference
+    let projected: Vec<Value> = values
+        .iter()
+        .map(|value| {
+            let mut row = Map::new();
+            for name in field_names {
+                if let Some(v) = value.get(name) {
+                    row.insert(name.clone(), v.clone());
+                }
+            }
+            Value::Object(row)
+        })
+        .collect();
+    let inferred = arrow::json::reader::infer_json_schema_from_iterator(
+        projected.iter().map(Ok::<&Value, arrow::error::ArrowError>),
+    )
+    .ok();
+
+    field_names
+        .iter()
+        .map(|name| {
+            let data_type = inferred
+                .as_ref()
+                .and_then(|schema| schema.field_with_name(name).ok())
+                .map(|f| f.data_type().clone())
+                .filter(|t| !matches!(t, DataType::Null))
+                .unwrap_or(DataType::Utf8);
+            Field::new(name, data_type, true)
+        })
+        .collect()
+}
+
+/// Build schema from samples, including all fields (input + annotator fields)
+pub fn build_schema_from_samples(
+    samples: &[Sample],
+    input_schema: &Schema,
+) -> anyhow::Result<Arc<Schema>> {
+    // Convert Sample to Value for processing
+    let values: Vec<Value> = samples.iter().map(|s| s.as_value().clone()).collect();
+
+    // Collect all field names (input + any new fields from samples)
+    let mut all_field_names: Vec<String> = input_schema
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+
+    // Find all fields in samples
+    for value in &values {
+        if let Some(obj) = value.as_object() {
+            for field_name in obj.keys() {
+                if !all_field_names.contains(field_name) {
+                    all_field_names.push(field_name.clone());
+                }
+            }
+        }
+    }
+    let mut binary_field_names: Vec<String> = Vec::new();
+    for sample in samples {
+        for field_name in sample.binary_fields().keys() {
+            if !all_field_names.contains(field_name) {
+                all_field_names.push(field_name.clone());
+            }
+            if !binary_field_names.contains(field_name) {
+                binary_field_names.push(field_name.clone());
+            }
+        }
+    }
+
+    // Input columns keep their original Arrow types; new fields are inferred from the samples
+    let new_field_names: Vec<String> = all_field_names
+        .iter()
+        .filter(|name| input_schema.field_with_name(name).is_err())
+        .filter(|name| !binary_field_names.contains(name))
+        .cloned()
+        .collect();
+    let value_refs: Vec<&Value> = values.iter().collect();
+    let inferred = infer_field_types(&value_refs, &new_field_names);
+
+    let fields: Vec<Field> = all_field_names
+        .iter()
+        .map(
+            |field_name| match input_schema.field_with_name(field_name) {
+                Ok(original_field) => {
+                    Field::new(field_name, original_field.data_type().clone(), true)
+                }
+                Err(_) if binary_field_names.contains(field_name) => {
+                    Field::new(field_name, DataType::Binary, true)
+                }
+                Err(_) => inferred
+                    .iter()
+                    .find(|f| f.name() == field_name)
+                    .cloned()
+                    .unwrap_or_else(|| Field::new(field_name, DataType::Utf8, true)),
+            },
+        )
+        .collect();
+
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Convert buffered samples into a RecordBatch matching `target_schema`
+pub fn samples_to_batch(
+    samples: &[Sample],
+    target_schema: &Arc<Schema>,
+) -> anyhow::Result<RecordBatch> {
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("Cannot create batch from empty samples"));
+    }
+
+    // Convert Sample to Value for processing
+    let values: Vec<Value> = samples.iter().map(|s| s.as_value().clone()).collect();
+
+    // Build arrays for each field in target_schema
+    let mut arrays = Vec::new();
+
+    for field in target_schema.fields() {
+        let field_name = field.name();
+        let data_type = field.data_type();
+
+        // Build array
+        let array: Arc<dyn arrow::array::Array> = match data_type {
+            DataType::Utf8 => {
+                let mut builder = StringBuilder::new();
+                for value in &values {
+                    match value.get(field_name) {
+                        Some(Value::String(s)) => builder.append_value(s),
+                        Some(Value::Null) | None => builder.append_null(),
+                        // Nested or non-string values are kept as JSON text
+                        Some(other) => builder.append_value(other.to_string()),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            DataType::Int64 => {
+                let mut builder = Int64Builder::new();
+                for value in &values {
+                    match value.get(field_name) {
+                        Some(Value::Number(n)) if n.is_i64() => {
+                            builder.append_value(n.as_i64().unwrap())
+                        }
+                        Some(Value::Null) => builder.append_null(),
+                        _ => builder.append_null(),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            DataType::Float64 => {
+                let mut builder = Float64Builder::new();
+                for value in &values {
+                    match value.get(field_name) {
+                        Some(Value::Number(n)) => match n.as_f64() {
+                            Some(f) => builder.append_value(f),
+                            None => builder.append_null(),
+                        },
+                        Some(Value::Null) => builder.append_null(),
+                        _ => builder.append_null(),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            DataType::Boolean => {
+                let mut builder = BooleanBuilder::new();
+                for value in &values {
+                    match value.get(field_name) {
+                        Some(Value::Bool(x)) => builder.append_value(*x),
+                        Some(Value::Null) => builder.append_null(),
+                        _ => builder.append_null(),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            DataType::Binary
+            | DataType::LargeBinary
+            | DataType::BinaryView
+            | DataType::FixedSizeBinary(_) => binary_to_array(samples, field)?,
+            _ => {
+                // Other types (Int32, Timestamp, Decimal, List, Struct, ...) go through the
+                // Arrow JSON decoder so the input column type is preserved
+                let column: Vec<&Value> = values
+                    .iter()
+                    .map(|value| value.get(field_name).unwrap_or(&Value::Null))
+                    .collect();
+                json_to_array(field, &column).map_err(|e| {
+                    anyhow::anyhow!("Failed to build column '{}': {}", field_name, e)
+                })?
+            }
+        };
+
+        arrays.push(array);
+    }
+
+    Ok(RecordBatch::try_new(Arc::clone(target_schema), arrays)?)
+}
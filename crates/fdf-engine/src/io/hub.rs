@@ -0,0 +1,596 @@
+//! Upload of sink output to a HuggingFace Hub dataset repo
+//!
+//! Sink uris of the form `hf://datasets/org/name[/path]` (or `sink.kind: huggingface`) are
+//! written to a local staging directory and pushed to the repo through the Hub commit API,
+//! either one commit per file or a single commit when the pipeline finishes (`sink.hub.commit`).
+//! Large files go through the Git LFS batch API, like `huggingface_hub` does. Staged files are
+//! streamed from disk; the small files the Hub stores inline are committed in batches of at most
+//! `MAX_INLINE_BYTES`.
+
+use super::output::{runtime, FileDigest, Output};
+use super::writer::{Writer, WrittenFile};
+use crate::spec::{HubOptions, SinkSpec};
+use arrow::datatypes::Schema;
+use base64::Engine;
+use fdf_sdk::Sample;
+use serde_json::{json, Value};
+use std::io::{Read, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Files staged for the end-of-run commit (sink.hub.commit: at_close)
+static PENDING: Mutex<Vec<StagedFile>> = Mutex::new(Vec::new());
+
+/// Size of the inline (non-LFS) file contents sent in one commit request
+const MAX_INLINE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Whether the sink uri points at a HuggingFace Hub dataset repo
+pub fn is_hub_uri(uri: &str) -> bool {
+    uri.starts_with("hf://")
+}
+
+/// HuggingFace token from the environment (same variables as the hf:// source)
+pub fn token() -> Option<String> {
+    std::env::var("HF_TOKEN")
+        .or_else(|_| std::env::var("HUGGINGFACE_TOKEN"))
+        .or_else(|_| std::env::var("HF_API_TOKEN"))
+        .ok()
+}
+
+/// Split hf://datasets/org/name/path/to/file into ("org/name", "path/to/file")
+fn parse_uri(uri: &str) -> anyhow::Result<(String, String)> {
+    let path = uri.strip_prefix("hf://datasets/").ok_or_else(|| {
+        anyhow::anyhow!(
+            "Invalid HuggingFace sink uri '{}'. Expected: hf://datasets/org/dataset[/path]",
+            uri
+        )
+    })?;
+    let parts: Vec<&str> = path.trim_end_matches('/').splitn(3, '/').collect();
+    if parts.len() < 2 || parts[0].is_empty() || parts[1].is_empty() {
+        return Err(anyhow::anyhow!(
+            "Invalid HuggingFace sink uri '{}'. Expected: hf://datasets/org/dataset[/path]",
+            uri
+        ));
+    }
+    let repo_id = format!("{}/{}", parts[0], parts[1]);
+    let path_in_repo = parts.get(2).copied().unwrap_or("").to_string();
+    Ok((repo_id, path_in_repo))
+}
+
+/// Local staging file for an hf:// output path
+pub fn staging_path(uri: &str) -> anyhow::Result<String> {
+    let (repo_id, path_in_repo) = parse_uri(uri)?;
+    let path = staging_root().join(repo_id).join(path_in_repo);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+fn staging_root() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("fdf-hub-{}", std::process::id()))
+}
+
+/// Create the dataset repo if it doesn't exist and add a README dataset card stub to new repos
+/// Called once before the pipeline runs so auth problems surface before any processing.
+pub fn prepare(spec: &SinkSpec) -> anyhow::Result<()> {
+    let (repo_id, base_path) = parse_uri(&spec.uri)?;
+    let client = HubClient::new()?;
+    let created = runtime().block_on(client.create_repo(&repo_id, spec.hub.private))?;
+
+    if created && spec.hub.dataset_card {
+        let local_path = staging_path(&format!("hf://datasets/{}/README.md", repo_id))?;
+        let mut output = Output::create(&local_path)?;
+        output.write_all(dataset_card(&repo_id, &base_path).as_bytes())?;
+        let card = HubFile::open("README.md", &local_path, output.finish()?)?;
+        let committed = runtime().block_on(client.commit(
+            &repo_id,
+            &spec.hub.revision,
+            &[card],
+            "Add dataset card",
+        ));
+        let _ = std::fs::remove_file(&local_path);
+        committed?;
+    }
+    Ok(())
+}
+
+/// Commit all files staged with sink.hub.commit: at_close, one commit per repo/revision
+/// Also removes the local staging directory once everything is uploaded.
+pub fn commit_pending() -> anyhow::Result<()> {
+    let staged: Vec<StagedFile> = std::mem::take(&mut *PENDING.lock().unwrap());
+    if staged.is_empty() {
+        let _ = std::fs::remove_dir_all(staging_root());
+        return Ok(());
+    }
+
+    let mut groups: Vec<((String, String), Vec<StagedFile>)> = Vec::new();
+    for file in staged {
+        let key = (file.repo_id.clone(), file.revision.clone());
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, files)) => files.push(file),
+            None => groups.push((key, vec![file])),
+        }
+    }
+
+    let client = HubClient::new()?;
+    for ((repo_id, revision), files) in groups {
+        let summary = format!("Upload {} files with foundation-data-factory", files.len());
+        upload_staged(&client, &repo_id, &revision, &files, &summary)?;
+    }
+    let _ = std::fs::remove_dir_all(staging_root());
+    Ok(())
+}
+
+fn dataset_card(repo_id: &str, base_path: &str) -> String {
+    let data_files = if base_path.is_empty() {
+        "final/**".to_string()
+    } else {
+        format!("{}/final/**", base_path)
+    };
+    let name = repo_id.rsplit('/').next().unwrap_or(repo_id);
+    format!(
+        "---\nconfigs:\n- config_name: default\n  data_files: \"{data_files}\"\n---\n\n# {name}\n\n\
+         This dataset was produced with [foundation-data-factory](https://github.com/duoan/foundation-data-factory).\n\
+         Filtered-out samples are kept under `trace/` and processing errors under `error/`.\n"
+    )
+}
+
+/// A file written locally and waiting to be committed
+struct StagedFile {
+    repo_id: String,
+    revision: String,
+    path_in_repo: String,
+    local_path: String,
+    digest: FileDigest, // Set when the local file is closed
+}
+
+/// Commit staged files, then remove the staging copies
+fn upload_staged(
+    client: &HubClient,
+    repo_id: &str,
+    revision: &str,
+    files: &[StagedFile],
+    summary: &str,
+) -> anyhow::Result<()> {
+    let hub_files = files
+        .iter()
+        .map(|file| HubFile::open(&file.path_in_repo, &file.local_path, file.digest.clone()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    runtime().block_on(client.commit(repo_id, revision, &hub_files, summary))?;
+    for file in files {
+        let _ = std::fs::remove_file(&file.local_path);
+    }
+    Ok(())
+}
+
+/// Writer that writes to a local staging file and uploads it to the Hub on close
+pub struct HubWriter {
+    inner: Box<dyn Writer>,
+    staged: StagedFile,
+    options: HubOptions,
+}
+
+impl HubWriter {
+    /// Wrap a writer whose output is `local_path`, destined for the hf:// `uri`
+    pub fn new(
+        inner: Box<dyn Writer>,
+        uri: &str,
+        local_path: &str,
+        options: HubOptions,
+    ) -> anyhow::Result<Self> {
+        let (repo_id, path_in_repo) = parse_uri(uri)?;
+        match options.commit.as_str() {
+            "per_file" | "at_close" => {}
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown hub commit mode '{}'. Expected 'per_file' or 'at_close'",
+                    other
+                ))
+            }
+        }
+        Ok(Self {
+            inner,
+            staged: StagedFile {
+                repo_id,
+                revision: options.revision.clone(),
+                path_in_repo,
+                local_path: local_path.to_string(),
+                digest: FileDigest::default(),
+            },
+            options,
+        })
+    }
+}
+
+impl Writer for HubWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.inner.write_sample(sample)
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        let files = self.inner.close()?;
+        let Some(file) = files.first() else {
+            return Ok(files);
+        };
+        self.staged.digest = file.digest.clone();
+
+        if self.options.commit == "per_file" {
+            let summary = format!("Upload {}", self.staged.path_in_repo);
+            upload_staged(
+                &HubClient::new()?,
+                &self.staged.repo_id,
+                &self.staged.revision,
+                std::slice::from_ref(&self.staged),
+                &summary,
+            )?;
+        } else {
+            PENDING.lock().unwrap().push(self.staged);
+        }
+        Ok(files)
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        self.inner.schema()
+    }
+}
+
+/// Local file to commit, read from disk when uploaded
+struct HubFile {
+    path_in_repo: String,
+    local_path: PathBuf,
+    size: u64,
+    oid: String,   // Hex SHA-256 of the content, the LFS object id
+    head: Vec<u8>, // First 512 bytes, which the Hub uses to tell text from binary files
+}
+
+impl HubFile {
+    /// A local file with the digest computed when it was written
+    fn open(path_in_repo: &str, local_path: &str, digest: FileDigest) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(local_path)
+            .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", local_path, e))?;
+        let mut head = Vec::with_capacity(512);
+        file.take(512).read_to_end(&mut head)?;
+        Ok(Self {
+            path_in_repo: path_in_repo.to_string(),
+            local_path: PathBuf::from(local_path),
+            size: digest.bytes,
+            oid: digest.sha256,
+            head,
+        })
+    }
+
+    /// Request body streaming `len` bytes of the file from `offset`
+    async fn body(&self, offset: u64, len: u64) -> anyhow::Result<reqwest::Body> {
+        let mut file = tokio::fs::File::open(&self.local_path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let stream = tokio_util::io::ReaderStream::new(file.take(len));
+        Ok(reqwest::Body::wrap_stream(stream))
+    }
+}
+
+/// Minimal client for the Hub repo/commit APIs
+struct HubClient {
+    endpoint: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl HubClient {
+    fn new() -> anyhow::Result<Self> {
+        let token = token();
+        if token.is_none() {
+            return Err(anyhow::anyhow!(
+                "Uploading to the HuggingFace Hub requires a token in HF_TOKEN"
+            ));
+        }
+        Ok(Self {
+            endpoint: std::env::var("HF_ENDPOINT")
+                .unwrap_or_else(|_| "https://huggingface.co".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            token,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Request to the Hub itself (authenticated); LFS storage urls are pre-signed and must not get the token
+    fn hub_request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Create a dataset repo; returns false if it already exists
+    async fn create_repo(&self, repo_id: &str, private: bool) -> anyhow::Result<bool> {
+        let (organization, name) = repo_id.split_once('/').unwrap_or(("", repo_id));
+        let response = self
+            .hub_request(
+                reqwest::Method::POST,
+                &format!("{}/api/repos/create", self.endpoint),
+            )
+            .json(&json!({
+                "type": "dataset",
+                "name": name,
+                "organization": organization,
+                "private": private,
+            }))
+            .send()
+            .await?;
+        match response.status().as_u16() {
+            200..=299 => Ok(true),
+            409 => Ok(false), // Already exists
+            status => Err(anyhow::anyhow!(
+                "Failed to create dataset repo '{}' ({}): {}",
+                repo_id,
+                status,
+                response.text().await.unwrap_or_default()
+            )),
+        }
+    }
+
+    /// Commit files to the repo, uploading large ones through LFS first. Files stored inline
+    /// are sent in the commit request, split into several commits beyond MAX_INLINE_BYTES
+    async fn commit(
+        &self,
+        repo_id: &str,
+        revision: &str,
+        files: &[HubFile],
+        summary: &str,
+    ) -> anyhow::Result<()> {
+        let revision = revision.replace('/', "%2F");
+        let lfs_paths = self.preupload(repo_id, &revision, files).await?;
+
+        let mut batches: Vec<Vec<Value>> = vec![Vec::new()];
+        let mut inline_bytes = 0;
+        for file in files {
+            let line = if lfs_paths.contains(&file.path_in_repo) {
+                self.upload_lfs(repo_id, file).await?;
+                json!({"key": "lfsFile", "value": {
+                    "path": file.path_in_repo,
+                    "algo": "sha256",
+                    "oid": file.oid,
+                    "size": file.size,
+                }})
+            } else {
+                if inline_bytes > 0 && inline_bytes + file.size > MAX_INLINE_BYTES {
+                    batches.push(Vec::new());
+                    inline_bytes = 0;
+                }
+                inline_bytes += file.size;
+                let content = tokio::fs::read(&file.local_path).await?;
+                json!({"key": "file", "value": {
+                    "path": file.path_in_repo,
+                    "content": base64::engine::general_purpose::STANDARD.encode(&content),
+                    "encoding": "base64",
+                }})
+            };
+            batches.last_mut().unwrap().push(line);
+        }
+
+        let count = batches.len();
+        for (index, batch) in batches.into_iter().enumerate() {
+            let summary = if count > 1 {
+                format!("{} ({}/{})", summary, index + 1, count)
+            } else {
+                summary.to_string()
+            };
+            let header = json!({"key": "header", "value": {"summary": summary, "description": ""}});
+            let body: String = std::iter::once(&header)
+                .chain(&batch)
+                .map(|line| format!("{}\n", line))
+                .collect();
+
+            let response = self
+                .hub_request(
+                    reqwest::Method::POST,
+                    &format!(
+                        "{}/api/datasets/{}/commit/{}",
+                        self.endpoint, repo_id, revision
+                    ),
+                )
+                .header("Content-Type", "application/x-ndjson")
+                .body(body)
+                .send()
+                .await?;
+            check_status(response, &format!("commit to '{}'", repo_id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Ask the Hub which files must be uploaded through LFS
+    async fn preupload(
+        &self,
+        repo_id: &str,
+        revision: &str,
+        files: &[HubFile],
+    ) -> anyhow::Result<Vec<String>> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let entries: Vec<Value> = files
+            .iter()
+            .map(|file| {
+                json!({
+                    "path": file.path_in_repo,
+                    "size": file.size,
+                    "sample": engine.encode(&file.head),
+                })
+            })
+            .collect();
+        let response = self
+            .hub_request(
+                reqwest::Method::POST,
+                &format!(
+                    "{}/api/datasets/{}/preupload/{}",
+                    self.endpoint, repo_id, revision
+                ),
+            )
+            .json(&json!({ "files": entries }))
+            .send()
+            .await?;
+        let body = check_status(response, &format!("preupload to '{}'", repo_id)).await?;
+
+        Ok(body["files"]
+            .as_array()
+            .map(|files| {
+                files
+                    .iter()
+                    .filter(|f| f["uploadMode"] == "lfs")
+                    .filter_map(|f| f["path"].as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Upload one object through the Git LFS batch API (basic or multipart transfer)
+    async fn upload_lfs(&self, repo_id: &str, file: &HubFile) -> anyhow::Result<()> {
+        let response = self
+            .hub_request(
+                reqwest::Method::POST,
+                &format!(
+                    "{}/datasets/{}.git/info/lfs/objects/batch",
+                    self.endpoint, repo_id
+                ),
+            )
+            .header("Accept", "application/vnd.git-lfs+json")
+            .header("Content-Type", "application/vnd.git-lfs+json")
+            .body(
+                json!({
+                    "operation": "upload",
+                    "transfers": ["basic", "multipart"],
+                    "objects": [{"oid": file.oid, "size": file.size}],
+                    "hash_algo": "sha256",
+                })
+                .to_string(),
+            )
+            .send()
+            .await?;
+        let body = check_status(response, "LFS batch request").await?;
+        let object = &body["objects"][0];
+        if let Some(error) = object.get("error") {
+            return Err(anyhow::anyhow!("LFS upload rejected: {}", error));
+        }
+
+        // No upload action means the object is already stored
+        let upload = &object["actions"]["upload"];
+        if let Some(href) = upload["href"].as_str() {
+            let header = upload["header"].as_object();
+            match header
+                .and_then(|h| h.get("chunk_size"))
+                .and_then(|c| c.as_str().and_then(|s| s.parse::<u64>().ok()))
+                .filter(|&chunk_size| chunk_size > 0)
+            {
+                Some(chunk_size) => {
+                    self.upload_lfs_multipart(href, header.unwrap(), chunk_size, file)
+                        .await?
+                }
+                None => {
+                    let response = self
+                        .client
+                        .put(href)
+                        .header(reqwest::header::CONTENT_LENGTH, file.size)
+                        .body(file.body(0, file.size).await?)
+                        .send()
+                        .await?;
+                    check_status(response, "LFS upload").await?;
+                }
+            }
+        }
+
+        if let Some(href) = object["actions"]["verify"]["href"].as_str() {
+            let response = self
+                .hub_request(reqwest::Method::POST, href)
+                .json(&json!({"oid": file.oid, "size": file.size}))
+                .send()
+                .await?;
+            check_status(response, "LFS verify").await?;
+        }
+        Ok(())
+    }
+
+    /// Multipart LFS transfer: one pre-signed url per part, then a completion request
+    async fn upload_lfs_multipart(
+        &self,
+        completion_url: &str,
+        header: &serde_json::Map<String, Value>,
+        chunk_size: u64,
+        file: &HubFile,
+    ) -> anyhow::Result<()> {
+        // Part urls are keyed by part number ("1", "2", ...) next to chunk_size
+        let mut part_urls: Vec<(usize, &str)> = header
+            .iter()
+            .filter_map(|(k, v)| Some((k.parse::<usize>().ok()?, v.as_str()?)))
+            .collect();
+        part_urls.sort_by_key(|(number, _)| *number);
+
+        let mut parts = Vec::with_capacity(part_urls.len());
+        // Each part is streamed from its offset in the staged file
+        let offsets = (0..file.size).step_by(chunk_size as usize);
+        for ((number, url), offset) in part_urls.iter().zip(offsets) {
+            let len = chunk_size.min(file.size - offset);
+            let response = self
+                .client
+                .put(*url)
+                .header(reqwest::header::CONTENT_LENGTH, len)
+                .body(file.body(offset, len).await?)
+                .send()
+                .await?;
+            let etag = response
+                .headers()
+                .get("ETag")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            check_status(response, &format!("LFS part {} upload", number)).await?;
+            parts.push(json!({"partNumber": number, "etag": etag}));
+        }
+
+        let response = self
+            .client
+            .post(completion_url)
+            .json(&json!({"oid": file.oid, "parts": parts}))
+            .send()
+            .await?;
+        check_status(response, "LFS multipart completion").await?;
+        Ok(())
+    }
+}
+
+/// Turn a non-success response into an error; returns the JSON body (Null if not JSON)
+async fn check_status(response: reqwest::Response, action: &str) -> anyhow::Result<Value> {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!(
+            "HuggingFace Hub {} failed ({}): {}",
+            action,
+            status,
+            text
+        ));
+    }
+    Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn hub_file_uses_the_digest_of_the_output() {
+        let path = std::env::temp_dir().join(format!("fdf-hub-file-{}", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let content: Vec<u8> = (0..2000u32).map(|i| (i % 251) as u8).collect();
+        let mut output = Output::create(&path).unwrap();
+        output.write_all(&content).unwrap();
+        let digest = output.finish().unwrap();
+
+        let file = HubFile::open("final/part-0.parquet", &path, digest).unwrap();
+        assert_eq!(file.size, 2000);
+        assert_eq!(file.head, &content[..512]);
+        assert_eq!(file.oid, format!("{:x}", Sha256::digest(&content)));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,368 @@
+//! Output destinations for writers
+//!
+//! Writers open their output through `Output::create`, which writes to a local file or, for
+//! `s3://bucket/key` uris, streams the bytes to S3 as a multipart upload so no local staging
+//! disk is needed. Credentials and region come from the standard AWS environment variables.
+//!
+//! Outputs are atomic: local files are written to `{path}.tmp` and renamed into place by
+//! `finish`, and S3 objects only appear once the multipart upload completes. An output dropped
+//! without `finish` (failed or crashed write) removes its temp file / aborts its upload, so
+//! readers never see truncated files.
+//!
+//! The size and SHA-256 of every output are computed as its bytes are written and returned by
+//! `finish`, so manifests don't have to read the files back.
+
+use futures::TryStreamExt;
+use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload, RetryConfig, WriteMultipart};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::OnceLock;
+
+/// Size of each uploaded part (S3 requires at least 5 MiB for all but the last part)
+const S3_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of parts uploaded concurrently per file
+const S3_MAX_CONCURRENT_PARTS: usize = 4;
+
+/// Number of times a failed request (including a part upload) is retried
+const S3_MAX_RETRIES: usize = 10;
+
+/// Whether the uri points at object storage or the HuggingFace Hub rather than the local filesystem
+pub fn is_remote(uri: &str) -> bool {
+    uri.starts_with("s3://") || uri.starts_with("hf://")
+}
+
+/// Create a local directory; a no-op for object storage, which has no directories
+pub fn create_dir_all(path: &str) -> anyhow::Result<()> {
+    if !is_remote(path) {
+        std::fs::create_dir_all(path)?;
+    }
+    Ok(())
+}
+
+/// Suffix of local files that are still being written
+pub const TEMP_SUFFIX: &str = ".tmp";
+
+/// Temp path a local output is written to before being renamed into place
+pub fn temp_path(path: &str) -> String {
+    format!("{}{}", path, TEMP_SUFFIX)
+}
+
+/// Best-effort removal of leftover temp files in a local directory (e.g. from a crashed run)
+pub fn remove_temp_files(dir: &str) {
+    if is_remote(dir) {
+        return;
+    }
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.to_string_lossy().ends_with(TEMP_SUFFIX) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Best-effort removal of an output file or object (used to drop empty outputs)
+pub fn remove(path: &str) {
+    if path.starts_with("s3://") {
+        if let Ok((store, location)) = s3_location(path) {
+            let _ = runtime().block_on(store.delete(&location));
+        }
+    } else if !is_remote(path) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Whether a file or object exists at the path; Hub uris are reported as missing
+pub fn exists(path: &str) -> anyhow::Result<bool> {
+    if path.starts_with("s3://") {
+        let (store, location) = s3_location(path)?;
+        match runtime().block_on(store.head(&location)) {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    } else {
+        Ok(!is_remote(path) && std::path::Path::new(path).exists())
+    }
+}
+
+/// Paths of the files directly inside a directory (or S3 prefix); empty if it does not exist
+/// Hub uris are not listed.
+pub fn list_files(dir: &str) -> anyhow::Result<Vec<String>> {
+    let mut files = Vec::new();
+    if dir.starts_with("s3://") {
+        let (store, prefix) = s3_location(dir)?;
+        let listing = runtime().block_on(store.list_with_delimiter(Some(&prefix)))?;
+        let base = dir.trim_end_matches('/');
+        for object in listing.objects {
+            if let Some(name) = object.location.filename() {
+                files.push(format!("{}/{}", base, name));
+            }
+        }
+    } else if !is_remote(dir) && std::path::Path::new(dir).is_dir() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Remove a directory (or S3 prefix) and everything below it; a no-op for Hub uris
+pub fn remove_dir_all(dir: &str) -> anyhow::Result<()> {
+    if dir.starts_with("s3://") {
+        let (store, prefix) = s3_location(dir)?;
+        let rt = runtime();
+        let objects: Vec<_> = rt.block_on(store.list(Some(&prefix)).try_collect())?;
+        for object in objects {
+            rt.block_on(store.delete(&object.location))?;
+        }
+    } else if !is_remote(dir) && std::path::Path::new(dir).exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Size in bytes and hex SHA-256 of an output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileDigest {
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+impl FileDigest {
+    /// Digest of a local file, for outputs not written through `Output` (SQLite databases are
+    /// updated in place, so they can only be hashed once closed)
+    pub fn of_file(path: &str) -> std::io::Result<Self> {
+        let mut hasher = Sha256::new();
+        let bytes = std::io::copy(&mut File::open(path)?, &mut hasher)?;
+        Ok(Self {
+            bytes,
+            sha256: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+/// A writable output: local file (written via a temp file) or S3 multipart upload, hashed as
+/// it is written
+pub struct Output {
+    target: Target,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+enum Target {
+    Local(LocalFile),
+    S3(S3Upload),
+}
+
+impl Output {
+    /// Open an output for writing; an existing file is only replaced once `finish` succeeds
+    pub fn create(path: &str) -> anyhow::Result<Self> {
+        let target = if path.starts_with("s3://") {
+            let (store, location) = s3_location(path)?;
+            Target::S3(S3Upload {
+                store,
+                location,
+                upload: None,
+            })
+        } else {
+            let temp_path = temp_path(path);
+            Target::Local(LocalFile {
+                writer: BufWriter::new(File::create(&temp_path)?),
+                temp_path,
+                path: path.to_string(),
+                finished: false,
+            })
+        };
+        Ok(Self {
+            target,
+            hasher: Sha256::new(),
+            bytes: 0,
+        })
+    }
+
+    /// Flush buffered data and publish the output: a local temp file is renamed into place,
+    /// an S3 upload sends its last part and completes. Returns the digest of the bytes written
+    pub fn finish(self) -> std::io::Result<FileDigest> {
+        match self.target {
+            Target::Local(file) => file.finish()?,
+            Target::S3(upload) => upload.finish()?,
+        }
+        Ok(FileDigest {
+            bytes: self.bytes,
+            sha256: format!("{:x}", self.hasher.finalize()),
+        })
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = match &mut self.target {
+            Target::Local(w) => w.write(buf)?,
+            Target::S3(w) => w.write(buf)?,
+        };
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match &mut self.target {
+            Target::Local(w) => w.write_all(buf)?,
+            Target::S3(w) => w.write_all(buf)?,
+        }
+        self.hasher.update(buf);
+        self.bytes += buf.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.target {
+            Target::Local(w) => w.flush(),
+            Target::S3(w) => w.flush(),
+        }
+    }
+}
+
+/// Local file written to a temp path and renamed to its final path on finish
+struct LocalFile {
+    writer: BufWriter<File>,
+    temp_path: String,
+    path: String,
+    finished: bool,
+}
+
+impl LocalFile {
+    fn finish(mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        std::fs::rename(&self.temp_path, &self.path)?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Write for LocalFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.writer.write_all(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for LocalFile {
+    fn drop(&mut self) {
+        // Not finished (failed write or rename): discard the partial temp file
+        if !self.finished {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Streaming multipart upload of a single S3 object
+/// The upload starts on the first write; parts are uploaded in the background and failed
+/// part requests are retried by the object store client.
+struct S3Upload {
+    store: AmazonS3,
+    location: ObjectPath,
+    upload: Option<WriteMultipart>,
+}
+
+impl S3Upload {
+    fn finish(mut self) -> std::io::Result<()> {
+        let rt = runtime();
+        match self.upload.take() {
+            Some(upload) => rt
+                .block_on(upload.finish())
+                .map_err(std::io::Error::other)?,
+            // Nothing was written: create an empty object, like File::create would
+            None => rt
+                .block_on(self.store.put(&self.location, PutPayload::default()))
+                .map_err(std::io::Error::other)?,
+        };
+        Ok(())
+    }
+}
+
+impl Write for S3Upload {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let rt = runtime();
+        if self.upload.is_none() {
+            let upload = rt
+                .block_on(self.store.put_multipart(&self.location))
+                .map_err(std::io::Error::other)?;
+            self.upload = Some(WriteMultipart::new_with_chunk_size(upload, S3_PART_SIZE));
+        }
+        let upload = self.upload.as_mut().unwrap();
+
+        // Back pressure: bound the number of parts held in memory
+        rt.block_on(upload.wait_for_capacity(S3_MAX_CONCURRENT_PARTS))
+            .map_err(std::io::Error::other)?;
+        let _guard = rt.enter(); // Part uploads are spawned onto the runtime
+        upload.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // Parts are uploaded once full; the final partial part is sent by finish()
+        Ok(())
+    }
+}
+
+impl Drop for S3Upload {
+    fn drop(&mut self) {
+        // Abort unfinished uploads so no orphaned parts are left behind
+        if let Some(upload) = self.upload.take() {
+            let _ = runtime().block_on(upload.abort());
+        }
+    }
+}
+
+/// Split an s3://bucket/key uri into a client for the bucket and the object key
+fn s3_location(uri: &str) -> anyhow::Result<(AmazonS3, ObjectPath)> {
+    let rest = uri.strip_prefix("s3://").unwrap_or(uri);
+    let (bucket, key) = rest
+        .split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid S3 uri '{}'. Expected: s3://bucket/path/to/file",
+                uri
+            )
+        })?;
+
+    let store = AmazonS3Builder::from_env()
+        .with_bucket_name(bucket)
+        .with_retry(RetryConfig {
+            max_retries: S3_MAX_RETRIES,
+            ..Default::default()
+        })
+        .build()?;
+    Ok((store, ObjectPath::from(key)))
+}
+
+/// Shared runtime driving S3 and Hub requests (writers are synchronous)
+pub(crate) fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime for uploads")
+    })
+}
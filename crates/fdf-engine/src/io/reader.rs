@@ -9,6 +9,7 @@ pub trait Reader: Iterator<Item = anyhow::Result<Sample>> {
     fn schema(&self) -> &Arc<Schema>;
 }
 
+pub mod cast;
 pub mod column_filter;
 pub mod huggingface;
 pub mod jsonl;
@@ -0,0 +1,219 @@
+use super::Reader;
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use fdf_sdk::Sample;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Target type of a source column cast
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastType {
+    Int,
+    Float,
+    String,
+    Bool,
+    /// Epoch integers (in the given unit) or RFC 3339 strings, stored as RFC 3339 strings
+    Timestamp(TimeUnit),
+}
+
+impl CastType {
+    /// Parse a cast type name from the spec
+    /// Supported: int, float, string, bool, timestamp (epoch seconds), timestamp_ms, timestamp_us, timestamp_ns
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name.to_lowercase().as_str() {
+            "int" | "int64" | "integer" => Ok(Self::Int),
+            "float" | "float64" | "double" => Ok(Self::Float),
+            "string" | "str" | "utf8" => Ok(Self::String),
+            "bool" | "boolean" => Ok(Self::Bool),
+            "timestamp" | "timestamp_s" => Ok(Self::Timestamp(TimeUnit::Second)),
+            "timestamp_ms" => Ok(Self::Timestamp(TimeUnit::Millisecond)),
+            "timestamp_us" => Ok(Self::Timestamp(TimeUnit::Microsecond)),
+            "timestamp_ns" => Ok(Self::Timestamp(TimeUnit::Nanosecond)),
+            other => Err(anyhow::anyhow!(
+                "Unknown cast type '{}'. Expected one of: int, float, string, bool, timestamp, timestamp_ms, timestamp_us, timestamp_ns",
+                other
+            )),
+        }
+    }
+
+    /// Arrow type of the column after the cast
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Self::Int => DataType::Int64,
+            Self::Float => DataType::Float64,
+            Self::String => DataType::Utf8,
+            Self::Bool => DataType::Boolean,
+            Self::Timestamp(unit) => DataType::Timestamp(*unit, Some("UTC".into())),
+        }
+    }
+
+    /// Whether a JSON value already has this type, without any conversion (null never matches)
+    /// Timestamps must be RFC 3339 strings, as produced by cast() and the readers.
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::Int => value.is_i64(),
+            Self::Float => value.is_number(),
+            Self::String => value.is_string(),
+            Self::Bool => value.is_boolean(),
+            Self::Timestamp(_) => value
+                .as_str()
+                .is_some_and(|s| DateTime::parse_from_rfc3339(s).is_ok()),
+        }
+    }
+
+    /// Cast a single JSON value. Null stays null.
+    pub fn cast(&self, value: &Value) -> Option<Value> {
+        if value.is_null() {
+            return Some(Value::Null);
+        }
+
+        match self {
+            Self::Int => match value {
+                Value::Number(n) => n
+                    .as_i64()
+                    .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+                    .map(Value::from),
+                Value::String(s) => s.trim().parse::<i64>().ok().map(Value::from),
+                Value::Bool(b) => Some(Value::from(*b as i64)),
+                _ => None,
+            },
+            Self::Float => match value {
+                Value::Number(n) => n.as_f64().and_then(float_value),
+                Value::String(s) => s.trim().parse::<f64>().ok().and_then(float_value),
+                Value::Bool(b) => float_value(if *b { 1.0 } else { 0.0 }),
+                _ => None,
+            },
+            Self::String => match value {
+                Value::String(s) => Some(Value::String(s.clone())),
+                other => Some(Value::String(other.to_string())),
+            },
+            Self::Bool => match value {
+                Value::Bool(b) => Some(Value::Bool(*b)),
+                Value::Number(n) => n.as_f64().map(|f| Value::Bool(f != 0.0)),
+                Value::String(s) => match s.trim().to_lowercase().as_str() {
+                    "true" | "1" | "yes" | "y" | "t" => Some(Value::Bool(true)),
+                    "false" | "0" | "no" | "n" | "f" => Some(Value::Bool(false)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Self::Timestamp(unit) => {
+                let epoch = match value {
+                    Value::Number(n) => n.as_i64(),
+                    Value::String(s) => {
+                        let s = s.trim();
+                        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+                            return Some(Value::String(
+                                dt.with_timezone(&Utc)
+                                    .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                            ));
+                        }
+                        s.parse::<i64>().ok()
+                    }
+                    _ => None,
+                }?;
+                let dt = match unit {
+                    TimeUnit::Second => DateTime::from_timestamp(epoch, 0),
+                    TimeUnit::Millisecond => DateTime::from_timestamp_millis(epoch),
+                    TimeUnit::Microsecond => DateTime::from_timestamp_micros(epoch),
+                    TimeUnit::Nanosecond => Some(DateTime::from_timestamp_nanos(epoch)),
+                }?;
+                Some(Value::String(
+                    dt.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ))
+            }
+        }
+    }
+}
+
+fn float_value(f: f64) -> Option<Value> {
+    serde_json::Number::from_f64(f).map(Value::Number)
+}
+
+/// A reader that casts columns to declared types (source.casts)
+pub struct CastReader {
+    inner: Box<dyn Reader>,
+    casts: Vec<(String, CastType)>,
+    cast_schema: Arc<Schema>,
+}
+
+impl CastReader {
+    /// Create a new CastReader
+    ///
+    /// # Arguments
+    /// * `inner` - The inner reader to wrap
+    /// * `casts` - Mapping from column name to target type name, e.g. {"score": "float"}
+    pub fn new(inner: Box<dyn Reader>, casts: HashMap<String, String>) -> anyhow::Result<Self> {
+        let mut parsed = Vec::with_capacity(casts.len());
+        for (column, type_name) in &casts {
+            let cast_type = CastType::parse(type_name)
+                .map_err(|e| anyhow::anyhow!("Invalid cast for column '{}': {}", column, e))?;
+            parsed.push((column.clone(), cast_type));
+        }
+        // Sort for deterministic schema and error ordering
+        parsed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Replace the types of cast columns; columns not in the source schema are appended
+        let original_schema = inner.schema();
+        let mut fields: Vec<Field> = original_schema
+            .fields()
+            .iter()
+            .map(
+                |f| match parsed.iter().find(|(column, _)| column == f.name()) {
+                    Some((_, cast_type)) => {
+                        Field::new(f.name(), cast_type.data_type(), f.is_nullable())
+                    }
+                    None => f.as_ref().clone(),
+                },
+            )
+            .collect();
+        for (column, cast_type) in &parsed {
+            if original_schema.field_with_name(column).is_err() {
+                fields.push(Field::new(column, cast_type.data_type(), true));
+            }
+        }
+
+        Ok(Self {
+            inner,
+            casts: parsed,
+            cast_schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// Cast all configured columns of a sample
+    fn cast_sample(&self, mut sample: Sample) -> anyhow::Result<Sample> {
+        for (column, cast_type) in &self.casts {
+            if let Some(value) = sample.get(column) {
+                let casted = cast_type.cast(value).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Cannot cast column '{}' value {} to {:?}",
+                        column,
+                        value,
+                        cast_type
+                    )
+                })?;
+                sample.set_value(column.clone(), casted);
+            }
+        }
+        Ok(sample)
+    }
+}
+
+impl Iterator for CastReader {
+    type Item = anyhow::Result<Sample>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner.next() {
+            Some(Ok(sample)) => Some(self.cast_sample(sample)),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+}
+
+impl Reader for CastReader {
+    fn schema(&self) -> &Arc<Schema> {
+        &self.cast_schema
+    }
+}
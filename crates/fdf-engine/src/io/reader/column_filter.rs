@@ -84,6 +84,8 @@ impl ColumnFilterReader {
             if let Some(value) = sample.get(original_name) {
                 // Clone the value and set it with the new name
                 filtered.set_value(new_name.clone(), value.clone());
+            } else if let Some(bytes) = sample.get_bytes(original_name) {
+                filtered.set_bytes(new_name.clone(), bytes);
             }
         }
 
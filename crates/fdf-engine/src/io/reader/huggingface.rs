@@ -162,7 +162,7 @@ impl HuggingFaceReader {
         }
 
         let api = builder.build()?;
-        println!("Dataset {}", dataset_id);
+        eprintln!("Dataset {}", dataset_id);
         let repo = api.dataset(dataset_id.to_string());
 
         // Try to find parquet files for the dataset
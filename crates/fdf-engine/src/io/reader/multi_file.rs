@@ -3,11 +3,14 @@ use arrow::datatypes::Schema;
 use fdf_sdk::Sample;
 use std::sync::Arc;
 
-/// A reader that wraps multiple readers and reads from them sequentially
+/// A reader that wraps multiple readers and reads from them sequentially,
+/// or round-robin in chunks when interleaving is enabled
 pub struct MultiFileReader {
     readers: Vec<Box<dyn Reader>>,
     current_reader_index: usize,
     schema: Arc<Schema>, // Schema from the first reader (all readers should have the same schema)
+    interleave_chunk_size: Option<usize>, // Samples taken from a reader before switching (None = sequential)
+    taken_from_current: usize,
 }
 
 impl MultiFileReader {
@@ -37,8 +40,66 @@ impl MultiFileReader {
             readers,
             current_reader_index: 0,
             schema,
+            interleave_chunk_size: None,
+            taken_from_current: 0,
         })
     }
+
+    /// Create a MultiFileReader that interleaves its inputs
+    /// Takes `chunk_size` samples from each reader in turn, so the output mixes all inputs
+    /// instead of being ordered by input file. Exhausted readers drop out of the rotation.
+    pub fn interleaved(readers: Vec<Box<dyn Reader>>, chunk_size: usize) -> anyhow::Result<Self> {
+        if chunk_size == 0 {
+            return Err(anyhow::anyhow!(
+                "Interleave chunk size must be greater than 0"
+            ));
+        }
+        let mut reader = Self::new(readers)?;
+        reader.interleave_chunk_size = Some(chunk_size);
+        Ok(reader)
+    }
+
+    /// Read the next sample sequentially: drain each reader before moving to the next
+    fn next_sequential(&mut self) -> Option<anyhow::Result<Sample>> {
+        // Try to get a sample from the current reader
+        while self.current_reader_index < self.readers.len() {
+            if let Some(result) = self.readers[self.current_reader_index].next() {
+                return Some(result);
+            }
+
+            // Current reader is exhausted, move to next
+            self.current_reader_index += 1;
+        }
+
+        // All readers are exhausted
+        None
+    }
+
+    /// Read the next sample round-robin, `chunk_size` samples per reader at a time
+    fn next_interleaved(&mut self, chunk_size: usize) -> Option<anyhow::Result<Sample>> {
+        while !self.readers.is_empty() {
+            // Current chunk is complete, rotate to the next reader
+            if self.taken_from_current >= chunk_size {
+                self.current_reader_index = (self.current_reader_index + 1) % self.readers.len();
+                self.taken_from_current = 0;
+            }
+
+            if let Some(result) = self.readers[self.current_reader_index].next() {
+                self.taken_from_current += 1;
+                return Some(result);
+            }
+
+            // Current reader is exhausted, drop it; the next reader slides into its slot
+            self.readers.remove(self.current_reader_index);
+            self.taken_from_current = 0;
+            if self.current_reader_index >= self.readers.len() {
+                self.current_reader_index = 0;
+            }
+        }
+
+        // All readers are exhausted
+        None
+    }
 }
 
 /// Check if two schemas are compatible (same field names and types)
@@ -60,18 +121,10 @@ impl Iterator for MultiFileReader {
     type Item = anyhow::Result<Sample>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Try to get a sample from the current reader
-        while self.current_reader_index < self.readers.len() {
-            if let Some(result) = self.readers[self.current_reader_index].next() {
-                return Some(result);
-            }
-
-            // Current reader is exhausted, move to next
-            self.current_reader_index += 1;
+        match self.interleave_chunk_size {
+            Some(chunk_size) => self.next_interleaved(chunk_size),
+            None => self.next_sequential(),
         }
-
-        // All readers are exhausted
-        None
     }
 }
 
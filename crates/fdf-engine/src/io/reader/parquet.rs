@@ -1,10 +1,9 @@
 use super::Reader;
+use crate::io::convert::{array_value_to_bytes, array_value_to_json};
 use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
-use arrow::array::*;
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{Field, Schema};
 use arrow::record_batch::RecordBatch;
 use fdf_sdk::Sample;
-use serde_json::Value;
 use std::collections::HashMap;
 use std::fs::File;
 use std::sync::Arc;
@@ -62,11 +61,8 @@ impl ParquetReader {
             if mapping.is_empty() {
                 (original_schema.clone(), None)
             } else {
-                // Build projection: get indices of columns to read
-                let mut projection_indices = Vec::new();
-                let mut column_rename: HashMap<usize, String> = HashMap::new();
-                let mut new_fields = Vec::new();
-
+                // Resolve each mapped column to its index in the file schema
+                let mut projected: Vec<(usize, String, Field)> = Vec::new();
                 for (new_name, original_name) in &mapping {
                     if let Some((idx, field)) = original_schema
                         .fields()
@@ -74,12 +70,10 @@ impl ParquetReader {
                         .enumerate()
                         .find(|(_, f)| f.name() == original_name)
                     {
-                        projection_indices.push(idx);
-                        column_rename.insert(projection_indices.len() - 1, new_name.clone());
-                        new_fields.push(Field::new(
+                        projected.push((
+                            idx,
                             new_name.clone(),
-                            field.data_type().clone(),
-                            field.is_nullable(),
+                            field.as_ref().clone().with_name(new_name.clone()),
                         ));
                     } else {
                         return Err(anyhow::anyhow!(
@@ -94,14 +88,24 @@ impl ParquetReader {
                     }
                 }
 
+                // Projected batches keep file column order, so order the schema the same way
+                projected.sort_by_key(|(idx, _, _)| *idx);
+                let projection_indices: Vec<usize> =
+                    projected.iter().map(|(idx, _, _)| *idx).collect();
+                let column_rename: HashMap<usize, String> = projected
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, (_, new_name, _))| (pos, new_name.clone()))
+                    .collect();
+                let new_fields: Vec<Field> =
+                    projected.into_iter().map(|(_, _, field)| field).collect();
+
                 // Apply projection using ProjectionMask
-                // Get the parquet file's schema descriptor from the builder
+                // Roots (top-level columns) rather than leaves so nested columns project whole
                 let parquet_metadata = builder.metadata().clone();
                 let schema_desc = parquet_metadata.file_metadata().schema_descr();
-                let projection_mask = ::parquet::arrow::ProjectionMask::leaves(
-                    schema_desc,
-                    projection_indices.clone(),
-                );
+                let projection_mask =
+                    ::parquet::arrow::ProjectionMask::roots(schema_desc, projection_indices);
                 builder = builder.with_projection(projection_mask);
 
                 (Arc::new(Schema::new(new_fields)), Some(column_rename))
@@ -129,6 +133,7 @@ impl ParquetReader {
         // Pre-allocate HashMap with known capacity to reduce reallocations
         let field_count = self.schema.fields().len();
         let mut map = Map::with_capacity(field_count);
+        let mut binary = Vec::new(); // Binary columns are carried as raw bytes
 
         for (col_idx, field) in self.schema.fields().iter().enumerate() {
             let array = batch.column(col_idx);
@@ -144,67 +149,19 @@ impl ParquetReader {
                 field.name().clone()
             };
 
-            let value = match field.data_type() {
-                DataType::Utf8 | DataType::LargeUtf8 => {
-                    if let Some(arr) = array.as_any().downcast_ref::<StringArray>() {
-                        if arr.is_null(row_idx) {
-                            Value::Null
-                        } else {
-                            // Direct string slice to avoid unnecessary allocation
-                            Value::String(arr.value(row_idx).to_string())
-                        }
-                    } else if let Some(arr) = array.as_any().downcast_ref::<LargeStringArray>() {
-                        if arr.is_null(row_idx) {
-                            Value::Null
-                        } else {
-                            Value::String(arr.value(row_idx).to_string())
-                        }
-                    } else {
-                        Value::Null
-                    }
-                }
-                DataType::Int64 => {
-                    if let Some(arr) = array.as_any().downcast_ref::<Int64Array>() {
-                        if arr.is_null(row_idx) {
-                            Value::Null
-                        } else {
-                            Value::Number(arr.value(row_idx).into())
-                        }
-                    } else {
-                        Value::Null
-                    }
-                }
-                DataType::Float64 => {
-                    if let Some(arr) = array.as_any().downcast_ref::<Float64Array>() {
-                        if arr.is_null(row_idx) {
-                            Value::Null
-                        } else {
-                            Value::Number(
-                                serde_json::Number::from_f64(arr.value(row_idx))
-                                    .unwrap_or_else(|| serde_json::Number::from(0)),
-                            )
-                        }
-                    } else {
-                        Value::Null
-                    }
-                }
-                DataType::Boolean => {
-                    if let Some(arr) = array.as_any().downcast_ref::<BooleanArray>() {
-                        if arr.is_null(row_idx) {
-                            Value::Null
-                        } else {
-                            Value::Bool(arr.value(row_idx))
-                        }
-                    } else {
-                        Value::Null
-                    }
+            match array_value_to_bytes(array.as_ref(), row_idx) {
+                Some(bytes) => binary.push((col_name, bytes)),
+                None => {
+                    map.insert(col_name, array_value_to_json(array.as_ref(), row_idx));
                 }
-                _ => Value::Null,
-            };
-            map.insert(col_name, value);
+            }
         }
 
-        Sample(Value::Object(map))
+        let mut sample = Sample::from_map(map);
+        for (col_name, bytes) in binary {
+            sample.set_bytes(col_name, bytes);
+        }
+        sample
     }
 
     /// Load the next batch if needed
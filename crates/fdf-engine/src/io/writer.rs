@@ -1,6 +1,8 @@
+use crate::io::output::FileDigest;
 use arrow::datatypes::Schema;
 use fdf_sdk::Sample;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Unified writer trait for different data sinks
 /// Writer manages its own buffer and automatically flushes when buffer reaches partition size
@@ -11,13 +13,57 @@ pub trait Writer {
 
     /// Close the writer and finalize the output
     /// This will flush any remaining samples in the buffer
-    /// Returns true if any data was written, false otherwise
-    fn close(self: Box<Self>) -> anyhow::Result<bool>;
+    /// Returns the files written, empty if no data was written (or the sink writes no files)
+    fn close(self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>>;
 
     /// Get the schema
     fn schema(&self) -> &Arc<Schema>;
+
+    /// Samples passed to `write_sample` that were not written (sink.schema on_mismatch: drop)
+    fn dropped(&self) -> usize {
+        0
+    }
+}
+
+/// An output file finished by a writer
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrittenFile {
+    pub path: String,
+    pub rows: usize,
+    pub digest: FileDigest, // Computed while writing
+}
+
+/// Delay before retry `attempt` (from 1) of a request to a search or vector database sink:
+/// 200ms doubled after every attempt, at most 30 seconds
+pub(crate) fn retry_delay(attempt: usize) -> Duration {
+    const BASE: Duration = Duration::from_millis(200);
+    const MAX: Duration = Duration::from_secs(30);
+    let exponent = attempt.saturating_sub(1).min(31) as u32;
+    BASE.saturating_mul(1 << exponent).min(MAX)
 }
 
+pub mod arrow_ipc;
+pub mod csv;
+pub mod elasticsearch;
 pub mod jsonl;
 pub mod parquet;
+pub mod qdrant;
 pub mod sharded;
+pub mod sqlite;
+pub mod stdout;
+pub mod validated;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_cap() {
+        assert_eq!(retry_delay(1), Duration::from_millis(200));
+        assert_eq!(retry_delay(2), Duration::from_millis(400));
+        assert_eq!(retry_delay(8), Duration::from_millis(25_600));
+        assert_eq!(retry_delay(9), Duration::from_secs(30));
+        assert_eq!(retry_delay(1000), Duration::from_secs(30));
+        assert_eq!(retry_delay(0), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,156 @@
+use super::{Writer, WrittenFile};
+use crate::io::convert::{build_schema_from_samples, samples_to_batch};
+use crate::io::output::{self, Output};
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use fdf_sdk::Sample;
+use std::sync::Arc;
+
+/// Arrow IPC flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcFormat {
+    /// Random-access file format (.arrow), can be memory-mapped
+    File,
+    /// Streaming format (.arrows), readable without seeking
+    Stream,
+}
+
+impl IpcFormat {
+    /// Infer the IPC flavour from a file path (.arrows is a stream, anything else a file)
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".arrows") {
+            Self::Stream
+        } else {
+            Self::File
+        }
+    }
+}
+
+/// Underlying IPC writer, created on first flush once the schema is known
+enum IpcOutput {
+    File(FileWriter<Output>),
+    Stream(StreamWriter<Output>),
+}
+
+impl IpcOutput {
+    fn write(&mut self, batch: &RecordBatch) -> arrow::error::Result<()> {
+        match self {
+            Self::File(w) => w.write(batch),
+            Self::Stream(w) => w.write(batch),
+        }
+    }
+
+    /// Write the footer / end-of-stream marker and return the underlying output
+    fn into_inner(self) -> arrow::error::Result<Output> {
+        match self {
+            Self::File(w) => w.into_inner(),
+            Self::Stream(w) => w.into_inner(),
+        }
+    }
+}
+
+pub struct ArrowIpcWriter {
+    writer: Option<IpcOutput>,
+    format: IpcFormat,
+    input_schema: Arc<Schema>,
+    actual_schema: Option<Arc<Schema>>,
+    buffer: Vec<Sample>,
+    partition_size: usize,
+    path: String,           // Store path for potential deletion
+    samples_written: usize, // Track number of samples written
+}
+
+impl ArrowIpcWriter {
+    pub fn new(path: &str, schema: Arc<Schema>) -> anyhow::Result<Self> {
+        Self::with_format(path, schema, IpcFormat::from_path(path))
+    }
+
+    /// Create an ArrowIpcWriter with an explicit IPC flavour
+    pub fn with_format(path: &str, schema: Arc<Schema>, format: IpcFormat) -> anyhow::Result<Self> {
+        Ok(Self {
+            writer: None, // Will be created on first flush
+            format,
+            input_schema: schema,
+            actual_schema: None,
+            buffer: Vec::new(),
+            partition_size: 10000, // Default partition size
+            path: path.to_string(),
+            samples_written: 0,
+        })
+    }
+
+    /// Initialize the IPC writer with the actual schema from samples
+    fn init_writer(&mut self) -> anyhow::Result<()> {
+        if self.writer.is_some() || self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // Build schema from actual samples (includes annotator fields)
+        let batch_schema = build_schema_from_samples(&self.buffer, &self.input_schema)?;
+        self.actual_schema = Some(batch_schema.clone());
+
+        let output_file = Output::create(&self.path)?;
+        self.writer = Some(match self.format {
+            IpcFormat::File => IpcOutput::File(FileWriter::try_new(output_file, &batch_schema)?),
+            IpcFormat::Stream => {
+                IpcOutput::Stream(StreamWriter::try_new(output_file, &batch_schema)?)
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Flush buffer to disk as one record batch
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.init_writer()?;
+
+        let actual_schema = self.actual_schema.as_ref().unwrap();
+        let batch = samples_to_batch(&self.buffer, actual_schema)?;
+        self.samples_written += self.buffer.len();
+        self.writer.as_mut().unwrap().write(&batch)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl Writer for ArrowIpcWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.buffer.push(sample);
+
+        // Auto-flush when buffer reaches partition size
+        if self.buffer.len() >= self.partition_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        // Flush remaining samples (this will initialize writer if needed)
+        self.flush()?;
+
+        // Write the footer / end-of-stream marker if the writer was initialized
+        match self.writer.take() {
+            Some(writer) => Ok(vec![WrittenFile {
+                digest: writer.into_inner()?.finish()?,
+                path: self.path,
+                rows: self.samples_written,
+            }]),
+            None => {
+                // No data was written and writer was never initialized: delete the file
+                output::remove(&self.path);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        // Return actual_schema if available, otherwise input_schema
+        self.actual_schema.as_ref().unwrap_or(&self.input_schema)
+    }
+}
@@ -0,0 +1,233 @@
+use super::{Writer, WrittenFile};
+use crate::io::convert::sample_to_json;
+use crate::io::output::{self, Output};
+use crate::spec::CsvOptions;
+use arrow::datatypes::Schema;
+use fdf_sdk::Sample;
+use serde_json::Value;
+use std::sync::Arc;
+
+pub struct CsvWriter {
+    writer: ::csv::Writer<Output>,
+    schema: Arc<Schema>,
+    options: CsvOptions,
+    columns: Option<Vec<String>>, // Fixed on first flush (csv.columns, or input schema + annotator fields)
+    buffer: Vec<Sample>,
+    partition_size: usize,
+    path: String,           // Store path for potential deletion
+    samples_written: usize, // Track number of samples written
+}
+
+impl CsvWriter {
+    pub fn new(path: &str, schema: Arc<Schema>) -> anyhow::Result<Self> {
+        Self::with_options(path, schema, CsvOptions::default())
+    }
+
+    /// Create a CsvWriter with explicit delimiter/quote/header options
+    pub fn with_options(
+        path: &str,
+        schema: Arc<Schema>,
+        options: CsvOptions,
+    ) -> anyhow::Result<Self> {
+        let delimiter = single_byte(options.delimiter.as_deref().unwrap_or(","), "delimiter")?;
+        let quote = single_byte(&options.quote, "quote")?;
+        let writer = ::csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .quote(quote)
+            .has_headers(false) // Header is written manually once columns are known
+            .from_writer(Output::create(path)?);
+
+        Ok(Self {
+            writer,
+            schema,
+            options,
+            columns: None,
+            buffer: Vec::new(),
+            partition_size: 50000,
+            path: path.to_string(),
+            samples_written: 0,
+        })
+    }
+
+    /// Determine output columns: csv.columns, else the input schema plus any new fields in the
+    /// buffer
+    fn init_columns(&mut self) -> anyhow::Result<()> {
+        if self.columns.is_some() {
+            return Ok(());
+        }
+        if let Some(columns) = &self.options.columns {
+            if self.options.header {
+                self.writer.write_record(columns)?;
+            }
+            self.columns = Some(columns.clone());
+            return Ok(());
+        }
+
+        let mut columns: Vec<String> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        for sample in &self.buffer {
+            if let Some(obj) = sample_to_json(sample).as_object() {
+                for key in obj.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+        }
+
+        if self.options.header {
+            self.writer.write_record(&columns)?;
+        }
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    /// Flush buffer to disk
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.init_columns()?;
+
+        let columns = self.columns.as_ref().unwrap();
+        let mut record: Vec<String> = Vec::with_capacity(columns.len());
+        for sample in &self.buffer {
+            record.clear();
+            let json = sample_to_json(sample);
+            // CSV has a fixed header: a field missing from it would be lost
+            if self.options.columns.is_none() {
+                if let Some(field) = json
+                    .as_object()
+                    .and_then(|obj| obj.keys().find(|key| !columns.contains(key)))
+                {
+                    return Err(anyhow::anyhow!(
+                        "Field '{}' first appears after the CSV header of {} was written; list \
+                         the columns to write in the sink's csv.columns",
+                        field,
+                        self.path
+                    ));
+                }
+            }
+            record.extend(columns.iter().map(|c| cell(json.get(c))));
+            self.writer.write_record(&record)?;
+        }
+
+        self.samples_written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Render a JSON value as a CSV cell; nested values are written as JSON text
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn single_byte(s: &str, name: &str) -> anyhow::Result<u8> {
+    // Allow "\t" written literally in YAML single-quoted strings
+    let s = if s == "\\t" { "\t" } else { s };
+    match s.as_bytes() {
+        [b] => Ok(*b),
+        _ => Err(anyhow::anyhow!(
+            "CSV {} must be a single ASCII character, got {:?}",
+            name,
+            s
+        )),
+    }
+}
+
+impl Writer for CsvWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.buffer.push(sample);
+
+        // Auto-flush when buffer reaches partition size
+        if self.buffer.len() >= self.partition_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        // Flush remaining samples
+        self.flush()?;
+        let digest = self
+            .writer
+            .into_inner()
+            .map_err(|e| anyhow::anyhow!("Failed to flush CSV output: {}", e.error()))?
+            .finish()?;
+
+        // If no data was written, delete the file
+        if self.samples_written == 0 {
+            output::remove(&self.path);
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![WrittenFile {
+            path: self.path,
+            rows: self.samples_written,
+            digest,
+        }])
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fdf_sdk::testing::sample;
+    use serde_json::json;
+
+    fn writer(name: &str, options: CsvOptions) -> (Box<CsvWriter>, String) {
+        let path = std::env::temp_dir()
+            .join(format!("fdf-csv-{}-{}.csv", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut writer =
+            CsvWriter::with_options(&path, Arc::new(Schema::empty()), options).unwrap();
+        writer.partition_size = 1; // Header fixed by the first sample
+        (Box::new(writer), path)
+    }
+
+    #[test]
+    fn field_after_header_fails() {
+        let (mut writer, path) = writer("late", CsvOptions::default());
+        writer.write_sample(sample(json!({"id": 1}))).unwrap();
+        let error = writer
+            .write_sample(sample(json!({"id": 2, "score": 0.5})))
+            .unwrap_err();
+        assert!(error.to_string().contains("Field 'score'"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn explicit_columns_are_written() {
+        let options = CsvOptions {
+            columns: Some(vec!["id".to_string(), "score".to_string()]),
+            ..CsvOptions::default()
+        };
+        let (mut writer, path) = writer("columns", options);
+        writer.write_sample(sample(json!({"id": 1}))).unwrap();
+        writer
+            .write_sample(sample(json!({"id": 2, "score": 0.5, "extra": true})))
+            .unwrap();
+        writer.close().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "id,score\n1,\n2,0.5\n"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,216 @@
+use super::{retry_delay, Writer, WrittenFile};
+use crate::io::convert::sample_to_json;
+use crate::io::output::runtime;
+use crate::spec::ElasticsearchOptions;
+use arrow::datatypes::Schema;
+use fdf_sdk::Sample;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Bulk-indexes samples into an Elasticsearch / OpenSearch index
+pub struct ElasticsearchWriter {
+    client: reqwest::Client,
+    bulk_url: String,
+    auth: Option<String>, // Authorization header value
+    options: ElasticsearchOptions,
+    schema: Arc<Schema>,
+    buffer: Vec<Sample>,
+    samples_written: usize, // Track number of samples written
+}
+
+/// Outcome of one bulk request
+enum BulkOutcome {
+    /// Request processed; positions (within the request) of documents rejected with 429
+    Indexed(Vec<usize>),
+    /// The whole request failed in a retryable way (connection error, 429, 5xx)
+    Failed(String),
+}
+
+impl ElasticsearchWriter {
+    /// Create a writer for the cluster at `url` (e.g. http://localhost:9200)
+    pub fn new(
+        url: &str,
+        schema: Arc<Schema>,
+        options: ElasticsearchOptions,
+    ) -> anyhow::Result<Self> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(anyhow::anyhow!(
+                "Elasticsearch sink uri must be an http(s) url, got '{}'",
+                url
+            ));
+        }
+        if options.batch_size == 0 {
+            return Err(anyhow::anyhow!(
+                "elasticsearch.batch_size must be greater than 0"
+            ));
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            bulk_url: format!("{}/_bulk", url.trim_end_matches('/')),
+            auth: auth_header(),
+            options,
+            schema,
+            buffer: Vec::new(),
+            samples_written: 0,
+        })
+    }
+
+    /// Index the buffered samples, retrying rejected documents with exponential backoff
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // Action and source line of each document
+        let mut documents = Vec::with_capacity(self.buffer.len());
+        for sample in &self.buffer {
+            let mut action = json!({"_index": self.options.index});
+            if let Some(id) = self.document_id(sample) {
+                action["_id"] = Value::String(id);
+            }
+            documents.push((
+                json!({ "index": action }).to_string(),
+                serde_json::to_string(sample_to_json(sample).as_ref())?,
+            ));
+        }
+
+        let mut pending: Vec<usize> = (0..documents.len()).collect();
+        let mut attempt = 0;
+        loop {
+            let mut body = String::new();
+            for &i in &pending {
+                body.push_str(&documents[i].0);
+                body.push('\n');
+                body.push_str(&documents[i].1);
+                body.push('\n');
+            }
+
+            let reason = match runtime().block_on(self.bulk(body))? {
+                BulkOutcome::Indexed(rejected) if rejected.is_empty() => break,
+                BulkOutcome::Indexed(rejected) => {
+                    let reason = format!("{} documents rejected with 429", rejected.len());
+                    pending = rejected.into_iter().map(|i| pending[i]).collect();
+                    reason
+                }
+                BulkOutcome::Failed(reason) => reason,
+            };
+
+            attempt += 1;
+            if attempt > self.options.max_retries {
+                return Err(anyhow::anyhow!(
+                    "Bulk indexing into '{}' failed after {} retries: {}",
+                    self.options.index,
+                    self.options.max_retries,
+                    reason
+                ));
+            }
+            std::thread::sleep(retry_delay(attempt));
+        }
+
+        self.samples_written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Send one bulk request; non-retryable failures are returned as errors
+    async fn bulk(&self, body: String) -> anyhow::Result<BulkOutcome> {
+        let mut request = self
+            .client
+            .post(&self.bulk_url)
+            .header("Content-Type", "application/x-ndjson")
+            .body(body);
+        if let Some(auth) = &self.auth {
+            request = request.header("Authorization", auth);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return Ok(BulkOutcome::Failed(e.to_string())),
+        };
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Ok(BulkOutcome::Failed(format!("{}: {}", status, text)));
+        }
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Bulk request to {} failed ({}): {}",
+                self.bulk_url,
+                status,
+                text
+            ));
+        }
+
+        // Per-document results, in request order
+        let result: Value = serde_json::from_str(&text)?;
+        if result["errors"].as_bool() != Some(true) {
+            return Ok(BulkOutcome::Indexed(Vec::new()));
+        }
+        let mut rejected = Vec::new();
+        let items = result["items"].as_array().cloned().unwrap_or_default();
+        for (i, item) in items.iter().enumerate() {
+            let item = &item["index"];
+            match item["status"].as_u64().unwrap_or(0) {
+                200..=299 => {}
+                429 => rejected.push(i),
+                status => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to index document {} into '{}' ({}): {}",
+                        item["_id"],
+                        self.options.index,
+                        status,
+                        item["error"]
+                    ))
+                }
+            }
+        }
+        Ok(BulkOutcome::Indexed(rejected))
+    }
+
+    /// Document _id from the configured id field (strings and numbers)
+    fn document_id(&self, sample: &Sample) -> Option<String> {
+        match sample.get(self.options.id_field.as_deref()?)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Authorization header from ES_API_KEY, or basic auth from ES_USERNAME / ES_PASSWORD
+fn auth_header() -> Option<String> {
+    use base64::Engine;
+
+    if let Ok(key) = std::env::var("ES_API_KEY") {
+        return Some(format!("ApiKey {}", key));
+    }
+    let username = std::env::var("ES_USERNAME").ok()?;
+    let password = std::env::var("ES_PASSWORD").unwrap_or_default();
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+    Some(format!("Basic {}", credentials))
+}
+
+impl Writer for ElasticsearchWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.buffer.push(sample);
+
+        // Send a bulk request once a batch is full
+        if self.buffer.len() >= self.options.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        // Index remaining samples
+        self.flush()?;
+        Ok(Vec::new()) // Nothing is written to storage
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+}
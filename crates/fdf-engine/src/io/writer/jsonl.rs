@@ -1,12 +1,92 @@
-use super::Writer;
+use super::{Writer, WrittenFile};
+use crate::io::convert::sample_to_json;
+use crate::io::output::{self, FileDigest, Output};
 use arrow::datatypes::Schema;
 use fdf_sdk::Sample;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use flate2::write::GzEncoder;
+use std::io::Write;
 use std::sync::Arc;
 
+/// Compression codec for JSONL output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonlCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl JsonlCompression {
+    /// Parse a codec name from the sink spec ("none", "gzip"/"gz", "zstd"/"zst")
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name.to_lowercase().as_str() {
+            "none" | "uncompressed" => Ok(Self::None),
+            "gzip" | "gz" => Ok(Self::Gzip),
+            "zstd" | "zst" => Ok(Self::Zstd),
+            other => Err(anyhow::anyhow!(
+                "Unsupported JSONL compression '{}'. Expected one of: none, gzip, zstd",
+                other
+            )),
+        }
+    }
+
+    /// Infer the codec from a file path (.jsonl.gz / .jsonl.zst)
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".gz") {
+            Self::Gzip
+        } else if path.ends_with(".zst") {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Streaming output stream: plain or compressed, so memory stays flat
+enum JsonlOutput {
+    Plain(Output),
+    Gzip(GzEncoder<Output>),
+    Zstd(zstd::Encoder<'static, Output>),
+}
+
+impl JsonlOutput {
+    /// Flush buffers and write compression trailers
+    fn finish(self) -> std::io::Result<FileDigest> {
+        match self {
+            Self::Plain(w) => w.finish(),
+            Self::Gzip(w) => w.finish()?.finish(),
+            Self::Zstd(w) => w.finish()?.finish(),
+        }
+    }
+}
+
+impl Write for JsonlOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(w) => w.write(buf),
+            Self::Gzip(w) => w.write(buf),
+            Self::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Plain(w) => w.write_all(buf),
+            Self::Gzip(w) => w.write_all(buf),
+            Self::Zstd(w) => w.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(w) => w.flush(),
+            Self::Gzip(w) => w.flush(),
+            Self::Zstd(w) => w.flush(),
+        }
+    }
+}
+
 pub struct JsonlWriter {
-    writer: BufWriter<File>,
+    writer: JsonlOutput,
     schema: Arc<Schema>,
     buffer: Vec<Sample>,
     partition_size: usize,
@@ -16,8 +96,32 @@ pub struct JsonlWriter {
 
 impl JsonlWriter {
     pub fn new(path: &str, schema: Arc<Schema>) -> anyhow::Result<Self> {
-        let output_file = File::create(path)?;
-        let writer = BufWriter::new(output_file);
+        Self::with_compression(path, schema, JsonlCompression::from_path(path), None)
+    }
+
+    /// Create a JsonlWriter that compresses its output
+    /// `level` is the codec-specific compression level (gzip: 0-9, zstd: 1-22); None uses the codec default
+    pub fn with_compression(
+        path: &str,
+        schema: Arc<Schema>,
+        compression: JsonlCompression,
+        level: Option<i32>,
+    ) -> anyhow::Result<Self> {
+        let output_file = Output::create(path)?;
+        let writer = match compression {
+            JsonlCompression::None => JsonlOutput::Plain(output_file),
+            JsonlCompression::Gzip => {
+                let level = match level {
+                    Some(l) => flate2::Compression::new(l.clamp(0, 9) as u32),
+                    None => flate2::Compression::default(),
+                };
+                JsonlOutput::Gzip(GzEncoder::new(output_file, level))
+            }
+            JsonlCompression::Zstd => JsonlOutput::Zstd(zstd::Encoder::new(
+                output_file,
+                level.unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL),
+            )?),
+        };
         Ok(Self {
             writer,
             schema,
@@ -38,8 +142,8 @@ impl JsonlWriter {
         // This reduces the number of write syscalls
         let mut output = String::with_capacity(self.buffer.len() * 200); // Estimate 200 bytes per sample
         for sample in &self.buffer {
-            let json_value = sample.as_value();
-            let json_str = serde_json::to_string(json_value)?;
+            let json_value = sample_to_json(sample);
+            let json_str = serde_json::to_string(json_value.as_ref())?;
             output.push_str(&json_str);
             output.push('\n');
         }
@@ -67,20 +171,23 @@ impl Writer for JsonlWriter {
         Ok(())
     }
 
-    fn close(mut self: Box<Self>) -> anyhow::Result<bool> {
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
         // Flush remaining samples
         self.flush()?;
-        // Now flush the BufWriter to ensure all data is written to disk
-        self.writer.flush()?;
-        let has_data = self.samples_written > 0;
+        // Now finish the stream to ensure all data (and compression trailers) is written to disk
+        let digest = self.writer.finish()?;
 
         // If no data was written, delete the file
-        if !has_data {
-            drop(self.writer); // Ensure file is closed before deletion
-            let _ = std::fs::remove_file(&self.path);
+        if self.samples_written == 0 {
+            output::remove(&self.path);
+            return Ok(Vec::new());
         }
 
-        Ok(has_data)
+        Ok(vec![WrittenFile {
+            path: self.path,
+            rows: self.samples_written,
+            digest,
+        }])
     }
 
     fn schema(&self) -> &Arc<Schema> {
@@ -0,0 +1,245 @@
+use super::{retry_delay, Writer, WrittenFile};
+use crate::io::convert::sample_to_json;
+use crate::io::output::runtime;
+use crate::spec::QdrantOptions;
+use arrow::datatypes::Schema;
+use fdf_sdk::Sample;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Writes (id, vector, payload) points into a Qdrant collection
+pub struct QdrantWriter {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    options: QdrantOptions,
+    schema: Arc<Schema>,
+    buffer: Vec<Sample>,
+    collection_ready: bool, // Collection checked / created on first flush
+    samples_written: usize, // Track number of samples written
+}
+
+impl QdrantWriter {
+    /// Create a writer for the Qdrant instance at `url` (e.g. http://localhost:6333)
+    pub fn new(url: &str, schema: Arc<Schema>, options: QdrantOptions) -> anyhow::Result<Self> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(anyhow::anyhow!(
+                "Qdrant sink uri must be an http(s) url, got '{}'",
+                url
+            ));
+        }
+        if options.batch_size == 0 {
+            return Err(anyhow::anyhow!("qdrant.batch_size must be greater than 0"));
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key: std::env::var("QDRANT_API_KEY").ok(),
+            options,
+            schema,
+            buffer: Vec::new(),
+            collection_ready: false,
+            samples_written: 0,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.url, path));
+        match &self.api_key {
+            Some(key) => request.header("api-key", key),
+            None => request,
+        }
+    }
+
+    /// Create the collection with the vector size of the first point, unless it exists
+    async fn ensure_collection(&self, size: usize) -> anyhow::Result<()> {
+        let path = format!("/collections/{}", self.options.collection);
+        let response = self.request(reqwest::Method::GET, &path).send().await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        let response = self
+            .request(reqwest::Method::PUT, &path)
+            .json(&json!({"vectors": {"size": size, "distance": self.options.distance}}))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to create Qdrant collection '{}' ({}): {}",
+                self.options.collection,
+                status,
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Upsert the buffered samples as points, retrying 429 / 5xx responses with backoff
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let points = self
+            .buffer
+            .iter()
+            .map(|sample| self.point(sample))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if !self.collection_ready {
+            let size = points[0]["vector"].as_array().map_or(0, |v| v.len());
+            runtime().block_on(self.ensure_collection(size))?;
+            self.collection_ready = true;
+        }
+
+        let path = format!("/collections/{}/points?wait=true", self.options.collection);
+        let body = json!({ "points": points });
+        let mut attempt = 0;
+        loop {
+            let result = runtime().block_on(async {
+                let response = self
+                    .request(reqwest::Method::PUT, &path)
+                    .json(&body)
+                    .send()
+                    .await?;
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                Ok::<_, reqwest::Error>((status, text))
+            });
+
+            let reason = match result {
+                Ok((status, _)) if status.is_success() => break,
+                Ok((status, text)) if status.as_u16() == 429 || status.is_server_error() => {
+                    format!("{}: {}", status, text)
+                }
+                Ok((status, text)) => {
+                    return Err(anyhow::anyhow!(
+                        "Upsert into Qdrant collection '{}' failed ({}): {}",
+                        self.options.collection,
+                        status,
+                        text
+                    ))
+                }
+                Err(e) => e.to_string(),
+            };
+
+            attempt += 1;
+            if attempt > self.options.max_retries {
+                return Err(anyhow::anyhow!(
+                    "Upsert into Qdrant collection '{}' failed after {} retries: {}",
+                    self.options.collection,
+                    self.options.max_retries,
+                    reason
+                ));
+            }
+            std::thread::sleep(retry_delay(attempt));
+        }
+
+        self.samples_written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Build a Qdrant point from a sample
+    fn point(&self, sample: &Sample) -> anyhow::Result<Value> {
+        let vector = sample
+            .get_array(&self.options.vector_field)
+            .filter(|v| !v.is_empty() && v.iter().all(Value::is_number))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Sample has no vector (array of numbers) in field '{}'",
+                    self.options.vector_field
+                )
+            })?;
+
+        let mut payload = Map::new();
+        match &self.options.payload_fields {
+            Some(fields) => {
+                for field in fields {
+                    if let Some(value) = sample.get(field) {
+                        payload.insert(field.clone(), value.clone());
+                    }
+                }
+            }
+            None => {
+                if let Some(object) = sample_to_json(sample).as_object() {
+                    for (key, value) in object {
+                        if *key != self.options.vector_field {
+                            payload.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        let id = match self.options.id_field.as_deref().and_then(|f| sample.get(f)) {
+            Some(value) => point_id(value),
+            None => Value::String(Uuid::new_v4().to_string()),
+        };
+
+        Ok(json!({"id": id, "vector": vector, "payload": payload}))
+    }
+}
+
+/// Qdrant point id: unsigned integers and UUID strings as-is, anything else hashed to a UUID
+fn point_id(value: &Value) -> Value {
+    if let Some(n) = value.as_u64() {
+        return Value::from(n);
+    }
+    let text = match value {
+        Value::String(s) => match Uuid::parse_str(s) {
+            Ok(uuid) => return Value::String(uuid.to_string()),
+            Err(_) => s.clone(),
+        },
+        other => other.to_string(),
+    };
+    let digest = Sha256::digest(text.as_bytes());
+    let uuid = Uuid::from_slice(&digest[..16]).expect("16 bytes");
+    Value::String(uuid.to_string())
+}
+
+impl Writer for QdrantWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.buffer.push(sample);
+
+        // Upsert once a batch is full
+        if self.buffer.len() >= self.options.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        // Upsert remaining samples
+        self.flush()?;
+        Ok(Vec::new()) // Nothing is written to storage
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_ids() {
+        assert_eq!(point_id(&json!(42)), json!(42));
+        let uuid = "67E55044-10B1-426F-9247-BB680E5FE0C8";
+        assert_eq!(point_id(&json!(uuid)), json!(uuid.to_lowercase()));
+        // Other ids are hashed, so that they stay the same across runs
+        let hashed = point_id(&json!("doc-1"));
+        assert_eq!(hashed, point_id(&json!("doc-1")));
+        assert_ne!(hashed, point_id(&json!("doc-2")));
+        assert!(Uuid::parse_str(hashed.as_str().unwrap()).is_ok());
+        assert_ne!(point_id(&json!(-1)), point_id(&json!("1")));
+    }
+}
@@ -1,4 +1,5 @@
-use super::Writer;
+use super::{Writer, WrittenFile};
+use crate::io::{file_extension, output};
 use arrow::datatypes::Schema;
 use fdf_sdk::Sample;
 use std::collections::HashMap;
@@ -8,29 +9,36 @@ use std::sync::{Arc, Mutex};
 type WriterFactoryFn =
     Box<dyn Fn(&str, Arc<Schema>) -> anyhow::Result<Box<dyn Writer>> + Send + Sync>;
 
-/// Sharded writer that automatically writes to multiple shards based on samples per shard
+/// How samples are assigned to shards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardMode {
+    /// Fill shards one after another, starting a new shard every `samples_per_shard` samples
+    Sequential { samples_per_shard: usize },
+    /// Route each sample to shard `hash(sample[key]) % num_shards`
+    /// The hash is stable across runs and platforms, so the same key always lands in the same
+    /// shard. Samples without the key are hashed as null.
+    Hash { key: String, num_shards: usize },
+}
+
+/// Sharded writer that writes samples to multiple shard files in a directory
 pub struct ShardedWriter {
     writers: Mutex<HashMap<String, Box<dyn Writer>>>,
-    shard_key: Option<String>,
+    mode: ShardMode,
     base_path: String, // Directory path
     #[allow(dead_code)] // Not used when base_path is directory
     base_name: String,
     extension: String, // File extension
     schema: Arc<Schema>,
-    samples_per_shard: usize,
     shard_name_pattern: String, // Pattern for shard file names
     create_writer: WriterFactoryFn,
     current_shard_id: std::sync::atomic::AtomicUsize,
     current_shard_count: Mutex<usize>,
-    // Track sample count per shard when using shard_key
-    shard_key_counts: Mutex<HashMap<String, usize>>,
 }
 
 impl ShardedWriter {
     /// Create a new sharded writer
     /// - base_path: Base path for shard files (e.g., "output/data")
-    /// - shard_key: Optional field name to use for sharding. If None, uses sequential sharding
-    /// - samples_per_shard: Number of samples per shard before creating a new shard
+    /// - mode: Sequential (fixed samples per shard) or hash sharding on a key (fixed shard count)
     /// - shard_name_pattern: Pattern for shard file names. Supports placeholders:
     ///   - {base}: Base name without extension
     ///   - {shard_id}: Shard ID (zero-padded to 8 digits by default)
@@ -39,52 +47,59 @@ impl ShardedWriter {
     ///
     ///   Default: "{base}.shard_{shard_id:08}.{ext}"
     /// - create_writer: Function to create individual writers
+    /// - append: Continue shard numbering after the shards already in base_path
     pub fn new(
         base_path: &str,
         schema: Arc<Schema>,
-        shard_key: Option<String>,
-        samples_per_shard: usize,
+        mode: ShardMode,
         shard_name_pattern: Option<String>,
         create_writer: WriterFactoryFn,
+        append: bool,
     ) -> anyhow::Result<Self> {
+        if let ShardMode::Hash { num_shards: 0, .. } = mode {
+            return Err(anyhow::anyhow!("num_shards must be greater than 0"));
+        }
+        // Appending would need to number hash shards after the existing ones, moving keys across runs
+        if append && matches!(mode, ShardMode::Hash { .. }) {
+            return Err(anyhow::anyhow!(
+                "Hash sharding cannot append to existing shards"
+            ));
+        }
+
         // base_path is a directory, extract extension from pattern or default to jsonl
-        let extension = if shard_name_pattern
-            .as_ref()
-            .is_some_and(|p| p.ends_with(".parquet"))
-        {
-            ".parquet".to_string()
-        } else if shard_name_pattern
-            .as_ref()
-            .is_some_and(|p| p.ends_with(".jsonl"))
-        {
-            ".jsonl".to_string()
-        } else if shard_name_pattern
-            .as_ref()
-            .is_some_and(|p| p.ends_with(".json"))
-        {
-            ".json".to_string()
-        } else {
-            // Default to jsonl
-            ".jsonl".to_string()
-        };
+        let extension = shard_name_pattern
+            .as_deref()
+            .and_then(file_extension)
+            .unwrap_or(".jsonl")
+            .to_string();
+
+        // Remove temp files left behind by a crashed run
+        output::remove_temp_files(base_path);
 
         // Default pattern: "part-{shard_id:08}.{ext}"
         let pattern =
             shard_name_pattern.unwrap_or_else(|| format!("part-{{shard_id:08}}{}", extension));
 
+        let first_shard_id = if append {
+            next_shard_id(
+                base_path,
+                &pattern.replace("{ext}", extension.trim_start_matches('.')),
+            )?
+        } else {
+            0
+        };
+
         Ok(Self {
             writers: Mutex::new(HashMap::new()),
-            shard_key,
+            mode,
             base_path: base_path.to_string(),
             base_name: String::new(), // Not used when base_path is directory
             extension,
             schema,
-            samples_per_shard,
             shard_name_pattern: pattern,
             create_writer,
-            current_shard_id: std::sync::atomic::AtomicUsize::new(0),
+            current_shard_id: std::sync::atomic::AtomicUsize::new(first_shard_id),
             current_shard_count: Mutex::new(0),
-            shard_key_counts: Mutex::new(HashMap::new()),
         })
     }
 
@@ -92,8 +107,8 @@ impl ShardedWriter {
     fn get_shard_path(&self, shard_id: usize) -> String {
         let mut result = self.shard_name_pattern.clone();
 
-        // Replace {ext} if present
-        result = result.replace("{ext}", &self.extension);
+        // Replace {ext} if present; the pattern has its own dot before it
+        result = result.replace("{ext}", self.extension.trim_start_matches('.'));
 
         // Replace {shard_id} with formatting
         // Support patterns like {shard_id:08} or just {shard_id}
@@ -130,61 +145,39 @@ impl ShardedWriter {
         Ok(())
     }
 
-    /// Determine shard ID and check if we need to advance to next shard
-    /// Returns the shard ID to write to
+    /// Determine the shard ID for a sample
     fn determine_shard_id(&self, sample: &Sample) -> anyhow::Result<usize> {
-        if let Some(ref key) = self.shard_key {
-            // Use field value for sharding
-            if let Some(value) = sample.get_str(key) {
-                // Get or create shard ID for this key value
-                let mut counts = self.shard_key_counts.lock().unwrap();
-                let count = counts.entry(value.to_string()).or_insert(0);
-
-                // Calculate which shard this key value should go to
-                // We use a simple hash to distribute key values across shards initially
-                use std::collections::hash_map::DefaultHasher;
-                use std::hash::{Hash, Hasher};
-                let mut hasher = DefaultHasher::new();
-                value.hash(&mut hasher);
-                let hash = hasher.finish();
-                let base_shard = (hash % 1000) as usize; // Start with a base shard based on hash
-
-                // If current shard is full, advance to next shard for this key
-                let shard_id = if *count >= self.samples_per_shard {
-                    // Find next available shard ID
-                    let next_id = base_shard + (*count / self.samples_per_shard);
-                    *count = 0;
-                    next_id
-                } else {
-                    base_shard + (*count / self.samples_per_shard)
+        match &self.mode {
+            ShardMode::Sequential { samples_per_shard } => {
+                self.check_and_advance_shard(*samples_per_shard)
+            }
+            ShardMode::Hash { key, num_shards } => {
+                // Hash strings and binary fields by their contents and other values by their JSON text
+                let hash = match (sample.get(key), sample.get_bytes(key)) {
+                    (_, Some(bytes)) => stable_hash(bytes),
+                    (Some(serde_json::Value::String(s)), _) => stable_hash(s.as_bytes()),
+                    (Some(value), _) => stable_hash(value.to_string().as_bytes()),
+                    (None, None) => stable_hash(b"null"),
                 };
-
-                *count += 1;
-                self.get_writer(shard_id)?;
-                Ok(shard_id)
-            } else {
-                // Fallback to sequential sharding
-                self.check_and_advance_shard()
+                Ok((hash % *num_shards as u64) as usize)
             }
-        } else {
-            // Sequential sharding based on samples_per_shard
-            self.check_and_advance_shard()
         }
     }
 
     /// Check if we need to move to next shard (for sequential sharding)
-    fn check_and_advance_shard(&self) -> anyhow::Result<usize> {
+    fn check_and_advance_shard(&self, samples_per_shard: usize) -> anyhow::Result<usize> {
         let mut count = self.current_shard_count.lock().unwrap();
         let current_id = self
             .current_shard_id
             .load(std::sync::atomic::Ordering::Relaxed);
 
         // If current shard is full, advance to next shard
-        if *count >= self.samples_per_shard {
+        if *count >= samples_per_shard {
             let next_id = current_id + 1;
             self.current_shard_id
                 .store(next_id, std::sync::atomic::Ordering::Relaxed);
-            *count = 0;
+            *count = 1; // This sample is the first of the new shard
+
             // Ensure writer exists for new shard
             self.get_writer(next_id)?;
             Ok(next_id)
@@ -195,6 +188,40 @@ impl ShardedWriter {
     }
 }
 
+/// Shard ID following the highest-numbered existing shard matching the name pattern
+fn next_shard_id(base_path: &str, name_pattern: &str) -> anyhow::Result<usize> {
+    // Turn the pattern into a regex capturing the shard ID, e.g. "part-(\d+)\.jsonl"
+    let placeholder = regex::Regex::new(r"\{shard_id(?::\d+)?\}").unwrap();
+    let parts: Vec<String> = placeholder.split(name_pattern).map(regex::escape).collect();
+    let re = regex::Regex::new(&format!("^{}$", parts.join(r"(\d+)")))?;
+
+    let mut next_id = 0;
+    for file in output::list_files(base_path)? {
+        let name = std::path::Path::new(&file)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Some(id) = re
+            .captures(&name)
+            .and_then(|caps| caps.get(1))
+            .and_then(|m| m.as_str().parse::<usize>().ok())
+        {
+            next_id = next_id.max(id + 1);
+        }
+    }
+    Ok(next_id)
+}
+
+/// 64-bit FNV-1a hash; unlike DefaultHasher its output is fixed across Rust releases
+fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
 impl Writer for ShardedWriter {
     fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
         // Determine which shard to write to
@@ -212,19 +239,197 @@ impl Writer for ShardedWriter {
         Ok(())
     }
 
-    fn close(self: Box<Self>) -> anyhow::Result<bool> {
+    fn close(self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
         // Close all shard writers
+        // On failure the remaining writers are dropped, which discards their temp files
         let mut writers = self.writers.lock().unwrap();
-        let mut has_any_data = false;
+        let mut files = Vec::new();
         for (_, writer) in writers.drain() {
-            if writer.close()? {
-                has_any_data = true;
-            }
+            files.extend(writer.close()?);
         }
-        Ok(has_any_data)
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
     }
 
     fn schema(&self) -> &Arc<Schema> {
         &self.schema
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fdf_sdk::testing::sample;
+    use serde_json::json;
+
+    /// Writer counting the samples it receives
+    struct Counter {
+        path: String,
+        samples: usize,
+        schema: Arc<Schema>,
+    }
+
+    impl Writer for Counter {
+        fn write_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+            self.samples += 1;
+            Ok(())
+        }
+
+        fn close(self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+            Ok(vec![WrittenFile {
+                path: self.path,
+                rows: self.samples,
+                digest: Default::default(),
+            }])
+        }
+
+        fn schema(&self) -> &Arc<Schema> {
+            &self.schema
+        }
+    }
+
+    fn sharded(base_path: &str, mode: ShardMode, append: bool) -> ShardedWriter {
+        try_sharded(base_path, mode, append).unwrap()
+    }
+
+    fn try_sharded(
+        base_path: &str,
+        mode: ShardMode,
+        append: bool,
+    ) -> anyhow::Result<ShardedWriter> {
+        let factory: WriterFactoryFn = Box::new(|path, schema| {
+            Ok(Box::new(Counter {
+                path: path.to_string(),
+                samples: 0,
+                schema,
+            }))
+        });
+        ShardedWriter::new(
+            base_path,
+            Arc::new(Schema::empty()),
+            mode,
+            Some("part-{shard_id:03}.{ext}".to_string()),
+            factory,
+            append,
+        )
+    }
+
+    fn file_names(writer: ShardedWriter) -> Vec<(String, usize)> {
+        Box::new(writer)
+            .close()
+            .unwrap()
+            .into_iter()
+            .map(|file| {
+                let name = std::path::Path::new(&file.path).file_name().unwrap();
+                (name.to_string_lossy().into_owned(), file.rows)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sequential_shards_fill_in_order() {
+        let mut writer = sharded(
+            "/nonexistent/out",
+            ShardMode::Sequential {
+                samples_per_shard: 2,
+            },
+            false,
+        );
+        for id in 0..5 {
+            writer.write_sample(sample(json!({ "id": id }))).unwrap();
+        }
+        let expected = [
+            ("part-000.jsonl", 2),
+            ("part-001.jsonl", 2),
+            ("part-002.jsonl", 1),
+        ];
+        let expected: Vec<(String, usize)> =
+            expected.iter().map(|(n, c)| (n.to_string(), *c)).collect();
+        assert_eq!(file_names(writer), expected);
+    }
+
+    #[test]
+    fn hash_routing_is_stable() {
+        let mode = ShardMode::Hash {
+            key: "user".to_string(),
+            num_shards: 4,
+        };
+        let writer = sharded("/nonexistent/out", mode, false);
+        let shard = |value: serde_json::Value| {
+            writer
+                .determine_shard_id(&sample(json!({ "user": value })))
+                .unwrap()
+        };
+        // FNV-1a of the key text, modulo the shard count
+        assert_eq!(shard(json!("alice")), (stable_hash(b"alice") % 4) as usize);
+        assert_eq!(shard(json!(42)), (stable_hash(b"42") % 4) as usize);
+        assert_eq!(shard(json!("alice")), shard(json!("alice")));
+        let missing = writer.determine_shard_id(&sample(json!({}))).unwrap();
+        assert_eq!(missing, shard(serde_json::Value::Null));
+        for user in 0..100 {
+            assert!(shard(json!(format!("user-{}", user))) < 4);
+        }
+    }
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn append_continues_after_existing_shards() {
+        let dir = std::env::temp_dir().join(format!("fdf-sharded-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["part-000.jsonl", "part-004.jsonl", "other-009.jsonl"] {
+            std::fs::write(dir.join(name), "{}\n").unwrap();
+        }
+        let base_path = dir.to_string_lossy().into_owned();
+
+        let mode = ShardMode::Sequential {
+            samples_per_shard: 10,
+        };
+        let mut writer = sharded(&base_path, mode, true);
+        writer.write_sample(sample(json!({"id": 1}))).unwrap();
+        assert_eq!(file_names(writer), [("part-005.jsonl".to_string(), 1)]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_sharding_rejects_append() {
+        let mode = ShardMode::Hash {
+            key: "user".to_string(),
+            num_shards: 4,
+        };
+        let error = try_sharded("/nonexistent/out", mode, true).err().unwrap();
+        assert!(error.to_string().contains("cannot append"));
+    }
+
+    #[test]
+    fn extension_is_the_longest_known_suffix() {
+        let cases = [
+            ("part-{shard_id}.jsonl.gz", ".jsonl.gz"),
+            ("part-{shard_id}.jsonl.zst", ".jsonl.zst"),
+            ("part-{shard_id}.ipc", ".ipc"),
+            ("part-{shard_id}.arrows", ".arrows"),
+            ("part-{shard_id}.{ext}", ".jsonl"),
+        ];
+        for (pattern, extension) in cases {
+            let factory: WriterFactoryFn = Box::new(|_, _| unreachable!("nothing is written"));
+            let writer = ShardedWriter::new(
+                "/nonexistent/out",
+                Arc::new(Schema::empty()),
+                ShardMode::Sequential {
+                    samples_per_shard: 1,
+                },
+                Some(pattern.to_string()),
+                factory,
+                false,
+            )
+            .unwrap();
+            assert_eq!(writer.extension, extension, "{}", pattern);
+        }
+    }
+}
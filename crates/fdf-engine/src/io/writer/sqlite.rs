@@ -0,0 +1,223 @@
+use super::{Writer, WrittenFile};
+use crate::io::convert::build_schema_from_samples;
+use crate::io::output::{self, FileDigest};
+use crate::spec::SqliteOptions;
+use arrow::datatypes::{DataType, Schema};
+use fdf_sdk::Sample;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Writes samples into a table of a local SQLite database
+/// The table is created on first flush with columns derived from the Arrow schema.
+pub struct SqliteWriter {
+    connection: Option<Connection>, // Taken on close
+    input_schema: Arc<Schema>,
+    actual_schema: Option<Arc<Schema>>,
+    options: SqliteOptions,
+    buffer: Vec<Sample>,
+    partition_size: usize,
+    path: String,           // Final database path
+    temp_path: String,      // Database is built here and renamed to path on close
+    samples_written: usize, // Track number of samples written
+}
+
+impl SqliteWriter {
+    pub fn new(path: &str, schema: Arc<Schema>) -> anyhow::Result<Self> {
+        Self::with_options(path, schema, SqliteOptions::default())
+    }
+
+    /// Create a SqliteWriter writing to the table named in `options`
+    pub fn with_options(
+        path: &str,
+        schema: Arc<Schema>,
+        options: SqliteOptions,
+    ) -> anyhow::Result<Self> {
+        if output::is_remote(path) {
+            return Err(anyhow::anyhow!(
+                "SQLite sink requires a local path, got '{}'",
+                path
+            ));
+        }
+
+        // Build a fresh database at a temp path so readers never see a partial file
+        let temp_path = output::temp_path(path);
+        let _ = std::fs::remove_file(&temp_path);
+        let connection = Connection::open(&temp_path)?;
+
+        Ok(Self {
+            connection: Some(connection),
+            input_schema: schema,
+            actual_schema: None,
+            options,
+            buffer: Vec::new(),
+            partition_size: 10000, // Default partition size
+            path: path.to_string(),
+            temp_path,
+            samples_written: 0,
+        })
+    }
+
+    fn connection(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+
+    /// Create the table from the actual schema (input + annotator fields) on first flush
+    fn init_table(&mut self) -> anyhow::Result<()> {
+        if self.actual_schema.is_some() {
+            return Ok(());
+        }
+
+        let schema = build_schema_from_samples(&self.buffer, &self.input_schema)?;
+        let columns: Vec<String> = schema
+            .fields()
+            .iter()
+            .map(|f| format!("{} {}", quote_identifier(f.name()), sql_type(f.data_type())))
+            .collect();
+        self.connection().execute(
+            &format!(
+                "CREATE TABLE {} ({})",
+                quote_identifier(&self.options.table),
+                columns.join(", ")
+            ),
+            [],
+        )?;
+
+        self.actual_schema = Some(schema);
+        Ok(())
+    }
+
+    /// Insert the buffered samples in a single transaction
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.init_table()?;
+
+        let schema = self.actual_schema.as_ref().unwrap();
+        let column_names: Vec<String> = schema
+            .fields()
+            .iter()
+            .map(|f| quote_identifier(f.name()))
+            .collect();
+        let placeholders = vec!["?"; column_names.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_identifier(&self.options.table),
+            column_names.join(", "),
+            placeholders
+        );
+
+        let transaction = self.connection.as_mut().unwrap().transaction()?;
+        {
+            let mut statement = transaction.prepare(&sql)?;
+            for sample in &self.buffer {
+                let row: Vec<SqlValue> = schema
+                    .fields()
+                    .iter()
+                    .map(|f| match sample.get_bytes(f.name()) {
+                        Some(bytes) => SqlValue::Blob(bytes.to_vec()),
+                        None => sql_value(sample.get(f.name())),
+                    })
+                    .collect();
+                statement.execute(rusqlite::params_from_iter(row))?;
+            }
+        }
+        transaction.commit()?;
+
+        self.samples_written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// SQLite column type for an Arrow type; nested and temporal values are stored as text
+fn sql_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => "INTEGER",
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => "REAL",
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+/// Convert a JSON value to a SQLite value; arrays and objects are stored as JSON text
+fn sql_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(*b as i64),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map(SqlValue::Real).unwrap_or(SqlValue::Null),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(other) => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Writer for SqliteWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.buffer.push(sample);
+
+        // Auto-flush when buffer reaches partition size
+        if self.buffer.len() >= self.partition_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        // Flush remaining samples
+        self.flush()?;
+        let has_data = self.samples_written > 0;
+
+        // Close the database, then publish it (or drop it if no data was written)
+        if let Some(connection) = self.connection.take() {
+            if let Err((_, e)) = connection.close() {
+                let _ = std::fs::remove_file(&self.temp_path);
+                return Err(e.into());
+            }
+        }
+        if !has_data {
+            let _ = std::fs::remove_file(&self.temp_path);
+            return Ok(Vec::new());
+        }
+        std::fs::rename(&self.temp_path, &self.path)?;
+
+        Ok(vec![WrittenFile {
+            digest: FileDigest::of_file(&self.path)?,
+            path: self.path.clone(),
+            rows: self.samples_written,
+        }])
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        // Return actual_schema if available, otherwise input_schema
+        self.actual_schema.as_ref().unwrap_or(&self.input_schema)
+    }
+}
+
+impl Drop for SqliteWriter {
+    fn drop(&mut self) {
+        // Not closed (failed run): discard the partial database
+        if let Some(connection) = self.connection.take() {
+            drop(connection);
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
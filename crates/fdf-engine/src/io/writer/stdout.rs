@@ -0,0 +1,64 @@
+use super::{Writer, WrittenFile};
+use crate::io::convert::sample_to_json;
+use arrow::datatypes::Schema;
+use fdf_sdk::Sample;
+use std::io::{BufWriter, ErrorKind, Stdout, Write};
+use std::sync::Arc;
+
+/// Writes samples as JSONL to standard output, e.g. for piping into `jq`
+/// If the reading end of the pipe goes away (e.g. `| head`), remaining samples are discarded.
+pub struct StdoutWriter {
+    writer: BufWriter<Stdout>,
+    schema: Arc<Schema>,
+    samples_written: usize, // Track number of samples written
+    closed: bool,           // Set once the pipe is closed by the reader
+}
+
+impl StdoutWriter {
+    pub fn new(schema: Arc<Schema>) -> Self {
+        Self {
+            writer: BufWriter::new(std::io::stdout()),
+            schema,
+            samples_written: 0,
+            closed: false,
+        }
+    }
+
+    /// Treat a broken pipe as the end of output rather than an error
+    fn handle(&mut self, result: std::io::Result<()>) -> anyhow::Result<()> {
+        match result {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            other => Ok(other?),
+        }
+    }
+}
+
+impl Writer for StdoutWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+
+        let mut line = serde_json::to_vec(sample_to_json(&sample).as_ref())?;
+        line.push(b'\n');
+        let result = self.writer.write_all(&line);
+        self.handle(result)?;
+        self.samples_written += 1;
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        if !self.closed {
+            let result = self.writer.flush();
+            self.handle(result)?;
+        }
+        Ok(Vec::new()) // Nothing is written to storage
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+}
@@ -0,0 +1,122 @@
+use super::{Writer, WrittenFile};
+use crate::io::reader::cast::CastType;
+use crate::spec::SinkSchema;
+use arrow::datatypes::{Field, Schema};
+use fdf_sdk::Sample;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Parsed declared sink schema (sink.schema)
+pub struct DeclaredSchema {
+    columns: Vec<(String, CastType, bool)>, // Name, type, nullable
+    drop_mismatches: bool,
+    arrow_schema: Arc<Schema>,
+}
+
+impl DeclaredSchema {
+    pub fn new(spec: &SinkSchema) -> anyhow::Result<Self> {
+        let drop_mismatches = match spec.on_mismatch.as_str() {
+            "error" => false,
+            "drop" => true,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown sink.schema.on_mismatch '{}'. Expected: error, drop",
+                    other
+                ))
+            }
+        };
+
+        let mut columns = Vec::with_capacity(spec.columns.len());
+        for column in &spec.columns {
+            let cast_type = CastType::parse(&column.data_type).map_err(|e| {
+                anyhow::anyhow!("Invalid type for sink column '{}': {}", column.name, e)
+            })?;
+            columns.push((column.name.clone(), cast_type, column.nullable));
+        }
+
+        let fields: Vec<Field> = columns
+            .iter()
+            .map(|(name, cast_type, nullable)| Field::new(name, cast_type.data_type(), *nullable))
+            .collect();
+
+        Ok(Self {
+            columns,
+            drop_mismatches,
+            arrow_schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// Arrow schema of the declared columns, in declaration order
+    pub fn arrow_schema(&self) -> &Arc<Schema> {
+        &self.arrow_schema
+    }
+
+    /// Project a sample onto the declared columns, checking presence and types
+    fn conform(&self, sample: &Sample) -> anyhow::Result<Sample> {
+        let mut row = Map::with_capacity(self.columns.len());
+        for (name, cast_type, nullable) in &self.columns {
+            let value = sample.get(name).cloned().unwrap_or(Value::Null);
+            if value.is_null() {
+                if !nullable {
+                    return Err(anyhow::anyhow!(
+                        "Sample does not match sink schema: column '{}' is missing or null",
+                        name
+                    ));
+                }
+            } else if !cast_type.matches(&value) {
+                return Err(anyhow::anyhow!(
+                    "Sample does not match sink schema: column '{}' expected {:?}, got {}",
+                    name,
+                    cast_type,
+                    value
+                ));
+            }
+            row.insert(name.clone(), value);
+        }
+        Ok(Sample::from_map(row))
+    }
+}
+
+/// Writer that validates samples against a declared sink schema before passing them on
+/// Only declared columns reach the inner writer, so every flush has the same schema.
+pub struct ValidatingWriter {
+    inner: Box<dyn Writer>,
+    schema: DeclaredSchema,
+    dropped: usize, // Samples skipped with on_mismatch: drop
+}
+
+impl ValidatingWriter {
+    /// Wrap a writer created with `schema.arrow_schema()` as its input schema
+    pub fn new(inner: Box<dyn Writer>, schema: DeclaredSchema) -> Self {
+        Self {
+            inner,
+            schema,
+            dropped: 0,
+        }
+    }
+}
+
+impl Writer for ValidatingWriter {
+    fn write_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        match self.schema.conform(&sample) {
+            Ok(conformed) => self.inner.write_sample(conformed),
+            Err(_) if self.schema.drop_mismatches => {
+                self.dropped += 1;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn close(self: Box<Self>) -> anyhow::Result<Vec<WrittenFile>> {
+        self.inner.close()
+    }
+
+    fn schema(&self) -> &Arc<Schema> {
+        self.schema.arrow_schema()
+    }
+
+    fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
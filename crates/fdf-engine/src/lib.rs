@@ -1,4 +1,5 @@
 pub mod io;
+pub mod manifest;
 pub mod plan;
 pub mod runner;
 pub mod spec;
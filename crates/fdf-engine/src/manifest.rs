@@ -0,0 +1,162 @@
+//! Run manifest and `_SUCCESS` marker
+//!
+//! After a successful run the engine writes `{uri}/manifest.json` describing the final output
+//! (files with row counts, sizes and checksums), per-operator statistics and fingerprints of
+//! the pipeline, then an empty `{uri}/_SUCCESS` marker. Downstream jobs should only read
+//! an output once `_SUCCESS` exists.
+
+use crate::io::output::{self, Output};
+use crate::io::writer::WrittenFile;
+use crate::plan::StepStatistics;
+use crate::spec::PipelineSpec;
+use fdf_sdk::MetricValue;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const SUCCESS_FILE: &str = "_SUCCESS";
+pub const FDF_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Keys of the metadata stamped into parquet output files
+pub const PIPELINE_FINGERPRINT_KEY: &str = "fdf.pipeline_fingerprint";
+pub const FDF_VERSION_KEY: &str = "fdf.version";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub run_id: String,     // Id of the run that wrote the output (Context::run_id)
+    pub created_at: String, // RFC 3339 timestamp of the end of the run
+    pub spec_fingerprint: String, // SHA-256 of the pipeline spec
+    pub pipeline_fingerprint: String, // See `pipeline_fingerprint`; also stamped into parquet files
+    pub fdf_version: String,
+    pub num_input_documents: usize,
+    pub num_output_documents: usize,
+    pub num_dropped_by_sink: usize, // Not matching sink.schema (on_mismatch: drop)
+    pub files: Vec<ManifestFile>,   // Final output files
+    pub operators: Vec<OperatorStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestFile {
+    pub path: String, // Relative to the sink uri
+    pub rows: usize,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorStats {
+    pub name: String,
+    pub index: usize,
+    pub version: String,
+    pub documents_in: usize,
+    pub documents_removed: usize,
+    pub documents_failed: usize, // Part of documents_removed: operator errors
+    pub processing_time_ms: u64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, MetricValue>, // Custom metrics recorded by the operator
+}
+
+impl Manifest {
+    /// Build the manifest for a finished run
+    /// `files` are the final output files, as reported by the writer on close
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        run_id: &str,
+        spec: &PipelineSpec,
+        pipeline_fingerprint: &str,
+        files: &[WrittenFile],
+        step_statistics: &[StepStatistics],
+        num_input_documents: usize,
+        num_output_documents: usize,
+        num_dropped_by_sink: usize,
+    ) -> anyhow::Result<Self> {
+        let base = format!("{}/", spec.sink.uri.trim_end_matches('/'));
+        let manifest_files = files
+            .iter()
+            .map(|file| ManifestFile {
+                path: file
+                    .path
+                    .strip_prefix(&base)
+                    .unwrap_or(&file.path)
+                    .to_string(),
+                rows: file.rows,
+                bytes: file.digest.bytes,
+                sha256: file.digest.sha256.clone(),
+            })
+            .collect();
+
+        let operators = step_statistics
+            .iter()
+            .map(|s| OperatorStats {
+                name: s.step_name.clone(),
+                index: s.step_index,
+                version: s.version.clone(),
+                documents_in: s.documents_remaining_before,
+                documents_removed: s.documents_removed,
+                documents_failed: s.documents_failed,
+                processing_time_ms: s.processing_time_ms,
+                metrics: s.metrics.clone(),
+            })
+            .collect();
+
+        Ok(Self {
+            run_id: run_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            spec_fingerprint: spec_fingerprint(spec)?,
+            pipeline_fingerprint: pipeline_fingerprint.to_string(),
+            fdf_version: FDF_VERSION.to_string(),
+            num_input_documents,
+            num_output_documents,
+            num_dropped_by_sink,
+            files: manifest_files,
+            operators,
+        })
+    }
+
+    /// Write `{uri}/manifest.json`, then the `{uri}/_SUCCESS` marker
+    pub fn write(&self, uri: &str) -> anyhow::Result<()> {
+        let base = uri.trim_end_matches('/');
+
+        let mut manifest = Output::create(&format!("{}/{}", base, MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(&mut manifest, self)?;
+        manifest.finish()?;
+
+        Output::create(&format!("{}/{}", base, SUCCESS_FILE))?.finish()?;
+        Ok(())
+    }
+}
+
+/// Remove the `_SUCCESS` marker of a previous run, so it only exists while the output is complete
+pub fn clear_success(uri: &str) {
+    output::remove(&format!("{}/{}", uri.trim_end_matches('/'), SUCCESS_FILE));
+}
+
+/// SHA-256 of the serialized pipeline spec; identical specs produce identical fingerprints
+pub fn spec_fingerprint(spec: &PipelineSpec) -> anyhow::Result<String> {
+    let bytes = serde_json::to_vec(spec)?;
+    Ok(format!("{:x}", Sha256::digest(bytes)))
+}
+
+/// SHA-256 of what determines the output of a pipeline: the fdf version, the seed and each
+/// step's operator name, version and config (`versions` holds the operator versions in step
+/// order). Sources, sinks and other I/O settings are left out, so the same pipeline run on
+/// other data or written elsewhere has the same fingerprint. Config keys are sorted, so
+/// reordering them in the YAML does not change it.
+pub fn pipeline_fingerprint(spec: &PipelineSpec, versions: &[String]) -> anyhow::Result<String> {
+    let mut steps = Vec::new();
+    for (node, version) in spec.pipeline.iter().zip(versions) {
+        steps.push(serde_json::json!({
+            "name": node.name,
+            "version": version,
+            "config": serde_json::to_value(&node.config)?, // Objects have sorted keys
+        }));
+    }
+    let resolved = serde_json::json!({
+        "fdf_version": FDF_VERSION,
+        "seed": spec.seed,
+        "steps": steps,
+    });
+    let bytes = serde_json::to_vec(&resolved)?;
+    Ok(format!("{:x}", Sha256::digest(bytes)))
+}
@@ -1,17 +1,27 @@
-use crate::io::{ReaderFactory, Writer, WriterFactory};
-use crate::spec::PipelineSpec;
-use fdf_sdk::{Operator, OperatorRegistry, Result, Sample};
+use crate::io::{hub, output, ReaderFactory, Writer, WriterFactory};
+use crate::manifest::{self, Manifest};
+use crate::spec::{PipelineSpec, SinkSpec};
+use arrow::datatypes::Schema;
+use fdf_sdk::metadata::DEFAULT_OPERATOR_VERSION;
+use fdf_sdk::{
+    BatchError, Context, MetricValue, Metrics, OpError, Operator, OperatorRegistry, Result, Sample,
+    SampleDiff, Value,
+};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::sync::Arc;
 
 pub struct Plan {
     operators: Vec<(String, Box<dyn Operator>)>,
+    versions: Vec<String>, // Operator versions, in step order
+    fingerprint: String,   // manifest::pipeline_fingerprint
     spec: PipelineSpec,
 }
 
 pub struct ProcessingStatistics {
     pub num_documents: usize,
+    pub documents_dropped_by_sink: usize, // Not matching sink.schema (on_mismatch: drop)
     pub step_statistics: Vec<StepStatistics>,
     pub read_time_ms: u64,
     pub write_time_ms: u64,
@@ -20,42 +30,223 @@ pub struct ProcessingStatistics {
 pub struct StepStatistics {
     pub step_name: String,
     pub step_index: usize,
+    pub version: String, // Operator version (registry metadata)
     pub processing_time_ms: u64,
     pub documents_removed: usize,
+    pub documents_failed: usize, // Part of documents_removed: operator errors, written to the error output
     pub documents_remaining_before: usize,
     pub total_documents: usize,
+    pub metrics: BTreeMap<String, MetricValue>, // Custom metrics recorded by the operator
 }
 
 impl Plan {
-    pub fn compile(spec: PipelineSpec, registry: &OperatorRegistry) -> Result<Self> {
+    pub fn compile(mut spec: PipelineSpec, registry: &OperatorRegistry) -> Result<Self> {
         let mut operators = Vec::new();
+        let mut versions = Vec::new();
+
+        // Steps using an old name (alias) run under the operator's current name
+        for operator_node in &mut spec.pipeline {
+            if let Some(name) = registry.resolve(&operator_node.name) {
+                if name != operator_node.name {
+                    eprintln!(
+                        "Warning: operator '{}' was renamed to '{}'; update the pipeline",
+                        operator_node.name, name
+                    );
+                    operator_node.name = name.to_string();
+                }
+            }
+        }
 
         for operator_node in &spec.pipeline {
             let operator: Box<dyn Operator> =
                 registry.build(&operator_node.name, &operator_node.config)?;
             operators.push((operator_node.name.clone(), operator));
+            versions.push(
+                registry
+                    .metadata(&operator_node.name)
+                    .map(|m| m.version.clone())
+                    .unwrap_or_else(|| DEFAULT_OPERATOR_VERSION.to_string()),
+            );
+        }
+
+        // Stamp the fingerprint into the output files, so each shard records what produced it
+        let fingerprint = manifest::pipeline_fingerprint(&spec, &versions)?;
+        for sink in std::iter::once(&mut spec.sink).chain(spec.sinks.iter_mut()) {
+            sink.metadata.insert(
+                manifest::PIPELINE_FINGERPRINT_KEY.to_string(),
+                fingerprint.clone(),
+            );
+            sink.metadata.insert(
+                manifest::FDF_VERSION_KEY.to_string(),
+                manifest::FDF_VERSION.to_string(),
+            );
+        }
+
+        let plan = Self {
+            operators,
+            versions,
+            fingerprint,
+            spec,
+        };
+        if plan.spec.validate_columns {
+            let reader = ReaderFactory::create(&plan.spec.source)?;
+            plan.validate_columns(reader.schema())?;
+        }
+        Ok(plan)
+    }
+
+    /// Check that every column an operator requires is in the source or produced by an earlier
+    /// step. Nested paths are accepted when their top-level column exists.
+    fn validate_columns(&self, source_schema: &Schema) -> Result<()> {
+        if source_schema.fields().is_empty() {
+            return Ok(()); // Nothing known about the input (e.g. empty JSONL file)
+        }
+
+        let mut available: Vec<String> = source_schema
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        for (step_idx, (name, op)) in self.operators.iter().enumerate() {
+            let Some(columns) = op.columns() else {
+                return Ok(()); // Undeclared outputs: any column may exist from here on
+            };
+            for column in &columns.requires {
+                if !column_available(&available, column) {
+                    return Err(anyhow::anyhow!(
+                        "Step {} ({}) requires column '{}' which is not in the source \
+                         and not produced by any prior step",
+                        step_idx,
+                        name,
+                        column
+                    ));
+                }
+            }
+            available.extend(columns.produces);
+        }
+        Ok(())
+    }
+
+    /// Fingerprint of the operators, their versions and configs (see manifest::pipeline_fingerprint)
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Run the pipeline: open the operators, process all samples, then close the operators
+    pub fn execute(&mut self) -> Result<ProcessingStatistics> {
+        let ctx = self.context()?;
+        // Each operator records its custom metrics separately
+        let contexts: Vec<Context> = self
+            .operators
+            .iter()
+            .map(|_| ctx.with_metrics(Metrics::default()))
+            .collect();
+        for (idx, op_ctx) in contexts.iter().enumerate() {
+            let (name, op) = &mut self.operators[idx];
+            if let Err(e) = op.open(op_ctx) {
+                let e = e.context(format!("Failed to open operator '{}'", name));
+                // The open error is the one to report
+                let _ = self.close_operators(idx);
+                return Err(e);
+            }
         }
 
-        Ok(Self { operators, spec })
+        let result = self.run(&ctx, &contexts);
+        let closed = self.close_operators(self.operators.len());
+        // Scratch files only live for the duration of the run
+        let _ = std::fs::remove_dir_all(ctx.scratch_dir());
+        let stats = result?;
+        closed?;
+        Ok(stats)
+    }
+
+    /// Context of a new run, with a fresh run id and its scratch directory created
+    fn context(&self) -> Result<Context> {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let scratch_base = match &self.spec.scratch_dir {
+            Some(dir) => std::path::PathBuf::from(dir),
+            None => std::env::temp_dir().join("fdf"),
+        };
+        let scratch_dir = scratch_base.join(&run_id);
+        std::fs::create_dir_all(&scratch_dir).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to create scratch directory {}: {}",
+                scratch_dir.display(),
+                e
+            )
+        })?;
+        Ok(Context::new(run_id, self.spec.seed, scratch_dir))
     }
 
-    pub fn execute(&self) -> Result<ProcessingStatistics> {
-        // Create output directory
-        if let Some(parent) = Path::new(&self.spec.sink.uri).parent() {
-            std::fs::create_dir_all(parent)?;
+    /// Close the first `count` operators; every one is closed even if an earlier close fails
+    fn close_operators(&mut self, count: usize) -> Result<()> {
+        let mut first_error = None;
+        for (name, op) in self.operators.iter_mut().take(count) {
+            if let Err(e) = op.close() {
+                first_error
+                    .get_or_insert(e.context(format!("Failed to close operator '{}'", name)));
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Process all samples; `contexts` holds the context of each operator
+    fn run(&self, ctx: &Context, contexts: &[Context]) -> Result<ProcessingStatistics> {
+        // HuggingFace Hub sink: create the dataset repo up front so auth errors surface early
+        let sink_kind = self.spec.sink.kind.as_str();
+        if hub::is_hub_uri(&self.spec.sink.uri) || sink_kind == "huggingface" || sink_kind == "hf" {
+            hub::prepare(&self.spec.sink)?;
+        }
+        for sink in &self.spec.sinks {
+            if hub::is_hub_uri(&sink.uri) || sink.kind == "huggingface" || sink.kind == "hf" {
+                hub::prepare(sink)?;
+            }
+        }
+
+        // Sinks without files (stdout, search clusters, vector databases) only receive final samples:
+        // no trace, error or manifest files are written
+        let writes_files = WriterFactory::writes_files(&self.spec.sink);
+
+        if writes_files {
+            self.validate_trace()?;
+            // Apply sink.mode to the outputs of previous runs
+            self.apply_sink_mode()?;
+            manifest::clear_success(&self.spec.sink.uri);
+
+            // Create output directory
+            if let Some(parent) = Path::new(&self.spec.sink.uri).parent() {
+                output::create_dir_all(&parent.to_string_lossy())?;
+            }
         }
 
         // Create reader using factory
-        let reader = ReaderFactory::create(&self.spec.source)?;
+        let mut reader = ReaderFactory::create(&self.spec.source)?;
         let input_schema = reader.schema().clone();
 
+        // Additional sinks (spec.sinks), each fed the samples it selects
+        let mut extra_sinks = self
+            .spec
+            .sinks
+            .iter()
+            .map(|sink| ExtraSink::new(sink, input_schema.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let keep_rejected = extra_sinks.iter().any(|s| s.select == "rejected");
+
         // Setup step-by-step output (lazy initialization - create writers only when needed)
-        let mut step_writers: HashMap<usize, Box<dyn Writer>> = HashMap::new();
-        let mut final_writer: Option<Box<dyn Writer>> = None;
+        let mut final_writer: Option<Box<dyn Writer>> = if !writes_files {
+            Some(WriterFactory::create(
+                &self.spec.sink,
+                input_schema.clone(),
+            )?)
+        } else {
+            None
+        };
         let mut err_writer: Option<Box<dyn Writer>> = None;
 
         // Pre-compute paths and file names for lazy writer creation
-        let trace_base = format!("{}/trace", self.spec.sink.uri.trim_end_matches('/'));
         let final_base = format!("{}/final", self.spec.sink.uri.trim_end_matches('/'));
         let error_base = format!("{}/error", self.spec.sink.uri.trim_end_matches('/'));
 
@@ -65,17 +256,24 @@ impl Plan {
             .and_then(|n| n.to_str())
             .unwrap_or("file.jsonl");
 
-        // Determine extension from sink kind or input file
-        let extension = if self.spec.sink.kind == "parquet" || input_file_name.ends_with(".parquet")
-        {
-            ".parquet"
+        // Determine extension from sink kind
+        let extension = WriterFactory::extension(&self.spec.sink);
+
+        let file_stem = input_file_name
+            .trim_end_matches(".parquet")
+            .trim_end_matches(".jsonl");
+        let file_name = format!("{}{}", file_stem, extension);
+
+        // Existing outputs were handled up front; writers only need to know whether to append
+        let writer_mode = if self.spec.sink.mode == "append" {
+            "append"
         } else {
-            ".jsonl"
+            "overwrite"
         };
 
-        let file_name = input_file_name
-            .replace(".parquet", extension)
-            .replace(".jsonl", extension);
+        // Trace writers may use their own format (spec.trace.kind)
+        let mut trace = TraceWriters::new(self, file_stem, writer_mode, input_schema.clone());
+        let trace_diffs = self.spec.trace.mode == "diff";
 
         // Step-by-step mode: track filtering at each step
         let mut total_rows = 0;
@@ -83,6 +281,8 @@ impl Plan {
         let mut step_stats: Vec<StepStatistics> = Vec::new();
         let mut documents_before_step: Vec<usize> = vec![0; self.operators.len()];
         let mut documents_removed_at_step: Vec<usize> = vec![0; self.operators.len()];
+        let mut documents_failed_at_step: Vec<usize> = vec![0; self.operators.len()];
+        let mut documents_emitted_by_step: Vec<usize> = vec![0; self.operators.len()];
         let mut step_processing_times: Vec<std::time::Duration> =
             vec![std::time::Duration::ZERO; self.operators.len()];
 
@@ -102,171 +302,256 @@ impl Plan {
         // Note: Read time is difficult to measure accurately in iterator-based API
         // as the actual disk I/O happens inside the iterator's next() method.
         // For Parquet, reading is batched, so individual sample reads are very fast.
-        for sample_result in reader {
-            match sample_result {
-                Ok(sample) => {
-                    let mut filtered_at_step: Option<usize> = None;
-                    let mut final_sample: Option<Sample> = None;
-                    let mut sample_before_step: Option<Sample> = None;
-                    let mut sample_opt: Option<Sample> = Some(sample);
-                    let enable_trace = self.spec.sink.enable_trace;
-
-                    for (step_idx, (_name, op)) in self.operators.iter().enumerate() {
-                        // Track documents that reached this step
-                        documents_before_step[step_idx] += 1;
-
-                        // Take sample from Option
-                        let current_sample = match sample_opt.take() {
-                            Some(s) => {
-                                // Only clone if trace is enabled (for trace output when filtered)
-                                if enable_trace {
-                                    sample_before_step = Some(s.clone());
-                                }
-                                s
-                            }
-                            None => break, // Should not happen
-                        };
+        let enable_trace = self.spec.sink.enable_trace && writes_files;
+        // A copy of the sample before each step is kept for trace, rejected sinks and
+        // retries of transient errors
+        let keep_copy = enable_trace || keep_rejected || self.spec.max_retries > 0;
+        let mut finished_steps = 0; // Steps whose end-of-stream samples were taken
+        let mut error_records: Vec<Sample> = Vec::new(); // Failed samples, written at the end of each iteration
+        loop {
+            // Samples still to process with the step they start at: an operator may
+            // emit several samples (flat_map), each running through the later steps.
+            // Once the input is exhausted, each step in order emits its remaining samples
+            // (Operator::finish), after those of earlier steps went through it.
+            let (mut pending, read): (Vec<(usize, Sample)>, bool) = match reader.next() {
+                Some(Ok(sample)) => (vec![(0, sample)], true),
+                Some(Err(e)) => {
+                    // Write to error writer (create lazily if needed)
+                    if !writes_files {
+                        eprintln!("Error: {e}");
+                    } else if err_writer.is_none() {
+                        err_writer = Some(self.error_writer(
+                            &error_base,
+                            &file_name,
+                            writer_mode,
+                            &input_schema,
+                        )?);
+                    }
+                    let mut error_sample = Sample::new();
+                    error_sample.set_str("error", format!("{e}"));
+                    let write_start = std::time::Instant::now();
+                    write_selected(&mut extra_sinks, "errors", &error_sample)?;
+                    if let Some(ref mut err_w) = err_writer {
+                        err_w.write_sample(error_sample)?;
+                    }
+                    write_time += write_start.elapsed();
+                    (Vec::new(), true)
+                }
+                None if finished_steps < self.operators.len() => {
+                    let step_idx = finished_steps;
+                    finished_steps += 1;
+                    let (name, op) = &self.operators[step_idx];
+                    let step_start = std::time::Instant::now();
+                    let outputs = match op.finish(&contexts[step_idx]) {
+                        Ok(outputs) => outputs,
+                        // A failed batch fails its samples only
+                        Err(e) if BatchError::find(&e).is_some() && !OpError::is_fatal(&e) => {
+                            let records = self.error_records(step_idx, None, &e)?;
+                            documents_removed_at_step[step_idx] += records.len();
+                            documents_failed_at_step[step_idx] += records.len();
+                            error_records.extend(records);
+                            Vec::new()
+                        }
+                        Err(e) => {
+                            return Err(e.context(format!("Step {} ({}) failed", step_idx, name)))
+                        }
+                    };
+                    step_processing_times[step_idx] += step_start.elapsed();
+                    documents_emitted_by_step[step_idx] += outputs.len();
+                    let pending = outputs
+                        .into_iter()
+                        .rev()
+                        .map(|sample| (step_idx + 1, sample))
+                        .collect();
+                    (pending, false)
+                }
+                None => break,
+            };
+            while let Some((first_step, sample)) = pending.pop() {
+                let mut filtered_at_step: Option<usize> = None;
+                let mut failed_at_step: Option<(usize, anyhow::Error)> = None;
+                let mut sample_before_step: Option<Sample> = None;
+                // Identifies the sample in the error output
+                let sample_id = sample.get_path(&self.spec.trace.id_col).cloned();
+                let mut sample_opt: Option<Sample> = Some(sample);
 
-                        // Measure processing time for this step
-                        let step_start = std::time::Instant::now();
-                        let result = op.process(current_sample);
-                        let step_duration = step_start.elapsed();
-                        step_processing_times[step_idx] += step_duration;
+                for (step_idx, (name, op)) in self.operators.iter().enumerate().skip(first_step) {
+                    // Track documents that reached this step
+                    documents_before_step[step_idx] += 1;
 
-                        match result {
-                            Ok(Some(modified)) => {
-                                sample_opt = Some(modified); // Continue with modified sample
-                            }
-                            Ok(None) => {
-                                // Sample was filtered out - write to trace output
-                                filtered_at_step = Some(step_idx);
-                                documents_removed_at_step[step_idx] += 1;
-                                break; // Filtered out at this step
-                            }
-                            Err(_) => {
-                                // Error during processing - write to trace output
-                                filtered_at_step = Some(step_idx);
-                                documents_removed_at_step[step_idx] += 1;
-                                break; // Filter out on error
+                    // Take sample from Option
+                    let current_sample = match sample_opt.take() {
+                        Some(s) => {
+                            // Only clone if the sample is needed when filtered or failing
+                            if keep_copy {
+                                sample_before_step = Some(s.clone());
                             }
+                            s
                         }
-                    }
+                        None => break, // Should not happen
+                    };
 
-                    // Only clone if we passed all steps (for final output)
-                    // This reduces cloning: we only clone once at the end for successful samples
-                    if let Some(s) = sample_opt {
-                        final_sample = Some(s.clone());
+                    // Measure processing time for this step
+                    let step_start = std::time::Instant::now();
+                    let mut result = op.flat_map(current_sample, &contexts[step_idx]);
+                    let mut retries = 0;
+                    while retries < self.spec.max_retries {
+                        match (&result, &sample_before_step) {
+                            (Err(e), Some(retry_sample)) if OpError::is_transient(e) => {
+                                retries += 1;
+                                result = op.flat_map(retry_sample.clone(), &contexts[step_idx]);
+                            }
+                            _ => break,
+                        }
                     }
+                    let step_duration = step_start.elapsed();
+                    step_processing_times[step_idx] += step_duration;
 
-                    // Write to appropriate step directory
-                    if let Some(step_idx) = filtered_at_step {
-                        // Write to step_XX directory (the sample before it was filtered)
-                        // Only if trace is enabled
-                        if enable_trace {
-                            // Create writer lazily if needed
-                            if let std::collections::hash_map::Entry::Vacant(e) =
-                                step_writers.entry(step_idx)
-                            {
-                                let step_dir = format!("{}/step_{:02}", trace_base, step_idx);
-                                std::fs::create_dir_all(&step_dir)?;
-                                // Use directory as URI to enable sharding if samples_per_shard > 0
-                                // Otherwise use file path
-                                let step_uri = if self.spec.sink.samples_per_shard > 0 {
-                                    step_dir.clone()
-                                } else {
-                                    format!("{}/{}", step_dir, file_name)
-                                };
-                                let writer = WriterFactory::create(
-                                    &crate::spec::SinkSpec {
-                                        kind: self.spec.sink.kind.clone(),
-                                        uri: step_uri,
-                                        mode: "overwrite".to_string(),
-                                        shard_key: None,
-                                        samples_per_shard: self.spec.sink.samples_per_shard,
-                                        shard_name_pattern: self
-                                            .spec
-                                            .sink
-                                            .shard_name_pattern
-                                            .clone(),
-                                        enable_trace: false, // Trace writers don't need trace themselves
-                                    },
-                                    input_schema.clone(),
-                                )?;
-                                e.insert(writer);
-                            }
-                            if let Some(writer) = step_writers.get_mut(&step_idx) {
-                                if let Some(sample_to_write) = sample_before_step {
+                    match result {
+                        Ok(outputs) if !outputs.is_empty() => {
+                            documents_emitted_by_step[step_idx] += outputs.len();
+                            if enable_trace && trace_diffs && !op.buffers() {
+                                // What the step changed, for each sample it produced
+                                let before = sample_before_step.as_ref().expect("kept for trace");
+                                for (output_idx, output) in outputs.iter().enumerate() {
+                                    let diff = before.diff(output);
+                                    let outcome = match output_idx {
+                                        0 if diff.is_empty() => continue,
+                                        0 => "modified",
+                                        _ => "emitted",
+                                    };
+                                    let record = self.diff_record(
+                                        total_input_documents,
+                                        before,
+                                        outcome,
+                                        Some(&diff),
+                                    )?;
                                     let write_start = std::time::Instant::now();
-                                    writer.write_sample(sample_to_write)?;
+                                    trace.offer(step_idx, record)?;
                                     write_time += write_start.elapsed();
                                 }
                             }
+                            let mut outputs = outputs.into_iter();
+                            sample_opt = outputs.next(); // Continue with the first output
+
+                            // Further outputs continue at the next step once this one is done
+                            let extra: Vec<Sample> = outputs.collect();
+                            for extra_sample in extra.into_iter().rev() {
+                                pending.push((step_idx + 1, extra_sample));
+                            }
                         }
-                    } else if let Some(final_sample_value) = final_sample {
-                        // Write to step_final directory
-                        // Create writer lazily if needed
-                        if final_writer.is_none() {
-                            std::fs::create_dir_all(&final_base)?;
-                            // Use directory as URI to enable sharding if samples_per_shard > 0
-                            // Otherwise use file path
-                            let final_uri = if self.spec.sink.samples_per_shard > 0 {
-                                final_base.clone()
-                            } else {
-                                format!("{}/{}", final_base, file_name)
-                            };
-                            final_writer = Some(WriterFactory::create(
-                                &crate::spec::SinkSpec {
-                                    kind: self.spec.sink.kind.clone(),
-                                    uri: final_uri,
-                                    mode: "overwrite".to_string(),
-                                    shard_key: None,
-                                    samples_per_shard: self.spec.sink.samples_per_shard,
-                                    shard_name_pattern: self.spec.sink.shard_name_pattern.clone(),
-                                    enable_trace: false, // Final writer doesn't need trace
-                                },
-                                input_schema.clone(),
-                            )?);
+                        Ok(_) if op.buffers() => break, // Held by the step, emitted later
+                        Ok(_) => {
+                            // Sample was filtered out - write to trace output
+                            filtered_at_step = Some(step_idx);
+                            documents_removed_at_step[step_idx] += 1;
+                            break; // Filtered out at this step
                         }
-                        if let Some(ref mut w) = final_writer {
-                            let write_start = std::time::Instant::now();
-                            w.write_sample(final_sample_value)?;
-                            write_time += write_start.elapsed();
-                            total_rows += 1;
+                        Err(e) if OpError::is_fatal(&e) => {
+                            return Err(e.context(format!("Step {} ({}) failed", step_idx, name)));
+                        }
+                        Err(e) => {
+                            // Invalid sample (or batch of samples) - write to error output
+                            let failed =
+                                BatchError::find(&e).map_or(1, |batch| batch.samples.len());
+                            failed_at_step = Some((step_idx, e));
+                            documents_removed_at_step[step_idx] += failed;
+                            documents_failed_at_step[step_idx] += failed;
+                            break;
                         }
                     }
                 }
-                Err(e) => {
-                    // Write to error writer (create lazily if needed)
-                    if err_writer.is_none() {
-                        std::fs::create_dir_all(&error_base)?;
-                        let err_file_path = format!("{}/{}", error_base, file_name);
-                        err_writer = Some(WriterFactory::create(
+
+                let final_sample = sample_opt; // Set if the sample passed all steps
+
+                // Write to appropriate step directory
+                if let Some((step_idx, e)) = failed_at_step {
+                    error_records.extend(self.error_records(step_idx, sample_id, &e)?);
+                } else if let Some(step_idx) = filtered_at_step {
+                    if let Some(ref rejected) = sample_before_step {
+                        let write_start = std::time::Instant::now();
+                        write_selected(&mut extra_sinks, "rejected", rejected)?;
+                        write_time += write_start.elapsed();
+                    }
+                    // Write to step_XX directory (the sample before it was filtered, or
+                    // a removal record in diff mode), only if trace is enabled
+                    if let (true, Some(removed)) = (enable_trace, sample_before_step) {
+                        let record = if trace_diffs {
+                            self.diff_record(total_input_documents, &removed, "removed", None)?
+                        } else {
+                            removed
+                        };
+                        let write_start = std::time::Instant::now();
+                        trace.offer(step_idx, record)?;
+                        write_time += write_start.elapsed();
+                    }
+                } else if let Some(final_sample_value) = final_sample {
+                    let write_start = std::time::Instant::now();
+                    write_selected(&mut extra_sinks, "final", &final_sample_value)?;
+                    write_time += write_start.elapsed();
+
+                    // Write to step_final directory
+                    // Create writer lazily if needed
+                    if final_writer.is_none() {
+                        output::create_dir_all(&final_base)?;
+                        // Use directory as URI to enable sharding if samples_per_shard > 0
+                        // Otherwise use file path
+                        let final_uri = if self.spec.sink.samples_per_shard > 0
+                            || self.spec.sink.shard_key.is_some()
+                        {
+                            final_base.clone()
+                        } else {
+                            format!("{}/{}", final_base, file_name)
+                        };
+                        final_writer = Some(WriterFactory::create(
                             &crate::spec::SinkSpec {
-                                kind: self.spec.sink.kind.clone(),
-                                uri: err_file_path,
-                                mode: "overwrite".to_string(),
-                                shard_key: None,
-                                samples_per_shard: 0, // Error files don't use sharding
-                                shard_name_pattern: None,
-                                enable_trace: false, // Error writer doesn't need trace
+                                uri: final_uri,
+                                mode: writer_mode.to_string(),
+                                enable_trace: false, // Final writer doesn't need trace
+                                ..self.spec.sink.clone()
                             },
                             input_schema.clone(),
                         )?);
                     }
-                    if let Some(ref mut err_w) = err_writer {
-                        let mut error_sample = Sample::new();
-                        error_sample.set_str("error", format!("{e}"));
+                    if let Some(ref mut w) = final_writer {
                         let write_start = std::time::Instant::now();
-                        err_w.write_sample(error_sample)?;
+                        w.write_sample(final_sample_value)?;
                         write_time += write_start.elapsed();
+                        total_rows += 1;
+                    }
+                }
+            }
+
+            for error_sample in error_records.drain(..) {
+                let write_start = std::time::Instant::now();
+                write_selected(&mut extra_sinks, "errors", &error_sample)?;
+                if !writes_files {
+                    eprintln!(
+                        "Error: {}",
+                        error_sample.get_str("error").unwrap_or_default()
+                    );
+                } else {
+                    if err_writer.is_none() {
+                        err_writer = Some(self.error_writer(
+                            &error_base,
+                            &file_name,
+                            writer_mode,
+                            &input_schema,
+                        )?);
+                    }
+                    if let Some(ref mut err_w) = err_writer {
+                        err_w.write_sample(error_sample)?;
                     }
                 }
+                write_time += write_start.elapsed();
             }
 
-            total_input_documents += 1;
-            // Update progress every 100 documents
-            if total_input_documents % 100 == 0 {
-                progress.set_position(total_input_documents as u64);
+            if read {
+                total_input_documents += 1;
+                // Update progress every 100 documents
+                if total_input_documents % 100 == 0 {
+                    progress.set_position(total_input_documents as u64);
+                }
             }
         }
 
@@ -274,56 +559,61 @@ impl Plan {
         progress.finish_with_message(format!("Processed {} documents", total_input_documents));
 
         // Close all writers and remove empty files
-        for (step_idx, writer) in step_writers {
-            if !writer.close()? {
-                // No data written, remove the empty file/directory
-                let step_dir = format!(
-                    "{}/trace/step_{:02}",
-                    self.spec.sink.uri.trim_end_matches('/'),
-                    step_idx
-                );
-                // If sharding was enabled, ShardedWriter handles cleanup
-                // If single file, try to remove it
-                if self.spec.sink.samples_per_shard == 0 {
-                    let file_path = format!("{}/{}", step_dir, file_name);
-                    let _ = std::fs::remove_file(&file_path);
-                }
-            }
-        }
+        trace.close()?;
+        let mut final_files = Vec::new();
+        let mut documents_dropped_by_sink = 0;
         if let Some(w) = final_writer {
-            if !w.close()? {
+            documents_dropped_by_sink = w.dropped();
+            total_rows -= documents_dropped_by_sink;
+            final_files = w.close()?; // Row counts and checksums per file, for the manifest
+            if final_files.is_empty() {
                 // No data written, remove empty files/directories
                 // If sharding was enabled, ShardedWriter handles cleanup
                 // If single file, try to remove it
-                if self.spec.sink.samples_per_shard == 0 {
+                if self.spec.sink.samples_per_shard == 0 && self.spec.sink.shard_key.is_none() {
                     let final_dir = format!("{}/final", self.spec.sink.uri.trim_end_matches('/'));
                     let file_path = format!("{}/{}", final_dir, file_name);
-                    let _ = std::fs::remove_file(&file_path);
+                    output::remove(&file_path);
                 }
             }
         }
         if let Some(w) = err_writer {
-            if !w.close()? {
+            if w.close()?.is_empty() {
                 // No data written, remove the empty file
                 let error_dir = format!("{}/error", self.spec.sink.uri.trim_end_matches('/'));
                 let file_path = format!("{}/{}", error_dir, file_name);
-                let _ = std::fs::remove_file(&file_path);
+                output::remove(&file_path);
             }
         }
 
+        for sink in extra_sinks {
+            sink.writer.close()?;
+        }
+
+        // Commit outputs staged for the Hub (sink.hub.commit: at_close)
+        hub::commit_pending()?;
+
         // Build step statistics
-        for (step_idx, (name, _)) in self.operators.iter().enumerate() {
+        for (step_idx, (name, op)) in self.operators.iter().enumerate() {
             let processing_time_ms = step_processing_times[step_idx].as_millis() as u64;
             let documents_remaining_before = documents_before_step[step_idx];
-            let documents_removed = documents_removed_at_step[step_idx];
+            let documents_removed = if op.buffers() {
+                documents_remaining_before.saturating_sub(documents_emitted_by_step[step_idx])
+            } else {
+                documents_removed_at_step[step_idx]
+            };
+            let documents_failed = documents_failed_at_step[step_idx];
 
             step_stats.push(StepStatistics {
                 step_name: name.clone(),
                 step_index: step_idx,
+                version: self.versions[step_idx].clone(),
                 processing_time_ms,
                 documents_removed,
+                documents_failed,
                 documents_remaining_before,
                 total_documents: total_input_documents,
+                metrics: contexts[step_idx].metrics().snapshot(),
             });
         }
 
@@ -333,11 +623,352 @@ impl Plan {
         // We'll estimate it in the runner based on total time.
         let estimated_read_time_ms = 0; // Set to 0, will be calculated in runner
 
+        // Describe the completed output and mark it done (Hub outputs are not staged locally)
+        if !hub::is_hub_uri(&self.spec.sink.uri) && writes_files {
+            Manifest::new(
+                ctx.run_id(),
+                &self.spec,
+                &self.fingerprint,
+                &final_files,
+                &step_stats,
+                total_input_documents,
+                total_rows,
+                documents_dropped_by_sink,
+            )?
+            .write(&self.spec.sink.uri)?;
+        }
+
         Ok(ProcessingStatistics {
             num_documents: total_rows,
+            documents_dropped_by_sink,
             step_statistics: step_stats,
             read_time_ms: estimated_read_time_ms,
             write_time_ms: write_time.as_millis() as u64,
         })
     }
+
+    /// Writer for read errors and samples that failed an operator
+    /// Error output is a single file; when appending it becomes a new shard in the error
+    /// directory so earlier errors are kept.
+    fn error_writer(
+        &self,
+        error_base: &str,
+        file_name: &str,
+        writer_mode: &str,
+        input_schema: &Arc<Schema>,
+    ) -> Result<Box<dyn Writer>> {
+        output::create_dir_all(error_base)?;
+        let (err_uri, err_samples_per_shard) = if writer_mode == "append" {
+            (error_base.to_string(), usize::MAX)
+        } else {
+            (format!("{}/{}", error_base, file_name), 0)
+        };
+        WriterFactory::create(
+            &SinkSpec {
+                uri: err_uri,
+                mode: writer_mode.to_string(),
+                shard_key: None,
+                samples_per_shard: err_samples_per_shard, // Error files are never split
+                shard_name_pattern: None,
+                schema: None,        // Error records have their own shape
+                enable_trace: false, // Error writer doesn't need trace
+                ..self.spec.sink.clone()
+            },
+            input_schema.clone(),
+        )
+    }
+
+    /// Error output records of a failure at a step: the error with the failed step, and the
+    /// id of the sample; one record per sample of a failed batch (BatchError)
+    fn error_records(
+        &self,
+        step_idx: usize,
+        sample_id: Option<Value>,
+        error: &anyhow::Error,
+    ) -> Result<Vec<Sample>> {
+        let message = format!(
+            "Step {} ({}): {:#}",
+            step_idx, self.operators[step_idx].0, error
+        );
+        let ids = match BatchError::find(error) {
+            Some(batch) => batch
+                .samples
+                .iter()
+                .map(|sample| sample.get_path(&self.spec.trace.id_col).cloned())
+                .collect(),
+            None => vec![sample_id],
+        };
+        ids.into_iter()
+            .map(|id| {
+                let mut error_sample = Sample::new();
+                if let Some(id) = id {
+                    error_sample.set_path(&self.spec.trace.id_col, id)?;
+                }
+                error_sample.set_str("error", message.clone());
+                Ok(error_sample)
+            })
+            .collect()
+    }
+
+    /// Trace record of what a step did to a sample (trace.mode: diff): the input position,
+    /// the sample id (trace.id_col), the outcome (modified, emitted, removed) and the
+    /// differences as JSON
+    fn diff_record(
+        &self,
+        input_index: usize,
+        before: &Sample,
+        outcome: &str,
+        diff: Option<&SampleDiff>,
+    ) -> Result<Sample> {
+        let id = match before.get_path(&self.spec.trace.id_col) {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        let mut record = Sample::new();
+        record.set_value("input_index", Value::from(input_index));
+        record.set_str("id", id);
+        record.set_str("outcome", outcome);
+        record.set_str(
+            "diff",
+            match diff {
+                Some(diff) => serde_json::to_string(diff)?,
+                None => "{}".to_string(),
+            },
+        );
+        Ok(record)
+    }
+
+    /// Root of the trace output: spec.trace.uri, or {sink.uri}/trace
+    fn trace_base(&self) -> String {
+        match &self.spec.trace.uri {
+            Some(uri) => uri.trim_end_matches('/').to_string(),
+            None => format!("{}/trace", self.spec.sink.uri.trim_end_matches('/')),
+        }
+    }
+
+    /// Sink settings for trace writers: the main sink, in the trace format if one is set
+    /// Compression settings only carry over when the format is unchanged.
+    fn trace_sink(&self) -> SinkSpec {
+        let sink = &self.spec.sink;
+        match &self.spec.trace.kind {
+            Some(kind) if *kind != sink.kind => SinkSpec {
+                kind: kind.clone(),
+                compression: None,
+                compression_level: None,
+                ..sink.clone()
+            },
+            _ => sink.clone(),
+        }
+    }
+
+    fn validate_trace(&self) -> Result<()> {
+        if !matches!(self.spec.trace.mode.as_str(), "removed" | "diff") {
+            return Err(anyhow::anyhow!(
+                "Unknown trace.mode '{}'. Expected: removed, diff",
+                self.spec.trace.mode
+            ));
+        }
+        let rate = self.spec.trace.sample_rate;
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(anyhow::anyhow!(
+                "trace.sample_rate must be between 0.0 and 1.0, got {}",
+                rate
+            ));
+        }
+        let trace_sink = self.trace_sink();
+        if !WriterFactory::writes_files(&trace_sink) {
+            return Err(anyhow::anyhow!(
+                "trace.kind '{}' does not write files",
+                trace_sink.kind
+            ));
+        }
+        Ok(())
+    }
+
+    /// Handle outputs left by previous runs according to sink.mode
+    /// - overwrite: remove the final/, trace and error/ outputs
+    /// - append: keep them; new shards are numbered after the existing ones (sequential sharding only)
+    /// - error_if_exists: fail if final/ already contains files
+    fn apply_sink_mode(&self) -> Result<()> {
+        let sink = &self.spec.sink;
+        let base = sink.uri.trim_end_matches('/');
+        match sink.mode.as_str() {
+            "overwrite" => {
+                for dir in ["final", "error"] {
+                    output::remove_dir_all(&format!("{}/{}", base, dir))?;
+                }
+                // A separate trace uri may be shared with other data: only clear the step directories
+                let trace_base = self.trace_base();
+                if self.spec.trace.uri.is_some() {
+                    for step_idx in 0..self.operators.len() {
+                        output::remove_dir_all(&format!("{}/step_{:02}", trace_base, step_idx))?;
+                    }
+                } else {
+                    output::remove_dir_all(&trace_base)?;
+                }
+            }
+            "append" => {
+                if sink.shard_key.is_some() {
+                    return Err(anyhow::anyhow!(
+                        "sink.mode append cannot be used with sink.shard_key: hash shards keep the same ids across runs"
+                    ));
+                }
+                if sink.samples_per_shard == 0 {
+                    return Err(anyhow::anyhow!(
+                        "sink.mode append requires sharded output (samples_per_shard > 0)"
+                    ));
+                }
+            }
+            "error_if_exists" => {
+                let final_dir = format!("{}/final", base);
+                if !output::list_files(&final_dir)?.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Sink output '{}' already exists (mode: error_if_exists)",
+                        final_dir
+                    ));
+                }
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown sink mode '{}'. Expected: overwrite, append, error_if_exists",
+                    other
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Additional sink (spec.sinks) receiving the samples chosen by its `select`
+struct ExtraSink {
+    select: String,
+    limit: Option<usize>,
+    writer: Box<dyn Writer>,
+    written: usize,
+}
+
+impl ExtraSink {
+    fn new(spec: &SinkSpec, schema: Arc<Schema>) -> Result<Self> {
+        if !matches!(spec.select.as_str(), "final" | "rejected" | "errors") {
+            return Err(anyhow::anyhow!(
+                "Unknown select '{}' for sink '{}'. Expected: final, rejected, errors",
+                spec.select,
+                spec.uri
+            ));
+        }
+        Ok(Self {
+            select: spec.select.clone(),
+            limit: spec.limit,
+            writer: WriterFactory::create(spec, schema)?,
+            written: 0,
+        })
+    }
+}
+
+/// Whether `column` (a dot path) is one of `available`, lies inside one of them, or contains one
+fn column_available(available: &[String], column: &str) -> bool {
+    available.iter().any(|a| {
+        a == column
+            || column
+                .strip_prefix(a.as_str())
+                .is_some_and(|rest| rest.starts_with('.'))
+            || a.strip_prefix(column)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Write a sample to every additional sink selecting `select` that is below its limit
+fn write_selected(sinks: &mut [ExtraSink], select: &str, sample: &Sample) -> Result<()> {
+    for sink in sinks.iter_mut().filter(|s| s.select == select) {
+        if sink.limit.is_some_and(|limit| sink.written >= limit) {
+            continue;
+        }
+        sink.writer.write_sample(sample.clone())?;
+        sink.written += 1;
+    }
+    Ok(())
+}
+
+/// Lazily created writers of the trace step directories ({trace root}/step_XX)
+struct TraceWriters {
+    base: String,
+    file_name: String, // Used when the trace is not sharded
+    sink: SinkSpec,    // Settings shared by the step writers
+    schema: Arc<Schema>,
+    sample_rate: f64,
+    offered: HashMap<usize, usize>, // Records offered per step, for sampling
+    writers: HashMap<usize, Box<dyn Writer>>,
+}
+
+impl TraceWriters {
+    fn new(plan: &Plan, file_stem: &str, writer_mode: &str, schema: Arc<Schema>) -> Self {
+        let sink = plan.trace_sink();
+        Self {
+            base: plan.trace_base(),
+            file_name: format!("{}{}", file_stem, WriterFactory::extension(&sink)),
+            sink: SinkSpec {
+                mode: writer_mode.to_string(),
+                shard_key: None,     // Trace output is sharded sequentially
+                schema: None,        // Trace samples are not final output
+                enable_trace: false, // Trace writers don't need trace themselves
+                ..sink
+            },
+            schema,
+            sample_rate: plan.spec.trace.sample_rate,
+            offered: HashMap::new(),
+            writers: HashMap::new(),
+        }
+    }
+
+    /// Write a record to a step's trace, subject to trace.sample_rate
+    fn offer(&mut self, step_idx: usize, record: Sample) -> Result<()> {
+        // Systematic sampling: write whenever the running count crosses an integer
+        let offered = self.offered.entry(step_idx).or_default();
+        *offered += 1;
+        let rate = self.sample_rate;
+        if (*offered as f64 * rate).floor() <= ((*offered - 1) as f64 * rate).floor() {
+            return Ok(());
+        }
+
+        if let std::collections::hash_map::Entry::Vacant(e) = self.writers.entry(step_idx) {
+            let step_dir = format!("{}/step_{:02}", self.base, step_idx);
+            output::create_dir_all(&step_dir)?;
+            // Use directory as URI to enable sharding if samples_per_shard > 0
+            // Otherwise use file path
+            let step_uri = if self.sink.samples_per_shard > 0 {
+                step_dir
+            } else {
+                format!("{}/{}", step_dir, self.file_name)
+            };
+            e.insert(WriterFactory::create(
+                &SinkSpec {
+                    uri: step_uri,
+                    ..self.sink.clone()
+                },
+                self.schema.clone(),
+            )?);
+        }
+        if let Some(writer) = self.writers.get_mut(&step_idx) {
+            writer.write_sample(record)?;
+        }
+        Ok(())
+    }
+
+    /// Close all writers, removing files without data
+    fn close(self) -> Result<()> {
+        for (step_idx, writer) in self.writers {
+            if writer.close()?.is_empty() {
+                // If sharding was enabled, ShardedWriter handles cleanup
+                // If single file, try to remove it
+                if self.sink.samples_per_shard == 0 {
+                    output::remove(&format!(
+                        "{}/step_{:02}/{}",
+                        self.base, step_idx, self.file_name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,11 +1,15 @@
 use crate::plan::Plan;
 use crate::spec::PipelineSpec;
-use fdf_sdk::OperatorRegistry;
 use fdf_sdk::Result;
+use fdf_sdk::{MetricValue, OperatorRegistry};
+use std::fmt::Write;
 use std::time::Instant;
 
 pub fn run_pipeline(spec: PipelineSpec, registry: &OperatorRegistry) -> Result<()> {
-    let plan = Plan::compile(spec, registry)?;
+    // Keep stdout clean for the data when the sink writes to it
+    let to_stdout = spec.sink.kind == "stdout";
+    let mut plan = Plan::compile(spec, registry)?;
+    let fingerprint = plan.fingerprint().to_string();
 
     // Start timing
     let start_time = Instant::now();
@@ -16,13 +20,27 @@ pub fn run_pipeline(spec: PipelineSpec, registry: &OperatorRegistry) -> Result<(
     // Calculate elapsed time
     let elapsed = start_time.elapsed();
 
-    // Print comprehensive statistics
-    println!("\n=== Processing Statistics ===");
-    println!(
+    // Print comprehensive statistics (to stderr for a stdout sink)
+    let mut report = String::new();
+    writeln!(report, "\n=== Processing Statistics ===")?;
+    writeln!(
+        report,
         "Total processing time: {:.2} seconds",
         elapsed.as_secs_f64()
-    );
-    println!("Number of documents processed: {}", stats.num_documents);
+    )?;
+    writeln!(
+        report,
+        "Number of documents processed: {}",
+        stats.num_documents
+    )?;
+    if stats.documents_dropped_by_sink > 0 {
+        writeln!(
+            report,
+            "Documents dropped by the sink schema: {}",
+            stats.documents_dropped_by_sink
+        )?;
+    }
+    writeln!(report, "Pipeline fingerprint: {}", fingerprint)?;
 
     // Print I/O statistics
     let write_time_percent = if elapsed.as_millis() > 0 {
@@ -49,18 +67,20 @@ pub fn run_pipeline(spec: PipelineSpec, registry: &OperatorRegistry) -> Result<(
         0.0
     };
 
-    println!("\n--- I/O Statistics ---");
-    println!(
+    writeln!(report, "\n--- I/O Statistics ---")?;
+    writeln!(
+        report,
         "Read time (estimated): {:.2}ms ({:.2}%)",
         estimated_read_time_ms, read_time_percent
-    );
-    println!(
+    )?;
+    writeln!(
+        report,
         "Write time: {:.2}ms ({:.2}%)",
         stats.write_time_ms, write_time_percent
-    );
+    )?;
 
     if !stats.step_statistics.is_empty() {
-        println!("\n--- Pipeline Step Statistics ---");
+        writeln!(report, "\n--- Pipeline Step Statistics ---")?;
         for step_stat in &stats.step_statistics {
             let processing_time_percent = if elapsed.as_millis() > 0 {
                 (step_stat.processing_time_ms as f64 * 100.0) / elapsed.as_millis() as f64
@@ -81,19 +101,54 @@ pub fn run_pipeline(spec: PipelineSpec, registry: &OperatorRegistry) -> Result<(
                 0.0
             };
 
-            println!("Step {} ({})", step_stat.step_index, step_stat.step_name);
-            println!(
+            writeln!(
+                report,
+                "Step {} ({})",
+                step_stat.step_index, step_stat.step_name
+            )?;
+            writeln!(
+                report,
                 "  Processing time: {:.2}ms ({:.2}%)",
                 step_stat.processing_time_ms, processing_time_percent
-            );
-            println!(
+            )?;
+            writeln!(
+                report,
                 "  Documents removed: {} ({:.2}% of remaining, {:.2}% of total)",
                 step_stat.documents_removed, removed_percent_of_remaining, removed_percent_of_total
-            );
+            )?;
+            if step_stat.documents_failed > 0 {
+                writeln!(
+                    report,
+                    "  Documents failed: {} (written to the error output)",
+                    step_stat.documents_failed
+                )?;
+            }
+            for (name, metric) in &step_stat.metrics {
+                match metric {
+                    MetricValue::Counter { value } => writeln!(report, "  {}: {}", name, value)?,
+                    MetricValue::Histogram {
+                        count, min, max, ..
+                    } => writeln!(
+                        report,
+                        "  {}: mean {:.4} (min {:.4}, max {:.4}, n {})",
+                        name,
+                        metric.mean().unwrap_or(0.0),
+                        min,
+                        max,
+                        count
+                    )?,
+                }
+            }
         }
     }
 
-    println!("============================\n");
+    writeln!(report, "============================\n")?;
+
+    if to_stdout {
+        eprint!("{report}");
+    } else {
+        print!("{report}");
+    }
 
     Ok(())
 }
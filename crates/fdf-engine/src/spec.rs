@@ -1,10 +1,34 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipelineSpec {
     pub source: SourceSpec,
     pub pipeline: Vec<OperatorNode>,
     pub sink: SinkSpec,
+    #[serde(default)]
+    pub sinks: Vec<SinkSpec>, // Additional sinks fed by the same run (see SinkSpec.select)
+    #[serde(default)]
+    pub trace: TraceSpec, // Where and how trace output is written (enabled by sink.enable_trace)
+    #[serde(default)]
+    pub plugins: Vec<String>, // Shared libraries providing extra operators (see fdf_sdk::plugin)
+    #[serde(default)]
+    pub seed: u64, // Pipeline-level seed handed to operators via Context
+    #[serde(default)]
+    pub scratch_dir: Option<String>, // Parent of the per-run scratch directory (default: system temp dir)
+    /// Check the columns operators require against the source schema before running
+    #[serde(default = "default_validate_columns")]
+    pub validate_columns: bool,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: usize, // Retries of a sample after a transient operator error (OpError::Transient)
+}
+
+fn default_validate_columns() -> bool {
+    true
+}
+
+fn default_max_retries() -> usize {
+    2
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +40,24 @@ pub struct SourceSpec {
     /// Batch size for reading parquet files. If None, uses default batch size.
     #[serde(default)]
     pub batch_size: Option<usize>,
+    /// Column type casts applied by the reader (column -> target type).
+    /// Supported types: int, float, string, bool, timestamp (epoch seconds), timestamp_ms, timestamp_us, timestamp_ns
+    #[serde(default)]
+    pub casts: std::collections::HashMap<String, String>,
+    /// How multiple input files are read: "sequential" (file by file) or "interleaved" (round-robin)
+    #[serde(default = "default_read_order")]
+    pub read_order: String,
+    /// Samples taken from each input before switching to the next one in interleaved mode
+    #[serde(default = "default_interleave_chunk_size")]
+    pub interleave_chunk_size: usize,
+}
+
+fn default_read_order() -> String {
+    "sequential".to_string()
+}
+
+fn default_interleave_chunk_size() -> usize {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -96,20 +138,317 @@ impl<'de> Deserialize<'de> for OperatorNode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SinkSpec {
     pub kind: String,
-    pub uri: String, // Base output URI
+    #[serde(default)]
+    pub uri: String, // Base output URI (not used by kind: stdout)
     #[serde(default = "default_mode")]
     pub mode: String,
     #[serde(default)]
-    pub shard_key: Option<String>, // Field name to use for sharding
+    pub shard_key: Option<String>, // Field to hash-shard on (requires num_shards). Sequential sharding if unset
+    #[serde(default)]
+    pub num_shards: Option<usize>, // Fixed number of hash shards; the same key value always lands in the same shard
     #[serde(default = "default_samples_per_shard")]
-    pub samples_per_shard: usize, // Number of samples per shard
+    pub samples_per_shard: usize, // Number of samples per shard (sequential sharding)
     #[serde(default)]
     pub shard_name_pattern: Option<String>, // Pattern for shard file names, e.g., "{base}.part-{shard_id:08}.{ext}" or "{base}-{shard_id:04d}.{ext}"
     #[serde(default = "default_enable_trace")]
     pub enable_trace: bool, // Enable trace output (creates {uri}/trace/step_xx/). Disable for better performance.
-                            // Trace and error outputs are enabled by default
-                            // Trace: automatically creates {uri}/trace/step_xx/ and {uri}/final/
-                            // Error: automatically creates {uri}/error/
+    // Trace and error outputs are enabled by default
+    // Trace: automatically creates {uri}/trace/step_xx/ and {uri}/final/
+    // Error: automatically creates {uri}/error/
+    #[serde(default)]
+    pub csv: CsvOptions, // Options for kind: csv / tsv
+    #[serde(default)]
+    pub compression: Option<String>, // Output compression: gzip/zstd for jsonl (inferred from uri extension if unset); none/snappy/lz4/zstd/gzip/brotli for parquet
+    #[serde(default)]
+    pub compression_level: Option<i32>, // Codec-specific compression level. Codec default if unset
+    #[serde(default)]
+    pub row_group_size: Option<usize>, // Max rows per parquet row group. Parquet default if unset
+    #[serde(default)]
+    pub dictionary: Option<bool>, // Enable/disable parquet dictionary encoding. Parquet default if unset
+    #[serde(default)]
+    pub hub: HubOptions, // Options for kind: huggingface / hf:// uris
+    #[serde(default)]
+    pub sqlite: SqliteOptions, // Options for kind: sqlite
+    #[serde(default)]
+    pub elasticsearch: ElasticsearchOptions, // Options for kind: elasticsearch / opensearch
+    #[serde(default)]
+    pub qdrant: QdrantOptions, // Options for kind: qdrant
+    #[serde(default)]
+    pub schema: Option<SinkSchema>, // Declared output columns; samples are validated against them
+    #[serde(default = "default_sink_select")]
+    pub select: String, // Samples an additional sink receives: final / rejected / errors. Ignored for the main sink
+    #[serde(default)]
+    pub limit: Option<usize>, // Max samples an additional sink writes (e.g. a small inspection sample)
+    #[serde(skip)]
+    pub metadata: BTreeMap<String, String>, // Set by the engine: key-value metadata stamped into parquet files
+}
+
+/// Trace output options (samples removed at each step, or what each step changed, written
+/// to step_XX/)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSpec {
+    #[serde(default)]
+    pub uri: Option<String>, // Trace root directory. Defaults to {sink.uri}/trace
+    #[serde(default)]
+    pub kind: Option<String>, // Trace format, e.g. jsonl while the final sink is parquet. Defaults to sink.kind
+    #[serde(default = "default_trace_sample_rate")]
+    pub sample_rate: f64, // Fraction (0.0-1.0) of the trace records of each step that are written
+    #[serde(default = "default_trace_mode")]
+    pub mode: String, // removed: whole samples removed by each step; diff: what each step changed
+    #[serde(default = "default_trace_id_col")]
+    pub id_col: String, // Field identifying samples in diff records
+}
+
+impl Default for TraceSpec {
+    fn default() -> Self {
+        Self {
+            uri: None,
+            kind: None,
+            sample_rate: default_trace_sample_rate(),
+            mode: default_trace_mode(),
+            id_col: default_trace_id_col(),
+        }
+    }
+}
+
+fn default_trace_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_trace_mode() -> String {
+    "removed".to_string()
+}
+
+fn default_trace_id_col() -> String {
+    "id".to_string()
+}
+
+/// HuggingFace Hub sink options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubOptions {
+    /// Create the dataset repo as private if it doesn't exist
+    #[serde(default)]
+    pub private: bool,
+    /// "at_close" (one commit when the pipeline finishes) or "per_file" (one commit per shard)
+    #[serde(default = "default_hub_commit")]
+    pub commit: String,
+    /// Branch to commit to
+    #[serde(default = "default_hub_revision")]
+    pub revision: String,
+    /// Add a README dataset card stub when the repo is created
+    #[serde(default = "default_hub_dataset_card")]
+    pub dataset_card: bool,
+}
+
+impl Default for HubOptions {
+    fn default() -> Self {
+        Self {
+            private: false,
+            commit: default_hub_commit(),
+            revision: default_hub_revision(),
+            dataset_card: default_hub_dataset_card(),
+        }
+    }
+}
+
+fn default_hub_commit() -> String {
+    "at_close".to_string()
+}
+
+fn default_hub_revision() -> String {
+    "main".to_string()
+}
+
+fn default_hub_dataset_card() -> bool {
+    true
+}
+
+/// CSV/TSV sink options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvOptions {
+    /// Field delimiter. Defaults to "," for csv and tab for tsv.
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// Quote character used for fields containing delimiters, quotes or newlines
+    #[serde(default = "default_csv_quote")]
+    pub quote: String,
+    /// Write a header row with the column names
+    #[serde(default = "default_csv_header")]
+    pub header: bool,
+    /// Columns written, in order; other fields are not written. By default the columns are
+    /// the input schema plus the fields of the first buffered samples, and a field first seen
+    /// later fails the run
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            quote: default_csv_quote(),
+            header: default_csv_header(),
+            columns: None,
+        }
+    }
+}
+
+/// Declared sink schema (sink.schema)
+/// Only the declared columns are written, in order, with fixed types.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkSchema {
+    pub columns: Vec<SinkColumn>,
+    /// What to do with a sample that does not match: "error" (fail the run) or "drop" (skip it)
+    #[serde(default = "default_on_mismatch")]
+    pub on_mismatch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkColumn {
+    pub name: String,
+    /// Column type: int, float, string, bool, timestamp, timestamp_ms, timestamp_us, timestamp_ns
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// Whether the column may be missing or null
+    #[serde(default = "default_column_nullable")]
+    pub nullable: bool,
+}
+
+fn default_on_mismatch() -> String {
+    "error".to_string()
+}
+
+fn default_column_nullable() -> bool {
+    true
+}
+
+/// Elasticsearch / OpenSearch sink options
+/// The sink uri is the cluster url, e.g. http://localhost:9200. Credentials come from
+/// ES_API_KEY or ES_USERNAME / ES_PASSWORD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElasticsearchOptions {
+    /// Index the samples are written to
+    #[serde(default = "default_es_index")]
+    pub index: String,
+    /// Field used as the document _id (ids are generated by the cluster if unset or missing)
+    #[serde(default)]
+    pub id_field: Option<String>,
+    /// Number of documents per bulk request
+    #[serde(default = "default_es_batch_size")]
+    pub batch_size: usize,
+    /// Retries of a bulk request (or of its rejected documents) on 429 / 5xx responses
+    #[serde(default = "default_sink_max_retries")]
+    pub max_retries: usize,
+}
+
+impl Default for ElasticsearchOptions {
+    fn default() -> Self {
+        Self {
+            index: default_es_index(),
+            id_field: None,
+            batch_size: default_es_batch_size(),
+            max_retries: default_sink_max_retries(),
+        }
+    }
+}
+
+fn default_es_index() -> String {
+    "fdf".to_string()
+}
+
+fn default_es_batch_size() -> usize {
+    500
+}
+
+fn default_sink_max_retries() -> usize {
+    3
+}
+
+/// Qdrant vector database sink options
+/// The sink uri is the Qdrant REST url, e.g. http://localhost:6333. The API key comes from
+/// QDRANT_API_KEY.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QdrantOptions {
+    /// Collection the points are written to (created on first write if missing)
+    #[serde(default = "default_qdrant_collection")]
+    pub collection: String,
+    /// Field holding the embedding (array of numbers)
+    #[serde(default = "default_qdrant_vector_field")]
+    pub vector_field: String,
+    /// Field used as point id. Unsigned integers and UUIDs are used as-is, other values are
+    /// hashed to a UUID. Ids are generated if unset.
+    #[serde(default)]
+    pub id_field: Option<String>,
+    /// Fields stored as point payload. All fields except the vector if unset
+    #[serde(default)]
+    pub payload_fields: Option<Vec<String>>,
+    /// Distance used when creating the collection: Cosine, Dot, Euclid, Manhattan
+    #[serde(default = "default_qdrant_distance")]
+    pub distance: String,
+    /// Number of points per upsert request
+    #[serde(default = "default_qdrant_batch_size")]
+    pub batch_size: usize,
+    /// Retries of an upsert request on 429 / 5xx responses
+    #[serde(default = "default_sink_max_retries")]
+    pub max_retries: usize,
+}
+
+impl Default for QdrantOptions {
+    fn default() -> Self {
+        Self {
+            collection: default_qdrant_collection(),
+            vector_field: default_qdrant_vector_field(),
+            id_field: None,
+            payload_fields: None,
+            distance: default_qdrant_distance(),
+            batch_size: default_qdrant_batch_size(),
+            max_retries: default_sink_max_retries(),
+        }
+    }
+}
+
+fn default_qdrant_collection() -> String {
+    "fdf".to_string()
+}
+
+fn default_qdrant_vector_field() -> String {
+    "embedding".to_string()
+}
+
+fn default_qdrant_distance() -> String {
+    "Cosine".to_string()
+}
+
+fn default_qdrant_batch_size() -> usize {
+    256
+}
+
+/// SQLite sink options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqliteOptions {
+    /// Table the samples are inserted into (created from the output schema)
+    #[serde(default = "default_sqlite_table")]
+    pub table: String,
+}
+
+impl Default for SqliteOptions {
+    fn default() -> Self {
+        Self {
+            table: default_sqlite_table(),
+        }
+    }
+}
+
+fn default_sqlite_table() -> String {
+    "samples".to_string()
+}
+
+fn default_csv_quote() -> String {
+    "\"".to_string()
+}
+
+fn default_csv_header() -> bool {
+    true
 }
 
 fn default_enable_trace() -> bool {
@@ -120,6 +459,10 @@ fn default_mode() -> String {
     "overwrite".to_string()
 }
 
+fn default_sink_select() -> String {
+    "final".to_string()
+}
+
 fn default_samples_per_shard() -> usize {
     10000
 }
@@ -0,0 +1,311 @@
+//! `#[fdf_operator]`: config deserialization and registration for operators
+//!
+//! Use it through `fdf_sdk::fdf_operator`; see its documentation there.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::{
+    Attribute, Expr, ExprLit, Fields, GenericArgument, ItemStruct, Lit, LitStr, Meta,
+    PathArguments, Type,
+};
+
+/// Operator-level settings from `#[fdf_operator(...)]`
+#[derive(Default)]
+struct OperatorArgs {
+    name: Option<LitStr>,
+    category: Option<LitStr>,
+    description: Option<LitStr>,
+    version: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    build: Option<syn::Path>, // fn(Self) -> Result<impl Operator>
+}
+
+/// Field-level settings from `#[param(...)]`
+#[derive(Default)]
+struct ParamArgs {
+    default: Option<String>, // YAML text
+    param_type: Option<String>,
+}
+
+#[proc_macro_attribute]
+pub fn fdf_operator(attr: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(attr.into(), item.into()) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut args = OperatorArgs::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            args.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("category") {
+            args.category = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("description") {
+            args.description = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("version") {
+            args.version = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("alias") {
+            args.aliases.push(meta.value()?.parse()?);
+        } else if meta.path.is_ident("build") {
+            let path: LitStr = meta.value()?.parse()?;
+            args.build = Some(path.parse()?);
+        } else {
+            return Err(meta.error("expected name, category, description, version, alias or build"));
+        }
+        Ok(())
+    });
+    parser.parse2(attr)?;
+
+    let mut item: ItemStruct = syn::parse2(item)?;
+    let name = args
+        .name
+        .ok_or_else(|| syn::Error::new(Span::call_site(), "missing `name = \"...\"`"))?;
+    let ident = item.ident.clone();
+
+    let category = match &args.category {
+        Some(category) => {
+            let variant = match category.value().as_str() {
+                "filter" => quote!(Filter),
+                "transformer" => quote!(Transformer),
+                "annotator" => quote!(Annotator),
+                _ => {
+                    return Err(syn::Error::new(
+                        category.span(),
+                        "expected \"filter\", \"transformer\" or \"annotator\"",
+                    ))
+                }
+            };
+            quote!(.category(::fdf_sdk::OperatorCategory::#variant))
+        }
+        None => quote!(),
+    };
+    let description = args
+        .description
+        .map(|d| d.value())
+        .unwrap_or_else(|| doc_comment(&item.attrs));
+    let version = args.version.map(|v| quote!(.version(#v)));
+    let aliases = &args.aliases;
+
+    let Fields::Named(fields) = &mut item.fields else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[fdf_operator] needs a struct with named fields",
+        ));
+    };
+
+    let mut params = Vec::new();
+    let mut default_fns = Vec::new();
+    for field in fields.named.iter_mut() {
+        let param = take_param_args(&mut field.attrs)?;
+        if has_serde_flag(&field.attrs, "skip") {
+            continue; // Not part of the config
+        }
+        let field_ident = field.ident.clone().expect("named field");
+        let param_name = serde_rename(&field.attrs)?.unwrap_or_else(|| field_ident.to_string());
+        let description = doc_comment(&field.attrs);
+        let param_type = param
+            .param_type
+            .unwrap_or_else(|| type_name(option_inner(&field.ty).unwrap_or(&field.ty)));
+
+        let spec = match &param.default {
+            Some(default) => {
+                // Deserialize the documented default, so the two cannot disagree
+                let default_fn = format_ident!("__fdf_default_{}_{}", ident, field_ident);
+                let default_fn_name = default_fn.to_string();
+                let ty = &field.ty;
+                default_fns.push(quote! {
+                    #[allow(non_snake_case)]
+                    fn #default_fn() -> #ty {
+                        ::fdf_sdk::__private::default_from_yaml(#default)
+                    }
+                });
+                field
+                    .attrs
+                    .push(syn::parse_quote!(#[serde(default = #default_fn_name)]));
+                quote! {
+                    ::fdf_sdk::ParamSpec::optional(#param_name, #param_type, #description)
+                        .with_default(#default)
+                }
+            }
+            None if option_inner(&field.ty).is_some()
+                || has_serde_flag(&field.attrs, "default") =>
+            {
+                quote!(::fdf_sdk::ParamSpec::optional(#param_name, #param_type, #description))
+            }
+            None => quote!(::fdf_sdk::ParamSpec::required(#param_name, #param_type, #description)),
+        };
+        params.push(quote!(.param(#spec)));
+    }
+
+    let build = match &args.build {
+        Some(build) => quote!(Ok(Box::new(#build(config)?))),
+        None => quote!(Ok(Box::new(config))),
+    };
+
+    Ok(quote! {
+        #[derive(::fdf_sdk::__private::serde::Deserialize)]
+        #[serde(crate = "::fdf_sdk::__private::serde", deny_unknown_fields)]
+        #item
+
+        impl #ident {
+            /// Registered operator name
+            pub const NAME: &'static str = #name;
+        }
+
+        #(#default_fns)*
+
+        pub fn register(registry: &mut ::fdf_sdk::OperatorRegistry) {
+            registry
+                .register_typed(#name, |config: #ident| #build)
+                .description(#description)
+                #(.alias(#aliases))*
+                #category
+                #version
+                #(#params)*;
+        }
+    })
+}
+
+/// Remove the `#[param(...)]` helper attributes of a field and return their settings
+fn take_param_args(attrs: &mut Vec<Attribute>) -> syn::Result<ParamArgs> {
+    let mut args = ParamArgs::default();
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("param") {
+            return true;
+        }
+        if result.is_ok() {
+            result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    args.default = Some(match meta.value()?.parse::<Lit>()? {
+                        Lit::Str(s) => s.value(),
+                        Lit::Int(i) => i.to_string(),
+                        Lit::Float(f) => f.to_string(),
+                        Lit::Bool(b) => b.value.to_string(),
+                        other => return Err(syn::Error::new_spanned(other, "unsupported default")),
+                    });
+                } else if meta.path.is_ident("type") {
+                    args.param_type = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("expected default or type"));
+                }
+                Ok(())
+            });
+        }
+        false
+    });
+    result.map(|_| args)
+}
+
+/// Doc comment lines joined with spaces
+fn doc_comment(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines.join(" ")
+}
+
+/// Whether a `#[serde(...)]` attribute sets `flag` (alone or with a value, e.g. `default = "f"`)
+fn has_serde_flag(attrs: &[Attribute], flag: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .any(|attr| {
+            let mut found = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident(flag) {
+                    found = true;
+                }
+                if meta.input.peek(syn::Token![=]) {
+                    let _: Expr = meta.value()?.parse()?;
+                }
+                Ok(())
+            });
+            found
+        })
+}
+
+/// Value of `#[serde(rename = "...")]`, if any
+fn serde_rename(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut rename = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: Expr = meta.value()?.parse()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rename)
+}
+
+/// `T` for `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    match generic_args(ty) {
+        Some((name, args)) if name == "Option" && args.len() == 1 => Some(args[0]),
+        _ => None,
+    }
+}
+
+/// Last path segment of a type with its type arguments
+fn generic_args(ty: &Type) -> Option<(String, Vec<&Type>)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Some((segment.ident.to_string(), args))
+}
+
+/// Parameter type as shown in the operator metadata ("string", "int", "list<string>", ...)
+fn type_name(ty: &Type) -> String {
+    let Some((name, args)) = generic_args(ty) else {
+        return "any".to_string();
+    };
+    match (name.as_str(), args.as_slice()) {
+        ("String" | "str" | "PathBuf", _) => "string".to_string(),
+        ("f32" | "f64", _) => "float".to_string(),
+        (
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+            | "usize",
+            _,
+        ) => "int".to_string(),
+        ("bool", _) => "bool".to_string(),
+        ("Option", [inner]) => type_name(inner),
+        ("Vec" | "HashSet" | "BTreeSet", [inner]) => format!("list<{}>", type_name(inner)),
+        ("HashMap" | "BTreeMap", [key, value]) => {
+            format!("map<{}, {}>", type_name(key), type_name(value))
+        }
+        _ => "any".to_string(),
+    }
+}
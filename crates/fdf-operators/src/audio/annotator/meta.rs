@@ -0,0 +1,63 @@
+use crate::audio::{audio_bytes, audio_info};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Annotates audio with its duration, sample rate, channels, bits per sample and codec, read
+/// from the container and codec headers. Audio that cannot be parsed fails with an error,
+/// sending the sample to the error output
+#[fdf_operator(name = "audio.meta", category = "annotator")]
+pub struct AudioMeta {
+    /// Binary field holding the encoded audio
+    #[param(default = "audio")]
+    audio_col: String,
+    /// String field holding the path of the audio file, read instead of audio_col if set
+    path_col: Option<String>,
+    /// Prefix of the fields written: <prefix>duration (seconds), <prefix>sample_rate,
+    /// <prefix>channels, <prefix>bits_per_sample (null for compressed codecs) and
+    /// <prefix>codec ("pcm_s16le", "flac", "mp3", ...)
+    #[param(default = "audio_")]
+    prefix: String,
+}
+
+impl AudioMeta {
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Operator for AudioMeta {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = audio_bytes(&sample, &self.audio_col, self.path_col.as_deref())?;
+        let info = audio_info(bytes.into_owned())?;
+        let optional = |value: Option<u64>| value.map_or(Value::Null, Value::from);
+        sample.set_path(&self.field("duration"), Value::from(info.duration))?;
+        sample.set_path(
+            &self.field("sample_rate"),
+            optional(info.sample_rate.map(u64::from)),
+        )?;
+        sample.set_path(
+            &self.field("channels"),
+            optional(info.channels.map(|c| c as u64)),
+        )?;
+        sample.set_path(
+            &self.field("bits_per_sample"),
+            optional(info.bits_per_sample.map(u64::from)),
+        )?;
+        sample.set_path(&self.field("codec"), Value::from(info.codec))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.audio_col);
+        let mut columns = ColumnSpec::new().requires(input);
+        for name in [
+            "duration",
+            "sample_rate",
+            "channels",
+            "bits_per_sample",
+            "codec",
+        ] {
+            columns = columns.produces(self.field(name));
+        }
+        Some(columns)
+    }
+}
@@ -1,5 +1,9 @@
+pub mod meta;
+pub mod transcribe;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register audio annotators when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    meta::register(registry);
+    transcribe::register(registry);
 }
@@ -0,0 +1,172 @@
+use crate::audio::whisper::{Whisper, SAMPLE_RATE};
+use crate::audio::{audio_bytes, decode_audio};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::path::Path;
+
+/// Annotates audio with its transcript by a Whisper model (ONNX), and the mean token
+/// log-probability of the transcript, so that text filters can run on speech datasets and
+/// unreliable transcripts be dropped (Whisper's own fallback threshold is -1)
+#[fdf_operator(
+    name = "audio.transcribe",
+    category = "annotator",
+    build = "TranscribeConfig::build"
+)]
+struct TranscribeConfig {
+    /// Binary field holding the encoded audio
+    #[param(default = "audio")]
+    audio_col: String,
+    /// String field holding the path of the audio file, read instead of audio_col if set
+    path_col: Option<String>,
+    /// Path of the ONNX Whisper encoder (input_features input, last_hidden_state output)
+    encoder_model_path: String,
+    /// Path of the ONNX Whisper decoder without past key values (input_ids and
+    /// encoder_hidden_states inputs, logits and present.* outputs); decoder_model.onnx next to
+    /// the encoder if unset
+    decoder_model_path: Option<String>,
+    /// Path of the ONNX Whisper decoder with past key values, which decodes each token from
+    /// the key/values of the previous ones instead of rerunning all tokens;
+    /// decoder_with_past_model.onnx next to the encoder if unset and present
+    decoder_with_past_model_path: Option<String>,
+    /// Path of the model's tokenizer.json; tokenizer.json next to the encoder if unset
+    tokenizer_path: Option<String>,
+    /// Language code of the speech ("en", "de", ...); detected on the first 30 seconds of
+    /// each clip if unset (English for English-only models)
+    language: Option<String>,
+    /// Mel bands of the model input: 80, or 128 for large-v3 models
+    #[param(default = 80)]
+    n_mels: usize,
+    /// Maximum tokens decoded per 30 second window
+    #[param(default = 224)]
+    max_tokens: usize,
+    /// Field receiving the transcript
+    #[param(default = "transcript")]
+    text_col: String,
+    /// Field receiving the mean log-probability of the transcript tokens
+    #[param(default = "transcript_logprob")]
+    logprob_col: String,
+    /// Field receiving the language of the transcript (configured or detected)
+    #[param(default = "transcript_language")]
+    language_col: String,
+}
+
+impl TranscribeConfig {
+    fn build(self) -> Result<Transcribe> {
+        if self.n_mels == 0 || self.max_tokens == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: n_mels and max_tokens must be positive",
+                Self::NAME
+            ));
+        }
+        let next_to_encoder = |name: &str| {
+            let path = Path::new(&self.encoder_model_path).with_file_name(name);
+            path.to_string_lossy().into_owned()
+        };
+        let decoder_model_path = self
+            .decoder_model_path
+            .clone()
+            .unwrap_or_else(|| next_to_encoder("decoder_model.onnx"));
+        let decoder_with_past_model_path =
+            self.decoder_with_past_model_path.clone().or_else(|| {
+                let path = next_to_encoder("decoder_with_past_model.onnx");
+                Path::new(&path).is_file().then_some(path)
+            });
+        let tokenizer_path = self
+            .tokenizer_path
+            .clone()
+            .unwrap_or_else(|| next_to_encoder("tokenizer.json"));
+        Ok(Transcribe {
+            audio_col: self.audio_col,
+            path_col: self.path_col,
+            encoder_model_path: self.encoder_model_path,
+            decoder_model_path,
+            decoder_with_past_model_path,
+            tokenizer_path,
+            language: self.language,
+            n_mels: self.n_mels,
+            max_tokens: self.max_tokens,
+            text_col: self.text_col,
+            logprob_col: self.logprob_col,
+            language_col: self.language_col,
+            whisper: None,
+        })
+    }
+}
+
+pub struct Transcribe {
+    audio_col: String,
+    path_col: Option<String>,
+    encoder_model_path: String,
+    decoder_model_path: String,
+    decoder_with_past_model_path: Option<String>,
+    tokenizer_path: String,
+    language: Option<String>,
+    n_mels: usize,
+    max_tokens: usize,
+    text_col: String,
+    logprob_col: String,
+    language_col: String,
+    whisper: Option<Whisper>, // Loaded in open
+}
+
+impl Operator for Transcribe {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let fatal =
+            |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", TranscribeConfig::NAME, e));
+        let whisper = Whisper::load(
+            &self.encoder_model_path,
+            &self.decoder_model_path,
+            self.decoder_with_past_model_path.as_deref(),
+            &self.tokenizer_path,
+            self.n_mels,
+            ctx,
+        )
+        .map_err(fatal)?;
+        if let Some(language) = &self.language {
+            let known = if whisper.is_multilingual() {
+                whisper.language_token(language).is_some()
+            } else {
+                language == "en"
+            };
+            if !known {
+                return Err(fatal(anyhow::anyhow!(
+                    "The model does not know the language {}",
+                    language
+                ))
+                .into());
+            }
+        }
+        self.whisper = Some(whisper);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let whisper = self.whisper.as_ref().expect("model is loaded in open");
+        let bytes = audio_bytes(&sample, &self.audio_col, self.path_col.as_deref())?;
+        let audio = decode_audio(bytes.into_owned())?
+            .into_mono()
+            .resample(SAMPLE_RATE);
+        let transcript =
+            whisper.transcribe(&audio.samples, self.language.as_deref(), self.max_tokens)?;
+
+        ctx.metrics().observe("avg_logprob", transcript.avg_logprob);
+        if self.language.is_none() {
+            ctx.metrics()
+                .increment(&format!("language.{}", transcript.language), 1);
+        }
+        sample.set_path(&self.text_col, Value::from(transcript.text))?;
+        sample.set_path(&self.logprob_col, Value::from(transcript.avg_logprob))?;
+        sample.set_path(&self.language_col, Value::from(transcript.language))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.audio_col);
+        Some(
+            ColumnSpec::new()
+                .requires(input)
+                .produces(&self.text_col)
+                .produces(&self.logprob_col)
+                .produces(&self.language_col),
+        )
+    }
+}
@@ -0,0 +1,120 @@
+use crate::audio::{audio_bytes, audio_info};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Keeps audio whose duration is within bounds, to drop clips too short to hold speech and
+/// recordings too long for the model context. The duration comes from the field written by
+/// audio.meta when present, else from the audio headers
+#[fdf_operator(
+    name = "audio.duration_filter",
+    category = "filter",
+    build = "DurationFilterConfig::build"
+)]
+struct DurationFilterConfig {
+    /// Binary field holding the encoded audio
+    #[param(default = "audio")]
+    audio_col: String,
+    /// String field holding the path of the audio file, read instead of audio_col if set
+    path_col: Option<String>,
+    /// Prefix of the duration field of audio.meta (<prefix>duration), and of the fields
+    /// written with annotate
+    #[param(default = "audio_")]
+    prefix: String,
+    /// Minimum duration in seconds
+    min_duration: Option<f64>,
+    /// Maximum duration in seconds
+    max_duration: Option<f64>,
+    /// Keep every clip and write its duration (<prefix>duration) and the failed bound
+    /// (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl DurationFilterConfig {
+    fn build(self) -> Result<DurationFilter> {
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err(anyhow::anyhow!(
+                    "{}: min_duration is above max_duration",
+                    Self::NAME
+                ));
+            }
+        }
+        Ok(DurationFilter {
+            audio_col: self.audio_col,
+            path_col: self.path_col,
+            prefix: self.prefix,
+            min_duration: self.min_duration,
+            max_duration: self.max_duration,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct DurationFilter {
+    audio_col: String,
+    path_col: Option<String>,
+    prefix: String,
+    min_duration: Option<f64>,
+    max_duration: Option<f64>,
+    annotate: bool,
+}
+
+impl DurationFilter {
+    /// Bound a clip of this duration fails, if any
+    pub fn reject(&self, duration: f64) -> Option<&'static str> {
+        if self.min_duration.is_some_and(|min| duration < min) {
+            Some("min_duration")
+        } else if self.max_duration.is_some_and(|max| duration > max) {
+            Some("max_duration")
+        } else {
+            None
+        }
+    }
+
+    /// Duration written by audio.meta, else read from the audio headers
+    fn duration(&self, sample: &Sample) -> Result<f64> {
+        let field = format!("{}duration", self.prefix);
+        if let Some(duration) = sample.get_path(&field).and_then(Value::as_f64) {
+            return Ok(duration);
+        }
+        let bytes = audio_bytes(sample, &self.audio_col, self.path_col.as_deref())?;
+        Ok(audio_info(bytes.into_owned())?.duration)
+    }
+}
+
+impl Operator for DurationFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let duration = self.duration(&sample)?;
+        ctx.metrics().observe("duration", duration);
+        let rejected = self.reject(duration);
+
+        if self.annotate {
+            sample.set_path(&format!("{}duration", self.prefix), Value::from(duration))?;
+            sample.set_path(
+                &format!("{}rejected_by", self.prefix),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match rejected {
+            Some(bound) => {
+                ctx.metrics().increment(&format!("rejected.{}", bound), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // The duration field of audio.meta is used when present, not required
+        let input = self.path_col.as_ref().unwrap_or(&self.audio_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            columns
+                .produces(format!("{}duration", self.prefix))
+                .produces(format!("{}rejected_by", self.prefix))
+        } else {
+            columns
+        })
+    }
+}
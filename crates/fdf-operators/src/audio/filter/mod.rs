@@ -1,5 +1,9 @@
+pub mod duration;
+pub mod silence;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register audio filters when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    duration::register(registry);
+    silence::register(registry);
 }
@@ -0,0 +1,166 @@
+use crate::audio::{audio_bytes, decode_audio, Audio};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+const POWER_FLOOR: f64 = 1e-10; // -100 dBFS, the level of digital silence
+
+/// Drops clips that are mostly silence or noise: the fraction of silent frames (RMS level
+/// below a threshold) and an estimated signal-to-noise ratio, the level of the loudest frames
+/// over the noise floor of the quietest ones
+#[fdf_operator(
+    name = "audio.silence_filter",
+    category = "filter",
+    build = "SilenceFilterConfig::build"
+)]
+struct SilenceFilterConfig {
+    /// Binary field holding the encoded audio
+    #[param(default = "audio")]
+    audio_col: String,
+    /// String field holding the path of the audio file, read instead of audio_col if set
+    path_col: Option<String>,
+    /// Length of the analysis frames in milliseconds
+    #[param(default = 25)]
+    frame_ms: u32,
+    /// Frames with an RMS level below it (dB relative to full scale) are silent
+    #[param(default = -40.0)]
+    silence_db: f64,
+    /// Maximum fraction of silent frames
+    #[param(default = 0.8)]
+    max_silence_ratio: f64,
+    /// Minimum estimated signal-to-noise ratio in dB; stationary noise scores close to 0
+    #[param(default = 5.0)]
+    min_snr: f64,
+    /// Fraction of the frames averaged for the noise floor (the quietest) and the signal
+    /// level (the loudest)
+    #[param(default = 0.1)]
+    percentile: f64,
+    /// Prefix of the fields written with annotate
+    #[param(default = "audio_")]
+    prefix: String,
+    /// Keep every clip and write <prefix>silence_ratio, <prefix>snr and the failed bound
+    /// (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl SilenceFilterConfig {
+    fn build(self) -> Result<SilenceFilter> {
+        if self.frame_ms == 0 {
+            return Err(anyhow::anyhow!("{}: frame_ms must be positive", Self::NAME));
+        }
+        if !(0.0..=1.0).contains(&self.max_silence_ratio) {
+            return Err(anyhow::anyhow!(
+                "{}: max_silence_ratio must be between 0 and 1",
+                Self::NAME
+            ));
+        }
+        if !(self.percentile > 0.0 && self.percentile <= 0.5) {
+            return Err(anyhow::anyhow!(
+                "{}: percentile must be above 0 and at most 0.5",
+                Self::NAME
+            ));
+        }
+        Ok(SilenceFilter {
+            audio_col: self.audio_col,
+            path_col: self.path_col,
+            frame_ms: self.frame_ms,
+            silence_db: self.silence_db,
+            max_silence_ratio: self.max_silence_ratio,
+            min_snr: self.min_snr,
+            percentile: self.percentile,
+            prefix: self.prefix,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct SilenceFilter {
+    audio_col: String,
+    path_col: Option<String>,
+    frame_ms: u32,
+    silence_db: f64,
+    max_silence_ratio: f64,
+    min_snr: f64,
+    percentile: f64,
+    prefix: String,
+    annotate: bool,
+}
+
+impl SilenceFilter {
+    /// Fraction of silent frames and estimated SNR in dB of a mono clip; clips shorter than
+    /// a frame are silent
+    pub fn measure(&self, audio: &Audio) -> (f64, f64) {
+        let frame = (audio.sample_rate as usize * self.frame_ms as usize / 1000).max(1);
+        let mut powers: Vec<f64> = audio
+            .samples
+            .chunks_exact(frame)
+            .map(|frame| {
+                let energy: f64 = frame.iter().map(|&s| f64::from(s) * f64::from(s)).sum();
+                (energy / frame.len() as f64).max(POWER_FLOOR)
+            })
+            .collect();
+        if powers.is_empty() {
+            return (1.0, 0.0);
+        }
+        let threshold = 10f64.powf(self.silence_db / 10.0);
+        let silent = powers.iter().filter(|&&power| power < threshold).count();
+        let silence_ratio = silent as f64 / powers.len() as f64;
+
+        powers.sort_by(f64::total_cmp);
+        let count = ((powers.len() as f64 * self.percentile).ceil() as usize).max(1);
+        let mean = |powers: &[f64]| powers.iter().sum::<f64>() / powers.len() as f64;
+        let noise = mean(&powers[..count]);
+        let signal = mean(&powers[powers.len() - count..]);
+        (silence_ratio, 10.0 * (signal / noise).log10())
+    }
+
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Operator for SilenceFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = audio_bytes(&sample, &self.audio_col, self.path_col.as_deref())?;
+        let audio = decode_audio(bytes.into_owned())?.into_mono();
+        let (silence_ratio, snr) = self.measure(&audio);
+        ctx.metrics().observe("silence_ratio", silence_ratio);
+        ctx.metrics().observe("snr", snr);
+        let rejected = if silence_ratio > self.max_silence_ratio {
+            Some("max_silence_ratio")
+        } else if snr < self.min_snr {
+            Some("min_snr")
+        } else {
+            None
+        };
+
+        if self.annotate {
+            sample.set_path(&self.field("silence_ratio"), Value::from(silence_ratio))?;
+            sample.set_path(&self.field("snr"), Value::from(snr))?;
+            sample.set_path(
+                &self.field("rejected_by"),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match rejected {
+            Some(bound) => {
+                ctx.metrics().increment(&format!("rejected.{}", bound), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.audio_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            columns
+                .produces(self.field("silence_ratio"))
+                .produces(self.field("snr"))
+                .produces(self.field("rejected_by"))
+        } else {
+            columns
+        })
+    }
+}
@@ -1,12 +1,284 @@
+//! Audio operators
+//!
+//! Audio is read from a binary field holding the encoded file (`audio_col`, e.g. a Parquet
+//! `Binary` column), or from the file named by a string field (`path_col`) when it is set.
+//! Containers and codecs are handled by symphonia: WAV, FLAC, MP3, Ogg Vorbis, AAC / M4A,
+//! ALAC and Matroska.
+
 pub mod annotator;
 pub mod filter;
 pub mod transformer;
+pub mod whisper;
+
+use fdf_sdk::{OperatorRegistry, Result, Sample, Value};
+use std::borrow::Cow;
+use std::f64::consts::PI;
+use std::io::{Cursor, ErrorKind};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+const SINC_ZERO_CROSSINGS: f64 = 16.0; // Of the resampling filter, on each side
+
+pub fn register(registry: &mut OperatorRegistry) {
+    transformer::register(registry);
+    filter::register(registry);
+    annotator::register(registry);
+}
+
+/// Encoded audio of a sample: the file named by the string field `path_col` if set, else the
+/// binary field `audio_col`
+pub fn audio_bytes<'a>(
+    sample: &'a Sample,
+    audio_col: &str,
+    path_col: Option<&str>,
+) -> Result<Cow<'a, [u8]>> {
+    match path_col {
+        Some(path_col) => {
+            let path = sample
+                .get_path(path_col)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Missing path field: {}", path_col))?;
+            std::fs::read(path)
+                .map(Cow::Owned)
+                .map_err(|e| anyhow::anyhow!("Cannot read audio {}: {}", path, e))
+        }
+        None => sample
+            .get_bytes(audio_col)
+            .map(Cow::Borrowed)
+            .ok_or_else(|| anyhow::anyhow!("Missing audio field: {}", audio_col)),
+    }
+}
+
+/// Properties of the default track of an audio file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioInfo {
+    pub duration: f64, // Seconds
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    pub bits_per_sample: Option<u32>,
+    pub codec: String, // Short codec name ("pcm_s16le", "flac", "mp3", ...)
+}
+
+/// Demuxer of encoded audio
+pub fn open_audio(bytes: Vec<u8>) -> Result<Box<dyn FormatReader>> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(
+            &Hint::new(),
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| anyhow::anyhow!("Unsupported audio: {}", e))?;
+    Ok(probed.format)
+}
+
+/// Properties of encoded audio, read from the container and codec headers. The duration of
+/// files whose headers do not give their length (e.g. MP3 without a Xing header) is the sum
+/// of their packet durations, read without decoding; that of truncated PCM files is bounded
+/// by their size
+pub fn audio_info(bytes: Vec<u8>) -> Result<AudioInfo> {
+    let size = bytes.len() as u64;
+    let mut format = open_audio(bytes)?;
+    let track = format
+        .default_track()
+        .ok_or_else(|| anyhow::anyhow!("Audio has no track"))?;
+    let (track_id, params) = (track.id, track.codec_params.clone());
+    let codec = symphonia::default::get_codecs()
+        .get_codec(params.codec)
+        .map_or("unknown", |codec| codec.short_name)
+        .to_string();
+
+    let frames = match params.n_frames {
+        Some(frames) => match (codec.starts_with("pcm_"), params.bits_per_sample) {
+            (true, Some(bits)) => {
+                let channels = params.channels.map_or(1, |channels| channels.count()) as u64;
+                frames.min(size / (channels * u64::from(bits.div_ceil(8))).max(1))
+            }
+            _ => frames,
+        },
+        None => {
+            let mut frames = 0;
+            // Reading stops at the end of the stream, or at the first damaged packet
+            while let Ok(packet) = format.next_packet() {
+                if packet.track_id() == track_id {
+                    frames += packet.dur;
+                }
+            }
+            frames
+        }
+    };
+    let duration = match (params.time_base, params.sample_rate) {
+        (Some(time_base), _) => {
+            let time = time_base.calc_time(frames);
+            time.seconds as f64 + time.frac
+        }
+        (None, Some(sample_rate)) => frames as f64 / f64::from(sample_rate),
+        (None, None) => return Err(anyhow::anyhow!("Audio duration is unknown")),
+    };
+    Ok(AudioInfo {
+        duration,
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|channels| channels.count()),
+        bits_per_sample: params.bits_per_sample,
+        codec,
+    })
+}
+
+/// Decoded audio
+#[derive(Debug, Clone)]
+pub struct Audio {
+    pub samples: Vec<f32>, // Interleaved channels, in [-1, 1]
+    pub channels: usize,
+    pub sample_rate: u32,
+}
+
+impl Audio {
+    /// Number of samples per channel
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    /// Audio mixed down to one channel, the mean of the channels
+    pub fn into_mono(self) -> Audio {
+        if self.channels == 1 {
+            return self;
+        }
+        let samples = self
+            .samples
+            .chunks_exact(self.channels)
+            .map(|frame| frame.iter().sum::<f32>() / self.channels as f32)
+            .collect();
+        Audio {
+            samples,
+            channels: 1,
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Audio resampled to `sample_rate` with a windowed sinc interpolator, low-passed below
+    /// the new Nyquist frequency when downsampling
+    pub fn resample(self, sample_rate: u32) -> Audio {
+        if sample_rate == self.sample_rate {
+            return self;
+        }
+        let ratio = f64::from(sample_rate) / f64::from(self.sample_rate);
+        let cutoff = ratio.min(1.0);
+        let half_width = (SINC_ZERO_CROSSINGS / cutoff).ceil() as isize; // In input samples
+        let (frames, channels) = (self.frames() as isize, self.channels);
+        let output_frames = (frames as f64 * ratio).round() as usize;
+
+        let mut samples = vec![0.0; output_frames * channels];
+        for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+            let time = i as f64 / ratio; // Position in input samples
+            let center = time.floor() as isize;
+            for j in (center - half_width + 1).max(0)..=(center + half_width).min(frames - 1) {
+                let distance = time - j as f64;
+                let x = PI * cutoff * distance;
+                let sinc = if x == 0.0 { 1.0 } else { x.sin() / x };
+                let window = 0.5 + 0.5 * (PI * distance / half_width as f64).cos();
+                let weight = (cutoff * sinc * window) as f32;
+                let input = &self.samples[j as usize * channels..][..channels];
+                for (output, input) in frame.iter_mut().zip(input) {
+                    *output += weight * input;
+                }
+            }
+        }
+        Audio {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// WAV file of the audio: integer PCM with 16 or 24 bits per sample, or 32-bit float.
+    /// Samples out of [-1, 1] are clipped
+    pub fn to_wav(&self, bits_per_sample: u16) -> Result<Vec<u8>> {
+        let float = bits_per_sample == 32;
+        let spec = hound::WavSpec {
+            channels: self.channels as u16,
+            sample_rate: self.sample_rate,
+            bits_per_sample,
+            sample_format: if float {
+                hound::SampleFormat::Float
+            } else {
+                hound::SampleFormat::Int
+            },
+        };
+        let mut wav = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec)
+            .map_err(|e| anyhow::anyhow!("Cannot encode WAV: {}", e))?;
+        let scale = ((1_i32 << (bits_per_sample - 1)) - 1) as f32;
+        for &sample in &self.samples {
+            let sample = sample.clamp(-1.0, 1.0);
+            let written = if float {
+                writer.write_sample(sample)
+            } else {
+                writer.write_sample((sample * scale).round() as i32)
+            };
+            written.map_err(|e| anyhow::anyhow!("Cannot encode WAV: {}", e))?;
+        }
+        writer
+            .finalize()
+            .map_err(|e| anyhow::anyhow!("Cannot encode WAV: {}", e))?;
+        Ok(wav.into_inner())
+    }
+}
 
-use fdf_sdk::OperatorRegistry;
+/// Samples of the default track of encoded audio. Damaged packets are skipped; truncated
+/// files are decoded up to where they end
+pub fn decode_audio(bytes: Vec<u8>) -> Result<Audio> {
+    let mut format = open_audio(bytes)?;
+    let track = format
+        .default_track()
+        .ok_or_else(|| anyhow::anyhow!("Audio has no track"))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow::anyhow!("Unsupported audio codec: {}", e))?;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register audio operators when implemented
-    // transformer::register(registry);
-    // filter::register(registry);
-    // annotator::register(registry);
+    let mut samples = Vec::new();
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut channels = track.codec_params.channels.map(|channels| channels.count());
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(anyhow::anyhow!("Cannot read audio: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(anyhow::anyhow!("Cannot decode audio: {}", e)),
+        };
+        let spec = *decoded.spec();
+        let count = spec.channels.count().max(1);
+        if samples.is_empty() {
+            (sample_rate, channels) = (Some(spec.rate), Some(count));
+        } else if channels != Some(count) || sample_rate != Some(spec.rate) {
+            return Err(anyhow::anyhow!("Audio format changes within the stream"));
+        }
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * count => buffer,
+            _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+    let sample_rate = sample_rate.ok_or_else(|| anyhow::anyhow!("Audio sample rate is unknown"))?;
+    Ok(Audio {
+        samples,
+        channels: channels.unwrap_or(1).max(1),
+        sample_rate,
+    })
 }
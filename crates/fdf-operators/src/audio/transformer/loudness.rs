@@ -0,0 +1,268 @@
+use crate::audio::{audio_bytes, decode_audio, Audio};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use std::f64::consts::PI;
+
+const BLOCK_SECONDS: f64 = 0.4; // Gating blocks of ITU-R BS.1770, overlapping by 75%
+const ABSOLUTE_GATE: f64 = -70.0; // LUFS
+const RELATIVE_GATE: f64 = -10.0; // LU below the loudness of the blocks above the absolute gate
+
+/// Normalizes the loudness of audio to a target, measured as the integrated loudness of
+/// EBU R128 (ITU-R BS.1770 K-weighting and gating), so that clips from different sources
+/// have consistent levels. The audio is rewritten as WAV in the sample
+#[fdf_operator(
+    name = "audio.loudness_normalize",
+    category = "transformer",
+    build = "LoudnessNormalizeConfig::build"
+)]
+struct LoudnessNormalizeConfig {
+    /// Binary field holding the encoded audio
+    #[param(default = "audio")]
+    audio_col: String,
+    /// String field holding the path of the audio file, read instead of audio_col if set
+    path_col: Option<String>,
+    /// Binary field receiving the normalized WAV (a top-level field); audio_col if unset
+    output_col: Option<String>,
+    /// Target integrated loudness in LUFS (-23 for EBU R128 broadcast, around -16 for
+    /// streaming)
+    #[param(default = -23.0)]
+    target_lufs: f64,
+    /// Maximum sample peak in dBFS; the gain is lowered for clips that would exceed it, which
+    /// then stay below the target
+    #[param(default = -1.0)]
+    max_peak: f64,
+    /// Maximum gain in dB, to not amplify faint recordings to noise; unlimited if unset
+    max_gain: Option<f64>,
+    /// Bits per sample of the WAV: 16 or 24 (integer PCM), or 32 (float)
+    #[param(default = 16)]
+    bits_per_sample: u16,
+    /// Prefix of the fields written (<prefix>loudness, the measured loudness, null for clips
+    /// too quiet to measure; <prefix>gain in dB), and of the fields of audio.meta
+    /// (<prefix>codec, <prefix>bits_per_sample), updated when present
+    #[param(default = "audio_")]
+    prefix: String,
+}
+
+impl LoudnessNormalizeConfig {
+    fn build(self) -> Result<LoudnessNormalize> {
+        if ![16, 24, 32].contains(&self.bits_per_sample) {
+            return Err(anyhow::anyhow!(
+                "{}: bits_per_sample must be 16, 24 or 32",
+                Self::NAME
+            ));
+        }
+        if self.target_lufs >= 0.0 || self.max_peak > 0.0 {
+            return Err(anyhow::anyhow!(
+                "{}: target_lufs and max_peak must be below 0",
+                Self::NAME
+            ));
+        }
+        let output_col = self.output_col.unwrap_or_else(|| self.audio_col.clone());
+        if output_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: audio is written to a top-level field",
+                Self::NAME
+            ));
+        }
+        Ok(LoudnessNormalize {
+            audio_col: self.audio_col,
+            path_col: self.path_col,
+            output_col,
+            target_lufs: self.target_lufs,
+            max_peak: self.max_peak,
+            max_gain: self.max_gain,
+            bits_per_sample: self.bits_per_sample,
+            prefix: self.prefix,
+        })
+    }
+}
+
+pub struct LoudnessNormalize {
+    audio_col: String,
+    path_col: Option<String>,
+    output_col: String,
+    target_lufs: f64,
+    max_peak: f64,
+    max_gain: Option<f64>,
+    bits_per_sample: u16,
+    prefix: String,
+}
+
+/// Second order IIR filter (direct form I)
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2], // a1, a2; a0 is 1
+}
+
+impl Biquad {
+    fn filter(&self, signal: &mut [f64]) {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for value in signal {
+            let x = *value;
+            let y =
+                self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            *value = y;
+        }
+    }
+}
+
+/// K-weighting filters of BS.1770 (a high shelf modeling the head, then a high pass), with
+/// coefficients derived for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate);
+
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+    [shelf, high_pass]
+}
+
+/// Loudness in LUFS of a mean square (summed over channels)
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Integrated loudness of audio in LUFS, or None if every block is below the absolute gate.
+/// Channels are weighted equally (the surround weights of BS.1770 are not applied); clips
+/// shorter than a block are measured as a single block
+pub fn integrated_loudness(audio: &Audio) -> Option<f64> {
+    let frames = audio.frames();
+    let block = ((BLOCK_SECONDS * f64::from(audio.sample_rate)) as usize).clamp(1, frames.max(1));
+    let step = (block / 4).max(1);
+    let filters = k_weighting(audio.sample_rate);
+
+    // Mean square of each block, summed over the K-weighted channels
+    let starts: Vec<usize> = (0..=frames.saturating_sub(block)).step_by(step).collect();
+    let mut powers = vec![0.0; starts.len()];
+    for channel in 0..audio.channels {
+        let mut signal: Vec<f64> = audio
+            .samples
+            .iter()
+            .skip(channel)
+            .step_by(audio.channels)
+            .map(|&sample| f64::from(sample))
+            .collect();
+        for filter in &filters {
+            filter.filter(&mut signal);
+        }
+        // Prefix sums of squares give the energy of every block in one pass
+        let mut energy = Vec::with_capacity(signal.len() + 1);
+        energy.push(0.0);
+        for value in &signal {
+            energy.push(energy.last().unwrap() + value * value);
+        }
+        for (power, &start) in powers.iter_mut().zip(&starts) {
+            let end = (start + block).min(signal.len());
+            *power += (energy[end] - energy[start]) / block as f64;
+        }
+    }
+
+    let gated = |threshold: f64| -> Option<f64> {
+        let kept: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&power| power > 0.0 && lufs(power) > threshold)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let ungated = gated(ABSOLUTE_GATE)?;
+    gated(lufs(ungated) + RELATIVE_GATE).map(lufs)
+}
+
+impl LoudnessNormalize {
+    /// Gain in dB bringing audio of this loudness to the target, within the peak and gain
+    /// limits, and whether the peak limit lowered it
+    pub fn gain(&self, loudness: f64, peak: f32) -> (f64, bool) {
+        let mut gain = self.target_lufs - loudness;
+        if let Some(max_gain) = self.max_gain {
+            gain = gain.min(max_gain);
+        }
+        let peak_gain = self.max_peak - 20.0 * f64::from(peak).log10();
+        if peak > 0.0 && gain > peak_gain {
+            return (peak_gain, true);
+        }
+        (gain, false)
+    }
+
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Operator for LoudnessNormalize {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = audio_bytes(&sample, &self.audio_col, self.path_col.as_deref())?;
+        let mut audio = decode_audio(bytes.into_owned())?;
+
+        // Clips too quiet to measure are rewritten unchanged
+        let loudness = integrated_loudness(&audio);
+        let gain = match loudness {
+            Some(loudness) => {
+                ctx.metrics().observe("loudness", loudness);
+                let peak = audio.samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+                let (gain, limited) = self.gain(loudness, peak);
+                if limited {
+                    ctx.metrics().increment("peak_limited", 1);
+                }
+                gain
+            }
+            None => {
+                ctx.metrics().increment("too_quiet", 1);
+                0.0
+            }
+        };
+        ctx.metrics().observe("gain", gain);
+        let factor = 10f64.powf(gain / 20.0) as f32;
+        for sample in &mut audio.samples {
+            *sample *= factor;
+        }
+        sample.set_bytes(&self.output_col, audio.to_wav(self.bits_per_sample)?);
+
+        sample.set_path(&self.field("loudness"), Value::from(loudness))?;
+        sample.set_path(&self.field("gain"), Value::from(gain))?;
+        let codec = match self.bits_per_sample {
+            32 => "pcm_f32le".to_string(),
+            bits => format!("pcm_s{}le", bits),
+        };
+        let updates = [
+            ("codec", Value::from(codec)),
+            ("bits_per_sample", Value::from(self.bits_per_sample)),
+        ];
+        for (name, value) in updates {
+            let field = self.field(name);
+            if sample.get_path(&field).is_some() {
+                sample.set_path(&field, value)?;
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.audio_col);
+        Some(
+            ColumnSpec::new()
+                .requires(input)
+                .produces(&self.output_col)
+                .produces(self.field("loudness"))
+                .produces(self.field("gain")),
+        )
+    }
+}
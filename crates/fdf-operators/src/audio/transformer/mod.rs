@@ -1,5 +1,7 @@
+pub mod loudness;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register audio transformers when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    loudness::register(registry);
 }
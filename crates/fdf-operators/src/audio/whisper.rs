@@ -0,0 +1,413 @@
+//! Whisper speech recognition models exported to ONNX (HuggingFace optimum layout, e.g.
+//! onnx-community/whisper-base): the encoder (`encoder_model.onnx`) takes the log-mel
+//! spectrogram of a 30 second window as `input_features` of shape [batch, n_mels, 3000] and
+//! outputs `last_hidden_state`; the decoder (`decoder_model.onnx`) takes `input_ids` and
+//! `encoder_hidden_states` and outputs `logits`. With the decoder with past key values
+//! (`decoder_with_past_model.onnx`), the `present.*` outputs of the decoder are fed back as its
+//! `past_key_values.*` inputs, so that each step only runs the new token; without it every
+//! step runs the decoder on all tokens. Transcription is greedy, without timestamps
+
+use crate::onnx::{Input, OnnxModel, Output};
+use crate::text::tokenizer::shared_huggingface;
+use fdf_sdk::{Context, Result};
+use ort::value::DynValue;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::sync::Arc;
+use tokenizers::Tokenizer;
+
+pub const SAMPLE_RATE: u32 = 16000;
+const N_FFT: usize = 400; // 25 ms frames
+const HOP_LENGTH: usize = 160; // 10 ms hop
+const WINDOW_SAMPLES: usize = 30 * SAMPLE_RATE as usize; // Model input window
+const WINDOW_FRAMES: usize = WINDOW_SAMPLES / HOP_LENGTH;
+const MULTILINGUAL_VOCAB: usize = 51865; // English-only models have a smaller vocabulary
+
+/// Log-mel spectrogram of Whisper's feature extractor
+pub struct LogMel {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,       // Periodic Hann window
+    filters: Vec<Vec<f32>>, // Mel filter bank, n_mels x (N_FFT / 2 + 1)
+}
+
+impl LogMel {
+    pub fn new(n_mels: usize) -> Self {
+        let window = (0..N_FFT)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / N_FFT as f32).cos())
+            .collect();
+        LogMel {
+            fft: RealFftPlanner::new().plan_fft_forward(N_FFT),
+            window,
+            filters: mel_filters(n_mels),
+        }
+    }
+
+    /// Features of a window of 16 kHz samples, zero-padded to 30 seconds: log10 of the mel
+    /// power spectrum, clamped to 8 below its maximum and scaled, in [n_mels, 3000] order
+    pub fn features(&self, samples: &[f32]) -> Vec<f32> {
+        let samples = &samples[..samples.len().min(WINDOW_SAMPLES)];
+        // Frames are centered, the window reflected at its ends
+        let half = N_FFT as isize / 2;
+        let last = WINDOW_SAMPLES as isize - 1;
+        let sample = |i: isize| {
+            let i = (i - half).abs();
+            let i = if i > last { 2 * last - i } else { i };
+            samples.get(i as usize).copied().unwrap_or(0.0)
+        };
+
+        let n_mels = self.filters.len();
+        let mut mel = vec![0.0; n_mels * WINDOW_FRAMES];
+        let mut input = self.fft.make_input_vec();
+        let mut spectrum = self.fft.make_output_vec();
+        let mut power = vec![0.0; spectrum.len()];
+        for frame in 0..WINDOW_FRAMES {
+            let start = (frame * HOP_LENGTH) as isize;
+            for (n, value) in input.iter_mut().enumerate() {
+                *value = sample(start + n as isize) * self.window[n];
+            }
+            self.fft
+                .process(&mut input, &mut spectrum)
+                .expect("buffers have the planned lengths");
+            for (power, bin) in power.iter_mut().zip(&spectrum) {
+                *power = bin.norm_sqr();
+            }
+            for (m, filter) in self.filters.iter().enumerate() {
+                let energy: f32 = filter.iter().zip(&power).map(|(w, p)| w * p).sum();
+                mel[m * WINDOW_FRAMES + frame] = energy.max(1e-10).log10();
+            }
+        }
+        let max = mel.iter().copied().fold(f32::MIN, f32::max);
+        for value in &mut mel {
+            *value = ((*value).max(max - 8.0) + 4.0) / 4.0;
+        }
+        mel
+    }
+}
+
+/// Mel filter bank of librosa (Slaney mel scale and area normalization) from 0 to 8 kHz
+fn mel_filters(n_mels: usize) -> Vec<Vec<f32>> {
+    const LINEAR_MAX: f64 = 1000.0; // Hz; the scale is linear below, logarithmic above
+    let log_step = 6.4f64.ln() / 27.0;
+    let to_mel = |hz: f64| {
+        if hz < LINEAR_MAX {
+            3.0 * hz / 200.0
+        } else {
+            15.0 + (hz / LINEAR_MAX).ln() / log_step
+        }
+    };
+    let to_hz = |mel: f64| {
+        if mel < 15.0 {
+            200.0 * mel / 3.0
+        } else {
+            LINEAR_MAX * ((mel - 15.0) * log_step).exp()
+        }
+    };
+
+    let nyquist = f64::from(SAMPLE_RATE) / 2.0;
+    let max_mel = to_mel(nyquist);
+    let edges: Vec<f64> = (0..n_mels + 2)
+        .map(|i| to_hz(max_mel * i as f64 / (n_mels + 1) as f64))
+        .collect();
+    (0..n_mels)
+        .map(|m| {
+            let (low, center, high) = (edges[m], edges[m + 1], edges[m + 2]);
+            (0..=N_FFT / 2)
+                .map(|bin| {
+                    let hz = bin as f64 * f64::from(SAMPLE_RATE) / N_FFT as f64;
+                    let rising = (hz - low) / (center - low);
+                    let falling = (high - hz) / (high - center);
+                    (rising.min(falling).max(0.0) * 2.0 / (high - low)) as f32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Transcript of a clip
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub text: String,
+    pub avg_logprob: f64, // Mean log-probability of the decoded tokens, end of text included
+    pub language: String,
+}
+
+/// Key/value cache of the decoder: the `present.*` outputs of the last steps, by the name of
+/// the `past_key_values.*` input they are fed to
+type Cache = Vec<(String, DynValue)>;
+
+/// Loaded Whisper model
+pub struct Whisper {
+    encoder: Arc<OnnxModel>,
+    decoder: Arc<OnnxModel>,
+    decoder_with_past: Option<Arc<OnnxModel>>,
+    tokenizer: Arc<Tokenizer>,
+    log_mel: LogMel,
+    end_of_text: u32,
+    start_of_transcript: u32,
+    transcribe: u32,
+    no_timestamps: u32,
+    languages: Vec<u32>, // Language tokens, empty for English-only models
+    blank: Option<u32>,  // Space token, not allowed first
+}
+
+impl Whisper {
+    /// Models and tokenizer loaded once per run; n_mels is 80, or 128 for large-v3 models
+    pub fn load(
+        encoder_path: &str,
+        decoder_path: &str,
+        decoder_with_past_path: Option<&str>,
+        tokenizer_path: &str,
+        n_mels: usize,
+        ctx: &Context,
+    ) -> Result<Self> {
+        let encoder = OnnxModel::load(encoder_path, ctx)?;
+        let decoder = OnnxModel::load(decoder_path, ctx)?;
+        let decoder_with_past = match decoder_with_past_path {
+            Some(path) => {
+                let model = OnnxModel::load(path, ctx)?;
+                let names = |names: &[String], prefix: &str| {
+                    names.iter().filter(|name| name.starts_with(prefix)).count()
+                };
+                let past = names(model.inputs(), "past_key_values.");
+                if past == 0 || names(decoder.outputs(), "present.") != past {
+                    return Err(anyhow::anyhow!(
+                        "{} must take the present.* outputs of {} as past_key_values.* inputs",
+                        path,
+                        decoder_path
+                    ));
+                }
+                Some(model)
+            }
+            None => None,
+        };
+        let tokenizer = shared_huggingface(tokenizer_path, ctx)?;
+        let special = |token: &str| {
+            tokenizer
+                .token_to_id(token)
+                .ok_or_else(|| anyhow::anyhow!("Tokenizer has no {} token", token))
+        };
+        let start_of_transcript = special("<|startoftranscript|>")?;
+        let languages = if tokenizer.get_vocab_size(true) >= MULTILINGUAL_VOCAB {
+            // Language tokens follow the start of transcript token, up to the task tokens
+            (start_of_transcript + 1..special("<|translate|>")?).collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Whisper {
+            end_of_text: special("<|endoftext|>")?,
+            start_of_transcript,
+            transcribe: special("<|transcribe|>")?,
+            no_timestamps: special("<|notimestamps|>")?,
+            languages,
+            blank: tokenizer.token_to_id("Ġ"),
+            encoder,
+            decoder,
+            decoder_with_past,
+            tokenizer,
+            log_mel: LogMel::new(n_mels),
+        })
+    }
+
+    /// Whether the model transcribes other languages than English
+    pub fn is_multilingual(&self) -> bool {
+        !self.languages.is_empty()
+    }
+
+    /// Token of a language code ("en", "de", ...), if the model knows it
+    pub fn language_token(&self, language: &str) -> Option<u32> {
+        let token = self.tokenizer.token_to_id(&format!("<|{}|>", language))?;
+        self.languages.contains(&token).then_some(token)
+    }
+
+    /// Transcript of 16 kHz mono samples, decoded in consecutive 30 second windows of at
+    /// most max_tokens tokens. The language is detected on the first window if unset
+    pub fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        max_tokens: usize,
+    ) -> Result<Transcript> {
+        let mut language = language.map(str::to_string);
+        let (mut tokens, mut logprob_sum, mut count) = (Vec::new(), 0.0, 0);
+        for window in samples.chunks(WINDOW_SAMPLES) {
+            let encoded = self.encode(window)?;
+            let language = match &language {
+                Some(language) => language,
+                None => language.insert(self.detect_language(&encoded)?),
+            };
+            let mut prompt = vec![self.start_of_transcript];
+            if self.is_multilingual() {
+                let token = self
+                    .language_token(language)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown language: {}", language))?;
+                prompt.extend([token, self.transcribe]);
+            }
+            prompt.push(self.no_timestamps);
+            let (window_tokens, window_logprob, scored) =
+                self.decode_greedy(&encoded, prompt, max_tokens)?;
+            logprob_sum += window_logprob;
+            count += scored;
+            tokens.extend(window_tokens);
+        }
+        let text = self
+            .tokenizer
+            .decode(&tokens, true)
+            .map_err(|e| anyhow::anyhow!("Cannot decode tokens: {}", e))?;
+        Ok(Transcript {
+            text: text.trim().to_string(),
+            avg_logprob: if count > 0 {
+                logprob_sum / count as f64
+            } else {
+                0.0
+            },
+            language: language.unwrap_or_else(|| "en".to_string()),
+        })
+    }
+
+    /// Encoder hidden states of a window
+    fn encode(&self, window: &[f32]) -> Result<DynValue> {
+        let features = self.log_mel.features(window);
+        let n_mels = self.log_mel.filters.len();
+        let outputs = self.encoder.run_values(vec![(
+            "input_features",
+            Input::F32(vec![1, n_mels, WINDOW_FRAMES], features),
+        )])?;
+        named(&self.encoder, outputs, "last_hidden_state")
+    }
+
+    /// Logits of the token following `tokens`. Once `cache` holds the key/values of the
+    /// previous tokens, only the last token is run through the decoder with past key values;
+    /// `cache` is updated with the new key/values
+    fn next_logits(
+        &self,
+        encoded: &DynValue,
+        tokens: &[u32],
+        cache: &mut Cache,
+    ) -> Result<Vec<f32>> {
+        let (model, outputs) = match &self.decoder_with_past {
+            Some(decoder) if !cache.is_empty() => {
+                let last = i64::from(*tokens.last().unwrap_or(&self.start_of_transcript));
+                let mut inputs = vec![("input_ids", Input::I64(vec![1, 1], vec![last]))];
+                inputs.extend(
+                    cache
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), Input::Value(value))),
+                );
+                (decoder, decoder.run_values(inputs)?)
+            }
+            _ => {
+                let ids = tokens.iter().map(|&token| i64::from(token)).collect();
+                let outputs = self.decoder.run_values(vec![
+                    ("input_ids", Input::I64(vec![1, tokens.len()], ids)),
+                    ("encoder_hidden_states", Input::Value(encoded)),
+                ])?;
+                (&self.decoder, outputs)
+            }
+        };
+
+        let mut logits = None;
+        for (name, value) in model.outputs().iter().zip(outputs) {
+            if name == "logits" {
+                logits = Some(value);
+            } else if let Some(key) = name.strip_prefix("present") {
+                // The decoder with past only outputs the self-attention key/values: the
+                // cross-attention ones of the first step are kept
+                if self.decoder_with_past.is_some() {
+                    let past = format!("past_key_values{}", key);
+                    match cache.iter_mut().find(|(name, _)| *name == past) {
+                        Some(entry) => entry.1 = value,
+                        None => cache.push((past, value)),
+                    }
+                }
+            }
+        }
+        let logits = Output::from_value(
+            &logits.ok_or_else(|| anyhow::anyhow!("Decoder has no logits output"))?,
+        )?;
+        let vocab = *logits.shape.last().unwrap_or(&0);
+        if logits.shape.len() != 3 || vocab == 0 {
+            return Err(anyhow::anyhow!(
+                "Decoder logits have shape {:?}, expected [batch, tokens, vocabulary]",
+                logits.shape
+            ));
+        }
+        Ok(logits.values[logits.values.len() - vocab..].to_vec())
+    }
+
+    /// Most likely language code of an encoded window (English for English-only models)
+    fn detect_language(&self, encoded: &DynValue) -> Result<String> {
+        let Some(&first) = self.languages.first() else {
+            return Ok("en".to_string());
+        };
+        let logits = self.next_logits(encoded, &[self.start_of_transcript], &mut Cache::new())?;
+        let token = self
+            .languages
+            .iter()
+            .copied()
+            .filter(|&token| (token as usize) < logits.len())
+            .max_by(|&a, &b| logits[a as usize].total_cmp(&logits[b as usize]))
+            .unwrap_or(first);
+        let name = self.tokenizer.id_to_token(token).unwrap_or_default();
+        Ok(name
+            .trim_start_matches("<|")
+            .trim_end_matches("|>")
+            .to_string())
+    }
+
+    /// Greedily decoded text tokens after the prompt, up to the end of text token or
+    /// max_tokens, with the sum and number of their log-probabilities (end of text included
+    /// if reached). Only text tokens and end of text are allowed; blanks and end of text
+    /// cannot come first
+    fn decode_greedy(
+        &self,
+        encoded: &DynValue,
+        mut tokens: Vec<u32>,
+        max_tokens: usize,
+    ) -> Result<(Vec<u32>, f64, usize)> {
+        let prompt = tokens.len();
+        let allowed = self.end_of_text as usize + 1;
+        let (mut logprob_sum, mut scored) = (0.0, 0);
+        let mut cache = Cache::new();
+        while tokens.len() - prompt < max_tokens {
+            let mut logits = self.next_logits(encoded, &tokens, &mut cache)?;
+            logits.truncate(allowed);
+            if tokens.len() == prompt {
+                for token in [Some(self.end_of_text), self.blank].into_iter().flatten() {
+                    if let Some(logit) = logits.get_mut(token as usize) {
+                        *logit = f32::NEG_INFINITY;
+                    }
+                }
+            }
+            let (token, &best) = logits
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .ok_or_else(|| anyhow::anyhow!("Decoder returned no logits"))?;
+            // Log-softmax of the chosen token over the allowed ones
+            let norm: f64 = logits
+                .iter()
+                .map(|&logit| f64::from(logit - best).exp())
+                .sum();
+            logprob_sum -= norm.ln();
+            scored += 1;
+            if token as u32 == self.end_of_text {
+                break;
+            }
+            tokens.push(token as u32);
+        }
+        Ok((tokens.split_off(prompt), logprob_sum, scored))
+    }
+}
+
+/// Output `name` of a model, else its first output
+fn named(model: &OnnxModel, outputs: Vec<DynValue>, name: &str) -> Result<DynValue> {
+    let index = model
+        .outputs()
+        .iter()
+        .position(|output| output == name)
+        .unwrap_or(0);
+    outputs
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| anyhow::anyhow!("Model has no output"))
+}
@@ -0,0 +1,458 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::Regex;
+
+/// Language written when neither the path nor the content identifies one
+pub const UNKNOWN: &str = "unknown";
+
+/// Languages of file extensions (lowercase, without the dot); ambiguous extensions list every
+/// candidate, decided by the content
+const EXTENSIONS: &[(&str, &[&str])] = &[
+    ("py", &["python"]),
+    ("pyw", &["python"]),
+    ("pyi", &["python"]),
+    ("js", &["javascript"]),
+    ("mjs", &["javascript"]),
+    ("cjs", &["javascript"]),
+    ("jsx", &["javascript"]),
+    ("ts", &["typescript"]),
+    ("tsx", &["typescript"]),
+    ("mts", &["typescript"]),
+    ("java", &["java"]),
+    ("c", &["c"]),
+    ("h", &["c", "cpp", "objective-c"]),
+    ("cc", &["cpp"]),
+    ("cpp", &["cpp"]),
+    ("cxx", &["cpp"]),
+    ("c++", &["cpp"]),
+    ("hpp", &["cpp"]),
+    ("hh", &["cpp"]),
+    ("hxx", &["cpp"]),
+    ("ino", &["cpp"]),
+    ("cs", &["csharp"]),
+    ("go", &["go"]),
+    ("rs", &["rust"]),
+    ("rb", &["ruby"]),
+    ("rake", &["ruby"]),
+    ("gemspec", &["ruby"]),
+    ("php", &["php"]),
+    ("phtml", &["php"]),
+    ("sh", &["shell"]),
+    ("bash", &["shell"]),
+    ("zsh", &["shell"]),
+    ("ksh", &["shell"]),
+    ("sql", &["sql"]),
+    ("html", &["html"]),
+    ("htm", &["html"]),
+    ("xhtml", &["html"]),
+    ("css", &["css"]),
+    ("scss", &["scss"]),
+    ("less", &["less"]),
+    ("kt", &["kotlin"]),
+    ("kts", &["kotlin"]),
+    ("swift", &["swift"]),
+    ("m", &["objective-c", "matlab"]),
+    ("mm", &["objective-c"]),
+    ("pl", &["perl"]),
+    ("pm", &["perl"]),
+    ("r", &["r"]),
+    ("scala", &["scala"]),
+    ("sc", &["scala"]),
+    ("hs", &["haskell"]),
+    ("lhs", &["haskell"]),
+    ("lua", &["lua"]),
+    ("md", &["markdown"]),
+    ("markdown", &["markdown"]),
+    ("yml", &["yaml"]),
+    ("yaml", &["yaml"]),
+    ("json", &["json"]),
+    ("toml", &["toml"]),
+    ("xml", &["xml"]),
+    ("mk", &["makefile"]),
+    ("cmake", &["cmake"]),
+    ("dart", &["dart"]),
+    ("ex", &["elixir"]),
+    ("exs", &["elixir"]),
+    ("erl", &["erlang"]),
+    ("clj", &["clojure"]),
+    ("jl", &["julia"]),
+    ("ml", &["ocaml"]),
+    ("mli", &["ocaml"]),
+    ("fs", &["fsharp"]),
+    ("fsx", &["fsharp"]),
+    ("vb", &["visual-basic"]),
+    ("pas", &["pascal"]),
+    ("f", &["fortran"]),
+    ("f90", &["fortran"]),
+    ("f95", &["fortran"]),
+    ("asm", &["assembly"]),
+    ("s", &["assembly"]),
+    ("ps1", &["powershell"]),
+    ("bat", &["batchfile"]),
+    ("cmd", &["batchfile"]),
+    ("tex", &["tex"]),
+    ("vue", &["vue"]),
+    ("svelte", &["svelte"]),
+    ("proto", &["protobuf"]),
+    ("groovy", &["groovy"]),
+    ("gradle", &["groovy"]),
+    ("zig", &["zig"]),
+    ("nim", &["nim"]),
+    ("v", &["verilog"]),
+    ("sv", &["systemverilog"]),
+    ("vhd", &["vhdl"]),
+    ("vhdl", &["vhdl"]),
+];
+
+/// Languages of file names without a telling extension
+const FILENAMES: &[(&str, &str)] = &[
+    ("dockerfile", "dockerfile"),
+    ("containerfile", "dockerfile"),
+    ("makefile", "makefile"),
+    ("gnumakefile", "makefile"),
+    ("cmakelists.txt", "cmake"),
+    ("gemfile", "ruby"),
+    ("rakefile", "ruby"),
+    ("vagrantfile", "ruby"),
+    ("jenkinsfile", "groovy"),
+    ("build.gradle", "groovy"),
+    ("cargo.lock", "toml"),
+];
+
+/// Languages of shebang interpreters
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("ksh", "shell"),
+    ("dash", "shell"),
+    ("node", "javascript"),
+    ("deno", "typescript"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("Rscript", "r"),
+];
+
+/// Content patterns (multi-line regexes) of languages and their weights, each counting up to
+/// MAX_PATTERN_HITS times
+const PATTERNS: &[(&str, &str, f64)] = &[
+    ("python", r"^\s*def \w+\(.*\)\s*(->.*)?:\s*$", 2.0),
+    ("python", r"^\s*(from [\w.]+ )?import [\w., ]+$", 1.0),
+    ("python", r"^\s*class \w+(\(.*\))?:\s*$", 2.0),
+    ("python", r"\bself\.\w+", 1.0),
+    ("python", r"^\s*elif\b", 2.0),
+    ("python", r#"__name__ == ['"]__main__['"]"#, 3.0),
+    ("javascript", r"\bfunction\s*\w*\s*\(", 1.0),
+    ("javascript", r"\b(const|let|var) \w+ = ", 1.0),
+    ("javascript", r"console\.log\(", 2.0),
+    ("javascript", r#"\brequire\(['"]"#, 2.0),
+    ("javascript", r"\bmodule\.exports\b", 3.0),
+    ("javascript", r"\bdocument\.\w+", 1.0),
+    ("javascript", r#"\bimport .* from ['"]"#, 1.0),
+    (
+        "typescript",
+        r"\w\??: (string|number|boolean|any|void|unknown)\b",
+        2.0,
+    ),
+    ("typescript", r"^\s*(export )?interface \w+ \{", 2.0),
+    ("typescript", r"^\s*(export )?type \w+ = ", 2.0),
+    ("typescript", r#"\bimport .* from ['"]"#, 1.0),
+    ("typescript", r"\b(public|private|readonly) \w+\??:", 2.0),
+    ("java", r"^\s*package [\w.]+;", 3.0),
+    ("java", r"^\s*import [\w.*]+;", 2.0),
+    (
+        "java",
+        r"\bpublic (static )?(final )?(class|interface|void)\b",
+        2.0,
+    ),
+    ("java", r"System\.out\.print", 3.0),
+    ("java", r"@Override\b", 2.0),
+    ("c", r"^\s*#include <\w+\.h>", 2.0),
+    ("c", r"\bprintf\(", 1.0),
+    ("c", r"\b(malloc|free|memcpy|strlen)\(", 2.0),
+    (
+        "c",
+        r"^\s*(static )?(int|void|char|unsigned|long) \*?\w+\(.*\)\s*\{?$",
+        1.0,
+    ),
+    ("c", r"\bstruct \w+ \{", 1.0),
+    (
+        "cpp",
+        r"^\s*#include <(iostream|vector|string|map|memory|algorithm)>",
+        3.0,
+    ),
+    ("cpp", r"\bstd::", 3.0),
+    ("cpp", r"^\s*namespace \w+", 2.0),
+    ("cpp", r"\btemplate\s*<", 3.0),
+    ("cpp", r"\bcout\s*<<", 3.0),
+    ("cpp", r"\bnullptr\b", 2.0),
+    ("csharp", r"^\s*using System", 3.0),
+    ("csharp", r"^\s*namespace [\w.]+", 1.0),
+    ("csharp", r"Console\.Write", 3.0),
+    ("csharp", r"\{ get; (private )?(set; )?\}", 3.0),
+    ("csharp", r"\bvar \w+ = new\b", 1.0),
+    ("go", r"^package \w+$", 3.0),
+    ("go", r"\bfunc (\(\w+ \*?\w+\) )?\w+\(", 3.0),
+    ("go", r":=", 1.0),
+    ("go", r"\bfmt\.\w+\(", 3.0),
+    ("go", r"^import \($", 2.0),
+    ("rust", r"\bfn \w+(<.*>)?\(", 2.0),
+    ("rust", r"\blet mut \w+", 3.0),
+    ("rust", r"^\s*use \w+(::\w+)+", 3.0),
+    ("rust", r"^\s*impl(<.*>)? \w+", 2.0),
+    ("rust", r"\bprintln!\(", 3.0),
+    ("rust", r"\bpub (fn|struct|enum|mod)\b", 2.0),
+    ("rust", r"#\[derive\(", 3.0),
+    ("ruby", r"^\s*def \w+[?!]?(\(.*\))?\s*$", 1.0),
+    ("ruby", r"^\s*end\s*$", 1.0),
+    ("ruby", r"\bputs\b", 2.0),
+    ("ruby", r#"^\s*require ['"]"#, 2.0),
+    ("ruby", r"\.each do \|", 3.0),
+    ("ruby", r"\battr_(accessor|reader|writer)\b", 3.0),
+    ("php", r"<\?php", 5.0),
+    ("php", r"\$this->", 3.0),
+    ("php", r"\becho\b", 1.0),
+    ("php", r"\$\w+\s*=", 1.0),
+    ("shell", r"^\s*(if \[|fi$|then$|esac$|done$)", 2.0),
+    ("shell", r"^\s*export \w+=", 2.0),
+    ("shell", r"\$\{\w+\}", 1.0),
+    ("shell", r"^\s*echo ", 1.0),
+    ("shell", r"^\s*(sudo|apt-get|cd|mkdir|rm -\w+) ", 1.0),
+    (
+        "sql",
+        r"(?i)^\s*(select .+ from|insert into|create table|update \w+ set|delete from|alter table)\b",
+        3.0,
+    ),
+    (
+        "sql",
+        r"(?i)\b(where|group by|order by|inner join|left join)\b",
+        1.0,
+    ),
+    ("html", r"(?i)<!doctype html", 5.0),
+    (
+        "html",
+        r"(?i)</?(html|head|body|div|span|p|a|script)\b[^>]*>",
+        1.0,
+    ),
+    (
+        "css",
+        r"^\s*[.#]?[\w-]+(\s*[,>+~ ]\s*[.#]?[\w-]+)*\s*\{\s*$",
+        1.0,
+    ),
+    ("css", r"^\s*[\w-]+\s*:\s*[^;]+;\s*$", 1.0),
+    ("css", r"@media\b", 3.0),
+    ("css", r"\b\d+px\b", 1.0),
+    ("kotlin", r"\bfun \w+\(", 3.0),
+    ("kotlin", r"\bval \w+(: \w+)? = ", 2.0),
+    ("kotlin", r"^\s*package [\w.]+$", 1.0),
+    ("swift", r"\bfunc \w+\(.*\)( -> \w+)? \{", 2.0),
+    ("swift", r"^\s*import (UIKit|Foundation|SwiftUI)", 4.0),
+    ("swift", r"\bguard let\b", 3.0),
+    ("swift", r"\bif let\b", 2.0),
+    ("objective-c", r#"^\s*#import [<"]"#, 3.0),
+    (
+        "objective-c",
+        r"@(interface|implementation|end|property)\b",
+        3.0,
+    ),
+    ("objective-c", r"\bNSString\b", 3.0),
+    ("perl", r"^\s*use strict;", 4.0),
+    ("perl", r"\bmy [$@%]\w+", 3.0),
+    ("perl", r"^\s*sub \w+ \{", 2.0),
+    ("perl", r"=~ [ms]?/", 2.0),
+    ("r", r"\w <- ", 1.0),
+    ("r", r"\blibrary\(\w+\)", 3.0),
+    ("r", r"\bdata\.frame\b", 3.0),
+    ("r", r"\bfunction\(", 1.0),
+    ("matlab", r"^\s*function \[?.*\]? ?= ?\w+\(", 3.0),
+    ("matlab", r"\bdisp\(", 2.0),
+    ("matlab", r"\b(zeros|ones)\(", 1.0),
+    ("matlab", r"^\s*%", 0.5),
+    ("scala", r"^\s*object \w+", 2.0),
+    ("scala", r"\bdef \w+(\[.*\])?\(.*\)\s*:\s*\w+", 3.0),
+    ("scala", r"\bcase class\b", 3.0),
+    ("haskell", r"^\s*module [\w.]+( \(.*\))? where", 4.0),
+    ("haskell", r"^\w+ :: ", 3.0),
+    ("haskell", r"^\s*import qualified\b", 4.0),
+    ("lua", r"\blocal \w+ = ", 2.0),
+    ("lua", r"~=", 2.0),
+    ("lua", r"--\[\[", 3.0),
+    ("lua", r"\bthen$", 0.5),
+    ("markdown", r"^#{1,6} \S", 1.0),
+    ("markdown", r"\[[^\]]+\]\([^)]+\)", 1.0),
+    ("markdown", r"^```", 2.0),
+    ("yaml", r"^[\w-]+:\s*$", 1.0),
+    ("yaml", r"^\s*- [\w-]+: ", 1.0),
+    ("yaml", r"^---$", 1.0),
+    (
+        "dockerfile",
+        r"^(FROM|RUN|CMD|COPY|ENTRYPOINT|WORKDIR|EXPOSE|ENV) ",
+        2.0,
+    ),
+    ("makefile", r"^\.PHONY:", 4.0),
+    ("makefile", r"^[\w./-]+\s*:([^=]|$)", 1.0),
+    ("makefile", r"\$\(\w+\)", 1.0),
+];
+
+/// Times a pattern counts at most, so that one repeated construct does not decide alone
+const MAX_PATTERN_HITS: usize = 3;
+
+/// Annotates code documents with their programming language, from the file name (extension
+/// or well-known names like Dockerfile) and, when it is missing or ambiguous, from the content
+/// (shebang line, then weighted syntax patterns)
+#[fdf_operator(
+    name = "code.language_id",
+    category = "annotator",
+    build = "CodeLanguageIdConfig::build"
+)]
+struct CodeLanguageIdConfig {
+    /// Field holding the code (dot path allowed)
+    #[param(default = "content")]
+    text_col: String,
+    /// Field holding the file path or name; the content alone decides if it is missing
+    #[param(default = "path")]
+    path_col: String,
+    /// Field receiving the language (lowercase, e.g. python, cpp, csharp; "unknown" if
+    /// undetected)
+    #[param(default = "code_lang")]
+    lang_col: String,
+    /// Minimum pattern score for a language detected from the content alone
+    #[param(default = 3.0)]
+    min_score: f64,
+}
+
+impl CodeLanguageIdConfig {
+    fn build(self) -> Result<CodeLanguageId> {
+        let patterns = PATTERNS
+            .iter()
+            .map(|&(language, pattern, weight)| {
+                Ok((language, Regex::new(&format!("(?m){}", pattern))?, weight))
+            })
+            .collect::<Result<_>>()?;
+        Ok(CodeLanguageId {
+            text_col: self.text_col,
+            path_col: self.path_col,
+            lang_col: self.lang_col,
+            min_score: self.min_score,
+            patterns,
+        })
+    }
+}
+
+pub struct CodeLanguageId {
+    text_col: String,
+    path_col: String,
+    lang_col: String,
+    min_score: f64,
+    patterns: Vec<(&'static str, Regex, f64)>,
+}
+
+impl CodeLanguageId {
+    /// Language of a file, from its path (if any) and content
+    pub fn detect(&self, path: Option<&str>, content: &str) -> &'static str {
+        let candidates = path.map(candidates).unwrap_or_default();
+        match candidates {
+            [language] => language,
+            [] => {
+                if let Some(language) = shebang(content) {
+                    return language;
+                }
+                match self.best(content, None) {
+                    Some((language, score)) if score >= self.min_score => language,
+                    _ => UNKNOWN,
+                }
+            }
+            // Ambiguous extension: the best scoring candidate, the first one if none scores
+            _ => self
+                .best(content, Some(candidates))
+                .map_or(candidates[0], |(language, _)| language),
+        }
+    }
+
+    /// Highest scoring language of the content (among `candidates` if set), if any scores
+    fn best(&self, content: &str, candidates: Option<&[&str]>) -> Option<(&'static str, f64)> {
+        let mut scores: Vec<(&'static str, f64)> = Vec::new();
+        for (language, pattern, weight) in &self.patterns {
+            if candidates.is_some_and(|candidates| !candidates.contains(language)) {
+                continue;
+            }
+            let hits = pattern.find_iter(content).take(MAX_PATTERN_HITS).count();
+            if hits == 0 {
+                continue;
+            }
+            match scores.iter_mut().find(|(l, _)| l == language) {
+                Some((_, score)) => *score += weight * hits as f64,
+                None => scores.push((language, weight * hits as f64)),
+            }
+        }
+        // First of equal scores, in pattern order
+        scores.into_iter().fold(
+            None,
+            |best: Option<(&str, f64)>, (language, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((language, score)),
+            },
+        )
+    }
+}
+
+/// Candidate languages of a path, from its file name or extension
+fn candidates(path: &str) -> &'static [&'static str] {
+    let name = path
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(path)
+        .to_lowercase();
+    if let Some((_, language)) = FILENAMES.iter().find(|(file, _)| *file == name) {
+        return std::slice::from_ref(language);
+    }
+    if name.starts_with("dockerfile.") {
+        return &["dockerfile"];
+    }
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => EXTENSIONS
+            .iter()
+            .find(|(ext, _)| *ext == extension)
+            .map_or(&[], |(_, languages)| *languages),
+        _ => &[],
+    }
+}
+
+/// Language of the shebang interpreter on the first line, if any
+fn shebang(content: &str) -> Option<&'static str> {
+    let line = content.strip_prefix("#!")?.lines().next()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    // python3.11, perl5.36
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|(interpreter, _)| *interpreter == program)
+        .map(|&(_, language)| language)
+}
+
+impl Operator for CodeLanguageId {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let content = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let path = sample.get_path(&self.path_col).and_then(Value::as_str);
+        let language = self.detect(path, content);
+        sample.set_path(&self.lang_col, Value::from(language))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.lang_col),
+        )
+    }
+}
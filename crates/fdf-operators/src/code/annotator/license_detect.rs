@@ -0,0 +1,297 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::Regex;
+
+/// License texts and notices, as SPDX identifiers with alternatives of phrases that must all
+/// appear (compared lowercase, on letters and digits only); the first matching entry wins, so
+/// licenses whose text mentions another one come first
+const LICENSE_TEXTS: &[(&str, &[&[&str]])] = &[
+    ("AGPL-3.0", &[&["gnu affero general public license"]]),
+    (
+        "LGPL-2.1",
+        &[&["gnu lesser general public license", "version 2.1"]],
+    ),
+    (
+        "LGPL-3.0",
+        &[&["gnu lesser general public license", "version 3"]],
+    ),
+    ("LGPL-2.0", &[&["gnu library general public license"]]),
+    ("GPL-3.0", &[&["gnu general public license", "version 3"]]),
+    ("GPL-2.0", &[&["gnu general public license", "version 2"]]),
+    (
+        "Apache-2.0",
+        &[
+            &["licensed under the apache license, version 2.0"],
+            &["apache license", "version 2.0, january 2004"],
+        ],
+    ),
+    (
+        "MPL-2.0",
+        &[
+            &["mozilla public license, v. 2.0"],
+            &["mozilla public license version 2.0"],
+        ],
+    ),
+    ("EPL-2.0", &[&["eclipse public license - v 2.0"]]),
+    ("EPL-1.0", &[&["eclipse public license - v 1.0"]]),
+    (
+        "BSD-3-Clause",
+        &[&[
+            "redistribution and use in source and binary forms",
+            "neither the name of",
+        ]],
+    ),
+    (
+        "BSD-2-Clause",
+        &[&[
+            "redistribution and use in source and binary forms",
+            "this list of conditions",
+        ]],
+    ),
+    (
+        "MIT",
+        &[&["permission is hereby granted, free of charge, to any person obtaining a copy"]],
+    ),
+    (
+        "ISC",
+        &[&["permission to use, copy, modify, and/or distribute this software for any purpose"]],
+    ),
+    ("BSL-1.0", &[&["boost software license - version 1.0"]]),
+    (
+        "Unlicense",
+        &[&["this is free and unencumbered software released into the public domain"]],
+    ),
+    ("CC0-1.0", &[&["cc0 1.0 universal"]]),
+    (
+        "Zlib",
+        &[&[
+            "this software is provided 'as-is', without any express or implied warranty",
+            "altered source versions must be plainly marked",
+        ]],
+    ),
+    ("WTFPL", &[&["do what the fuck you want to public license"]]),
+];
+
+/// Phrase of GNU notices allowing later versions
+const OR_LATER: &str = "or (at your option) any later version";
+
+/// License types of SPDX identifiers (prefixes, so that GPL-3.0 covers GPL-3.0-or-later), from
+/// the least to the most restrictive
+const LICENSE_TYPES: &[(&str, &[&str])] = &[
+    ("public-domain", &["Unlicense", "CC0-"]),
+    (
+        "permissive",
+        &[
+            "MIT",
+            "Apache-",
+            "BSD-",
+            "0BSD",
+            "ISC",
+            "BSL-",
+            "Zlib",
+            "WTFPL",
+            "X11",
+            "PostgreSQL",
+            "Python-",
+            "PSF-",
+        ],
+    ),
+    (
+        "weak-copyleft",
+        &["LGPL-", "MPL-", "EPL-", "CDDL-", "EUPL-", "OSL-"],
+    ),
+    ("copyleft", &["GPL-", "AGPL-"]),
+];
+
+/// License type of unknown identifiers, treated as the most restrictive
+const UNKNOWN_TYPE: &str = "unknown";
+
+/// File names (before any extension, uppercase) of license files, scanned in full
+const LICENSE_FILES: &[&str] = &["LICENSE", "LICENCE", "COPYING", "UNLICENSE", "COPYRIGHT"];
+
+/// Annotates code documents with their license: an SPDX-License-Identifier tag, or a license
+/// text or notice recognized in the header (in the whole file for LICENSE / COPYING files)
+#[fdf_operator(
+    name = "code.license_detect",
+    category = "annotator",
+    build = "LicenseDetectConfig::build"
+)]
+struct LicenseDetectConfig {
+    /// Field holding the code (dot path allowed)
+    #[param(default = "content")]
+    text_col: String,
+    /// Field holding the file path, to scan LICENSE / COPYING files in full
+    #[param(default = "path")]
+    path_col: String,
+    /// Field receiving the SPDX license identifier or expression (null if none is found)
+    #[param(default = "license")]
+    license_col: String,
+    /// Field receiving the license type (public-domain, permissive, weak-copyleft, copyleft,
+    /// unknown; null without license), if set
+    type_col: Option<String>,
+    /// Lines scanned at the start of files other than license files
+    #[param(default = 50)]
+    header_lines: usize,
+}
+
+impl LicenseDetectConfig {
+    fn build(self) -> Result<LicenseDetect> {
+        let texts = LICENSE_TEXTS
+            .iter()
+            .map(|&(id, alternatives)| {
+                let alternatives = alternatives
+                    .iter()
+                    .map(|phrases| phrases.iter().map(|phrase| normalize(phrase)).collect())
+                    .collect();
+                (id, alternatives)
+            })
+            .collect();
+        Ok(LicenseDetect {
+            text_col: self.text_col,
+            path_col: self.path_col,
+            license_col: self.license_col,
+            type_col: self.type_col,
+            header_lines: self.header_lines,
+            spdx: Regex::new(r"SPDX-License-Identifier:\s*([A-Za-z0-9.+\-() ]+)")?,
+            texts,
+            or_later: normalize(OR_LATER),
+        })
+    }
+}
+
+pub struct LicenseDetect {
+    text_col: String,
+    path_col: String,
+    license_col: String,
+    type_col: Option<String>,
+    header_lines: usize,
+    spdx: Regex,
+    texts: Vec<(&'static str, Vec<Vec<String>>)>, // Normalized phrases
+    or_later: String,                             // Normalized
+}
+
+impl LicenseDetect {
+    /// SPDX identifier or expression of a file, if one is found
+    pub fn detect(&self, content: &str, path: Option<&str>) -> Option<String> {
+        let scanned = if path.is_some_and(is_license_file) {
+            content
+        } else {
+            let end = content
+                .match_indices('\n')
+                .nth(self.header_lines.saturating_sub(1))
+                .map_or(content.len(), |(i, _)| i);
+            &content[..end]
+        };
+
+        if let Some(captures) = self.spdx.captures(scanned) {
+            let expression = captures[1].trim();
+            if !expression.is_empty() {
+                return Some(expression.to_string());
+            }
+        }
+        let text = normalize(scanned);
+        let (id, _) = self.texts.iter().find(|(_, alternatives)| {
+            alternatives
+                .iter()
+                .any(|phrases| phrases.iter().all(|phrase| text.contains(phrase.as_str())))
+        })?;
+        Some(if id.contains("GPL") {
+            let suffix = if text.contains(&self.or_later) {
+                "-or-later"
+            } else {
+                "-only"
+            };
+            format!("{}{}", id, suffix)
+        } else {
+            id.to_string()
+        })
+    }
+}
+
+/// Type of an SPDX expression: the least restrictive one of OR alternatives, the most
+/// restrictive one of licenses combined with AND
+pub fn license_type(expression: &str) -> &'static str {
+    let rank = |id: &str| {
+        LICENSE_TYPES
+            .iter()
+            .position(|(_, prefixes)| prefixes.iter().any(|prefix| id.starts_with(prefix)))
+            .unwrap_or(LICENSE_TYPES.len())
+    };
+    let mut ids = Vec::new();
+    let mut any_of = false;
+    let mut tokens = expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !token.is_empty());
+    while let Some(token) = tokens.next() {
+        match token {
+            "OR" | "or" => any_of = true,
+            "AND" | "and" => {}
+            "WITH" | "with" => {
+                tokens.next(); // Exception of the previous license
+            }
+            id => ids.push(rank(id)),
+        }
+    }
+    let rank = if any_of {
+        ids.into_iter().min()
+    } else {
+        ids.into_iter().max()
+    };
+    match rank {
+        Some(rank) if rank < LICENSE_TYPES.len() => LICENSE_TYPES[rank].0,
+        _ => UNKNOWN_TYPE,
+    }
+}
+
+/// Whether a path names a license file (LICENSE, LICENSE.md, COPYING.txt, ...)
+fn is_license_file(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let stem = name.split(['.', '-', '_']).next().unwrap_or(name);
+    LICENSE_FILES.contains(&stem.to_uppercase().as_str())
+}
+
+/// Lowercase letters and digits of a text, other characters collapsed to single spaces, so
+/// that comment markers, punctuation and line wrapping do not matter
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut space = true;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            normalized.extend(c.to_lowercase());
+            space = false;
+        } else if !space {
+            normalized.push(' ');
+            space = true;
+        }
+    }
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
+
+impl Operator for LicenseDetect {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let content = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let path = sample.get_path(&self.path_col).and_then(Value::as_str);
+        let license = self.detect(content, path);
+        if let Some(type_col) = &self.type_col {
+            let license_type = license
+                .as_deref()
+                .map_or(Value::Null, |license| Value::from(license_type(license)));
+            sample.set_path(type_col, license_type)?;
+        }
+        sample.set_path(&self.license_col, license.map_or(Value::Null, Value::from))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(&self.license_col);
+        Some(match &self.type_col {
+            Some(type_col) => columns.produces(type_col),
+            None => columns,
+        })
+    }
+}
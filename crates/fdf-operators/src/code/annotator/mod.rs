@@ -0,0 +1,9 @@
+pub mod language_id;
+pub mod license_detect;
+
+use fdf_sdk::OperatorRegistry;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    language_id::register(registry);
+    license_detect::register(registry);
+}
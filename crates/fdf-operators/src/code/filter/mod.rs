@@ -0,0 +1,7 @@
+pub mod quality;
+
+use fdf_sdk::OperatorRegistry;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    quality::register(registry);
+}
@@ -0,0 +1,298 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Phrases marking generated files, looked for (case-insensitively) in the first lines
+const AUTOGENERATED_PHRASES: &[&str] = &[
+    "auto-generated",
+    "autogenerated",
+    "automatically generated",
+    "generated automatically",
+    "this file is generated",
+    "code generated",
+    "generated by",
+    "do not edit",
+];
+
+/// Lines looked at for generated-file phrases
+const HEADER_LINES: usize = 5;
+
+/// Encoded data blobs (BigCode preprocessing): base64, hexadecimal byte lists, unicode escapes
+const ENCODED_DATA_PATTERNS: &[&str] = &[
+    r"[a-zA-Z0-9+/\n=]{64,}",
+    r"(?:\b(?:0x|\\x)?[0-9a-fA-F]{2}(?:,|\b\s*)){8,}",
+    r"(?:\\u[0-9a-fA-F]{4}){8,}",
+];
+
+/// Lines at least this long with (almost) no whitespace mark minified code
+const MINIFIED_LINE_LENGTH: usize = 500;
+
+/// Thresholds overridden for one language, unset ones keeping the operator's
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Thresholds {
+    pub max_line_length: Option<usize>,
+    pub max_mean_line_length: Option<f64>,
+    pub min_alphanum_frac: Option<f64>,
+    pub max_encoded_data_frac: Option<f64>,
+    pub max_encoded_data_chars: Option<usize>,
+    pub drop_autogenerated: Option<bool>,
+}
+
+/// StarCoder-style code quality filter: drops files with overlong lines, little alphanumeric
+/// content, generated or minified code, or mostly encoded data (base64, hex, unicode escapes),
+/// with thresholds configurable per language
+#[fdf_operator(
+    name = "code.quality_filter",
+    category = "filter",
+    build = "CodeQualityConfig::build"
+)]
+struct CodeQualityConfig {
+    /// Field holding the code (dot path allowed)
+    #[param(default = "content")]
+    text_col: String,
+    /// Field holding the language, as written by code.language_id; defaults apply to files
+    /// without it
+    #[param(default = "code_lang")]
+    lang_col: String,
+    /// Field holding the file path, for .min.js / .min.css files
+    #[param(default = "path")]
+    path_col: String,
+    /// Drop files with a longer line (in characters)
+    #[param(default = 1000)]
+    max_line_length: usize,
+    /// Drop files with a higher mean line length
+    #[param(default = 100.0)]
+    max_mean_line_length: f64,
+    /// Drop files with a lower fraction of alphanumeric characters
+    #[param(default = 0.25)]
+    min_alphanum_frac: f64,
+    /// Drop generated files ("generated by", "do not edit" in the first lines) and minified
+    /// code
+    #[param(default = true)]
+    drop_autogenerated: bool,
+    /// Drop files with a higher fraction of characters in encoded data blobs
+    #[param(default = 0.5)]
+    max_encoded_data_frac: f64,
+    /// Drop files with a longer encoded data blob
+    #[param(default = 1024)]
+    max_encoded_data_chars: usize,
+    /// Thresholds of single languages, e.g. {html: {max_mean_line_length: 200}, json:
+    /// {min_alphanum_frac: 0.1}}
+    languages: Option<BTreeMap<String, Thresholds>>,
+    /// Only write the statistics (<prefix><stat>) and the failed rule (<prefix>rejected_by,
+    /// null if passed), keeping every file
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the fields written with annotate
+    #[param(default = "code_")]
+    prefix: String,
+}
+
+impl CodeQualityConfig {
+    fn build(self) -> Result<CodeQualityFilter> {
+        let encoded_data = ENCODED_DATA_PATTERNS
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<std::result::Result<_, _>>()?;
+        let defaults = Thresholds {
+            max_line_length: Some(self.max_line_length),
+            max_mean_line_length: Some(self.max_mean_line_length),
+            min_alphanum_frac: Some(self.min_alphanum_frac),
+            max_encoded_data_frac: Some(self.max_encoded_data_frac),
+            max_encoded_data_chars: Some(self.max_encoded_data_chars),
+            drop_autogenerated: Some(self.drop_autogenerated),
+        };
+        let languages = self
+            .languages
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(language, thresholds)| (language.to_lowercase(), thresholds))
+            .collect();
+        Ok(CodeQualityFilter {
+            text_col: self.text_col,
+            lang_col: self.lang_col,
+            path_col: self.path_col,
+            defaults,
+            languages,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            encoded_data,
+        })
+    }
+}
+
+pub struct CodeQualityFilter {
+    text_col: String,
+    lang_col: String,
+    path_col: String,
+    defaults: Thresholds, // Every threshold set
+    languages: BTreeMap<String, Thresholds>,
+    annotate: bool,
+    prefix: String,
+    encoded_data: Vec<Regex>,
+}
+
+/// Statistics of a code file
+#[derive(Debug, Clone, Default)]
+pub struct CodeStats {
+    pub max_line_length: usize,
+    pub mean_line_length: f64,
+    pub alphanum_frac: f64,
+    pub autogenerated: bool,
+    pub encoded_data_frac: f64,
+    pub encoded_data_chars: usize, // Longest blob
+}
+
+impl CodeQualityFilter {
+    pub fn stats(&self, content: &str, path: Option<&str>) -> CodeStats {
+        let lengths: Vec<usize> = content.lines().map(|line| line.chars().count()).collect();
+        let chars = content.chars().count();
+        let alphanum = content.chars().filter(|c| c.is_alphanumeric()).count();
+
+        let mut encoded = vec![false; content.len()];
+        let mut longest_blob = 0;
+        for pattern in &self.encoded_data {
+            for m in pattern.find_iter(content) {
+                longest_blob = longest_blob.max(m.len());
+                encoded[m.range()].iter_mut().for_each(|byte| *byte = true);
+            }
+        }
+        let encoded_bytes = encoded.iter().filter(|&&byte| byte).count();
+
+        CodeStats {
+            max_line_length: lengths.iter().copied().max().unwrap_or(0),
+            mean_line_length: if lengths.is_empty() {
+                0.0
+            } else {
+                lengths.iter().sum::<usize>() as f64 / lengths.len() as f64
+            },
+            alphanum_frac: if chars == 0 {
+                0.0
+            } else {
+                alphanum as f64 / chars as f64
+            },
+            autogenerated: is_autogenerated(content) || is_minified(content, path),
+            encoded_data_frac: if content.is_empty() {
+                0.0
+            } else {
+                encoded_bytes as f64 / content.len() as f64
+            },
+            encoded_data_chars: longest_blob,
+        }
+    }
+
+    /// Threshold of a language, falling back to the operator's
+    fn threshold<T>(&self, language: Option<&str>, get: impl Fn(&Thresholds) -> Option<T>) -> T {
+        language
+            .and_then(|language| self.languages.get(language))
+            .and_then(&get)
+            .or_else(|| get(&self.defaults))
+            .expect("defaults set every threshold")
+    }
+
+    /// Rule failed by a file, if any
+    pub fn reject(&self, stats: &CodeStats, language: Option<&str>) -> Option<&'static str> {
+        if stats.max_line_length > self.threshold(language, |t| t.max_line_length) {
+            return Some("max_line_length");
+        }
+        if stats.mean_line_length > self.threshold(language, |t| t.max_mean_line_length) {
+            return Some("mean_line_length");
+        }
+        if stats.alphanum_frac < self.threshold(language, |t| t.min_alphanum_frac) {
+            return Some("alphanum_frac");
+        }
+        if stats.autogenerated && self.threshold(language, |t| t.drop_autogenerated) {
+            return Some("autogenerated");
+        }
+        if stats.encoded_data_frac > self.threshold(language, |t| t.max_encoded_data_frac)
+            || stats.encoded_data_chars > self.threshold(language, |t| t.max_encoded_data_chars)
+        {
+            return Some("encoded_data");
+        }
+        None
+    }
+}
+
+/// Whether the first lines of a file say it is generated
+fn is_autogenerated(content: &str) -> bool {
+    content.lines().take(HEADER_LINES).any(|line| {
+        let line = line.to_lowercase();
+        AUTOGENERATED_PHRASES
+            .iter()
+            .any(|phrase| line.contains(phrase))
+    })
+}
+
+/// Whether a file is minified: named .min.js / .min.css, or with a long line almost without
+/// whitespace
+fn is_minified(content: &str, path: Option<&str>) -> bool {
+    if path.is_some_and(|path| {
+        let path = path.to_lowercase();
+        path.ends_with(".min.js") || path.ends_with(".min.css")
+    }) {
+        return true;
+    }
+    content.lines().any(|line| {
+        let length = line.chars().count();
+        length >= MINIFIED_LINE_LENGTH
+            && line.chars().filter(|c| c.is_whitespace()).count() * 50 < length
+    })
+}
+
+impl Operator for CodeQualityFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let content = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let path = sample.get_path(&self.path_col).and_then(Value::as_str);
+        let language = sample
+            .get_path(&self.lang_col)
+            .and_then(Value::as_str)
+            .map(str::to_lowercase);
+        let stats = self.stats(content, path);
+        let rejected = self.reject(&stats, language.as_deref());
+
+        if !self.annotate {
+            return Ok(match rejected {
+                Some(rule) => {
+                    ctx.metrics().increment(&format!("rejected.{}", rule), 1);
+                    None
+                }
+                None => Some(sample),
+            });
+        }
+        for (stat, value) in [
+            ("max_line_length", Value::from(stats.max_line_length)),
+            ("mean_line_length", Value::from(stats.mean_line_length)),
+            ("alphanum_frac", Value::from(stats.alphanum_frac)),
+            ("autogenerated", Value::Bool(stats.autogenerated)),
+            ("encoded_data_frac", Value::from(stats.encoded_data_frac)),
+            ("encoded_data_chars", Value::from(stats.encoded_data_chars)),
+            ("rejected_by", rejected.map_or(Value::Null, Value::from)),
+        ] {
+            sample.set_path(&format!("{}{}", self.prefix, stat), value)?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        if self.annotate {
+            for stat in [
+                "max_line_length",
+                "mean_line_length",
+                "alphanum_frac",
+                "autogenerated",
+                "encoded_data_frac",
+                "encoded_data_chars",
+                "rejected_by",
+            ] {
+                columns = columns.produces(format!("{}{}", self.prefix, stat));
+            }
+        }
+        Some(columns)
+    }
+}
@@ -0,0 +1,9 @@
+pub mod annotator;
+pub mod filter;
+
+use fdf_sdk::OperatorRegistry;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    filter::register(registry);
+    annotator::register(registry);
+}
@@ -1,22 +1,22 @@
-use fdf_sdk::{Operator, Result, Sample};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
 
+/// Adds a random UUID v4 to each sample
+#[fdf_operator(name = "common.add_id", category = "annotator", alias = "add_id")]
 pub struct AddIdAnnotator {
+    /// Field to write the id to (dot path allowed)
+    #[param(default = "id")]
     id_col: String,
 }
 
 impl Operator for AddIdAnnotator {
-    fn process(&self, mut sample: Sample) -> Result<Option<Sample>> {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
         // Generate UUID4
         let id = uuid::Uuid::new_v4().to_string();
-        sample.set_str(&self.id_col, id);
+        sample.set_path(&self.id_col, Value::String(id))?;
         Ok(Some(sample)) // Keep the sample
     }
-}
-
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register("add_id", |config: &serde_yaml::Value| {
-        let id_col = config["id_col"].as_str().unwrap_or("id").to_string();
 
-        Ok(Box::new(AddIdAnnotator { id_col }))
-    });
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().produces(&self.id_col))
+    }
 }
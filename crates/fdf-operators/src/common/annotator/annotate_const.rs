@@ -0,0 +1,26 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Sets a field to the same value on every sample (e.g. a source or license tag)
+#[fdf_operator(
+    name = "common.annotate_const",
+    category = "annotator",
+    alias = "annotate_const",
+    description = "Sets a field to a constant value on every sample"
+)]
+pub struct AnnotateConst {
+    /// Field to set (dot path allowed)
+    col: String,
+    /// Value written to the field (string, number, bool, list or map)
+    value: Value,
+}
+
+impl Operator for AnnotateConst {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        sample.set_path(&self.col, self.value.clone())?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().produces(&self.col))
+    }
+}
@@ -0,0 +1,52 @@
+use crate::common::expr::Expr;
+use fdf_sdk::{ColumnSpec, Context, Operator, OperatorCategory, ParamSpec, Result, Sample};
+use serde::Deserialize;
+
+/// Writes the result of an expression to a field, e.g. `tokens / words`
+pub struct ExprAnnotator {
+    expr: Expr,
+    output_col: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExprAnnotatorConfig {
+    expr: String,
+    output_col: String,
+}
+
+impl Operator for ExprAnnotator {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let value = self.expr.eval(&sample)?;
+        sample.set_path(&self.output_col, value)?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().produces(&self.output_col))
+    }
+}
+
+pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
+    registry
+        .register_typed("common.expr_annotator", |config: ExprAnnotatorConfig| {
+            let expr = Expr::parse(&config.expr)
+                .map_err(|e| anyhow::anyhow!("Invalid expr '{}': {}", config.expr, e))?;
+            Ok(Box::new(ExprAnnotator {
+                expr,
+                output_col: config.output_col,
+            }))
+        })
+        .description("Adds a field computed by an expression over the sample's fields")
+        .category(OperatorCategory::Annotator)
+        .param(ParamSpec::required(
+            "expr",
+            "string",
+            "Expression, e.g. \"tokens / words\"",
+        ))
+        .param(ParamSpec::required(
+            "output_col",
+            "string",
+            "Field to write the result to (dot path allowed)",
+        ));
+}
@@ -1,7 +1,11 @@
 mod add_id;
+mod annotate_const;
+mod expr_annotator;
 
 use fdf_sdk::OperatorRegistry;
 
 pub fn register(registry: &mut OperatorRegistry) {
     add_id::register(registry);
+    annotate_const::register(registry);
+    expr_annotator::register(registry);
 }
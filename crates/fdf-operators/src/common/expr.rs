@@ -0,0 +1,691 @@
+//! Small expression language used by common.expr_filter and common.expr_annotator
+//!
+//! An expression combines sample fields (dot paths, e.g. `meta.score`), literals (`100`, `0.8`,
+//! `'en'`, `true`, `null`), arithmetic (`+ - * / %`), comparisons (`== != < <= > >=`),
+//! boolean logic (`&& || !`) and function calls, e.g. `len(text) > 100 && score >= 0.8`.
+//!
+//! Missing fields are null. Arithmetic with null gives null, ordered comparisons with null are
+//! false, and `/` by zero gives null. In a boolean context null, false, 0 and "" are false.
+
+use fdf_sdk::{Result, Sample, Value};
+
+/// A parsed expression
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+const BINARY_OPS: [(&str, BinaryOp); 13] = [
+    ("||", BinaryOp::Or),
+    ("&&", BinaryOp::And),
+    ("==", BinaryOp::Eq),
+    ("!=", BinaryOp::Ne),
+    ("<", BinaryOp::Lt),
+    ("<=", BinaryOp::Le),
+    (">", BinaryOp::Gt),
+    (">=", BinaryOp::Ge),
+    ("+", BinaryOp::Add),
+    ("-", BinaryOp::Sub),
+    ("*", BinaryOp::Mul),
+    ("/", BinaryOp::Div),
+    ("%", BinaryOp::Rem),
+];
+
+impl BinaryOp {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        BINARY_OPS
+            .iter()
+            .find(|(s, _)| *s == symbol)
+            .map(|(_, op)| *op)
+    }
+
+    fn symbol(&self) -> &'static str {
+        BINARY_OPS
+            .iter()
+            .find(|(_, op)| op == self)
+            .map_or("?", |(s, _)| s)
+    }
+
+    /// Binding strength; higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne => 3,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => 4,
+            Self::Add | Self::Sub => 5,
+            Self::Mul | Self::Div | Self::Rem => 6,
+        }
+    }
+}
+
+/// Built-in functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Len,        // Characters of a string, elements of an array or object
+    Lower,      // Lowercase string
+    Upper,      // Uppercase string
+    Trim,       // String without surrounding whitespace
+    Contains,   // Substring of a string, or element of an array
+    StartsWith, // String prefix check
+    EndsWith,   // String suffix check
+    WordCount,  // Whitespace-separated words of a string
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Min,      // Smallest of one or more numbers
+    Max,      // Largest of one or more numbers
+    IsNull,   // Whether a value is null / missing
+    Coalesce, // First non-null argument
+}
+
+const FUNCTIONS: [(&str, Function); 16] = [
+    ("len", Function::Len),
+    ("lower", Function::Lower),
+    ("upper", Function::Upper),
+    ("trim", Function::Trim),
+    ("contains", Function::Contains),
+    ("starts_with", Function::StartsWith),
+    ("ends_with", Function::EndsWith),
+    ("word_count", Function::WordCount),
+    ("abs", Function::Abs),
+    ("round", Function::Round),
+    ("floor", Function::Floor),
+    ("ceil", Function::Ceil),
+    ("min", Function::Min),
+    ("max", Function::Max),
+    ("is_null", Function::IsNull),
+    ("coalesce", Function::Coalesce),
+];
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        FUNCTIONS.iter().find(|(n, _)| *n == name).map(|(_, f)| *f)
+    }
+
+    fn name(&self) -> &'static str {
+        FUNCTIONS
+            .iter()
+            .find(|(_, f)| f == self)
+            .map_or("?", |(n, _)| n)
+    }
+
+    /// Whether `count` arguments are accepted
+    fn accepts(&self, count: usize) -> bool {
+        match self {
+            Self::Contains | Self::StartsWith | Self::EndsWith => count == 2,
+            Self::Min | Self::Max | Self::Coalesce => count >= 1,
+            _ => count == 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(Value),
+    Ident(String),
+    Symbol(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const SYMBOLS: [&str; 16] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")",
+];
+
+/// Split an expression into (position, token) pairs
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = match text.parse::<i64>() {
+                Ok(n) => Value::from(n),
+                Err(_) => text
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid number '{}' at position {}", text, start)
+                    })?,
+            };
+            tokens.push((start, Token::Literal(value)));
+        } else if c == '\'' || c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "Unterminated string starting at position {}",
+                            start
+                        ))
+                    }
+                    Some(&q) if q == c => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&other) => {
+                        text.push(other);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((start, Token::Literal(Value::String(text))));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let token = match word.as_str() {
+                "true" => Token::Literal(Value::Bool(true)),
+                "false" => Token::Literal(Value::Bool(false)),
+                "null" => Token::Literal(Value::Null),
+                _ => Token::Ident(word),
+            };
+            tokens.push((start, token));
+        } else if c == ',' {
+            i += 1;
+            tokens.push((start, Token::Comma));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(**s))
+                .ok_or_else(|| anyhow::anyhow!("Unexpected '{}' at position {}", c, start))?;
+            i += symbol.chars().count();
+            tokens.push((
+                start,
+                match *symbol {
+                    "(" => Token::LParen,
+                    ")" => Token::RParen,
+                    other => Token::Symbol(other),
+                },
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Precedence-climbing parser over the token list
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize, // Position reported for errors at the end of the input
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<()> {
+        if self.peek() == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Expected {} at position {}",
+                what,
+                self.position()
+            ))
+        }
+    }
+
+    fn parse_expr(&mut self, min_precedence: u8) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(Token::Symbol(symbol)) = self.peek() {
+            let op = match BinaryOp::from_symbol(symbol) {
+                Some(op) if op.precedence() >= min_precedence => op,
+                _ => break,
+            };
+            self.pos += 1;
+            let rhs = self.parse_expr(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some(Token::Symbol("!")) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Symbol("-")) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let position = self.position();
+        match self.tokens.get(self.pos).map(|(_, t)| t.clone()) {
+            Some(Token::Literal(value)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(value))
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Expr::Field(name));
+                }
+                let function = Function::from_name(&name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown function '{}' at position {}", name, position)
+                })?;
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.parse_expr(1)?);
+                        if self.peek() == Some(&Token::Comma) {
+                            self.pos += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.expect(Token::RParen, "')'")?;
+                if !function.accepts(args.len()) {
+                    return Err(anyhow::anyhow!(
+                        "Wrong number of arguments ({}) for '{}' at position {}",
+                        args.len(),
+                        name,
+                        position
+                    ));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.parse_expr(1)?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            _ => Err(anyhow::anyhow!(
+                "Expected a value, field or '(' at position {}",
+                position
+            )),
+        }
+    }
+}
+
+impl Expr {
+    /// Parse an expression, reporting syntax errors with their position
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            end: source.chars().count(),
+        };
+        let expr = parser.parse_expr(1)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(anyhow::anyhow!(
+                "Unexpected input at position {}",
+                parser.position()
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluate the expression against a sample
+    pub fn eval(&self, sample: &Sample) -> Result<Value> {
+        match self {
+            Self::Literal(value) => Ok(value.clone()),
+            Self::Field(path) => Ok(sample.get_path(path).cloned().unwrap_or(Value::Null)),
+            Self::Not(inner) => Ok(Value::Bool(!truthy(&inner.eval(sample)?))),
+            Self::Neg(inner) => match inner.eval(sample)? {
+                Value::Null => Ok(Value::Null),
+                Value::Number(n) => Ok(match n.as_i64().and_then(i64::checked_neg) {
+                    Some(i) => Value::from(i),
+                    None => float(-n.as_f64().unwrap_or_default()),
+                }),
+                other => Err(anyhow::anyhow!("Cannot negate {}", type_name(&other))),
+            },
+            Self::Binary(BinaryOp::And, lhs, rhs) => Ok(Value::Bool(
+                truthy(&lhs.eval(sample)?) && truthy(&rhs.eval(sample)?),
+            )),
+            Self::Binary(BinaryOp::Or, lhs, rhs) => Ok(Value::Bool(
+                truthy(&lhs.eval(sample)?) || truthy(&rhs.eval(sample)?),
+            )),
+            Self::Binary(op, lhs, rhs) => binary(*op, lhs.eval(sample)?, rhs.eval(sample)?),
+            Self::Call(function, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.eval(sample))
+                    .collect::<Result<Vec<_>>>()?;
+                call(*function, values)
+            }
+        }
+    }
+}
+
+/// Boolean value of a result: null, false, 0, "" and empty arrays are false
+pub fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Float result; non-finite values (e.g. division by zero) become null
+fn float(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value> {
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne => {
+            let equal = match (lhs.as_f64(), rhs.as_f64()) {
+                (Some(a), Some(b)) => a == b, // 1 == 1.0
+                _ => lhs == rhs,
+            };
+            Ok(Value::Bool(equal == (op == BinaryOp::Eq)))
+        }
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&lhs, &rhs) {
+                (Value::Null, _) | (_, Value::Null) => return Ok(Value::Bool(false)),
+                (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot compare {} with {}",
+                        type_name(&lhs),
+                        type_name(&rhs)
+                    ))
+                }
+            };
+            Ok(Value::Bool(ordering.is_some_and(|o| match op {
+                BinaryOp::Lt => o.is_lt(),
+                BinaryOp::Le => o.is_le(),
+                BinaryOp::Gt => o.is_gt(),
+                _ => o.is_ge(),
+            })))
+        }
+        _ => arithmetic(op, lhs, rhs),
+    }
+}
+
+fn arithmetic(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value> {
+    match (&lhs, &rhs) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::String(a), Value::String(b)) if op == BinaryOp::Add => {
+            Ok(Value::String(format!("{}{}", a, b)))
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            // Integer arithmetic when both sides are integers and the result fits
+            if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                let result = match op {
+                    BinaryOp::Add => a.checked_add(b),
+                    BinaryOp::Sub => a.checked_sub(b),
+                    BinaryOp::Mul => a.checked_mul(b),
+                    BinaryOp::Rem if b == 0 => return Ok(Value::Null),
+                    BinaryOp::Rem => a.checked_rem(b),
+                    _ => None,
+                };
+                if let Some(result) = result {
+                    return Ok(Value::from(result));
+                }
+            }
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+            Ok(float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                _ => a % b,
+            }))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Cannot apply '{}' to {} and {}",
+            op.symbol(),
+            type_name(&lhs),
+            type_name(&rhs)
+        )),
+    }
+}
+
+fn call(function: Function, args: Vec<Value>) -> Result<Value> {
+    let arg = &args[0];
+    let wrong_type = || {
+        anyhow::anyhow!(
+            "{}() does not accept a {} argument",
+            function.name(),
+            type_name(arg)
+        )
+    };
+
+    match function {
+        Function::IsNull => Ok(Value::Bool(arg.is_null())),
+        Function::Coalesce => Ok(args.into_iter().find(|v| !v.is_null()).unwrap_or_default()),
+        Function::Min | Function::Max => {
+            let mut best: Option<Value> = None;
+            for value in args {
+                if value.is_null() {
+                    continue;
+                }
+                let f = value.as_f64().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}() does not accept a {} argument",
+                        function.name(),
+                        type_name(&value)
+                    )
+                })?;
+                let better = best.as_ref().is_none_or(|b| {
+                    let current = b.as_f64().unwrap_or_default();
+                    if function == Function::Min {
+                        f < current
+                    } else {
+                        f > current
+                    }
+                });
+                if better {
+                    best = Some(value);
+                }
+            }
+            Ok(best.unwrap_or_default())
+        }
+        _ if arg.is_null() => Ok(Value::Null),
+        Function::Len => match arg {
+            Value::String(s) => Ok(Value::from(s.chars().count())),
+            Value::Array(items) => Ok(Value::from(items.len())),
+            Value::Object(map) => Ok(Value::from(map.len())),
+            _ => Err(wrong_type()),
+        },
+        Function::Lower | Function::Upper | Function::Trim | Function::WordCount => {
+            let s = arg.as_str().ok_or_else(wrong_type)?;
+            Ok(match function {
+                Function::Lower => Value::String(s.to_lowercase()),
+                Function::Upper => Value::String(s.to_uppercase()),
+                Function::Trim => Value::String(s.trim().to_string()),
+                _ => Value::from(s.split_whitespace().count()),
+            })
+        }
+        Function::Contains => match (arg, &args[1]) {
+            (Value::String(s), Value::String(sub)) => Ok(Value::Bool(s.contains(sub.as_str()))),
+            (Value::Array(items), needle) => Ok(Value::Bool(items.contains(needle))),
+            _ => Err(wrong_type()),
+        },
+        Function::StartsWith | Function::EndsWith => match (arg, &args[1]) {
+            (Value::String(s), Value::String(affix)) => {
+                Ok(Value::Bool(if function == Function::StartsWith {
+                    s.starts_with(affix.as_str())
+                } else {
+                    s.ends_with(affix.as_str())
+                }))
+            }
+            _ => Err(wrong_type()),
+        },
+        Function::Abs | Function::Round | Function::Floor | Function::Ceil => {
+            if let (Function::Abs, Some(i)) = (function, arg.as_i64()) {
+                if let Some(abs) = i.checked_abs() {
+                    return Ok(Value::from(abs));
+                }
+            }
+            let f = arg.as_f64().ok_or_else(wrong_type)?;
+            let result = match function {
+                Function::Abs => return Ok(float(f.abs())),
+                Function::Round => f.round(),
+                Function::Floor => f.floor(),
+                _ => f.ceil(),
+            };
+            // Whole numbers are returned as integers when they fit
+            if result.abs() < i64::MAX as f64 {
+                Ok(Value::from(result as i64))
+            } else {
+                Ok(float(result))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fdf_sdk::testing::sample;
+    use serde_json::json;
+
+    fn eval(source: &str, value: Value) -> Value {
+        Expr::parse(source).unwrap().eval(&sample(value)).unwrap()
+    }
+
+    #[test]
+    fn precedence_and_grouping() {
+        assert_eq!(eval("1 + 2 * 3", json!({})), json!(7));
+        assert_eq!(eval("(1 + 2) * 3", json!({})), json!(9));
+        assert_eq!(eval("10 - 4 - 3", json!({})), json!(3));
+        assert_eq!(eval("-2 * 3 + 7 % 4", json!({})), json!(-3));
+        assert_eq!(eval("1 < 2 == 2 < 3", json!({})), json!(true));
+        assert_eq!(eval("false || true && !false", json!({})), json!(true));
+    }
+
+    #[test]
+    fn fields_literals_and_functions() {
+        let doc = json!({"text": "  Hello world  ", "meta": {"score": 0.9, "lang": "en"}});
+        assert_eq!(eval("word_count(text)", doc.clone()), json!(2));
+        assert_eq!(eval("lower(trim(text))", doc.clone()), json!("hello world"));
+        assert_eq!(
+            eval("meta.score >= 0.8 && meta.lang == 'en'", doc.clone()),
+            json!(true)
+        );
+        assert_eq!(eval("contains(text, \"world\")", doc.clone()), json!(true));
+        assert_eq!(eval("max(1, meta.score, 3.5)", doc.clone()), json!(3.5));
+        assert_eq!(eval("coalesce(meta.missing, 'x')", doc), json!("x"));
+        assert_eq!(eval("round(2.5) + floor(-0.5)", json!({})), json!(2));
+    }
+
+    #[test]
+    fn null_semantics() {
+        assert_eq!(eval("missing + 1", json!({})), Value::Null);
+        assert_eq!(eval("missing < 1", json!({})), json!(false));
+        assert_eq!(eval("missing >= 1", json!({})), json!(false));
+        assert_eq!(eval("missing == null", json!({})), json!(true));
+        assert_eq!(eval("1 / 0", json!({})), Value::Null);
+        assert_eq!(eval("5 % 0", json!({})), Value::Null);
+        assert_eq!(eval("is_null(a.b.c)", json!({"a": 1})), json!(true));
+        assert_eq!(eval("1 == 1.0", json!({})), json!(true));
+    }
+
+    #[test]
+    fn truthiness() {
+        for falsy in [json!(null), json!(false), json!(0), json!(""), json!([])] {
+            assert!(!truthy(&falsy), "{} is truthy", falsy);
+        }
+        for true_value in [json!(true), json!(-1), json!("0"), json!([0]), json!({})] {
+            assert!(truthy(&true_value), "{} is falsy", true_value);
+        }
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for source in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 2",
+            "len(",
+            "unknown(1)",
+            "'unterminated",
+            "a === b",
+        ] {
+            assert!(Expr::parse(source).is_err(), "{:?} parsed", source);
+        }
+    }
+
+    #[test]
+    fn type_errors() {
+        let expr = Expr::parse("text > 1").unwrap();
+        assert!(expr.eval(&sample(json!({"text": "a"}))).is_err());
+        let expr = Expr::parse("-text").unwrap();
+        assert!(expr.eval(&sample(json!({"text": "a"}))).is_err());
+        assert_eq!(eval("'a' + 'b'", json!({})), json!("ab"));
+    }
+}
@@ -0,0 +1,265 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Drops documents whose URL is listed in a UT1-style blocklist (one directory per category
+/// holding `domains` and/or `urls` files), for the configured categories
+#[fdf_operator(
+    name = "common.domain_filter",
+    category = "filter",
+    build = "DomainFilterConfig::build"
+)]
+struct DomainFilterConfig {
+    /// Field holding the document URL (dot path allowed)
+    #[param(default = "url")]
+    url_col: String,
+    /// Blocklist root directory, e.g. an extracted UT1 blacklists archive
+    blocklist_dir: String,
+    /// Categories dropped (subdirectory names, e.g. adult, gambling, malware)
+    categories: Vec<String>,
+    /// A listed domain also blocks its subdomains
+    #[param(default = true)]
+    match_subdomains: bool,
+    /// Also read the categories' `urls` files (URL prefixes without scheme)
+    #[param(default = true)]
+    use_urls: bool,
+}
+
+impl DomainFilterConfig {
+    fn build(self) -> Result<DomainFilter> {
+        if self.categories.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{}: categories must not be empty",
+                Self::NAME
+            ));
+        }
+        Ok(DomainFilter {
+            url_col: self.url_col,
+            blocklist_dir: self.blocklist_dir,
+            categories: self.categories,
+            match_subdomains: self.match_subdomains,
+            use_urls: self.use_urls,
+            blocklist: None,
+        })
+    }
+}
+
+pub struct DomainFilter {
+    url_col: String,
+    blocklist_dir: String,
+    categories: Vec<String>,
+    match_subdomains: bool,
+    use_urls: bool,
+    blocklist: Option<Arc<Blocklist>>, // Loaded in open, shared through the context
+}
+
+impl Operator for DomainFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let mut categories = self.categories.clone();
+        categories.sort();
+        categories.dedup();
+        let key = format!(
+            "ut1:{}:{}:{}",
+            self.blocklist_dir,
+            categories.join(","),
+            self.use_urls
+        );
+        let blocklist = ctx
+            .resources()
+            .get_or_load(&key, || {
+                Blocklist::load(Path::new(&self.blocklist_dir), &categories, self.use_urls)
+            })
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", DomainFilterConfig::NAME, e)))?;
+        self.blocklist = Some(blocklist);
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let url = sample
+            .get_path(&self.url_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing url field: {}", self.url_col))?;
+        let blocklist = self
+            .blocklist
+            .as_ref()
+            .expect("blocklist is loaded in open");
+
+        match blocklist.category(url, self.match_subdomains) {
+            Some(category) => {
+                ctx.metrics().increment(&format!("blocked.{}", category), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.url_col))
+    }
+}
+
+/// Domains (as a trie of reversed labels) and URL prefixes of the blocked categories
+struct Blocklist {
+    categories: Vec<String>,
+    domains: DomainTrie,
+    urls: HashMap<String, u16>, // URL without scheme and "www." -> category
+}
+
+impl Blocklist {
+    fn load(dir: &Path, categories: &[String], use_urls: bool) -> Result<Self> {
+        let mut blocklist = Self {
+            categories: categories.to_vec(),
+            domains: DomainTrie::default(),
+            urls: HashMap::new(),
+        };
+        for (index, category) in categories.iter().enumerate() {
+            let category_dir = dir.join(category);
+            if !category_dir.is_dir() {
+                return Err(anyhow::anyhow!(
+                    "Category '{}' not found in blocklist directory {}",
+                    category,
+                    dir.display()
+                ));
+            }
+            let domains = category_dir.join("domains");
+            let urls = category_dir.join("urls");
+            let read_urls = use_urls && urls.is_file();
+            if !domains.is_file() && !read_urls {
+                return Err(anyhow::anyhow!(
+                    "Category '{}' has no domains file in {}",
+                    category,
+                    category_dir.display()
+                ));
+            }
+            if domains.is_file() {
+                for line in read_entries(&domains)? {
+                    blocklist
+                        .domains
+                        .insert(&normalize_host(&line), index as u16);
+                }
+            }
+            if read_urls {
+                for line in read_entries(&urls)? {
+                    let (host, path) = split_url(&line);
+                    let url = format!("{}{}", normalize_host(host), path.trim_end_matches('/'));
+                    blocklist.urls.entry(url).or_insert(index as u16);
+                }
+            }
+        }
+        Ok(blocklist)
+    }
+
+    /// Blocked category of a URL, if any
+    fn category(&self, url: &str, match_subdomains: bool) -> Option<&str> {
+        let (host, path) = split_url(url);
+        let host = normalize_host(host);
+        let index = self.domains.find(&host, match_subdomains).or_else(|| {
+            if self.urls.is_empty() {
+                return None;
+            }
+            // Prefixes of the path ending at a segment boundary, longest first
+            let path = path.split(['?', '#']).next().unwrap_or("");
+            let mut prefix = format!("{}{}", host, path.trim_end_matches('/'));
+            loop {
+                if let Some(&index) = self.urls.get(&prefix) {
+                    return Some(index);
+                }
+                match prefix.rfind('/') {
+                    Some(slash) if slash >= host.len() => prefix.truncate(slash),
+                    _ => return None,
+                }
+            }
+        })?;
+        Some(&self.categories[index as usize])
+    }
+}
+
+#[derive(Default)]
+struct DomainTrie {
+    nodes: Vec<TrieNode>, // nodes[0] is the root
+}
+
+#[derive(Default)]
+struct TrieNode {
+    children: HashMap<Box<str>, u32>,
+    category: Option<u16>, // Set if the domain ending here is listed
+}
+
+impl DomainTrie {
+    fn insert(&mut self, domain: &str, category: u16) {
+        if self.nodes.is_empty() {
+            self.nodes.push(TrieNode::default());
+        }
+        let mut node = 0;
+        for label in domain.rsplit('.').filter(|label| !label.is_empty()) {
+            node = match self.nodes[node].children.get(label) {
+                Some(&child) => child as usize,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[node].children.insert(label.into(), child as u32);
+                    child
+                }
+            };
+        }
+        if node != 0 {
+            // The first category listing a domain wins
+            self.nodes[node].category.get_or_insert(category);
+        }
+    }
+
+    /// Category of `host`, or (with `match_subdomains`) of its closest listed parent domain
+    fn find(&self, host: &str, match_subdomains: bool) -> Option<u16> {
+        let mut node = self.nodes.first()?;
+        let mut found = None;
+        for label in host.rsplit('.').filter(|label| !label.is_empty()) {
+            node = match node.children.get(label) {
+                Some(&child) => &self.nodes[child as usize],
+                None => return found,
+            };
+            if match_subdomains && node.category.is_some() {
+                found = node.category;
+            }
+        }
+        node.category.or(found)
+    }
+}
+
+/// Non-empty, non-comment lines of a blocklist file
+fn read_entries(path: &Path) -> Result<Vec<String>> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Host and rest (path, query) of a URL, with or without scheme
+fn split_url(url: &str) -> (&str, &str) {
+    let url = url.trim();
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (authority, path) = url
+        .find(['/', '?', '#'])
+        .map_or((url, ""), |i| (&url[..i], &url[i..]));
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host.rfind(':') {
+        Some(i) if host[i + 1..].chars().all(|c| c.is_ascii_digit()) => &host[..i],
+        _ => host,
+    };
+    (host, path)
+}
+
+/// Lowercase host without trailing dot and "www." prefix
+fn normalize_host(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    match host.strip_prefix("www.") {
+        Some(rest) => rest.to_string(),
+        None => host,
+    }
+}
@@ -0,0 +1,78 @@
+use crate::common::expr::{truthy, Expr};
+use fdf_sdk::{ColumnSpec, Context, Operator, OperatorCategory, ParamSpec, Result, Sample};
+use serde::Deserialize;
+
+/// Keeps samples for which an expression is true, e.g. `len(text) > 100 && score >= 0.8`
+pub struct ExprFilter {
+    expr: Expr,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExprFilterConfig {
+    expr: String,
+}
+
+impl Operator for ExprFilter {
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        if truthy(&self.expr.eval(&sample)?) {
+            Ok(Some(sample))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // Missing fields evaluate to null, so the expression requires no columns
+        Some(ColumnSpec::new())
+    }
+}
+
+pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
+    registry
+        .register_typed("common.expr_filter", |config: ExprFilterConfig| {
+            let expr = Expr::parse(&config.expr)
+                .map_err(|e| anyhow::anyhow!("Invalid expr '{}': {}", config.expr, e))?;
+            Ok(Box::new(ExprFilter { expr }))
+        })
+        .description("Keeps samples for which an expression over their fields is true")
+        .category(OperatorCategory::Filter)
+        .param(ParamSpec::required(
+            "expr",
+            "string",
+            "Expression, e.g. \"len(text) > 100 && score >= 0.8\"",
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use fdf_sdk::testing::{build_operator, run_operator, samples};
+    use serde_json::json;
+
+    #[test]
+    fn keeps_samples_matching_the_expression() {
+        let mut op = build_operator(
+            super::register,
+            "common.expr_filter",
+            "expr: len(text) > 3 && score >= 0.5",
+        )
+        .unwrap();
+        let inputs = samples([
+            json!({"id": 1, "text": "long enough", "score": 0.9}),
+            json!({"id": 2, "text": "abc", "score": 0.9}),
+            json!({"id": 3, "text": "long enough", "score": 0.1}),
+            json!({"id": 4, "text": "long enough"}),
+        ]);
+        let outputs = run_operator(op.as_mut(), inputs).unwrap();
+        let ids: Vec<&serde_json::Value> = outputs.iter().map(|s| &s.as_value()["id"]).collect();
+        assert_eq!(ids, [&json!(1)]);
+    }
+
+    #[test]
+    fn invalid_expression_fails_the_build() {
+        let error = build_operator(super::register, "common.expr_filter", "expr: len(text >")
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("Invalid expr"));
+    }
+}
@@ -0,0 +1,29 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Keeps samples whose numeric field is less than or equal to a value
+#[fdf_operator(name = "common.leq_filter", category = "filter", alias = "filter_leq")]
+pub struct LeqFilter {
+    /// Numeric field to compare (dot path allowed)
+    col: String,
+    /// Maximum value kept
+    value: f64,
+}
+
+impl Operator for LeqFilter {
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let val = sample
+            .get_path(&self.col)
+            .and_then(Value::as_f64)
+            .ok_or_else(|| anyhow::anyhow!("Missing numeric field: {}", self.col))?;
+
+        if val <= self.value {
+            Ok(Some(sample))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.col))
+    }
+}
@@ -1,7 +1,17 @@
+pub mod domain_filter;
+pub mod expr_filter;
+pub mod leq;
 pub mod numeric_range_filter;
+pub mod random_sample;
+pub mod weighted_sample;
 
 use fdf_sdk::OperatorRegistry;
 
 pub fn register(registry: &mut OperatorRegistry) {
     numeric_range_filter::register(registry);
+    expr_filter::register(registry);
+    leq::register(registry);
+    random_sample::register(registry);
+    weighted_sample::register(registry);
+    domain_filter::register(registry);
 }
@@ -1,17 +1,29 @@
-use fdf_sdk::{Operator, Result, Sample};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
 
+/// Keeps samples whose numeric field is within [lower_bound, upper_bound]
+#[fdf_operator(
+    name = "common.numeric_range_filter",
+    category = "filter",
+    alias = "numeric_range_filter"
+)]
 pub struct NumericRangeFilter {
+    /// Numeric field to check (dot path allowed)
     col: String,
+    /// Minimum value kept
     lower_bound: Option<f64>,
+    /// Maximum value kept
     upper_bound: Option<f64>,
+    /// Keep samples outside the range instead
+    #[param(default = false)]
     negate: bool,
 }
 
 impl Operator for NumericRangeFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
         // Get numeric value
         let value = sample
-            .get_f64(&self.col)
+            .get_path(&self.col)
+            .and_then(Value::as_f64)
             .ok_or_else(|| anyhow::anyhow!("Missing numeric field: {}", self.col))?;
 
         // Check bounds
@@ -26,20 +38,8 @@ impl Operator for NumericRangeFilter {
             Ok(None)
         }
     }
-}
-
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register("numeric_range_filter", |config: &serde_yaml::Value| {
-        let col = config["col"].as_str().unwrap().to_string();
-        let lower_bound = config["lower_bound"].as_f64();
-        let upper_bound = config["upper_bound"].as_f64();
-        let negate = config["negate"].as_bool().unwrap_or(false);
 
-        Ok(Box::new(NumericRangeFilter {
-            col,
-            lower_bound,
-            upper_bound,
-            negate,
-        }))
-    });
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.col))
+    }
 }
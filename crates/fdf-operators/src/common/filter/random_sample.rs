@@ -0,0 +1,52 @@
+use fdf_sdk::{fdf_operator, sampling, ColumnSpec, Context, Operator, Result, Sample};
+
+/// Keeps a deterministic random fraction of the samples, decided by a hash of the id field (or
+/// the whole sample) and the seed
+#[fdf_operator(
+    name = "common.random_sample",
+    category = "filter",
+    build = "RandomSample::validate"
+)]
+pub struct RandomSample {
+    /// Fraction of samples kept, between 0.0 and 1.0
+    rate: f64,
+    /// Field the decision is derived from (dot path allowed); the whole sample if unset
+    id_col: Option<String>,
+    /// Seed of the selection; derived from the pipeline seed if unset
+    seed: Option<u64>,
+    #[serde(skip)]
+    resolved_seed: u64,
+}
+
+impl RandomSample {
+    fn validate(self) -> Result<Self> {
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(anyhow::anyhow!(
+                "{}: rate must be between 0.0 and 1.0, got {}",
+                Self::NAME,
+                self.rate
+            ));
+        }
+        Ok(self)
+    }
+}
+
+impl Operator for RandomSample {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        self.resolved_seed = self.seed.unwrap_or_else(|| ctx.seed_for(Self::NAME));
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let u = sampling::rand01(&sample, self.id_col.as_deref(), self.resolved_seed)?;
+        Ok((u < self.rate).then_some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new();
+        if let Some(id_col) = &self.id_col {
+            columns = columns.requires(id_col);
+        }
+        Some(columns)
+    }
+}
@@ -0,0 +1,47 @@
+use fdf_sdk::{
+    fdf_operator, sampling, ColumnSpec, Context, OpError, Operator, Result, Sample, Value,
+};
+
+/// Keeps each sample with a probability given by a score field (score * scale, clamped to
+/// [0, 1]), decided deterministically like common.random_sample
+#[fdf_operator(name = "common.weighted_sample", category = "filter")]
+pub struct WeightedSample {
+    /// Numeric field giving the keep probability (dot path allowed)
+    score_col: String,
+    /// Factor applied to the score
+    #[param(default = 1.0)]
+    scale: f64,
+    /// Field the decision is derived from (dot path allowed); the whole sample if unset
+    id_col: Option<String>,
+    /// Seed of the selection; derived from the pipeline seed if unset
+    seed: Option<u64>,
+    #[serde(skip)]
+    resolved_seed: u64,
+}
+
+impl Operator for WeightedSample {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        self.resolved_seed = self.seed.unwrap_or_else(|| ctx.seed_for(Self::NAME));
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let score = sample
+            .get_path(&self.score_col)
+            .and_then(Value::as_f64)
+            .ok_or_else(|| {
+                OpError::sample_invalid(format!("Missing numeric field: {}", self.score_col))
+            })?;
+        let p = sampling::keep_probability(score, self.scale);
+        let u = sampling::rand01(&sample, self.id_col.as_deref(), self.resolved_seed)?;
+        Ok((u < p).then_some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.score_col);
+        if let Some(id_col) = &self.id_col {
+            columns = columns.requires(id_col);
+        }
+        Some(columns)
+    }
+}
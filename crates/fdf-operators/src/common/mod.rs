@@ -1,4 +1,5 @@
 pub mod annotator;
+pub mod expr;
 pub mod filter;
 pub mod transformer;
 
@@ -7,6 +8,5 @@ use fdf_sdk::OperatorRegistry;
 pub fn register(registry: &mut OperatorRegistry) {
     filter::register(registry);
     annotator::register(registry);
-    // TODO: Register common transformers when implemented
-    // transformer::register(registry);
+    transformer::register(registry);
 }
@@ -0,0 +1,128 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnInvalid {
+    Error, // Fail the sample
+    Skip,  // Leave the sample unchanged
+}
+
+/// Promotes sub-fields of a JSON object, nested or stored as a JSON string, to top-level
+/// fields (e.g. metadata.url to url)
+#[fdf_operator(
+    name = "common.flatten_json",
+    category = "transformer",
+    build = "FlattenJsonConfig::build"
+)]
+struct FlattenJsonConfig {
+    /// Field holding the object, or a string containing its JSON (dot path allowed)
+    col: String,
+    /// Sub-fields promoted (dot paths within the object) and their output fields, e.g.
+    /// {url: url, "info.lang": language}; every key of the object if unset
+    fields: Option<BTreeMap<String, String>>,
+    /// Prefix of the output fields, when every key is promoted
+    #[param(default = "''")]
+    prefix: String,
+    /// Remove col once flattened
+    #[param(default = false)]
+    drop_col: bool,
+    /// Missing, null or unparsable col (or JSON that is not an object): "error" fails the
+    /// sample, "skip" leaves it unchanged
+    #[param(default = "error", type = "string")]
+    on_invalid: OnInvalid,
+}
+
+impl FlattenJsonConfig {
+    fn build(self) -> Result<FlattenJson> {
+        if self.fields.is_some() && !self.prefix.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{}: prefix is only used without fields",
+                Self::NAME
+            ));
+        }
+        Ok(FlattenJson {
+            col: self.col,
+            fields: self.fields,
+            prefix: self.prefix,
+            drop_col: self.drop_col,
+            on_invalid: self.on_invalid,
+        })
+    }
+}
+
+pub struct FlattenJson {
+    col: String,
+    fields: Option<BTreeMap<String, String>>,
+    prefix: String,
+    drop_col: bool,
+    on_invalid: OnInvalid,
+}
+
+impl FlattenJson {
+    /// The object of a sample, parsed if it is a string
+    fn object(&self, sample: &Sample) -> Result<Sample> {
+        let object = match sample.get_path(&self.col) {
+            Some(Value::String(json)) => serde_json::from_str(json)
+                .map_err(|e| anyhow::anyhow!("Invalid JSON in {}: {}", self.col, e))?,
+            Some(Value::Null) | None => {
+                return Err(anyhow::anyhow!("Missing JSON field: {}", self.col))
+            }
+            Some(value) => value.clone(),
+        };
+        Sample::from_value(object)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a JSON object", self.col))
+    }
+}
+
+impl Operator for FlattenJson {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let object = match self.object(&sample) {
+            Ok(object) => object,
+            Err(e) => {
+                return match self.on_invalid {
+                    OnInvalid::Error => Err(e),
+                    OnInvalid::Skip => {
+                        ctx.metrics().increment("invalid", 1);
+                        Ok(Some(sample))
+                    }
+                }
+            }
+        };
+        if self.drop_col {
+            sample.remove_path(&self.col);
+        }
+        match &self.fields {
+            Some(fields) => {
+                for (path, output) in fields {
+                    let value = object.get_path(path).cloned().unwrap_or(Value::Null);
+                    sample.set_path(output, value)?;
+                }
+            }
+            None => {
+                let Value::Object(map) = object.into_value() else {
+                    unreachable!("samples are objects");
+                };
+                for (key, value) in map {
+                    sample.set_value(format!("{}{}", self.prefix, key), value);
+                }
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // Output fields depend on the data when every key is promoted
+        let fields = self.fields.as_ref()?;
+        let columns = match self.on_invalid {
+            OnInvalid::Error => ColumnSpec::new().requires(&self.col),
+            OnInvalid::Skip => ColumnSpec::new(),
+        };
+        Some(
+            fields
+                .values()
+                .fold(columns, |columns, output| columns.produces(output)),
+        )
+    }
+}
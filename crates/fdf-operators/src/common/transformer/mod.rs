@@ -1,5 +1,11 @@
+mod flatten_json;
+mod subprocess;
+mod template;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register common transformers when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    subprocess::register(registry);
+    template::register(registry);
+    flatten_json::register(registry);
 }
@@ -0,0 +1,507 @@
+use fdf_sdk::{
+    BatchError, Context, Metrics, OpError, Operator, OperatorCategory, ParamSpec, Result, Sample,
+    Value,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Streams samples through an external command as JSONL over stdin / stdout
+///
+/// For every input line the command writes one output line: a JSON object (the sample to
+/// keep, replacing the input), `null` (drop it) or an array of objects (several samples
+/// replacing the input, e.g. chunks). It must flush stdout after each line or
+/// batch. Samples are buffered and sent `batch_size` at a time; the samples of a batch are
+/// emitted when it returns, the last partial batch when the input is exhausted. If the
+/// command exits, breaks the protocol or does not answer a batch within `timeout` seconds, it
+/// is killed and restarted and the batch retried, up to `max_restarts` times; every sample of
+/// a batch that still fails goes to the error output. A command that cannot be started aborts
+/// the run. Once the input is exhausted the command's stdin is closed; an exit with an error
+/// status (or no exit within `timeout`) is counted in the `command_exit_failures` metric.
+pub struct SubprocessOperator {
+    config: SubprocessConfig,
+    buffer: Mutex<Vec<Sample>>,      // Samples of the next batch
+    process: Mutex<Option<Running>>, // Only used by the thread sending a batch
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubprocessConfig {
+    command: Vec<String>, // Program and arguments
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default = "default_batch_size")]
+    batch_size: usize,
+    #[serde(default = "default_max_restarts")]
+    max_restarts: usize,
+    #[serde(default = "default_timeout")]
+    timeout: u64, // Seconds
+}
+
+fn default_batch_size() -> usize {
+    64
+}
+
+fn default_max_restarts() -> usize {
+    3
+}
+
+fn default_timeout() -> u64 {
+    300
+}
+
+/// Running command with its pipes
+struct Running {
+    child: Child,
+    // Batches for the thread writing stdin; stdin closes when the sender is dropped. A
+    // separate writer means a command answering before reading all its input cannot block
+    // on a full stdout pipe
+    batches: Option<Sender<Arc<Vec<String>>>>,
+    writer: Option<JoinHandle<()>>,
+    // Lines of stdout, read by another thread so that waiting for them can time out; closed
+    // when the command exits
+    outputs: Receiver<std::io::Result<String>>,
+}
+
+impl Running {
+    /// Send a batch and read one output line per input line within `timeout`
+    fn exchange(&mut self, lines: &Arc<Vec<String>>, timeout: Duration) -> Result<Vec<Vec<Value>>> {
+        let sent = self
+            .batches
+            .as_ref()
+            .is_some_and(|batches| batches.send(lines.clone()).is_ok());
+        if !sent {
+            return Err(anyhow::anyhow!("command closed its input"));
+        }
+        read_outputs(&self.outputs, lines.len(), timeout)
+    }
+
+    /// Close stdin and wait for the command to exit, killing it once `timeout` has passed
+    /// (None then)
+    fn wait(mut self, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+        drop(self.batches.take());
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                self.join_writer();
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                self.kill();
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn kill(mut self) {
+        // The writer stops on the broken pipe, the reader at the end of stdout
+        drop(self.batches.take());
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        self.join_writer();
+    }
+
+    fn join_writer(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Read `count` output lines, each holding the samples produced from one input line
+fn read_outputs(
+    outputs_rx: &Receiver<std::io::Result<String>>,
+    count: usize,
+    timeout: Duration,
+) -> Result<Vec<Vec<Value>>> {
+    let deadline = Instant::now() + timeout;
+    let mut outputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        let line = match outputs_rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            Ok(line) => line?,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(anyhow::anyhow!(
+                    "no output within {} seconds",
+                    timeout.as_secs_f64()
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(anyhow::anyhow!("command exited")),
+        };
+        let output = match serde_json::from_str(line.trim_end())? {
+            Value::Null => Vec::new(),
+            value @ Value::Object(_) => vec![value],
+            Value::Array(values) if values.iter().all(Value::is_object) => values,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "expected a JSON object, array of objects or null per line, got {}",
+                    other
+                ))
+            }
+        };
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
+impl SubprocessOperator {
+    fn new(config: SubprocessConfig) -> Result<Self> {
+        if config.command.is_empty() {
+            return Err(anyhow::anyhow!(
+                "common.subprocess: command must not be empty"
+            ));
+        }
+        if config.batch_size == 0 {
+            return Err(anyhow::anyhow!(
+                "common.subprocess: batch_size must be greater than 0"
+            ));
+        }
+        Ok(Self {
+            config,
+            buffer: Mutex::new(Vec::new()),
+            process: Mutex::new(None),
+        })
+    }
+
+    fn spawn(&self) -> Result<Running> {
+        let mut child = Command::new(&self.config.command[0])
+            .args(&self.config.command[1..])
+            .envs(&self.config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start '{}': {}", self.config.command[0], e))?;
+        let mut stdin = BufWriter::new(child.stdin.take().expect("stdin is piped"));
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        let (batches, received) = channel::<Arc<Vec<String>>>();
+        let writer = std::thread::spawn(move || {
+            for lines in received {
+                let written = lines
+                    .iter()
+                    .try_for_each(|line| {
+                        stdin.write_all(line.as_bytes())?;
+                        stdin.write_all(b"\n")
+                    })
+                    .and_then(|_| stdin.flush());
+                if written.is_err() {
+                    return; // The command exited; the reader reports it
+                }
+            }
+        });
+        let (lines, outputs) = channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = match stdout.read_line(&mut line) {
+                Ok(0) => return, // The command exited
+                Ok(_) => Ok(line),
+                Err(e) => Err(e),
+            };
+            let failed = read.is_err();
+            if lines.send(read).is_err() || failed {
+                return;
+            }
+        });
+        Ok(Running {
+            child,
+            batches: Some(batches),
+            writer: Some(writer),
+            outputs,
+        })
+    }
+
+    /// Exchange a batch with the command, restarting it on failure (a timeout counts as one)
+    /// Failures are counted in the `command_failures` metric.
+    fn send_batch(
+        &self,
+        lines: Vec<String>,
+        metrics: &Metrics,
+    ) -> std::result::Result<Vec<Vec<Value>>, OpError> {
+        let lines = Arc::new(lines);
+        let mut process = self.process.lock().unwrap();
+        let mut last_error = String::new();
+        for _ in 0..=self.config.max_restarts {
+            let running = match process.as_mut() {
+                Some(running) => running,
+                None => match self.spawn() {
+                    Ok(running) => process.insert(running),
+                    Err(e) => return Err(OpError::fatal(e.to_string())),
+                },
+            };
+            match running.exchange(&lines, Duration::from_secs(self.config.timeout)) {
+                Ok(outputs) => return Ok(outputs),
+                Err(e) => {
+                    last_error = e.to_string();
+                    metrics.increment("command_failures", 1);
+                    if let Some(running) = process.take() {
+                        running.kill();
+                    }
+                }
+            }
+        }
+        Err(OpError::sample_invalid(format!(
+            "Command '{}' failed after {} restarts: {}",
+            self.config.command.join(" "),
+            self.config.max_restarts,
+            last_error
+        )))
+    }
+
+    /// Samples the command produced from a batch, in input order
+    /// A batch that fails is returned in a BatchError, so that each of its samples fails.
+    fn run_batch(&self, batch: Vec<Sample>, metrics: &Metrics) -> Result<Vec<Sample>> {
+        let lines = batch
+            .iter()
+            .map(|sample| serde_json::to_string(sample.as_value()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let outputs = match self.send_batch(lines, metrics) {
+            Ok(outputs) => outputs,
+            Err(OpError::SampleInvalid(message)) => {
+                return Err(BatchError::new(message, batch).into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut results = Vec::with_capacity(batch.len());
+        for (sample, values) in batch.into_iter().zip(outputs) {
+            for value in values {
+                let mut result = Sample::from_value(value).expect("outputs are objects");
+                // Binary fields are not sent to the command
+                for (key, bytes) in sample.binary_fields() {
+                    if result.get(key).is_none() {
+                        result.set_bytes(key.clone(), bytes.clone());
+                    }
+                }
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+}
+
+impl Operator for SubprocessOperator {
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        // A batch of one, so that the result belongs to this sample
+        let mut outputs = self.run_batch(vec![sample], &Metrics::default())?;
+        if outputs.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "Command returned {} samples for one input; run it through the engine",
+                outputs.len()
+            ));
+        }
+        Ok(outputs.pop())
+    }
+
+    fn flat_map(&self, sample: Sample, ctx: &Context) -> Result<Vec<Sample>> {
+        let batch = {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.push(sample);
+            if buffer.len() < self.config.batch_size {
+                return Ok(Vec::new());
+            }
+            std::mem::take(&mut *buffer)
+        };
+        self.run_batch(batch, ctx.metrics())
+    }
+
+    fn finish(&self, ctx: &Context) -> Result<Vec<Sample>> {
+        let batch = std::mem::take(&mut *self.buffer.lock().unwrap());
+        let outputs = match batch.is_empty() {
+            true => Ok(Vec::new()),
+            false => self.run_batch(batch, ctx.metrics()),
+        };
+
+        // Closing stdin lets the command finish
+        if let Some(running) = self.process.lock().unwrap().take() {
+            let status = running.wait(Duration::from_secs(self.config.timeout))?;
+            if !status.is_some_and(|status| status.success()) {
+                ctx.metrics().increment("command_exit_failures", 1);
+            }
+        }
+        outputs
+    }
+
+    fn buffers(&self) -> bool {
+        true
+    }
+
+    fn close(&mut self) -> Result<()> {
+        // Still running if the run failed before finish
+        if let Some(running) = self.process.get_mut().unwrap().take() {
+            running.kill();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SubprocessOperator {
+    fn drop(&mut self) {
+        if let Some(running) = self.process.get_mut().unwrap().take() {
+            running.kill();
+        }
+    }
+}
+
+pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
+    registry
+        .register_typed("common.subprocess", |config: SubprocessConfig| {
+            Ok(Box::new(SubprocessOperator::new(config)?))
+        })
+        .description(
+            "Pipes samples as JSONL through an external command, which returns each sample \
+             (possibly modified), null to drop it, or an array of samples replacing it",
+        )
+        .category(OperatorCategory::Transformer)
+        .param(ParamSpec::required(
+            "command",
+            "list<string>",
+            "Program and arguments, e.g. [python3, clean.py]",
+        ))
+        .param(ParamSpec::optional(
+            "env",
+            "map<string, string>",
+            "Extra environment variables for the command",
+        ))
+        .param(
+            ParamSpec::optional(
+                "batch_size",
+                "int",
+                "Samples buffered and sent to the command at once",
+            )
+            .with_default("64"),
+        )
+        .param(
+            ParamSpec::optional(
+                "max_restarts",
+                "int",
+                "Restarts of a crashed command before the batch fails",
+            )
+            .with_default("3"),
+        )
+        .param(
+            ParamSpec::optional(
+                "timeout",
+                "int",
+                "Seconds to wait for the outputs of a batch (or for the command to exit at the \
+                 end) before the command is killed and handled as crashed",
+            )
+            .with_default("300"),
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use fdf_sdk::testing::{build_operator, run_operator_with, samples};
+    use fdf_sdk::Context;
+    use serde_json::json;
+
+    #[test]
+    fn batches_are_emitted_in_order_with_the_rest_at_finish() {
+        let mut op = build_operator(
+            super::register,
+            "common.subprocess",
+            "command: [cat]\nbatch_size: 2",
+        )
+        .unwrap();
+        let inputs = samples((0..5).map(|id| json!({ "id": id })));
+        let run = run_operator_with(op.as_mut(), inputs, &Context::default()).unwrap();
+
+        let ids: Vec<&serde_json::Value> =
+            run.outputs.iter().map(|s| &s.as_value()["id"]).collect();
+        assert_eq!(ids, [&json!(0), &json!(1), &json!(2), &json!(3), &json!(4)]);
+        // Samples waiting for their batch produce nothing when they arrive
+        assert_eq!(run.dropped, 3);
+        assert!(op.buffers());
+    }
+
+    #[test]
+    fn failing_command_fails_the_batch_after_restarts() {
+        let mut op = build_operator(
+            super::register,
+            "common.subprocess",
+            "command: [sh, -c, 'read line; exit 1']\nbatch_size: 1\nmax_restarts: 1",
+        )
+        .unwrap();
+        let error = run_operator_with(
+            op.as_mut(),
+            samples([json!({"id": 0})]),
+            &Context::default(),
+        )
+        .unwrap_err();
+        let batch = fdf_sdk::BatchError::find(&error).unwrap();
+        assert_eq!(batch.samples.len(), 1);
+        assert!(batch.message.contains("failed after 1 restarts"));
+    }
+
+    #[test]
+    fn failed_last_batch_fails_its_samples() {
+        // The command answers the first batch, then crashes on the rest
+        let mut op = build_operator(
+            super::register,
+            "common.subprocess",
+            "command: [sh, -c, 'read a; read b; echo \"$a\"; echo \"$b\"; exit 1']\n\
+             batch_size: 2\nmax_restarts: 0",
+        )
+        .unwrap();
+        let ctx = Context::default();
+        op.open(&ctx).unwrap();
+        let inputs = samples((0..3).map(|id| json!({ "id": id })));
+        let outputs: Vec<_> = inputs
+            .into_iter()
+            .flat_map(|sample| op.flat_map(sample, &ctx).unwrap())
+            .collect();
+        assert_eq!(outputs.len(), 2);
+
+        let error = op.finish(&ctx).unwrap_err();
+        let batch = fdf_sdk::BatchError::find(&error).unwrap();
+        assert_eq!(batch.samples[0].as_value(), &json!({"id": 2}));
+        op.close().unwrap();
+    }
+
+    #[test]
+    fn command_not_answering_times_out() {
+        let mut op = build_operator(
+            super::register,
+            "common.subprocess",
+            "command: [sh, -c, 'exec sleep 30']\nbatch_size: 1\nmax_restarts: 0\ntimeout: 1",
+        )
+        .unwrap();
+        let start = std::time::Instant::now();
+        let error = run_operator_with(
+            op.as_mut(),
+            samples([json!({"id": 0})]),
+            &Context::default(),
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("no output within 1 seconds"));
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn error_exit_is_counted() {
+        let mut op = build_operator(
+            super::register,
+            "common.subprocess",
+            "command: [sh, -c, 'while read line; do echo \"$line\"; done; exit 3']",
+        )
+        .unwrap();
+        let run = run_operator_with(
+            op.as_mut(),
+            samples([json!({"id": 0})]),
+            &Context::default(),
+        )
+        .unwrap();
+        assert_eq!(run.outputs.len(), 1);
+        assert_eq!(
+            run.metrics["command_exit_failures"],
+            fdf_sdk::MetricValue::Counter { value: 1 }
+        );
+    }
+}
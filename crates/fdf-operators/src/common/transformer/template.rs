@@ -0,0 +1,152 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnMissing {
+    Error, // Fail the sample
+    Empty, // Render the field as an empty string
+    Drop,  // Drop the sample
+}
+
+/// Piece of a parsed template
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(String), // Dot path
+}
+
+/// Renders a template referencing other fields into a field, e.g. a prompt
+/// "### Question:\n{question}\n### Answer:\n{answer}" for supervised fine-tuning
+#[fdf_operator(
+    name = "common.template",
+    category = "transformer",
+    build = "TemplateConfig::build"
+)]
+struct TemplateConfig {
+    /// Template: {field} is replaced by the field (dot path allowed), {{ and }} are literal
+    /// braces; strings are inserted as is, other values as JSON
+    template: String,
+    /// Field receiving the rendered text (dot path allowed)
+    #[param(default = "text")]
+    output_col: String,
+    /// Missing or null fields: "error" fails the sample, "empty" renders nothing, "drop" drops
+    /// the sample
+    #[param(default = "error", type = "string")]
+    on_missing: OnMissing,
+}
+
+impl TemplateConfig {
+    fn build(self) -> Result<Template> {
+        let parts = parse(&self.template)
+            .map_err(|e| anyhow::anyhow!("{}: invalid template: {}", Self::NAME, e))?;
+        Ok(Template {
+            parts,
+            output_col: self.output_col,
+            on_missing: self.on_missing,
+        })
+    }
+}
+
+pub struct Template {
+    parts: Vec<Part>,
+    output_col: String,
+    on_missing: OnMissing,
+}
+
+impl Template {
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(field) => Some(field.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+}
+
+/// Literal text and fields of a template
+fn parse(template: &str) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => {
+                            return Err(anyhow::anyhow!(
+                                "unclosed {{ (write {{{{ for a literal brace)"
+                            ))
+                        }
+                        Some(c) => field.push(c),
+                    }
+                }
+                let field = field.trim();
+                if field.is_empty() {
+                    return Err(anyhow::anyhow!("empty field name {{}}"));
+                }
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Field(field.to_string()));
+            }
+            '}' => {
+                return Err(anyhow::anyhow!(
+                    "unmatched }} (write }}}} for a literal brace)"
+                ))
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+impl Operator for Template {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Field(field) => match sample.get_path(field) {
+                    Some(Value::String(text)) => rendered.push_str(text),
+                    Some(Value::Null) | None => match self.on_missing {
+                        OnMissing::Error => {
+                            return Err(anyhow::anyhow!("Missing template field: {}", field))
+                        }
+                        OnMissing::Empty => {}
+                        OnMissing::Drop => {
+                            ctx.metrics().increment(&format!("missing.{}", field), 1);
+                            return Ok(None);
+                        }
+                    },
+                    Some(value) => rendered.push_str(&value.to_string()),
+                },
+            }
+        }
+        sample.set_path(&self.output_col, Value::String(rendered))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new();
+        if self.on_missing == OnMissing::Error {
+            for field in self.fields() {
+                columns = columns.requires(field);
+            }
+        }
+        Some(columns.produces(&self.output_col))
+    }
+}
@@ -0,0 +1,133 @@
+use crate::image::clip::{image_embedding, normalize, Preprocess};
+use crate::image::{decode_image, image_bytes};
+use crate::onnx::OnnxModel;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    List,   // Array of numbers
+    Binary, // Binary field of little-endian f32 values
+}
+
+/// Annotates images with their CLIP embedding computed by an ONNX vision model, for
+/// similarity deduplication, aesthetic scoring and image-text alignment filtering
+#[fdf_operator(
+    name = "image.clip_embed",
+    category = "annotator",
+    build = "ClipEmbedConfig::build"
+)]
+struct ClipEmbedConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Path of the ONNX CLIP vision model (pixel_values input, image_embeds output)
+    model_path: String,
+    /// Field receiving the embedding (a top-level field with format binary)
+    #[param(default = "clip_embedding")]
+    embedding_col: String,
+    /// Width and height of the model input
+    #[param(default = 224)]
+    image_size: u32,
+    /// Per-channel mean of the image normalization (OpenAI CLIP values by default)
+    #[param(default = "[0.48145466, 0.4578275, 0.40821073]")]
+    mean: [f32; 3],
+    /// Per-channel standard deviation of the image normalization
+    #[param(default = "[0.26862954, 0.26130258, 0.27577711]")]
+    std: [f32; 3],
+    /// Scale embeddings to unit length (L2), so that dot products are cosine similarities
+    #[param(default = true)]
+    normalize: bool,
+    /// "list" writes an array of numbers, "binary" 4 little-endian bytes (f32) per dimension
+    #[param(default = "list", type = "string")]
+    format: Format,
+}
+
+impl ClipEmbedConfig {
+    fn build(self) -> Result<ClipEmbed> {
+        if self.image_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: image_size must be positive",
+                Self::NAME
+            ));
+        }
+        if self.std.iter().any(|&std| std <= 0.0) {
+            return Err(anyhow::anyhow!("{}: std must be positive", Self::NAME));
+        }
+        if self.format == Format::Binary && self.embedding_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: binary embeddings are written to a top-level field",
+                Self::NAME
+            ));
+        }
+        Ok(ClipEmbed {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            model_path: self.model_path,
+            embedding_col: self.embedding_col,
+            preprocess: Preprocess {
+                size: self.image_size,
+                mean: self.mean,
+                std: self.std,
+                crop: true,
+            },
+            normalize: self.normalize,
+            format: self.format,
+            model: None,
+        })
+    }
+}
+
+pub struct ClipEmbed {
+    image_col: String,
+    path_col: Option<String>,
+    model_path: String,
+    embedding_col: String,
+    preprocess: Preprocess,
+    normalize: bool,
+    format: Format,
+    model: Option<Arc<OnnxModel>>, // Loaded in open
+}
+
+impl Operator for ClipEmbed {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let model = OnnxModel::load(&self.model_path, ctx)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", ClipEmbedConfig::NAME, e)))?;
+        self.model = Some(model);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        let mut embedding = image_embedding(model, &self.preprocess, &image)?;
+        if self.normalize {
+            normalize(&mut embedding);
+        }
+        match self.format {
+            Format::List => {
+                let values = embedding.into_iter().map(Value::from).collect();
+                sample.set_path(&self.embedding_col, Value::Array(values))?;
+            }
+            Format::Binary => {
+                let bytes: Vec<u8> = embedding.iter().flat_map(|v| v.to_le_bytes()).collect();
+                sample.set_bytes(&self.embedding_col, bytes);
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        Some(
+            ColumnSpec::new()
+                .requires(input)
+                .produces(&self.embedding_col),
+        )
+    }
+}
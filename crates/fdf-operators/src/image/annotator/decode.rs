@@ -0,0 +1,87 @@
+use crate::image::{decode_image, format_name, image_bytes, image_format};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::{ImageDecoder, ImageReader};
+use std::io::Cursor;
+
+/// Decodes images and annotates their width, height, aspect ratio, channels and format.
+/// Images that cannot be decoded (corrupt, truncated, unsupported format) fail with an error,
+/// sending the sample to the error output
+#[fdf_operator(name = "image.decode", category = "annotator")]
+pub struct ImageDecode {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Prefix of the fields written: <prefix>width, <prefix>height, <prefix>aspect_ratio
+    /// (width / height), <prefix>channels and <prefix>format ("png", "jpeg", ...)
+    #[param(default = "image_")]
+    prefix: String,
+    /// Decode the pixels to check the whole image; false only reads the header
+    #[param(default = true)]
+    verify: bool,
+}
+
+/// Properties of an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub channels: u8,
+    pub format: String,
+}
+
+impl ImageDecode {
+    pub fn info(&self, bytes: &[u8]) -> Result<ImageInfo> {
+        if self.verify {
+            let (image, format) = decode_image(bytes)?;
+            return Ok(ImageInfo {
+                width: image.width(),
+                height: image.height(),
+                channels: image.color().channel_count(),
+                format: format_name(format),
+            });
+        }
+        let format = image_format(bytes)?;
+        let decoder = ImageReader::with_format(Cursor::new(bytes), format)
+            .into_decoder()
+            .map_err(|e| anyhow::anyhow!("Cannot decode image: {}", e))?;
+        let (width, height) = decoder.dimensions();
+        Ok(ImageInfo {
+            width,
+            height,
+            channels: decoder.color_type().channel_count(),
+            format: format_name(format),
+        })
+    }
+
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Operator for ImageDecode {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let info = self.info(&bytes)?;
+        let aspect_ratio = match info.height {
+            0 => 0.0,
+            height => f64::from(info.width) / f64::from(height),
+        };
+        sample.set_path(&self.field("width"), Value::from(info.width))?;
+        sample.set_path(&self.field("height"), Value::from(info.height))?;
+        sample.set_path(&self.field("aspect_ratio"), Value::from(aspect_ratio))?;
+        sample.set_path(&self.field("channels"), Value::from(info.channels))?;
+        sample.set_path(&self.field("format"), Value::from(info.format))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let mut columns = ColumnSpec::new().requires(input);
+        for name in ["width", "height", "aspect_ratio", "channels", "format"] {
+            columns = columns.produces(self.field(name));
+        }
+        Some(columns)
+    }
+}
@@ -0,0 +1,313 @@
+use crate::image::{format_name, image_bytes, image_format};
+use exif::{DateTime, Exif, Field, In, Tag};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// Annotates images with EXIF metadata (camera make and model, capture time, orientation,
+/// GPS presence and selected tags) and optionally strips all metadata from the image bytes
+/// for privacy-safe redistribution, without re-encoding the pixels
+#[fdf_operator(
+    name = "image.exif",
+    category = "annotator",
+    build = "ExifConfig::build"
+)]
+struct ExifConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Prefix of the fields written: <prefix>has_exif, <prefix>make, <prefix>model,
+    /// <prefix>datetime, <prefix>orientation and <prefix>has_gps
+    #[param(default = "exif_")]
+    prefix: String,
+    /// Additional EXIF tags to extract by name (e.g. "LensModel", "ExposureTime"), written
+    /// as text to <prefix><lowercase name>
+    tags: Option<Vec<String>>,
+    /// Remove EXIF, XMP, IPTC, comments and text chunks from JPEG, PNG and WebP images
+    #[param(default = false)]
+    strip: bool,
+    /// Binary field receiving the stripped image (a top-level field); image_col if unset
+    output_col: Option<String>,
+}
+
+impl ExifConfig {
+    fn build(self) -> Result<ImageExif> {
+        let output_col = self.output_col.unwrap_or_else(|| self.image_col.clone());
+        if self.strip && output_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: images are written to a top-level field",
+                Self::NAME
+            ));
+        }
+        Ok(ImageExif {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            prefix: self.prefix,
+            tags: self.tags.unwrap_or_default(),
+            strip: self.strip,
+            output_col,
+        })
+    }
+}
+
+pub struct ImageExif {
+    image_col: String,
+    path_col: Option<String>,
+    prefix: String,
+    tags: Vec<String>,
+    strip: bool,
+    output_col: String,
+}
+
+impl ImageExif {
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Fields annotated from the EXIF data of an image (null values without it)
+    pub fn annotations(&self, exif: Option<&Exif>) -> Vec<(String, Value)> {
+        let primary = |tag: Tag| exif.and_then(|exif| exif.get_field(tag, In::PRIMARY));
+        let tag_text = |tag: Tag| primary(tag).and_then(|field| text(field, exif?));
+        let datetime = primary(Tag::DateTimeOriginal)
+            .or_else(|| primary(Tag::DateTime))
+            .and_then(datetime);
+        let orientation = primary(Tag::Orientation).and_then(|field| field.value.get_uint(0));
+        let has_gps = primary(Tag::GPSLatitude).is_some() && primary(Tag::GPSLongitude).is_some();
+
+        let optional = |value: Option<String>| value.map_or(Value::Null, Value::from);
+        let mut annotations = vec![
+            (self.field("has_exif"), Value::from(exif.is_some())),
+            (self.field("make"), optional(tag_text(Tag::Make))),
+            (self.field("model"), optional(tag_text(Tag::Model))),
+            (self.field("datetime"), optional(datetime)),
+            (
+                self.field("orientation"),
+                orientation.map_or(Value::Null, Value::from),
+            ),
+            (self.field("has_gps"), Value::from(has_gps)),
+        ];
+        for name in &self.tags {
+            let value = exif.and_then(|exif| {
+                exif.fields()
+                    .find(|field| {
+                        field.ifd_num == In::PRIMARY
+                            && field.tag.to_string().eq_ignore_ascii_case(name)
+                    })
+                    .and_then(|field| text(field, exif))
+            });
+            annotations.push((self.field(&name.to_lowercase()), optional(value)));
+        }
+        annotations
+    }
+}
+
+/// Value of a field as text: ASCII values as they are, others formatted with their unit
+fn text(field: &Field, exif: &Exif) -> Option<String> {
+    let text = match &field.value {
+        exif::Value::Ascii(values) => String::from_utf8_lossy(values.first()?).into_owned(),
+        _ => field.display_value().with_unit(exif).to_string(),
+    };
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// EXIF date and time as ISO 8601 ("2024-05-01T12:30:00"), without a time zone
+fn datetime(field: &Field) -> Option<String> {
+    let exif::Value::Ascii(values) = &field.value else {
+        return None;
+    };
+    let dt = DateTime::from_ascii(values.first()?).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+    ))
+}
+
+/// Image bytes without metadata, or None if the image has none to remove. Pixel data, color
+/// profiles and the other chunks needed to display the image are kept as they are
+pub fn strip_metadata(bytes: &[u8]) -> Result<Option<Vec<u8>>> {
+    let stripped = match image_format(bytes)? {
+        ImageFormat::Jpeg => strip_jpeg(bytes)?,
+        ImageFormat::Png => strip_png(bytes)?,
+        ImageFormat::WebP => strip_webp(bytes)?,
+        // GIF and BMP carry no EXIF; TIFF stores it in the image structure itself
+        ImageFormat::Gif | ImageFormat::Bmp => bytes.to_vec(),
+        format => {
+            return Err(anyhow::anyhow!(
+                "Cannot strip metadata from {} images",
+                format_name(format)
+            ))
+        }
+    };
+    Ok((stripped.len() != bytes.len()).then_some(stripped))
+}
+
+/// JPEG without APPn segments other than JFIF, ICC profiles and Adobe color transforms,
+/// and without comments
+fn strip_jpeg(bytes: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || anyhow::anyhow!("Invalid JPEG structure");
+    let mut out = bytes[..2].to_vec(); // SOI
+    let mut pos = 2;
+    loop {
+        // Fill bytes (0xFF) may precede a marker
+        while bytes.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let (&prefix, &marker) = bytes.get(pos).zip(bytes.get(pos + 1)).ok_or_else(invalid)?;
+        if prefix != 0xFF {
+            return Err(invalid());
+        }
+        if marker == 0xD9 || (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+            // Markers without a segment
+            out.extend_from_slice(&bytes[pos..pos + 2]);
+            pos += 2;
+            if marker == 0xD9 {
+                break;
+            }
+            continue;
+        }
+        let length = usize::from(u16::from_be_bytes([
+            *bytes.get(pos + 2).ok_or_else(invalid)?,
+            *bytes.get(pos + 3).ok_or_else(invalid)?,
+        ]));
+        if length < 2 {
+            return Err(invalid());
+        }
+        let end = pos + 2 + length;
+        let segment = bytes.get(pos..end).ok_or_else(invalid)?;
+        let payload = &segment[4..];
+        let keep = match marker {
+            0xE0 => payload.starts_with(b"JFIF\0") || payload.starts_with(b"JFXX\0"),
+            0xE2 => payload.starts_with(b"ICC_PROFILE\0"),
+            0xEE => payload.starts_with(b"Adobe"),
+            0xE1 | 0xE3..=0xED | 0xEF | 0xFE => false,
+            _ => true,
+        };
+        if keep {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+        if marker == 0xDA {
+            // Start of scan: entropy-coded data and the remaining markers are kept as they are
+            out.extend_from_slice(&bytes[pos..]);
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// PNG without EXIF, text and modification time chunks
+fn strip_png(bytes: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || anyhow::anyhow!("Invalid PNG structure");
+    let mut out = bytes[..8].to_vec(); // Signature
+    let mut pos = 8;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(invalid)?;
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        let end = pos + 12 + length; // Length, type, data and CRC
+        let chunk = bytes.get(pos..end).ok_or_else(invalid)?;
+        if !matches!(
+            &header[4..],
+            b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME"
+        ) {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+        if &header[4..] == b"IEND" {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// WebP without EXIF and XMP chunks, their flags cleared in the VP8X header
+fn strip_webp(bytes: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || anyhow::anyhow!("Invalid WebP structure");
+    let mut out = bytes[..12].to_vec(); // RIFF header, size fixed below
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(invalid)?;
+        let length = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+        let end = (pos + 8 + length + length % 2).min(bytes.len()); // Chunks are padded to even
+        let chunk = bytes.get(pos..end).ok_or_else(invalid)?;
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(chunk);
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !(0x08 | 0x04); // EXIF and XMP present
+                }
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+    let size = u32::try_from(out.len() - 8).map_err(|_| invalid())?;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(out)
+}
+
+impl Operator for ImageExif {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let exif = match exif::Reader::new().read_from_container(&mut Cursor::new(&bytes[..])) {
+            Ok(exif) => Some(exif),
+            Err(exif::Error::NotFound(_)) => None,
+            Err(_) => {
+                // Unreadable metadata is annotated as missing, and still stripped
+                ctx.metrics().increment("invalid_exif", 1);
+                None
+            }
+        };
+        let annotations = self.annotations(exif.as_ref());
+        // Images without metadata are copied as they are when read from another field
+        let output = match (self.strip, strip_metadata(&bytes)) {
+            (false, _) => None,
+            (true, Err(e)) => return Err(e),
+            (true, Ok(Some(stripped))) => {
+                ctx.metrics().increment("stripped", 1);
+                Some(stripped)
+            }
+            (true, Ok(None)) if self.path_col.is_some() || self.output_col != self.image_col => {
+                Some(bytes.into_owned())
+            }
+            (true, Ok(None)) => None,
+        };
+
+        if let Some(output) = output {
+            sample.set_bytes(&self.output_col, output);
+        }
+        for (field, value) in annotations {
+            sample.set_path(&field, value)?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let mut columns = ColumnSpec::new().requires(input);
+        let names = [
+            "has_exif",
+            "make",
+            "model",
+            "datetime",
+            "orientation",
+            "has_gps",
+        ];
+        for name in names
+            .into_iter()
+            .map(String::from)
+            .chain(self.tags.iter().map(|tag| tag.to_lowercase()))
+        {
+            columns = columns.produces(self.field(&name));
+        }
+        Some(if self.strip {
+            columns.produces(&self.output_col)
+        } else {
+            columns
+        })
+    }
+}
@@ -1,5 +1,15 @@
+pub mod clip_embed;
+pub mod decode;
+pub mod exif;
+pub mod ocr;
+pub mod phash;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register image annotators when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    decode::register(registry);
+    clip_embed::register(registry);
+    phash::register(registry);
+    exif::register(registry);
+    ocr::register(registry);
 }
@@ -0,0 +1,174 @@
+use crate::image::{decode_image, image_bytes, image_format};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use image::codecs::png::PngEncoder;
+use image::ImageFormat;
+use std::borrow::Cow;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Annotates images with the text recognized by Tesseract OCR and its number of characters,
+/// to filter or route text-heavy images (screenshots, memes, documents). The tesseract
+/// command must be installed, with the data of the configured languages
+#[fdf_operator(name = "image.ocr", category = "annotator", build = "OcrConfig::build")]
+struct OcrConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Field receiving the recognized text
+    #[param(default = "ocr_text")]
+    text_col: String,
+    /// Field receiving the number of recognized characters, whitespace excluded
+    #[param(default = "ocr_chars")]
+    chars_col: String,
+    /// Tesseract languages, joined with "+" (e.g. "eng+deu")
+    #[param(default = "[eng]")]
+    languages: Vec<String>,
+    /// Tesseract page segmentation mode (e.g. 11 for sparse text); Tesseract's default
+    /// (automatic) if unset
+    psm: Option<u8>,
+    /// Tesseract executable
+    #[param(default = "tesseract")]
+    command: String,
+}
+
+impl OcrConfig {
+    fn build(self) -> Result<Ocr> {
+        if self.languages.is_empty() {
+            return Err(anyhow::anyhow!("{}: languages is empty", Self::NAME));
+        }
+        if self.psm.is_some_and(|psm| psm > 13) {
+            return Err(anyhow::anyhow!(
+                "{}: psm must be between 0 and 13",
+                Self::NAME
+            ));
+        }
+        Ok(Ocr {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            text_col: self.text_col,
+            chars_col: self.chars_col,
+            languages: self.languages.join("+"),
+            psm: self.psm,
+            command: self.command,
+        })
+    }
+}
+
+pub struct Ocr {
+    image_col: String,
+    path_col: Option<String>,
+    text_col: String,
+    chars_col: String,
+    languages: String,
+    psm: Option<u8>,
+    command: String,
+}
+
+impl Ocr {
+    /// Text recognized in an encoded image
+    pub fn recognize(&self, bytes: &[u8]) -> Result<String> {
+        // Formats every Leptonica build reads are passed as they are, others as PNG
+        let bytes = match image_format(bytes)? {
+            ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff | ImageFormat::Bmp => {
+                Cow::Borrowed(bytes)
+            }
+            _ => {
+                let (image, _) = decode_image(bytes)?;
+                let mut png = Vec::new();
+                image
+                    .write_with_encoder(PngEncoder::new(&mut png))
+                    .map_err(|e| anyhow::anyhow!("Cannot encode image: {}", e))?;
+                Cow::Owned(png)
+            }
+        };
+
+        let mut command = Command::new(&self.command);
+        command.args(["stdin", "stdout", "-l", &self.languages]);
+        if let Some(psm) = self.psm {
+            command.args(["--psm", &psm.to_string()]);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Cannot run {}: {}", self.command, e))?;
+        // Written from a separate thread so that tesseract cannot block on a full stdout pipe
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let output = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || stdin.write_all(&bytes));
+            let output = child.wait_with_output();
+            let _ = writer.join();
+            output
+        })
+        .map_err(|e| anyhow::anyhow!("{} failed: {}", self.command, e))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} failed ({}): {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        // Lines are kept; the form feed ending each page is not
+        Ok(text.trim_matches(|c: char| c.is_whitespace()).to_string())
+    }
+}
+
+impl Operator for Ocr {
+    fn open(&mut self, _ctx: &Context) -> Result<()> {
+        let fatal = |message: String| OpError::fatal(format!("{}: {}", OcrConfig::NAME, message));
+        let output = Command::new(&self.command)
+            .arg("--list-langs")
+            .output()
+            .map_err(|e| {
+                fatal(format!(
+                    "Cannot run {} (install Tesseract OCR or set command): {}",
+                    self.command, e
+                ))
+            })?;
+        // Older versions print the list to stderr
+        let listed = [output.stdout, output.stderr].concat();
+        let listed = String::from_utf8_lossy(&listed);
+        let installed: Vec<&str> = listed.lines().skip(1).map(str::trim).collect();
+        if let Some(missing) = self
+            .languages
+            .split('+')
+            .find(|language| !installed.contains(language))
+        {
+            return Err(fatal(format!(
+                "Tesseract language data {} is not installed",
+                missing
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let text = self.recognize(&bytes)?;
+        drop(bytes);
+        let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+        if chars > 0 {
+            ctx.metrics().increment("with_text", 1);
+        }
+        ctx.metrics().observe("ocr_chars", chars as f64);
+        sample.set_path(&self.text_col, Value::from(text))?;
+        sample.set_path(&self.chars_col, Value::from(chars))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        Some(
+            ColumnSpec::new()
+                .requires(input)
+                .produces(&self.text_col)
+                .produces(&self.chars_col),
+        )
+    }
+}
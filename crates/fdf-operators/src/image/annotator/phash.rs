@@ -0,0 +1,121 @@
+use crate::image::{decode_image, image_bytes};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Phash, // Signs of the low frequencies of the DCT, robust to scaling and compression
+    Dhash, // Brightness gradients between neighbouring pixels, faster
+}
+
+impl Algorithm {
+    /// 64-bit perceptual hash of an image, bits in row-major order from the most significant
+    /// (the order of the Python imagehash library)
+    pub fn hash(self, image: &DynamicImage) -> u64 {
+        match self {
+            Self::Phash => phash(image),
+            Self::Dhash => dhash(image),
+        }
+    }
+}
+
+/// Hash of an image as 16 hex digits
+pub fn hash_to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+/// Hash written by hash_to_hex
+pub fn hash_from_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 16)
+}
+
+/// pHash: grayscale image reduced to 32 x 32, 2D DCT, each of the 8 x 8 lowest frequencies
+/// compared to their median
+fn phash(image: &DynamicImage) -> u64 {
+    const SIZE: usize = 32;
+    const LOW: usize = 8;
+    let gray = image
+        .resize_exact(SIZE as u32, SIZE as u32, FilterType::Lanczos3)
+        .to_luma8();
+    let pixels: Vec<f64> = gray.pixels().map(|p| f64::from(p.0[0])).collect();
+
+    // DCT-II of the rows, then of the columns, of the lowest frequencies only
+    let cos: Vec<f64> = (0..LOW * SIZE)
+        .map(|i| {
+            let (k, n) = (i / SIZE, i % SIZE);
+            (std::f64::consts::PI * k as f64 * (2 * n + 1) as f64 / (2 * SIZE) as f64).cos()
+        })
+        .collect();
+    let mut rows = vec![0.0; SIZE * LOW]; // [row][frequency]
+    for row in 0..SIZE {
+        for k in 0..LOW {
+            rows[row * LOW + k] = (0..SIZE)
+                .map(|n| pixels[row * SIZE + n] * cos[k * SIZE + n])
+                .sum();
+        }
+    }
+    let mut low = vec![0.0; LOW * LOW]; // [vertical frequency][horizontal frequency]
+    for k in 0..LOW {
+        for column in 0..LOW {
+            low[k * LOW + column] = (0..SIZE)
+                .map(|n| rows[n * LOW + column] * cos[k * SIZE + n])
+                .sum();
+        }
+    }
+
+    let mut sorted = low.clone();
+    sorted.sort_by(f64::total_cmp);
+    let median = (sorted[LOW * LOW / 2 - 1] + sorted[LOW * LOW / 2]) / 2.0;
+    bits(low.iter().map(|&value| value > median))
+}
+
+/// dHash: grayscale image reduced to 9 x 8, each pixel compared to its right neighbour
+fn dhash(image: &DynamicImage) -> u64 {
+    let gray = image.resize_exact(9, 8, FilterType::Lanczos3).to_luma8();
+    bits((0..8).flat_map(|y| {
+        let gray = &gray;
+        (0..8).map(move |x| gray.get_pixel(x + 1, y).0[0] > gray.get_pixel(x, y).0[0])
+    }))
+}
+
+fn bits(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.fold(0, |hash, bit| (hash << 1) | u64::from(bit))
+}
+
+/// Annotates images with a 64-bit perceptual hash (16 hex digits), equal or close (in
+/// Hamming distance) for visually similar images, for near-duplicate detection with
+/// image.phash_dedup
+#[fdf_operator(name = "image.phash", category = "annotator")]
+pub struct ImagePhash {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Field receiving the hash
+    #[param(default = "phash")]
+    hash_col: String,
+    /// "phash" (DCT-based) or "dhash" (gradient-based)
+    #[param(default = "phash", type = "string")]
+    algorithm: Algorithm,
+}
+
+impl Operator for ImagePhash {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        let hash = self.algorithm.hash(&image);
+        sample.set_path(&self.hash_col, Value::from(hash_to_hex(hash)))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        Some(ColumnSpec::new().requires(input).produces(&self.hash_col))
+    }
+}
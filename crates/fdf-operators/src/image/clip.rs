@@ -0,0 +1,108 @@
+//! CLIP models exported to ONNX (HuggingFace optimum layout, e.g. Xenova/clip-vit-base-patch32):
+//! the vision model (`vision_model.onnx`) takes `pixel_values` of shape [batch, 3, size, size]
+//! and outputs the projected `image_embeds`; the text model (`text_model.onnx`) takes
+//! `input_ids` and `attention_mask` and outputs the projected `text_embeds`
+
+use crate::onnx::{Input, OnnxModel, Output};
+use fdf_sdk::Result;
+use image::imageops::FilterType;
+use image::DynamicImage;
+#[cfg(feature = "text-ml")]
+use tokenizers::Tokenizer;
+
+/// Image preprocessing of a CLIP model, or of another vision model taking square inputs
+#[derive(Debug, Clone)]
+pub struct Preprocess {
+    pub size: u32,      // Input width and height
+    pub mean: [f32; 3], // Per channel, of values in [0, 1]
+    pub std: [f32; 3],
+    pub crop: bool, // Center crop after resizing the shortest side (CLIP), else stretch
+}
+
+impl Preprocess {
+    /// Pixel values of an image as the CLIP image processor computes them: the shortest side
+    /// resized to `size` (bicubic), center-cropped to a square (or the image resized to
+    /// `size` x `size` without crop), scaled to [0, 1] and normalized per channel, in
+    /// channel-first order
+    pub fn pixel_values(&self, image: &DynamicImage) -> Vec<f32> {
+        let size = self.size;
+        let rgb = if self.crop {
+            let (width, height) = (image.width().max(1), image.height().max(1));
+            let scale = size as f64 / width.min(height) as f64;
+            let resized = image.resize_exact(
+                ((width as f64 * scale).round() as u32).max(size),
+                ((height as f64 * scale).round() as u32).max(size),
+                FilterType::CatmullRom,
+            );
+            let left = (resized.width() - size) / 2;
+            let top = (resized.height() - size) / 2;
+            resized.crop_imm(left, top, size, size).to_rgb8()
+        } else {
+            image
+                .resize_exact(size, size, FilterType::CatmullRom)
+                .to_rgb8()
+        };
+
+        let plane = (size * size) as usize;
+        let mut values = vec![0.0; 3 * plane];
+        for (i, pixel) in rgb.pixels().enumerate() {
+            for channel in 0..3 {
+                let value = f32::from(pixel.0[channel]) / 255.0;
+                values[channel * plane + i] = (value - self.mean[channel]) / self.std[channel];
+            }
+        }
+        values
+    }
+}
+
+/// Image embedding of a CLIP vision model: its `image_embeds` output, else its first output
+pub fn image_embedding(
+    model: &OnnxModel,
+    preprocess: &Preprocess,
+    image: &DynamicImage,
+) -> Result<Vec<f32>> {
+    let size = preprocess.size as usize;
+    let pixels = Input::F32(vec![1, 3, size, size], preprocess.pixel_values(image));
+    let outputs = model.run(vec![("pixel_values", pixels)])?;
+    projected(model, outputs, "image_embeds")
+}
+
+/// Text embedding of a CLIP text model: its `text_embeds` output, else its first output
+#[cfg(feature = "text-ml")]
+pub fn text_embedding(model: &OnnxModel, tokenizer: &Tokenizer, text: &str) -> Result<Vec<f32>> {
+    let encoding = tokenizer
+        .encode(text, true)
+        .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+    let outputs = model.run(crate::text::model::encoder_inputs(model, &[&encoding]))?;
+    projected(model, outputs, "text_embeds")
+}
+
+/// Values of the output `name` of a single-input run, else of the first output
+fn projected(model: &OnnxModel, outputs: Vec<Output>, name: &str) -> Result<Vec<f32>> {
+    let index = model
+        .outputs()
+        .iter()
+        .position(|output| output == name)
+        .unwrap_or(0);
+    let output = outputs
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| anyhow::anyhow!("Model has no output"))?;
+    if output.shape.len() != 2 {
+        return Err(anyhow::anyhow!(
+            "Model output {} has shape {:?}, expected [batch, dimensions] ({})",
+            model.outputs()[index],
+            output.shape,
+            name
+        ));
+    }
+    Ok(output.values)
+}
+
+/// Vector scaled to unit length (L2)
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod phash_dedup;
+pub mod resolution;
+pub mod sharpness;
+pub mod valid;
+pub mod watermark;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register image filters when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    valid::register(registry);
+    resolution::register(registry);
+    phash_dedup::register(registry);
+    sharpness::register(registry);
+    watermark::register(registry);
 }
@@ -0,0 +1,278 @@
+use crate::image::annotator::phash::{hash_from_hex, hash_to_hex, Algorithm};
+use crate::image::{decode_image, image_bytes};
+use fdf_sdk::{
+    fdf_operator, ColumnSpec, Context, OpError, Result, Sample, StateHandle, Stateful,
+    StatefulOperator, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const HASHES_FILE: &str = "hashes.bin"; // Kept hashes, 8 little-endian bytes each
+const META_FILE: &str = "index.json";
+
+/// Drops images whose perceptual hash is within a Hamming distance of an image already kept
+/// (near-duplicates: resized, recompressed or slightly edited copies). Hashes written by
+/// image.phash are used when present. With index_dir, the kept hashes are loaded from and
+/// saved to disk, so that later runs and shards also deduplicate against them
+#[fdf_operator(
+    name = "image.phash_dedup",
+    category = "filter",
+    build = "PhashDedupConfig::build"
+)]
+struct PhashDedupConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Field holding the hash of image.phash, used when present; the computed hash is written
+    /// there otherwise
+    #[param(default = "phash")]
+    hash_col: String,
+    /// Algorithm of the hashes: "phash" or "dhash"
+    #[param(default = "phash", type = "string")]
+    algorithm: Algorithm,
+    /// Maximum number of differing bits (of 64) for two images to be duplicates; 0 only drops
+    /// identical hashes
+    #[param(default = 4)]
+    max_distance: u32,
+    /// Directory of the on-disk hash index, loaded in open if it exists and written with
+    /// every kept hash when the run succeeds (a failed run leaves it unchanged)
+    index_dir: Option<String>,
+    /// Keep every image and write the Hamming distance to the closest kept image within
+    /// max_distance (null if none) to distance_col instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Field receiving the distance with annotate
+    #[param(default = "phash_distance")]
+    distance_col: String,
+}
+
+impl PhashDedupConfig {
+    fn build(self) -> Result<Stateful<PhashDedup>> {
+        if self.max_distance > 16 {
+            return Err(anyhow::anyhow!(
+                "{}: max_distance must be at most 16",
+                Self::NAME
+            ));
+        }
+        Ok(Stateful::new(PhashDedup {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            hash_col: self.hash_col,
+            algorithm: self.algorithm,
+            max_distance: self.max_distance,
+            index_dir: self.index_dir.map(PathBuf::from),
+            annotate: self.annotate,
+            distance_col: self.distance_col,
+        }))
+    }
+}
+
+pub struct PhashDedup {
+    image_col: String,
+    path_col: Option<String>,
+    hash_col: String,
+    algorithm: Algorithm,
+    max_distance: u32,
+    index_dir: Option<PathBuf>,
+    annotate: bool,
+    distance_col: String,
+}
+
+/// Settings of an on-disk index
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexMeta {
+    algorithm: Algorithm,
+    hashes: u64,
+}
+
+/// Hashes of the images kept so far, searchable by Hamming distance (multi-index hashing): the
+/// 64 bits are split into max_distance + 1 bands, and two hashes within max_distance bits agree
+/// on at least one band
+pub struct HashIndex {
+    max_distance: u32,
+    bands: Vec<(u32, u32)>,                // Shift and width of each band
+    tables: Vec<HashMap<u64, Vec<usize>>>, // Per band: band value -> indices in hashes
+    hashes: Vec<u64>,
+}
+
+impl HashIndex {
+    fn new(max_distance: u32) -> Self {
+        let count = max_distance + 1;
+        let bands = (0..count)
+            .map(|band| {
+                let start = 64 * band / count;
+                (start, 64 * (band + 1) / count - start)
+            })
+            .collect();
+        Self {
+            max_distance,
+            bands,
+            tables: vec![HashMap::new(); count as usize],
+            hashes: Vec::new(),
+        }
+    }
+
+    fn band_values(&self, hash: u64) -> impl Iterator<Item = u64> + '_ {
+        self.bands
+            .iter()
+            .map(move |&(shift, width)| (hash >> shift) & (u64::MAX >> (64 - width)))
+    }
+
+    /// Smallest distance to an indexed hash, if within max_distance
+    fn closest(&self, hash: u64) -> Option<u32> {
+        self.band_values(hash)
+            .zip(&self.tables)
+            .filter_map(|(value, table)| table.get(&value))
+            .flatten()
+            .map(|&index| (self.hashes[index] ^ hash).count_ones())
+            .filter(|&distance| distance <= self.max_distance)
+            .min()
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let index = self.hashes.len();
+        let values: Vec<u64> = self.band_values(hash).collect();
+        for (value, table) in values.into_iter().zip(&mut self.tables) {
+            table.entry(value).or_default().push(index);
+        }
+        self.hashes.push(hash);
+    }
+}
+
+impl PhashDedup {
+    /// Hash written by image.phash, else computed from the image
+    fn hash(&self, sample: &Sample) -> Result<(u64, bool)> {
+        if let Some(value) = sample.get_path(&self.hash_col) {
+            let hash = value.as_str().and_then(hash_from_hex).ok_or_else(|| {
+                anyhow::anyhow!("Invalid hash in {} (16 hex digits expected)", self.hash_col)
+            })?;
+            return Ok((hash, false));
+        }
+        let bytes = image_bytes(sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        Ok((self.algorithm.hash(&image), true))
+    }
+
+    /// Hashes of the index in `index_dir`, if any, checking they use the same algorithm
+    fn load_index(&self, index_dir: &Path) -> Result<Vec<u64>> {
+        let meta_path = index_dir.join(META_FILE);
+        if !meta_path.exists() {
+            return Ok(Vec::new());
+        }
+        let meta: IndexMeta = std::fs::read_to_string(&meta_path)
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", meta_path.display(), e))
+            .and_then(|json| Ok(serde_json::from_str(&json)?))?;
+        if meta.algorithm != self.algorithm {
+            return Err(anyhow::anyhow!(
+                "Index in {} holds {} hashes, not {}",
+                index_dir.display(),
+                format!("{:?}", meta.algorithm).to_lowercase(),
+                format!("{:?}", self.algorithm).to_lowercase()
+            ));
+        }
+        let hashes_path = index_dir.join(HASHES_FILE);
+        let bytes = std::fs::read(&hashes_path)
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", hashes_path.display(), e))?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+
+    /// Write every kept hash to `index_dir`, replacing the previous files
+    fn save_index(&self, index: &HashIndex, index_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(index_dir)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", index_dir.display(), e))?;
+        let bytes: Vec<u8> = index.hashes.iter().flat_map(|h| h.to_le_bytes()).collect();
+        let meta = IndexMeta {
+            algorithm: self.algorithm,
+            hashes: index.hashes.len() as u64,
+        };
+        let files = [
+            (HASHES_FILE, bytes),
+            (META_FILE, serde_json::to_vec_pretty(&meta)?),
+        ];
+        for (name, contents) in files {
+            // Written aside then renamed, so that an interrupted run keeps the previous index
+            let path = index_dir.join(name);
+            let partial = index_dir.join(format!("{}.partial", name));
+            std::fs::write(&partial, contents)
+                .and_then(|_| std::fs::rename(&partial, &path))
+                .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+impl StatefulOperator for PhashDedup {
+    type State = HashIndex;
+
+    fn open_state(&mut self, _ctx: &Context) -> Result<HashIndex> {
+        let mut index = HashIndex::new(self.max_distance);
+        let Some(index_dir) = &self.index_dir else {
+            return Ok(index);
+        };
+        let hashes = self
+            .load_index(index_dir)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", PhashDedupConfig::NAME, e)))?;
+        for hash in hashes {
+            index.insert(hash);
+        }
+        Ok(index)
+    }
+
+    fn process_with_state(
+        &self,
+        index: &StateHandle<HashIndex>,
+        mut sample: Sample,
+        ctx: &Context,
+    ) -> Result<Option<Sample>> {
+        let (hash, computed) = self.hash(&sample)?;
+        let distance = {
+            let mut index = index.write();
+            let distance = index.closest(hash);
+            if distance.is_none() {
+                index.insert(hash);
+            }
+            distance
+        };
+
+        if self.annotate {
+            sample.set_path(
+                &self.distance_col,
+                distance.map_or(Value::Null, Value::from),
+            )?;
+        } else if distance.is_some() {
+            ctx.metrics().increment("rejected.duplicate", 1);
+            return Ok(None);
+        }
+        if computed {
+            sample.set_path(&self.hash_col, Value::from(hash_to_hex(hash)))?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // The hash field of image.phash is used when present, not required
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let columns = ColumnSpec::new().requires(input).produces(&self.hash_col);
+        Some(if self.annotate {
+            columns.produces(&self.distance_col)
+        } else {
+            columns
+        })
+    }
+
+    fn finish_state(&self, index: &StateHandle<HashIndex>, _ctx: &Context) -> Result<Vec<Sample>> {
+        // Saved only when every sample went through, not in close: a failed run would keep
+        // hashes of images that were never written, dropping them as duplicates later
+        if let Some(index_dir) = &self.index_dir {
+            self.save_index(&index.read(), index_dir)
+                .map_err(|e| anyhow::anyhow!("{}: {:#}", PhashDedupConfig::NAME, e))?;
+        }
+        Ok(Vec::new())
+    }
+}
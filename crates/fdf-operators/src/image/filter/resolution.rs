@@ -0,0 +1,197 @@
+use crate::image::{image_bytes, image_dimensions};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Keeps images whose width, height, pixel count and aspect ratio are within bounds, to drop
+/// icons, banners and extreme panoramas. Dimensions come from the fields written by
+/// image.decode when present, else from the image header
+#[fdf_operator(
+    name = "image.resolution_filter",
+    category = "filter",
+    build = "ResolutionFilterConfig::build"
+)]
+struct ResolutionFilterConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Prefix of the dimension fields of image.decode (<prefix>width, <prefix>height), and of
+    /// the fields written with annotate
+    #[param(default = "image_")]
+    prefix: String,
+    /// Minimum width in pixels
+    min_width: Option<u32>,
+    /// Maximum width in pixels
+    max_width: Option<u32>,
+    /// Minimum height in pixels
+    min_height: Option<u32>,
+    /// Maximum height in pixels
+    max_height: Option<u32>,
+    /// Minimum number of pixels (width x height)
+    min_pixels: Option<u64>,
+    /// Maximum number of pixels (width x height)
+    max_pixels: Option<u64>,
+    /// Minimum aspect ratio (width / height; below 1 for portrait images)
+    min_aspect_ratio: Option<f64>,
+    /// Maximum aspect ratio (width / height)
+    max_aspect_ratio: Option<f64>,
+    /// Keep every image and write its dimensions (<prefix>width, <prefix>height) and the
+    /// failed bound (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl ResolutionFilterConfig {
+    fn build(self) -> Result<ResolutionFilter> {
+        let bounds = [
+            (self.min_width.map(f64::from), self.max_width.map(f64::from)),
+            (
+                self.min_height.map(f64::from),
+                self.max_height.map(f64::from),
+            ),
+            (
+                self.min_pixels.map(|p| p as f64),
+                self.max_pixels.map(|p| p as f64),
+            ),
+            (self.min_aspect_ratio, self.max_aspect_ratio),
+        ];
+        for (min, max) in bounds {
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(anyhow::anyhow!(
+                        "{}: a minimum is above its maximum",
+                        Self::NAME
+                    ));
+                }
+            }
+        }
+        if self.min_aspect_ratio.is_some_and(|ratio| ratio <= 0.0) {
+            return Err(anyhow::anyhow!(
+                "{}: min_aspect_ratio must be positive",
+                Self::NAME
+            ));
+        }
+        Ok(ResolutionFilter {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            prefix: self.prefix,
+            min_width: self.min_width,
+            max_width: self.max_width,
+            min_height: self.min_height,
+            max_height: self.max_height,
+            min_pixels: self.min_pixels,
+            max_pixels: self.max_pixels,
+            min_aspect_ratio: self.min_aspect_ratio,
+            max_aspect_ratio: self.max_aspect_ratio,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct ResolutionFilter {
+    image_col: String,
+    path_col: Option<String>,
+    prefix: String,
+    min_width: Option<u32>,
+    max_width: Option<u32>,
+    min_height: Option<u32>,
+    max_height: Option<u32>,
+    min_pixels: Option<u64>,
+    max_pixels: Option<u64>,
+    min_aspect_ratio: Option<f64>,
+    max_aspect_ratio: Option<f64>,
+    annotate: bool,
+}
+
+impl ResolutionFilter {
+    /// First bound an image of this size fails, if any
+    pub fn reject(&self, width: u32, height: u32) -> Option<&'static str> {
+        let pixels = u64::from(width) * u64::from(height);
+        let aspect_ratio = f64::from(width) / f64::from(height.max(1));
+        let checks = [
+            ("min_width", self.min_width.is_some_and(|min| width < min)),
+            ("max_width", self.max_width.is_some_and(|max| width > max)),
+            (
+                "min_height",
+                self.min_height.is_some_and(|min| height < min),
+            ),
+            (
+                "max_height",
+                self.max_height.is_some_and(|max| height > max),
+            ),
+            (
+                "min_pixels",
+                self.min_pixels.is_some_and(|min| pixels < min),
+            ),
+            (
+                "max_pixels",
+                self.max_pixels.is_some_and(|max| pixels > max),
+            ),
+            (
+                "min_aspect_ratio",
+                self.min_aspect_ratio.is_some_and(|min| aspect_ratio < min),
+            ),
+            (
+                "max_aspect_ratio",
+                self.max_aspect_ratio.is_some_and(|max| aspect_ratio > max),
+            ),
+        ];
+        checks
+            .into_iter()
+            .find(|(_, failed)| *failed)
+            .map(|(bound, _)| bound)
+    }
+
+    /// Dimensions written by image.decode, else read from the image header
+    fn dimensions(&self, sample: &Sample) -> Result<(u32, u32)> {
+        let field = |name: &str| {
+            sample
+                .get_path(&format!("{}{}", self.prefix, name))
+                .and_then(Value::as_u64)
+                .and_then(|value| u32::try_from(value).ok())
+        };
+        if let (Some(width), Some(height)) = (field("width"), field("height")) {
+            return Ok((width, height));
+        }
+        let bytes = image_bytes(sample, &self.image_col, self.path_col.as_deref())?;
+        image_dimensions(&bytes)
+    }
+}
+
+impl Operator for ResolutionFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let (width, height) = self.dimensions(&sample)?;
+        let rejected = self.reject(width, height);
+
+        if self.annotate {
+            sample.set_path(&format!("{}width", self.prefix), Value::from(width))?;
+            sample.set_path(&format!("{}height", self.prefix), Value::from(height))?;
+            sample.set_path(
+                &format!("{}rejected_by", self.prefix),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match rejected {
+            Some(bound) => {
+                ctx.metrics().increment(&format!("rejected.{}", bound), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // The dimension fields of image.decode are used when present, not required
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            columns
+                .produces(format!("{}width", self.prefix))
+                .produces(format!("{}height", self.prefix))
+                .produces(format!("{}rejected_by", self.prefix))
+        } else {
+            columns
+        })
+    }
+}
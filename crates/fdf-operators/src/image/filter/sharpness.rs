@@ -0,0 +1,172 @@
+use crate::image::{decode_image, image_bytes};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+
+/// Drops blurry, near-uniform and solid-color images, measured by the variance of the
+/// Laplacian (sharpness) and the entropy of the grayscale histogram
+#[fdf_operator(
+    name = "image.sharpness_filter",
+    category = "filter",
+    build = "SharpnessFilterConfig::build"
+)]
+struct SharpnessFilterConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Minimum variance of the Laplacian of the grayscale image (0-255 values); blurry
+    /// images score low
+    #[param(default = 100.0)]
+    min_sharpness: f64,
+    /// Minimum Shannon entropy of the grayscale histogram, in bits (0 for a solid color, at
+    /// most 8)
+    #[param(default = 3.0)]
+    min_entropy: f64,
+    /// Images are measured after being downscaled to fit max_edge x max_edge pixels, for
+    /// speed and scores comparable across resolutions; 0 measures them at full size
+    #[param(default = 512)]
+    max_edge: u32,
+    /// Prefix of the fields written with annotate
+    #[param(default = "image_")]
+    prefix: String,
+    /// Keep every image and write <prefix>sharpness, <prefix>entropy and the failed bound
+    /// (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl SharpnessFilterConfig {
+    fn build(self) -> Result<SharpnessFilter> {
+        if self.min_sharpness < 0.0 || !(0.0..=8.0).contains(&self.min_entropy) {
+            return Err(anyhow::anyhow!(
+                "{}: min_sharpness must be positive and min_entropy between 0 and 8",
+                Self::NAME
+            ));
+        }
+        Ok(SharpnessFilter {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            min_sharpness: self.min_sharpness,
+            min_entropy: self.min_entropy,
+            max_edge: self.max_edge,
+            prefix: self.prefix,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct SharpnessFilter {
+    image_col: String,
+    path_col: Option<String>,
+    min_sharpness: f64,
+    min_entropy: f64,
+    max_edge: u32,
+    prefix: String,
+    annotate: bool,
+}
+
+impl SharpnessFilter {
+    /// Sharpness and entropy of an image
+    pub fn measure(&self, image: &DynamicImage) -> (f64, f64) {
+        let fits = self.max_edge == 0 || image.width().max(image.height()) <= self.max_edge;
+        let gray = match fits {
+            true => image.to_luma8(),
+            false => image
+                .resize(self.max_edge, self.max_edge, FilterType::Triangle)
+                .to_luma8(),
+        };
+        (laplacian_variance(&gray), entropy(&gray))
+    }
+
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+/// Variance of the 4-neighbour Laplacian over the inner pixels (0 for images under 3 x 3)
+fn laplacian_variance(gray: &GrayImage) -> f64 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    let pixel = |x: u32, y: u32| f64::from(gray.get_pixel(x, y).0[0]);
+    let (mut sum, mut squares) = (0.0, 0.0);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let value = pixel(x - 1, y) + pixel(x + 1, y) + pixel(x, y - 1) + pixel(x, y + 1)
+                - 4.0 * pixel(x, y);
+            sum += value;
+            squares += value * value;
+        }
+    }
+    let count = f64::from((width - 2) * (height - 2));
+    let mean = sum / count;
+    squares / count - mean * mean
+}
+
+/// Shannon entropy of the histogram of gray levels, in bits
+fn entropy(gray: &GrayImage) -> f64 {
+    let mut histogram = [0u64; 256];
+    for pixel in gray.pixels() {
+        histogram[usize::from(pixel.0[0])] += 1;
+    }
+    let total = gray.pixels().len() as f64;
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+impl Operator for SharpnessFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        drop(bytes);
+        let (sharpness, entropy) = self.measure(&image);
+        ctx.metrics().observe("sharpness", sharpness);
+        ctx.metrics().observe("entropy", entropy);
+        let rejected = if entropy < self.min_entropy {
+            Some("min_entropy")
+        } else if sharpness < self.min_sharpness {
+            Some("min_sharpness")
+        } else {
+            None
+        };
+
+        if self.annotate {
+            sample.set_path(&self.field("sharpness"), Value::from(sharpness))?;
+            sample.set_path(&self.field("entropy"), Value::from(entropy))?;
+            sample.set_path(
+                &self.field("rejected_by"),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match rejected {
+            Some(bound) => {
+                ctx.metrics().increment(&format!("rejected.{}", bound), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            columns
+                .produces(self.field("sharpness"))
+                .produces(self.field("entropy"))
+                .produces(self.field("rejected_by"))
+        } else {
+            columns
+        })
+    }
+}
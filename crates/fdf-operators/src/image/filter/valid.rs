@@ -0,0 +1,154 @@
+use crate::image::{format_name, image_bytes};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::error::ImageError;
+use image::{ImageFormat, ImageReader};
+use std::io::{Cursor, ErrorKind};
+
+/// Fully decodes images and drops those that are truncated, undecodable, in an unknown
+/// format or mislabeled (file extension or MIME type not matching the magic bytes)
+#[fdf_operator(
+    name = "image.valid_filter",
+    category = "filter",
+    build = "ValidFilterConfig::build"
+)]
+struct ValidFilterConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// String field holding the file name, URL or MIME type the format is checked against;
+    /// the path of path_col if unset
+    label_col: Option<String>,
+    /// Prefix of the fields written with annotate
+    #[param(default = "image_")]
+    prefix: String,
+    /// Keep every image and write the failure reason (<prefix>rejected_by: unknown_format,
+    /// mislabeled, truncated or undecodable; null if valid) and the decoder message
+    /// (<prefix>error) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl ValidFilterConfig {
+    fn build(self) -> Result<ValidFilter> {
+        let label_col = self.label_col.or_else(|| self.path_col.clone());
+        Ok(ValidFilter {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            label_col,
+            prefix: self.prefix,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct ValidFilter {
+    image_col: String,
+    path_col: Option<String>,
+    label_col: Option<String>,
+    prefix: String,
+    annotate: bool,
+}
+
+/// Why an image is invalid, and the details
+pub type Invalid = (&'static str, String);
+
+/// Reason an encoded image is invalid, if any. `label` is a file name, URL or MIME type the
+/// format must match; labels without a known image extension are not checked
+pub fn validate(bytes: &[u8], label: Option<&str>) -> Option<Invalid> {
+    let format = match image::guess_format(bytes) {
+        Ok(format) => format,
+        Err(e) => return Some(("unknown_format", e.to_string())),
+    };
+    if let Some(labeled) = label.and_then(labeled_format) {
+        if labeled != format {
+            return Some((
+                "mislabeled",
+                format!(
+                    "Labeled {} but the content is {}",
+                    format_name(labeled),
+                    format_name(format)
+                ),
+            ));
+        }
+    }
+    // The JPEG decoder fills missing data in, so the end of image marker is checked
+    if format == ImageFormat::Jpeg && !has_jpeg_end(bytes) {
+        return Some(("truncated", "JPEG end of image marker not found".into()));
+    }
+    match ImageReader::with_format(Cursor::new(bytes), format).decode() {
+        Ok(_) => None,
+        Err(ImageError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+            Some(("truncated", e.to_string()))
+        }
+        Err(e) => Some(("undecodable", e.to_string())),
+    }
+}
+
+/// Format named by a MIME type ("image/png") or by the extension of a file name or URL
+fn labeled_format(label: &str) -> Option<ImageFormat> {
+    let label = label.trim();
+    if let Some(mime) = label.strip_prefix("image/") {
+        return ImageFormat::from_mime_type(format!("image/{}", mime.to_lowercase()));
+    }
+    let path = label.split(['?', '#']).next().unwrap_or(label);
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (_, extension) = name.rsplit_once('.')?;
+    ImageFormat::from_extension(extension)
+}
+
+/// Whether the last bytes of a JPEG file hold its end of image marker (some files have
+/// trailing padding)
+fn has_jpeg_end(bytes: &[u8]) -> bool {
+    let tail = &bytes[bytes.len().saturating_sub(1024)..];
+    tail.windows(2).any(|marker| marker == [0xFF, 0xD9])
+}
+
+impl ValidFilter {
+    fn field(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Operator for ValidFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let label = self
+            .label_col
+            .as_ref()
+            .and_then(|label_col| sample.get_path(label_col))
+            .and_then(Value::as_str);
+        let invalid = validate(&bytes, label);
+        drop(bytes);
+
+        if self.annotate {
+            let (reason, error) = match invalid {
+                Some((reason, error)) => (Value::from(reason), Value::from(error)),
+                None => (Value::Null, Value::Null),
+            };
+            sample.set_path(&self.field("rejected_by"), reason)?;
+            sample.set_path(&self.field("error"), error)?;
+            return Ok(Some(sample));
+        }
+        match invalid {
+            Some((reason, _)) => {
+                ctx.metrics().increment(&format!("rejected.{}", reason), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            columns
+                .produces(self.field("rejected_by"))
+                .produces(self.field("error"))
+        } else {
+            columns
+        })
+    }
+}
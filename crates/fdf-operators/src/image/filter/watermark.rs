@@ -0,0 +1,160 @@
+use crate::image::clip::Preprocess;
+use crate::image::{decode_image, image_bytes};
+use crate::onnx::{Input, OnnxModel};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::sync::Arc;
+
+/// Scores images with an ONNX watermark detection model (e.g. the EfficientNet classifier of
+/// LAION-5B-WatermarkDetection), writing the watermark probability and dropping images above
+/// an optional threshold
+#[fdf_operator(
+    name = "image.watermark_filter",
+    category = "filter",
+    build = "WatermarkFilterConfig::build"
+)]
+struct WatermarkFilterConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Path of the ONNX model: one image input of shape [batch, 3, size, size], class logits
+    /// output of shape [batch, classes] (a single logit is read with a sigmoid)
+    model_path: String,
+    /// Class of the watermark among the model outputs
+    #[param(default = 0)]
+    watermark_class: usize,
+    /// Width and height the images are resized to (without crop)
+    #[param(default = 256)]
+    image_size: u32,
+    /// Per-channel mean of the image normalization (ImageNet values by default)
+    #[param(default = "[0.485, 0.456, 0.406]")]
+    mean: [f32; 3],
+    /// Per-channel standard deviation of the image normalization
+    #[param(default = "[0.229, 0.224, 0.225]")]
+    std: [f32; 3],
+    /// Images with a watermark probability at or above it are dropped (LAION used 0.8);
+    /// images are only annotated if unset
+    threshold: Option<f64>,
+    /// Field receiving the watermark probability of kept images
+    #[param(default = "pwatermark")]
+    score_col: String,
+}
+
+impl WatermarkFilterConfig {
+    fn build(self) -> Result<WatermarkFilter> {
+        if self
+            .threshold
+            .is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+        {
+            return Err(anyhow::anyhow!(
+                "{}: threshold must be between 0 and 1",
+                Self::NAME
+            ));
+        }
+        if self.image_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: image_size must be positive",
+                Self::NAME
+            ));
+        }
+        if self.std.iter().any(|&std| std <= 0.0) {
+            return Err(anyhow::anyhow!("{}: std must be positive", Self::NAME));
+        }
+        Ok(WatermarkFilter {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            model_path: self.model_path,
+            watermark_class: self.watermark_class,
+            preprocess: Preprocess {
+                size: self.image_size,
+                mean: self.mean,
+                std: self.std,
+                crop: false,
+            },
+            threshold: self.threshold,
+            score_col: self.score_col,
+            model: None,
+        })
+    }
+}
+
+pub struct WatermarkFilter {
+    image_col: String,
+    path_col: Option<String>,
+    model_path: String,
+    watermark_class: usize,
+    preprocess: Preprocess,
+    threshold: Option<f64>,
+    score_col: String,
+    model: Option<Arc<OnnxModel>>, // Loaded in open
+}
+
+impl WatermarkFilter {
+    /// Watermark probability of an image
+    pub fn probability(&self, sample: &Sample) -> Result<f64> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let bytes = image_bytes(sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        let size = self.preprocess.size as usize;
+        let pixels = Input::F32(vec![1, 3, size, size], self.preprocess.pixel_values(&image));
+        let input = model
+            .inputs()
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Model has no input"))?;
+        let output = model
+            .run(vec![(input.as_str(), pixels)])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Model has no output"))?;
+        let logits: Vec<f64> = output.values.iter().map(|&v| f64::from(v)).collect();
+        if output.shape.len() != 2 || logits.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Model output has shape {:?}, expected [batch, classes]",
+                output.shape
+            ));
+        }
+
+        if logits.len() == 1 {
+            return Ok(1.0 / (1.0 + (-logits[0]).exp()));
+        }
+        let logit = *logits.get(self.watermark_class).ok_or_else(|| {
+            anyhow::anyhow!(
+                "watermark_class is {}, the model has {} classes",
+                self.watermark_class,
+                logits.len()
+            )
+        })?;
+        let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let sum: f64 = logits.iter().map(|logit| (logit - max).exp()).sum();
+        Ok((logit - max).exp() / sum)
+    }
+}
+
+impl Operator for WatermarkFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let model = OnnxModel::load(&self.model_path, ctx)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", WatermarkFilterConfig::NAME, e)))?;
+        self.model = Some(model);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let probability = self.probability(&sample)?;
+        ctx.metrics().observe("pwatermark", probability);
+        if self
+            .threshold
+            .is_some_and(|threshold| probability >= threshold)
+        {
+            ctx.metrics().increment("rejected.watermark", 1);
+            return Ok(None);
+        }
+        sample.set_path(&self.score_col, Value::from(probability))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        Some(ColumnSpec::new().requires(input).produces(&self.score_col))
+    }
+}
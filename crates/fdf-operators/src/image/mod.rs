@@ -1,12 +1,70 @@
+//! Image operators
+//!
+//! Images are read from a binary field holding the encoded file (`image_col`, e.g. a Parquet
+//! `Binary` column), or from the file named by a string field (`path_col`) when it is set.
+
 pub mod annotator;
+pub mod clip;
 pub mod filter;
 pub mod transformer;
 
-use fdf_sdk::OperatorRegistry;
+use fdf_sdk::{OperatorRegistry, Result, Sample, Value};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::borrow::Cow;
+use std::io::Cursor;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    transformer::register(registry);
+    filter::register(registry);
+    annotator::register(registry);
+}
+
+/// Encoded image of a sample: the file named by the string field `path_col` if set, else the
+/// binary field `image_col`
+pub fn image_bytes<'a>(
+    sample: &'a Sample,
+    image_col: &str,
+    path_col: Option<&str>,
+) -> Result<Cow<'a, [u8]>> {
+    match path_col {
+        Some(path_col) => {
+            let path = sample
+                .get_path(path_col)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Missing path field: {}", path_col))?;
+            std::fs::read(path)
+                .map(Cow::Owned)
+                .map_err(|e| anyhow::anyhow!("Cannot read image {}: {}", path, e))
+        }
+        None => sample
+            .get_bytes(image_col)
+            .map(Cow::Borrowed)
+            .ok_or_else(|| anyhow::anyhow!("Missing image field: {}", image_col)),
+    }
+}
+
+/// Format of encoded image bytes, guessed from their signature
+pub fn image_format(bytes: &[u8]) -> Result<ImageFormat> {
+    image::guess_format(bytes).map_err(|e| anyhow::anyhow!("Unknown image format: {}", e))
+}
+
+/// Decoded image with its format
+pub fn decode_image(bytes: &[u8]) -> Result<(DynamicImage, ImageFormat)> {
+    let format = image_format(bytes)?;
+    let image = ImageReader::with_format(Cursor::new(bytes), format)
+        .decode()
+        .map_err(|e| anyhow::anyhow!("Cannot decode image: {}", e))?;
+    Ok((image, format))
+}
+
+/// Width and height of an encoded image, read from its header
+pub fn image_dimensions(bytes: &[u8]) -> Result<(u32, u32)> {
+    ImageReader::with_format(Cursor::new(bytes), image_format(bytes)?)
+        .into_dimensions()
+        .map_err(|e| anyhow::anyhow!("Cannot decode image: {}", e))
+}
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register image operators when implemented
-    // transformer::register(registry);
-    // filter::register(registry);
-    // annotator::register(registry);
+/// Lowercase name of an image format ("png", "jpeg", "webp", ...)
+pub fn format_name(format: ImageFormat) -> String {
+    format!("{:?}", format).to_lowercase()
 }
@@ -1,5 +1,7 @@
+pub mod resize;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register image transformers when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    resize::register(registry);
 }
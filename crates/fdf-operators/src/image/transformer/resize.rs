@@ -0,0 +1,186 @@
+use crate::image::{decode_image, format_name, image_bytes};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Jpeg, // Lossy, at `quality`; transparent pixels are blended onto white
+    Png,  // Lossless
+    Webp, // Lossless (the encoder has no lossy mode)
+}
+
+impl Format {
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Png => ImageFormat::Png,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+/// Resizes images to fit a maximum edge length (keeping their aspect ratio, never enlarging)
+/// and re-encodes them to JPEG, PNG or WebP, storing the new bytes in the sample to shrink
+/// multimodal datasets
+#[fdf_operator(
+    name = "image.resize",
+    category = "transformer",
+    build = "ResizeConfig::build"
+)]
+struct ResizeConfig {
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Binary field receiving the new image (a top-level field); image_col if unset
+    output_col: Option<String>,
+    /// Maximum width and height in pixels; images are only re-encoded if unset
+    max_edge: Option<u32>,
+    /// Output format: "jpeg", "png" or "webp"
+    #[param(default = "jpeg", type = "string")]
+    format: Format,
+    /// JPEG quality, from 1 to 100
+    #[param(default = 85)]
+    quality: u8,
+    /// Prefix of the fields of image.decode (<prefix>width, <prefix>height, <prefix>format),
+    /// updated when present
+    #[param(default = "image_")]
+    prefix: String,
+}
+
+impl ResizeConfig {
+    fn build(self) -> Result<Resize> {
+        if !(1..=100).contains(&self.quality) {
+            return Err(anyhow::anyhow!(
+                "{}: quality must be between 1 and 100",
+                Self::NAME
+            ));
+        }
+        if self.max_edge == Some(0) {
+            return Err(anyhow::anyhow!("{}: max_edge must be positive", Self::NAME));
+        }
+        let output_col = self.output_col.unwrap_or_else(|| self.image_col.clone());
+        if output_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: images are written to a top-level field",
+                Self::NAME
+            ));
+        }
+        Ok(Resize {
+            image_col: self.image_col,
+            path_col: self.path_col,
+            output_col,
+            max_edge: self.max_edge,
+            format: self.format,
+            quality: self.quality,
+            prefix: self.prefix,
+        })
+    }
+}
+
+pub struct Resize {
+    image_col: String,
+    path_col: Option<String>,
+    output_col: String,
+    max_edge: Option<u32>,
+    format: Format,
+    quality: u8,
+    prefix: String,
+}
+
+impl Resize {
+    /// Image fitted in max_edge x max_edge, or None if it already fits
+    fn fit(&self, image: &DynamicImage) -> Option<DynamicImage> {
+        let max_edge = self.max_edge?;
+        if image.width().max(image.height()) <= max_edge {
+            return None;
+        }
+        Some(image.resize(max_edge, max_edge, FilterType::Lanczos3))
+    }
+
+    /// Image encoded in the output format
+    pub fn encode(&self, image: &DynamicImage) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let encoded = match self.format {
+            Format::Jpeg => DynamicImage::ImageRgb8(blend_on_white(image))
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, self.quality)),
+            Format::Png => match image {
+                DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                    DynamicImage::ImageRgba8(image.to_rgba8())
+                        .write_with_encoder(PngEncoder::new(&mut bytes))
+                }
+                _ => image.write_with_encoder(PngEncoder::new(&mut bytes)),
+            },
+            Format::Webp if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_with_encoder(WebPEncoder::new_lossless(&mut bytes)),
+            Format::Webp => DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(WebPEncoder::new_lossless(&mut bytes)),
+        };
+        encoded.map_err(|e| anyhow::anyhow!("Cannot encode image: {}", e))?;
+        Ok(bytes)
+    }
+}
+
+/// RGB image with transparent pixels blended onto a white background
+fn blend_on_white(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend =
+            |c: u8| ((u16::from(c) * u16::from(a) + 255 * (255 - u16::from(a))) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+impl Operator for Resize {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let bytes = image_bytes(&sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, format) = decode_image(&bytes)?;
+        let resized = self.fit(&image);
+        let image = resized.as_ref().unwrap_or(&image);
+        ctx.metrics().increment("input_bytes", bytes.len() as u64);
+
+        // Images that fit and are already in the output format keep their bytes
+        let encoded = match (&resized, format == self.format.image_format()) {
+            (None, true) => bytes.into_owned(),
+            _ => self.encode(image)?,
+        };
+        if resized.is_some() {
+            ctx.metrics().increment("resized", 1);
+        }
+        ctx.metrics()
+            .increment("output_bytes", encoded.len() as u64);
+        sample.set_bytes(&self.output_col, encoded);
+
+        let updates = [
+            ("width", Value::from(image.width())),
+            ("height", Value::from(image.height())),
+            (
+                "format",
+                Value::from(format_name(self.format.image_format())),
+            ),
+        ];
+        for (name, value) in updates {
+            let field = format!("{}{}", self.prefix, name);
+            if sample.get_path(&field).is_some() {
+                sample.set_path(&field, value)?;
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.image_col);
+        Some(ColumnSpec::new().requires(input).produces(&self.output_col))
+    }
+}
@@ -1,7 +1,15 @@
+#[cfg(feature = "audio")]
 pub mod audio;
+pub mod code;
 pub mod common;
+#[cfg(feature = "image")]
 pub mod image;
+#[cfg(all(feature = "image", feature = "text-ml"))]
+pub mod multimodal;
+#[cfg(feature = "onnx")]
+pub mod onnx;
 pub mod text;
+#[cfg(feature = "video")]
 pub mod video;
 
 use fdf_sdk::OperatorRegistry;
@@ -10,11 +18,29 @@ pub fn register_all(registry: &mut OperatorRegistry) -> Result<(), fdf_sdk::Erro
     // Register common operators first (available for all modalities)
     common::register(registry);
 
-    // Register modality-specific operators
+    // Register modality-specific operators, as far as their features are enabled
     text::register(registry);
-    // TODO: Register other modalities when implemented
-    // image::register(registry);
-    // video::register(registry);
-    // audio::register(registry);
+    code::register(registry);
+    #[cfg(feature = "image")]
+    image::register(registry);
+    #[cfg(feature = "video")]
+    video::register(registry);
+    #[cfg(feature = "audio")]
+    audio::register(registry);
+    #[cfg(all(feature = "image", feature = "text-ml"))]
+    multimodal::register(registry);
     Ok(())
 }
+
+/// Optional operator groups compiled into this build (cargo features of fdf-operators)
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("text-ml", cfg!(feature = "text-ml")),
+        ("image", cfg!(feature = "image")),
+        ("audio", cfg!(feature = "audio")),
+        ("video", cfg!(feature = "video")),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect()
+}
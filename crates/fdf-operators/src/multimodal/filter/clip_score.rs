@@ -0,0 +1,217 @@
+use crate::image::clip::{image_embedding, normalize, text_embedding, Preprocess};
+use crate::image::{decode_image, image_bytes};
+use crate::onnx::OnnxModel;
+use crate::text::model::load_tokenizer;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::Tokenizer;
+
+/// Drops image-text pairs whose CLIP score, the cosine similarity of the image and caption
+/// embeddings, is below a threshold (LAION-style caption dataset cleaning). Image embeddings
+/// written by image.clip_embed are used when present
+#[fdf_operator(
+    name = "multimodal.clip_score_filter",
+    category = "filter",
+    build = "ClipScoreFilterConfig::build"
+)]
+struct ClipScoreFilterConfig {
+    /// Field holding the caption (dot path allowed)
+    #[param(default = "caption")]
+    text_col: String,
+    /// Binary field holding the encoded image
+    #[param(default = "image")]
+    image_col: String,
+    /// String field holding the path of the image file, read instead of image_col if set
+    path_col: Option<String>,
+    /// Field holding the image embedding of image.clip_embed (array or binary f32), used
+    /// when present
+    #[param(default = "clip_embedding")]
+    embedding_col: String,
+    /// Path of the ONNX CLIP text model (input_ids and attention_mask inputs, text_embeds
+    /// output)
+    text_model_path: String,
+    /// Path of the ONNX CLIP vision model, for images without an embedding
+    vision_model_path: Option<String>,
+    /// Path of the text model's tokenizer.json; tokenizer.json next to it if unset
+    tokenizer_path: Option<String>,
+    /// Maximum caption length in tokens (CLIP models have 77 positions)
+    #[param(default = 77)]
+    max_length: usize,
+    /// Width and height of the vision model input
+    #[param(default = 224)]
+    image_size: u32,
+    /// Per-channel mean of the image normalization (OpenAI CLIP values by default)
+    #[param(default = "[0.48145466, 0.4578275, 0.40821073]")]
+    mean: [f32; 3],
+    /// Per-channel standard deviation of the image normalization
+    #[param(default = "[0.26862954, 0.26130258, 0.27577711]")]
+    std: [f32; 3],
+    /// Minimum CLIP score kept (0.28 is the LAION-400M threshold for ViT-B/32)
+    #[param(default = 0.28)]
+    min_score: f64,
+    /// Field receiving the CLIP score of kept pairs
+    #[param(default = "clip_score")]
+    score_col: String,
+    /// Keep every pair and only write the score, for threshold sweeps
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl ClipScoreFilterConfig {
+    fn build(self) -> Result<ClipScoreFilter> {
+        if !(-1.0..=1.0).contains(&self.min_score) {
+            return Err(anyhow::anyhow!(
+                "{}: min_score must be between -1 and 1",
+                Self::NAME
+            ));
+        }
+        if self.max_length < 3 || self.image_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_length must be at least 3 and image_size positive",
+                Self::NAME
+            ));
+        }
+        if self.std.iter().any(|&std| std <= 0.0) {
+            return Err(anyhow::anyhow!("{}: std must be positive", Self::NAME));
+        }
+        let tokenizer_path = self.tokenizer_path.unwrap_or_else(|| {
+            let path = Path::new(&self.text_model_path).with_file_name("tokenizer.json");
+            path.to_string_lossy().into_owned()
+        });
+        Ok(ClipScoreFilter {
+            text_col: self.text_col,
+            image_col: self.image_col,
+            path_col: self.path_col,
+            embedding_col: self.embedding_col,
+            text_model_path: self.text_model_path,
+            vision_model_path: self.vision_model_path,
+            tokenizer_path,
+            max_length: self.max_length,
+            preprocess: Preprocess {
+                size: self.image_size,
+                mean: self.mean,
+                std: self.std,
+                crop: true,
+            },
+            min_score: self.min_score,
+            score_col: self.score_col,
+            annotate: self.annotate,
+            text_model: None,
+            vision_model: None,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct ClipScoreFilter {
+    text_col: String,
+    image_col: String,
+    path_col: Option<String>,
+    embedding_col: String,
+    text_model_path: String,
+    vision_model_path: Option<String>,
+    tokenizer_path: String,
+    max_length: usize,
+    preprocess: Preprocess,
+    min_score: f64,
+    score_col: String,
+    annotate: bool,
+    text_model: Option<Arc<OnnxModel>>,   // Loaded in open
+    vision_model: Option<Arc<OnnxModel>>, // Loaded in open if configured
+    tokenizer: Option<Tokenizer>,         // Loaded in open
+}
+
+impl ClipScoreFilter {
+    /// Image embedding stored by image.clip_embed, else computed by the vision model
+    fn image_embedding(&self, sample: &Sample) -> Result<Vec<f32>> {
+        if let Some(bytes) = sample.get_bytes(&self.embedding_col) {
+            return Ok(bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect());
+        }
+        if let Some(values) = sample
+            .get_path(&self.embedding_col)
+            .and_then(Value::as_array)
+        {
+            return values
+                .iter()
+                .map(|value| value.as_f64().map(|v| v as f32))
+                .collect::<Option<_>>()
+                .ok_or_else(|| anyhow::anyhow!("Invalid embedding in {}", self.embedding_col));
+        }
+        let Some(model) = &self.vision_model else {
+            return Err(anyhow::anyhow!(
+                "Missing image embedding field: {} (or set vision_model_path)",
+                self.embedding_col
+            ));
+        };
+        let bytes = image_bytes(sample, &self.image_col, self.path_col.as_deref())?;
+        let (image, _) = decode_image(&bytes)?;
+        image_embedding(model, &self.preprocess, &image)
+    }
+
+    /// Cosine similarity of the image and caption of a pair
+    pub fn score(&self, sample: &Sample) -> Result<f64> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let model = self.text_model.as_ref().expect("model is loaded in open");
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let mut text_embedding = text_embedding(model, tokenizer, text)?;
+        let mut image_embedding = self.image_embedding(sample)?;
+        if text_embedding.len() != image_embedding.len() {
+            return Err(anyhow::anyhow!(
+                "Image embedding has {} dimensions, text embedding {}",
+                image_embedding.len(),
+                text_embedding.len()
+            ));
+        }
+        normalize(&mut text_embedding);
+        normalize(&mut image_embedding);
+        Ok(text_embedding
+            .iter()
+            .zip(&image_embedding)
+            .map(|(t, i)| f64::from(t * i))
+            .sum())
+    }
+}
+
+impl Operator for ClipScoreFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = ClipScoreFilterConfig::NAME;
+        let fatal = |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", name, e));
+        self.text_model = Some(OnnxModel::load(&self.text_model_path, ctx).map_err(fatal)?);
+        if let Some(path) = &self.vision_model_path {
+            self.vision_model = Some(OnnxModel::load(path, ctx).map_err(fatal)?);
+        }
+        self.tokenizer =
+            Some(load_tokenizer(&self.tokenizer_path, self.max_length, ctx).map_err(fatal)?);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let score = self.score(&sample)?;
+        ctx.metrics().observe("clip_score", score);
+        if score < self.min_score && !self.annotate {
+            ctx.metrics().increment("rejected.min_score", 1);
+            return Ok(None);
+        }
+        sample.set_path(&self.score_col, Value::from(score))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // The image is only read for pairs without an embedding
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.score_col),
+        )
+    }
+}
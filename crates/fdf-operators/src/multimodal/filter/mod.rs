@@ -0,0 +1,7 @@
+pub mod clip_score;
+
+use fdf_sdk::OperatorRegistry;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    clip_score::register(registry);
+}
@@ -0,0 +1,9 @@
+//! Operators on image-text pairs (caption datasets), built with the image and text-ml features
+
+pub mod filter;
+
+use fdf_sdk::OperatorRegistry;
+
+pub fn register(registry: &mut OperatorRegistry) {
+    filter::register(registry);
+}
@@ -0,0 +1,126 @@
+//! ONNX models for model-based operators (NER, classifiers, embeddings)
+//!
+//! Inference uses ONNX Runtime, loaded at run time: the library is `libonnxruntime.so`
+//! (`.dylib`, `.dll`) next to the executable or on the library search path, or the file named by
+//! the `ORT_DYLIB_PATH` environment variable. Models are loaded once per run (context resource
+//! cache) and shared by the operators using them.
+
+use fdf_sdk::{Context, Result};
+use ort::session::{Session, SessionInputValue};
+use ort::value::{DynValue, Tensor};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+
+/// Input tensor of a model
+pub enum Input<'a> {
+    I64(Vec<usize>, Vec<i64>), // Shape and values
+    F32(Vec<usize>, Vec<f32>),
+    Value(&'a DynValue), // Output of an earlier run (see `run_values`), passed without a copy
+}
+
+/// Output tensor of a model, as f32 values
+pub struct Output {
+    pub shape: Vec<usize>,
+    pub values: Vec<f32>,
+}
+
+impl Output {
+    /// Values of an f32 tensor
+    pub fn from_value(value: &DynValue) -> Result<Self> {
+        let (shape, values) = value.try_extract_tensor::<f32>()?;
+        Ok(Output {
+            shape: shape.iter().map(|&d| d as usize).collect(),
+            values: values.to_vec(),
+        })
+    }
+}
+
+/// Loaded ONNX model
+pub struct OnnxModel {
+    session: Mutex<Session>, // Runs need exclusive access
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl OnnxModel {
+    /// Model at `path`, loaded once per run
+    pub fn load(path: &str, ctx: &Context) -> Result<Arc<Self>> {
+        ctx.resources()
+            .get_or_load(&format!("onnx:{}", path), || Self::open(path))
+    }
+
+    fn open(path: &str) -> Result<Self> {
+        if !std::path::Path::new(path).is_file() {
+            return Err(anyhow::anyhow!("ONNX model {} not found", path));
+        }
+        // ort panics when the ONNX Runtime library cannot be loaded
+        let session = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            Session::builder()?.commit_from_file(path)
+        }))
+        .map_err(|panic| {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default();
+            anyhow::anyhow!(
+                "Cannot load ONNX Runtime (install libonnxruntime or set ORT_DYLIB_PATH): {}",
+                message
+            )
+        })?
+        .map_err(|e| anyhow::anyhow!("Cannot load ONNX model {}: {}", path, e))?;
+        Ok(OnnxModel {
+            inputs: session.inputs.iter().map(|i| i.name.clone()).collect(),
+            outputs: session.outputs.iter().map(|o| o.name.clone()).collect(),
+            session: Mutex::new(session),
+        })
+    }
+
+    /// Names of the model inputs
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Names of the model outputs
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Run the model on named inputs; returns the outputs in model order
+    pub fn run(&self, inputs: Vec<(&str, Input)>) -> Result<Vec<Output>> {
+        self.run_values(inputs)?
+            .iter()
+            .zip(&self.outputs)
+            .map(|(value, name)| {
+                Output::from_value(value)
+                    .map_err(|e| anyhow::anyhow!("ONNX output {}: {}", name, e))
+            })
+            .collect()
+    }
+
+    /// Run the model on named inputs; returns the outputs in model order as ONNX Runtime
+    /// values, which later runs can take as inputs (`Input::Value`) without copying them
+    pub fn run_values(&self, inputs: Vec<(&str, Input)>) -> Result<Vec<DynValue>> {
+        let mut values: Vec<(String, SessionInputValue)> = Vec::with_capacity(inputs.len());
+        for (name, input) in inputs {
+            let value = match input {
+                Input::I64(shape, data) => Tensor::from_array((shape, data))?.into_dyn().into(),
+                Input::F32(shape, data) => Tensor::from_array((shape, data))?.into_dyn().into(),
+                Input::Value(value) => value.into(),
+            };
+            values.push((name.to_string(), value));
+        }
+        let mut session = self.session.lock().unwrap();
+        let mut outputs = session
+            .run(values)
+            .map_err(|e| anyhow::anyhow!("ONNX inference failed: {}", e))?;
+        self.outputs
+            .iter()
+            .map(|name| {
+                outputs
+                    .remove(name)
+                    .ok_or_else(|| anyhow::anyhow!("ONNX output {} is missing", name))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::Regex;
+
+/// Email addresses, plain or obfuscated ("jane [at] example [dot] com")
+const EMAIL: &str = r"(?i)\b[a-z0-9._%+\-]+@[a-z0-9\-]+(?:\.[a-z0-9\-]+)*\.[a-z]{2,}\b|\b[a-z0-9._%+\-]+\s*[\[(]at[\])]\s*[a-z0-9\-]+(?:\s*[\[(]dot[\])]\s*[a-z0-9\-]+)+\b";
+
+/// Phone numbers: an optional country code and area code, then groups of digits separated by
+/// spaces, dots or dashes
+const PHONE: &str = r"(?:\+\d{1,3}[\s.\-]?)?(?:\(\d{1,4}\)[\s.\-]?)?\d{2,4}(?:[\s.\-]\d{2,4}){1,4}";
+
+/// Digits of a phone number, so that short numbers are not counted
+const PHONE_DIGITS: std::ops::RangeInclusive<usize> = 7..=15;
+
+/// Numbers looking like phone numbers that are not: ISO dates and IPv4 addresses
+const NOT_PHONE: &str = r"^(?:\d{4}-\d{2}-\d{2}|\d{1,3}(?:\.\d{1,3}){3})$";
+
+/// URLs with a scheme or starting with www.
+const URL: &str = r#"(?i)\b(?:https?://|ftp://|www\.)[^\s<>"'()\[\]]+"#;
+
+/// Annotates documents with the number of email addresses, phone numbers and URLs they contain
+/// (without redacting them) and their density, a spamminess signal
+#[fdf_operator(
+    name = "text.contact_stats",
+    category = "annotator",
+    build = "ContactStatsConfig::build"
+)]
+struct ContactStatsConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Prefix of the fields written: <prefix>emails, <prefix>phones, <prefix>urls and
+    /// <prefix>density (contacts per 100 words)
+    #[param(default = "contact_")]
+    prefix: String,
+}
+
+impl ContactStatsConfig {
+    fn build(self) -> Result<ContactStats> {
+        Ok(ContactStats {
+            columns: ["emails", "phones", "urls", "density"]
+                .map(|name| format!("{}{}", self.prefix, name)),
+            text_col: self.text_col,
+            email: Regex::new(EMAIL)?,
+            phone: Regex::new(PHONE)?,
+            not_phone: Regex::new(NOT_PHONE)?,
+            url: Regex::new(URL)?,
+        })
+    }
+}
+
+pub struct ContactStats {
+    text_col: String,
+    columns: [String; 4], // Emails, phones, urls, density
+    email: Regex,
+    phone: Regex,
+    not_phone: Regex,
+    url: Regex,
+}
+
+/// Contacts found in a text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Contacts {
+    pub emails: usize,
+    pub phones: usize,
+    pub urls: usize,
+    pub density: f64, // Contacts per 100 words
+}
+
+impl ContactStats {
+    pub fn count(&self, text: &str) -> Contacts {
+        let emails = self.email.find_iter(text).count();
+        let urls = self.url.find_iter(text).count();
+        let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        let phones = self
+            .phone
+            .find_iter(text)
+            .filter(|m| {
+                // Whole numbers only, not part of a longer number, word or email address
+                let digits = m.as_str().chars().filter(char::is_ascii_digit).count();
+                PHONE_DIGITS.contains(&digits)
+                    && !is_word(text[..m.start()].chars().next_back())
+                    && !is_word(text[m.end()..].chars().next())
+                    && !self.not_phone.is_match(m.as_str())
+            })
+            .count();
+        let words = text.split_whitespace().count();
+        let density = if words == 0 {
+            0.0
+        } else {
+            (emails + phones + urls) as f64 * 100.0 / words as f64
+        };
+        Contacts {
+            emails,
+            phones,
+            urls,
+            density,
+        }
+    }
+}
+
+impl Operator for ContactStats {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let contacts = self.count(text);
+        let [emails, phones, urls, density] = &self.columns;
+        sample.set_path(emails, Value::from(contacts.emails))?;
+        sample.set_path(phones, Value::from(contacts.phones))?;
+        sample.set_path(urls, Value::from(contacts.urls))?;
+        sample.set_path(density, Value::from(contacts.density))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(self.columns.iter().fold(
+            ColumnSpec::new().requires(&self.text_col),
+            |columns, column| columns.produces(column),
+        ))
+    }
+}
@@ -0,0 +1,252 @@
+use crate::onnx::{OnnxModel, Output};
+use crate::text::model::{encode_windows, encoder_inputs, load_tokenizer, pad_batch};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::{Encoding, Tokenizer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pooling {
+    Mean, // Mean of the token embeddings (sentence-transformers default)
+    Cls,  // Embedding of the first token
+    Max,  // Maximum of each dimension over the tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    List,   // Array of numbers
+    Binary, // Binary field of little-endian f32 values
+}
+
+/// Annotates documents with an embedding computed by a local ONNX sentence-transformer
+/// (e.g. all-MiniLM-L6-v2 exported with optimum), for embedding-based clustering and
+/// deduplication or a vector database sink
+#[fdf_operator(
+    name = "text.embed",
+    category = "annotator",
+    build = "EmbedConfig::build"
+)]
+struct EmbedConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the ONNX model: token embeddings (last_hidden_state) as first output, or
+    /// pooled sentence embeddings
+    model_path: String,
+    /// Path of the model's tokenizer.json; tokenizer.json next to the model if unset
+    tokenizer_path: Option<String>,
+    /// Field receiving the embedding (a top-level field with format binary)
+    #[param(default = "embedding")]
+    embedding_col: String,
+    /// Pooling of the token embeddings: "mean", "cls" or "max"
+    #[param(default = "mean", type = "string")]
+    pooling: Pooling,
+    /// Scale embeddings to unit length (L2), so that dot products are cosine similarities
+    #[param(default = true)]
+    normalize: bool,
+    /// Tokens per model window, special tokens included
+    #[param(default = 512)]
+    max_length: usize,
+    /// Windows embedded per document, their embeddings being averaged; 1 embeds the beginning
+    /// of the text only
+    #[param(default = 1)]
+    max_windows: usize,
+    /// Windows run through the model at once
+    #[param(default = 16)]
+    batch_size: usize,
+    /// "list" writes an array of numbers, "binary" 4 little-endian bytes (f32) per dimension
+    #[param(default = "list", type = "string")]
+    format: Format,
+}
+
+impl EmbedConfig {
+    fn build(self) -> Result<Embed> {
+        if self.max_length < 3 {
+            return Err(anyhow::anyhow!(
+                "{}: max_length must be at least 3",
+                Self::NAME
+            ));
+        }
+        if self.max_windows == 0 || self.batch_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_windows and batch_size must be positive",
+                Self::NAME
+            ));
+        }
+        if self.format == Format::Binary && self.embedding_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: binary embeddings are written to a top-level field",
+                Self::NAME
+            ));
+        }
+        let tokenizer_path = self.tokenizer_path.unwrap_or_else(|| {
+            let path = Path::new(&self.model_path).with_file_name("tokenizer.json");
+            path.to_string_lossy().into_owned()
+        });
+        Ok(Embed {
+            text_col: self.text_col,
+            model_path: self.model_path,
+            tokenizer_path,
+            embedding_col: self.embedding_col,
+            pooling: self.pooling,
+            normalize: self.normalize,
+            max_length: self.max_length,
+            max_windows: self.max_windows,
+            batch_size: self.batch_size,
+            format: self.format,
+            model: None,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct Embed {
+    text_col: String,
+    model_path: String,
+    tokenizer_path: String,
+    embedding_col: String,
+    pooling: Pooling,
+    normalize: bool,
+    max_length: usize,
+    max_windows: usize,
+    batch_size: usize,
+    format: Format,
+    model: Option<Arc<OnnxModel>>, // Loaded in open
+    tokenizer: Option<Tokenizer>,  // Loaded in open
+}
+
+impl Embed {
+    /// Embedding of a text, averaged over its windows
+    pub fn embed(&self, text: &str) -> Result<Vec<f64>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let mut windows = encode_windows(tokenizer, text)?;
+        windows.truncate(self.max_windows);
+
+        let mut sum: Vec<f64> = Vec::new();
+        for batch in windows.chunks_mut(self.batch_size) {
+            pad_batch(tokenizer, batch);
+            let batch: Vec<_> = batch.iter().collect();
+            let outputs = model.run(encoder_inputs(model, &batch))?;
+            let output = outputs
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("Model has no output"))?;
+            for (i, encoding) in batch.iter().enumerate() {
+                let embedding = self.pool(output, i, encoding)?;
+                if sum.is_empty() {
+                    sum = vec![0.0; embedding.len()];
+                }
+                for (total, value) in sum.iter_mut().zip(embedding) {
+                    *total += value;
+                }
+            }
+        }
+        let mut embedding: Vec<f64> = sum
+            .into_iter()
+            .map(|total| total / windows.len() as f64)
+            .collect();
+        if self.normalize {
+            let norm = embedding.iter().map(|v| v * v).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                embedding.iter_mut().for_each(|v| *v /= norm);
+            }
+        }
+        Ok(embedding)
+    }
+
+    /// Embedding of the `index`-th window of a batch: pooled token embeddings of a
+    /// [batch, tokens, dimensions] output, or the row of a [batch, dimensions] output
+    fn pool(&self, output: &Output, index: usize, encoding: &Encoding) -> Result<Vec<f64>> {
+        match output.shape[..] {
+            [_, dimensions] => {
+                let row = &output.values[index * dimensions..(index + 1) * dimensions];
+                Ok(row.iter().map(|&v| f64::from(v)).collect())
+            }
+            [_, tokens, dimensions] if tokens == encoding.len() && dimensions > 0 => {
+                let window = &output.values[index * tokens * dimensions..][..tokens * dimensions];
+                let token_embeddings = window
+                    .chunks(dimensions)
+                    .zip(encoding.get_attention_mask())
+                    .filter(|(_, &mask)| mask == 1)
+                    .map(|(token, _)| token);
+                let mut pooled = vec![0.0; dimensions];
+                match self.pooling {
+                    Pooling::Cls => {
+                        let first = window.chunks(dimensions).next().unwrap_or_default();
+                        pooled = first.iter().map(|&v| f64::from(v)).collect();
+                    }
+                    Pooling::Mean => {
+                        let mut count = 0;
+                        for token in token_embeddings {
+                            for (total, &v) in pooled.iter_mut().zip(token) {
+                                *total += f64::from(v);
+                            }
+                            count += 1;
+                        }
+                        pooled.iter_mut().for_each(|v| *v /= count.max(1) as f64);
+                    }
+                    Pooling::Max => {
+                        pooled.fill(f64::NEG_INFINITY);
+                        for token in token_embeddings {
+                            for (max, &v) in pooled.iter_mut().zip(token) {
+                                *max = max.max(f64::from(v));
+                            }
+                        }
+                    }
+                }
+                Ok(pooled)
+            }
+            _ => Err(anyhow::anyhow!(
+                "Unexpected model output shape {:?} for {} tokens",
+                output.shape,
+                encoding.len()
+            )),
+        }
+    }
+}
+
+impl Operator for Embed {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let fatal = |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", EmbedConfig::NAME, e));
+        self.model = Some(OnnxModel::load(&self.model_path, ctx).map_err(fatal)?);
+        self.tokenizer =
+            Some(load_tokenizer(&self.tokenizer_path, self.max_length, ctx).map_err(fatal)?);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let embedding = self.embed(text)?;
+        match self.format {
+            Format::List => {
+                let values = embedding.into_iter().map(Value::from).collect();
+                sample.set_path(&self.embedding_col, Value::Array(values))?;
+            }
+            Format::Binary => {
+                let bytes: Vec<u8> = embedding
+                    .iter()
+                    .flat_map(|&v| (v as f32).to_le_bytes())
+                    .collect();
+                sample.set_bytes(&self.embedding_col, bytes);
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.embedding_col),
+        )
+    }
+}
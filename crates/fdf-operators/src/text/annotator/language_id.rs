@@ -0,0 +1,239 @@
+#[cfg(feature = "text-ml")]
+use crate::text::fasttext::FastTextModel;
+#[cfg(feature = "text-ml")]
+use fdf_sdk::OpError;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use serde::Deserialize;
+#[cfg(feature = "text-ml")]
+use std::sync::Arc;
+
+/// Code written when no language is detected (ISO 639-2 "undetermined")
+pub const UNDETERMINED: &str = "und";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Whatlang, // Bundled pure-Rust detector (69 languages)
+    Fasttext, // User-supplied fasttext language id model, e.g. lid.176.bin
+}
+
+/// Annotates each document with its language (ISO 639-1 code where one exists, as in fasttext
+/// lid.176; "und" when undetected) and the detector's confidence
+#[fdf_operator(
+    name = "text.language_id",
+    category = "annotator",
+    build = "LanguageIdConfig::build"
+)]
+struct LanguageIdConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Detector: "whatlang" (bundled) or "fasttext" (needs model_path and the text-ml feature)
+    #[param(default = "whatlang", type = "string")]
+    backend: Backend,
+    /// Path of the fasttext language id model (.bin), e.g. lid.176.bin; labels are written
+    /// without their "__label__" prefix
+    model_path: Option<String>,
+    /// Field to write the language code to
+    #[param(default = "language")]
+    language_col: String,
+    /// Field to write the confidence (0 to 1) to
+    #[param(default = "language_score")]
+    score_col: String,
+}
+
+impl LanguageIdConfig {
+    fn build(self) -> Result<LanguageId> {
+        Ok(LanguageId {
+            detector: Detector::new(Self::NAME, self.backend, self.model_path)?,
+            text_col: self.text_col,
+            language_col: self.language_col,
+            score_col: self.score_col,
+        })
+    }
+}
+
+pub struct LanguageId {
+    text_col: String,
+    language_col: String,
+    score_col: String,
+    detector: Detector,
+}
+
+impl Operator for LanguageId {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        self.detector.open(ctx, LanguageIdConfig::NAME)
+    }
+
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+
+        let (language, score) = self.detector.detect(text);
+        sample.set_path(&self.language_col, Value::String(language))?;
+        sample.set_path(&self.score_col, Value::from(score))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.language_col)
+                .produces(&self.score_col),
+        )
+    }
+}
+
+/// Language detection with one of the backends, shared by the language operators
+pub(crate) struct Detector {
+    backend: Backend,
+    #[cfg(feature = "text-ml")]
+    model_path: Option<String>,
+    #[cfg(feature = "text-ml")]
+    model: Option<Arc<FastTextModel>>, // Loaded in open
+}
+
+impl Detector {
+    /// Check the backend settings of `operator`
+    pub(crate) fn new(
+        operator: &str,
+        backend: Backend,
+        model_path: Option<String>,
+    ) -> Result<Self> {
+        match (backend, &model_path) {
+            (Backend::Fasttext, None) => Err(anyhow::anyhow!(
+                "{}: backend 'fasttext' needs model_path",
+                operator
+            )),
+            (Backend::Fasttext, Some(_)) if !cfg!(feature = "text-ml") => Err(anyhow::anyhow!(
+                "{}: backend 'fasttext' needs fdf built with the text-ml feature",
+                operator
+            )),
+            (Backend::Whatlang, Some(_)) => Err(anyhow::anyhow!(
+                "{}: model_path is only used by backend 'fasttext'",
+                operator
+            )),
+            _ => Ok(Self {
+                backend,
+                #[cfg(feature = "text-ml")]
+                model_path,
+                #[cfg(feature = "text-ml")]
+                model: None,
+            }),
+        }
+    }
+
+    /// Load the fasttext model, if any (a missing or invalid model aborts the run)
+    #[cfg_attr(not(feature = "text-ml"), allow(unused_variables))]
+    pub(crate) fn open(&mut self, ctx: &Context, operator: &str) -> Result<()> {
+        #[cfg(feature = "text-ml")]
+        if let (Backend::Fasttext, Some(path)) = (self.backend, &self.model_path) {
+            let model = FastTextModel::shared(ctx, path)
+                .map_err(|e| OpError::fatal(format!("{}: {:#}", operator, e)))?;
+            self.model = Some(model);
+        }
+        Ok(())
+    }
+
+    /// Language code and confidence of a text
+    pub(crate) fn detect(&self, text: &str) -> (String, f64) {
+        if text.trim().is_empty() {
+            return (UNDETERMINED.to_string(), 0.0);
+        }
+        match self.backend {
+            Backend::Whatlang => match whatlang::detect(text) {
+                Some(info) => (iso639_1(info.lang()).to_string(), info.confidence()),
+                None => (UNDETERMINED.to_string(), 0.0),
+            },
+            #[cfg(feature = "text-ml")]
+            Backend::Fasttext => {
+                let model = self.model.as_ref().expect("model is loaded in open");
+                match model.predict(text, 1, 0.0).into_iter().next() {
+                    Some((label, probability)) => (label, f64::from(probability)),
+                    None => (UNDETERMINED.to_string(), 0.0),
+                }
+            }
+            #[cfg(not(feature = "text-ml"))]
+            Backend::Fasttext => unreachable!("rejected by Detector::new"),
+        }
+    }
+}
+
+/// Two-letter code of a whatlang language (the codes used by fasttext lid.176)
+fn iso639_1(lang: whatlang::Lang) -> &'static str {
+    use whatlang::Lang::*;
+    match lang {
+        Afr => "af",
+        Aka => "ak",
+        Amh => "am",
+        Ara => "ar",
+        Aze => "az",
+        Bel => "be",
+        Ben => "bn",
+        Bul => "bg",
+        Cat => "ca",
+        Ces => "cs",
+        Cmn => "zh",
+        Dan => "da",
+        Deu => "de",
+        Ell => "el",
+        Eng => "en",
+        Epo => "eo",
+        Est => "et",
+        Fin => "fi",
+        Fra => "fr",
+        Guj => "gu",
+        Heb => "he",
+        Hin => "hi",
+        Hrv => "hr",
+        Hun => "hu",
+        Hye => "hy",
+        Ind => "id",
+        Ita => "it",
+        Jav => "jv",
+        Jpn => "ja",
+        Kan => "kn",
+        Kat => "ka",
+        Khm => "km",
+        Kor => "ko",
+        Lat => "la",
+        Lav => "lv",
+        Lit => "lt",
+        Mal => "ml",
+        Mar => "mr",
+        Mkd => "mk",
+        Mya => "my",
+        Nep => "ne",
+        Nld => "nl",
+        Nob => "no",
+        Ori => "or",
+        Pan => "pa",
+        Pes => "fa",
+        Pol => "pl",
+        Por => "pt",
+        Ron => "ro",
+        Rus => "ru",
+        Sin => "si",
+        Slk => "sk",
+        Slv => "sl",
+        Sna => "sn",
+        Spa => "es",
+        Srp => "sr",
+        Swe => "sv",
+        Tam => "ta",
+        Tel => "te",
+        Tgl => "tl",
+        Tha => "th",
+        Tuk => "tk",
+        Tur => "tr",
+        Ukr => "uk",
+        Urd => "ur",
+        Uzb => "uz",
+        Vie => "vi",
+        Yid => "yi",
+        Zul => "zu",
+    }
+}
@@ -0,0 +1,212 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, SeededRng, Value};
+use serde::Deserialize;
+
+/// Mersenne prime 2^61 - 1, modulus of the permutations
+const PRIME: u64 = (1 << 61) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Word, // Shingles of consecutive words
+    Char, // Shingles of consecutive characters
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    List,   // Array of integers
+    Binary, // Binary field of little-endian u32 values
+}
+
+/// Annotates documents with their MinHash signature, so that signatures computed once (and
+/// stored, e.g. in Parquet) can be reused by several near-duplicate detection runs.
+/// Signatures are only comparable when computed with the same num_perm, shingle_size, unit,
+/// lowercase and seed.
+#[fdf_operator(
+    name = "text.minhash_signature",
+    category = "annotator",
+    build = "MinHashSignatureConfig::build"
+)]
+struct MinHashSignatureConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field receiving the signature (a top-level field with format binary)
+    #[param(default = "minhash")]
+    signature_col: String,
+    /// Number of permutations, the length of the signature
+    #[param(default = 128)]
+    num_perm: usize,
+    /// Number of units per shingle
+    #[param(default = 5)]
+    shingle_size: usize,
+    /// Shingle units: "word" (whitespace-separated) or "char"
+    #[param(default = "word", type = "string")]
+    unit: Unit,
+    /// Lowercase the text before shingling
+    #[param(default = true)]
+    lowercase: bool,
+    /// Seed of the permutations; fixed by default so that signatures of different runs match
+    #[param(default = 1)]
+    seed: u64,
+    /// "list" writes an array of integers, "binary" 4 little-endian bytes per permutation
+    #[param(default = "list", type = "string")]
+    format: Format,
+}
+
+impl MinHashSignatureConfig {
+    fn build(self) -> Result<MinHashSignature> {
+        if self.num_perm == 0 || self.shingle_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: num_perm and shingle_size must be positive",
+                Self::NAME
+            ));
+        }
+        if self.format == Format::Binary && self.signature_col.contains('.') {
+            return Err(anyhow::anyhow!(
+                "{}: binary signatures are written to a top-level field",
+                Self::NAME
+            ));
+        }
+        Ok(MinHashSignature {
+            minhash: MinHash::new(self.num_perm, self.seed),
+            text_col: self.text_col,
+            signature_col: self.signature_col,
+            shingle_size: self.shingle_size,
+            unit: self.unit,
+            lowercase: self.lowercase,
+            format: self.format,
+        })
+    }
+}
+
+pub struct MinHashSignature {
+    text_col: String,
+    signature_col: String,
+    shingle_size: usize,
+    unit: Unit,
+    lowercase: bool,
+    format: Format,
+    minhash: MinHash,
+}
+
+/// MinHash with universal hash permutations (a * x + b) mod 2^61 - 1, as in datasketch
+pub struct MinHash {
+    permutations: Vec<(u64, u64)>, // (a, b)
+}
+
+impl MinHash {
+    pub fn new(num_perm: usize, seed: u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        let permutations = (0..num_perm)
+            .map(|_| (1 + rng.below(PRIME - 1), rng.below(PRIME)))
+            .collect();
+        MinHash { permutations }
+    }
+
+    /// Signature of a set of 32-bit shingle hashes; u32::MAX everywhere for an empty set
+    pub fn signature(&self, hashes: &[u32]) -> Vec<u32> {
+        self.permutations
+            .iter()
+            .map(|&(a, b)| {
+                hashes
+                    .iter()
+                    .map(|&hash| {
+                        let permuted = (a as u128 * hash as u128 + b as u128) % PRIME as u128;
+                        permuted as u32
+                    })
+                    .min()
+                    .unwrap_or(u32::MAX)
+            })
+            .collect()
+    }
+}
+
+/// Estimated Jaccard similarity of two signatures: the fraction of equal values
+pub fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let equal = a.iter().zip(b).filter(|(a, b)| a == b).count();
+    equal as f64 / a.len() as f64
+}
+
+impl MinHashSignature {
+    /// 32-bit hashes of the shingles of a text; a text shorter than a shingle is one shingle
+    fn shingle_hashes(&self, text: &str) -> Vec<u32> {
+        let lower;
+        let text = if self.lowercase {
+            lower = text.to_lowercase();
+            &lower
+        } else {
+            text
+        };
+        // Byte ranges of the units
+        let units: Vec<(usize, usize)> = match self.unit {
+            Unit::Word => text
+                .split_whitespace()
+                .map(|word| {
+                    let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                    (start, start + word.len())
+                })
+                .collect(),
+            Unit::Char => text
+                .char_indices()
+                .map(|(i, c)| (i, i + c.len_utf8()))
+                .collect(),
+        };
+        let hash = |units: &[(usize, usize)]| {
+            let mut shingle = String::new();
+            for (i, &(start, end)) in units.iter().enumerate() {
+                if i > 0 && self.unit == Unit::Word {
+                    shingle.push(' '); // Words are compared whatever the whitespace between them
+                }
+                shingle.push_str(&text[start..end]);
+            }
+            xxhash_rust::xxh3::xxh3_64(shingle.as_bytes()) as u32
+        };
+        if units.is_empty() {
+            return Vec::new();
+        }
+        if units.len() < self.shingle_size {
+            return vec![hash(&units)];
+        }
+        let mut hashes: Vec<u32> = units.windows(self.shingle_size).map(hash).collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes
+    }
+
+    pub fn signature(&self, text: &str) -> Vec<u32> {
+        self.minhash.signature(&self.shingle_hashes(text))
+    }
+}
+
+impl Operator for MinHashSignature {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let signature = self.signature(text);
+        match self.format {
+            Format::List => {
+                let values = signature.into_iter().map(Value::from).collect();
+                sample.set_path(&self.signature_col, Value::Array(values))?;
+            }
+            Format::Binary => {
+                let bytes: Vec<u8> = signature.iter().flat_map(|v| v.to_le_bytes()).collect();
+                sample.set_bytes(&self.signature_col, bytes);
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.signature_col),
+        )
+    }
+}
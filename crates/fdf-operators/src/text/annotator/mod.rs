@@ -1,5 +1,23 @@
+pub mod contact_stats;
+#[cfg(feature = "text-ml")]
+pub mod embed;
+pub mod language_id;
+pub mod minhash_signature;
+pub mod ngram_overlap;
+pub mod quality_score;
+pub mod sentence_split;
+pub mod textstat;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register text annotators when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    language_id::register(registry);
+    sentence_split::register(registry);
+    contact_stats::register(registry);
+    textstat::register(registry);
+    minhash_signature::register(registry);
+    ngram_overlap::register(registry);
+    quality_score::register(registry);
+    #[cfg(feature = "text-ml")]
+    embed::register(registry);
 }
@@ -0,0 +1,199 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Annotates documents with their n-gram overlap with reference texts (evaluation benchmarks):
+/// the largest fraction of a reference's n-grams found in the document, to choose a
+/// decontamination threshold before filtering
+#[fdf_operator(
+    name = "text.ngram_overlap",
+    category = "annotator",
+    build = "NgramOverlapConfig::build"
+)]
+struct NgramOverlapConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Reference files: one reference per line, or JSONL (.jsonl) with the reference in
+    /// reference_col
+    reference_paths: Vec<String>,
+    /// Field holding the reference in JSONL reference files (dot path allowed)
+    #[param(default = "text")]
+    reference_col: String,
+    /// Words per n-gram; references with fewer words are skipped
+    #[param(default = 13)]
+    n: usize,
+    /// Field receiving the largest overlap fraction (0 to 1)
+    #[param(default = "ngram_overlap")]
+    overlap_col: String,
+    /// Field receiving the reference with the largest overlap ("<file>:<line>", null without
+    /// overlap), if set
+    reference_id_col: Option<String>,
+}
+
+impl NgramOverlapConfig {
+    fn build(self) -> Result<NgramOverlap> {
+        if self.reference_paths.is_empty() {
+            return Err(anyhow::anyhow!("{}: reference_paths is empty", Self::NAME));
+        }
+        if self.n == 0 {
+            return Err(anyhow::anyhow!("{}: n must be positive", Self::NAME));
+        }
+        Ok(NgramOverlap {
+            text_col: self.text_col,
+            reference_paths: self.reference_paths,
+            reference_col: self.reference_col,
+            n: self.n,
+            overlap_col: self.overlap_col,
+            reference_id_col: self.reference_id_col,
+            index: None,
+        })
+    }
+}
+
+pub struct NgramOverlap {
+    text_col: String,
+    reference_paths: Vec<String>,
+    reference_col: String,
+    n: usize,
+    overlap_col: String,
+    reference_id_col: Option<String>,
+    index: Option<Arc<ReferenceIndex>>, // Loaded in open
+}
+
+/// N-grams of the references
+pub struct ReferenceIndex {
+    ngrams: HashMap<u64, Vec<u32>>, // N-gram hash to the references containing it
+    references: Vec<(String, usize)>, // Id and number of distinct n-grams
+}
+
+impl ReferenceIndex {
+    fn load(paths: &[String], reference_col: &str, n: usize) -> Result<Self> {
+        let mut index = ReferenceIndex {
+            ngrams: HashMap::new(),
+            references: Vec::new(),
+        };
+        for path in paths {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read reference file {}: {}", path, e))?;
+            let jsonl = path.ends_with(".jsonl");
+            for (line_idx, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let text = if jsonl {
+                    let record: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+                        anyhow::anyhow!("Invalid JSON in {} line {}: {}", path, line_idx + 1, e)
+                    })?;
+                    Sample::from_value(record)
+                        .and_then(|record| {
+                            record
+                                .get_path(reference_col)
+                                .and_then(Value::as_str)
+                                .map(str::to_string)
+                        })
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Missing {} in {} line {}",
+                                reference_col,
+                                path,
+                                line_idx + 1
+                            )
+                        })?
+                } else {
+                    line.to_string()
+                };
+                let ngrams = ngram_hashes(&text, n);
+                if ngrams.is_empty() {
+                    continue; // Shorter than an n-gram
+                }
+                let reference = index.references.len() as u32;
+                for &ngram in &ngrams {
+                    index.ngrams.entry(ngram).or_default().push(reference);
+                }
+                index
+                    .references
+                    .push((format!("{}:{}", path, line_idx + 1), ngrams.len()));
+            }
+        }
+        Ok(index)
+    }
+
+    /// Largest fraction of a reference's n-grams among `ngrams`, with that reference's id
+    pub fn max_overlap(&self, ngrams: &HashSet<u64>) -> Option<(f64, &str)> {
+        let mut shared: HashMap<u32, usize> = HashMap::new();
+        for ngram in ngrams {
+            for &reference in self.ngrams.get(ngram).into_iter().flatten() {
+                *shared.entry(reference).or_default() += 1;
+            }
+        }
+        shared
+            .into_iter()
+            .map(|(reference, count)| {
+                let (id, total) = &self.references[reference as usize];
+                (count as f64 / *total as f64, id.as_str())
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.cmp(a.1)))
+    }
+}
+
+/// Distinct hashes of the n-grams of lowercased words, punctuation stripped
+fn ngram_hashes(text: &str, n: usize) -> HashSet<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    words
+        .windows(n)
+        .map(|ngram| xxhash_rust::xxh3::xxh3_64(ngram.join(" ").as_bytes()))
+        .collect()
+}
+
+impl Operator for NgramOverlap {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let key = format!(
+            "ngram_overlap:{}:{}:{}",
+            self.reference_paths.join("\n"),
+            self.reference_col,
+            self.n
+        );
+        let index = ctx
+            .resources()
+            .get_or_load(&key, || {
+                ReferenceIndex::load(&self.reference_paths, &self.reference_col, self.n)
+            })
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", NgramOverlapConfig::NAME, e)))?;
+        self.index = Some(index);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let index = self.index.as_ref().expect("index is loaded in open");
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let overlap = index.max_overlap(&ngram_hashes(text, self.n));
+        let (fraction, reference) = match overlap {
+            Some((fraction, reference)) => (fraction, Value::from(reference)),
+            None => (0.0, Value::Null),
+        };
+        ctx.metrics().observe("overlap", fraction);
+        if let Some(reference_id_col) = &self.reference_id_col {
+            sample.set_path(reference_id_col, reference)?;
+        }
+        sample.set_path(&self.overlap_col, Value::from(fraction))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(&self.overlap_col);
+        Some(match &self.reference_id_col {
+            Some(reference_id_col) => columns.produces(reference_id_col),
+            None => columns,
+        })
+    }
+}
@@ -0,0 +1,240 @@
+use crate::text::filter::gopher::{Document, DuplicateNgram, DuplicateUnits, TopNgram};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+
+/// Signals combined into the score
+const SIGNALS: &[&str] = &[
+    "symbol_ratio",
+    "stop_words",
+    "alpha_words",
+    "repetition",
+    "perplexity",
+];
+
+/// Symbols ('#', ellipses) per word at which the symbol signal is 0 (the Gopher threshold)
+const MAX_SYMBOL_RATIO: f64 = 0.1;
+/// Distinct English stop words at which the stop word signal is 1 (the Gopher threshold)
+const MIN_STOP_WORDS: f64 = 2.0;
+/// Fraction of words with a letter at which the alphabetic signal is 1 (the Gopher threshold)
+const MIN_ALPHA_WORDS_RATIO: f64 = 0.8;
+
+/// Annotates documents with a quality score in [0, 1]: the weighted mean of signals scored
+/// from 0 (bad) to 1 (good), for score-based sampling instead of cascading hard filters
+#[fdf_operator(
+    name = "text.quality_score",
+    category = "annotator",
+    build = "QualityScoreConfig::build"
+)]
+struct QualityScoreConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Weight of each signal: symbol_ratio ('#' and ellipses per word), stop_words (distinct
+    /// English stop words), alpha_words (words with a letter), repetition (Gopher repeated
+    /// lines and n-grams), perplexity (from perplexity_col); 0 disables a signal
+    #[param(
+        default = "{symbol_ratio: 1.0, stop_words: 1.0, alpha_words: 1.0, repetition: 1.0, perplexity: 1.0}"
+    )]
+    weights: BTreeMap<String, f64>,
+    /// Field holding a language model perplexity (e.g. of a KenLM model); the perplexity
+    /// signal is left out of the mean of documents without it
+    #[param(default = "perplexity")]
+    perplexity_col: String,
+    /// Perplexities scoring 1 and 0 (log scale between them)
+    #[param(default = "[100.0, 1000.0]")]
+    perplexity_range: (f64, f64),
+    /// Field receiving the score
+    #[param(default = "quality_score")]
+    score_col: String,
+    /// Field receiving the score of each signal, if set
+    signals_col: Option<String>,
+}
+
+impl QualityScoreConfig {
+    fn build(self) -> Result<QualityScore> {
+        for (signal, weight) in &self.weights {
+            if !SIGNALS.contains(&signal.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "{}: unknown signal {} (expected one of {})",
+                    Self::NAME,
+                    signal,
+                    SIGNALS.join(", ")
+                ));
+            }
+            if !weight.is_finite() || *weight < 0.0 {
+                return Err(anyhow::anyhow!(
+                    "{}: weight of {} must be non-negative",
+                    Self::NAME,
+                    signal
+                ));
+            }
+        }
+        if self.weights.values().all(|&weight| weight == 0.0) {
+            return Err(anyhow::anyhow!("{}: every weight is 0", Self::NAME));
+        }
+        let (best, worst) = self.perplexity_range;
+        if !(best > 0.0 && best < worst) {
+            return Err(anyhow::anyhow!(
+                "{}: perplexity_range must be [best, worst] with 0 < best < worst",
+                Self::NAME
+            ));
+        }
+        Ok(QualityScore {
+            text_col: self.text_col,
+            weights: self
+                .weights
+                .into_iter()
+                .filter(|&(_, weight)| weight > 0.0)
+                .collect(),
+            perplexity_col: self.perplexity_col,
+            perplexity_range: self.perplexity_range,
+            score_col: self.score_col,
+            signals_col: self.signals_col,
+            repetition: Repetition::default(),
+        })
+    }
+}
+
+pub struct QualityScore {
+    text_col: String,
+    weights: BTreeMap<String, f64>, // Positive weights only
+    perplexity_col: String,
+    perplexity_range: (f64, f64),
+    score_col: String,
+    signals_col: Option<String>,
+    repetition: Repetition,
+}
+
+/// Gopher repetition rules with their default thresholds
+struct Repetition {
+    lines: DuplicateUnits,
+    paragraphs: DuplicateUnits,
+    top_ngrams: Vec<TopNgram>,
+    dup_ngrams: Vec<DuplicateNgram>,
+}
+
+impl Default for Repetition {
+    fn default() -> Self {
+        let units = |paragraphs| DuplicateUnits {
+            paragraphs,
+            max_dup_frac: 0.3,
+            max_dup_char_frac: 0.2,
+        };
+        Repetition {
+            lines: units(false),
+            paragraphs: units(true),
+            top_ngrams: [(2, 0.2), (3, 0.18), (4, 0.16)]
+                .map(|(n, max_char_frac)| TopNgram { n, max_char_frac })
+                .into(),
+            dup_ngrams: [
+                (5, 0.15),
+                (6, 0.14),
+                (7, 0.13),
+                (8, 0.12),
+                (9, 0.11),
+                (10, 0.1),
+            ]
+            .map(|(n, max_char_frac)| DuplicateNgram { n, max_char_frac })
+            .into(),
+        }
+    }
+}
+
+impl Repetition {
+    /// Largest statistic relative to its threshold (1 at the threshold)
+    fn worst(&self, doc: &Document) -> f64 {
+        let mut ratios = Vec::new();
+        for units in [&self.lines, &self.paragraphs] {
+            let (frac, char_frac) = units.fractions(doc);
+            ratios.push(frac / units.max_dup_frac);
+            ratios.push(char_frac / units.max_dup_char_frac);
+        }
+        for rule in &self.top_ngrams {
+            ratios.push(rule.fraction(doc) / rule.max_char_frac);
+        }
+        for rule in &self.dup_ngrams {
+            ratios.push(rule.fraction(doc) / rule.max_char_frac);
+        }
+        ratios.into_iter().fold(0.0, f64::max)
+    }
+}
+
+impl QualityScore {
+    /// Score of each weighted signal, from 0 (bad) to 1 (good); perplexity is None when the
+    /// sample has none
+    pub fn signals(&self, text: &str, perplexity: Option<f64>) -> BTreeMap<&str, Option<f64>> {
+        let doc = Document::new(text);
+        self.weights
+            .keys()
+            .map(|signal| {
+                let score = match signal.as_str() {
+                    "symbol_ratio" => {
+                        let stats = doc.quality();
+                        let ratio = stats.hash_ratio + stats.ellipsis_ratio;
+                        Some(1.0 - ratio / MAX_SYMBOL_RATIO)
+                    }
+                    "stop_words" => Some(doc.quality().stop_words as f64 / MIN_STOP_WORDS),
+                    "alpha_words" => Some(doc.quality().alpha_words_ratio / MIN_ALPHA_WORDS_RATIO),
+                    "repetition" => Some(1.0 - self.repetition.worst(&doc)),
+                    "perplexity" => perplexity.map(|perplexity| {
+                        let (best, worst) = self.perplexity_range;
+                        (worst.ln() - perplexity.max(f64::MIN_POSITIVE).ln())
+                            / (worst.ln() - best.ln())
+                    }),
+                    _ => unreachable!("signals are checked in build"),
+                };
+                (signal.as_str(), score.map(|score| score.clamp(0.0, 1.0)))
+            })
+            .collect()
+    }
+
+    /// Weighted mean of the available signals
+    pub fn score(&self, signals: &BTreeMap<&str, Option<f64>>) -> f64 {
+        let (sum, weights) = signals
+            .iter()
+            .filter_map(|(signal, score)| Some((self.weights[*signal], (*score)?)))
+            .fold((0.0, 0.0), |(sum, weights), (weight, score)| {
+                (sum + weight * score, weights + weight)
+            });
+        if weights == 0.0 {
+            0.0
+        } else {
+            sum / weights
+        }
+    }
+}
+
+impl Operator for QualityScore {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let perplexity = sample
+            .get_path(&self.perplexity_col)
+            .and_then(Value::as_f64);
+        let signals = self.signals(text, perplexity);
+        let score = self.score(&signals);
+        ctx.metrics().observe("quality_score", score);
+
+        if let Some(signals_col) = &self.signals_col {
+            let signals = signals
+                .iter()
+                .map(|(signal, score)| (signal.to_string(), score.map_or(Value::Null, Value::from)))
+                .collect();
+            sample.set_path(signals_col, Value::Object(signals))?;
+        }
+        sample.set_path(&self.score_col, Value::from(score))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(&self.score_col);
+        Some(match &self.signals_col {
+            Some(signals_col) => columns.produces(signals_col),
+            None => columns,
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use crate::text::sentences::split_sentences;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Splits the text into sentences (rule-based, with per-language abbreviations), written as an
+/// array of strings
+#[fdf_operator(name = "text.sentence_split", category = "annotator")]
+pub struct SentenceSplit {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field to write the sentences to
+    #[param(default = "sentences")]
+    sentences_col: String,
+    /// Language of the abbreviation rules (ISO 639-1: en, de, fr, es, it, pt, nl, ru; others
+    /// use language-independent rules only)
+    #[param(default = "en")]
+    language: String,
+    /// Field holding each document's language (e.g. from text.language_id), used instead of
+    /// language when present
+    language_col: Option<String>,
+    /// Also end a sentence at every line break (lists, headings, verse)
+    #[param(default = false)]
+    split_lines: bool,
+}
+
+impl Operator for SentenceSplit {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let language = self
+            .language_col
+            .as_ref()
+            .and_then(|col| sample.get_path(col))
+            .and_then(Value::as_str)
+            .unwrap_or(&self.language);
+
+        let sentences = split_sentences(text, language, self.split_lines)
+            .into_iter()
+            .map(|sentence| Value::String(sentence.to_string()))
+            .collect();
+        sample.set_path(&self.sentences_col, Value::Array(sentences))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.sentences_col),
+        )
+    }
+}
@@ -0,0 +1,238 @@
+use crate::text::sentences::split_sentences;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Counts written, as integers
+const COUNTS: &[&str] = &[
+    "sentences",
+    "words",
+    "syllables",
+    "polysyllables",
+    "difficult_words",
+];
+
+/// Readability scores written, rounded to 2 decimals
+const SCORES: &[&str] = &[
+    "flesch_reading_ease",
+    "flesch_kincaid_grade",
+    "automated_readability_index",
+    "coleman_liau_index",
+    "gunning_fog",
+    "smog_index",
+];
+
+/// Annotates documents with readability statistics (textstat): counts of sentences, words,
+/// syllables and difficult words, and the Flesch, Flesch-Kincaid, ARI, Coleman-Liau, Gunning
+/// fog and SMOG scores. Syllables are estimated with English rules.
+#[fdf_operator(
+    name = "text.textstat",
+    category = "annotator",
+    build = "TextStatConfig::build"
+)]
+struct TextStatConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Statistics written (sentences, words, syllables, polysyllables, difficult_words,
+    /// flesch_reading_ease, flesch_kincaid_grade, automated_readability_index,
+    /// coleman_liau_index, gunning_fog, smog_index); all if unset
+    metrics: Option<Vec<String>>,
+    /// Prefix of the fields written, e.g. "textstat_"
+    #[param(default = "''")]
+    prefix: String,
+}
+
+impl TextStatConfig {
+    fn build(self) -> Result<TextStat> {
+        let metrics = match self.metrics {
+            Some(metrics) => {
+                for metric in &metrics {
+                    if !COUNTS.contains(&metric.as_str()) && !SCORES.contains(&metric.as_str()) {
+                        return Err(anyhow::anyhow!(
+                            "{}: unknown metric {} (expected one of {}, {})",
+                            Self::NAME,
+                            metric,
+                            COUNTS.join(", "),
+                            SCORES.join(", ")
+                        ));
+                    }
+                }
+                metrics
+            }
+            None => COUNTS
+                .iter()
+                .chain(SCORES)
+                .map(|metric| metric.to_string())
+                .collect(),
+        };
+        Ok(TextStat {
+            text_col: self.text_col,
+            columns: metrics
+                .into_iter()
+                .map(|metric| (format!("{}{}", self.prefix, metric), metric))
+                .collect(),
+        })
+    }
+}
+
+pub struct TextStat {
+    text_col: String,
+    columns: Vec<(String, String)>, // Output field and metric
+}
+
+/// Readability statistics of a text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub sentences: usize,
+    pub words: usize,
+    pub letters: usize, // Letters and digits of the words
+    pub syllables: usize,
+    pub polysyllables: usize,   // Words of 3 syllables or more
+    pub difficult_words: usize, // Polysyllables except proper nouns, compounds and inflections
+}
+
+impl Stats {
+    pub fn of(text: &str) -> Self {
+        let mut stats = Stats {
+            sentences: split_sentences(text, "en", false).len(),
+            ..Stats::default()
+        };
+        for word in text.split_whitespace() {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if word.is_empty() {
+                continue;
+            }
+            let count = syllables(word);
+            stats.words += 1;
+            stats.letters += word.chars().filter(|c| c.is_alphanumeric()).count();
+            stats.syllables += count;
+            if count >= 3 {
+                stats.polysyllables += 1;
+                if is_difficult(word) {
+                    stats.difficult_words += 1;
+                }
+            }
+        }
+        if stats.words > 0 {
+            stats.sentences = stats.sentences.max(1);
+        }
+        stats
+    }
+
+    /// Value of a metric; formulas are None without words
+    pub fn metric(&self, metric: &str) -> Option<f64> {
+        let count = match metric {
+            "sentences" => Some(self.sentences),
+            "words" => Some(self.words),
+            "syllables" => Some(self.syllables),
+            "polysyllables" => Some(self.polysyllables),
+            "difficult_words" => Some(self.difficult_words),
+            _ => None,
+        };
+        if let Some(count) = count {
+            return Some(count as f64);
+        }
+        if self.words == 0 {
+            return None;
+        }
+        let words = self.words as f64;
+        let sentences = self.sentences as f64;
+        let words_per_sentence = words / sentences;
+        let syllables_per_word = self.syllables as f64 / words;
+        Some(match metric {
+            "flesch_reading_ease" => {
+                206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word
+            }
+            "flesch_kincaid_grade" => 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            "automated_readability_index" => {
+                4.71 * self.letters as f64 / words + 0.5 * words_per_sentence - 21.43
+            }
+            "coleman_liau_index" => {
+                let letters_per_100 = self.letters as f64 * 100.0 / words;
+                let sentences_per_100 = sentences * 100.0 / words;
+                0.0588 * letters_per_100 - 0.296 * sentences_per_100 - 15.8
+            }
+            "gunning_fog" => {
+                0.4 * (words_per_sentence + 100.0 * self.difficult_words as f64 / words)
+            }
+            "smog_index" => 1.043 * (self.polysyllables as f64 * 30.0 / sentences).sqrt() + 3.1291,
+            _ => unreachable!("metrics are checked in build"),
+        })
+    }
+}
+
+/// Estimated syllables of an English word: groups of vowels, less a silent final e and the
+/// silent e of -es / -ed endings; at least one
+pub fn syllables(word: &str) -> usize {
+    let word: Vec<u8> = word
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if word.len() <= 3 {
+        return 1;
+    }
+    let is_vowel = |i: usize| match word[i] {
+        b'a' | b'e' | b'i' | b'o' | b'u' => true,
+        b'y' => i > 0, // A leading y is a consonant ("yes")
+        _ => false,
+    };
+    let mut count = (0..word.len())
+        .filter(|&i| is_vowel(i) && (i == 0 || !is_vowel(i - 1)))
+        .count();
+
+    let n = word.len();
+    let silent = match &word[n - 2..] {
+        // "make", but not "table" or "free"
+        [c, b'e'] => (*c != b'l' || is_vowel(n - 3)) && !is_vowel(n - 2),
+        // "jumped", but not "wanted"
+        [b'e', b'd'] => !matches!(word[n - 3], b't' | b'd') && !is_vowel(n - 3),
+        // "makes", but not "boxes" or "pages"
+        [b'e', b's'] => {
+            !matches!(word[n - 3], b's' | b'x' | b'z' | b'c' | b'g' | b'h') && !is_vowel(n - 3)
+        }
+        _ => false,
+    };
+    if silent {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Whether a polysyllabic word is difficult (Gunning fog's complex words): not a proper noun
+/// or a compound, and still polysyllabic without an -es, -ed or -ing ending
+fn is_difficult(word: &str) -> bool {
+    if word.starts_with(char::is_uppercase) || word.contains('-') {
+        return false;
+    }
+    let stem = ["ing", "es", "ed"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    syllables(stem) >= 3
+}
+
+impl Operator for TextStat {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let stats = Stats::of(text);
+        for (column, metric) in &self.columns {
+            let value = match stats.metric(metric) {
+                Some(value) if COUNTS.contains(&metric.as_str()) => Value::from(value as u64),
+                Some(value) => Value::from((value * 100.0).round() / 100.0),
+                None => Value::Null,
+            };
+            sample.set_path(column, value)?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(self.columns.iter().fold(
+            ColumnSpec::new().requires(&self.text_col),
+            |columns, (column, _)| columns.produces(column),
+        ))
+    }
+}
@@ -0,0 +1,566 @@
+//! Reader for fasttext supervised models (`.bin`, e.g. lid.176.bin) and their predictions
+//!
+//! Follows the fasttext 0.9 file format and prediction path (`model.predict(text)`): tokens split
+//! on whitespace, word and character n-gram rows of the input matrix averaged, then softmax,
+//! one-vs-all or hierarchical softmax over the labels. Quantized models (`.ftz`) are not
+//! supported.
+
+use fdf_sdk::{Context, Result};
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: i32 = 793712314;
+const VERSION: i32 = 12;
+const EOS: &str = "</s>";
+const BOW: &str = "<";
+const EOW: &str = ">";
+const LABEL_PREFIX: &str = "__label__";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Loss {
+    HierarchicalSoftmax,
+    NegativeSampling,
+    Softmax,
+    OneVsAll,
+}
+
+/// Node of the hierarchical softmax tree (leaves are the labels)
+#[derive(Debug, Clone)]
+struct Node {
+    left: i32,
+    right: i32,
+    count: i64,
+}
+
+/// Loaded fasttext supervised model
+pub struct FastTextModel {
+    dim: usize,
+    word_ngrams: i32,
+    loss: Loss,
+    bucket: i32,
+    minn: usize,
+    maxn: usize,
+    nwords: i32,
+    words: HashMap<String, i32>,         // Word -> id (labels excluded)
+    subwords: Vec<Vec<i32>>,             // Input rows of each word: itself and its n-grams
+    labels: Vec<String>,                 // Without the "__label__" prefix
+    pruneidx: Option<HashMap<i32, i32>>, // n-gram bucket -> row of pruned models
+    input: Vec<f32>,                     // (nwords + bucket) x dim
+    output: Vec<f32>,                    // nlabels x dim
+    tree: Vec<Node>,                     // Hierarchical softmax only
+}
+
+impl FastTextModel {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("Cannot open fasttext model {}: {}", path.display(), e))?;
+        Self::read(&mut BufReader::new(file))
+            .map_err(|e| e.context(format!("Invalid fasttext model {}", path.display())))
+    }
+
+    /// The model at `path`, loaded once per run and shared through the context's resource cache
+    pub fn shared(ctx: &Context, path: &str) -> Result<Arc<Self>> {
+        ctx.resources()
+            .get_or_load(&format!("fasttext:{}", path), || Self::load(path))
+    }
+
+    fn read(r: &mut impl Read) -> Result<Self> {
+        if read_i32(r)? != MAGIC {
+            return Err(anyhow::anyhow!("not a fasttext model (bad magic number)"));
+        }
+        let version = read_i32(r)?;
+        if version > VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported file format version {}",
+                version
+            ));
+        }
+
+        // Args
+        let dim = read_i32(r)? as usize;
+        let _ws = read_i32(r)?;
+        let _epoch = read_i32(r)?;
+        let _min_count = read_i32(r)?;
+        let _neg = read_i32(r)?;
+        let word_ngrams = read_i32(r)?;
+        let loss = match read_i32(r)? {
+            1 => Loss::HierarchicalSoftmax,
+            2 => Loss::NegativeSampling,
+            3 => Loss::Softmax,
+            4 => Loss::OneVsAll,
+            other => return Err(anyhow::anyhow!("unknown loss {}", other)),
+        };
+        if read_i32(r)? != 3 {
+            return Err(anyhow::anyhow!(
+                "not a supervised model (word vector models cannot classify)"
+            ));
+        }
+        let bucket = read_i32(r)?;
+        let minn = read_i32(r)?.max(0) as usize;
+        let mut maxn = read_i32(r)?.max(0) as usize;
+        let _lr_update_rate = read_i32(r)?;
+        let _t = read_f64(r)?;
+        if version == 11 {
+            maxn = 0; // Supervised models of format 11 have no subwords
+        }
+
+        // Dictionary
+        let size = read_i32(r)?;
+        let nwords = read_i32(r)?;
+        let nlabels = read_i32(r)?;
+        let _ntokens = read_i64(r)?;
+        let pruneidx_size = read_i64(r)?;
+        let mut entries = Vec::with_capacity(size.max(0) as usize);
+        for _ in 0..size {
+            let mut word = Vec::new();
+            loop {
+                let c = read_u8(r)?;
+                if c == 0 {
+                    break;
+                }
+                word.push(c);
+            }
+            let count = read_i64(r)?;
+            let is_label = read_u8(r)? == 1;
+            entries.push((String::from_utf8_lossy(&word).into_owned(), count, is_label));
+        }
+        let pruneidx = if pruneidx_size >= 0 {
+            let mut pruneidx = HashMap::new();
+            for _ in 0..pruneidx_size {
+                let (from, to) = (read_i32(r)?, read_i32(r)?);
+                pruneidx.insert(from, to);
+            }
+            Some(pruneidx)
+        } else {
+            None
+        };
+
+        // Matrices
+        if read_u8(r)? != 0 {
+            return Err(anyhow::anyhow!(
+                "quantized models (.ftz) are not supported, use the .bin model"
+            ));
+        }
+        let input = read_matrix(r, dim)?;
+        let _qout = read_u8(r)?;
+        let output = read_matrix(r, dim)?;
+
+        let mut model = Self {
+            dim,
+            word_ngrams,
+            loss,
+            bucket,
+            minn,
+            maxn,
+            nwords,
+            words: HashMap::new(),
+            subwords: Vec::with_capacity(nwords.max(0) as usize),
+            labels: Vec::with_capacity(nlabels.max(0) as usize),
+            pruneidx,
+            input,
+            output,
+            tree: Vec::new(),
+        };
+        let mut label_counts = Vec::new();
+        for (word, count, is_label) in entries {
+            if is_label {
+                let label = word.strip_prefix(LABEL_PREFIX).unwrap_or(&word);
+                model.labels.push(label.to_string());
+                label_counts.push(count);
+            } else {
+                let id = model.subwords.len() as i32;
+                let mut rows = vec![id];
+                if word != EOS {
+                    model.push_subwords(&format!("{}{}{}", BOW, word, EOW), &mut rows);
+                }
+                model.subwords.push(rows);
+                model.words.insert(word, id);
+            }
+        }
+
+        let rows = model.input.len() / dim.max(1);
+        if model.subwords.len() as i32 != nwords || model.labels.len() as i32 != nlabels {
+            return Err(anyhow::anyhow!("dictionary does not match its header"));
+        }
+        let ngram_rows = match &model.pruneidx {
+            None => model.bucket.max(0),
+            Some(pruneidx) => pruneidx.values().map(|&row| row + 1).max().unwrap_or(0),
+        };
+        if rows < (nwords + ngram_rows) as usize {
+            return Err(anyhow::anyhow!(
+                "input matrix is smaller than the dictionary"
+            ));
+        }
+        if loss == Loss::HierarchicalSoftmax {
+            model.tree = build_tree(&label_counts);
+        }
+        Ok(model)
+    }
+
+    /// Labels of the model, without the "__label__" prefix
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Up to `k` most probable labels with a probability of at least `threshold`, most probable
+    /// first; newlines in `text` are treated as spaces
+    pub fn predict(&self, text: &str, k: usize, threshold: f32) -> Vec<(String, f32)> {
+        let rows = self.input_rows(text);
+        if rows.is_empty() || k == 0 || self.labels.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hidden = vec![0.0f32; self.dim];
+        for &row in &rows {
+            let row = &self.input[row as usize * self.dim..(row as usize + 1) * self.dim];
+            for (h, x) in hidden.iter_mut().zip(row) {
+                *h += x;
+            }
+        }
+        let scale = 1.0 / rows.len() as f32;
+        hidden.iter_mut().for_each(|h| *h *= scale);
+
+        // (log probability, label index)
+        let mut best: Vec<(f32, usize)> = match self.loss {
+            Loss::HierarchicalSoftmax => {
+                let mut best = Vec::new();
+                let root = self.tree.len() as i32 - 1;
+                self.dfs(k, threshold, root, 0.0, &mut best, &hidden);
+                best
+            }
+            Loss::Softmax => {
+                let logits: Vec<f32> = (0..self.labels.len())
+                    .map(|i| self.dot_output(i, &hidden))
+                    .collect();
+                let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let exp: Vec<f32> = logits.iter().map(|x| (x - max).exp()).collect();
+                let sum: f32 = exp.iter().sum();
+                exp.iter()
+                    .enumerate()
+                    .map(|(i, e)| (std_log(e / sum), i))
+                    .collect()
+            }
+            Loss::NegativeSampling | Loss::OneVsAll => (0..self.labels.len())
+                .map(|i| (std_log(sigmoid(self.dot_output(i, &hidden))), i))
+                .collect(),
+        };
+
+        best.retain(|(score, _)| *score >= std_log(threshold));
+        best.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        best.truncate(k);
+        best.into_iter()
+            .map(|(score, i)| (self.labels[i].clone(), score.exp()))
+            .collect()
+    }
+
+    /// Input matrix rows of a line: words with their n-grams, then word n-grams
+    fn input_rows(&self, text: &str) -> Vec<i32> {
+        let mut rows = Vec::new();
+        let mut hashes = Vec::new();
+        let tokens = text
+            .split([' ', '\n', '\r', '\t', '\x0b', '\x0c', '\0'])
+            .filter(|token| !token.is_empty())
+            .chain(std::iter::once(EOS)); // Line end, as `predict` appends a newline
+        for token in tokens {
+            match self.words.get(token) {
+                Some(&id) => {
+                    if self.maxn == 0 {
+                        rows.push(id);
+                    } else {
+                        rows.extend_from_slice(&self.subwords[id as usize]);
+                    }
+                }
+                None if token.starts_with(LABEL_PREFIX) => continue,
+                None => {
+                    if token != EOS {
+                        self.push_subwords(&format!("{}{}{}", BOW, token, EOW), &mut rows);
+                    }
+                }
+            }
+            hashes.push(hash(token.as_bytes()) as i32);
+        }
+
+        for i in 0..hashes.len() {
+            let mut h = hashes[i] as i64 as u64;
+            for &next in hashes
+                .iter()
+                .take(i + self.word_ngrams.max(1) as usize)
+                .skip(i + 1)
+            {
+                h = h.wrapping_mul(116049371).wrapping_add(next as i64 as u64);
+                self.push_hash(&mut rows, (h % self.bucket as u64) as i32);
+            }
+        }
+        rows
+    }
+
+    /// Character n-grams (`minn` to `maxn` characters) of a word wrapped in "<" and ">"
+    fn push_subwords(&self, word: &str, rows: &mut Vec<i32>) {
+        if self.maxn == 0 || self.bucket <= 0 {
+            return;
+        }
+        let starts: Vec<usize> = word.char_indices().map(|(i, _)| i).collect();
+        let nchars = starts.len();
+        for i in 0..nchars {
+            for n in self.minn.max(1)..=self.maxn {
+                if i + n > nchars {
+                    break;
+                }
+                // Single "<" or ">" are not n-grams
+                if n == 1 && (i == 0 || i + n == nchars) {
+                    continue;
+                }
+                let end = starts.get(i + n).copied().unwrap_or(word.len());
+                let ngram = &word.as_bytes()[starts[i]..end];
+                self.push_hash(rows, (hash(ngram) % self.bucket as u32) as i32);
+            }
+        }
+    }
+
+    fn push_hash(&self, rows: &mut Vec<i32>, id: i32) {
+        let id = match &self.pruneidx {
+            None => id,
+            Some(pruneidx) => match pruneidx.get(&id) {
+                Some(&row) => row,
+                None => return,
+            },
+        };
+        if id >= 0 {
+            rows.push(self.nwords + id);
+        }
+    }
+
+    fn dot_output(&self, row: usize, hidden: &[f32]) -> f32 {
+        self.output[row * self.dim..(row + 1) * self.dim]
+            .iter()
+            .zip(hidden)
+            .map(|(w, h)| w * h)
+            .sum()
+    }
+
+    fn dfs(
+        &self,
+        k: usize,
+        threshold: f32,
+        node: i32,
+        score: f32,
+        best: &mut Vec<(f32, usize)>,
+        hidden: &[f32],
+    ) {
+        if score < std_log(threshold) {
+            return;
+        }
+        if best.len() >= k {
+            let worst = best.iter().map(|b| b.0).fold(f32::INFINITY, f32::min);
+            if score < worst {
+                return;
+            }
+        }
+        let n = &self.tree[node as usize];
+        if n.left == -1 && n.right == -1 {
+            best.push((score, node as usize));
+            if best.len() > k {
+                best.sort_by(|a, b| b.0.total_cmp(&a.0));
+                best.truncate(k);
+            }
+            return;
+        }
+        let f = sigmoid(self.dot_output(node as usize - self.labels.len(), hidden));
+        self.dfs(k, threshold, n.left, score + std_log(1.0 - f), best, hidden);
+        self.dfs(k, threshold, n.right, score + std_log(f), best, hidden);
+    }
+}
+
+/// Huffman tree over the label counts (labels are sorted by decreasing count)
+fn build_tree(counts: &[i64]) -> Vec<Node> {
+    let osz = counts.len();
+    if osz == 0 {
+        return Vec::new();
+    }
+    let mut tree: Vec<Node> = (0..2 * osz - 1)
+        .map(|i| Node {
+            left: -1,
+            right: -1,
+            count: counts.get(i).copied().unwrap_or(1_000_000_000_000_000),
+        })
+        .collect();
+    let mut leaf = osz as i64 - 1;
+    let mut node = osz;
+    for i in osz..2 * osz - 1 {
+        let mut mini = [0usize; 2];
+        for m in mini.iter_mut() {
+            if leaf >= 0 && tree[leaf as usize].count < tree[node].count {
+                *m = leaf as usize;
+                leaf -= 1;
+            } else {
+                *m = node;
+                node += 1;
+            }
+        }
+        tree[i].left = mini[0] as i32;
+        tree[i].right = mini[1] as i32;
+        tree[i].count = tree[mini[0]].count + tree[mini[1]].count;
+    }
+    tree
+}
+
+/// FNV-1a over bytes, sign-extended as in fasttext
+fn hash(bytes: &[u8]) -> u32 {
+    let mut h: u32 = 2166136261;
+    for &b in bytes {
+        h ^= b as i8 as i32 as u32;
+        h = h.wrapping_mul(16777619);
+    }
+    h
+}
+
+fn std_log(x: f32) -> f32 {
+    (x + 1e-5).ln()
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn read_matrix(r: &mut impl Read, dim: usize) -> Result<Vec<f32>> {
+    let m = read_i64(r)?;
+    let n = read_i64(r)?;
+    if m < 0 || n as usize != dim {
+        return Err(anyhow::anyhow!(
+            "matrix of {}x{} does not match dim {}",
+            m,
+            n,
+            dim
+        ));
+    }
+    let mut bytes = vec![0u8; m as usize * n as usize * 4];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn read_i32(r: &mut impl Read) -> Result<i32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(i32::from_le_bytes(b))
+}
+
+fn read_i64(r: &mut impl Read) -> Result<i64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(i64::from_le_bytes(b))
+}
+
+fn read_f64(r: &mut impl Read) -> Result<f64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(f64::from_le_bytes(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Softmax model of dim 2 without subwords: "good" points to label pos, "bad" to neg
+    fn model_bytes(version: i32, quantized: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let i32s = |values: &[i32], bytes: &mut Vec<u8>| {
+            values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()))
+        };
+        i32s(&[MAGIC, version], &mut bytes);
+        // dim, ws, epoch, min_count, neg, word_ngrams, loss, model, bucket, minn, maxn,
+        // lr_update_rate
+        i32s(&[2, 5, 5, 1, 5, 1, 3, 3, 0, 0, 0, 100], &mut bytes);
+        bytes.extend_from_slice(&1e-4f64.to_le_bytes());
+
+        i32s(&[5, 3, 2], &mut bytes);
+        bytes.extend_from_slice(&10i64.to_le_bytes()); // ntokens
+        bytes.extend_from_slice(&(-1i64).to_le_bytes()); // pruneidx_size
+        for (word, is_label) in [
+            (EOS, false),
+            ("good", false),
+            ("bad", false),
+            ("__label__pos", true),
+            ("__label__neg", true),
+        ] {
+            bytes.extend_from_slice(word.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&1i64.to_le_bytes());
+            bytes.push(is_label as u8);
+        }
+
+        bytes.push(quantized as u8);
+        let matrix = |rows: &[[f32; 2]], bytes: &mut Vec<u8>| {
+            bytes.extend_from_slice(&(rows.len() as i64).to_le_bytes());
+            bytes.extend_from_slice(&2i64.to_le_bytes());
+            for value in rows.iter().flatten() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+        matrix(&[[0.0, 0.0], [4.0, 0.0], [0.0, 4.0]], &mut bytes);
+        bytes.push(0);
+        matrix(&[[1.0, 0.0], [0.0, 1.0]], &mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn predicts_with_a_softmax_model() {
+        let model = FastTextModel::read(&mut model_bytes(VERSION, false).as_slice()).unwrap();
+        assert_eq!(model.labels(), ["pos", "neg"]);
+
+        // "good" and the line end average to (2, 0): softmax of logits 2 and 0
+        let predictions = model.predict("good", 2, 0.0);
+        assert_eq!(predictions[0].0, "pos");
+        assert!((predictions[0].1 - 0.8808).abs() < 1e-3);
+        assert_eq!(predictions[1].0, "neg");
+
+        assert_eq!(model.predict("bad\nbad", 1, 0.0)[0].0, "neg");
+        assert!(model.predict("good", 2, 0.9).is_empty());
+        assert!(model.predict("", 1, 0.0).len() == 1); // Only the line end
+        assert!(model.predict("good", 0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_models() {
+        let mut bytes = model_bytes(VERSION, false);
+        bytes[0] ^= 1;
+        let error = FastTextModel::read(&mut bytes.as_slice()).err().unwrap();
+        assert!(error.to_string().contains("bad magic number"));
+
+        let error = FastTextModel::read(&mut model_bytes(VERSION + 1, false).as_slice())
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains("unsupported file format version"));
+
+        let error = FastTextModel::read(&mut model_bytes(VERSION, true).as_slice())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("quantized"));
+
+        let bytes = model_bytes(VERSION, false);
+        assert!(FastTextModel::read(&mut &bytes[..bytes.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn hash_matches_fasttext() {
+        // Values of fasttext's Dictionary::hash, which sign-extends bytes above 127
+        assert_eq!(hash(b""), 2166136261);
+        assert_eq!(hash(b"a"), 3826002220);
+        assert_eq!(hash("é".as_bytes()), 1023043777);
+    }
+}
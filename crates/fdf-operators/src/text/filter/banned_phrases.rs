@@ -0,0 +1,231 @@
+use super::word_blocklist::whole_matches;
+use aho_corasick::AhoCorasick;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Drops (or annotates) documents containing banned phrases grouped into named categories
+/// (ads, gambling, crypto spam, ...), reporting the matched category so that removals can be
+/// analyzed per reason
+#[fdf_operator(
+    name = "text.banned_phrase_filter",
+    category = "filter",
+    build = "BannedPhraseConfig::build"
+)]
+struct BannedPhraseConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of a YAML or JSON file mapping each category to its phrases, e.g.
+    /// {gambling: ["online casino", "free spins"]}
+    categories_path: Option<String>,
+    /// Categories and their phrases, in addition to the file
+    categories: Option<BTreeMap<String, Vec<String>>>,
+    /// Match case-insensitively
+    #[param(default = true)]
+    ignore_case: bool,
+    /// Only match whole words (not preceded or followed by a letter or digit); disable for
+    /// languages written without spaces (Chinese, Japanese, Thai)
+    #[param(default = true)]
+    word_boundaries: bool,
+    /// Drop documents with more matches than this in a category
+    #[param(default = 0)]
+    max_matches: usize,
+    /// Only write the matched category to category_col, keeping every document
+    #[param(default = false)]
+    annotate: bool,
+    /// Field receiving the category with the most matches (null if none), with annotate
+    #[param(default = "banned_category")]
+    category_col: String,
+    /// Field receiving the number of matches per matched category, with annotate, if set
+    matches_col: Option<String>,
+}
+
+impl BannedPhraseConfig {
+    fn build(self) -> Result<BannedPhraseFilter> {
+        if self.categories_path.is_none() && self.categories.is_none() {
+            return Err(anyhow::anyhow!(
+                "{}: set categories_path and/or categories",
+                Self::NAME
+            ));
+        }
+        Ok(BannedPhraseFilter {
+            text_col: self.text_col,
+            categories_path: self.categories_path,
+            categories: self.categories.unwrap_or_default(),
+            ignore_case: self.ignore_case,
+            word_boundaries: self.word_boundaries,
+            max_matches: self.max_matches,
+            annotate: self.annotate,
+            category_col: self.category_col,
+            matches_col: self.matches_col,
+            matcher: None,
+        })
+    }
+}
+
+pub struct BannedPhraseFilter {
+    text_col: String,
+    categories_path: Option<String>,
+    categories: BTreeMap<String, Vec<String>>,
+    ignore_case: bool,
+    word_boundaries: bool,
+    max_matches: usize,
+    annotate: bool,
+    category_col: String,
+    matches_col: Option<String>,
+    matcher: Option<Arc<PhraseMatcher>>, // Built in open
+}
+
+/// Automaton of the phrases of every category
+pub struct PhraseMatcher {
+    automaton: AhoCorasick,
+    categories: Vec<String>,
+    phrase_categories: Vec<usize>, // Category of each phrase
+}
+
+impl PhraseMatcher {
+    fn load(
+        path: Option<&str>,
+        inline: &BTreeMap<String, Vec<String>>,
+        ignore_case: bool,
+    ) -> Result<Self> {
+        let mut categories = BTreeMap::new();
+        if let Some(path) = path {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read categories {}: {}", path, e))?;
+            let file: BTreeMap<String, Vec<String>> = serde_yaml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid categories file {}: {}", path, e))?;
+            categories = file;
+        }
+        for (category, phrases) in inline {
+            categories
+                .entry(category.clone())
+                .or_insert_with(Vec::new)
+                .extend(phrases.iter().cloned());
+        }
+
+        let mut phrases = Vec::new();
+        let mut phrase_categories = Vec::new();
+        for (idx, category_phrases) in categories.values().enumerate() {
+            for phrase in category_phrases {
+                let phrase = phrase.trim();
+                if phrase.is_empty() {
+                    continue;
+                }
+                phrases.push(if ignore_case {
+                    phrase.to_lowercase()
+                } else {
+                    phrase.to_string()
+                });
+                phrase_categories.push(idx);
+            }
+        }
+        if phrases.is_empty() {
+            return Err(anyhow::anyhow!("no banned phrases"));
+        }
+        Ok(PhraseMatcher {
+            automaton: AhoCorasick::new(&phrases)?,
+            categories: categories.into_keys().collect(),
+            phrase_categories,
+        })
+    }
+}
+
+impl BannedPhraseFilter {
+    /// Number of banned phrase occurrences per matched category
+    pub fn count_matches(&self, text: &str) -> BTreeMap<&str, usize> {
+        let matcher = self.matcher.as_ref().expect("matcher is built in open");
+        let lower;
+        let text = if self.ignore_case {
+            lower = text.to_lowercase();
+            &lower
+        } else {
+            text
+        };
+        let mut counts = BTreeMap::new();
+        for m in whole_matches(&matcher.automaton, text, self.word_boundaries) {
+            let category = matcher.phrase_categories[m.pattern().as_usize()];
+            *counts
+                .entry(matcher.categories[category].as_str())
+                .or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Category with the most matches, the first by name on ties
+fn top_category<'a>(counts: &BTreeMap<&'a str, usize>) -> Option<(&'a str, usize)> {
+    counts
+        .iter()
+        .map(|(&category, &count)| (category, count))
+        .fold(None, |top, (category, count)| match top {
+            Some((_, top_count)) if top_count >= count => top,
+            _ => Some((category, count)),
+        })
+}
+
+impl Operator for BannedPhraseFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = BannedPhraseConfig::NAME;
+        let key = format!(
+            "banned_phrases:{}:{:?}:{}",
+            self.categories_path.as_deref().unwrap_or_default(),
+            self.categories,
+            self.ignore_case
+        );
+        let matcher = ctx
+            .resources()
+            .get_or_load(&key, || {
+                PhraseMatcher::load(
+                    self.categories_path.as_deref(),
+                    &self.categories,
+                    self.ignore_case,
+                )
+            })
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", name, e)))?;
+        self.matcher = Some(matcher);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let counts = self.count_matches(text);
+        let top = top_category(&counts);
+
+        if self.annotate {
+            if let Some(matches_col) = &self.matches_col {
+                let matches = counts
+                    .iter()
+                    .map(|(category, count)| (category.to_string(), Value::from(*count)))
+                    .collect();
+                sample.set_path(matches_col, Value::Object(matches))?;
+            }
+            let category = top.map_or(Value::Null, |(category, _)| Value::from(category));
+            sample.set_path(&self.category_col, category)?;
+            return Ok(Some(sample));
+        }
+        match top {
+            Some((category, count)) if count > self.max_matches => {
+                ctx.metrics()
+                    .increment(&format!("rejected.{}", category), 1);
+                Ok(None)
+            }
+            _ => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        if self.annotate {
+            columns = columns.produces(&self.category_col);
+            if let Some(matches_col) = &self.matches_col {
+                columns = columns.produces(matches_col);
+            }
+        }
+        Some(columns)
+    }
+}
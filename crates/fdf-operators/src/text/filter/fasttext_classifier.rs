@@ -1,25 +1,179 @@
-// Placeholder - will implement later
-use fdf_sdk::{Operator, Result, Sample};
+use crate::text::fasttext::FastTextModel;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Scores documents with a fasttext classifier (quality, topic, toxicity models; softmax and
+/// multi-label one-vs-all models), writing one probability field per label and keeping or
+/// dropping documents by per-label thresholds
+#[fdf_operator(
+    name = "text.fasttext_classifier_filter",
+    category = "filter",
+    build = "FastTextClassifierConfig::build"
+)]
+struct FastTextClassifierConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the fasttext classifier model (.bin)
+    model_path: String,
+    /// Labels scored, without the "__label__" prefix; every label of the model if unset
+    labels: Option<Vec<String>>,
+    /// Prefix of the score fields: the probability of label L is written to <prefix>L
+    #[param(default = "fasttext_")]
+    prefix: String,
+    /// Keep a document only if one of these labels has at least its threshold, e.g.
+    /// {hq: 0.5}
+    keep: Option<BTreeMap<String, f64>>,
+    /// Drop a document if one of these labels has at least its threshold, e.g. {spam: 0.9}
+    drop: Option<BTreeMap<String, f64>>,
+    /// Only write the scores (and <prefix>passed with keep or drop) and keep every document,
+    /// for threshold sweeps
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl FastTextClassifierConfig {
+    fn build(self) -> Result<FastTextClassifierFilter> {
+        let keep = self.keep.unwrap_or_default();
+        let drop = self.drop.unwrap_or_default();
+        if !self.annotate && keep.is_empty() && drop.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{}: set keep and/or drop thresholds, or annotate: true",
+                Self::NAME
+            ));
+        }
+        for (label, threshold) in keep.iter().chain(&drop) {
+            if !(0.0..=1.0).contains(threshold) {
+                return Err(anyhow::anyhow!(
+                    "{}: threshold of label {} must be between 0 and 1",
+                    Self::NAME,
+                    label
+                ));
+            }
+        }
+        Ok(FastTextClassifierFilter {
+            text_col: self.text_col,
+            model_path: self.model_path,
+            labels: self.labels,
+            prefix: self.prefix,
+            keep,
+            drop,
+            annotate: self.annotate,
+            model: None,
+        })
+    }
+}
 
 pub struct FastTextClassifierFilter {
-    #[allow(dead_code)]
     text_col: String,
+    model_path: String,
+    labels: Option<Vec<String>>, // All labels of the model once opened
+    prefix: String,
+    keep: BTreeMap<String, f64>,
+    drop: BTreeMap<String, f64>,
+    annotate: bool,
+    model: Option<Arc<FastTextModel>>, // Loaded in open
 }
 
-impl Operator for FastTextClassifierFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
-        // TODO: Implement FastText classifier filter
-        Ok(Some(sample)) // Placeholder - keep all records for now
+impl FastTextClassifierFilter {
+    /// Why a document with these label probabilities is rejected, if it is
+    fn reject(&self, scores: &BTreeMap<&str, f64>) -> Option<String> {
+        let score = |label: &str| scores.get(label).copied().unwrap_or(0.0);
+        if let Some(label) = self
+            .drop
+            .iter()
+            .find(|(label, &threshold)| score(label) >= threshold)
+            .map(|(label, _)| label)
+        {
+            return Some(format!("drop.{}", label));
+        }
+        if !self.keep.is_empty()
+            && !self
+                .keep
+                .iter()
+                .any(|(label, &threshold)| score(label) >= threshold)
+        {
+            return Some("keep".to_string());
+        }
+        None
+    }
+
+    fn filters(&self) -> bool {
+        !self.keep.is_empty() || !self.drop.is_empty()
     }
 }
 
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register(
-        "text.fasttext_classifier_filter",
-        |_config: &serde_yaml::Value| {
-            Ok(Box::new(FastTextClassifierFilter {
-                text_col: "text".to_string(),
-            }))
-        },
-    );
+impl Operator for FastTextClassifierFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = FastTextClassifierConfig::NAME;
+        let model = FastTextModel::shared(ctx, &self.model_path)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", name, e)))?;
+        let model_labels = model.labels();
+        for label in self
+            .labels
+            .iter()
+            .flatten()
+            .chain(self.keep.keys())
+            .chain(self.drop.keys())
+        {
+            if !model_labels.contains(label) {
+                return Err(OpError::fatal(format!(
+                    "{}: label {} is not in the model (labels: {})",
+                    name,
+                    label,
+                    model_labels.join(", ")
+                ))
+                .into());
+            }
+        }
+        self.labels.get_or_insert_with(|| model_labels.to_vec());
+        self.model = Some(model);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+
+        // Every label, so that labels below the top ones still get a (small) probability
+        let predictions = model.predict(text, model.labels().len(), 0.0);
+        let scores: BTreeMap<&str, f64> = predictions
+            .iter()
+            .map(|(label, probability)| (label.as_str(), f64::from(*probability)))
+            .collect();
+        let rejected = self.reject(&scores);
+
+        if !self.annotate {
+            if let Some(reason) = &rejected {
+                ctx.metrics().increment(&format!("rejected.{}", reason), 1);
+                return Ok(None);
+            }
+        }
+        for label in self.labels.iter().flatten() {
+            let score = scores.get(label.as_str()).copied().unwrap_or(0.0);
+            sample.set_path(&format!("{}{}", self.prefix, label), Value::from(score))?;
+        }
+        if self.annotate && self.filters() {
+            sample.set_path(
+                &format!("{}passed", self.prefix),
+                Value::Bool(rejected.is_none()),
+            )?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        for label in self.labels.iter().flatten() {
+            columns = columns.produces(format!("{}{}", self.prefix, label));
+        }
+        if self.annotate && self.filters() {
+            columns = columns.produces(format!("{}passed", self.prefix));
+        }
+        Some(columns)
+    }
 }
@@ -0,0 +1,36 @@
+use super::{AlphaWords, GopherFilter};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: keeps documents where most words contain an alphabetic character
+#[fdf_operator(
+    name = "text.gopher_alpha_words_filter",
+    category = "filter",
+    build = "AlphaWordsFilterConfig::build"
+)]
+struct AlphaWordsFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Minimum fraction of words with an alphabetic character
+    #[param(default = 0.8)]
+    min_alpha_words_ratio: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl AlphaWordsFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(AlphaWords {
+                min_alpha_words_ratio: self.min_alpha_words_ratio,
+            })],
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use super::{BulletEllipsis, GopherFilter};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents made mostly of bullet lines, or with many lines ending in an
+/// ellipsis
+#[fdf_operator(
+    name = "text.gopher_bullet_ellipsis_filter",
+    category = "filter",
+    build = "BulletEllipsisFilterConfig::build"
+)]
+struct BulletEllipsisFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum fraction of lines starting with a bullet
+    #[param(default = 0.9)]
+    max_bullet_lines_ratio: f64,
+    /// Maximum fraction of lines ending with an ellipsis
+    #[param(default = 0.3)]
+    max_ellipsis_lines_ratio: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl BulletEllipsisFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(BulletEllipsis {
+                max_bullet_lines_ratio: self.max_bullet_lines_ratio,
+                max_ellipsis_lines_ratio: self.max_ellipsis_lines_ratio,
+            })],
+        })
+    }
+}
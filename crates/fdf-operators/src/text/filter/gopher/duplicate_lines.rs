@@ -0,0 +1,41 @@
+use super::{DuplicateUnits, GopherFilter};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents with many lines repeating an earlier line
+#[fdf_operator(
+    name = "text.gopher_duplicate_lines_filter",
+    category = "filter",
+    build = "DuplicateLinesFilterConfig::build"
+)]
+struct DuplicateLinesFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum fraction of lines repeating an earlier line
+    #[param(default = 0.3)]
+    max_dup_line_frac: f64,
+    /// Maximum fraction of characters in repeated lines
+    #[param(default = 0.2)]
+    max_dup_line_char_frac: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl DuplicateLinesFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(DuplicateUnits {
+                paragraphs: false,
+                max_dup_frac: self.max_dup_line_frac,
+                max_dup_char_frac: self.max_dup_line_char_frac,
+            })],
+        })
+    }
+}
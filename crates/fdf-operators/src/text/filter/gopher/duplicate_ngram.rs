@@ -0,0 +1,43 @@
+use super::{DuplicateNgram, GopherFilter};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents with too many characters in repeated word n-grams
+#[fdf_operator(
+    name = "text.gopher_duplicate_ngram_filter",
+    category = "filter",
+    build = "DuplicateNgramFilterConfig::build"
+)]
+struct DuplicateNgramFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// N-gram size in words
+    #[param(default = 5)]
+    n: usize,
+    /// Maximum fraction of characters in n-grams repeating an earlier one
+    #[param(default = 0.15)]
+    max_char_frac: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl DuplicateNgramFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        if self.n == 0 {
+            return Err(anyhow::anyhow!("{}: n must be positive", Self::NAME));
+        }
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(DuplicateNgram {
+                n: self.n,
+                max_char_frac: self.max_char_frac,
+            })],
+        })
+    }
+}
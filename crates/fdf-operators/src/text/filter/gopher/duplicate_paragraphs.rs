@@ -0,0 +1,42 @@
+use super::{DuplicateUnits, GopherFilter};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents with many paragraphs (separated by blank lines) repeating an
+/// earlier one
+#[fdf_operator(
+    name = "text.gopher_duplicate_paragraphs_filter",
+    category = "filter",
+    build = "DuplicateParagraphsFilterConfig::build"
+)]
+struct DuplicateParagraphsFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum fraction of paragraphs repeating an earlier paragraph
+    #[param(default = 0.3)]
+    max_dup_para_frac: f64,
+    /// Maximum fraction of characters in repeated paragraphs
+    #[param(default = 0.2)]
+    max_dup_para_char_frac: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl DuplicateParagraphsFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(DuplicateUnits {
+                paragraphs: true,
+                max_dup_frac: self.max_dup_para_frac,
+                max_dup_char_frac: self.max_dup_para_char_frac,
+            })],
+        })
+    }
+}
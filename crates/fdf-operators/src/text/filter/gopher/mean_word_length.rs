@@ -0,0 +1,40 @@
+use super::{GopherFilter, MeanWordLength};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: keeps documents whose mean word length (in characters) is within a range
+#[fdf_operator(
+    name = "text.gopher_mean_word_length_filter",
+    category = "filter",
+    build = "MeanWordLengthFilterConfig::build"
+)]
+struct MeanWordLengthFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Minimum mean word length
+    #[param(default = 3.0)]
+    min_mean_word_length: f64,
+    /// Maximum mean word length
+    #[param(default = 10.0)]
+    max_mean_word_length: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl MeanWordLengthFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(MeanWordLength {
+                min_mean_word_length: self.min_mean_word_length,
+                max_mean_word_length: self.max_mean_word_length,
+            })],
+        })
+    }
+}
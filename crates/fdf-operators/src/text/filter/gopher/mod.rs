@@ -0,0 +1,529 @@
+//! Gopher (Rae et al., 2021) quality heuristics
+//!
+//! Each rule is an operator of its own (`text.gopher_word_count_filter`, ...) so it can be
+//! tuned or left out independently; `text.gopher_quality_filter` and
+//! `text.gopher_repetition_filter` apply the quality and repetition rules of the paper. With
+//! `annotate: true` the operators keep every document and write the rules' statistics (prefixed
+//! with `prefix`) and the first failed rule (`<prefix>rejected_by`) instead.
+
+pub mod alpha_words;
+pub mod bullet_ellipsis;
+pub mod duplicate_lines;
+pub mod duplicate_ngram;
+pub mod duplicate_paragraphs;
+pub mod mean_word_length;
+pub mod quality;
+pub mod repetition;
+pub mod stop_words;
+pub mod symbol_ratio;
+pub mod top_ngram;
+pub mod word_count;
+
+use fdf_sdk::{ColumnSpec, Context, Operator, OperatorRegistry, Result, Sample, Value};
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+
+pub fn register(registry: &mut OperatorRegistry) {
+    quality::register(registry);
+    word_count::register(registry);
+    mean_word_length::register(registry);
+    symbol_ratio::register(registry);
+    bullet_ellipsis::register(registry);
+    alpha_words::register(registry);
+    stop_words::register(registry);
+    repetition::register(registry);
+    duplicate_lines::register(registry);
+    duplicate_paragraphs::register(registry);
+    top_ngram::register(registry);
+    duplicate_ngram::register(registry);
+}
+
+const BULLETS: &[char] = &[
+    '•', '‣', '●', '○', '◦', '▪', '▫', '■', '□', '-', '*', '–', '—',
+];
+const STOP_WORDS: &[&str] = &["the", "be", "to", "of", "and", "that", "have", "with"];
+
+/// Document statistics the Gopher rules are checked against
+pub struct GopherStats {
+    pub word_count: usize,         // Words with a non-punctuation character
+    pub mean_word_length: f64,     // In characters, over those words
+    pub hash_ratio: f64,           // '#' per word
+    pub ellipsis_ratio: f64,       // "..." and '…' per word
+    pub bullet_lines_ratio: f64,   // Fraction of lines starting with a bullet
+    pub ellipsis_lines_ratio: f64, // Fraction of lines ending with an ellipsis
+    pub alpha_words_ratio: f64,    // Fraction of words with an alphabetic character
+    pub stop_words: usize,         // Distinct English stop words present
+}
+
+impl GopherStats {
+    pub fn compute(text: &str) -> Self {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let content_words: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|word| word.chars().any(|c| !c.is_ascii_punctuation()))
+            .collect();
+        let word_count = content_words.len();
+        let per_word = |count: usize| count as f64 / word_count.max(1) as f64;
+
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let per_line = |count: usize| count as f64 / lines.len().max(1) as f64;
+        let stop_words: HashSet<String> = words
+            .iter()
+            .map(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase()
+            })
+            .filter(|word| STOP_WORDS.contains(&word.as_str()))
+            .collect();
+
+        Self {
+            word_count,
+            mean_word_length: content_words
+                .iter()
+                .map(|word| word.chars().count())
+                .sum::<usize>() as f64
+                / word_count.max(1) as f64,
+            hash_ratio: per_word(text.matches('#').count()),
+            ellipsis_ratio: per_word(text.matches("...").count() + text.matches('…').count()),
+            bullet_lines_ratio: per_line(
+                lines
+                    .iter()
+                    .filter(|line| line.starts_with(BULLETS))
+                    .count(),
+            ),
+            ellipsis_lines_ratio: per_line(
+                lines
+                    .iter()
+                    .filter(|line| line.ends_with("...") || line.ends_with('…'))
+                    .count(),
+            ),
+            alpha_words_ratio: words
+                .iter()
+                .filter(|word| word.chars().any(char::is_alphabetic))
+                .count() as f64
+                / words.len().max(1) as f64,
+            stop_words: stop_words.len(),
+        }
+    }
+}
+
+/// Text being checked, with the statistics rules share computed on first use
+pub struct Document<'a> {
+    pub text: &'a str,
+    quality: OnceCell<GopherStats>,
+    words: OnceCell<Vec<&'a str>>,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            quality: OnceCell::new(),
+            words: OnceCell::new(),
+        }
+    }
+
+    pub fn quality(&self) -> &GopherStats {
+        self.quality.get_or_init(|| GopherStats::compute(self.text))
+    }
+
+    pub fn words(&self) -> &[&'a str] {
+        self.words
+            .get_or_init(|| self.text.split_whitespace().collect())
+    }
+
+    /// Characters of the text, the denominator of the repetition fractions
+    fn chars(&self) -> usize {
+        self.text.chars().count().max(1)
+    }
+}
+
+/// One Gopher rule with its thresholds
+pub trait GopherRule: Send + Sync {
+    /// Names of the rule's statistics, as written in annotate mode (without prefix)
+    fn stat_names(&self) -> Vec<String>;
+
+    /// The rule's statistics, in stat_names order
+    fn stats(&self, doc: &Document) -> Vec<Value>;
+
+    /// Name of the failed check, if the document is rejected
+    fn reject(&self, doc: &Document) -> Option<String>;
+}
+
+/// Operator applying Gopher rules: drops documents failing one, or annotates them all
+pub struct GopherFilter {
+    pub text_col: String,
+    pub annotate: bool,
+    pub prefix: String,
+    pub rules: Vec<Box<dyn GopherRule>>,
+}
+
+impl Operator for GopherFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let doc = Document::new(text);
+        let rejected_by = self.rules.iter().find_map(|rule| rule.reject(&doc));
+
+        if !self.annotate {
+            return Ok(match rejected_by {
+                Some(check) => {
+                    ctx.metrics().increment(&format!("rejected.{}", check), 1);
+                    None
+                }
+                None => Some(sample),
+            });
+        }
+        let stats: Vec<(String, Value)> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.stat_names().into_iter().zip(rule.stats(&doc)))
+            .collect();
+        for (name, value) in stats {
+            sample.set_path(&format!("{}{}", self.prefix, name), value)?;
+        }
+        let rejected_by = rejected_by.map_or(Value::Null, Value::String);
+        sample.set_path(&format!("{}rejected_by", self.prefix), rejected_by)?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        if self.annotate {
+            for name in self.rules.iter().flat_map(|rule| rule.stat_names()) {
+                columns = columns.produces(format!("{}{}", self.prefix, name));
+            }
+            columns = columns.produces(format!("{}rejected_by", self.prefix));
+        }
+        Some(columns)
+    }
+}
+
+/// Names for GopherRule::stat_names
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Name of the first check whose condition holds
+fn first_failed(checks: &[(bool, &str)]) -> Option<String> {
+    checks
+        .iter()
+        .find(|(failed, _)| *failed)
+        .map(|(_, name)| name.to_string())
+}
+
+pub struct WordCount {
+    pub min_words: usize,
+    pub max_words: usize,
+}
+
+impl GopherRule for WordCount {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["word_count"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(doc.quality().word_count)]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        let words = doc.quality().word_count;
+        first_failed(&[
+            (words < self.min_words, "min_words"),
+            (words > self.max_words, "max_words"),
+        ])
+    }
+}
+
+pub struct MeanWordLength {
+    pub min_mean_word_length: f64,
+    pub max_mean_word_length: f64,
+}
+
+impl GopherRule for MeanWordLength {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["mean_word_length"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(doc.quality().mean_word_length)]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        let length = doc.quality().mean_word_length;
+        first_failed(&[
+            (length < self.min_mean_word_length, "min_mean_word_length"),
+            (length > self.max_mean_word_length, "max_mean_word_length"),
+        ])
+    }
+}
+
+pub struct SymbolRatio {
+    pub max_symbol_word_ratio: f64,
+}
+
+impl GopherRule for SymbolRatio {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["hash_ratio", "ellipsis_ratio"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        let stats = doc.quality();
+        vec![
+            Value::from(stats.hash_ratio),
+            Value::from(stats.ellipsis_ratio),
+        ]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        let stats = doc.quality();
+        first_failed(&[
+            (
+                stats.hash_ratio > self.max_symbol_word_ratio,
+                "max_hash_ratio",
+            ),
+            (
+                stats.ellipsis_ratio > self.max_symbol_word_ratio,
+                "max_ellipsis_ratio",
+            ),
+        ])
+    }
+}
+
+pub struct BulletEllipsis {
+    pub max_bullet_lines_ratio: f64,
+    pub max_ellipsis_lines_ratio: f64,
+}
+
+impl GopherRule for BulletEllipsis {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["bullet_lines_ratio", "ellipsis_lines_ratio"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        let stats = doc.quality();
+        vec![
+            Value::from(stats.bullet_lines_ratio),
+            Value::from(stats.ellipsis_lines_ratio),
+        ]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        let stats = doc.quality();
+        first_failed(&[
+            (
+                stats.bullet_lines_ratio > self.max_bullet_lines_ratio,
+                "max_bullet_lines_ratio",
+            ),
+            (
+                stats.ellipsis_lines_ratio > self.max_ellipsis_lines_ratio,
+                "max_ellipsis_lines_ratio",
+            ),
+        ])
+    }
+}
+
+pub struct AlphaWords {
+    pub min_alpha_words_ratio: f64,
+}
+
+impl GopherRule for AlphaWords {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["alpha_words_ratio"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(doc.quality().alpha_words_ratio)]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        first_failed(&[(
+            doc.quality().alpha_words_ratio < self.min_alpha_words_ratio,
+            "min_alpha_words_ratio",
+        )])
+    }
+}
+
+pub struct StopWords {
+    pub min_stop_words: usize,
+}
+
+impl GopherRule for StopWords {
+    fn stat_names(&self) -> Vec<String> {
+        names(&["stop_words"])
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(doc.quality().stop_words)]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        first_failed(&[(
+            doc.quality().stop_words < self.min_stop_words,
+            "min_stop_words",
+        )])
+    }
+}
+
+/// Repeated lines (unit "line") or paragraphs (unit "para"): the fraction of units that repeat
+/// an earlier one, and the fraction of characters in them
+pub struct DuplicateUnits {
+    pub paragraphs: bool, // Units are paragraphs (separated by blank lines) instead of lines
+    pub max_dup_frac: f64,
+    pub max_dup_char_frac: f64,
+}
+
+impl DuplicateUnits {
+    fn unit(&self) -> &'static str {
+        if self.paragraphs {
+            "para"
+        } else {
+            "line"
+        }
+    }
+
+    /// (fraction of duplicate units, fraction of characters in duplicate units)
+    pub(crate) fn fractions(&self, doc: &Document) -> (f64, f64) {
+        let units: Vec<&str> = if self.paragraphs {
+            doc.text
+                .trim()
+                .split("\n\n")
+                .map(|unit| unit.trim_matches('\n'))
+                .filter(|unit| !unit.is_empty())
+                .collect()
+        } else {
+            doc.text
+                .split('\n')
+                .filter(|unit| !unit.is_empty())
+                .collect()
+        };
+        let mut seen = HashSet::new();
+        let (mut dup_units, mut dup_chars) = (0, 0);
+        for unit in &units {
+            if !seen.insert(*unit) {
+                dup_units += 1;
+                dup_chars += unit.chars().count();
+            }
+        }
+        (
+            dup_units as f64 / units.len().max(1) as f64,
+            dup_chars as f64 / doc.chars() as f64,
+        )
+    }
+}
+
+impl GopherRule for DuplicateUnits {
+    fn stat_names(&self) -> Vec<String> {
+        vec![
+            format!("dup_{}_frac", self.unit()),
+            format!("dup_{}_char_frac", self.unit()),
+        ]
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        let (frac, char_frac) = self.fractions(doc);
+        vec![Value::from(frac), Value::from(char_frac)]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        let (frac, char_frac) = self.fractions(doc);
+        first_failed(&[
+            (
+                frac > self.max_dup_frac,
+                &format!("dup_{}_frac", self.unit()),
+            ),
+            (
+                char_frac > self.max_dup_char_frac,
+                &format!("dup_{}_char_frac", self.unit()),
+            ),
+        ])
+    }
+}
+
+/// Fraction of characters in the most frequent word n-gram (occurrences × n-gram length)
+pub struct TopNgram {
+    pub n: usize,
+    pub max_char_frac: f64,
+}
+
+impl TopNgram {
+    pub(crate) fn fraction(&self, doc: &Document) -> f64 {
+        let words = doc.words();
+        if words.len() < self.n {
+            return 0.0;
+        }
+        let mut counts: HashMap<&[&str], usize> = HashMap::new();
+        for ngram in words.windows(self.n) {
+            *counts.entry(ngram).or_default() += 1;
+        }
+        let top = counts
+            .iter()
+            .map(|(ngram, count)| {
+                let chars: usize = ngram.iter().map(|word| word.chars().count()).sum();
+                (*count, (chars + self.n - 1) * count)
+            })
+            .max()
+            .map_or(0, |(_, chars)| chars);
+        top as f64 / doc.chars() as f64
+    }
+}
+
+impl GopherRule for TopNgram {
+    fn stat_names(&self) -> Vec<String> {
+        vec![format!("top_{}gram_char_frac", self.n)]
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(self.fraction(doc))]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        (self.fraction(doc) > self.max_char_frac).then(|| format!("top_{}gram_char_frac", self.n))
+    }
+}
+
+/// Fraction of characters in word n-grams repeating an earlier one (each word counted once)
+pub struct DuplicateNgram {
+    pub n: usize,
+    pub max_char_frac: f64,
+}
+
+impl DuplicateNgram {
+    pub(crate) fn fraction(&self, doc: &Document) -> f64 {
+        let words = doc.words();
+        let mut seen = HashSet::new();
+        let mut dup_chars = 0;
+        let mut i = 0;
+        while i + self.n <= words.len() {
+            let ngram = &words[i..i + self.n];
+            if seen.insert(ngram) {
+                i += 1;
+            } else {
+                dup_chars += ngram.iter().map(|word| word.chars().count()).sum::<usize>();
+                i += self.n; // Overlapping repeats are not counted twice
+            }
+        }
+        dup_chars as f64 / doc.chars() as f64
+    }
+}
+
+impl GopherRule for DuplicateNgram {
+    fn stat_names(&self) -> Vec<String> {
+        vec![format!("dup_{}gram_char_frac", self.n)]
+    }
+
+    fn stats(&self, doc: &Document) -> Vec<Value> {
+        vec![Value::from(self.fraction(doc))]
+    }
+
+    fn reject(&self, doc: &Document) -> Option<String> {
+        (self.fraction(doc) > self.max_char_frac).then(|| format!("dup_{}gram_char_frac", self.n))
+    }
+}
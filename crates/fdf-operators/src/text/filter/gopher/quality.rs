@@ -0,0 +1,83 @@
+use super::{
+    AlphaWords, BulletEllipsis, GopherFilter, MeanWordLength, StopWords, SymbolRatio, WordCount,
+};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher quality heuristics: word count, mean word length, symbol ratio, bullet and ellipsis
+/// lines, alphabetic words and stop words (each also available as its own filter)
+#[fdf_operator(
+    name = "text.gopher_quality_filter",
+    category = "filter",
+    build = "GopherQualityFilterConfig::build"
+)]
+struct GopherQualityFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Minimum number of words
+    #[param(default = 50)]
+    min_words: usize,
+    /// Maximum number of words
+    #[param(default = 100000)]
+    max_words: usize,
+    /// Minimum mean word length
+    #[param(default = 3.0)]
+    min_mean_word_length: f64,
+    /// Maximum mean word length
+    #[param(default = 10.0)]
+    max_mean_word_length: f64,
+    /// Maximum number of '#', and of ellipses ("...", '…'), per word
+    #[param(default = 0.1)]
+    max_symbol_word_ratio: f64,
+    /// Maximum fraction of lines starting with a bullet
+    #[param(default = 0.9)]
+    max_bullet_lines_ratio: f64,
+    /// Maximum fraction of lines ending with an ellipsis
+    #[param(default = 0.3)]
+    max_ellipsis_lines_ratio: f64,
+    /// Minimum fraction of words with an alphabetic character
+    #[param(default = 0.8)]
+    min_alpha_words_ratio: f64,
+    /// Minimum number of distinct English stop words (0 for non-English text)
+    #[param(default = 2)]
+    min_stop_words: usize,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl GopherQualityFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![
+                Box::new(WordCount {
+                    min_words: self.min_words,
+                    max_words: self.max_words,
+                }),
+                Box::new(MeanWordLength {
+                    min_mean_word_length: self.min_mean_word_length,
+                    max_mean_word_length: self.max_mean_word_length,
+                }),
+                Box::new(SymbolRatio {
+                    max_symbol_word_ratio: self.max_symbol_word_ratio,
+                }),
+                Box::new(BulletEllipsis {
+                    max_bullet_lines_ratio: self.max_bullet_lines_ratio,
+                    max_ellipsis_lines_ratio: self.max_ellipsis_lines_ratio,
+                }),
+                Box::new(AlphaWords {
+                    min_alpha_words_ratio: self.min_alpha_words_ratio,
+                }),
+                Box::new(StopWords {
+                    min_stop_words: self.min_stop_words,
+                }),
+            ],
+        })
+    }
+}
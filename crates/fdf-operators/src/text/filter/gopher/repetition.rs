@@ -0,0 +1,85 @@
+use super::{DuplicateNgram, DuplicateUnits, GopherFilter, GopherRule, TopNgram};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher repetition heuristics: repeated lines and paragraphs, most frequent n-grams and
+/// repeated n-grams (each also available as its own filter)
+#[fdf_operator(
+    name = "text.gopher_repetition_filter",
+    category = "filter",
+    build = "GopherRepetitionFilterConfig::build"
+)]
+struct GopherRepetitionFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum fraction of lines repeating an earlier line
+    #[param(default = 0.3)]
+    max_dup_line_frac: f64,
+    /// Maximum fraction of characters in repeated lines
+    #[param(default = 0.2)]
+    max_dup_line_char_frac: f64,
+    /// Maximum fraction of paragraphs repeating an earlier paragraph
+    #[param(default = 0.3)]
+    max_dup_para_frac: f64,
+    /// Maximum fraction of characters in repeated paragraphs
+    #[param(default = 0.2)]
+    max_dup_para_char_frac: f64,
+    /// [n, max fraction] pairs: characters covered by the most frequent word n-gram
+    #[param(
+        default = "[[2, 0.2], [3, 0.18], [4, 0.16]]",
+        type = "list<[int, float]>"
+    )]
+    top_ngrams: Vec<(usize, f64)>,
+    /// [n, max fraction] pairs: characters in word n-grams repeating an earlier one
+    #[param(
+        default = "[[5, 0.15], [6, 0.14], [7, 0.13], [8, 0.12], [9, 0.11], [10, 0.1]]",
+        type = "list<[int, float]>"
+    )]
+    dup_ngrams: Vec<(usize, f64)>,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl GopherRepetitionFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        if self
+            .top_ngrams
+            .iter()
+            .chain(&self.dup_ngrams)
+            .any(|&(n, _)| n == 0)
+        {
+            return Err(anyhow::anyhow!(
+                "{}: n-gram sizes must be positive",
+                Self::NAME
+            ));
+        }
+        let mut rules: Vec<Box<dyn GopherRule>> = vec![
+            Box::new(DuplicateUnits {
+                paragraphs: true,
+                max_dup_frac: self.max_dup_para_frac,
+                max_dup_char_frac: self.max_dup_para_char_frac,
+            }),
+            Box::new(DuplicateUnits {
+                paragraphs: false,
+                max_dup_frac: self.max_dup_line_frac,
+                max_dup_char_frac: self.max_dup_line_char_frac,
+            }),
+        ];
+        for (n, max_char_frac) in self.top_ngrams {
+            rules.push(Box::new(TopNgram { n, max_char_frac }));
+        }
+        for (n, max_char_frac) in self.dup_ngrams {
+            rules.push(Box::new(DuplicateNgram { n, max_char_frac }));
+        }
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules,
+        })
+    }
+}
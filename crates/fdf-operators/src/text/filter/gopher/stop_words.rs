@@ -0,0 +1,37 @@
+use super::{GopherFilter, StopWords};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: keeps documents containing English stop words (the, be, to, of, and, that, have,
+/// with)
+#[fdf_operator(
+    name = "text.gopher_stop_words_filter",
+    category = "filter",
+    build = "StopWordsFilterConfig::build"
+)]
+struct StopWordsFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Minimum number of distinct stop words
+    #[param(default = 2)]
+    min_stop_words: usize,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl StopWordsFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(StopWords {
+                min_stop_words: self.min_stop_words,
+            })],
+        })
+    }
+}
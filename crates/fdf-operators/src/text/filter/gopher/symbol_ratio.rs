@@ -0,0 +1,36 @@
+use super::{GopherFilter, SymbolRatio};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents with too many hash symbols or ellipses per word
+#[fdf_operator(
+    name = "text.gopher_symbol_ratio_filter",
+    category = "filter",
+    build = "SymbolRatioFilterConfig::build"
+)]
+struct SymbolRatioFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum number of '#', and of ellipses ("...", '…'), per word
+    #[param(default = 0.1)]
+    max_symbol_word_ratio: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl SymbolRatioFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(SymbolRatio {
+                max_symbol_word_ratio: self.max_symbol_word_ratio,
+            })],
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use super::{GopherFilter, TopNgram};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: drops documents whose most frequent word n-gram covers too many characters
+#[fdf_operator(
+    name = "text.gopher_top_ngram_filter",
+    category = "filter",
+    build = "TopNgramFilterConfig::build"
+)]
+struct TopNgramFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// N-gram size in words
+    #[param(default = 2)]
+    n: usize,
+    /// Maximum fraction of characters covered by the most frequent n-gram
+    #[param(default = 0.2)]
+    max_char_frac: f64,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl TopNgramFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        if self.n == 0 {
+            return Err(anyhow::anyhow!("{}: n must be positive", Self::NAME));
+        }
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(TopNgram {
+                n: self.n,
+                max_char_frac: self.max_char_frac,
+            })],
+        })
+    }
+}
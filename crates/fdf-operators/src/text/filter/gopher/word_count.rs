@@ -0,0 +1,41 @@
+use super::{GopherFilter, WordCount};
+use fdf_sdk::{fdf_operator, Result};
+
+/// Gopher rule: keeps documents with a number of words (containing a non-punctuation character)
+/// within a range
+#[fdf_operator(
+    name = "text.gopher_word_count_filter",
+    category = "filter",
+    build = "WordCountFilterConfig::build"
+)]
+struct WordCountFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Minimum number of words
+    #[param(default = 50)]
+    min_words: usize,
+    /// Maximum number of words
+    #[param(default = 100000)]
+    max_words: usize,
+    /// Keep every document and write the statistics and failed rule instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "gopher_")]
+    prefix: String,
+}
+
+impl WordCountFilterConfig {
+    fn build(self) -> Result<GopherFilter> {
+        Ok(GopherFilter {
+            text_col: self.text_col,
+            annotate: self.annotate,
+            prefix: self.prefix,
+            rules: vec![Box::new(WordCount {
+                min_words: self.min_words,
+                max_words: self.max_words,
+            })],
+        })
+    }
+}
@@ -1,25 +0,0 @@
-// Placeholder - will implement later
-use fdf_sdk::{Operator, Result, Sample};
-
-pub struct GopherQualityFilter {
-    #[allow(dead_code)]
-    text_col: String,
-}
-
-impl Operator for GopherQualityFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
-        // TODO: Implement Gopher quality filter
-        Ok(Some(sample)) // Placeholder - keep all records for now
-    }
-}
-
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register(
-        "text.gopher_quality_filter",
-        |_config: &serde_yaml::Value| {
-            Ok(Box::new(GopherQualityFilter {
-                text_col: "text".to_string(),
-            }))
-        },
-    );
-}
@@ -1,25 +0,0 @@
-// Placeholder - will implement later
-use fdf_sdk::{Operator, Result, Sample};
-
-pub struct GopherRepetitionFilter {
-    #[allow(dead_code)]
-    text_col: String,
-}
-
-impl Operator for GopherRepetitionFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
-        // TODO: Implement Gopher repetition filter
-        Ok(Some(sample)) // Placeholder - keep all records for now
-    }
-}
-
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register(
-        "text.gopher_repetition_filter",
-        |_config: &serde_yaml::Value| {
-            Ok(Box::new(GopherRepetitionFilter {
-                text_col: "text".to_string(),
-            }))
-        },
-    );
-}
@@ -0,0 +1,136 @@
+use crate::text::annotator::language_id::{Backend, Detector};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Keeps documents in one of the allowed languages, detected with at least a minimum confidence
+#[fdf_operator(
+    name = "text.language_filter",
+    category = "filter",
+    build = "LanguageFilterConfig::build"
+)]
+struct LanguageFilterConfig {
+    /// Languages kept (codes as written by text.language_id, e.g. en, zh); any if unset
+    languages: Option<Vec<String>>,
+    /// Minimum detection confidence kept (0 to 1)
+    #[param(default = 0.0)]
+    min_score: f64,
+    /// Detect the language of text_col here instead of reading language_col and score_col
+    #[param(default = false)]
+    detect: bool,
+    /// Field holding the text, with detect (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Detector, with detect: "whatlang" (bundled) or "fasttext" (needs model_path)
+    #[param(default = "whatlang", type = "string")]
+    backend: Backend,
+    /// Path of the fasttext language id model (.bin), with detect and backend fasttext
+    model_path: Option<String>,
+    /// Field holding the language code, as written by text.language_id
+    #[param(default = "language")]
+    language_col: String,
+    /// Field holding the detection confidence, as written by text.language_id
+    #[param(default = "language_score")]
+    score_col: String,
+}
+
+impl LanguageFilterConfig {
+    fn build(self) -> Result<LanguageFilter> {
+        if self.languages.is_none() && self.min_score <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "{}: set languages and/or min_score",
+                Self::NAME
+            ));
+        }
+        if !self.detect && self.model_path.is_some() {
+            return Err(anyhow::anyhow!(
+                "{}: model_path is only used with detect: true",
+                Self::NAME
+            ));
+        }
+        let detector = if self.detect {
+            Some(Detector::new(Self::NAME, self.backend, self.model_path)?)
+        } else {
+            None
+        };
+        Ok(LanguageFilter {
+            languages: self
+                .languages
+                .map(|languages| languages.iter().map(|l| l.to_lowercase()).collect()),
+            min_score: self.min_score,
+            text_col: self.text_col,
+            language_col: self.language_col,
+            score_col: self.score_col,
+            detector,
+        })
+    }
+}
+
+pub struct LanguageFilter {
+    languages: Option<Vec<String>>, // Lowercase
+    min_score: f64,
+    text_col: String,
+    language_col: String,
+    score_col: String,
+    detector: Option<Detector>, // Inline detection
+}
+
+impl LanguageFilter {
+    /// Language and confidence of a sample, detected or read from its fields
+    fn language(&self, sample: &Sample) -> Result<(String, f64)> {
+        if let Some(detector) = &self.detector {
+            let text = sample
+                .get_path(&self.text_col)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+            return Ok(detector.detect(text));
+        }
+
+        let language = sample
+            .get_path(&self.language_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing language field: {}", self.language_col))?;
+        let score = if self.min_score > 0.0 {
+            sample
+                .get_path(&self.score_col)
+                .and_then(Value::as_f64)
+                .ok_or_else(|| anyhow::anyhow!("Missing numeric field: {}", self.score_col))?
+        } else {
+            0.0 // Not checked
+        };
+        Ok((language.to_string(), score))
+    }
+}
+
+impl Operator for LanguageFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        match &mut self.detector {
+            Some(detector) => detector.open(ctx, LanguageFilterConfig::NAME),
+            None => Ok(()),
+        }
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let (language, score) = self.language(&sample)?;
+        let allowed = match &self.languages {
+            Some(languages) => languages.contains(&language.to_lowercase()),
+            None => true,
+        };
+        if allowed && score >= self.min_score {
+            Ok(Some(sample))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new();
+        Some(if self.detector.is_some() {
+            columns.requires(&self.text_col)
+        } else if self.min_score > 0.0 {
+            columns
+                .requires(&self.language_col)
+                .requires(&self.score_col)
+        } else {
+            columns.requires(&self.language_col)
+        })
+    }
+}
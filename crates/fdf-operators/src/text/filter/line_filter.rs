@@ -0,0 +1,155 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Phrases of navigation, cookie and legal boilerplate lines (C4 and DataTrove line rules)
+const BOILERPLATE_PHRASES: &[&str] = &[
+    "javascript",
+    "terms of use",
+    "privacy policy",
+    "cookie policy",
+    "uses cookies",
+    "use of cookies",
+    "use cookies",
+    "accept cookies",
+    "all rights reserved",
+];
+
+/// Characters a line may end with when terminal punctuation is required
+const TERMINAL_PUNCTUATION: &[char] = &[
+    '.', '!', '?', '"', '\'', '”', '’', '…', '。', '！', '？', '؟', '।',
+];
+
+/// Removes lines matching cleaning rules (RefinedWeb / C4 style: too short, no terminal
+/// punctuation, all caps, mostly digits, boilerplate phrases) and drops documents losing too
+/// many lines; every rule is off unless configured, except the boilerplate phrases
+#[fdf_operator(
+    name = "text.line_filter",
+    category = "filter",
+    build = "LineFilter::validate"
+)]
+pub struct LineFilter {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Remove lines with fewer words
+    #[param(default = 0)]
+    min_words: usize,
+    /// Remove lines not ending with terminal punctuation (. ! ? … quotes, CJK full stops)
+    #[param(default = false)]
+    require_terminal_punct: bool,
+    /// Remove lines whose letters are more than this fraction uppercase (0 to 1)
+    #[param(default = 1.0)]
+    max_uppercase_ratio: f64,
+    /// Remove lines whose non-space characters are more than this fraction digits (0 to 1)
+    #[param(default = 1.0)]
+    max_digit_ratio: f64,
+    /// Remove lines containing one of these phrases (case-insensitive); unset uses a built-in
+    /// list (javascript, cookie and legal notices), [] disables the rule
+    boilerplate_phrases: Option<Vec<String>>,
+    /// Drop the document if more than this fraction of its non-blank lines was removed
+    #[param(default = 1.0)]
+    max_removed_ratio: f64,
+}
+
+impl LineFilter {
+    fn validate(mut self) -> Result<Self> {
+        for (name, ratio) in [
+            ("max_uppercase_ratio", self.max_uppercase_ratio),
+            ("max_digit_ratio", self.max_digit_ratio),
+            ("max_removed_ratio", self.max_removed_ratio),
+        ] {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(anyhow::anyhow!(
+                    "{}: {} must be between 0 and 1",
+                    Self::NAME,
+                    name
+                ));
+            }
+        }
+        let phrases = self
+            .boilerplate_phrases
+            .get_or_insert_with(|| BOILERPLATE_PHRASES.iter().map(|p| p.to_string()).collect());
+        for phrase in phrases.iter_mut() {
+            *phrase = phrase.to_lowercase();
+        }
+        Ok(self)
+    }
+
+    /// Rule removing a (non-blank) line, if any
+    fn rule(&self, line: &str) -> Option<&'static str> {
+        let line = line.trim();
+        let phrases = self.boilerplate_phrases.as_deref().unwrap_or_default();
+        if !phrases.is_empty() {
+            let lower = line.to_lowercase();
+            if phrases.iter().any(|phrase| lower.contains(phrase.as_str())) {
+                return Some("boilerplate");
+            }
+        }
+        if self.min_words > 0 && line.split_whitespace().count() < self.min_words {
+            return Some("too_few_words");
+        }
+        if self.require_terminal_punct && !line.ends_with(TERMINAL_PUNCTUATION) {
+            return Some("no_terminal_punct");
+        }
+        if self.max_uppercase_ratio < 1.0 {
+            let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+            let upper = line.chars().filter(|c| c.is_uppercase()).count();
+            if letters > 0 && upper as f64 / letters as f64 > self.max_uppercase_ratio {
+                return Some("uppercase");
+            }
+        }
+        if self.max_digit_ratio < 1.0 {
+            let chars = line.chars().filter(|c| !c.is_whitespace()).count();
+            let digits = line.chars().filter(|c| c.is_numeric()).count();
+            if chars > 0 && digits as f64 / chars as f64 > self.max_digit_ratio {
+                return Some("digits");
+            }
+        }
+        None
+    }
+}
+
+impl Operator for LineFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+
+        let mut kept: Vec<&str> = Vec::new();
+        let (mut lines, mut removed) = (0, 0);
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                // Removed lines leave no leading blank lines or runs of blank lines
+                if kept.last().is_some_and(|last| !last.trim().is_empty()) {
+                    kept.push(line);
+                }
+                continue;
+            }
+            lines += 1;
+            match self.rule(line) {
+                Some(rule) => {
+                    removed += 1;
+                    ctx.metrics()
+                        .increment(&format!("lines_removed.{}", rule), 1);
+                }
+                None => kept.push(line),
+            }
+        }
+        if removed == 0 {
+            return Ok(Some(sample));
+        }
+        if kept.iter().all(|line| line.trim().is_empty())
+            || removed as f64 / lines as f64 > self.max_removed_ratio
+        {
+            return Ok(None);
+        }
+        while kept.last().is_some_and(|last| last.trim().is_empty()) {
+            kept.pop();
+        }
+        *text = kept.join("\n");
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
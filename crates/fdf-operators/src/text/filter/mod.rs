@@ -1,17 +1,39 @@
+pub mod banned_phrases;
+#[cfg(feature = "text-ml")]
 pub mod fasttext_classifier;
-pub mod gopher_quality;
-pub mod gopher_repetition;
-pub mod leq;
+pub mod gopher;
+pub mod language_filter;
+pub mod line_filter;
+pub mod nsfw;
+#[cfg(feature = "text-ml")]
+pub mod onnx_classifier;
+pub mod placeholder;
+pub mod regex_filter;
+pub mod script_filter;
 pub mod symbol_ratio;
 pub mod text_len;
+#[cfg(feature = "text-ml")]
+pub mod toxicity;
+pub mod word_blocklist;
 
 use fdf_sdk::OperatorRegistry;
 
 pub fn register(registry: &mut OperatorRegistry) {
     text_len::register(registry);
     symbol_ratio::register(registry);
-    leq::register(registry);
-    gopher_quality::register(registry);
-    gopher_repetition::register(registry);
+    gopher::register(registry);
+    language_filter::register(registry);
+    line_filter::register(registry);
+    nsfw::register(registry);
+    word_blocklist::register(registry);
+    regex_filter::register(registry);
+    placeholder::register(registry);
+    script_filter::register(registry);
+    banned_phrases::register(registry);
+    #[cfg(feature = "text-ml")]
     fasttext_classifier::register(registry);
+    #[cfg(feature = "text-ml")]
+    toxicity::register(registry);
+    #[cfg(feature = "text-ml")]
+    onnx_classifier::register(registry);
 }
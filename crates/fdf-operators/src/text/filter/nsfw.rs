@@ -0,0 +1,302 @@
+#[cfg(feature = "text-ml")]
+use crate::text::fasttext::FastTextModel;
+#[cfg(feature = "text-ml")]
+use fdf_sdk::OpError;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(feature = "text-ml")]
+use std::sync::Arc;
+
+/// Adult-content words and their weights; ambiguous words (anatomy, art, names) weigh little
+const KEYWORDS: &[(&str, f64)] = &[
+    ("porn", 1.0),
+    ("porno", 1.0),
+    ("pornhub", 1.0),
+    ("xvideos", 1.0),
+    ("xhamster", 1.0),
+    ("xxx", 1.0),
+    ("blowjob", 1.0),
+    ("handjob", 1.0),
+    ("cumshot", 1.0),
+    ("creampie", 1.0),
+    ("gangbang", 1.0),
+    ("bukkake", 1.0),
+    ("deepthroat", 1.0),
+    ("hentai", 1.0),
+    ("milf", 1.0),
+    ("camgirl", 1.0),
+    ("camgirls", 1.0),
+    ("onlyfans", 0.8),
+    ("nsfw", 0.8),
+    ("pussy", 0.8),
+    ("tits", 0.8),
+    ("horny", 0.8),
+    ("slut", 0.8),
+    ("dildo", 0.8),
+    ("nudes", 0.7),
+    ("bdsm", 0.7),
+    ("threesome", 0.7),
+    ("orgy", 0.7),
+    ("pornography", 0.6),
+    ("erotica", 0.6),
+    ("boobs", 0.6),
+    ("cock", 0.6),
+    ("whore", 0.6),
+    ("hardcore", 0.5),
+    ("erotic", 0.5),
+    ("fetish", 0.5),
+    ("anal", 0.5),
+    ("stripper", 0.5),
+    ("escorts", 0.5),
+    ("escort", 0.4),
+    ("nude", 0.4),
+    ("sexy", 0.4),
+    ("dick", 0.4),
+    ("orgasm", 0.4),
+    ("vibrator", 0.4),
+    ("sex", 0.3),
+    ("naked", 0.3),
+    ("masturbation", 0.3),
+    ("lingerie", 0.2),
+];
+
+/// Words of medical, educational and legal contexts, each offsetting part of a keyword hit
+const CONTEXT_TERMS: &[&str] = &[
+    "health",
+    "medical",
+    "medicine",
+    "clinical",
+    "clinic",
+    "patient",
+    "patients",
+    "doctor",
+    "physician",
+    "disease",
+    "symptoms",
+    "treatment",
+    "diagnosis",
+    "anatomy",
+    "biology",
+    "reproductive",
+    "pregnancy",
+    "contraception",
+    "contraceptive",
+    "hiv",
+    "std",
+    "sti",
+    "infection",
+    "therapy",
+    "education",
+    "educational",
+    "research",
+    "study",
+    "studies",
+    "consent",
+    "abuse",
+    "prevention",
+    "law",
+    "legal",
+    "court",
+    "policy",
+];
+
+/// Texts shorter than this many words are scored as if they had it, so that a single word
+/// does not make a short text explicit
+const MIN_WORDS: usize = 50;
+
+/// Scores adult content with a weighted keyword heuristic (discounted by medical, educational
+/// and legal context words), optionally combined with a fasttext classifier, and drops
+/// documents scoring at least the threshold
+#[fdf_operator(
+    name = "text.nsfw_filter",
+    category = "filter",
+    build = "NsfwConfig::build"
+)]
+struct NsfwConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field receiving the score (0 to 1)
+    #[param(default = "nsfw_score")]
+    score_col: String,
+    /// Documents scoring at least this are dropped (0 to 1); the default only drops explicit
+    /// content (about 5 strong keywords per 100 words)
+    #[param(default = 0.8)]
+    threshold: f64,
+    /// Keywords (single words, case-insensitive) and their weights; unset uses a built-in
+    /// English list
+    keywords: Option<BTreeMap<String, f64>>,
+    /// Context words (single words, case-insensitive) offsetting keyword hits; unset uses a
+    /// built-in list of medical, educational and legal words, [] disables the discount
+    context_terms: Option<Vec<String>>,
+    /// Keyword weight offset by each context word occurrence
+    #[param(default = 0.5)]
+    context_discount: f64,
+    /// Path of a fasttext NSFW classifier (.bin) combined with the keyword score (needs the
+    /// text-ml feature)
+    model_path: Option<String>,
+    /// Label of the model meaning NSFW, without the "__label__" prefix
+    #[param(default = "nsfw")]
+    model_label: String,
+    /// Weight of the model probability in the score, the keyword score having the rest
+    #[param(default = 0.5)]
+    model_weight: f64,
+    /// Only write the score, keeping every document
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl NsfwConfig {
+    fn build(self) -> Result<NsfwFilter> {
+        for (name, value) in [
+            ("threshold", self.threshold),
+            ("model_weight", self.model_weight),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow::anyhow!(
+                    "{}: {} must be between 0 and 1",
+                    Self::NAME,
+                    name
+                ));
+            }
+        }
+        if self.model_path.is_some() && !cfg!(feature = "text-ml") {
+            return Err(anyhow::anyhow!(
+                "{}: model_path needs fdf built with the text-ml feature",
+                Self::NAME
+            ));
+        }
+        let keywords = match self.keywords {
+            Some(keywords) => keywords
+                .into_iter()
+                .map(|(word, weight)| (word.to_lowercase(), weight))
+                .collect(),
+            None => KEYWORDS
+                .iter()
+                .map(|&(word, weight)| (word.to_string(), weight))
+                .collect(),
+        };
+        let context_terms = match self.context_terms {
+            Some(terms) => terms.iter().map(|term| term.to_lowercase()).collect(),
+            None => CONTEXT_TERMS.iter().map(|term| term.to_string()).collect(),
+        };
+        Ok(NsfwFilter {
+            text_col: self.text_col,
+            score_col: self.score_col,
+            threshold: self.threshold,
+            keywords,
+            context_terms,
+            context_discount: self.context_discount,
+            model_path: self.model_path,
+            model_label: self.model_label,
+            model_weight: self.model_weight,
+            annotate: self.annotate,
+            #[cfg(feature = "text-ml")]
+            model: None,
+        })
+    }
+}
+
+pub struct NsfwFilter {
+    text_col: String,
+    score_col: String,
+    threshold: f64,
+    keywords: HashMap<String, f64>, // Lowercase
+    context_terms: HashSet<String>, // Lowercase
+    context_discount: f64,
+    #[cfg_attr(not(feature = "text-ml"), allow(dead_code))]
+    model_path: Option<String>,
+    #[cfg_attr(not(feature = "text-ml"), allow(dead_code))]
+    model_label: String,
+    model_weight: f64,
+    annotate: bool,
+    #[cfg(feature = "text-ml")]
+    model: Option<Arc<FastTextModel>>, // Loaded in open
+}
+
+impl NsfwFilter {
+    /// Keyword score of a text (0 to 1): discounted keyword weight per 100 words, saturating
+    pub fn keyword_score(&self, text: &str) -> f64 {
+        let (mut words, mut hits, mut context) = (0, 0.0, 0);
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            words += 1;
+            let word = word.to_lowercase();
+            if let Some(weight) = self.keywords.get(&word) {
+                hits += weight;
+            } else if self.context_terms.contains(&word) {
+                context += 1;
+            }
+        }
+        let hits = (hits - self.context_discount * context as f64).max(0.0);
+        let density = 100.0 * hits / words.max(MIN_WORDS) as f64;
+        1.0 - (-density / 3.0).exp()
+    }
+
+    /// Probability of the NSFW label of the model, if any
+    fn model_score(&self, _text: &str) -> Option<f64> {
+        #[cfg(feature = "text-ml")]
+        if let Some(model) = &self.model {
+            let probability = model
+                .predict(_text, model.labels().len(), 0.0)
+                .into_iter()
+                .find(|(label, _)| *label == self.model_label)
+                .map_or(0.0, |(_, probability)| f64::from(probability));
+            return Some(probability);
+        }
+        None
+    }
+}
+
+impl Operator for NsfwFilter {
+    #[cfg_attr(not(feature = "text-ml"), allow(unused_variables))]
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        #[cfg(feature = "text-ml")]
+        if let Some(path) = &self.model_path {
+            let name = NsfwConfig::NAME;
+            let model = FastTextModel::shared(ctx, path)
+                .map_err(|e| OpError::fatal(format!("{}: {:#}", name, e)))?;
+            if !model.labels().contains(&self.model_label) {
+                return Err(OpError::fatal(format!(
+                    "{}: label {} is not in the model (labels: {})",
+                    name,
+                    self.model_label,
+                    model.labels().join(", ")
+                ))
+                .into());
+            }
+            self.model = Some(model);
+        }
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let keyword_score = self.keyword_score(text);
+        let score = match self.model_score(text) {
+            Some(model_score) => {
+                (1.0 - self.model_weight) * keyword_score + self.model_weight * model_score
+            }
+            None => keyword_score,
+        };
+        if !self.annotate && score >= self.threshold {
+            ctx.metrics().increment("rejected", 1);
+            return Ok(None);
+        }
+        sample.set_path(&self.score_col, Value::from(score))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.score_col),
+        )
+    }
+}
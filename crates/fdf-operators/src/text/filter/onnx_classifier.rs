@@ -0,0 +1,286 @@
+use crate::onnx::OnnxModel;
+use crate::text::model::{encode_windows, encoder_inputs, load_tokenizer, model_labels, pad_batch};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::Tokenizer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    Softmax, // Probabilities of exclusive classes
+    Sigmoid, // Independent probability of each label (multi-label models)
+    #[serde(rename = "none")]
+    Raw, // Model outputs as-is (regression heads such as the FineWeb-Edu scorer)
+}
+
+/// Scores documents with an ONNX sequence classification model (e.g. the FineWeb-Edu
+/// educational value scorer exported with optimum), writing one score field per label and
+/// keeping or dropping documents by per-label thresholds
+#[fdf_operator(
+    name = "text.onnx_classifier",
+    category = "filter",
+    build = "OnnxClassifierConfig::build"
+)]
+struct OnnxClassifierConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the ONNX sequence classification model
+    model_path: String,
+    /// Path of the model's tokenizer.json; tokenizer.json next to the model if unset
+    tokenizer_path: Option<String>,
+    /// Name of each model output (e.g. ["edu"]); id2label of the config.json next to the
+    /// model if unset
+    labels: Option<Vec<String>>,
+    /// Applied to the model outputs: "softmax", "sigmoid" or "none"; softmax for models with
+    /// several labels and none for single-output (regression) models if unset
+    #[param(type = "string")]
+    activation: Option<Activation>,
+    /// Prefix of the score fields: the score of label L is written to <prefix>L
+    #[param(default = "onnx_")]
+    prefix: String,
+    /// Tokens per model window, special tokens included
+    #[param(default = 512)]
+    max_length: usize,
+    /// Windows scored per document, the scores being averaged; 1 scores the beginning of
+    /// the text only, as classifiers are usually trained
+    #[param(default = 1)]
+    max_windows: usize,
+    /// Windows run through the model at once
+    #[param(default = 16)]
+    batch_size: usize,
+    /// Keep a document only if one of these labels has at least its threshold, e.g.
+    /// {edu: 3.0}
+    keep: Option<BTreeMap<String, f64>>,
+    /// Drop a document if one of these labels has at least its threshold, e.g. {spam: 0.9}
+    drop: Option<BTreeMap<String, f64>>,
+    /// Only write the scores (and <prefix>passed with keep or drop) and keep every document,
+    /// for threshold sweeps
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl OnnxClassifierConfig {
+    fn build(self) -> Result<OnnxClassifier> {
+        if self.max_length < 3 {
+            return Err(anyhow::anyhow!(
+                "{}: max_length must be at least 3",
+                Self::NAME
+            ));
+        }
+        if self.max_windows == 0 || self.batch_size == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_windows and batch_size must be positive",
+                Self::NAME
+            ));
+        }
+        let keep = self.keep.unwrap_or_default();
+        let drop = self.drop.unwrap_or_default();
+        if matches!(
+            self.activation,
+            Some(Activation::Softmax | Activation::Sigmoid)
+        ) {
+            for (label, threshold) in keep.iter().chain(&drop) {
+                if !(0.0..=1.0).contains(threshold) {
+                    return Err(anyhow::anyhow!(
+                        "{}: threshold of label {} must be between 0 and 1",
+                        Self::NAME,
+                        label
+                    ));
+                }
+            }
+        }
+        let tokenizer_path = self.tokenizer_path.unwrap_or_else(|| {
+            let path = Path::new(&self.model_path).with_file_name("tokenizer.json");
+            path.to_string_lossy().into_owned()
+        });
+        Ok(OnnxClassifier {
+            text_col: self.text_col,
+            model_path: self.model_path,
+            tokenizer_path,
+            labels: self.labels.unwrap_or_default(),
+            activation: self.activation,
+            prefix: self.prefix,
+            max_length: self.max_length,
+            max_windows: self.max_windows,
+            batch_size: self.batch_size,
+            keep,
+            drop,
+            annotate: self.annotate,
+            model: None,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct OnnxClassifier {
+    text_col: String,
+    model_path: String,
+    tokenizer_path: String,
+    labels: Vec<String>, // Read from config.json in open if not configured
+    activation: Option<Activation>, // Set in open from the number of labels if not configured
+    prefix: String,
+    max_length: usize,
+    max_windows: usize,
+    batch_size: usize,
+    keep: BTreeMap<String, f64>,
+    drop: BTreeMap<String, f64>,
+    annotate: bool,
+    model: Option<Arc<OnnxModel>>, // Loaded in open
+    tokenizer: Option<Tokenizer>,  // Loaded in open
+}
+
+impl OnnxClassifier {
+    /// Score of each label for a text, averaged over its windows
+    pub fn scores(&self, text: &str) -> Result<Vec<f64>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let num_labels = self.labels.len();
+        let mut windows = encode_windows(tokenizer, text)?;
+        windows.truncate(self.max_windows);
+
+        let mut sums = vec![0.0; num_labels];
+        for batch in windows.chunks_mut(self.batch_size) {
+            pad_batch(tokenizer, batch);
+            let batch: Vec<_> = batch.iter().collect();
+            let outputs = model.run(encoder_inputs(model, &batch))?;
+            let logits = outputs
+                .first()
+                .filter(|logits| logits.values.len() == batch.len() * num_labels)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Model output does not match {} windows x {} labels",
+                        batch.len(),
+                        num_labels
+                    )
+                })?;
+            for row in logits.values.chunks(num_labels) {
+                for (sum, score) in sums.iter_mut().zip(self.activate(row)) {
+                    *sum += score;
+                }
+            }
+        }
+        Ok(sums
+            .into_iter()
+            .map(|sum| sum / windows.len() as f64)
+            .collect())
+    }
+
+    fn activate(&self, logits: &[f32]) -> Vec<f64> {
+        let logits = logits.iter().map(|&logit| f64::from(logit));
+        match self.activation.expect("activation is set in open") {
+            Activation::Softmax => {
+                let max = logits.clone().fold(f64::NEG_INFINITY, f64::max);
+                let exp: Vec<f64> = logits.map(|logit| (logit - max).exp()).collect();
+                let sum: f64 = exp.iter().sum();
+                exp.into_iter().map(|e| e / sum).collect()
+            }
+            Activation::Sigmoid => logits.map(|logit| 1.0 / (1.0 + (-logit).exp())).collect(),
+            Activation::Raw => logits.collect(),
+        }
+    }
+
+    /// Why a document with these label scores is rejected, if it is
+    fn reject(&self, scores: &BTreeMap<&str, f64>) -> Option<String> {
+        let score = |label: &str| scores.get(label).copied().unwrap_or(f64::NEG_INFINITY);
+        if let Some(label) = self
+            .drop
+            .iter()
+            .find(|(label, &threshold)| score(label) >= threshold)
+            .map(|(label, _)| label)
+        {
+            return Some(format!("drop.{}", label));
+        }
+        if !self.keep.is_empty()
+            && !self
+                .keep
+                .iter()
+                .any(|(label, &threshold)| score(label) >= threshold)
+        {
+            return Some("keep".to_string());
+        }
+        None
+    }
+
+    fn filters(&self) -> bool {
+        !self.keep.is_empty() || !self.drop.is_empty()
+    }
+}
+
+impl Operator for OnnxClassifier {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = OnnxClassifierConfig::NAME;
+        let fatal = |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", name, e));
+        if self.labels.is_empty() {
+            self.labels = model_labels(&self.model_path).map_err(fatal)?;
+        }
+        for label in self.keep.keys().chain(self.drop.keys()) {
+            if !self.labels.contains(label) {
+                return Err(fatal(anyhow::anyhow!(
+                    "label {} is not in the model (labels: {})",
+                    label,
+                    self.labels.join(", ")
+                ))
+                .into());
+            }
+        }
+        self.activation.get_or_insert(if self.labels.len() > 1 {
+            Activation::Softmax
+        } else {
+            Activation::Raw
+        });
+        self.model = Some(OnnxModel::load(&self.model_path, ctx).map_err(fatal)?);
+        self.tokenizer =
+            Some(load_tokenizer(&self.tokenizer_path, self.max_length, ctx).map_err(fatal)?);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let values = self.scores(text)?;
+        let scores: BTreeMap<&str, f64> =
+            self.labels.iter().map(String::as_str).zip(values).collect();
+        let rejected = self.reject(&scores);
+
+        if !self.annotate {
+            if let Some(reason) = &rejected {
+                ctx.metrics().increment(&format!("rejected.{}", reason), 1);
+                return Ok(None);
+            }
+        }
+        for (label, score) in &scores {
+            sample.set_path(&format!("{}{}", self.prefix, label), Value::from(*score))?;
+        }
+        if self.annotate && self.filters() {
+            sample.set_path(
+                &format!("{}passed", self.prefix),
+                Value::Bool(rejected.is_none()),
+            )?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // Score fields depend on the model labels, known once opened
+        if self.labels.is_empty() {
+            return None;
+        }
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        for label in &self.labels {
+            columns = columns.produces(format!("{}{}", self.prefix, label));
+        }
+        if self.annotate && self.filters() {
+            columns = columns.produces(format!("{}passed", self.prefix));
+        }
+        Some(columns)
+    }
+}
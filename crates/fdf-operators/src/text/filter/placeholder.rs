@@ -0,0 +1,191 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::{Regex, RegexBuilder};
+
+/// Categories of placeholder and error pages with their patterns (case-insensitive), checked
+/// in order
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "lorem_ipsum",
+        &[
+            r"\blorem ipsum\b",
+            r"\bdolor sit amet\b",
+            r"\bconsectetur adipiscing elit\b",
+            r"\bsed do eiusmod tempor\b",
+        ],
+    ),
+    (
+        "error_page",
+        &[
+            r"\b404\b.{0,40}\b(not found|error)\b",
+            r"\bpage not found\b",
+            r"\bthe page you (were|are) looking for\b",
+            r"\bthe requested (url|page) was not found\b",
+            r"\bthis page (does not|doesn't) exist\b",
+            r"\b(500 )?internal server error\b",
+            r"\b(502 )?bad gateway\b",
+            r"\b(503 )?service (temporarily )?unavailable\b",
+            r"\bsomething went wrong\b",
+        ],
+    ),
+    (
+        "access_denied",
+        &[
+            r"\baccess denied\b",
+            r"\b403\b.{0,20}\bforbidden\b",
+            r"\byou (don't|do not) have permission to access\b",
+            r"\byour (ip|ip address|access) has been (blocked|banned|denied)\b",
+            r"\bnot available in your (country|region)\b",
+            r"\bplease log ?in to (continue|view|access)\b",
+        ],
+    ),
+    (
+        "captcha",
+        &[
+            r"\bcaptcha\b",
+            r"\bverify (that )?you are (a )?human\b",
+            r"\bare you a robot\b",
+            r"\bi'?m not a robot\b",
+            r"\bchecking (if the site connection is secure|your browser before accessing)\b",
+            r"\benable javascript and cookies to continue\b",
+            r"\bunusual traffic from your (computer )?network\b",
+            r"\bcomplete the security check\b",
+        ],
+    ),
+    (
+        "cookie_wall",
+        &[
+            r"\b(we|this (web)?site) uses? cookies\b",
+            r"\baccept (all )?cookies\b",
+            r"\bcookie (policy|settings|preferences|consent)\b",
+            r"\bby (continuing to (use|browse)|using) (this|our) (web)?site,? you (agree|consent)\b",
+        ],
+    ),
+];
+
+/// Categories found anywhere in a document; the others only in short documents, as a longer
+/// page mentioning them is real content
+const ANYWHERE: &[&str] = &["lorem_ipsum"];
+
+/// Drops placeholder text (lorem ipsum) and pages without content: error pages (404, 5xx),
+/// access denied pages, CAPTCHA interstitials and cookie-consent walls
+#[fdf_operator(
+    name = "text.placeholder_filter",
+    category = "filter",
+    build = "PlaceholderConfig::build"
+)]
+struct PlaceholderConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Categories detected (lorem_ipsum, error_page, access_denied, captcha, cookie_wall);
+    /// all if unset
+    categories: Option<Vec<String>>,
+    /// Documents with more words are only checked for lorem ipsum
+    #[param(default = 200)]
+    max_words: usize,
+    /// Only write the matched category to category_col, keeping every document
+    #[param(default = false)]
+    annotate: bool,
+    /// Field receiving the matched category (null if none), with annotate
+    #[param(default = "placeholder_category")]
+    category_col: String,
+}
+
+impl PlaceholderConfig {
+    fn build(self) -> Result<PlaceholderFilter> {
+        let selected = match &self.categories {
+            Some(categories) => {
+                for category in categories {
+                    if !CATEGORIES.iter().any(|(name, _)| name == category) {
+                        return Err(anyhow::anyhow!(
+                            "{}: unknown category {} (expected one of {})",
+                            Self::NAME,
+                            category,
+                            CATEGORIES
+                                .iter()
+                                .map(|(name, _)| *name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ));
+                    }
+                }
+                categories.clone()
+            }
+            None => CATEGORIES
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        };
+        let categories = CATEGORIES
+            .iter()
+            .filter(|(name, _)| selected.iter().any(|category| category == name))
+            .map(|&(name, patterns)| {
+                let pattern = RegexBuilder::new(&patterns.join("|"))
+                    .case_insensitive(true)
+                    .build()?;
+                Ok((name, pattern))
+            })
+            .collect::<Result<_>>()?;
+        Ok(PlaceholderFilter {
+            text_col: self.text_col,
+            categories,
+            max_words: self.max_words,
+            annotate: self.annotate,
+            category_col: self.category_col,
+        })
+    }
+}
+
+pub struct PlaceholderFilter {
+    text_col: String,
+    categories: Vec<(&'static str, Regex)>,
+    max_words: usize,
+    annotate: bool,
+    category_col: String,
+}
+
+impl PlaceholderFilter {
+    /// First category a text matches, if any
+    pub fn category(&self, text: &str) -> Option<&'static str> {
+        let short = text.split_whitespace().nth(self.max_words).is_none();
+        self.categories
+            .iter()
+            .filter(|(name, _)| short || ANYWHERE.contains(name))
+            .find(|(_, pattern)| pattern.is_match(text))
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Operator for PlaceholderFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let category = self.category(text);
+        if self.annotate {
+            sample.set_path(
+                &self.category_col,
+                category.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match category {
+            Some(category) => {
+                ctx.metrics()
+                    .increment(&format!("rejected.{}", category), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new().requires(&self.text_col);
+        Some(if self.annotate {
+            columns.produces(&self.category_col)
+        } else {
+            columns
+        })
+    }
+}
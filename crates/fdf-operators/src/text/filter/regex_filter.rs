@@ -0,0 +1,101 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    KeepMatch, // Keep matching documents, drop the others
+    DropMatch, // Drop matching documents
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Combine {
+    Any, // A document matches if one pattern matches
+    All, // A document matches if every pattern matches
+}
+
+/// Keeps or drops documents whose field matches regular expressions
+#[fdf_operator(
+    name = "text.regex_filter",
+    category = "filter",
+    build = "RegexFilterConfig::build"
+)]
+struct RegexFilterConfig {
+    /// Field matched (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Regular expressions (Rust regex syntax), matched anywhere in the field unless anchored
+    /// with ^ / $
+    patterns: Vec<String>,
+    /// "keep_match" keeps matching documents only, "drop_match" drops them
+    #[param(default = "drop_match", type = "string")]
+    mode: Mode,
+    /// "any": a document matches if one pattern matches; "all": if every pattern matches
+    #[param(default = "any", type = "string")]
+    combine: Combine,
+    /// Match case-insensitively
+    #[param(default = false)]
+    ignore_case: bool,
+}
+
+impl RegexFilterConfig {
+    fn build(self) -> Result<RegexFilter> {
+        if self.patterns.is_empty() {
+            return Err(anyhow::anyhow!("{}: patterns is empty", Self::NAME));
+        }
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(self.ignore_case)
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("{}: invalid pattern: {}", Self::NAME, e))
+            })
+            .collect::<Result<_>>()?;
+        Ok(RegexFilter {
+            text_col: self.text_col,
+            patterns,
+            mode: self.mode,
+            combine: self.combine,
+        })
+    }
+}
+
+pub struct RegexFilter {
+    text_col: String,
+    patterns: Vec<Regex>,
+    mode: Mode,
+    combine: Combine,
+}
+
+impl RegexFilter {
+    pub fn matches(&self, text: &str) -> bool {
+        match self.combine {
+            Combine::Any => self.patterns.iter().any(|pattern| pattern.is_match(text)),
+            Combine::All => self.patterns.iter().all(|pattern| pattern.is_match(text)),
+        }
+    }
+}
+
+impl Operator for RegexFilter {
+    fn process_with_context(&self, sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let keep = self.matches(text) == (self.mode == Mode::KeepMatch);
+        if keep {
+            Ok(Some(sample))
+        } else {
+            ctx.metrics().increment("rejected", 1);
+            Ok(None)
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
@@ -0,0 +1,256 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+use unicode_script::{Script, UnicodeScript};
+
+/// Scripts written together in CJK languages (Japanese kana with kanji, Korean hangul with
+/// hanja), counted as one script
+const CJK: &[Script] = &[
+    Script::Han,
+    Script::Hiragana,
+    Script::Katakana,
+    Script::Hangul,
+    Script::Bopomofo,
+];
+
+/// Scripts written without spaces between words, whose letters separate words when checking
+/// for mixed words ("東京はTokyo" is not spoofing)
+const UNSPACED: &[Script] = &[Script::Thai, Script::Lao, Script::Khmer, Script::Myanmar];
+
+/// Drops documents mixing implausible scripts (Cyrillic letters inside Latin words, many
+/// scripts in one text) or with garbage characters (replacement characters, private-use and
+/// control codepoints), usually encoding corruption or spam
+#[fdf_operator(
+    name = "text.script_filter",
+    category = "filter",
+    build = "ScriptFilterConfig::build"
+)]
+struct ScriptFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Drop documents with a higher fraction of garbage characters (U+FFFD, private use,
+    /// control characters other than tab and newlines, unassigned) among non-space characters
+    #[param(default = 0.01)]
+    max_garbage_ratio: f64,
+    /// Drop documents with more scripts holding at least min_script_fraction of the letters
+    /// (CJK scripts count as one; 0: unlimited)
+    #[param(default = 2)]
+    max_scripts: usize,
+    /// Fraction of the letters a script needs to count for max_scripts
+    #[param(default = 0.05)]
+    min_script_fraction: f64,
+    /// Drop documents with a higher fraction of words mixing letters of several scripts
+    /// (homoglyph spoofing such as a Cyrillic "а" in a Latin word)
+    #[param(default = 0.1)]
+    max_mixed_word_ratio: f64,
+    /// Only write the statistics (<prefix>distribution, the fraction of letters per script,
+    /// <prefix>scripts, <prefix>garbage_ratio, <prefix>mixed_word_ratio) and the failed rule
+    /// (<prefix>rejected_by, null if passed), keeping every document
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the fields written with annotate
+    #[param(default = "script_")]
+    prefix: String,
+}
+
+impl ScriptFilterConfig {
+    fn build(self) -> Result<ScriptFilter> {
+        for (name, value) in [
+            ("max_garbage_ratio", self.max_garbage_ratio),
+            ("min_script_fraction", self.min_script_fraction),
+            ("max_mixed_word_ratio", self.max_mixed_word_ratio),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow::anyhow!(
+                    "{}: {} must be between 0 and 1",
+                    Self::NAME,
+                    name
+                ));
+            }
+        }
+        Ok(ScriptFilter {
+            text_col: self.text_col,
+            max_garbage_ratio: self.max_garbage_ratio,
+            max_scripts: self.max_scripts,
+            min_script_fraction: self.min_script_fraction,
+            max_mixed_word_ratio: self.max_mixed_word_ratio,
+            annotate: self.annotate,
+            prefix: self.prefix,
+        })
+    }
+}
+
+pub struct ScriptFilter {
+    text_col: String,
+    max_garbage_ratio: f64,
+    max_scripts: usize,
+    min_script_fraction: f64,
+    max_mixed_word_ratio: f64,
+    annotate: bool,
+    prefix: String,
+}
+
+/// Script statistics of a text
+#[derive(Debug, Clone, Default)]
+pub struct ScriptStats {
+    pub distribution: BTreeMap<&'static str, f64>, // Fraction of the letters per script
+    pub scripts: usize, // Scripts (CJK counting as one) above min_script_fraction
+    pub garbage_ratio: f64,
+    pub mixed_word_ratio: f64,
+}
+
+impl ScriptFilter {
+    pub fn stats(&self, text: &str) -> ScriptStats {
+        let mut letters: BTreeMap<&'static str, usize> = BTreeMap::new();
+        let mut groups: BTreeMap<&'static str, usize> = BTreeMap::new();
+        let mut total_letters = 0;
+        let mut chars = 0; // Non-space characters
+        let mut garbage = 0;
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            chars += 1;
+            if is_garbage(c) {
+                garbage += 1;
+            } else if let Some(script) = letter_script(c) {
+                total_letters += 1;
+                *letters.entry(script.full_name()).or_default() += 1;
+                *groups.entry(group(script)).or_default() += 1;
+            }
+        }
+
+        let mut words = 0; // Words with letters
+        let mut mixed_words = 0;
+        for word in text.split_whitespace() {
+            let mut word_group = None;
+            let mut mixed = false;
+            let mut letters = false;
+            for script in word.chars().filter_map(letter_script) {
+                letters = true;
+                if CJK.contains(&script) || UNSPACED.contains(&script) {
+                    word_group = None;
+                    continue;
+                }
+                let script_group = group(script);
+                mixed |= word_group.is_some_and(|word_group| word_group != script_group);
+                word_group = Some(script_group);
+            }
+            if letters {
+                words += 1;
+                mixed_words += usize::from(mixed);
+            }
+        }
+
+        let fraction = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 / total as f64
+            }
+        };
+        ScriptStats {
+            distribution: letters
+                .into_iter()
+                .map(|(script, count)| (script, fraction(count, total_letters)))
+                .collect(),
+            scripts: groups
+                .values()
+                .filter(|&&count| fraction(count, total_letters) >= self.min_script_fraction)
+                .count(),
+            garbage_ratio: fraction(garbage, chars),
+            mixed_word_ratio: fraction(mixed_words, words),
+        }
+    }
+
+    /// First rule a document fails
+    fn reject(&self, stats: &ScriptStats) -> Option<&'static str> {
+        if stats.garbage_ratio > self.max_garbage_ratio {
+            Some("garbage_ratio")
+        } else if self.max_scripts > 0 && stats.scripts > self.max_scripts {
+            Some("scripts")
+        } else if stats.mixed_word_ratio > self.max_mixed_word_ratio {
+            Some("mixed_words")
+        } else {
+            None
+        }
+    }
+}
+
+/// Script of a letter, None for other characters and letters shared by scripts
+fn letter_script(c: char) -> Option<Script> {
+    if !c.is_alphabetic() {
+        return None;
+    }
+    match c.script() {
+        Script::Common | Script::Inherited | Script::Unknown => None,
+        script => Some(script),
+    }
+}
+
+/// Script counted for mixtures: CJK scripts are one
+fn group(script: Script) -> &'static str {
+    if CJK.contains(&script) {
+        "CJK"
+    } else {
+        script.full_name()
+    }
+}
+
+/// Whether a character is a replacement, private-use, control or unassigned codepoint
+fn is_garbage(c: char) -> bool {
+    matches!(c as u32,
+        0xFFFD // Replacement character
+        | 0xE000..=0xF8FF | 0xF0000..=0xFFFFD | 0x100000..=0x10FFFD // Private use
+    ) || (c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        || (c.script() == Script::Unknown && !c.is_whitespace())
+}
+
+impl Operator for ScriptFilter {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let stats = self.stats(text);
+        let rejected = self.reject(&stats);
+
+        if !self.annotate {
+            return Ok(match rejected {
+                Some(rule) => {
+                    ctx.metrics().increment(&format!("rejected.{}", rule), 1);
+                    None
+                }
+                None => Some(sample),
+            });
+        }
+        let distribution = stats
+            .distribution
+            .iter()
+            .map(|(script, fraction)| (script.to_string(), Value::from(*fraction)))
+            .collect();
+        for (stat, value) in [
+            ("distribution", Value::Object(distribution)),
+            ("scripts", Value::from(stats.scripts)),
+            ("garbage_ratio", Value::from(stats.garbage_ratio)),
+            ("mixed_word_ratio", Value::from(stats.mixed_word_ratio)),
+            ("rejected_by", rejected.map_or(Value::Null, Value::from)),
+        ] {
+            sample.set_path(&format!("{}{}", self.prefix, stat), value)?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new().requires(&self.text_col);
+        if self.annotate {
+            for stat in [
+                "distribution",
+                "scripts",
+                "garbage_ratio",
+                "mixed_word_ratio",
+                "rejected_by",
+            ] {
+                columns = columns.produces(format!("{}{}", self.prefix, stat));
+            }
+        }
+        Some(columns)
+    }
+}
@@ -1,82 +1,236 @@
-use fdf_sdk::{Operator, Result, Sample};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
 use regex::Regex;
+use serde::Deserialize;
+
+/// Symbols of the Gopher rule: '#' and ellipses
+const GOPHER_SYMBOLS: &str = r"#|\.\.\.|\. \. \.|\u{2026}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Per {
+    Words, // Symbol matches per whitespace-separated word
+    Chars, // Characters in symbol matches per character
+}
 
 pub struct SymbolRatioFilter {
     text_col: String,
-    max_symbol_to_word_ratio: f64,
     symbol_pattern: Regex, // Pre-compiled regex for better performance
+    per: Per,
+    min_ratio: f64,
+    max_ratio: f64,
+    annotate: bool,
+    prefix: String,
 }
 
 impl SymbolRatioFilter {
-    /// Create a new SymbolRatioFilter with pre-compiled regex
+    /// Create a new SymbolRatioFilter counting the Gopher symbols per word
     pub fn new(text_col: String, max_symbol_to_word_ratio: f64) -> Result<Self> {
+        Self::with_pattern(
+            text_col,
+            GOPHER_SYMBOLS,
+            Per::Words,
+            0.0,
+            max_symbol_to_word_ratio,
+        )
+    }
+
+    /// Create a new SymbolRatioFilter keeping ratios of `pattern` matches within
+    /// [min_ratio, max_ratio]
+    pub fn with_pattern(
+        text_col: String,
+        pattern: &str,
+        per: Per,
+        min_ratio: f64,
+        max_ratio: f64,
+    ) -> Result<Self> {
         // Compile regex once during initialization
-        let symbol_pattern = Regex::new(r"#|\.\.\.|\. \. \.|\u{2026}")?;
+        let symbol_pattern = Regex::new(pattern)?;
         Ok(Self {
             text_col,
-            max_symbol_to_word_ratio,
             symbol_pattern,
+            per,
+            min_ratio,
+            max_ratio,
+            annotate: false,
+            prefix: String::new(),
         })
     }
+
+    /// Ratio of symbols in a text
+    pub fn ratio(&self, text: &str) -> f64 {
+        match self.per {
+            Per::Words => {
+                // Count symbols using pre-compiled regex (much faster)
+                let num_symbols = self.symbol_pattern.find_iter(text).count();
+                num_symbols as f64 / count_words(text) as f64
+            }
+            Per::Chars => {
+                let num_symbol_chars: usize = self
+                    .symbol_pattern
+                    .find_iter(text)
+                    .map(|m| m.as_str().chars().count())
+                    .sum();
+                num_symbol_chars as f64 / text.chars().count().max(1) as f64
+            }
+        }
+    }
+
+    /// Threshold a ratio fails
+    fn reject(&self, ratio: f64) -> Option<&'static str> {
+        if ratio < self.min_ratio {
+            Some("min_ratio")
+        } else if ratio > self.max_ratio {
+            Some("max_ratio")
+        } else {
+            None
+        }
+    }
+}
+
+/// Whitespace-separated words, at least 1
+fn count_words(text: &str) -> usize {
+    // Count words efficiently using byte-based iteration for better performance
+    // This avoids the overhead of char iteration and is faster for ASCII text
+    let bytes = text.as_bytes();
+    let mut word_count = 0;
+    let mut in_word = false;
+
+    for &byte in bytes {
+        let is_whitespace = byte == b' ' || byte == b'\t' || byte == b'\n' || byte == b'\r';
+        if is_whitespace {
+            if in_word {
+                in_word = false;
+            }
+        } else if !in_word {
+            word_count += 1;
+            in_word = true;
+        }
+    }
+    word_count.max(1)
 }
 
 impl Operator for SymbolRatioFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
         // Get text field
         let text = sample
-            .get_str(&self.text_col)
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
             .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
 
-        // Count symbols using pre-compiled regex (much faster)
-        let num_symbols = self.symbol_pattern.find_iter(text).count();
+        // Calculate ratio
+        let ratio = self.ratio(text);
+        let rejected = self.reject(ratio);
 
-        // Count words efficiently using byte-based iteration for better performance
-        // This avoids the overhead of char iteration and is faster for ASCII text
-        let num_words = if text.is_empty() {
-            1
-        } else {
-            // Use byte-based counting for ASCII text (most common case)
-            // This is faster than char-based iteration
-            let bytes = text.as_bytes();
-            let mut word_count = 0;
-            let mut in_word = false;
-
-            for &byte in bytes {
-                let is_whitespace = byte == b' ' || byte == b'\t' || byte == b'\n' || byte == b'\r';
-                if is_whitespace {
-                    if in_word {
-                        in_word = false;
-                    }
-                } else if !in_word {
-                    word_count += 1;
-                    in_word = true;
-                }
-            }
-            word_count.max(1)
-        };
+        if self.annotate {
+            sample.set_path(&format!("{}ratio", self.prefix), Value::from(ratio))?;
+            sample.set_path(
+                &format!("{}rejected_by", self.prefix),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
 
-        // Calculate ratio
-        let ratio = num_symbols as f64 / num_words as f64;
+        // Filter: keep rows where min_ratio <= ratio <= max_ratio
+        match rejected {
+            Some(threshold) => {
+                ctx.metrics()
+                    .increment(&format!("rejected.{}", threshold), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
 
-        // Filter: keep rows where ratio <= max_symbol_to_word_ratio
-        if ratio <= self.max_symbol_to_word_ratio {
-            Ok(Some(sample))
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new().requires(&self.text_col);
+        Some(if self.annotate {
+            columns
+                .produces(format!("{}ratio", self.prefix))
+                .produces(format!("{}rejected_by", self.prefix))
         } else {
-            Ok(None)
-        }
+            columns
+        })
     }
 }
 
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register("text_symbol_ratio_filter", |config: &serde_yaml::Value| {
-        let text_col = config["text_col"].as_str().unwrap_or("text").to_string();
-        let max_symbol_to_word_ratio = config["max_symbol_to_word_ratio"]
-            .as_f64()
-            .unwrap_or(f64::MAX);
+/// Keeps samples whose ratio of symbols to words (or characters) is within thresholds; the
+/// symbols default to the Gopher ones ('#', '...')
+#[fdf_operator(
+    name = "text.symbol_ratio_filter",
+    category = "filter",
+    alias = "text_symbol_ratio_filter",
+    build = "SymbolRatioFilterConfig::build"
+)]
+struct SymbolRatioFilterConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Symbols counted, as literal strings (e.g. ["{", "}", ";"]); the Gopher symbols ('#',
+    /// "...", ". . .", '…') if neither symbols nor symbol_pattern is set
+    symbols: Option<Vec<String>>,
+    /// Regex of the symbols counted, instead of symbols
+    symbol_pattern: Option<String>,
+    /// Denominator of the ratio: "words" (symbol matches per word) or "chars" (characters in
+    /// symbol matches per character)
+    #[param(default = "words", type = "string")]
+    per: Per,
+    /// Minimum ratio kept
+    #[param(default = 0.0)]
+    min_ratio: f64,
+    /// Maximum ratio kept (unbounded if unset)
+    #[serde(alias = "max_symbol_to_word_ratio")]
+    max_ratio: Option<f64>,
+    /// Keep every document and write the ratio (<prefix>ratio) and the failed threshold
+    /// (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+    /// Prefix of the annotation fields
+    #[param(default = "symbol_")]
+    prefix: String,
+}
 
-        Ok(Box::new(SymbolRatioFilter::new(
-            text_col,
-            max_symbol_to_word_ratio,
-        )?))
-    });
+impl SymbolRatioFilterConfig {
+    fn build(self) -> Result<SymbolRatioFilter> {
+        let pattern = match (self.symbols, self.symbol_pattern) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "{}: set either symbols or symbol_pattern",
+                    Self::NAME
+                ))
+            }
+            (Some(symbols), None) => {
+                if symbols.iter().all(String::is_empty) {
+                    return Err(anyhow::anyhow!("{}: symbols is empty", Self::NAME));
+                }
+                symbols
+                    .iter()
+                    .filter(|symbol| !symbol.is_empty())
+                    .map(|symbol| regex::escape(symbol))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            }
+            (None, Some(pattern)) => pattern,
+            (None, None) => GOPHER_SYMBOLS.to_string(),
+        };
+        let max_ratio = self.max_ratio.unwrap_or(f64::MAX);
+        if self.min_ratio > max_ratio {
+            return Err(anyhow::anyhow!(
+                "{}: min_ratio is above max_ratio",
+                Self::NAME
+            ));
+        }
+        let filter = SymbolRatioFilter::with_pattern(
+            self.text_col,
+            &pattern,
+            self.per,
+            self.min_ratio,
+            max_ratio,
+        )
+        .map_err(|e| anyhow::anyhow!("{}: invalid symbol_pattern: {}", Self::NAME, e))?;
+        Ok(SymbolRatioFilter {
+            annotate: self.annotate,
+            prefix: self.prefix,
+            ..filter
+        })
+    }
 }
@@ -1,13 +1,92 @@
-use fdf_sdk::{Operator, Result, Sample};
+use crate::text::tokenizer::Tokenizer;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Bytes,  // UTF-8 bytes
+    Chars,  // Unicode characters
+    Words,  // Runs of non-whitespace
+    Tokens, // Tokens of `tokenizer`
+}
+
+/// Keeps samples whose text length (in characters, bytes, words or tokens) is within bounds
+#[fdf_operator(
+    name = "text.len_filter",
+    category = "filter",
+    alias = "text_len_filter",
+    build = "TextLenFilterConfig::build"
+)]
+struct TextLenFilterConfig {
+    /// Field holding the text (dot path allowed)
+    text_col: String,
+    /// Minimum length kept
+    lower_bound: Option<u32>,
+    /// Maximum length kept
+    upper_bound: Option<u32>,
+    /// Unit the length is measured in: "chars", "bytes", "words" or "tokens"
+    #[param(default = "chars", type = "string")]
+    unit: Unit,
+    /// Tokenizer of unit "tokens": a tiktoken encoding (cl100k_base, o200k_base, p50k_base,
+    /// r50k_base) or the path of a HuggingFace tokenizer.json
+    #[param(default = "cl100k_base")]
+    tokenizer: String,
+}
+
+impl TextLenFilterConfig {
+    fn build(self) -> Result<TextLenFilter> {
+        if self.unit == Unit::Tokens {
+            Tokenizer::check(&self.tokenizer)
+                .map_err(|e| anyhow::anyhow!("{}: {:#}", Self::NAME, e))?;
+        }
+        Ok(TextLenFilter {
+            text_col: self.text_col,
+            lower_bound: self.lower_bound,
+            upper_bound: self.upper_bound,
+            unit: self.unit,
+            tokenizer_name: self.tokenizer,
+            tokenizer: None,
+        })
+    }
+}
 
 pub struct TextLenFilter {
     text_col: String,
     lower_bound: Option<u32>,
     upper_bound: Option<u32>,
+    unit: Unit,
+    tokenizer_name: String,
+    tokenizer: Option<Tokenizer>, // Loaded in open for unit "tokens"
+}
+
+impl TextLenFilter {
+    /// Length of a text in the configured unit
+    pub fn length(&self, text: &str) -> Result<usize> {
+        match self.unit {
+            Unit::Bytes => Ok(text.len()),
+            Unit::Chars => Ok(text.chars().count()),
+            Unit::Words => Ok(text.split_whitespace().count()),
+            Unit::Tokens => self
+                .tokenizer
+                .as_ref()
+                .expect("tokenizer is loaded in open")
+                .count(text),
+        }
+    }
 }
 
 impl Operator for TextLenFilter {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        if self.unit == Unit::Tokens {
+            let tokenizer = Tokenizer::load(&self.tokenizer_name, ctx)
+                .map_err(|e| OpError::fatal(format!("{}: {:#}", TextLenFilterConfig::NAME, e)))?;
+            self.tokenizer = Some(tokenizer);
+        }
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
         // If no bounds specified, keep all records
         if self.lower_bound.is_none() && self.upper_bound.is_none() {
             return Ok(Some(sample));
@@ -15,15 +94,21 @@ impl Operator for TextLenFilter {
 
         // Get text field
         let text = sample
-            .get_str(&self.text_col)
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
             .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
 
-        // Calculate length (character count)
-        let len = text.chars().count() as u32;
+        let len = self.length(text)?;
 
         // Check bounds
-        let lower_ok = self.lower_bound.map(|lb| len >= lb).unwrap_or(true);
-        let upper_ok = self.upper_bound.map(|ub| len <= ub).unwrap_or(true);
+        let lower_ok = self
+            .lower_bound
+            .map(|lb| len >= lb as usize)
+            .unwrap_or(true);
+        let upper_ok = self
+            .upper_bound
+            .map(|ub| len <= ub as usize)
+            .unwrap_or(true);
 
         if lower_ok && upper_ok {
             Ok(Some(sample))
@@ -31,18 +116,8 @@ impl Operator for TextLenFilter {
             Ok(None)
         }
     }
-}
 
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register("text_len_filter", |config: &serde_yaml::Value| {
-        let text_col = config["text_col"].as_str().unwrap().to_string();
-        let lower_bound = config["lower_bound"].as_u64().map(|v| v as u32);
-        let upper_bound = config["upper_bound"].as_u64().map(|v| v as u32);
-
-        Ok(Box::new(TextLenFilter {
-            text_col,
-            lower_bound,
-            upper_bound,
-        }))
-    });
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
 }
@@ -0,0 +1,179 @@
+use crate::text::fasttext::FastTextModel;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Labels of toxicity models meaning "not toxic", not scored as categories by default
+const NON_TOXIC_LABELS: &[&str] = &[
+    "non_toxic",
+    "non-toxic",
+    "nontoxic",
+    "not_toxic",
+    "clean",
+    "neutral",
+    "safe",
+    "ok",
+];
+
+/// Scores documents with a fasttext toxicity model (e.g. Jigsaw-trained threat, insult, hate
+/// classifiers), writing a probability per toxicity category and dropping documents where a
+/// category reaches its threshold
+#[fdf_operator(
+    name = "text.toxicity_filter",
+    category = "filter",
+    build = "ToxicityConfig::build"
+)]
+struct ToxicityConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the fasttext toxicity model (.bin, softmax or multi-label one-vs-all)
+    model_path: String,
+    /// Toxicity categories (model labels without the "__label__" prefix); every label of the
+    /// model except non-toxic ones (non_toxic, clean, neutral, safe, ...) if unset
+    categories: Option<Vec<String>>,
+    /// Default threshold of the categories (0 to 1)
+    #[param(default = 0.5)]
+    threshold: f64,
+    /// Thresholds of single categories, overriding threshold, e.g. {insult: 0.8}
+    thresholds: Option<BTreeMap<String, f64>>,
+    /// Prefix of the score fields: <prefix><category> for each category and <prefix>score
+    /// for the highest (for down-weighting)
+    #[param(default = "toxicity_")]
+    prefix: String,
+    /// Only write the scores and <prefix>toxic, keeping every document
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl ToxicityConfig {
+    fn build(self) -> Result<ToxicityFilter> {
+        let thresholds = self.thresholds.unwrap_or_default();
+        let named = thresholds.iter().map(|(c, t)| (c.as_str(), *t));
+        for (category, threshold) in std::iter::once(("threshold", self.threshold)).chain(named) {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow::anyhow!(
+                    "{}: threshold of {} must be between 0 and 1",
+                    Self::NAME,
+                    category
+                ));
+            }
+        }
+        Ok(ToxicityFilter {
+            text_col: self.text_col,
+            model_path: self.model_path,
+            categories: self.categories,
+            threshold: self.threshold,
+            thresholds,
+            prefix: self.prefix,
+            annotate: self.annotate,
+            model: None,
+        })
+    }
+}
+
+pub struct ToxicityFilter {
+    text_col: String,
+    model_path: String,
+    categories: Option<Vec<String>>, // Resolved from the model labels in open
+    threshold: f64,
+    thresholds: BTreeMap<String, f64>,
+    prefix: String,
+    annotate: bool,
+    model: Option<Arc<FastTextModel>>, // Loaded in open
+}
+
+impl ToxicityFilter {
+    fn threshold(&self, category: &str) -> f64 {
+        self.thresholds
+            .get(category)
+            .copied()
+            .unwrap_or(self.threshold)
+    }
+}
+
+impl Operator for ToxicityFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = ToxicityConfig::NAME;
+        let model = FastTextModel::shared(ctx, &self.model_path)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", name, e)))?;
+        let labels = model.labels();
+        let categories = self.categories.get_or_insert_with(|| {
+            labels
+                .iter()
+                .filter(|label| !NON_TOXIC_LABELS.contains(&label.to_lowercase().as_str()))
+                .cloned()
+                .collect()
+        });
+        for category in categories.iter().chain(self.thresholds.keys()) {
+            if !labels.contains(category) {
+                return Err(OpError::fatal(format!(
+                    "{}: category {} is not a label of the model (labels: {})",
+                    name,
+                    category,
+                    labels.join(", ")
+                ))
+                .into());
+            }
+        }
+        if categories.is_empty() {
+            return Err(OpError::fatal(format!("{}: no toxicity categories", name)).into());
+        }
+        self.model = Some(model);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+
+        let predictions = model.predict(text, model.labels().len(), 0.0);
+        let scores: BTreeMap<&str, f64> = predictions
+            .iter()
+            .map(|(label, probability)| (label.as_str(), f64::from(*probability)))
+            .collect();
+        let categories = self.categories.as_deref().unwrap_or_default();
+        let score = |category: &String| scores.get(category.as_str()).copied().unwrap_or(0.0);
+        let toxic = categories
+            .iter()
+            .find(|category| score(category) >= self.threshold(category));
+
+        if let (Some(category), false) = (toxic, self.annotate) {
+            ctx.metrics()
+                .increment(&format!("rejected.{}", category), 1);
+            return Ok(None);
+        }
+        let mut max: f64 = 0.0;
+        for category in categories {
+            max = max.max(score(category));
+            sample.set_path(
+                &format!("{}{}", self.prefix, category),
+                Value::from(score(category)),
+            )?;
+        }
+        sample.set_path(&format!("{}score", self.prefix), Value::from(max))?;
+        if self.annotate {
+            sample.set_path(
+                &format!("{}toxic", self.prefix),
+                Value::Bool(toxic.is_some()),
+            )?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(format!("{}score", self.prefix));
+        for category in self.categories.iter().flatten() {
+            columns = columns.produces(format!("{}{}", self.prefix, category));
+        }
+        if self.annotate {
+            columns = columns.produces(format!("{}toxic", self.prefix));
+        }
+        Some(columns)
+    }
+}
@@ -0,0 +1,181 @@
+use aho_corasick::AhoCorasick;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::sync::Arc;
+
+/// Drops (or annotates) documents containing blocked words or phrases, matched as whole words
+/// so that a blocked word inside a longer one ("Scunthorpe", "classic") is not a match
+#[fdf_operator(
+    name = "text.word_blocklist_filter",
+    category = "filter",
+    build = "WordBlocklistConfig::build"
+)]
+struct WordBlocklistConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the blocklist: one word or phrase per line, # starting a comment line
+    blocklist_path: Option<String>,
+    /// Blocked words or phrases, in addition to the blocklist file
+    phrases: Option<Vec<String>>,
+    /// Match case-insensitively
+    #[param(default = true)]
+    ignore_case: bool,
+    /// Only match whole words (not preceded or followed by a letter or digit); disable for
+    /// languages written without spaces (Chinese, Japanese, Thai)
+    #[param(default = true)]
+    word_boundaries: bool,
+    /// Drop documents with more matches than this
+    #[param(default = 0)]
+    max_matches: usize,
+    /// Only write the number of matches to matches_col, keeping every document
+    #[param(default = false)]
+    annotate: bool,
+    /// Field receiving the number of matches, with annotate
+    #[param(default = "blocklist_matches")]
+    matches_col: String,
+}
+
+impl WordBlocklistConfig {
+    fn build(self) -> Result<WordBlocklistFilter> {
+        if self.blocklist_path.is_none() && self.phrases.is_none() {
+            return Err(anyhow::anyhow!(
+                "{}: set blocklist_path and/or phrases",
+                Self::NAME
+            ));
+        }
+        Ok(WordBlocklistFilter {
+            text_col: self.text_col,
+            blocklist_path: self.blocklist_path,
+            phrases: self.phrases.unwrap_or_default(),
+            ignore_case: self.ignore_case,
+            word_boundaries: self.word_boundaries,
+            max_matches: self.max_matches,
+            annotate: self.annotate,
+            matches_col: self.matches_col,
+            matcher: None,
+        })
+    }
+}
+
+pub struct WordBlocklistFilter {
+    text_col: String,
+    blocklist_path: Option<String>,
+    phrases: Vec<String>,
+    ignore_case: bool,
+    word_boundaries: bool,
+    max_matches: usize,
+    annotate: bool,
+    matches_col: String,
+    matcher: Option<Arc<AhoCorasick>>, // Built in open
+}
+
+impl WordBlocklistFilter {
+    /// Blocked phrases, from the file then the inline list
+    fn load_phrases(&self) -> Result<Vec<String>> {
+        let mut phrases = Vec::new();
+        if let Some(path) = &self.blocklist_path {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read blocklist {}: {}", path, e))?;
+            phrases.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        phrases.extend(self.phrases.iter().map(|phrase| phrase.trim().to_string()));
+        phrases.retain(|phrase| !phrase.is_empty());
+        if self.ignore_case {
+            for phrase in &mut phrases {
+                *phrase = phrase.to_lowercase();
+            }
+        }
+        Ok(phrases)
+    }
+
+    /// Number of blocked phrase occurrences in a text
+    pub fn count_matches(&self, text: &str) -> usize {
+        let matcher = self.matcher.as_ref().expect("matcher is built in open");
+        let lower;
+        let text = if self.ignore_case {
+            lower = text.to_lowercase();
+            &lower
+        } else {
+            text
+        };
+        whole_matches(matcher, text, self.word_boundaries).count()
+    }
+}
+
+/// Non-overlapping matches of `matcher` in `text`, only of whole words with `word_boundaries`
+pub(crate) fn whole_matches<'a>(
+    matcher: &'a AhoCorasick,
+    text: &'a str,
+    word_boundaries: bool,
+) -> impl Iterator<Item = aho_corasick::Match> + 'a {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    // Overlapping matches, so that a match failing the word boundaries ("classic") does not
+    // hide one passing them
+    let mut last_end = 0;
+    matcher.find_overlapping_iter(text).filter(move |m| {
+        if m.start() < last_end {
+            return false; // Inside a match counted already
+        }
+        if word_boundaries
+            && (is_word(text[..m.start()].chars().next_back())
+                || is_word(text[m.end()..].chars().next()))
+        {
+            return false;
+        }
+        last_end = m.end();
+        true
+    })
+}
+
+impl Operator for WordBlocklistFilter {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = WordBlocklistConfig::NAME;
+        let key = format!(
+            "word_blocklist:{}:{}:{}",
+            self.blocklist_path.as_deref().unwrap_or_default(),
+            self.phrases.join("\n"),
+            self.ignore_case
+        );
+        let matcher = ctx
+            .resources()
+            .get_or_load(&key, || {
+                let phrases = self.load_phrases()?;
+                AhoCorasick::new(&phrases).map_err(anyhow::Error::from)
+            })
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", name, e)))?;
+        self.matcher = Some(matcher);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let matches = self.count_matches(text);
+        if self.annotate {
+            sample.set_path(&self.matches_col, Value::from(matches))?;
+            return Ok(Some(sample));
+        }
+        if matches > self.max_matches {
+            ctx.metrics().increment("rejected", 1);
+            return Ok(None);
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new().requires(&self.text_col);
+        Some(if self.annotate {
+            columns.produces(&self.matches_col)
+        } else {
+            columns
+        })
+    }
+}
@@ -0,0 +1,237 @@
+//! On-disk index of hashes repeated across documents, for two-pass deduplication
+//!
+//! The index pass appends the distinct hashes of each document to shard files in the scratch
+//! directory; when the run succeeds, the hashes found in at least `min_documents` documents are
+//! written to `index_dir`, one sorted file per shard, with the settings of the operator
+//! (`index.json`, written last). A failed run discards the shards and publishes nothing. The
+//! remove pass loads the index and checks that its settings match.
+
+use fdf_sdk::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SHARDS: usize = 64; // Hashes are partitioned by their top bits
+const META_FILE: &str = "index.json";
+
+/// Index pass: hashes appended to shard files in the scratch directory
+pub struct ShardWriter {
+    dir: PathBuf,
+    shards: Vec<BufWriter<File>>,
+    pub documents: u64, // Documents added
+}
+
+impl ShardWriter {
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", dir.display(), e))?;
+        let shards = (0..SHARDS)
+            .map(|shard| {
+                let path = dir.join(format!("windows-{:02}.bin", shard));
+                File::create(&path)
+                    .map(BufWriter::new)
+                    .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", path.display(), e))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            shards,
+            documents: 0,
+        })
+    }
+
+    pub fn add_document(&mut self, hashes: HashSet<u64>) -> Result<()> {
+        for hash in hashes {
+            self.shards[shard(hash)].write_all(&hash.to_le_bytes())?;
+        }
+        self.documents += 1;
+        Ok(())
+    }
+
+    /// Write the hashes found in at least min_documents documents to `index_dir`, one sorted
+    /// file per shard, and the index settings
+    pub fn finish(self, index_dir: &Path, min_documents: u32, meta: &impl Serialize) -> Result<()> {
+        std::fs::create_dir_all(index_dir)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", index_dir.display(), e))?;
+        // The settings mark a complete index: removed first, so that an index interrupted
+        // while being replaced is refused by the remove pass
+        let meta_path = index_dir.join(META_FILE);
+        match std::fs::remove_file(&meta_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!(
+                    "Cannot remove {}: {}",
+                    meta_path.display(),
+                    e
+                ))
+            }
+            _ => {}
+        }
+        for (index, shard) in self.shards.into_iter().enumerate() {
+            shard
+                .into_inner()
+                .map_err(|e| anyhow::anyhow!("Cannot write hash shard: {}", e.error()))?;
+            let path = self.dir.join(format!("windows-{:02}.bin", index));
+            let mut hashes = read_hashes(&path)?;
+            hashes.sort_unstable();
+
+            let mut out = BufWriter::new(File::create(index_dir.join(repeated_file(index)))?);
+            for run in hashes.chunk_by(|a, b| a == b) {
+                if run.len() >= min_documents as usize {
+                    out.write_all(&run[0].to_le_bytes())?;
+                }
+            }
+            out.flush()?;
+            let _ = std::fs::remove_file(&path);
+        }
+        std::fs::write(&meta_path, serde_json::to_string_pretty(meta)?)
+            .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", meta_path.display(), e))
+    }
+
+    /// Delete the shard files without publishing anything, when the run failed
+    pub fn discard(self) {
+        drop(self.shards);
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// State of a two-pass deduplication operator
+pub enum PassState {
+    Index(Option<ShardWriter>), // Taken when the index is published or discarded
+    Remove(Arc<RepeatedHashes>),
+}
+
+impl PassState {
+    /// Publish the index of a successful index pass, with the settings given by `meta` for the
+    /// number of documents indexed
+    pub fn publish<M: Serialize>(
+        &mut self,
+        index_dir: &Path,
+        min_documents: u32,
+        meta: impl FnOnce(u64) -> M,
+    ) -> Result<()> {
+        match self {
+            PassState::Index(writer) => match writer.take() {
+                Some(writer) => {
+                    let meta = meta(writer.documents);
+                    writer.finish(index_dir, min_documents, &meta)
+                }
+                None => Ok(()),
+            },
+            PassState::Remove(_) => Ok(()),
+        }
+    }
+
+    /// Discard the shards of an index pass that was not published (failed run)
+    pub fn discard(&mut self) {
+        if let PassState::Index(writer) = self {
+            if let Some(writer) = writer.take() {
+                writer.discard();
+            }
+        }
+    }
+}
+
+/// Settings an index was built with
+pub fn read_meta<T: DeserializeOwned>(index_dir: &Path) -> Result<T> {
+    let meta_path = index_dir.join(META_FILE);
+    std::fs::read_to_string(&meta_path)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Cannot read {} (run the index pass first): {}",
+                meta_path.display(),
+                e
+            )
+        })
+        .and_then(|json| Ok(serde_json::from_str(&json)?))
+}
+
+/// Remove pass: hashes repeated across documents, sorted per shard
+pub struct RepeatedHashes {
+    shards: Vec<Vec<u64>>,
+}
+
+impl RepeatedHashes {
+    pub fn load(index_dir: &Path) -> Result<Self> {
+        let shards = (0..SHARDS)
+            .map(|index| read_hashes(&index_dir.join(repeated_file(index))))
+            .collect::<Result<_>>()?;
+        Ok(Self { shards })
+    }
+
+    pub fn contains(&self, hash: u64) -> bool {
+        self.shards[shard(hash)].binary_search(&hash).is_ok()
+    }
+}
+
+fn shard(hash: u64) -> usize {
+    (hash >> 58) as usize % SHARDS
+}
+
+fn repeated_file(shard: usize) -> String {
+    format!("repeated-{:02}.bin", shard)
+}
+
+fn read_hashes(path: &Path) -> Result<Vec<u64>> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .map(BufReader::new)
+        .and_then(|mut reader| reader.read_to_end(&mut bytes))
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("fdf-hash-index-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn keeps_hashes_in_enough_documents() {
+        let (scratch, index_dir) = (temp_dir("scratch"), temp_dir("index"));
+        let mut writer = ShardWriter::create(&scratch).unwrap();
+        // Hashes spread over shards by their top bits
+        let (a, b, c) = (1u64, u64::MAX - 7, 0x8000_0000_0000_0042);
+        writer.add_document(HashSet::from([a, b])).unwrap();
+        writer.add_document(HashSet::from([a, b, c])).unwrap();
+        writer.add_document(HashSet::from([a])).unwrap();
+        assert_eq!(writer.documents, 3);
+        writer
+            .finish(&index_dir, 2, &json!({"documents": 3}))
+            .unwrap();
+
+        let repeated = RepeatedHashes::load(&index_dir).unwrap();
+        assert!(repeated.contains(a) && repeated.contains(b));
+        assert!(!repeated.contains(c) && !repeated.contains(2));
+        let meta: serde_json::Value = read_meta(&index_dir).unwrap();
+        assert_eq!(meta["documents"], 3);
+        assert!(!scratch.join("windows-00.bin").exists());
+        let _ = std::fs::remove_dir_all(&index_dir);
+    }
+
+    #[test]
+    fn discard_publishes_nothing() {
+        let (scratch, index_dir) = (temp_dir("discard"), temp_dir("discard-index"));
+        let mut writer = ShardWriter::create(&scratch).unwrap();
+        writer.add_document(HashSet::from([1, 2])).unwrap();
+        writer.discard();
+
+        assert!(!scratch.exists());
+        assert!(!index_dir.exists());
+        let error = read_meta::<serde_json::Value>(&index_dir).unwrap_err();
+        assert!(error.to_string().contains("run the index pass first"));
+    }
+}
@@ -1,5 +1,12 @@
 pub mod annotator;
+#[cfg(feature = "text-ml")]
+pub mod fasttext;
 pub mod filter;
+pub mod hash_index;
+#[cfg(feature = "text-ml")]
+pub mod model;
+pub mod sentences;
+pub mod tokenizer;
 pub mod transformer;
 
 use fdf_sdk::OperatorRegistry;
@@ -0,0 +1,99 @@
+//! Transformer encoders exported to ONNX (HuggingFace optimum layout): a `model.onnx` taking
+//! `input_ids`, `attention_mask` and optionally `token_type_ids`, its `tokenizer.json` and
+//! `config.json` (label names in `id2label`)
+
+use crate::onnx::{Input, OnnxModel};
+use crate::text::tokenizer::shared_huggingface;
+use fdf_sdk::{Context, Result};
+use std::path::Path;
+use tokenizers::{Encoding, PaddingDirection, Tokenizer, TruncationParams};
+
+/// HuggingFace tokenizer cutting texts into windows of at most `max_length` tokens (special
+/// tokens included); the windows after the first are the overflowing encodings
+pub fn load_tokenizer(path: &str, max_length: usize, ctx: &Context) -> Result<Tokenizer> {
+    let mut tokenizer = (*shared_huggingface(path, ctx)?).clone();
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length,
+            ..TruncationParams::default()
+        }))
+        .map_err(|e| anyhow::anyhow!("Invalid max_length {}: {}", max_length, e))?;
+    Ok(tokenizer)
+}
+
+/// Encoding of a text in windows of the tokenizer's max_length
+pub fn encode_windows(tokenizer: &Tokenizer, text: &str) -> Result<Vec<Encoding>> {
+    let mut encoding = tokenizer
+        .encode(text, true)
+        .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+    let overflowing = encoding.take_overflowing();
+    Ok(std::iter::once(encoding).chain(overflowing).collect())
+}
+
+/// Pads encodings to the longest one with the tokenizer's padding token (id 0 if it has
+/// none), so that they can run as one batch
+pub fn pad_batch(tokenizer: &Tokenizer, encodings: &mut [Encoding]) {
+    let len = encodings.iter().map(Encoding::len).max().unwrap_or(0);
+    let (pad_id, pad_type_id, pad_token) =
+        tokenizer.get_padding().map_or((0, 0, "[PAD]"), |padding| {
+            (
+                padding.pad_id,
+                padding.pad_type_id,
+                padding.pad_token.as_str(),
+            )
+        });
+    for encoding in encodings {
+        encoding.pad(len, pad_id, pad_type_id, pad_token, PaddingDirection::Right);
+    }
+}
+
+/// Model inputs of a batch of encodings of the same length
+pub fn encoder_inputs<'a>(model: &'a OnnxModel, batch: &[&Encoding]) -> Vec<(&'a str, Input<'a>)> {
+    let len = batch.first().map_or(0, |encoding| encoding.len());
+    let shape = vec![batch.len(), len];
+    model
+        .inputs()
+        .iter()
+        .filter_map(|name| {
+            let ids: fn(&Encoding) -> &[u32] = match name.as_str() {
+                "input_ids" => Encoding::get_ids,
+                "attention_mask" => Encoding::get_attention_mask,
+                "token_type_ids" => Encoding::get_type_ids,
+                _ => return None,
+            };
+            let values = batch
+                .iter()
+                .flat_map(|encoding| ids(encoding).iter().map(|&id| id as i64))
+                .collect();
+            Some((name.as_str(), Input::I64(shape.clone(), values)))
+        })
+        .collect()
+}
+
+/// Label names of the model: `id2label` of the config.json next to it
+pub fn model_labels(model_path: &str) -> Result<Vec<String>> {
+    let config_path = Path::new(model_path).with_file_name("config.json");
+    let content = std::fs::read_to_string(&config_path).map_err(|e| {
+        anyhow::anyhow!(
+            "Cannot read {} for the labels (or set labels): {}",
+            config_path.display(),
+            e
+        )
+    })?;
+    let config: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", config_path.display(), e))?;
+    let id2label = config
+        .get("id2label")
+        .and_then(serde_json::Value::as_object)
+        .ok_or_else(|| anyhow::anyhow!("No id2label in {}", config_path.display()))?;
+    let mut labels = vec![String::new(); id2label.len()];
+    for (id, label) in id2label {
+        let id: usize = id
+            .parse()
+            .ok()
+            .filter(|&id| id < labels.len())
+            .ok_or_else(|| anyhow::anyhow!("Invalid label id {} in id2label", id))?;
+        labels[id] = label.as_str().unwrap_or_default().to_string();
+    }
+    Ok(labels)
+}
@@ -0,0 +1,161 @@
+//! Rule-based sentence splitting shared by sentence-level operators (split, dedup, chunking)
+//!
+//! A sentence ends at '.', '!', '?', '…' (and the Arabic, Urdu and Devanagari terminators)
+//! followed by whitespace, or at a CJK full stop, exclamation or question mark; closing quotes
+//! and brackets stay with the sentence. A '.' does not end a sentence after an abbreviation of
+//! the language ("Dr.", "z.B."), a single letter ("J. Smith") or before a lowercase word. Blank
+//! lines always end a sentence.
+
+/// Closing quotes and brackets kept at the end of the sentence they follow
+const CLOSING: &[char] = &[
+    '"', '\'', '”', '’', '»', '›', ')', ']', '}', '」', '』', '）', '】', '〉', '》',
+];
+
+/// Abbreviations (lowercase, without the final '.') that do not end a sentence; abbreviations
+/// with inner dots ("e.g.", "z.B.") are recognized without a list
+fn abbreviations(language: &str) -> &'static [&'static str] {
+    let language = language.split(['-', '_']).next().unwrap_or("");
+    match language {
+        "en" => &[
+            "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "inc", "ltd", "co",
+            "corp", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov",
+            "dec", "no", "nos", "fig", "figs", "al", "approx", "dept", "est", "gen", "gov", "lt",
+            "col", "capt", "sgt", "rev", "vol", "pp", "ed", "eds", "mt", "ft", "ave", "blvd",
+        ],
+        "de" => &[
+            "bzw", "ca", "usw", "vgl", "nr", "str", "dr", "prof", "hr", "fr", "evtl", "ggf",
+            "inkl", "bspw", "jh", "abs", "bd", "dipl", "ing", "geb", "gest", "max", "min", "mio",
+            "mrd", "tel", "zzgl", "jan", "feb", "aug", "sept", "okt", "nov", "dez",
+        ],
+        "fr" => &[
+            "m", "mm", "mme", "mmes", "mlle", "dr", "pr", "st", "ste", "etc", "cf", "env", "no",
+            "av", "bd", "janv", "févr", "avr", "juil", "sept", "oct", "nov", "déc",
+        ],
+        "es" => &[
+            "sr", "sra", "srta", "dr", "dra", "ud", "uds", "etc", "pág", "núm", "av", "avda",
+            "prof", "ej", "aprox", "dpto", "ene", "feb", "abr", "ago", "sept", "oct", "dic",
+        ],
+        "it" => &[
+            "sig", "sigg", "sigra", "dott", "dottssa", "prof", "ecc", "pag", "avv", "ing", "geom",
+            "gen", "feb", "mar", "apr", "giu", "lug", "ago", "sett", "ott", "nov", "dic",
+        ],
+        "pt" => &[
+            "sr", "sra", "srta", "dr", "dra", "prof", "etc", "pág", "av", "exmo", "exma", "jan",
+            "fev", "mar", "abr", "jun", "jul", "ago", "set", "out", "nov", "dez",
+        ],
+        "nl" => &[
+            "dhr", "mevr", "mr", "dr", "prof", "bijv", "ca", "enz", "nr", "blz", "jl", "ir", "ing",
+            "drs", "jan", "feb", "mrt", "apr", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+        ],
+        "ru" => &[
+            "г", "гг", "др", "им", "ул", "стр", "см", "тыс", "млн", "млрд", "руб", "коп", "проф",
+            "акад", "доц", "напр", "т", "д", "пр", "рис", "табл", "гл",
+        ],
+        _ => &[],
+    }
+}
+
+/// Sentences of `text`, trimmed, in order; `language` is an ISO 639-1 code ("en", "de") choosing
+/// the abbreviation list, and `split_lines` also ends a sentence at every line break
+pub fn split_sentences<'a>(text: &'a str, language: &str, split_lines: bool) -> Vec<&'a str> {
+    let abbreviations = abbreviations(language);
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let push = |sentences: &mut Vec<&'a str>, start: usize, end: usize| {
+        let sentence = text[start..end].trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        match c {
+            '\n' => {
+                let blank_line = chars[i + 1..]
+                    .iter()
+                    .map(|&(_, c)| c)
+                    .take_while(|c| c.is_whitespace())
+                    .any(|c| c == '\n');
+                if split_lines || blank_line {
+                    push(&mut sentences, start, offset);
+                    start = offset;
+                }
+                i += 1;
+            }
+            '。' | '！' | '？' | '｡' => {
+                let end = skip(&chars, i + 1, |c| matches!(c, '。' | '！' | '？' | '｡'));
+                let end = skip(&chars, end, |c| CLOSING.contains(&c));
+                let end_offset = chars.get(end).map_or(text.len(), |&(offset, _)| offset);
+                push(&mut sentences, start, end_offset);
+                start = end_offset;
+                i = end;
+            }
+            '.' | '!' | '?' | '…' | '؟' | '۔' | '।' | '॥' => {
+                let terminators = skip(&chars, i + 1, |c| {
+                    matches!(c, '.' | '!' | '?' | '…' | '؟' | '۔' | '।' | '॥')
+                });
+                let end = skip(&chars, terminators, |c| CLOSING.contains(&c));
+                let at_break = chars.get(end).is_none_or(|&(_, c)| c.is_whitespace());
+                if at_break && !continues(&chars, i, terminators, end, abbreviations) {
+                    let end_offset = chars.get(end).map_or(text.len(), |&(offset, _)| offset);
+                    push(&mut sentences, start, end_offset);
+                    start = end_offset;
+                }
+                i = end.max(i + 1);
+            }
+            _ => i += 1,
+        }
+    }
+    push(&mut sentences, start, text.len());
+    sentences
+}
+
+/// Index of the first char at or after `from` not matching `predicate`
+fn skip(chars: &[(usize, char)], from: usize, predicate: impl Fn(char) -> bool) -> usize {
+    from + chars[from.min(chars.len())..]
+        .iter()
+        .take_while(|&&(_, c)| predicate(c))
+        .count()
+}
+
+/// Whether the terminators at `chars[first..last]` (followed by closing chars up to `end`)
+/// continue the sentence: an abbreviation, an initial, or a lowercase next word after '.' or '…'
+fn continues(
+    chars: &[(usize, char)],
+    first: usize,
+    last: usize,
+    end: usize,
+    abbreviations: &[&str],
+) -> bool {
+    let terminators: String = chars[first..last].iter().map(|&(_, c)| c).collect();
+    if terminators != "." && terminators != "..." && terminators != "…" {
+        return false;
+    }
+    let next = chars[end..]
+        .iter()
+        .map(|&(_, c)| c)
+        .find(|c| !c.is_whitespace());
+    if next.is_some_and(char::is_lowercase) {
+        return true;
+    }
+    if terminators != "." || end != last {
+        return false;
+    }
+
+    // Word before the '.', without opening punctuation
+    let word: String = chars[..first]
+        .iter()
+        .rev()
+        .map(|&(_, c)| c)
+        .take_while(|c| !c.is_whitespace() && !matches!(c, '(' | '[' | '"' | '“' | '«' | '\''))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+    let dotted = word.contains('.') && word.split('.').all(|part| part.chars().count() <= 3);
+    initial || dotted || abbreviations.contains(&word.to_lowercase().as_str())
+}
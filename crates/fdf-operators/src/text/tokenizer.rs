@@ -0,0 +1,136 @@
+//! Tokenizers for token-based text operators (budgets, truncation, counts)
+//!
+//! Operators take the tokenizer name in their config (`tokenizer`): "whitespace" (words
+//! separated by whitespace), "chars" (Unicode characters), a tiktoken encoding ("cl100k_base",
+//! "o200k_base", "p50k_base", "r50k_base") or the path of a HuggingFace `tokenizer.json`. The
+//! last two need the text-ml feature.
+
+use fdf_sdk::{Context, Result};
+#[cfg(any(feature = "text-ml", feature = "audio"))]
+use std::sync::Arc;
+
+const TIKTOKEN_ENCODINGS: [&str; 4] = ["cl100k_base", "o200k_base", "p50k_base", "r50k_base"];
+
+#[derive(Clone)]
+pub enum Tokenizer {
+    Whitespace,
+    Chars,
+    #[cfg(feature = "text-ml")]
+    Tiktoken(&'static tiktoken_rs::CoreBPE),
+    #[cfg(feature = "text-ml")]
+    HuggingFace(Arc<tokenizers::Tokenizer>),
+}
+
+impl Tokenizer {
+    /// Check a tokenizer name when the config is read; files are only opened by `load`
+    pub fn check(name: &str) -> Result<()> {
+        let model_based = TIKTOKEN_ENCODINGS.contains(&name) || name.ends_with(".json");
+        match name {
+            "whitespace" | "chars" => Ok(()),
+            _ if model_based && !cfg!(feature = "text-ml") => Err(anyhow::anyhow!(
+                "Tokenizer '{}' needs fdf built with the text-ml feature",
+                name
+            )),
+            _ if model_based => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "Unknown tokenizer '{}': expected whitespace, chars, {} or the path of a \
+                 tokenizer.json",
+                name,
+                TIKTOKEN_ENCODINGS.join(", ")
+            )),
+        }
+    }
+
+    /// Tokenizer by name; tokenizer files are loaded once per run (context resource cache)
+    #[cfg_attr(not(feature = "text-ml"), allow(unused_variables))]
+    pub fn load(name: &str, ctx: &Context) -> Result<Self> {
+        Self::check(name)?;
+        match name {
+            "whitespace" => Ok(Self::Whitespace),
+            "chars" => Ok(Self::Chars),
+            #[cfg(feature = "text-ml")]
+            "cl100k_base" => Ok(Self::Tiktoken(tiktoken_rs::cl100k_base_singleton())),
+            #[cfg(feature = "text-ml")]
+            "o200k_base" => Ok(Self::Tiktoken(tiktoken_rs::o200k_base_singleton())),
+            #[cfg(feature = "text-ml")]
+            "p50k_base" => Ok(Self::Tiktoken(tiktoken_rs::p50k_base_singleton())),
+            #[cfg(feature = "text-ml")]
+            "r50k_base" => Ok(Self::Tiktoken(tiktoken_rs::r50k_base_singleton())),
+            #[cfg(feature = "text-ml")]
+            path => Ok(Self::HuggingFace(shared_huggingface(path, ctx)?)),
+            #[cfg(not(feature = "text-ml"))]
+            _ => unreachable!("rejected by check"),
+        }
+    }
+
+    /// Number of tokens in `text`
+    pub fn count(&self, text: &str) -> Result<usize> {
+        match self {
+            Self::Whitespace => Ok(text.split_whitespace().count()),
+            Self::Chars => Ok(text.chars().count()),
+            #[cfg(feature = "text-ml")]
+            Self::Tiktoken(bpe) => Ok(bpe.encode_ordinary(text).len()),
+            #[cfg(feature = "text-ml")]
+            Self::HuggingFace(_) => Ok(self.token_ends(text)?.len()),
+        }
+    }
+
+    /// Byte offset in `text` where each token ends (always a char boundary), in order
+    pub fn token_ends(&self, text: &str) -> Result<Vec<usize>> {
+        match self {
+            Self::Whitespace => Ok(text
+                .split_whitespace()
+                .map(|word| word.as_ptr() as usize - text.as_ptr() as usize + word.len())
+                .collect()),
+            Self::Chars => Ok(text.char_indices().map(|(i, c)| i + c.len_utf8()).collect()),
+            #[cfg(feature = "text-ml")]
+            Self::Tiktoken(bpe) => {
+                let mut end = 0;
+                let ends = bpe
+                    ._decode_native_and_split(bpe.encode_ordinary(text))
+                    .map(|bytes| {
+                        end += bytes.len();
+                        floor_char_boundary(text, end)
+                    })
+                    .collect();
+                Ok(ends)
+            }
+            #[cfg(feature = "text-ml")]
+            Self::HuggingFace(tokenizer) => {
+                let encoding = tokenizer
+                    .encode(text, false)
+                    .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+                let mut end = 0;
+                Ok(encoding
+                    .get_offsets()
+                    .iter()
+                    .map(|&(_, token_end)| {
+                        end = end.max(floor_char_boundary(text, token_end));
+                        end
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+/// Largest char boundary of `text` at or before `index`
+#[cfg(feature = "text-ml")]
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// HuggingFace `tokenizer.json` at `path`, loaded once per run and shared through the
+/// context's resource cache
+#[cfg(any(feature = "text-ml", feature = "audio"))]
+pub fn shared_huggingface(path: &str, ctx: &Context) -> Result<Arc<tokenizers::Tokenizer>> {
+    ctx.resources()
+        .get_or_load(&format!("tokenizer:{}", path), || {
+            tokenizers::Tokenizer::from_file(path)
+                .map_err(|e| anyhow::anyhow!("Cannot load tokenizer {}: {}", path, e))
+        })
+}
@@ -0,0 +1,281 @@
+use crate::onnx::OnnxModel;
+use crate::text::model::{encode_windows, encoder_inputs, load_tokenizer, model_labels};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::{Encoding, Tokenizer};
+
+/// Masks named entities (person names, organizations, locations) found by an ONNX token
+/// classification model (e.g. dslim/bert-base-NER exported with optimum) with typed
+/// placeholders, for datasets derived from support tickets or emails
+#[fdf_operator(
+    name = "text.anonymize_entities",
+    category = "transformer",
+    build = "AnonymizeEntitiesConfig::build"
+)]
+struct AnonymizeEntitiesConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Path of the ONNX token classification model
+    model_path: String,
+    /// Path of the model's tokenizer.json; tokenizer.json next to the model if unset
+    tokenizer_path: Option<String>,
+    /// Label of each model output (e.g. ["O", "B-PER", "I-PER", ...]); id2label of the
+    /// config.json next to the model if unset
+    labels: Option<Vec<String>>,
+    /// Placeholder of each entity type masked (label without its B-/I- prefix); other types
+    /// are kept
+    #[param(default = "{PER: '[PERSON]', ORG: '[ORGANIZATION]', LOC: '[LOCATION]'}")]
+    placeholders: BTreeMap<String, String>,
+    /// Minimum score (mean probability of the entity's words) of a masked entity
+    #[param(default = 0.5)]
+    min_score: f64,
+    /// Tokens per model window, special tokens included; longer texts take several windows
+    #[param(default = 512)]
+    max_length: usize,
+    /// Field receiving the number of masked entities per type, if set
+    counts_col: Option<String>,
+}
+
+impl AnonymizeEntitiesConfig {
+    fn build(self) -> Result<AnonymizeEntities> {
+        if self.placeholders.is_empty() {
+            return Err(anyhow::anyhow!("{}: placeholders is empty", Self::NAME));
+        }
+        if !(0.0..=1.0).contains(&self.min_score) {
+            return Err(anyhow::anyhow!(
+                "{}: min_score must be between 0 and 1",
+                Self::NAME
+            ));
+        }
+        if self.max_length < 3 {
+            return Err(anyhow::anyhow!(
+                "{}: max_length must be at least 3",
+                Self::NAME
+            ));
+        }
+        let tokenizer_path = self.tokenizer_path.unwrap_or_else(|| {
+            let path = Path::new(&self.model_path).with_file_name("tokenizer.json");
+            path.to_string_lossy().into_owned()
+        });
+        Ok(AnonymizeEntities {
+            text_col: self.text_col,
+            model_path: self.model_path,
+            tokenizer_path,
+            labels: self.labels.unwrap_or_default(),
+            placeholders: self.placeholders,
+            min_score: self.min_score,
+            max_length: self.max_length,
+            counts_col: self.counts_col,
+            model: None,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct AnonymizeEntities {
+    text_col: String,
+    model_path: String,
+    tokenizer_path: String,
+    labels: Vec<String>, // Read from config.json in open if not configured
+    placeholders: BTreeMap<String, String>,
+    min_score: f64,
+    max_length: usize,
+    counts_col: Option<String>,
+    model: Option<Arc<OnnxModel>>, // Loaded in open
+    tokenizer: Option<Tokenizer>,  // Loaded in open
+}
+
+/// Entity found in a text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub kind: String, // Label without its B-/I- prefix
+    pub start: usize, // Byte range in the text
+    pub end: usize,
+    pub score: f64, // Mean probability of its words
+}
+
+/// Entity type of a label ("B-PER" and "I-PER" are "PER"), None for "O"
+fn entity_kind(label: &str) -> Option<(&str, bool)> {
+    if label == "O" || label.is_empty() {
+        return None;
+    }
+    match label.split_once('-') {
+        Some((prefix, kind)) if matches!(prefix, "B" | "I" | "E" | "S" | "L" | "U") => {
+            Some((kind, matches!(prefix, "B" | "S" | "U"))) // (type, begins an entity)
+        }
+        _ => Some((label, false)),
+    }
+}
+
+impl AnonymizeEntities {
+    /// Entities of one model window, each word labelled by its first token
+    fn window_entities(&self, encoding: &Encoding, logits: &[f32]) -> Vec<Entity> {
+        let num_labels = self.labels.len();
+        let mut entities: Vec<(Entity, usize)> = Vec::new(); // With their number of words
+        let mut current_word = None;
+        let mut in_entity = false; // Whether the current word is in the last entity
+        let tokens = encoding
+            .get_word_ids()
+            .iter()
+            .zip(encoding.get_offsets())
+            .zip(encoding.get_special_tokens_mask())
+            .enumerate();
+        for (i, ((word, &(start, end)), &special)) in tokens {
+            let Some(word) = *word else { continue };
+            if special == 1 || start == end {
+                continue;
+            }
+            if current_word == Some(word) {
+                // Later token of the word: extends its entity
+                if let (true, Some((entity, _))) = (in_entity, entities.last_mut()) {
+                    entity.end = end;
+                }
+                continue;
+            }
+            current_word = Some(word);
+            let previous_in_entity = std::mem::replace(&mut in_entity, false);
+
+            let (label, score) = softmax_max(&logits[i * num_labels..(i + 1) * num_labels]);
+            let Some((kind, begins)) = entity_kind(&self.labels[label]) else {
+                continue;
+            };
+            in_entity = true;
+            match entities.last_mut() {
+                Some((entity, words)) if !begins && previous_in_entity && entity.kind == kind => {
+                    entity.end = end;
+                    entity.score += score;
+                    *words += 1;
+                }
+                _ => {
+                    let entity = Entity {
+                        kind: kind.to_string(),
+                        start,
+                        end,
+                        score,
+                    };
+                    entities.push((entity, 1));
+                }
+            }
+        }
+        entities
+            .into_iter()
+            .map(|(mut entity, words)| {
+                entity.score /= words as f64;
+                entity
+            })
+            .collect()
+    }
+
+    /// Entities of a text masked by this operator, in order
+    pub fn entities(&self, text: &str) -> Result<Vec<Entity>> {
+        let model = self.model.as_ref().expect("model is loaded in open");
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let mut entities = Vec::new();
+        for encoding in encode_windows(tokenizer, text)? {
+            let outputs = model.run(encoder_inputs(model, &[&encoding]))?;
+            let logits = outputs
+                .first()
+                .filter(|logits| logits.values.len() == encoding.len() * self.labels.len())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Model output does not match {} tokens x {} labels",
+                        encoding.len(),
+                        self.labels.len()
+                    )
+                })?;
+            entities.extend(
+                self.window_entities(&encoding, &logits.values)
+                    .into_iter()
+                    .filter(|entity| {
+                        entity.score >= self.min_score
+                            && self.placeholders.contains_key(&entity.kind)
+                    }),
+            );
+        }
+        entities.sort_by_key(|entity| entity.start);
+        Ok(entities)
+    }
+}
+
+/// Index and probability of the largest logit
+fn softmax_max(logits: &[f32]) -> (usize, f64) {
+    let (best, max) =
+        logits
+            .iter()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |(best, max), (i, &logit)| {
+                if logit > max {
+                    (i, logit)
+                } else {
+                    (best, max)
+                }
+            });
+    let sum: f64 = logits.iter().map(|&l| ((l - max) as f64).exp()).sum();
+    (best, 1.0 / sum)
+}
+
+impl Operator for AnonymizeEntities {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let name = AnonymizeEntitiesConfig::NAME;
+        let fatal = |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", name, e));
+        if self.labels.is_empty() {
+            self.labels = model_labels(&self.model_path).map_err(fatal)?;
+        }
+        self.model = Some(OnnxModel::load(&self.model_path, ctx).map_err(fatal)?);
+        self.tokenizer =
+            Some(load_tokenizer(&self.tokenizer_path, self.max_length, ctx).map_err(fatal)?);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let entities = self.entities(text)?;
+
+        let mut masked = String::with_capacity(text.len());
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut last_end = 0;
+        for entity in &entities {
+            if entity.start < last_end {
+                continue; // Overlaps a masked entity
+            }
+            masked.push_str(&text[last_end..entity.start]);
+            masked.push_str(&self.placeholders[&entity.kind]);
+            last_end = entity.end;
+            *counts.entry(&entity.kind).or_default() += 1;
+        }
+        masked.push_str(&text[last_end..]);
+
+        for (kind, count) in &counts {
+            ctx.metrics()
+                .increment(&format!("masked.{}", kind), *count as u64);
+        }
+        if let Some(counts_col) = &self.counts_col {
+            let counts = counts
+                .iter()
+                .map(|(kind, count)| (kind.to_string(), Value::from(*count)))
+                .collect();
+            sample.set_path(counts_col, Value::Object(counts))?;
+        }
+        sample.set_path(&self.text_col, Value::from(masked))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(&self.text_col);
+        Some(match &self.counts_col {
+            Some(counts_col) => columns.produces(counts_col),
+            None => columns,
+        })
+    }
+}
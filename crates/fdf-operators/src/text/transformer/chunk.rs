@@ -0,0 +1,259 @@
+use crate::text::sentences::split_sentences;
+use crate::text::tokenizer::Tokenizer;
+use crate::text::transformer::truncate_tokens::Boundary;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+
+/// Splits long documents into chunks of at most max_tokens tokens, overlapping by a number of
+/// tokens, emitting one sample per chunk with its parent id and index
+#[fdf_operator(
+    name = "text.chunk",
+    category = "transformer",
+    build = "ChunkConfig::build"
+)]
+struct ChunkConfig {
+    /// Field holding the text, replaced by each chunk (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum number of tokens of a chunk
+    max_tokens: usize,
+    /// Tokens repeated from the end of a chunk at the start of the next one (less than
+    /// max_tokens)
+    #[param(default = 0)]
+    overlap: usize,
+    /// Tokenizer: whitespace, chars, a tiktoken encoding (cl100k_base, o200k_base, p50k_base,
+    /// r50k_base) or the path of a HuggingFace tokenizer.json
+    #[param(default = "whitespace")]
+    tokenizer: String,
+    /// Where chunks end: "token", "sentence" or "paragraph"; falls back to the token boundary
+    /// when no sentence or paragraph end fits
+    #[param(default = "token", type = "string")]
+    boundary: Boundary,
+    /// Language of the sentence splitting rules (ISO 639-1), for the sentence boundary
+    #[param(default = "en")]
+    language: String,
+    /// Field holding the document id, copied to parent_col; chunks get "<id>_<index>" ids
+    #[param(default = "id")]
+    id_col: String,
+    /// Field receiving the id of the chunked document (null if it has none)
+    #[param(default = "parent_id")]
+    parent_col: String,
+    /// Field receiving the index of the chunk in its document, from 0
+    #[param(default = "chunk_index")]
+    index_col: String,
+}
+
+impl ChunkConfig {
+    fn build(self) -> Result<Chunk> {
+        if self.max_tokens == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_tokens must be positive",
+                Self::NAME
+            ));
+        }
+        if self.overlap >= self.max_tokens {
+            return Err(anyhow::anyhow!(
+                "{}: overlap must be less than max_tokens",
+                Self::NAME
+            ));
+        }
+        Tokenizer::check(&self.tokenizer)
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", Self::NAME, e))?;
+        Ok(Chunk {
+            text_col: self.text_col,
+            max_tokens: self.max_tokens,
+            overlap: self.overlap,
+            tokenizer_name: self.tokenizer,
+            boundary: self.boundary,
+            language: self.language,
+            id_col: self.id_col,
+            parent_col: self.parent_col,
+            index_col: self.index_col,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct Chunk {
+    text_col: String,
+    max_tokens: usize,
+    overlap: usize,
+    tokenizer_name: String,
+    boundary: Boundary,
+    language: String,
+    id_col: String,
+    parent_col: String,
+    index_col: String,
+    tokenizer: Option<Tokenizer>, // Loaded in open
+}
+
+impl Chunk {
+    /// Chunks of a text, trimmed of surrounding whitespace
+    pub fn chunks<'a>(&self, text: &'a str) -> Result<Vec<&'a str>> {
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let ends = tokenizer.token_ends(text)?;
+        // Byte offsets where sentences end (text.sentence_split rules)
+        let sentence_ends: Vec<usize> = match self.boundary {
+            Boundary::Sentence => split_sentences(text, &self.language, false)
+                .iter()
+                .map(|sentence| {
+                    sentence.as_ptr() as usize - text.as_ptr() as usize + sentence.len()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut chunks = Vec::new();
+        let mut first = 0; // Index of the first token of the chunk
+        while first < ends.len() {
+            let start = if first == 0 { 0 } else { ends[first - 1] };
+            let last = (first + self.max_tokens).min(ends.len());
+            let mut end = ends[last - 1];
+            let mut next = last;
+            if last < ends.len() {
+                let boundary = match self.boundary {
+                    Boundary::Token => None,
+                    Boundary::Sentence => {
+                        let fitting =
+                            sentence_ends.partition_point(|&sentence_end| sentence_end <= end);
+                        fitting.checked_sub(1).map(|last| sentence_ends[last])
+                    }
+                    Boundary::Paragraph => text[start..end].rfind("\n\n").map(|i| start + i),
+                };
+                // Tokens ending within the boundary; the chunk must advance past the overlap
+                if let Some(boundary) = boundary.filter(|&boundary| boundary > start) {
+                    let covered = ends.partition_point(|&token_end| token_end <= boundary);
+                    if covered > first + self.overlap {
+                        end = boundary;
+                        next = covered;
+                    }
+                }
+            }
+            let chunk = text[start..end].trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk);
+            }
+            if next == ends.len() {
+                break;
+            }
+            first = next - self.overlap;
+        }
+        Ok(chunks)
+    }
+}
+
+impl Operator for Chunk {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let tokenizer = Tokenizer::load(&self.tokenizer_name, ctx)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", ChunkConfig::NAME, e)))?;
+        self.tokenizer = Some(tokenizer);
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let mut chunks = self.flat_map(sample, &Context::default())?;
+        if chunks.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "Document split into {} chunks; run it through the engine",
+                chunks.len()
+            ));
+        }
+        Ok(chunks.pop())
+    }
+
+    fn flat_map(&self, sample: Sample, ctx: &Context) -> Result<Vec<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let chunks = self.chunks(text)?;
+        let parent = sample
+            .get_path(&self.id_col)
+            .filter(|id| !id.is_null())
+            .cloned();
+
+        let mut samples = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut sample = sample.clone();
+            sample.set_path(&self.text_col, Value::from(*chunk))?;
+            if let Some(parent) = &parent {
+                let id = match parent {
+                    Value::String(id) => format!("{}_{}", id, index),
+                    id => format!("{}_{}", id, index), // Numeric ids
+                };
+                sample.set_path(&self.id_col, Value::String(id))?;
+            }
+            sample.set_path(&self.parent_col, parent.clone().unwrap_or(Value::Null))?;
+            sample.set_path(&self.index_col, Value::from(index))?;
+            samples.push(sample);
+        }
+        if samples.len() > 1 {
+            ctx.metrics().increment("chunked_documents", 1);
+        }
+        ctx.metrics().increment("chunks", samples.len() as u64);
+        Ok(samples)
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.parent_col)
+                .produces(&self.index_col),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fdf_sdk::testing::{build_operator, run_operator, sample};
+    use serde_json::json;
+
+    fn chunk_texts(config: &str, text: &str) -> Vec<String> {
+        let mut op = build_operator(super::register, "text.chunk", config).unwrap();
+        let outputs =
+            run_operator(op.as_mut(), [sample(json!({"id": "d", "text": text}))]).unwrap();
+        outputs
+            .iter()
+            .map(|s| s.as_value()["text"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn sentence_boundary_skips_abbreviations() {
+        let text = "We met Dr. Smith at noon. He was late again today.";
+        let chunks = chunk_texts("max_tokens: 8\nboundary: sentence", text);
+        assert_eq!(
+            chunks,
+            ["We met Dr. Smith at noon.", "He was late again today."]
+        );
+    }
+
+    #[test]
+    fn token_boundary_with_overlap() {
+        let chunks = chunk_texts("max_tokens: 3\noverlap: 1", "a b c d e f g");
+        assert_eq!(chunks, ["a b c", "c d e", "e f g"]);
+    }
+
+    #[test]
+    fn chunks_get_parent_ids() {
+        let mut op = build_operator(super::register, "text.chunk", "max_tokens: 2").unwrap();
+        let outputs =
+            run_operator(op.as_mut(), [sample(json!({"id": 7, "text": "a b c"}))]).unwrap();
+        let ids: Vec<(&serde_json::Value, &serde_json::Value, &serde_json::Value)> = outputs
+            .iter()
+            .map(|s| {
+                let value = s.as_value();
+                (&value["id"], &value["parent_id"], &value["chunk_index"])
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                (&json!("7_0"), &json!(7), &json!(0)),
+                (&json!("7_1"), &json!(7), &json!(1))
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use encoding_rs::Encoding;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Decodes a binary field of text in an unknown legacy charset (Latin-1, Windows-125x,
+/// Shift_JIS, EUC-KR, GBK, ...) to UTF-8: the encoding is taken from a byte order mark, else
+/// detected from the bytes (chardetng, as in Firefox), and written next to the text
+#[fdf_operator(
+    name = "text.decode_charset",
+    category = "transformer",
+    build = "DecodeCharsetConfig::build"
+)]
+struct DecodeCharsetConfig {
+    /// Binary field holding the raw bytes; a string field is taken as already decoded UTF-8
+    bytes_col: String,
+    /// Field to write the decoded text to (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field to write the encoding name to (e.g. "Shift_JIS", "windows-1252")
+    #[param(default = "encoding")]
+    encoding_col: String,
+    /// Encoding label used instead of detection (WHATWG labels: "latin1", "sjis", "gbk", ...);
+    /// a byte order mark still takes precedence
+    encoding: Option<String>,
+    /// Top-level domain of the documents' origin (e.g. "jp", "ru"), a hint for detection
+    tld: Option<String>,
+    /// Keep the binary field after decoding
+    #[param(default = false)]
+    keep_bytes: bool,
+}
+
+impl DecodeCharsetConfig {
+    fn build(self) -> Result<DecodeCharset> {
+        let encoding = match &self.encoding {
+            Some(label) => Some(
+                Encoding::for_label(label.trim().as_bytes())
+                    .ok_or_else(|| anyhow::anyhow!("{}: unknown encoding {}", Self::NAME, label))?,
+            ),
+            None => None,
+        };
+        Ok(DecodeCharset {
+            bytes_col: self.bytes_col,
+            text_col: self.text_col,
+            encoding_col: self.encoding_col,
+            encoding,
+            tld: self
+                .tld
+                .map(|tld| tld.trim_start_matches('.').to_lowercase()),
+            keep_bytes: self.keep_bytes,
+        })
+    }
+}
+
+pub struct DecodeCharset {
+    bytes_col: String,
+    text_col: String,
+    encoding_col: String,
+    encoding: Option<&'static Encoding>,
+    tld: Option<String>,
+    keep_bytes: bool,
+}
+
+impl DecodeCharset {
+    /// Encoding of the bytes: byte order mark, configured encoding or detected one, with
+    /// whether detection was confident (always for the first two)
+    pub fn encoding_of(&self, bytes: &[u8]) -> (&'static Encoding, bool) {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return (encoding, true);
+        }
+        if let Some(encoding) = self.encoding {
+            return (encoding, true);
+        }
+        let mut detector = chardetng::EncodingDetector::new();
+        detector.feed(bytes, true);
+        detector.guess_assess(self.tld.as_deref().map(str::as_bytes), true)
+    }
+}
+
+impl Operator for DecodeCharset {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let (text, encoding) = match sample.get_bytes(&self.bytes_col) {
+            Some(bytes) => {
+                let (encoding, confident) = self.encoding_of(bytes);
+                // Strips the byte order mark; malformed sequences become U+FFFD
+                let (text, encoding, malformed) = encoding.decode(bytes);
+                if !confident {
+                    ctx.metrics().increment("uncertain", 1);
+                }
+                if malformed {
+                    ctx.metrics().increment("malformed", 1);
+                }
+                (text.into_owned(), encoding.name())
+            }
+            None => match sample.get_path(&self.bytes_col) {
+                Some(Value::String(text)) => (text.clone(), encoding_rs::UTF_8.name()),
+                _ => return Err(anyhow::anyhow!("Missing bytes field: {}", self.bytes_col)),
+            },
+        };
+        ctx.metrics()
+            .increment(&format!("encoding.{}", encoding), 1);
+        if !self.keep_bytes && self.bytes_col != self.text_col {
+            sample.remove_bytes(&self.bytes_col);
+        }
+        sample.set_path(&self.text_col, Value::from(text))?;
+        sample.set_path(&self.encoding_col, Value::from(encoding))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.bytes_col)
+                .produces(&self.text_col)
+                .produces(&self.encoding_col),
+        )
+    }
+}
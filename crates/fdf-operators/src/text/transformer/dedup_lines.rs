@@ -0,0 +1,119 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Line,      // Lines (blank lines are never removed)
+    Paragraph, // Blocks separated by blank lines
+}
+
+/// Removes lines or paragraphs repeated within a document (navigation, footers, boilerplate
+/// blocks repeated across a scraped page)
+#[fdf_operator(name = "text.dedup_lines", category = "transformer")]
+pub struct DedupLines {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Unit compared: "line" or "paragraph"; units are compared with surrounding whitespace
+    /// trimmed
+    #[param(default = "line", type = "string")]
+    unit: Unit,
+    /// Keep the first occurrence of a repeated unit; otherwise every occurrence is removed
+    #[param(default = true)]
+    keep_first: bool,
+    /// Only units occurring at least this many times (2 or more) in the document are
+    /// deduplicated
+    #[param(default = 2)]
+    min_count: usize,
+}
+
+impl DedupLines {
+    /// Deduplicated text and the number of units removed
+    pub fn dedup(&self, text: &str) -> (String, usize) {
+        let units: Vec<&str> = match self.unit {
+            Unit::Line => text.split('\n').collect(),
+            Unit::Paragraph => split_paragraphs(text),
+        };
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for unit in &units {
+            let key = unit.trim();
+            if !key.is_empty() {
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+        let min_count = self.min_count.max(2);
+        if counts.values().all(|&count| count < min_count) {
+            return (text.to_string(), 0);
+        }
+
+        let mut seen = HashSet::new();
+        let mut kept: Vec<&str> = Vec::with_capacity(units.len());
+        let mut removed = 0;
+        for unit in units {
+            let key = unit.trim();
+            if !key.is_empty() && counts[key] >= min_count {
+                let first = seen.insert(key);
+                if !(first && self.keep_first) {
+                    removed += 1;
+                    continue;
+                }
+            }
+            // Removed lines leave no leading blank lines or runs of blank lines
+            if key.is_empty() && kept.last().is_none_or(|last| last.trim().is_empty()) {
+                continue;
+            }
+            kept.push(unit);
+        }
+        while kept.last().is_some_and(|last| last.trim().is_empty()) {
+            kept.pop();
+        }
+        let separator = match self.unit {
+            Unit::Line => "\n",
+            Unit::Paragraph => "\n\n",
+        };
+        (kept.join(separator), removed)
+    }
+}
+
+impl Operator for DedupLines {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        let (deduped, removed) = self.dedup(text);
+        if removed > 0 {
+            *text = deduped;
+            ctx.metrics().increment("removed_units", removed as u64);
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
+
+/// Paragraphs of `text` (separated by lines containing only whitespace), trimmed of blank lines
+pub(crate) fn split_paragraphs(text: &str) -> Vec<&str> {
+    let mut paragraphs = Vec::new();
+    let mut start = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(start) = start.take() {
+                paragraphs.push(text[start..end].trim_end_matches(['\n', '\r']));
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.len();
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        paragraphs.push(text[start..end].trim_end_matches(['\n', '\r']));
+    }
+    paragraphs
+}
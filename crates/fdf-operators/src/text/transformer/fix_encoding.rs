@@ -0,0 +1,142 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+
+/// Characters of the Windows-1252 bytes 0x80 to 0x9F ('\0' for the five unassigned bytes)
+const CP1252_HIGH: [char; 32] = [
+    '€', '\0', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\0', 'Ž', '\0', '\0', '‘',
+    '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\0', 'ž', 'Ÿ',
+];
+
+const MAX_PASSES: usize = 3; // Text encoded twice or three times is repaired too
+
+/// Repairs mojibake (UTF-8 text decoded as Windows-1252 or Latin-1, e.g. "cafÃ©" or "â€™") in a
+/// text field in place, and records whether the text was changed
+#[fdf_operator(name = "text.fix_encoding", category = "transformer")]
+pub struct FixEncoding {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field to write whether a repair was applied to (bool)
+    #[param(default = "encoding_fixed")]
+    fixed_col: String,
+    /// Also replace stray C1 control characters (U+0080 to U+009F) with the Windows-1252
+    /// characters they usually stand for
+    #[param(default = true)]
+    fix_c1_controls: bool,
+}
+
+impl Operator for FixEncoding {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        let fixed = match fix_mojibake(text, self.fix_c1_controls) {
+            Some(repaired) => {
+                *text = repaired;
+                ctx.metrics().increment("fixed", 1);
+                true
+            }
+            None => false,
+        };
+        sample.set_path(&self.fixed_col, Value::Bool(fixed))?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.fixed_col),
+        )
+    }
+}
+
+/// Repaired text, or None if no mojibake was found
+pub fn fix_mojibake(text: &str, fix_c1_controls: bool) -> Option<String> {
+    let mut repaired: Option<String> = None;
+    for _ in 0..MAX_PASSES {
+        match fix_utf8_sequences(repaired.as_deref().unwrap_or(text)) {
+            Some(pass) => repaired = Some(pass),
+            None => break,
+        }
+    }
+    if fix_c1_controls {
+        let current = repaired.as_deref().unwrap_or(text);
+        if current.chars().any(|c| cp1252_for_c1(c).is_some()) {
+            repaired = Some(
+                current
+                    .chars()
+                    .map(|c| cp1252_for_c1(c).unwrap_or(c))
+                    .collect(),
+            );
+        }
+    }
+    repaired
+}
+
+/// One pass replacing runs of characters whose Windows-1252 bytes form a UTF-8 sequence by the
+/// character they encode
+fn fix_utf8_sequences(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some((decoded, len)) = decode_at(&chars[i..]) {
+            out.push(decoded);
+            i += len;
+            changed = true;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    changed.then_some(out)
+}
+
+/// Character encoded by the Windows-1252 bytes of the first chars, and how many chars it took
+fn decode_at(chars: &[char]) -> Option<(char, usize)> {
+    let lead = sloppy_cp1252_byte(chars[0])?;
+    let len = match lead {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let mut bytes = [lead, 0, 0, 0];
+    for (byte, &c) in bytes[1..len].iter_mut().zip(chars.get(1..len)?) {
+        *byte = sloppy_cp1252_byte(c).filter(|b| (0x80..=0xBF).contains(b))?;
+    }
+    let decoded = std::str::from_utf8(&bytes[..len]).ok()?.chars().next()?;
+    // Two characters can be legitimate text ("CAFÉ…", "„GRÜN“"): only take two-byte sequences
+    // decoding to Latin, Greek, Cyrillic, Hebrew or Arabic letters, as mojibake of those does, or
+    // to Windows-1252 characters (one step of text encoded twice)
+    let plausible = matches!(decoded, '\u{80}'..='\u{17F}' | '\u{370}'..='\u{6FF}')
+        || CP1252_HIGH.contains(&decoded);
+    if len == 2 && !plausible {
+        return None;
+    }
+    Some((decoded, len))
+}
+
+/// Byte of a character in Windows-1252, taking C1 control characters (Latin-1 decoding of the
+/// bytes 0x80 to 0x9F) as their own byte
+fn sloppy_cp1252_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        code @ 0..=0xFF => Some(code as u8),
+        _ => CP1252_HIGH
+            .iter()
+            .position(|&high| high == c)
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+/// Windows-1252 character of a C1 control character, if its byte is assigned there
+fn cp1252_for_c1(c: char) -> Option<char> {
+    match c as u32 {
+        0x80..=0x9F => Some(CP1252_HIGH[c as usize - 0x80]).filter(|&high| high != '\0'),
+        _ => None,
+    }
+}
@@ -0,0 +1,395 @@
+use ego_tree::{NodeId, NodeRef};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use scraper::{Html, Node};
+use std::collections::HashMap;
+
+/// Elements whose content is never main text
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "head", "svg", "math", "iframe", "object", "embed",
+    "canvas", "form", "button", "input", "select", "textarea", "label", "nav", "footer", "header",
+    "aside", "menu", "dialog",
+];
+
+/// Elements that start a new text block
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "body",
+    "td",
+    "th",
+    "tr",
+    "table",
+    "li",
+    "ul",
+    "ol",
+    "dl",
+    "dt",
+    "dd",
+    "pre",
+    "blockquote",
+    "figure",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "address",
+    "details",
+    "summary",
+    "center",
+];
+
+/// class / id words of boilerplate containers, and of content containers (which win)
+const NEGATIVE_HINTS: &[&str] = &[
+    "comment",
+    "footer",
+    "sidebar",
+    "navbar",
+    "nav",
+    "menu",
+    "breadcrumb",
+    "share",
+    "social",
+    "cookie",
+    "banner",
+    "advert",
+    "ads",
+    "sponsor",
+    "promo",
+    "related",
+    "popup",
+    "modal",
+    "newsletter",
+    "subscribe",
+    "signup",
+    "widget",
+    "masthead",
+    "pagination",
+    "skip",
+];
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "content", "main", "post", "entry", "story", "text", "body", "blog",
+];
+
+const MIN_PARAGRAPH_CHARS: usize = 25; // Shorter blocks do not score their containers
+const MAX_LINK_DENSITY: f64 = 0.5; // Blocks mostly made of link text are dropped
+
+/// Extracts the main content of an HTML document (readability-style: boilerplate pruning, text
+/// and link density scoring), replacing the HTML with plain text
+#[fdf_operator(name = "text.html_extract", category = "transformer")]
+pub struct HtmlExtract {
+    /// Field holding the HTML, replaced by the extracted text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Field to write the extraction confidence (0 to 1) to
+    #[param(default = "extraction_confidence")]
+    confidence_col: String,
+    /// Field to write the page title to; not written if unset
+    title_col: Option<String>,
+    /// Drop documents without extractable content
+    #[param(default = true)]
+    drop_empty: bool,
+}
+
+impl Operator for HtmlExtract {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let html = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+
+        let extracted = extract(html);
+        if extracted.text.is_empty() && self.drop_empty {
+            return Ok(None);
+        }
+        sample.set_path(&self.text_col, Value::String(extracted.text))?;
+        sample.set_path(&self.confidence_col, Value::from(extracted.confidence))?;
+        if let Some(title_col) = &self.title_col {
+            let title = extracted.title.map_or(Value::Null, Value::String);
+            sample.set_path(title_col, title)?;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let mut columns = ColumnSpec::new()
+            .requires(&self.text_col)
+            .produces(&self.text_col)
+            .produces(&self.confidence_col);
+        if let Some(title_col) = &self.title_col {
+            columns = columns.produces(title_col);
+        }
+        Some(columns)
+    }
+}
+
+/// Main content of a page
+pub struct Extracted {
+    pub text: String, // Blocks separated by blank lines
+    pub confidence: f64,
+    pub title: Option<String>,
+}
+
+/// Run of text inside one block element (up to the next nested block)
+struct Block {
+    text: String,
+    link_chars: usize,
+    ancestors: Vec<NodeId>, // Enclosing elements, innermost first
+}
+
+impl Block {
+    fn chars(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn link_density(&self) -> f64 {
+        self.link_chars as f64 / self.chars().max(1) as f64
+    }
+}
+
+#[derive(Default)]
+struct Walker {
+    blocks: Vec<Block>,
+    current: Option<usize>,      // Block receiving text
+    ancestors: Vec<NodeId>,      // Elements being walked, outermost first
+    bonus: HashMap<NodeId, f64>, // Score of content-like containers (<article>, class="post")
+}
+
+impl Walker {
+    fn walk(&mut self, node: NodeRef<'_, Node>, in_link: bool, preformatted: bool) {
+        match node.value() {
+            Node::Text(text) => self.push_text(text, in_link, preformatted),
+            Node::Element(element) => {
+                let tag = element.name();
+                if SKIPPED_TAGS.contains(&tag) || is_boilerplate(element) {
+                    return;
+                }
+                if tag == "br" {
+                    self.push_text("\n", in_link, true);
+                    return;
+                }
+                if matches!(tag, "article" | "main") || has_hint(element, POSITIVE_HINTS) {
+                    self.bonus.insert(node.id(), 25.0);
+                }
+
+                let block = BLOCK_TAGS.contains(&tag);
+                if block {
+                    self.current = None;
+                }
+                self.ancestors.push(node.id());
+                for child in node.children() {
+                    self.walk(child, in_link || tag == "a", preformatted || tag == "pre");
+                }
+                self.ancestors.pop();
+                if block {
+                    self.current = None;
+                }
+            }
+            Node::Document | Node::Fragment => {
+                for child in node.children() {
+                    self.walk(child, in_link, preformatted);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, text: &str, in_link: bool, preformatted: bool) {
+        let text = if preformatted {
+            text.to_string()
+        } else {
+            // Collapse whitespace but keep a single separating space
+            let mut collapsed = String::with_capacity(text.len());
+            let mut space = false;
+            for c in text.chars() {
+                if c.is_whitespace() {
+                    space = true;
+                } else {
+                    if space {
+                        collapsed.push(' ');
+                    }
+                    space = false;
+                    collapsed.push(c);
+                }
+            }
+            if space {
+                collapsed.push(' ');
+            }
+            collapsed
+        };
+        if text.trim().is_empty() && !text.contains('\n') {
+            if let Some(index) = self.current {
+                if !self.blocks[index].text.ends_with([' ', '\n']) && !text.is_empty() {
+                    self.blocks[index].text.push(' ');
+                }
+            }
+            return;
+        }
+
+        let index = match self.current {
+            Some(index) => index,
+            None => {
+                self.blocks.push(Block {
+                    text: String::new(),
+                    link_chars: 0,
+                    ancestors: self.ancestors.iter().rev().copied().collect(),
+                });
+                self.blocks.len() - 1
+            }
+        };
+        self.current = Some(index);
+        let block = &mut self.blocks[index];
+        let text = if block.text.is_empty() || block.text.ends_with('\n') {
+            text.trim_start_matches(' ')
+        } else {
+            &text
+        };
+        block.text.push_str(text);
+        if in_link {
+            block.link_chars += text.trim().chars().count();
+        }
+    }
+}
+
+/// Extract the main content of an HTML document
+pub fn extract(html: &str) -> Extracted {
+    let document = Html::parse_document(html);
+    let mut walker = Walker::default();
+    walker.walk(document.tree.root(), false, false);
+
+    let blocks: Vec<Block> = walker
+        .blocks
+        .into_iter()
+        .map(|mut block| {
+            block.text = block
+                .text
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string();
+            block
+        })
+        .filter(|block| !block.text.is_empty())
+        .collect();
+
+    // Paragraphs score their parent and grandparent containers
+    let mut scores = walker.bonus.clone();
+    for block in blocks.iter().filter(|b| b.chars() >= MIN_PARAGRAPH_CHARS) {
+        let score = 1.0
+            + block.text.matches([',', '，', '、']).count() as f64
+            + (block.chars() as f64 / 100.0).min(3.0);
+        for (depth, ancestor) in block.ancestors.iter().take(3).enumerate() {
+            let weight = [1.0, 1.0, 0.5][depth];
+            *scores.entry(*ancestor).or_default() += score * weight;
+        }
+    }
+
+    // Weigh containers by how little of their text is links
+    let mut chars: HashMap<NodeId, (usize, usize)> = HashMap::new(); // (chars, link chars)
+    for block in &blocks {
+        for ancestor in &block.ancestors {
+            let entry = chars.entry(*ancestor).or_default();
+            entry.0 += block.chars();
+            entry.1 += block.link_chars;
+        }
+    }
+    let link_density = |id: &NodeId| {
+        chars
+            .get(id)
+            .map_or(1.0, |(chars, links)| *links as f64 / (*chars).max(1) as f64)
+    };
+    let top = scores
+        .iter()
+        .filter(|(id, _)| chars.contains_key(*id))
+        .map(|(id, score)| (*id, score * (1.0 - link_density(id))))
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+
+    // Content roots: the best container and its siblings scoring almost as well
+    let roots: Vec<NodeId> = match top {
+        Some((top_id, top_score)) if top_score > 0.0 => {
+            let threshold = (top_score * 0.2).max(10.0);
+            let mut roots = vec![top_id];
+            if let Some(parent) = document.tree.get(top_id).and_then(|node| node.parent()) {
+                roots.extend(
+                    parent
+                        .children()
+                        .map(|sibling| sibling.id())
+                        .filter(|&id| id != top_id)
+                        .filter(|id| {
+                            scores.get(id).copied().unwrap_or(0.0) * (1.0 - link_density(id))
+                                >= threshold
+                        }),
+                );
+            }
+            roots
+        }
+        _ => Vec::new(),
+    };
+
+    let kept: Vec<&Block> = blocks
+        .iter()
+        .filter(|block| roots.is_empty() || block.ancestors.iter().any(|a| roots.contains(a)))
+        .filter(|block| block.link_density() < MAX_LINK_DENSITY)
+        .collect();
+    let text = kept
+        .iter()
+        .map(|block| block.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // Confidence: a well-scored container, enough text, few links
+    let text_chars: usize = kept.iter().map(|b| b.chars()).sum();
+    let link_chars: usize = kept.iter().map(|b| b.link_chars).sum();
+    let confidence = if text_chars == 0 {
+        0.0
+    } else {
+        let container = top.map_or(0.0, |(_, score)| (score / 40.0).min(1.0));
+        let length = (text_chars as f64 / 1000.0).min(1.0);
+        let links = 1.0 - link_chars as f64 / text_chars as f64;
+        (0.5 * container + 0.5 * length) * links
+    };
+
+    Extracted {
+        text,
+        confidence,
+        title: title(&document),
+    }
+}
+
+/// Text of the first <title> element, whitespace collapsed
+fn title(document: &Html) -> Option<String> {
+    let element = document.tree.root().descendants().find(|node| {
+        node.value()
+            .as_element()
+            .is_some_and(|e| e.name() == "title")
+    })?;
+    let text: String = element
+        .descendants()
+        .filter_map(|node| node.value().as_text().map(|text| &**text))
+        .collect();
+    let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Whether an element's class or id marks it as boilerplate (and not also as content)
+fn is_boilerplate(element: &scraper::node::Element) -> bool {
+    has_hint(element, NEGATIVE_HINTS) && !has_hint(element, POSITIVE_HINTS)
+}
+
+/// Whether a word of the element's class or id (split on '-', '_' and spaces) is in `hints`
+fn has_hint(element: &scraper::node::Element, hints: &[&str]) -> bool {
+    let class = element.attr("class").unwrap_or("");
+    let id = element.id().unwrap_or("");
+    class
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .chain(id.split(|c: char| c.is_whitespace() || c == '-' || c == '_'))
+        .any(|word| hints.contains(&word.to_ascii_lowercase().as_str()))
+}
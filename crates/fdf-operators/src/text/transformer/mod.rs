@@ -1,7 +1,35 @@
+#[cfg(feature = "text-ml")]
+pub mod anonymize_entities;
+pub mod chunk;
+pub mod decode_charset;
+pub mod dedup_lines;
+pub mod fix_encoding;
+pub mod html_extract;
 pub mod normalize;
+pub mod pack;
+pub mod sentence_dedup;
+pub mod squeeze;
+pub mod strip_boilerplate;
+pub mod substring_dedup;
+pub mod truncate_tokens;
+pub mod unicode_normalize;
 
 use fdf_sdk::OperatorRegistry;
 
 pub fn register(registry: &mut OperatorRegistry) {
     normalize::register(registry);
+    truncate_tokens::register(registry);
+    html_extract::register(registry);
+    unicode_normalize::register(registry);
+    decode_charset::register(registry);
+    fix_encoding::register(registry);
+    dedup_lines::register(registry);
+    substring_dedup::register(registry);
+    sentence_dedup::register(registry);
+    chunk::register(registry);
+    pack::register(registry);
+    squeeze::register(registry);
+    strip_boilerplate::register(registry);
+    #[cfg(feature = "text-ml")]
+    anonymize_entities::register(registry);
 }
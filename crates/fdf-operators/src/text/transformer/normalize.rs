@@ -1,15 +1,26 @@
-use fdf_sdk::{Operator, Result, Sample};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
 
+/// Lowercases and/or strips surrounding whitespace from a text field in place
+#[fdf_operator(
+    name = "text.normalize_transformer",
+    category = "transformer",
+    alias = "text_normalize_transformer"
+)]
 pub struct NormalizeTransformer {
+    /// Field holding the text (dot path allowed)
     text_col: String,
+    /// Lowercase the text
+    #[param(default = false)]
     lowercase: bool,
+    /// Trim leading and trailing whitespace
+    #[param(default = false)]
     strip: bool,
 }
 
 impl Operator for NormalizeTransformer {
-    fn process(&self, mut sample: Sample) -> Result<Option<Sample>> {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
         // Try to get mutable reference to the string for in-place modification
-        if let Some(text_mut) = sample.get_str_mut(&self.text_col) {
+        if let Some(Value::String(text_mut)) = sample.get_path_mut(&self.text_col) {
             // In-place modification: modify the string directly
             if self.strip && self.lowercase {
                 // Both operations: trim first, then lowercase
@@ -56,21 +67,8 @@ impl Operator for NormalizeTransformer {
 
         Ok(Some(sample)) // Keep the sample
     }
-}
-
-pub fn register(registry: &mut fdf_sdk::OperatorRegistry) {
-    registry.register(
-        "text_normalize_transformer",
-        |config: &serde_yaml::Value| {
-            let text_col = config["text_col"].as_str().unwrap().to_string();
-            let lowercase = config["lowercase"].as_bool().unwrap_or(false);
-            let strip = config["strip"].as_bool().unwrap_or(false);
 
-            Ok(Box::new(NormalizeTransformer {
-                text_col,
-                lowercase,
-                strip,
-            }))
-        },
-    );
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
 }
@@ -0,0 +1,250 @@
+use crate::text::tokenizer::Tokenizer;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    Split,    // Continue the document in the next window
+    Truncate, // Start the document in a new window, dropping the tokens beyond max_tokens
+}
+
+/// Packs documents into windows of at most max_tokens tokens for pretraining: documents are
+/// concatenated with a separator, and each window records where its documents start and end.
+/// Input documents are consumed (counted as removed by the step) and windows are emitted as
+/// they fill up, the last one at the end of the stream.
+#[fdf_operator(
+    name = "text.pack",
+    category = "transformer",
+    build = "PackConfig::build"
+)]
+struct PackConfig {
+    /// Field holding the text (dot path allowed); windows hold their text in the same field
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum number of tokens of a window, separators included
+    max_tokens: usize,
+    /// Tokenizer: whitespace, chars, a tiktoken encoding (cl100k_base, o200k_base, p50k_base,
+    /// r50k_base) or the path of a HuggingFace tokenizer.json
+    #[param(default = "whitespace")]
+    tokenizer: String,
+    /// Text inserted between documents of a window, e.g. an end-of-text token
+    #[param(default = "<|endoftext|>")]
+    separator: String,
+    /// Documents not fitting in the current window: "split" continues them in the next one,
+    /// "truncate" starts them in a new window and drops their tokens beyond max_tokens
+    #[param(default = "split", type = "string")]
+    overflow: Overflow,
+    /// Field holding the document id, recorded in the boundary map (null if missing)
+    #[param(default = "id")]
+    id_col: String,
+    /// Field receiving the boundary map of a window: one {id, start, end, text_start,
+    /// text_end} entry per document (part), with token and byte offsets in the window
+    #[param(default = "doc_boundaries")]
+    boundaries_col: String,
+    /// Field receiving the number of tokens of a window
+    #[param(default = "num_tokens")]
+    tokens_col: String,
+    /// Drop the last window if it is not full
+    #[param(default = false)]
+    drop_last: bool,
+}
+
+impl PackConfig {
+    fn build(self) -> Result<Pack> {
+        if self.max_tokens == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_tokens must be positive",
+                Self::NAME
+            ));
+        }
+        Tokenizer::check(&self.tokenizer)
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", Self::NAME, e))?;
+        Ok(Pack {
+            text_col: self.text_col,
+            max_tokens: self.max_tokens,
+            tokenizer_name: self.tokenizer,
+            separator: self.separator,
+            overflow: self.overflow,
+            id_col: self.id_col,
+            boundaries_col: self.boundaries_col,
+            tokens_col: self.tokens_col,
+            drop_last: self.drop_last,
+            tokenizer: None,
+            separator_tokens: 0,
+            window: Mutex::new(Window::default()),
+        })
+    }
+}
+
+pub struct Pack {
+    text_col: String,
+    max_tokens: usize,
+    tokenizer_name: String,
+    separator: String,
+    overflow: Overflow,
+    id_col: String,
+    boundaries_col: String,
+    tokens_col: String,
+    drop_last: bool,
+    tokenizer: Option<Tokenizer>, // Loaded in open
+    separator_tokens: usize,      // Counted in open
+    window: Mutex<Window>,        // Window being filled
+}
+
+/// Window being filled
+#[derive(Default)]
+struct Window {
+    text: String,
+    tokens: usize,
+    boundaries: Vec<Value>,
+}
+
+impl Pack {
+    /// Emit the window into `windows` and start a new one
+    fn emit(&self, window: &mut Window, windows: &mut Vec<Sample>) -> Result<()> {
+        let window = std::mem::take(window);
+        let mut sample = Sample::new();
+        sample.set_path(&self.text_col, Value::String(window.text))?;
+        sample.set_path(&self.boundaries_col, Value::Array(window.boundaries))?;
+        sample.set_path(&self.tokens_col, Value::from(window.tokens))?;
+        windows.push(sample);
+        Ok(())
+    }
+
+    /// Add a document to the window, emitting the windows it fills
+    fn add(
+        &self,
+        window: &mut Window,
+        text: &str,
+        id: &Value,
+        ctx: &Context,
+    ) -> Result<Vec<Sample>> {
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let ends = tokenizer.token_ends(text)?;
+        let mut windows = Vec::new();
+        let mut first = 0; // Index of the first token not packed yet
+        while first < ends.len() {
+            let rest = ends.len() - first;
+            if first == 0 && window.tokens > 0 {
+                // The separator and at least one token (the whole document when truncating)
+                // must fit after the previous document
+                let needed = match self.overflow {
+                    Overflow::Split => 1,
+                    Overflow::Truncate => rest,
+                };
+                if window.tokens + self.separator_tokens + needed > self.max_tokens {
+                    self.emit(window, &mut windows)?;
+                } else {
+                    window.text.push_str(&self.separator);
+                    window.tokens += self.separator_tokens;
+                }
+            }
+            let count = rest.min(self.max_tokens - window.tokens);
+            let last = first + count;
+            let start = if first == 0 { 0 } else { ends[first - 1] };
+            let end = if last == ends.len() {
+                text.len()
+            } else {
+                ends[last - 1]
+            };
+            window.boundaries.push(json!({
+                "id": id,
+                "start": window.tokens,
+                "end": window.tokens + count,
+                "text_start": window.text.len(),
+                "text_end": window.text.len() + end - start,
+            }));
+            window.text.push_str(&text[start..end]);
+            window.tokens += count;
+            if window.tokens >= self.max_tokens {
+                self.emit(window, &mut windows)?;
+            }
+            if last < ends.len() {
+                match self.overflow {
+                    Overflow::Split => ctx.metrics().increment("split_parts", 1),
+                    Overflow::Truncate => {
+                        ctx.metrics().increment("truncated_documents", 1);
+                        break;
+                    }
+                }
+            }
+            first = last;
+        }
+        Ok(windows)
+    }
+}
+
+impl Operator for Pack {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let tokenizer = Tokenizer::load(&self.tokenizer_name, ctx)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", PackConfig::NAME, e)))?;
+        self.separator_tokens = tokenizer.count(&self.separator)?;
+        if self.separator_tokens >= self.max_tokens {
+            return Err(OpError::fatal(format!(
+                "{}: the separator does not fit in max_tokens",
+                PackConfig::NAME
+            ))
+            .into());
+        }
+        self.tokenizer = Some(tokenizer);
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let mut windows = self.flat_map(sample, &Context::default())?;
+        if windows.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "Document filled {} windows; run it through the engine",
+                windows.len()
+            ));
+        }
+        Ok(windows.pop())
+    }
+
+    fn flat_map(&self, sample: Sample, ctx: &Context) -> Result<Vec<Sample>> {
+        let text = sample
+            .get_path(&self.text_col)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing text field: {}", self.text_col))?;
+        let id = sample
+            .get_path(&self.id_col)
+            .cloned()
+            .unwrap_or(Value::Null);
+        let mut window = self.window.lock().unwrap();
+        let windows = self.add(&mut window, text, &id, ctx)?;
+        ctx.metrics().increment("windows", windows.len() as u64);
+        Ok(windows)
+    }
+
+    fn finish(&self, ctx: &Context) -> Result<Vec<Sample>> {
+        let mut window = self.window.lock().unwrap();
+        let mut windows = Vec::new();
+        if window.tokens > 0 {
+            if self.drop_last {
+                ctx.metrics()
+                    .increment("dropped_tokens", window.tokens as u64);
+                *window = Window::default();
+            } else {
+                self.emit(&mut window, &mut windows)?;
+                ctx.metrics().increment("windows", 1);
+            }
+        }
+        Ok(windows)
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // Windows only hold the packed fields
+        Some(
+            ColumnSpec::new()
+                .requires(&self.text_col)
+                .produces(&self.boundaries_col)
+                .produces(&self.tokens_col),
+        )
+    }
+}
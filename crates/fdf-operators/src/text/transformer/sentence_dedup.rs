@@ -0,0 +1,366 @@
+use super::dedup_lines::split_paragraphs;
+use crate::text::hash_index::{read_meta, PassState, RepeatedHashes, ShardWriter};
+use crate::text::sentences::split_sentences;
+use fdf_sdk::{
+    fdf_operator, ColumnSpec, Context, OpError, Result, Sample, StateHandle, Stateful,
+    StatefulOperator, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Index,  // First pass: record the units of every document in index_dir
+    Remove, // Second pass: remove the units the index saw in too many documents
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Sentence,  // Sentences (text.sentence_split rules)
+    Line,      // Lines
+    Paragraph, // Blocks separated by blank lines
+}
+
+/// Removes sentences (or lines, paragraphs) found in many documents across the corpus:
+/// boilerplate such as cookie notices, share prompts or legal footers. Runs as two pipelines
+/// over the same data: `mode: index` counts the documents containing each unit in an on-disk
+/// index, `mode: remove` rebuilds documents from the units in at most `max_documents` of them.
+#[fdf_operator(
+    name = "text.sentence_dedup",
+    category = "transformer",
+    build = "SentenceDedupConfig::build"
+)]
+struct SentenceDedupConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// "index" (first pass, documents pass through unchanged) or "remove" (second pass)
+    #[param(type = "string")]
+    mode: Mode,
+    /// Directory of the unit index, written by the index pass when it succeeds and read by the
+    /// remove pass
+    index_dir: String,
+    /// Unit deduplicated: "sentence", "line" or "paragraph"; units are compared lowercased
+    /// with whitespace collapsed
+    #[param(default = "sentence", type = "string")]
+    unit: Unit,
+    /// Units found in more documents than this are removed
+    #[param(default = 10)]
+    max_documents: u32,
+    /// Units with fewer words are never removed ("Thank you.", list items)
+    #[param(default = 3)]
+    min_words: usize,
+    /// Language of the sentence splitting rules (ISO 639-1)
+    #[param(default = "en")]
+    language: String,
+    /// Drop documents left empty by the removal
+    #[param(default = true)]
+    drop_empty: bool,
+}
+
+impl SentenceDedupConfig {
+    fn build(self) -> Result<Stateful<SentenceDedup>> {
+        if self.max_documents == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_documents must be positive",
+                Self::NAME
+            ));
+        }
+        Ok(Stateful::new(SentenceDedup {
+            text_col: self.text_col,
+            mode: self.mode,
+            index_dir: PathBuf::from(self.index_dir),
+            meta: IndexMeta {
+                unit: self.unit,
+                max_documents: self.max_documents,
+                min_words: self.min_words,
+                language: self.language,
+                documents: 0,
+            },
+            drop_empty: self.drop_empty,
+        }))
+    }
+}
+
+/// Settings the index was built with, checked by the remove pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexMeta {
+    unit: Unit,
+    max_documents: u32,
+    min_words: usize,
+    language: String,
+    documents: u64, // Documents indexed
+}
+
+pub struct SentenceDedup {
+    text_col: String,
+    mode: Mode,
+    index_dir: PathBuf,
+    meta: IndexMeta,
+    drop_empty: bool,
+}
+
+impl SentenceDedup {
+    /// Units of a text (trimmed slices of it, in order) with their hashes, None for units too
+    /// short to be removed
+    fn units<'a>(&self, text: &'a str) -> Vec<(&'a str, Option<u64>)> {
+        let units = match self.meta.unit {
+            Unit::Sentence => split_sentences(text, &self.meta.language, false),
+            Unit::Line => text
+                .split('\n')
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect(),
+            Unit::Paragraph => split_paragraphs(text).into_iter().map(str::trim).collect(),
+        };
+        units
+            .into_iter()
+            .map(|unit| {
+                let words: Vec<String> = unit.split_whitespace().map(str::to_lowercase).collect();
+                let hash = (words.len() >= self.meta.min_words.max(1))
+                    .then(|| xxhash_rust::xxh3::xxh3_64(words.join(" ").as_bytes()));
+                (unit, hash)
+            })
+            .collect()
+    }
+
+    /// Text rebuilt from the units not repeated across documents, and the number of units
+    /// removed; None if nothing is removed
+    fn remove(&self, repeated: &RepeatedHashes, text: &str) -> Option<(String, usize)> {
+        let units = self.units(text);
+        let removed = units
+            .iter()
+            .filter(|(_, hash)| hash.is_some_and(|hash| repeated.contains(hash)))
+            .count();
+        if removed == 0 {
+            return None;
+        }
+
+        let mut out = String::with_capacity(text.len());
+        let mut separator: Option<&str> = None; // Widest gap since the last kept unit
+        let mut last_end = 0;
+        for (unit, hash) in units {
+            let start = unit.as_ptr() as usize - text.as_ptr() as usize;
+            let gap = &text[last_end..start];
+            if separator.is_none_or(|separator| newlines(gap) > newlines(separator)) {
+                separator = Some(gap);
+            }
+            last_end = start + unit.len();
+            if hash.is_some_and(|hash| repeated.contains(hash)) {
+                continue;
+            }
+            if !out.is_empty() {
+                // Keep the line structure of the removed text at the cut
+                match separator {
+                    Some(separator) if !separator.is_empty() => out.push_str(separator),
+                    _ => out.push(' '),
+                }
+            }
+            out.push_str(unit);
+            separator = None;
+        }
+        Some((out, removed))
+    }
+}
+
+fn newlines(text: &str) -> usize {
+    text.matches('\n').count()
+}
+
+impl StatefulOperator for SentenceDedup {
+    type State = PassState;
+
+    fn open_state(&mut self, ctx: &Context) -> Result<PassState> {
+        let fatal =
+            |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", SentenceDedupConfig::NAME, e));
+        match self.mode {
+            Mode::Index => {
+                let dir = ctx.scratch_dir().join("sentence_dedup").join(format!(
+                    "{:x}",
+                    xxhash_rust::xxh3::xxh3_64(self.index_dir.to_string_lossy().as_bytes())
+                ));
+                let writer = ShardWriter::create(&dir).map_err(fatal)?;
+                Ok(PassState::Index(Some(writer)))
+            }
+            Mode::Remove => {
+                let key = format!("sentence_dedup:{}", self.index_dir.display());
+                let repeated = ctx
+                    .resources()
+                    .get_or_load(&key, || load_index(&self.index_dir, &self.meta))
+                    .map_err(fatal)?;
+                Ok(PassState::Remove(repeated))
+            }
+        }
+    }
+
+    fn process_with_state(
+        &self,
+        state: &StateHandle<PassState>,
+        mut sample: Sample,
+        ctx: &Context,
+    ) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        match self.mode {
+            Mode::Index => {
+                // A document counts once per unit, however often it repeats it
+                let distinct: HashSet<u64> = self
+                    .units(text)
+                    .into_iter()
+                    .filter_map(|(_, hash)| hash)
+                    .collect();
+                let mut state = state.write();
+                let PassState::Index(Some(writer)) = &mut *state else {
+                    return Err(anyhow::anyhow!("Index already published"));
+                };
+                writer.add_document(distinct)?;
+                Ok(Some(sample))
+            }
+            Mode::Remove => {
+                let PassState::Remove(repeated) = &*state.read() else {
+                    unreachable!("the remove pass loads the index");
+                };
+                let Some((deduped, removed)) = self.remove(repeated, text) else {
+                    return Ok(Some(sample));
+                };
+                ctx.metrics().increment("documents_changed", 1);
+                ctx.metrics().increment("removed_units", removed as u64);
+                if deduped.trim().is_empty() && self.drop_empty {
+                    return Ok(None);
+                }
+                *text = deduped;
+                Ok(Some(sample))
+            }
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+
+    fn finish_state(&self, state: &StateHandle<PassState>, _ctx: &Context) -> Result<Vec<Sample>> {
+        // The index is only published once every document was indexed
+        state
+            .write()
+            .publish(&self.index_dir, self.meta.max_documents + 1, |documents| {
+                IndexMeta {
+                    documents,
+                    ..self.meta.clone()
+                }
+            })
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", SentenceDedupConfig::NAME, e))?;
+        Ok(Vec::new())
+    }
+
+    fn close_state(&mut self, state: &mut PassState) -> Result<()> {
+        // Left by a failed run: the partial index is not published
+        state.discard();
+        Ok(())
+    }
+}
+
+/// Units of the index in `index_dir` found in too many documents, checking it was built with
+/// the same settings
+fn load_index(index_dir: &Path, expected: &IndexMeta) -> Result<RepeatedHashes> {
+    let meta: IndexMeta = read_meta(index_dir)?;
+    if meta.unit != expected.unit
+        || meta.max_documents != expected.max_documents
+        || meta.min_words != expected.min_words
+        || meta.language != expected.language
+    {
+        return Err(anyhow::anyhow!(
+            "Index in {} was built with unit {:?}, max_documents {}, min_words {} and language \
+             {}; the remove pass must use the same settings",
+            index_dir.display(),
+            meta.unit,
+            meta.max_documents,
+            meta.min_words,
+            meta.language
+        ));
+    }
+    RepeatedHashes::load(index_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use fdf_sdk::testing::{build_operator, run_operator, sample, samples};
+    use serde_json::json;
+
+    fn config(mode: &str, index_dir: &std::path::Path) -> String {
+        format!(
+            "mode: {}\nindex_dir: {}\nunit: line\nmax_documents: 1\nmin_words: 2",
+            mode,
+            index_dir.display()
+        )
+    }
+
+    fn documents() -> Vec<fdf_sdk::Sample> {
+        samples([
+            json!({"text": "First article body.\nAccept all cookies now"}),
+            json!({"text": "Second article body.\nAccept all cookies now"}),
+            json!({"text": "Accept all cookies now"}),
+        ])
+    }
+
+    #[test]
+    fn removes_lines_repeated_across_documents() {
+        let index_dir = std::env::temp_dir().join(format!("fdf-sentence-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&index_dir);
+
+        let mut index = build_operator(
+            super::register,
+            "text.sentence_dedup",
+            &config("index", &index_dir),
+        )
+        .unwrap();
+        assert_eq!(run_operator(index.as_mut(), documents()).unwrap().len(), 3);
+
+        let mut remove = build_operator(
+            super::register,
+            "text.sentence_dedup",
+            &config("remove", &index_dir),
+        )
+        .unwrap();
+        let outputs = run_operator(remove.as_mut(), documents()).unwrap();
+        let texts: Vec<&serde_json::Value> =
+            outputs.iter().map(|s| &s.as_value()["text"]).collect();
+        assert_eq!(
+            texts,
+            [
+                &json!("First article body."),
+                &json!("Second article body.")
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&index_dir);
+    }
+
+    #[test]
+    fn failed_index_pass_publishes_nothing() {
+        let index_dir =
+            std::env::temp_dir().join(format!("fdf-sentence-failed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&index_dir);
+
+        let mut index = build_operator(
+            super::register,
+            "text.sentence_dedup",
+            &config("index", &index_dir),
+        )
+        .unwrap();
+        let mut inputs = documents();
+        inputs.insert(1, sample(json!({"id": 1})));
+        assert!(run_operator(index.as_mut(), inputs).is_err());
+        assert!(!index_dir.exists());
+
+        let mut remove = build_operator(
+            super::register,
+            "text.sentence_dedup",
+            &config("remove", &index_dir),
+        )
+        .unwrap();
+        assert!(run_operator(remove.as_mut(), documents()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::{Captures, Regex};
+
+/// Zero width joiner, joining emoji into one (family, profession emoji)
+const ZWJ: char = '\u{200D}';
+
+/// Collapses runs of repeated characters ("!!!!!!!", "--------", "😂😂😂😂😂") and blank lines
+/// down to configurable maxima
+#[fdf_operator(
+    name = "text.squeeze",
+    category = "transformer",
+    build = "SqueezeConfig::build"
+)]
+struct SqueezeConfig {
+    /// Field holding the text, squeezed in place (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum run of the same punctuation or symbol character (0: unlimited)
+    #[param(default = 3)]
+    max_repeat: usize,
+    /// Maximum run of the same letter, e.g. 3 turns "soooooo" into "sooo" (0: unlimited);
+    /// digits are never squeezed
+    #[param(default = 0)]
+    max_letter_repeat: usize,
+    /// Maximum run of the same emoji, skin tone and joined sequences included (0: unlimited)
+    #[param(default = 3)]
+    max_emoji_repeat: usize,
+    /// Maximum consecutive newlines, lines of whitespace counting as blank (0: unlimited)
+    #[param(default = 2)]
+    max_newlines: usize,
+}
+
+impl SqueezeConfig {
+    fn build(self) -> Result<Squeeze> {
+        Ok(Squeeze {
+            text_col: self.text_col,
+            max_repeat: self.max_repeat,
+            max_letter_repeat: self.max_letter_repeat,
+            max_emoji_repeat: self.max_emoji_repeat,
+            max_newlines: self.max_newlines,
+            newlines: Regex::new(r"\n(?:[^\S\n]*\n)+")?,
+        })
+    }
+}
+
+pub struct Squeeze {
+    text_col: String,
+    max_repeat: usize,
+    max_letter_repeat: usize,
+    max_emoji_repeat: usize,
+    max_newlines: usize,
+    newlines: Regex, // Runs of newlines with whitespace between them
+}
+
+impl Squeeze {
+    /// The squeezed text, or None if nothing was squeezed
+    pub fn squeeze(&self, text: &str) -> Option<String> {
+        let mut squeezed = String::with_capacity(text.len());
+        let mut changed = false;
+        let mut previous = "";
+        let mut run = 0;
+        for unit in units(text) {
+            if unit == previous {
+                run += 1;
+            } else {
+                previous = unit;
+                run = 1;
+            }
+            let limit = self.limit(unit);
+            if limit == 0 || run <= limit {
+                squeezed.push_str(unit);
+            } else {
+                changed = true;
+            }
+        }
+
+        if self.max_newlines > 0 {
+            let blank_lines = self.newlines.replace_all(&squeezed, |captures: &Captures| {
+                let run = &captures[0];
+                if run.matches('\n').count() > self.max_newlines {
+                    "\n".repeat(self.max_newlines)
+                } else {
+                    run.to_string()
+                }
+            });
+            if blank_lines.len() != squeezed.len() {
+                changed = true;
+                squeezed = blank_lines.into_owned();
+            }
+        }
+        changed.then_some(squeezed)
+    }
+
+    /// Maximum run of a unit (0: unlimited)
+    fn limit(&self, unit: &str) -> usize {
+        let c = unit.chars().next().unwrap_or_default();
+        if c.is_whitespace() || c.is_numeric() {
+            0 // Newlines are squeezed separately
+        } else if c.is_alphabetic() {
+            self.max_letter_repeat
+        } else if unit.len() > c.len_utf8() || is_emoji(c) {
+            self.max_emoji_repeat
+        } else {
+            self.max_repeat
+        }
+    }
+}
+
+/// Characters of a text, emoji with their modifiers (variation selectors, skin tones, keycaps)
+/// and ZWJ sequences kept together
+fn units(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut end = first.len_utf8();
+        let mut joined = false; // After a ZWJ, the next character belongs to the unit
+        for (i, c) in chars {
+            if joined || c == ZWJ || is_emoji_modifier(c) {
+                joined = c == ZWJ;
+                end = i + c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let (unit, next) = rest.split_at(end);
+        rest = next;
+        Some(unit)
+    })
+}
+
+/// Whether a character is an emoji or pictograph
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF // Pictographs, emoticons, transport, supplemental symbols
+        | 0x2600..=0x27BF // Miscellaneous symbols, dingbats
+        | 0x2B00..=0x2BFF // Arrows, stars
+    )
+}
+
+/// Whether a character modifies the preceding emoji
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c as u32,
+        0xFE0E | 0xFE0F // Variation selectors
+        | 0x1F3FB..=0x1F3FF // Skin tones
+        | 0x20E3 // Combining keycap
+        | 0xE0020..=0xE007F // Tags (subdivision flags)
+    )
+}
+
+impl Operator for Squeeze {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        if let Some(squeezed) = self.squeeze(text) {
+            ctx.metrics()
+                .increment("removed_bytes", (text.len() - squeezed.len()) as u64);
+            ctx.metrics().increment("squeezed_documents", 1);
+            *text = squeezed;
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
@@ -0,0 +1,245 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use regex::{Regex, RegexBuilder};
+
+/// Categories of header and footer lines with their patterns (case-insensitive, matched
+/// against trimmed lines)
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "navigation",
+        &[
+            r"^skip to (main )?(content|navigation)$",
+            r"^(advertisement|sponsored( content)?|menu|home|back to top|scroll to top)$",
+            r"^(jump|go) to (navigation|search)$",
+        ],
+    ),
+    (
+        "copyright",
+        &[
+            r"^(©|\(c\)|copyright\b)",
+            r"\ball rights reserved\b",
+            r"^powered by \w+",
+        ],
+    ),
+    (
+        "newsletter",
+        &[
+            r"\b(subscribe|sign up|signup)\b.{0,40}\bnewsletter\b",
+            r"\bnewsletter\b.{0,20}\b(subscribe|sign up)\b",
+            r"^(enter your e-?mail( address)?|unsubscribe\b)",
+            r"^get (our|the) latest (news|updates|stories)",
+        ],
+    ),
+    (
+        "share",
+        &[
+            r"^(share (this|on)\b|follow us on\b|like us on\b)",
+            r"^(tweet|pin it|e-?mail this|print this( page| article)?)$",
+            r"^(facebook|twitter|linkedin|whatsapp|reddit|pinterest)([ ,|/]+(facebook|twitter|linkedin|whatsapp|reddit|pinterest|e-?mail))*$",
+        ],
+    ),
+    (
+        "signature",
+        &[
+            r"^--\s*$",
+            r"^sent from my (iphone|ipad|android|mobile|phone|blackberry|galaxy)",
+            r"^((best|kind|warm) )?regards,?$",
+            r"^(cheers|sincerely|thanks( again)?|thank you),?$",
+            r"^(posted|last edited) by \S+",
+        ],
+    ),
+    (
+        "citation",
+        &[
+            r"^(cite (this|as)\b|how to cite\b|citation:)",
+            r"^(retrieved|accessed) (from|on)\b",
+            r"^doi:\s*10\.",
+            r"^(references|bibliography|further reading|external links)$",
+        ],
+    ),
+];
+
+/// Categories whose first line starts a footer block: the lines after it are removed too
+/// (signature text, citation lists)
+const BLOCK_STARTS: &[&str] = &["signature", "citation"];
+
+/// Removes boilerplate blocks at the start and end of documents, line by line from both ends:
+/// navigation, copyright notices, newsletter prompts, share buttons, forum and email
+/// signatures, citation footers
+#[fdf_operator(
+    name = "text.strip_boilerplate",
+    category = "transformer",
+    build = "StripBoilerplateConfig::build"
+)]
+struct StripBoilerplateConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Built-in categories stripped (navigation, copyright, newsletter, share, signature,
+    /// citation); all if unset, [] for patterns only
+    categories: Option<Vec<String>>,
+    /// Additional line patterns (regexes, case-insensitive, matched against trimmed lines),
+    /// counted as category "custom"
+    patterns: Option<Vec<String>>,
+    /// Non-blank lines examined at each end of the document
+    #[param(default = 10)]
+    max_lines: usize,
+    /// Drop documents left empty
+    #[param(default = true)]
+    drop_empty: bool,
+}
+
+impl StripBoilerplateConfig {
+    fn build(self) -> Result<StripBoilerplate> {
+        let names = || CATEGORIES.iter().map(|(name, _)| *name);
+        let selected: Vec<String> = match self.categories {
+            Some(categories) => {
+                for category in &categories {
+                    if !names().any(|name| name == category) {
+                        return Err(anyhow::anyhow!(
+                            "{}: unknown category {} (expected one of {})",
+                            Self::NAME,
+                            category,
+                            names().collect::<Vec<_>>().join(", ")
+                        ));
+                    }
+                }
+                categories
+            }
+            None => names().map(str::to_string).collect(),
+        };
+        let compile = |patterns: &[&str]| {
+            RegexBuilder::new(&patterns.join("|"))
+                .case_insensitive(true)
+                .build()
+        };
+        let mut categories = CATEGORIES
+            .iter()
+            .filter(|(name, _)| selected.iter().any(|category| category == name))
+            .map(|&(name, patterns)| Ok((name, compile(patterns)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(patterns) = self.patterns.filter(|patterns| !patterns.is_empty()) {
+            let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+            let pattern = compile(&patterns)
+                .map_err(|e| anyhow::anyhow!("{}: invalid pattern: {}", Self::NAME, e))?;
+            categories.push(("custom", pattern));
+        }
+        if categories.is_empty() {
+            return Err(anyhow::anyhow!("{}: no categories or patterns", Self::NAME));
+        }
+        Ok(StripBoilerplate {
+            text_col: self.text_col,
+            categories,
+            max_lines: self.max_lines,
+            drop_empty: self.drop_empty,
+        })
+    }
+}
+
+pub struct StripBoilerplate {
+    text_col: String,
+    categories: Vec<(&'static str, Regex)>,
+    max_lines: usize,
+    drop_empty: bool,
+}
+
+impl StripBoilerplate {
+    /// Category of a boilerplate line, if any
+    fn category(&self, line: &str) -> Option<&'static str> {
+        let line = line.trim();
+        self.categories
+            .iter()
+            .find(|(_, pattern)| pattern.is_match(line))
+            .map(|(name, _)| *name)
+    }
+
+    /// Text without its header and footer lines, and the categories of the non-blank lines
+    /// removed; None if nothing is removed
+    pub fn strip(&self, text: &str) -> Option<(String, Vec<&'static str>)> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut removed = Vec::new();
+
+        // Header: leading boilerplate lines
+        let mut start = 0;
+        let mut examined = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if examined == self.max_lines {
+                break;
+            }
+            examined += 1;
+            match self.category(line) {
+                Some(category) => {
+                    removed.push(category);
+                    start = i + 1;
+                }
+                None => break,
+            }
+        }
+
+        // Footer: trailing boilerplate lines, or everything from a block start
+        let mut end = lines.len();
+        let mut footer = Vec::new(); // Categories of the lines from `end`
+        let mut pending = Vec::new(); // Categories of lines below `end` not yet removed
+        let mut examined = 0;
+        let mut contiguous = true;
+        for i in (start..lines.len()).rev() {
+            if lines[i].trim().is_empty() {
+                continue;
+            }
+            if examined == self.max_lines {
+                break;
+            }
+            examined += 1;
+            match self.category(lines[i]) {
+                Some(category) if contiguous || BLOCK_STARTS.contains(&category) => {
+                    // Lines of the block that match no pattern count as its category
+                    footer.extend(
+                        pending
+                            .drain(..)
+                            .map(|line: Option<_>| line.unwrap_or(category)),
+                    );
+                    footer.push(category);
+                    end = i;
+                }
+                category => {
+                    contiguous &= category.is_some();
+                    pending.push(category);
+                }
+            }
+        }
+        removed.extend(footer);
+
+        if removed.is_empty() {
+            return None;
+        }
+        let kept = lines[start..end].join("\n");
+        Some((kept.trim_matches('\n').to_string(), removed))
+    }
+}
+
+impl Operator for StripBoilerplate {
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        let Some((stripped, removed)) = self.strip(text) else {
+            return Ok(Some(sample));
+        };
+        ctx.metrics().increment("stripped_documents", 1);
+        for category in removed {
+            ctx.metrics()
+                .increment(&format!("lines_removed.{}", category), 1);
+        }
+        if stripped.trim().is_empty() && self.drop_empty {
+            return Ok(None);
+        }
+        *text = stripped;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
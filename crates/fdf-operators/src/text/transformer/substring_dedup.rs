@@ -0,0 +1,298 @@
+use crate::text::hash_index::{read_meta, PassState, RepeatedHashes, ShardWriter};
+use crate::text::tokenizer::Tokenizer;
+use fdf_sdk::{
+    fdf_operator, ColumnSpec, Context, OpError, Result, Sample, StateHandle, Stateful,
+    StatefulOperator, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const HASH_BASE: u64 = 0x100000001b3; // Multiplier of the rolling window hash
+
+type Span = (usize, usize); // Byte range of a token
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Index,  // First pass: record the windows of every document in index_dir
+    Remove, // Second pass: remove the windows the index saw in several documents
+}
+
+/// Exact substring deduplication across documents (Lee et al., 2022): spans of at least
+/// `window` tokens occurring in several documents are removed. Runs as two pipelines over the
+/// same data: `mode: index` builds an on-disk index of hashed token windows, `mode: remove`
+/// removes the repeated spans using it.
+#[fdf_operator(
+    name = "text.substring_dedup",
+    category = "transformer",
+    build = "SubstringDedupConfig::build"
+)]
+struct SubstringDedupConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// "index" (first pass, documents pass through unchanged) or "remove" (second pass)
+    #[param(type = "string")]
+    mode: Mode,
+    /// Directory of the window index, written by the index pass when it succeeds and read by
+    /// the remove pass
+    index_dir: String,
+    /// Length in tokens of the shortest span removed
+    #[param(default = 50)]
+    window: usize,
+    /// Tokenizer: whitespace, chars, a tiktoken encoding or the path of a tokenizer.json
+    #[param(default = "whitespace")]
+    tokenizer: String,
+    /// A window is repeated when this many documents contain it
+    #[param(default = 2)]
+    min_documents: u32,
+    /// Drop documents left empty by the removal
+    #[param(default = true)]
+    drop_empty: bool,
+}
+
+impl SubstringDedupConfig {
+    fn build(self) -> Result<Stateful<SubstringDedup>> {
+        if self.window == 0 {
+            return Err(anyhow::anyhow!("{}: window must be positive", Self::NAME));
+        }
+        if self.min_documents < 2 {
+            return Err(anyhow::anyhow!(
+                "{}: min_documents must be at least 2",
+                Self::NAME
+            ));
+        }
+        Tokenizer::check(&self.tokenizer)
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", Self::NAME, e))?;
+        Ok(Stateful::new(SubstringDedup {
+            text_col: self.text_col,
+            mode: self.mode,
+            index_dir: PathBuf::from(self.index_dir),
+            meta: IndexMeta {
+                window: self.window,
+                tokenizer: self.tokenizer,
+                min_documents: self.min_documents,
+                documents: 0,
+            },
+            drop_empty: self.drop_empty,
+            tokenizer: None,
+        }))
+    }
+}
+
+/// Settings the index was built with, checked by the remove pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexMeta {
+    window: usize,
+    tokenizer: String,
+    min_documents: u32,
+    documents: u64, // Documents indexed
+}
+
+pub struct SubstringDedup {
+    text_col: String,
+    mode: Mode,
+    index_dir: PathBuf,
+    meta: IndexMeta,
+    drop_empty: bool,
+    tokenizer: Option<Tokenizer>, // Loaded in open
+}
+
+impl SubstringDedup {
+    /// Token spans (byte ranges, without leading whitespace) and window hashes of a text; hash i
+    /// covers tokens i..i + window
+    fn windows(&self, text: &str) -> Result<(Vec<Span>, Vec<u64>)> {
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let mut start = 0;
+        let mut spans = Vec::new();
+        let mut token_hashes = Vec::new();
+        for end in tokenizer.token_ends(text)? {
+            let token = text[start..end].trim_start();
+            if !token.is_empty() {
+                spans.push((end - token.len(), end));
+                token_hashes.push(xxhash_rust::xxh3::xxh3_64(token.as_bytes()));
+            }
+            start = end;
+        }
+
+        let window = self.meta.window;
+        if token_hashes.len() < window {
+            return Ok((spans, Vec::new()));
+        }
+        // Rolling polynomial hash over token hashes
+        let top_power = (1..window).fold(1u64, |power, _| power.wrapping_mul(HASH_BASE));
+        let mut hash = token_hashes[..window].iter().fold(0u64, |hash, &h| {
+            hash.wrapping_mul(HASH_BASE).wrapping_add(h)
+        });
+        let mut hashes = Vec::with_capacity(token_hashes.len() - window + 1);
+        hashes.push(hash);
+        for i in window..token_hashes.len() {
+            hash = hash
+                .wrapping_sub(token_hashes[i - window].wrapping_mul(top_power))
+                .wrapping_mul(HASH_BASE)
+                .wrapping_add(token_hashes[i]);
+            hashes.push(hash);
+        }
+        Ok((spans, hashes))
+    }
+
+    /// Text without the spans covered by repeated windows, and the number of bytes removed
+    fn remove(&self, repeated: &RepeatedHashes, text: &str) -> Result<Option<(String, usize)>> {
+        let (spans, hashes) = self.windows(text)?;
+        let mut covered = vec![false; spans.len()];
+        let mut any = false;
+        for (i, hash) in hashes.iter().enumerate() {
+            if repeated.contains(*hash) {
+                covered[i..i + self.meta.window].fill(true);
+                any = true;
+            }
+        }
+        if !any {
+            return Ok(None);
+        }
+
+        let mut out = String::with_capacity(text.len());
+        let mut kept_from = 0;
+        let mut i = 0;
+        while i < spans.len() {
+            if !covered[i] {
+                i += 1;
+                continue;
+            }
+            let run_start = spans[i].0;
+            while i < spans.len() && covered[i] {
+                i += 1;
+            }
+            let run_end = spans[i - 1].1;
+            out.push_str(text[kept_from..run_start].trim_end());
+            // Keep the removed span's line structure at the cut
+            let next = text[run_end..].trim_start();
+            if !out.is_empty() && !next.is_empty() {
+                let separator = if text[run_start..run_end].contains('\n') {
+                    "\n"
+                } else {
+                    " "
+                };
+                out.push_str(separator);
+            }
+            kept_from = text.len() - next.len();
+        }
+        out.push_str(&text[kept_from..]);
+        let removed = text.len() - out.len();
+        Ok(Some((out, removed)))
+    }
+}
+
+impl StatefulOperator for SubstringDedup {
+    type State = PassState;
+
+    fn open_state(&mut self, ctx: &Context) -> Result<PassState> {
+        let fatal =
+            |e: anyhow::Error| OpError::fatal(format!("{}: {:#}", SubstringDedupConfig::NAME, e));
+        self.tokenizer = Some(Tokenizer::load(&self.meta.tokenizer, ctx).map_err(fatal)?);
+        match self.mode {
+            Mode::Index => {
+                let dir = ctx.scratch_dir().join("substring_dedup").join(format!(
+                    "{:x}",
+                    xxhash_rust::xxh3::xxh3_64(self.index_dir.to_string_lossy().as_bytes())
+                ));
+                let writer = ShardWriter::create(&dir).map_err(fatal)?;
+                Ok(PassState::Index(Some(writer)))
+            }
+            Mode::Remove => {
+                let key = format!("substring_dedup:{}", self.index_dir.display());
+                let repeated = ctx
+                    .resources()
+                    .get_or_load(&key, || load_index(&self.index_dir, &self.meta))
+                    .map_err(fatal)?;
+                Ok(PassState::Remove(repeated))
+            }
+        }
+    }
+
+    fn process_with_state(
+        &self,
+        state: &StateHandle<PassState>,
+        mut sample: Sample,
+        ctx: &Context,
+    ) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        match self.mode {
+            Mode::Index => {
+                let (_, hashes) = self.windows(text)?;
+                // A document counts once per window, however often it repeats it
+                let distinct: HashSet<u64> = hashes.into_iter().collect();
+                let mut state = state.write();
+                let PassState::Index(Some(writer)) = &mut *state else {
+                    return Err(anyhow::anyhow!("Index already published"));
+                };
+                writer.add_document(distinct)?;
+                Ok(Some(sample))
+            }
+            Mode::Remove => {
+                let PassState::Remove(repeated) = &*state.read() else {
+                    unreachable!("the remove pass loads the index");
+                };
+                let Some((deduped, removed)) = self.remove(repeated, text)? else {
+                    return Ok(Some(sample));
+                };
+                ctx.metrics().increment("documents_changed", 1);
+                ctx.metrics().increment("removed_bytes", removed as u64);
+                if deduped.trim().is_empty() && self.drop_empty {
+                    return Ok(None);
+                }
+                *text = deduped;
+                Ok(Some(sample))
+            }
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+
+    fn finish_state(&self, state: &StateHandle<PassState>, _ctx: &Context) -> Result<Vec<Sample>> {
+        // The index is only published once every document was indexed
+        state
+            .write()
+            .publish(&self.index_dir, self.meta.min_documents, |documents| {
+                IndexMeta {
+                    documents,
+                    ..self.meta.clone()
+                }
+            })
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", SubstringDedupConfig::NAME, e))?;
+        Ok(Vec::new())
+    }
+
+    fn close_state(&mut self, state: &mut PassState) -> Result<()> {
+        // Left by a failed run: the partial index is not published
+        state.discard();
+        Ok(())
+    }
+}
+
+/// Repeated windows of the index in `index_dir`, checking it was built with the same settings
+fn load_index(index_dir: &Path, expected: &IndexMeta) -> Result<RepeatedHashes> {
+    let meta: IndexMeta = read_meta(index_dir)?;
+    if meta.window != expected.window
+        || meta.tokenizer != expected.tokenizer
+        || meta.min_documents != expected.min_documents
+    {
+        return Err(anyhow::anyhow!(
+            "Index in {} was built with window {}, tokenizer {} and min_documents {}; the \
+             remove pass must use the same settings",
+            index_dir.display(),
+            meta.window,
+            meta.tokenizer,
+            meta.min_documents
+        ));
+    }
+    RepeatedHashes::load(index_dir)
+}
@@ -0,0 +1,125 @@
+use crate::text::tokenizer::Tokenizer;
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Boundary {
+    Token,     // Right after the last token that fits
+    Sentence,  // After the last complete sentence that fits
+    Paragraph, // Before the last paragraph break (blank line) that fits
+}
+
+/// Truncates a text field to at most max_tokens tokens
+#[fdf_operator(
+    name = "text.truncate_tokens",
+    category = "transformer",
+    build = "TruncateTokensConfig::build"
+)]
+struct TruncateTokensConfig {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Maximum number of tokens kept
+    max_tokens: usize,
+    /// Tokenizer: whitespace, chars, a tiktoken encoding (cl100k_base, o200k_base, p50k_base,
+    /// r50k_base) or the path of a HuggingFace tokenizer.json
+    #[param(default = "whitespace")]
+    tokenizer: String,
+    /// Where the text is cut: "token", "sentence" or "paragraph"; falls back to the token
+    /// boundary when no sentence or paragraph end fits
+    #[param(default = "token", type = "string")]
+    boundary: Boundary,
+}
+
+impl TruncateTokensConfig {
+    fn build(self) -> Result<TruncateTokens> {
+        if self.max_tokens == 0 {
+            return Err(anyhow::anyhow!(
+                "{}: max_tokens must be positive",
+                Self::NAME
+            ));
+        }
+        Tokenizer::check(&self.tokenizer)
+            .map_err(|e| anyhow::anyhow!("{}: {:#}", Self::NAME, e))?;
+        Ok(TruncateTokens {
+            text_col: self.text_col,
+            max_tokens: self.max_tokens,
+            tokenizer_name: self.tokenizer,
+            boundary: self.boundary,
+            tokenizer: None,
+        })
+    }
+}
+
+pub struct TruncateTokens {
+    text_col: String,
+    max_tokens: usize,
+    tokenizer_name: String,
+    boundary: Boundary,
+    tokenizer: Option<Tokenizer>, // Loaded in open
+}
+
+impl TruncateTokens {
+    /// Byte length of the truncated text, or None if it fits
+    fn cut(&self, text: &str) -> Result<Option<usize>> {
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .expect("tokenizer is loaded in open");
+        let ends = tokenizer.token_ends(text)?;
+        if ends.len() <= self.max_tokens {
+            return Ok(None);
+        }
+        let end = ends[self.max_tokens - 1];
+        let boundary = match self.boundary {
+            Boundary::Token => None,
+            Boundary::Sentence => last_sentence_end(text, end),
+            Boundary::Paragraph => text[..end].rfind("\n\n"),
+        };
+        Ok(Some(boundary.unwrap_or(end)))
+    }
+}
+
+impl Operator for TruncateTokens {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        let tokenizer = Tokenizer::load(&self.tokenizer_name, ctx)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", TruncateTokensConfig::NAME, e)))?;
+        self.tokenizer = Some(tokenizer);
+        Ok(())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        if let Some(len) = self.cut(text)? {
+            text.truncate(len);
+            text.truncate(text.trim_end().len());
+            ctx.metrics().increment("truncated", 1);
+        }
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
+
+/// End (after the terminator) of the last sentence of `text` completed within `text[..end]`:
+/// a '.', '!' or '?' followed by whitespace, or a CJK full stop, exclamation or question mark
+fn last_sentence_end(text: &str, end: usize) -> Option<usize> {
+    let mut next = text[end..].chars().next();
+    for (i, c) in text[..end].char_indices().rev() {
+        let complete = match c {
+            '.' | '!' | '?' => next.is_some_and(char::is_whitespace),
+            '。' | '！' | '？' => true,
+            _ => false,
+        };
+        if complete {
+            return Some(i + c.len_utf8());
+        }
+        next = Some(c);
+    }
+    None
+}
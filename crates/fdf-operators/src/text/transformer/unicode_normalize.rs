@@ -0,0 +1,138 @@
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, Operator, Result, Sample, Value};
+use serde::Deserialize;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Form {
+    None,
+    Nfc,  // Canonical composition
+    Nfkc, // Compatibility composition (fullwidth forms, ligatures, nbsp...)
+    Nfd,
+    Nfkd,
+}
+
+/// Normalizes the Unicode form, punctuation, invisible characters and whitespace of a text field
+/// in place; each step can be turned off
+#[fdf_operator(name = "text.unicode_normalize", category = "transformer")]
+pub struct UnicodeNormalize {
+    /// Field holding the text (dot path allowed)
+    #[param(default = "text")]
+    text_col: String,
+    /// Unicode normalization form: none, nfc, nfkc, nfd or nfkd
+    #[param(default = "nfc", type = "string")]
+    form: Form,
+    /// Replace curly quotes, primes and dashes with their ASCII forms, and "…" with "..."
+    #[param(default = true)]
+    normalize_punctuation: bool,
+    /// Remove control characters (except tab and newline) and invisible characters (zero-width
+    /// space, word joiner, BOM, soft hyphen); CRLF and CR become newlines
+    #[param(default = true)]
+    remove_control: bool,
+    /// Collapse runs of spaces and tabs into one space, trim every line and keep at most one
+    /// blank line in a row
+    #[param(default = true)]
+    collapse_whitespace: bool,
+}
+
+impl UnicodeNormalize {
+    pub fn normalize(&self, text: &str) -> String {
+        let text: String = match self.form {
+            Form::None => text.to_string(),
+            Form::Nfc => text.nfc().collect(),
+            Form::Nfkc => text.nfkc().collect(),
+            Form::Nfd => text.nfd().collect(),
+            Form::Nfkd => text.nfkd().collect(),
+        };
+
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if self.remove_control {
+                if c == '\r' {
+                    if chars.peek() != Some(&'\n') {
+                        out.push('\n');
+                    }
+                    continue;
+                }
+                if is_invisible(c) {
+                    continue;
+                }
+            }
+            if self.normalize_punctuation {
+                if let Some(ascii) = ascii_punctuation(c) {
+                    out.push_str(ascii);
+                    continue;
+                }
+            }
+            out.push(c);
+        }
+
+        if self.collapse_whitespace {
+            collapse_whitespace(&out)
+        } else {
+            out
+        }
+    }
+}
+
+impl Operator for UnicodeNormalize {
+    fn process_with_context(&self, mut sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let Some(Value::String(text)) = sample.get_path_mut(&self.text_col) else {
+            return Err(anyhow::anyhow!("Missing text field: {}", self.text_col));
+        };
+        *text = self.normalize(text);
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        Some(ColumnSpec::new().requires(&self.text_col))
+    }
+}
+
+/// Control (but tab and newline) or invisible formatting character. Zero-width (non-)joiners
+/// are kept: they are meaningful in emoji sequences and Persian or Indic scripts.
+fn is_invisible(c: char) -> bool {
+    match c {
+        '\t' | '\n' => false,
+        '\u{200B}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' | '\u{180E}' => true,
+        '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => true, // Bidi
+        _ => c.is_control(),
+    }
+}
+
+fn ascii_punctuation(c: char) -> Option<&'static str> {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some("'"),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some("\""),
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
+        | '\u{2212}' => Some("-"),
+        '\u{2026}' => Some("..."),
+        _ => None,
+    }
+}
+
+/// Single spaces between words, no trailing spaces, at most one blank line in a row, trimmed
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.split('\n') {
+        let mut words = line
+            .split(|c: char| c.is_whitespace())
+            .filter(|w| !w.is_empty());
+        let Some(first) = words.next() else {
+            blank_lines += 1;
+            continue;
+        };
+        if !out.is_empty() {
+            out.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        blank_lines = 0;
+        out.push_str(first);
+        for word in words {
+            out.push(' ');
+            out.push_str(word);
+        }
+    }
+    out
+}
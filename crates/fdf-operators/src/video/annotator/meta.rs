@@ -0,0 +1,58 @@
+use crate::video::{check_ffprobe, probe_video, VideoInfo};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample};
+use std::time::Duration;
+
+/// Annotates videos with their duration, frame rate, resolution, codec and whether they have
+/// an audio track, probed with ffprobe. Videos that cannot be parsed or have no video stream
+/// fail with an error, sending the sample to the error output
+#[fdf_operator(name = "video.meta", category = "annotator")]
+pub struct VideoMeta {
+    /// Binary field holding the encoded video
+    #[param(default = "video")]
+    video_col: String,
+    /// String field holding the path of the video file, read instead of video_col if set
+    path_col: Option<String>,
+    /// Prefix of the fields written: <prefix>duration (seconds), <prefix>fps, <prefix>width,
+    /// <prefix>height, <prefix>codec ("h264", "hevc", "vp9", ...) and <prefix>has_audio
+    #[param(default = "video_")]
+    prefix: String,
+    /// ffprobe executable
+    #[param(default = "ffprobe")]
+    command: String,
+    /// Seconds after which ffprobe is killed, failing the video
+    #[param(default = 60)]
+    timeout: u64,
+}
+
+impl Operator for VideoMeta {
+    fn open(&mut self, _ctx: &Context) -> Result<()> {
+        check_ffprobe(&self.command)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", Self::NAME, e)).into())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let info = probe_video(
+            &sample,
+            &self.video_col,
+            self.path_col.as_deref(),
+            &self.command,
+            Duration::from_secs(self.timeout),
+        )?;
+        if let Some(duration) = info.duration {
+            ctx.metrics().observe("duration", duration);
+        }
+        ctx.metrics().increment(&format!("codec.{}", info.codec), 1);
+        info.write(&mut sample, &self.prefix)?;
+        Ok(Some(sample))
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        let input = self.path_col.as_ref().unwrap_or(&self.video_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(
+            VideoInfo::fields(&self.prefix)
+                .into_iter()
+                .fold(columns, ColumnSpec::produces),
+        )
+    }
+}
@@ -0,0 +1,178 @@
+use crate::video::{check_ffprobe, probe_video, VideoInfo};
+use fdf_sdk::{fdf_operator, ColumnSpec, Context, OpError, Operator, Result, Sample, Value};
+use std::time::Duration;
+
+/// Drops videos whose duration, frame rate, resolution or codec are out of bounds, to remove
+/// unusable clips before expensive decoding steps. The properties come from the fields
+/// written by video.meta when present, else are probed with ffprobe; videos whose duration or
+/// frame rate is unknown fail the bounds set on it
+#[fdf_operator(
+    name = "video.meta_filter",
+    category = "filter",
+    build = "MetaFilterConfig::build"
+)]
+struct MetaFilterConfig {
+    /// Binary field holding the encoded video
+    #[param(default = "video")]
+    video_col: String,
+    /// String field holding the path of the video file, read instead of video_col if set
+    path_col: Option<String>,
+    /// Prefix of the fields of video.meta, and of the fields written with annotate
+    #[param(default = "video_")]
+    prefix: String,
+    /// Minimum duration in seconds
+    min_duration: Option<f64>,
+    /// Maximum duration in seconds
+    max_duration: Option<f64>,
+    /// Minimum frame rate
+    min_fps: Option<f64>,
+    /// Maximum frame rate
+    max_fps: Option<f64>,
+    /// Minimum width in pixels
+    min_width: Option<u64>,
+    /// Minimum height in pixels
+    min_height: Option<u64>,
+    /// Codecs kept (FFmpeg names, e.g. [h264, hevc, vp9, av1]); any if unset
+    codecs: Option<Vec<String>>,
+    /// ffprobe executable
+    #[param(default = "ffprobe")]
+    command: String,
+    /// Seconds after which ffprobe is killed, failing the video
+    #[param(default = 60)]
+    timeout: u64,
+    /// Keep every video and write its properties (the fields of video.meta) and the failed
+    /// bound (<prefix>rejected_by, null if passed) instead of filtering
+    #[param(default = false)]
+    annotate: bool,
+}
+
+impl MetaFilterConfig {
+    fn build(self) -> Result<MetaFilter> {
+        let inverted = |min: Option<f64>, max: Option<f64>| matches!((min, max), (Some(min), Some(max)) if min > max);
+        if inverted(self.min_duration, self.max_duration) {
+            return Err(anyhow::anyhow!(
+                "{}: min_duration is above max_duration",
+                Self::NAME
+            ));
+        }
+        if inverted(self.min_fps, self.max_fps) {
+            return Err(anyhow::anyhow!("{}: min_fps is above max_fps", Self::NAME));
+        }
+        Ok(MetaFilter {
+            video_col: self.video_col,
+            path_col: self.path_col,
+            prefix: self.prefix,
+            min_duration: self.min_duration,
+            max_duration: self.max_duration,
+            min_fps: self.min_fps,
+            max_fps: self.max_fps,
+            min_width: self.min_width,
+            min_height: self.min_height,
+            codecs: self
+                .codecs
+                .map(|codecs| codecs.iter().map(|codec| codec.to_lowercase()).collect()),
+            command: self.command,
+            timeout: self.timeout,
+            annotate: self.annotate,
+        })
+    }
+}
+
+pub struct MetaFilter {
+    video_col: String,
+    path_col: Option<String>,
+    prefix: String,
+    min_duration: Option<f64>,
+    max_duration: Option<f64>,
+    min_fps: Option<f64>,
+    max_fps: Option<f64>,
+    min_width: Option<u64>,
+    min_height: Option<u64>,
+    codecs: Option<Vec<String>>,
+    command: String,
+    timeout: u64,
+    annotate: bool,
+}
+
+impl MetaFilter {
+    /// Bound a video with these properties fails, if any
+    pub fn reject(&self, info: &VideoInfo) -> Option<&'static str> {
+        let below = |value: Option<f64>, min: Option<f64>| {
+            min.is_some_and(|min| value.is_none_or(|value| value < min))
+        };
+        let above = |value: Option<f64>, max: Option<f64>| {
+            max.is_some_and(|max| value.is_none_or(|value| value > max))
+        };
+        if let Some(codecs) = &self.codecs {
+            if !codecs.contains(&info.codec.to_lowercase()) {
+                return Some("codec");
+            }
+        }
+        if self.min_width.is_some_and(|min| info.width < min) {
+            Some("min_width")
+        } else if self.min_height.is_some_and(|min| info.height < min) {
+            Some("min_height")
+        } else if below(info.duration, self.min_duration) {
+            Some("min_duration")
+        } else if above(info.duration, self.max_duration) {
+            Some("max_duration")
+        } else if below(info.fps, self.min_fps) {
+            Some("min_fps")
+        } else if above(info.fps, self.max_fps) {
+            Some("max_fps")
+        } else {
+            None
+        }
+    }
+}
+
+impl Operator for MetaFilter {
+    fn open(&mut self, _ctx: &Context) -> Result<()> {
+        check_ffprobe(&self.command)
+            .map_err(|e| OpError::fatal(format!("{}: {:#}", MetaFilterConfig::NAME, e)).into())
+    }
+
+    fn process_with_context(&self, mut sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let info = match VideoInfo::from_fields(&sample, &self.prefix) {
+            Some(info) => info,
+            None => probe_video(
+                &sample,
+                &self.video_col,
+                self.path_col.as_deref(),
+                &self.command,
+                Duration::from_secs(self.timeout),
+            )?,
+        };
+        let rejected = self.reject(&info);
+
+        if self.annotate {
+            info.write(&mut sample, &self.prefix)?;
+            sample.set_path(
+                &format!("{}rejected_by", self.prefix),
+                rejected.map_or(Value::Null, Value::from),
+            )?;
+            return Ok(Some(sample));
+        }
+        match rejected {
+            Some(bound) => {
+                ctx.metrics().increment(&format!("rejected.{}", bound), 1);
+                Ok(None)
+            }
+            None => Ok(Some(sample)),
+        }
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        // The fields of video.meta are used when present, not required
+        let input = self.path_col.as_ref().unwrap_or(&self.video_col);
+        let columns = ColumnSpec::new().requires(input);
+        Some(if self.annotate {
+            VideoInfo::fields(&self.prefix)
+                .into_iter()
+                .fold(columns, ColumnSpec::produces)
+                .produces(format!("{}rejected_by", self.prefix))
+        } else {
+            columns
+        })
+    }
+}
@@ -1,5 +1,7 @@
+pub mod meta;
+
 use fdf_sdk::OperatorRegistry;
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register video filters when implemented
+pub fn register(registry: &mut OperatorRegistry) {
+    meta::register(registry);
 }
@@ -1,12 +1,310 @@
+//! Video operators
+//!
+//! Video is read from a binary field holding the encoded file (`video_col`, e.g. a Parquet
+//! `Binary` column), or from the file named by a string field (`path_col`) when it is set.
+//! Containers and codecs are probed with the `ffprobe` command of FFmpeg, which must be
+//! installed; binary fields are piped to its standard input. Probes running longer than the
+//! operator's `timeout` are killed, so that corrupt inputs cannot stall a run.
+
 pub mod annotator;
 pub mod filter;
 pub mod transformer;
 
-use fdf_sdk::OperatorRegistry;
+use fdf_sdk::{OperatorRegistry, Result, Sample, Value};
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+pub fn register(registry: &mut OperatorRegistry) {
+    transformer::register(registry);
+    filter::register(registry);
+    annotator::register(registry);
+}
+
+/// Properties of the first video stream of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoInfo {
+    pub duration: Option<f64>, // Seconds, of the container (else of the stream)
+    pub fps: Option<f64>,      // Average frame rate
+    pub width: u64,
+    pub height: u64,
+    pub codec: String, // FFmpeg codec name ("h264", "hevc", "vp9", "av1", ...)
+    pub has_audio: bool,
+}
+
+impl VideoInfo {
+    /// Properties written by video.meta with this prefix, if the sample has them
+    pub fn from_fields(sample: &Sample, prefix: &str) -> Option<Self> {
+        let field = |name: &str| sample.get_path(&format!("{}{}", prefix, name));
+        Some(VideoInfo {
+            duration: field("duration").and_then(Value::as_f64),
+            fps: field("fps").and_then(Value::as_f64),
+            width: field("width")?.as_u64()?,
+            height: field("height")?.as_u64()?,
+            codec: field("codec")?.as_str()?.to_string(),
+            has_audio: field("has_audio").and_then(Value::as_bool)?,
+        })
+    }
+
+    /// Write the properties to <prefix>duration, <prefix>fps, <prefix>width,
+    /// <prefix>height, <prefix>codec and <prefix>has_audio
+    pub fn write(&self, sample: &mut Sample, prefix: &str) -> Result<()> {
+        let optional = |value: Option<f64>| value.map_or(Value::Null, Value::from);
+        let fields = [
+            ("duration", optional(self.duration)),
+            ("fps", optional(self.fps)),
+            ("width", Value::from(self.width)),
+            ("height", Value::from(self.height)),
+            ("codec", Value::from(self.codec.as_str())),
+            ("has_audio", Value::from(self.has_audio)),
+        ];
+        for (name, value) in fields {
+            sample.set_path(&format!("{}{}", prefix, name), value)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the fields written
+    pub fn fields(prefix: &str) -> Vec<String> {
+        ["duration", "fps", "width", "height", "codec", "has_audio"]
+            .iter()
+            .map(|name| format!("{}{}", prefix, name))
+            .collect()
+    }
+}
+
+/// Check that the ffprobe command runs
+pub fn check_ffprobe(command: &str) -> Result<()> {
+    let output = Command::new(command)
+        .arg("-version")
+        .output()
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Cannot run {} (install FFmpeg or set command): {}",
+                command,
+                e
+            )
+        })?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} -version failed ({})",
+            command,
+            output.status
+        ));
+    }
+    Ok(())
+}
+
+/// Properties of the video of a sample: the file named by the string field `path_col` if
+/// set, else the binary field `video_col`, probed with the ffprobe `command` within `timeout`
+pub fn probe_video(
+    sample: &Sample,
+    video_col: &str,
+    path_col: Option<&str>,
+    command: &str,
+    timeout: Duration,
+) -> Result<VideoInfo> {
+    match path_col {
+        Some(path_col) => {
+            let path = sample
+                .get_path(path_col)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Missing path field: {}", path_col))?;
+            ffprobe(command, path.as_ref(), None, timeout)
+        }
+        None => {
+            let bytes = sample
+                .get_bytes(video_col)
+                .ok_or_else(|| anyhow::anyhow!("Missing video field: {}", video_col))?;
+            // MP4 files with the index at their end are read up to it from the pipe
+            ffprobe(command, "pipe:0".as_ref(), Some(bytes), timeout)
+        }
+    }
+}
+
+/// Contents of a pipe, up to its end
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buffer);
+    }
+    buffer
+}
+
+/// Wait for a child process, killing it once `timeout` has passed
+fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Properties of a video read from the JSON output of ffprobe: the file `input`, or `stdin`
+/// piped to it (`input` is then "pipe:0")
+fn ffprobe(
+    command: &str,
+    input: &OsStr,
+    stdin: Option<&[u8]>,
+    timeout: Duration,
+) -> Result<VideoInfo> {
+    let mut child = Command::new(command)
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(input)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Cannot run {}: {}", command, e))?;
+
+    // The pipes are served by threads, so that ffprobe never blocks on a full pipe
+    let (status, stdout, stderr) = std::thread::scope(|scope| {
+        if let (Some(bytes), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            // ffprobe stops reading once it has the streams: the write then fails, which is fine
+            scope.spawn(move || {
+                let _ = pipe.write_all(bytes);
+            });
+        }
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        let stdout = scope.spawn(move || read_all(stdout));
+        let stderr = scope.spawn(move || read_all(stderr));
+        let status = wait_timeout(&mut child, timeout);
+        (
+            status,
+            stdout.join().unwrap_or_default(),
+            stderr.join().unwrap_or_default(),
+        )
+    });
+    let status =
+        status?.ok_or_else(|| anyhow::anyhow!("{} timed out after {:?}", command, timeout))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Unsupported video ({}): {}",
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+    let probed: Value = serde_json::from_slice(&stdout)
+        .map_err(|e| anyhow::anyhow!("Invalid {} output: {}", command, e))?;
+
+    let streams = probed
+        .get("streams")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let is_type =
+        |stream: &Value, kind: &str| stream.get("codec_type").and_then(Value::as_str) == Some(kind);
+    // Cover art of audio files is a video stream of one attached picture
+    let video = streams
+        .iter()
+        .find(|stream| {
+            let attached = stream
+                .pointer("/disposition/attached_pic")
+                .and_then(Value::as_u64);
+            is_type(stream, "video") && attached != Some(1)
+        })
+        .ok_or_else(|| anyhow::anyhow!("Video has no video stream"))?;
+
+    // ffprobe writes durations as strings and frame rates as fractions ("30000/1001")
+    let seconds = |value: Option<&Value>| value?.as_str()?.parse::<f64>().ok();
+    let duration = seconds(probed.pointer("/format/duration")).or(seconds(video.get("duration")));
+    let rate = |name: &str| {
+        let (numerator, denominator) = video.get(name)?.as_str()?.split_once('/')?;
+        let (numerator, denominator) = (
+            numerator.parse::<f64>().ok()?,
+            denominator.parse::<f64>().ok()?,
+        );
+        (numerator > 0.0 && denominator > 0.0).then(|| numerator / denominator)
+    };
+    let dimension = |name: &str| video.get(name).and_then(Value::as_u64).unwrap_or(0);
+    Ok(VideoInfo {
+        duration,
+        fps: rate("avg_frame_rate").or_else(|| rate("r_frame_rate")),
+        width: dimension("width"),
+        height: dimension("height"),
+        codec: video
+            .get("codec_name")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string(),
+        has_audio: streams.iter().any(|stream| is_type(stream, "audio")),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use fdf_sdk::testing::SampleBuilder;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    /// ffprobe stand-in answering by the first 4 bytes piped to it
+    fn fake_probe(test: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("fdf-fakeprobe-{}-{}", test, std::process::id()));
+        let script = r#"#!/bin/sh
+for last; do :; done
+[ "$last" = "pipe:0" ] || exit 2
+case "$(head -c 4)" in
+good) echo '{"streams":[{"codec_type":"video","codec_name":"h264","width":640,"height":360,"avg_frame_rate":"25/1"}],"format":{"duration":"4.0"}}';;
+hang) exec sleep 10;;
+*) echo "Invalid data found when processing input" >&2; exit 1;;
+esac
+"#;
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn probe(command: &Path, bytes: &[u8], timeout: Duration) -> Result<VideoInfo> {
+        let sample = SampleBuilder::new().bytes("video", bytes).build();
+        probe_video(&sample, "video", None, &command.to_string_lossy(), timeout)
+    }
+
+    #[test]
+    fn binary_fields_are_piped() {
+        let command = fake_probe("pipe");
+        // ffprobe stops reading early: the rest of a large input is not written
+        let mut bytes = b"good".to_vec();
+        bytes.resize(4 << 20, 0);
+        let info = probe(&command, &bytes, Duration::from_secs(10)).unwrap();
+        assert_eq!((info.width, info.height), (640, 360));
+        assert_eq!(info.codec, "h264");
+        assert_eq!(info.fps, Some(25.0));
+        assert_eq!(info.duration, Some(4.0));
+        assert!(!info.has_audio);
+
+        let error = probe(&command, b"junk", Duration::from_secs(10)).unwrap_err();
+        assert!(error.to_string().contains("Invalid data"));
+        let _ = std::fs::remove_file(&command);
+    }
 
-pub fn register(_registry: &mut OperatorRegistry) {
-    // TODO: Register video operators when implemented
-    // transformer::register(registry);
-    // filter::register(registry);
-    // annotator::register(registry);
+    #[test]
+    fn probes_are_killed_after_the_timeout() {
+        let command = fake_probe("timeout");
+        let started = Instant::now();
+        let error = probe(&command, b"hang", Duration::from_millis(200)).unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let _ = std::fs::remove_file(&command);
+    }
 }
@@ -0,0 +1,27 @@
+use crate::Result;
+use serde::de::DeserializeOwned;
+
+/// Deserialize an operator's YAML config into a typed struct
+///
+/// Errors name the operator and the problem reported by serde: missing fields, unknown fields
+/// (with `#[serde(deny_unknown_fields)]`) and values of the wrong type. An empty config
+/// (`- common.add_id:`) is read as an empty mapping, so structs with all-default fields still parse.
+pub fn parse_config<T: DeserializeOwned>(operator: &str, config: &serde_yaml::Value) -> Result<T> {
+    let config = match config {
+        serde_yaml::Value::Null => serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+        other => other.clone(),
+    };
+    serde_path_to_error::deserialize(config).map_err(|e| {
+        let path = e.path().to_string();
+        if path == "." {
+            anyhow::anyhow!("Invalid config for operator '{}': {}", operator, e.inner())
+        } else {
+            anyhow::anyhow!(
+                "Invalid config for operator '{}': field '{}': {}",
+                operator,
+                path,
+                e.inner()
+            )
+        }
+    })
+}
@@ -1,7 +1,157 @@
-// Context is kept for backward compatibility but is not used in the new architecture
-// Operators in the new architecture don't need context
+use crate::{Metrics, Result};
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-#[derive(Default, Clone)]
+/// Run-wide information and shared resources, passed to operators in `open` and
+/// `process_with_context`
+///
+/// Cloning is cheap and clones share the same resource cache.
+#[derive(Clone)]
 pub struct Context {
-    // Empty - not used in new architecture
+    run_id: String,
+    seed: u64,            // Pipeline-level seed (spec `seed`)
+    scratch_dir: PathBuf, // Per-run directory for temporary files, removed after the run
+    resources: ResourceCache,
+    metrics: Metrics, // Custom metrics of the operator this context was given to
+}
+
+impl Context {
+    pub fn new(run_id: impl Into<String>, seed: u64, scratch_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            run_id: run_id.into(),
+            seed,
+            scratch_dir: scratch_dir.into(),
+            resources: ResourceCache::default(),
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Same run, recording into `metrics`; the engine gives each operator its own
+    pub fn with_metrics(&self, metrics: Metrics) -> Self {
+        Self {
+            metrics,
+            ..self.clone()
+        }
+    }
+
+    /// Unique id of the current run
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Seed for a named random stream, derived from the pipeline seed
+    /// Operators should use their own name so they do not draw the same numbers.
+    pub fn seed_for(&self, name: &str) -> u64 {
+        xxhash_rust::xxh3::xxh3_64_with_seed(name.as_bytes(), self.seed)
+    }
+
+    /// Random number generator for a named stream (see `seed_for`)
+    pub fn rng(&self, name: &str) -> SeededRng {
+        SeededRng::new(self.seed_for(name))
+    }
+
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch_dir
+    }
+
+    /// Cache of resources (models, dictionaries) shared by all operators of the run
+    pub fn resources(&self) -> &ResourceCache {
+        &self.resources
+    }
+
+    /// Custom counters and histograms of the current operator
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new("local", 0, std::env::temp_dir().join("fdf"))
+    }
+}
+
+type Slot = Arc<Mutex<Option<Arc<dyn Any + Send + Sync>>>>;
+
+/// Resources loaded once per run and shared by key, e.g. one fasttext model used by several
+/// operators
+#[derive(Clone, Default)]
+pub struct ResourceCache {
+    slots: Arc<Mutex<HashMap<String, Slot>>>,
+}
+
+impl ResourceCache {
+    /// Return the resource stored under `key`, loading it with `load` on first use
+    /// Concurrent callers for the same key wait for a single load; other keys load in parallel.
+    /// A failed load is not cached, so a later call tries again.
+    pub fn get_or_load<T, F>(&self, key: &str, load: F) -> Result<Arc<T>>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> Result<T>,
+    {
+        let slot = self
+            .slots
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        let mut slot = slot.lock().unwrap();
+        let resource = match slot.as_ref() {
+            Some(resource) => resource.clone(),
+            None => slot.insert(Arc::new(load()?)).clone(),
+        };
+        resource.downcast::<T>().map_err(|_| {
+            anyhow::anyhow!(
+                "Resource '{}' is cached with a different type than {}",
+                key,
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Whether a resource is cached under `key`
+    pub fn contains(&self, key: &str) -> bool {
+        let slot = self.slots.lock().unwrap().get(key).cloned();
+        slot.is_some_and(|slot| slot.lock().unwrap().is_some())
+    }
+}
+
+/// Small deterministic random number generator (SplitMix64)
+/// Not cryptographically secure; meant for reproducible sampling and shuffling.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // top 53 bits -> f64
+        ((self.next_u64() >> 11) as f64) * (1.0 / ((1u64 << 53) as f64))
+    }
+
+    /// Uniform integer in [0, n); `n` must be greater than 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
 }
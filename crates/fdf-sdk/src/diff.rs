@@ -0,0 +1,149 @@
+//! Field-level differences between two samples, e.g. what one pipeline step changed
+
+use crate::{Sample, Value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Differences from one sample to another, keyed by dot path
+///
+/// Nested objects are compared field by field (`meta.lang`), arrays and other values as a
+/// whole. Only new values are kept: the old ones are those of the `before` sample, so a chain
+/// of diffs (one per pipeline step) stores each value once. Binary fields are only listed by
+/// name in `binary_changed`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SampleDiff {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub added: BTreeMap<String, Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub changed: BTreeMap<String, Value>, // Modified fields with their new values
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub binary_changed: Vec<String>, // Binary fields added, removed or modified
+}
+
+impl SampleDiff {
+    /// Differences from `before` to `after`
+    pub fn between(before: &Sample, after: &Sample) -> Self {
+        let mut diff = Self::default();
+        diff_values("", before.as_value(), after.as_value(), &mut diff);
+
+        let (old, new) = (before.binary_fields(), after.binary_fields());
+        for key in old
+            .keys()
+            .chain(new.keys().filter(|k| !old.contains_key(*k)))
+        {
+            if old.get(key) != new.get(key) {
+                diff.binary_changed.push(key.clone());
+            }
+        }
+        diff.removed.sort();
+        diff.binary_changed.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.binary_changed.is_empty()
+    }
+
+    /// Apply the differences to the `before` sample, producing `after` (binary fields excepted)
+    pub fn apply(&self, sample: &mut Sample) -> crate::Result<()> {
+        for path in &self.removed {
+            sample.remove_path(path);
+        }
+        for (path, value) in &self.added {
+            sample.set_path(path, value.clone())?;
+        }
+        for (path, value) in &self.changed {
+            sample.set_path(path, value.clone())?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_values(path: &str, before: &Value, after: &Value, diff: &mut SampleDiff) {
+    match (before, after) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let field = join(path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_values(&field, old_value, new_value, diff),
+                    None => diff.removed.push(field),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    diff.added.insert(join(path, key), new_value.clone());
+                }
+            }
+        }
+        _ if before != after => {
+            diff.changed.insert(path.to_string(), after.clone());
+        }
+        _ => {}
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{sample, SampleBuilder};
+    use serde_json::json;
+
+    #[test]
+    fn nested_fields_are_compared_by_path() {
+        let before = sample(json!({"text": "a", "meta": {"lang": "en", "score": 1}, "tags": [1]}));
+        let after = sample(json!({"text": "a", "meta": {"lang": "de"}, "tags": [1, 2], "id": 7}));
+        let diff = SampleDiff::between(&before, &after);
+
+        assert_eq!(diff.added, BTreeMap::from([("id".to_string(), json!(7))]));
+        assert_eq!(diff.removed, ["meta.score"]);
+        assert_eq!(
+            diff.changed,
+            BTreeMap::from([
+                ("meta.lang".to_string(), json!("de")),
+                ("tags".to_string(), json!([1, 2]))
+            ])
+        );
+        assert!(diff.binary_changed.is_empty());
+    }
+
+    #[test]
+    fn apply_reproduces_the_new_sample() {
+        let before = sample(json!({"a": 1, "b": {"c": "x", "d": null}, "e": [1]}));
+        let after = sample(json!({"a": 2, "b": {"c": "y"}, "f": {"g": true}}));
+        let diff = SampleDiff::between(&before, &after);
+
+        let mut rebuilt = before.clone();
+        diff.apply(&mut rebuilt).unwrap();
+        assert_eq!(rebuilt.as_value(), after.as_value());
+        assert!(SampleDiff::between(&after, &rebuilt).is_empty());
+    }
+
+    #[test]
+    fn binary_fields_are_listed_by_name() {
+        let before = SampleBuilder::new()
+            .bytes("same", [1])
+            .bytes("edited", [1])
+            .bytes("dropped", [1])
+            .build();
+        let after = SampleBuilder::new()
+            .bytes("same", [1])
+            .bytes("edited", [2])
+            .bytes("new", [1])
+            .build();
+        let diff = SampleDiff::between(&before, &after);
+        assert_eq!(diff.binary_changed, ["dropped", "edited", "new"]);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::Sample;
+use std::fmt;
+
+/// Operator error telling the engine how to handle a failure
+///
+/// Operators return it through `anyhow`, e.g. `Err(OpError::fatal("model file missing"))?`,
+/// possibly with added context. Errors of any other type are handled like `SampleInvalid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpError {
+    /// This sample cannot be processed: it goes to the error output and the run continues
+    SampleInvalid(String),
+    /// Temporary failure (timeout, rate limit): the sample is retried up to `max_retries`
+    /// times (spec), then handled like `SampleInvalid`
+    Transient(String),
+    /// No sample can be processed (e.g. a model file is missing): the run is aborted
+    Fatal(String),
+}
+
+impl OpError {
+    pub fn sample_invalid(message: impl Into<String>) -> Self {
+        Self::SampleInvalid(message.into())
+    }
+
+    pub fn transient(message: impl Into<String>) -> Self {
+        Self::Transient(message.into())
+    }
+
+    pub fn fatal(message: impl Into<String>) -> Self {
+        Self::Fatal(message.into())
+    }
+
+    /// The `OpError` in an error's chain (outermost first), if any
+    pub fn find(error: &anyhow::Error) -> Option<&OpError> {
+        error.chain().find_map(|e| e.downcast_ref::<OpError>())
+    }
+
+    pub fn is_fatal(error: &anyhow::Error) -> bool {
+        matches!(Self::find(error), Some(Self::Fatal(_)))
+    }
+
+    pub fn is_transient(error: &anyhow::Error) -> bool {
+        matches!(Self::find(error), Some(Self::Transient(_)))
+    }
+}
+
+impl fmt::Display for OpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SampleInvalid(message) => write!(f, "invalid sample: {}", message),
+            Self::Transient(message) => write!(f, "transient error: {}", message),
+            Self::Fatal(message) => write!(f, "fatal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for OpError {}
+
+/// Failure of a batch of samples held by an operator (Operator::buffers), returned by the
+/// call that processed the batch (`flat_map` or `finish`)
+///
+/// Every sample of the batch goes to the error output with the message and the run
+/// continues, like `OpError::SampleInvalid` for a single sample.
+#[derive(Debug)]
+pub struct BatchError {
+    pub message: String,
+    pub samples: Vec<Sample>, // The inputs of the batch
+}
+
+impl BatchError {
+    pub fn new(message: impl Into<String>, samples: Vec<Sample>) -> Self {
+        Self {
+            message: message.into(),
+            samples,
+        }
+    }
+
+    /// The `BatchError` in an error's chain, if any
+    pub fn find(error: &anyhow::Error) -> Option<&BatchError> {
+        error.chain().find_map(|e| e.downcast_ref::<BatchError>())
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "batch of {} samples failed: {}",
+            self.samples.len(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for BatchError {}
@@ -1,22 +1,68 @@
-pub mod base;
+pub mod config;
 pub mod context;
+pub mod diff;
+pub mod error;
+pub mod metadata;
+pub mod metrics;
 pub mod micropartition;
 pub mod op;
-pub mod record;
+pub mod plugin;
 pub mod registry;
 pub mod sample;
+pub mod sampling;
+pub mod stateful;
+pub mod testing;
 
 // Main exports
-pub use op::{Operator, OperatorFactory};
+pub use context::{Context, ResourceCache, SeededRng};
+pub use diff::SampleDiff;
+pub use error::{BatchError, OpError};
+pub use metadata::{OperatorCategory, OperatorMetadata, ParamSpec};
+pub use metrics::{MetricSink, MetricValue, Metrics};
+pub use op::{ColumnSpec, Operator, OperatorFactory};
 pub use registry::OperatorRegistry;
 pub use sample::Sample;
+pub use stateful::{StateHandle, Stateful, StatefulOperator};
 // Re-export serde_json::Value for convenience
 pub use serde_json::Value;
 
-// Deprecated exports (kept for backward compatibility - will be removed)
-#[allow(deprecated)]
-pub use base::{BaseAnnotator, BaseFilter, BaseTransformer};
-pub use context::Context;
-
 // Re-export anyhow for convenience
 pub use anyhow::{Error, Result};
+
+/// Declare an operator: derives the config deserialization and generates its `register` function
+///
+/// The struct is both the YAML config and the operator (or, with `build = "path"`, the input
+/// of a `fn(Self) -> Result<impl Operator>` that validates it or builds the operator). Params
+/// are listed from the fields: name, type from the Rust type (`Option<T>` is an optional `T`),
+/// description from the field doc comment. `#[param(default = ...)]` sets a default used both
+/// for deserialization and in the metadata; `#[serde(skip)]` fields are not params.
+///
+/// ```ignore
+/// /// Keeps samples whose text length (in characters) is within bounds
+/// #[fdf_operator(name = "text.len_filter", category = "filter", alias = "text_len_filter")]
+/// pub struct TextLenFilter {
+///     /// Field holding the text (dot path allowed)
+///     text_col: String,
+///     /// Minimum length kept
+///     lower_bound: Option<u32>,
+/// }
+///
+/// impl Operator for TextLenFilter { /* ... */ }
+/// ```
+///
+/// Attribute arguments: `name` (required), `category` (`"filter"`, `"transformer"` or
+/// `"annotator"`), `description` (defaults to the struct doc comment), `version`, `alias`
+/// (repeatable) and `build`.
+pub use fdf_macros::fdf_operator;
+
+// Used by code generated by #[fdf_operator]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+
+    /// Default value of a `#[param(default = ...)]` field, given as YAML
+    pub fn default_from_yaml<T: serde::de::DeserializeOwned>(yaml: &str) -> T {
+        serde_yaml::from_str(yaml)
+            .unwrap_or_else(|e| panic!("Invalid #[param(default)] value '{}': {}", yaml, e))
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of operator, as used in the docs and catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatorCategory {
+    Filter,      // Keeps or drops samples
+    Transformer, // Modifies existing fields
+    Annotator,   // Adds new fields
+}
+
+impl OperatorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Filter => "filter",
+            Self::Transformer => "transformer",
+            Self::Annotator => "annotator",
+        }
+    }
+}
+
+/// One config parameter of an operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParamSpec {
+    pub name: String,
+    pub param_type: String, // e.g. "string", "int", "float", "bool", "list<string>"
+    pub required: bool,
+    pub default: Option<String>, // Default value as written in YAML
+    pub description: String,
+}
+
+impl ParamSpec {
+    pub fn required(name: &str, param_type: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            required: true,
+            default: None,
+            description: description.to_string(),
+        }
+    }
+
+    pub fn optional(name: &str, param_type: &str, description: &str) -> Self {
+        Self {
+            required: false,
+            ..Self::required(name, param_type, description)
+        }
+    }
+
+    pub fn with_default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+}
+
+/// Version of operators registered without one
+pub const DEFAULT_OPERATOR_VERSION: &str = "1";
+
+/// Description of a registered operator, queryable from the registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperatorMetadata {
+    pub description: String,
+    pub category: Option<OperatorCategory>,
+    pub params: Vec<ParamSpec>,
+    #[serde(default = "default_version")]
+    pub version: String, // Bumped when the operator's output changes; part of the pipeline fingerprint
+    #[serde(default)]
+    pub aliases: Vec<String>, // Former names, still accepted in pipelines
+}
+
+fn default_version() -> String {
+    DEFAULT_OPERATOR_VERSION.to_string()
+}
+
+impl Default for OperatorMetadata {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            category: None,
+            params: Vec::new(),
+            version: default_version(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// Handle returned by OperatorRegistry::register to attach metadata to the registration
+pub struct Registration<'a> {
+    metadata: &'a mut OperatorMetadata,
+}
+
+impl<'a> Registration<'a> {
+    pub(crate) fn new(metadata: &'a mut OperatorMetadata) -> Self {
+        Self { metadata }
+    }
+
+    pub fn description(self, description: &str) -> Self {
+        self.metadata.description = description.to_string();
+        self
+    }
+
+    pub fn category(self, category: OperatorCategory) -> Self {
+        self.metadata.category = Some(category);
+        self
+    }
+
+    pub fn param(self, param: ParamSpec) -> Self {
+        self.metadata.params.push(param);
+        self
+    }
+
+    /// Other name the operator can be used by, typically its name before a rename
+    /// Names registered as operators take precedence over aliases.
+    pub fn alias(self, alias: &str) -> Self {
+        self.metadata.aliases.push(alias.to_string());
+        self
+    }
+
+    /// Operator version, e.g. "2" or "1.1.0" (default "1")
+    /// Bump it whenever the same config produces different output, so pipeline fingerprints change.
+    pub fn version(self, version: &str) -> Self {
+        self.metadata.version = version.to_string();
+        self
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Current value of a custom metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MetricValue {
+    Counter {
+        value: u64,
+    },
+    Histogram {
+        count: u64,
+        sum: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl MetricValue {
+    /// Mean of the observed values (histograms only)
+    pub fn mean(&self) -> Option<f64> {
+        match self {
+            Self::Histogram { count, sum, .. } if *count > 0 => Some(sum / *count as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Receiver of the updates of a Metrics created with `Metrics::forwarding`
+pub trait MetricSink: Send + Sync {
+    fn increment(&self, name: &str, by: u64);
+    fn observe(&self, name: &str, value: f64);
+}
+
+/// Named counters and histograms recorded by one operator, e.g. "urls_redacted" or
+/// "perplexity"; reported with the operator's statistics after the run
+///
+/// Clones share the same values. A name keeps the kind it was first recorded with:
+/// observing a counter or incrementing a histogram is ignored.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    values: Arc<Mutex<BTreeMap<String, MetricValue>>>,
+    sink: Option<Arc<dyn MetricSink>>, // Also receives every update
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics")
+            .field("values", &self.values)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Metrics {
+    /// Metrics also passing every update to `sink`, e.g. to the host of a plugin
+    pub fn forwarding(sink: Arc<dyn MetricSink>) -> Self {
+        Self {
+            values: Arc::default(),
+            sink: Some(sink),
+        }
+    }
+
+    /// Add `by` to a counter
+    pub fn increment(&self, name: &str, by: u64) {
+        if let Some(sink) = &self.sink {
+            sink.increment(name, by);
+        }
+        let mut values = self.values.lock().unwrap();
+        let metric = values
+            .entry(name.to_string())
+            .or_insert(MetricValue::Counter { value: 0 });
+        if let MetricValue::Counter { value } = metric {
+            *value += by;
+        }
+    }
+
+    /// Record a value in a histogram (count, sum, min and max are kept); NaN is ignored
+    pub fn observe(&self, name: &str, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        if let Some(sink) = &self.sink {
+            sink.observe(name, value);
+        }
+        let mut values = self.values.lock().unwrap();
+        let metric = values
+            .entry(name.to_string())
+            .or_insert(MetricValue::Histogram {
+                count: 0,
+                sum: 0.0,
+                min: value,
+                max: value,
+            });
+        if let MetricValue::Histogram {
+            count,
+            sum,
+            min,
+            max,
+        } = metric
+        {
+            *count += 1;
+            *sum += value;
+            *min = min.min(value);
+            *max = max.max(value);
+        }
+    }
+
+    /// Current values of all metrics, by name
+    pub fn snapshot(&self) -> BTreeMap<String, MetricValue> {
+        self.values.lock().unwrap().clone()
+    }
+}
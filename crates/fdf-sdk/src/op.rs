@@ -1,11 +1,85 @@
-use crate::{Result, Sample};
+use crate::{Context, Result, Sample};
 
 /// Operator trait - unified interface for all operators
 /// Returns:
 /// - Some(sample) if the sample should be kept (may be modified)
 /// - None if the sample should be filtered out
+///
+/// `open` is called once before the first sample (e.g. to load a model) and `close` once
+/// after the last one (e.g. to flush buffers or report final stats), also when the run fails.
+/// The engine calls `flat_map`, which defaults to `process_with_context`, the method every
+/// operator implements; it receives the run's Context (seed, shared resources, metrics).
+/// `process` runs it with a default Context, for callers without a run. Override
+/// `flat_map` to emit several samples per input (chunking, sentence splitting, archive
+/// extraction); each emitted sample runs through the following steps. Operators that buffer
+/// samples across inputs (packing, batching) emit the rest in `finish`.
 pub trait Operator: Send + Sync {
-    fn process(&self, sample: Sample) -> Result<Option<Sample>>;
+    fn open(&mut self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+
+    /// `process_with_context` with a default Context
+    fn process(&self, sample: Sample) -> Result<Option<Sample>> {
+        self.process_with_context(sample, &Context::default())
+    }
+
+    fn process_with_context(&self, sample: Sample, ctx: &Context) -> Result<Option<Sample>>;
+
+    /// Columns (dot paths) the operator requires and produces, checked when the pipeline is
+    /// compiled. `None` means undeclared: any column may exist after this step, so the
+    /// following steps are not checked.
+    fn columns(&self) -> Option<ColumnSpec> {
+        None
+    }
+
+    /// Samples produced from `sample`, in order; an empty list drops it
+    fn flat_map(&self, sample: Sample, ctx: &Context) -> Result<Vec<Sample>> {
+        Ok(self
+            .process_with_context(sample, ctx)?
+            .into_iter()
+            .collect())
+    }
+
+    /// Samples emitted once the input is exhausted, before `close`; they run through the
+    /// following steps. Steps finish in order, after the samples emitted by earlier steps
+    fn finish(&self, _ctx: &Context) -> Result<Vec<Sample>> {
+        Ok(Vec::new())
+    }
+
+    /// Whether the operator holds samples back and emits their results with later inputs or
+    /// in `finish` (batching). An input producing no sample is then not counted, traced or
+    /// written to rejected outputs as removed; the step is counted as removing the inputs it
+    /// did not emit a sample for, and its outputs are not traced as changes of an input
+    fn buffers(&self) -> bool {
+        false
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Columns read and written by an operator (see Operator::columns)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub requires: Vec<String>, // Must be in the source or produced by an earlier step
+    pub produces: Vec<String>, // Added or overwritten
+}
+
+impl ColumnSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn requires(mut self, column: impl Into<String>) -> Self {
+        self.requires.push(column.into());
+        self
+    }
+
+    pub fn produces(mut self, column: impl Into<String>) -> Self {
+        self.produces.push(column.into());
+        self
+    }
 }
 
 /// Factory for creating operators from config
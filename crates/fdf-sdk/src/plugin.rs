@@ -0,0 +1,675 @@
+//! Native operator plugins loaded from shared libraries
+//!
+//! A plugin is a `cdylib` exporting one C function, `fdf_plugin_v1`, that returns a table
+//! of function pointers (FdfPluginV1). Everything crossing the boundary is plain bytes:
+//! operator configs and samples are JSON, errors are UTF-8 messages. Plugins therefore do
+//! not need the same compiler or crate versions as the host, only the same ABI_VERSION.
+//!
+//! Rust plugins register their operators as usual and export them with `export_plugin!`:
+//!
+//! ```ignore
+//! fn register(registry: &mut fdf_sdk::OperatorRegistry) -> fdf_sdk::Result<()> {
+//!     registry.register_typed("acme.pii_filter", |config: PiiConfig| Ok(Box::new(PiiFilter::new(config))));
+//!     Ok(())
+//! }
+//!
+//! fdf_sdk::export_plugin!(register);
+//! ```
+//!
+//! Binary fields of a sample are not passed to plugin operators; the host keeps them and
+//! puts them back on the samples returned for it (not on the samples of `finish`).
+//!
+//! Plugin operators get the run's Context in `open`: run id, seed and scratch directory, and
+//! metrics recorded through callbacks into the host. Their resource cache is their own.
+
+use crate::{
+    Context, MetricSink, Metrics, Operator, OperatorMetadata, OperatorRegistry, Result, Sample,
+};
+use serde::{Deserialize, Serialize};
+use std::ffi::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Version of the plugin ABI, bumped on any change to FdfPluginV1 or its payloads
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the function every plugin exports: `extern "C" fn() -> *const FdfPluginV1`
+pub const ENTRY_SYMBOL: &str = "fdf_plugin_v1";
+
+pub const STATUS_OK: i32 = 0; // Call succeeded (process: sample kept and written to `out`)
+pub const STATUS_DROPPED: i32 = 1; // process: sample filtered out; buffers: operator buffers
+pub const STATUS_ERROR: i32 = -1; // Error message written to the buffer argument
+
+/// Bytes allocated by the plugin; the host copies them and hands the buffer to `free_buffer`
+#[repr(C)]
+pub struct FdfBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub cap: usize,
+}
+
+impl FdfBuffer {
+    pub fn empty() -> Self {
+        Self {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = std::mem::ManuallyDrop::new(bytes);
+        Self {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            cap: bytes.capacity(),
+        }
+    }
+
+    /// # Safety
+    /// The buffer must come from `from_vec` (or be empty) and be used only once
+    pub unsafe fn into_vec(self) -> Vec<u8> {
+        if self.ptr.is_null() {
+            return Vec::new();
+        }
+        Vec::from_raw_parts(self.ptr, self.len, self.cap)
+    }
+}
+
+/// Run information passed to `open`, as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginContext {
+    pub run_id: String,
+    pub seed: u64,
+    pub scratch_dir: PathBuf,
+}
+
+/// Metrics callbacks of the host, passed to `open`; `host` stays valid until the handle is
+/// destroyed and the callbacks may be called from several threads
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FdfMetricsV1 {
+    pub host: *mut c_void,
+    pub increment:
+        unsafe extern "C" fn(host: *mut c_void, name: *const u8, name_len: usize, by: u64),
+    pub observe:
+        unsafe extern "C" fn(host: *mut c_void, name: *const u8, name_len: usize, value: f64),
+}
+
+/// Function table exported by a plugin
+///
+/// Handles are opaque pointers owned by the plugin. `process` and `flat_map` may be called
+/// from several threads at the same time, like Operator::flat_map; the other calls are never
+/// concurrent for one handle.
+#[repr(C)]
+pub struct FdfPluginV1 {
+    pub abi_version: u32, // Must stay the first field so any version can be checked
+    /// Writes a JSON array of PluginOperatorInfo to `out`
+    pub operators: unsafe extern "C" fn(out: *mut FdfBuffer) -> i32,
+    /// Creates an operator from its name and JSON config, writing its handle to `handle`
+    pub create: unsafe extern "C" fn(
+        name: *const u8,
+        name_len: usize,
+        config: *const u8,
+        config_len: usize,
+        handle: *mut *mut c_void,
+        error: *mut FdfBuffer,
+    ) -> i32,
+    /// Opens the operator with the run's PluginContext (JSON) and the host's metrics
+    pub open: unsafe extern "C" fn(
+        handle: *mut c_void,
+        context: *const u8,
+        context_len: usize,
+        metrics: *const FdfMetricsV1,
+        error: *mut FdfBuffer,
+    ) -> i32,
+    /// Processes one JSON sample, writing the kept sample (or an error) to `out`
+    pub process: unsafe extern "C" fn(
+        handle: *mut c_void,
+        sample: *const u8,
+        sample_len: usize,
+        out: *mut FdfBuffer,
+    ) -> i32,
+    /// Processes one JSON sample, writing a JSON array of the samples produced (or an error)
+    /// to `out`
+    pub flat_map: unsafe extern "C" fn(
+        handle: *mut c_void,
+        sample: *const u8,
+        sample_len: usize,
+        out: *mut FdfBuffer,
+    ) -> i32,
+    /// Writes a JSON array of the samples emitted once the input is exhausted to `out`
+    pub finish: unsafe extern "C" fn(handle: *mut c_void, out: *mut FdfBuffer) -> i32,
+    /// STATUS_DROPPED if the operator buffers samples (Operator::buffers), else STATUS_OK
+    pub buffers: unsafe extern "C" fn(handle: *mut c_void) -> i32,
+    pub close: unsafe extern "C" fn(handle: *mut c_void, error: *mut FdfBuffer) -> i32,
+    pub destroy: unsafe extern "C" fn(handle: *mut c_void),
+    pub free_buffer: unsafe extern "C" fn(buffer: FdfBuffer),
+}
+
+/// Operator provided by a plugin, as listed by FdfPluginV1::operators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginOperatorInfo {
+    pub name: String,
+    #[serde(default)]
+    pub metadata: OperatorMetadata,
+}
+
+/// Export the operators registered by `$register` (fn(&mut OperatorRegistry) -> Result<()>)
+/// as a plugin; use once in a `cdylib` crate
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:path) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn fdf_plugin_v1() -> *const $crate::plugin::FdfPluginV1 {
+            use $crate::plugin::{guest, FdfBuffer, FdfPluginV1};
+
+            static REGISTRY: ::std::sync::OnceLock<
+                ::std::result::Result<$crate::OperatorRegistry, String>,
+            > = ::std::sync::OnceLock::new();
+
+            fn registry() -> &'static ::std::result::Result<$crate::OperatorRegistry, String> {
+                REGISTRY.get_or_init(|| {
+                    let mut registry = $crate::OperatorRegistry::new();
+                    $register(&mut registry)
+                        .map(|_| registry)
+                        .map_err(|e| e.to_string())
+                })
+            }
+
+            unsafe extern "C" fn operators(out: *mut FdfBuffer) -> i32 {
+                guest::operators(registry(), out)
+            }
+
+            unsafe extern "C" fn create(
+                name: *const u8,
+                name_len: usize,
+                config: *const u8,
+                config_len: usize,
+                handle: *mut *mut ::std::ffi::c_void,
+                error: *mut FdfBuffer,
+            ) -> i32 {
+                guest::create(
+                    registry(),
+                    name,
+                    name_len,
+                    config,
+                    config_len,
+                    handle,
+                    error,
+                )
+            }
+
+            static PLUGIN: FdfPluginV1 = FdfPluginV1 {
+                abi_version: $crate::plugin::ABI_VERSION,
+                operators,
+                create,
+                open: guest::open,
+                process: guest::process,
+                flat_map: guest::flat_map,
+                finish: guest::finish,
+                buffers: guest::buffers,
+                close: guest::close,
+                destroy: guest::destroy,
+                free_buffer: guest::free_buffer,
+            };
+            &PLUGIN
+        }
+    };
+}
+
+/// Plugin-side implementation of the function table, used by `export_plugin!`
+#[doc(hidden)]
+pub mod guest {
+    use super::*;
+
+    /// Operator with the Context of its run, set in `open`
+    struct Handle {
+        operator: Box<dyn Operator>,
+        ctx: Context,
+    }
+
+    /// Metrics recorded into the host through its callbacks
+    struct HostMetrics(FdfMetricsV1);
+
+    // The host's metrics are thread-safe and outlive the handle
+    unsafe impl Send for HostMetrics {}
+    unsafe impl Sync for HostMetrics {}
+
+    impl MetricSink for HostMetrics {
+        fn increment(&self, name: &str, by: u64) {
+            unsafe { (self.0.increment)(self.0.host, name.as_ptr(), name.len(), by) }
+        }
+
+        fn observe(&self, name: &str, value: f64) {
+            unsafe { (self.0.observe)(self.0.host, name.as_ptr(), name.len(), value) }
+        }
+    }
+
+    unsafe fn read_sample(sample: *const u8, sample_len: usize) -> Result<Sample> {
+        let value: serde_json::Value =
+            serde_json::from_slice(std::slice::from_raw_parts(sample, sample_len))?;
+        Sample::from_value(value)
+            .ok_or_else(|| anyhow::anyhow!("Sample passed to plugin is not an object"))
+    }
+
+    fn write_samples(samples: &[Sample]) -> Result<FdfBuffer> {
+        let values: Vec<&serde_json::Value> = samples.iter().map(Sample::as_value).collect();
+        Ok(FdfBuffer::from_vec(serde_json::to_vec(&values)?))
+    }
+
+    /// Run `f`, turning errors and panics into STATUS_ERROR with the message in `error`
+    unsafe fn call(error: *mut FdfBuffer, f: impl FnOnce() -> Result<i32>) -> i32 {
+        let message = match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(Ok(status)) => return status,
+            Ok(Err(e)) => format!("{:#}", e),
+            Err(panic) => match panic.downcast_ref::<&str>() {
+                Some(s) => format!("Plugin operator panicked: {}", s),
+                None => match panic.downcast_ref::<String>() {
+                    Some(s) => format!("Plugin operator panicked: {}", s),
+                    None => "Plugin operator panicked".to_string(),
+                },
+            },
+        };
+        *error = FdfBuffer::from_vec(message.into_bytes());
+        STATUS_ERROR
+    }
+
+    pub unsafe fn operators(
+        registry: &std::result::Result<OperatorRegistry, String>,
+        out: *mut FdfBuffer,
+    ) -> i32 {
+        call(out, || {
+            let registry = registry.as_ref().map_err(|e| anyhow::anyhow!("{}", e))?;
+            let operators: Vec<PluginOperatorInfo> = registry
+                .list()
+                .into_iter()
+                .map(|(name, metadata)| PluginOperatorInfo {
+                    name: name.to_string(),
+                    metadata: metadata.clone(),
+                })
+                .collect();
+            *out = FdfBuffer::from_vec(serde_json::to_vec(&operators)?);
+            Ok(STATUS_OK)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn create(
+        registry: &std::result::Result<OperatorRegistry, String>,
+        name: *const u8,
+        name_len: usize,
+        config: *const u8,
+        config_len: usize,
+        handle: *mut *mut c_void,
+        error: *mut FdfBuffer,
+    ) -> i32 {
+        call(error, || {
+            let registry = registry.as_ref().map_err(|e| anyhow::anyhow!("{}", e))?;
+            let name = std::str::from_utf8(std::slice::from_raw_parts(name, name_len))?;
+            let config: serde_yaml::Value =
+                serde_json::from_slice(std::slice::from_raw_parts(config, config_len))?;
+            let operator = Handle {
+                operator: registry.build(name, &config)?,
+                ctx: Context::default(),
+            };
+            *handle = Box::into_raw(Box::new(operator)) as *mut c_void;
+            Ok(STATUS_OK)
+        })
+    }
+
+    pub unsafe extern "C" fn open(
+        handle: *mut c_void,
+        context: *const u8,
+        context_len: usize,
+        metrics: *const FdfMetricsV1,
+        error: *mut FdfBuffer,
+    ) -> i32 {
+        call(error, || {
+            let context: PluginContext =
+                serde_json::from_slice(std::slice::from_raw_parts(context, context_len))?;
+            let handle = &mut *(handle as *mut Handle);
+            handle.ctx = Context::new(context.run_id, context.seed, context.scratch_dir)
+                .with_metrics(Metrics::forwarding(Arc::new(HostMetrics(*metrics))));
+            handle.operator.open(&handle.ctx)?;
+            Ok(STATUS_OK)
+        })
+    }
+
+    pub unsafe extern "C" fn process(
+        handle: *mut c_void,
+        sample: *const u8,
+        sample_len: usize,
+        out: *mut FdfBuffer,
+    ) -> i32 {
+        call(out, || {
+            let sample = read_sample(sample, sample_len)?;
+            let handle = &*(handle as *const Handle);
+            match handle.operator.process_with_context(sample, &handle.ctx)? {
+                Some(sample) => {
+                    *out = FdfBuffer::from_vec(serde_json::to_vec(sample.as_value())?);
+                    Ok(STATUS_OK)
+                }
+                None => Ok(STATUS_DROPPED),
+            }
+        })
+    }
+
+    pub unsafe extern "C" fn flat_map(
+        handle: *mut c_void,
+        sample: *const u8,
+        sample_len: usize,
+        out: *mut FdfBuffer,
+    ) -> i32 {
+        call(out, || {
+            let sample = read_sample(sample, sample_len)?;
+            let handle = &*(handle as *const Handle);
+            *out = write_samples(&handle.operator.flat_map(sample, &handle.ctx)?)?;
+            Ok(STATUS_OK)
+        })
+    }
+
+    pub unsafe extern "C" fn finish(handle: *mut c_void, out: *mut FdfBuffer) -> i32 {
+        call(out, || {
+            let handle = &*(handle as *const Handle);
+            *out = write_samples(&handle.operator.finish(&handle.ctx)?)?;
+            Ok(STATUS_OK)
+        })
+    }
+
+    pub unsafe extern "C" fn buffers(handle: *mut c_void) -> i32 {
+        match (*(handle as *const Handle)).operator.buffers() {
+            true => STATUS_DROPPED,
+            false => STATUS_OK,
+        }
+    }
+
+    pub unsafe extern "C" fn close(handle: *mut c_void, error: *mut FdfBuffer) -> i32 {
+        call(error, || {
+            (*(handle as *mut Handle)).operator.close()?;
+            Ok(STATUS_OK)
+        })
+    }
+
+    pub unsafe extern "C" fn destroy(handle: *mut c_void) {
+        drop(Box::from_raw(handle as *mut Handle));
+    }
+
+    pub unsafe extern "C" fn free_buffer(buffer: FdfBuffer) {
+        drop(buffer.into_vec());
+    }
+}
+
+/// Loaded plugin library; kept alive as long as operators created from it exist
+struct LoadedPlugin {
+    path: String,
+    api: *const FdfPluginV1, // Points into the library
+    _library: libloading::Library,
+}
+
+// The function table is immutable and plugin functions are required to be thread-safe
+unsafe impl Send for LoadedPlugin {}
+unsafe impl Sync for LoadedPlugin {}
+
+impl LoadedPlugin {
+    fn api(&self) -> &FdfPluginV1 {
+        unsafe { &*self.api }
+    }
+
+    /// Copy a buffer written by the plugin and release it
+    fn take(&self, buffer: FdfBuffer) -> Vec<u8> {
+        if buffer.ptr.is_null() {
+            return Vec::new();
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(buffer.ptr, buffer.len) }.to_vec();
+        unsafe { (self.api().free_buffer)(buffer) };
+        bytes
+    }
+
+    /// Turn a status and its buffer into a result
+    fn check(&self, status: i32, buffer: FdfBuffer) -> Result<Vec<u8>> {
+        let bytes = self.take(buffer);
+        if status == STATUS_ERROR {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&bytes)));
+        }
+        Ok(bytes)
+    }
+
+    fn create(self: &Arc<Self>, name: &str, config: &serde_yaml::Value) -> Result<PluginOperator> {
+        let config = serde_json::to_vec(config)?;
+        let mut handle = std::ptr::null_mut();
+        let mut error = FdfBuffer::empty();
+        let status = unsafe {
+            (self.api().create)(
+                name.as_ptr(),
+                name.len(),
+                config.as_ptr(),
+                config.len(),
+                &mut handle,
+                &mut error,
+            )
+        };
+        self.check(status, error)?;
+        if handle.is_null() {
+            return Err(anyhow::anyhow!(
+                "Plugin '{}' returned no operator for '{}'",
+                self.path,
+                name
+            ));
+        }
+        Ok(PluginOperator {
+            plugin: self.clone(),
+            handle,
+            metrics: Box::default(),
+        })
+    }
+}
+
+/// Operator implemented by a plugin
+struct PluginOperator {
+    plugin: Arc<LoadedPlugin>,
+    handle: *mut c_void,
+    metrics: Box<Metrics>, // Metrics of the run, recorded by the plugin through host callbacks
+}
+
+unsafe impl Send for PluginOperator {}
+unsafe impl Sync for PluginOperator {}
+
+unsafe extern "C" fn host_increment(host: *mut c_void, name: *const u8, name_len: usize, by: u64) {
+    let name = String::from_utf8_lossy(std::slice::from_raw_parts(name, name_len));
+    (*(host as *const Metrics)).increment(&name, by);
+}
+
+unsafe extern "C" fn host_observe(host: *mut c_void, name: *const u8, name_len: usize, value: f64) {
+    let name = String::from_utf8_lossy(std::slice::from_raw_parts(name, name_len));
+    (*(host as *const Metrics)).observe(&name, value);
+}
+
+impl PluginOperator {
+    /// Samples of a JSON array returned by the plugin, with the binary fields of `input`
+    fn read_samples(&self, bytes: &[u8], input: Option<&Sample>) -> Result<Vec<Sample>> {
+        let values: Vec<serde_json::Value> = serde_json::from_slice(bytes)?;
+        values
+            .into_iter()
+            .map(|value| {
+                let mut sample = Sample::from_value(value).ok_or_else(|| {
+                    anyhow::anyhow!("Plugin operator returned a sample that is not an object")
+                })?;
+                if let Some(input) = input {
+                    restore_binary_fields(input, &mut sample);
+                }
+                Ok(sample)
+            })
+            .collect()
+    }
+}
+
+/// Binary fields never leave the host: put those of `input` back unless they were replaced
+fn restore_binary_fields(input: &Sample, output: &mut Sample) {
+    for (key, bytes) in input.binary_fields() {
+        if output.get(key).is_none() {
+            output.set_bytes(key.clone(), bytes.clone());
+        }
+    }
+}
+
+impl Operator for PluginOperator {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        *self.metrics = ctx.metrics().clone();
+        let context = serde_json::to_vec(&PluginContext {
+            run_id: ctx.run_id().to_string(),
+            seed: ctx.seed(),
+            scratch_dir: ctx.scratch_dir().to_path_buf(),
+        })?;
+        let metrics = FdfMetricsV1 {
+            host: &*self.metrics as *const Metrics as *mut c_void,
+            increment: host_increment,
+            observe: host_observe,
+        };
+        let mut error = FdfBuffer::empty();
+        let status = unsafe {
+            (self.plugin.api().open)(
+                self.handle,
+                context.as_ptr(),
+                context.len(),
+                &metrics,
+                &mut error,
+            )
+        };
+        self.plugin.check(status, error).map(|_| ())
+    }
+
+    fn process_with_context(&self, sample: Sample, _ctx: &Context) -> Result<Option<Sample>> {
+        let input = serde_json::to_vec(sample.as_value())?;
+        let mut out = FdfBuffer::empty();
+        let status = unsafe {
+            (self.plugin.api().process)(self.handle, input.as_ptr(), input.len(), &mut out)
+        };
+        let output = self.plugin.check(status, out)?;
+        if status == STATUS_DROPPED {
+            return Ok(None);
+        }
+
+        let value: serde_json::Value = serde_json::from_slice(&output)?;
+        let mut result = Sample::from_value(value).ok_or_else(|| {
+            anyhow::anyhow!("Plugin operator returned a sample that is not an object")
+        })?;
+        restore_binary_fields(&sample, &mut result);
+        Ok(Some(result))
+    }
+
+    fn flat_map(&self, sample: Sample, _ctx: &Context) -> Result<Vec<Sample>> {
+        let input = serde_json::to_vec(sample.as_value())?;
+        let mut out = FdfBuffer::empty();
+        let status = unsafe {
+            (self.plugin.api().flat_map)(self.handle, input.as_ptr(), input.len(), &mut out)
+        };
+        let output = self.plugin.check(status, out)?;
+        self.read_samples(&output, Some(&sample))
+    }
+
+    fn finish(&self, _ctx: &Context) -> Result<Vec<Sample>> {
+        let mut out = FdfBuffer::empty();
+        let status = unsafe { (self.plugin.api().finish)(self.handle, &mut out) };
+        let output = self.plugin.check(status, out)?;
+        self.read_samples(&output, None)
+    }
+
+    fn buffers(&self) -> bool {
+        unsafe { (self.plugin.api().buffers)(self.handle) == STATUS_DROPPED }
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let mut error = FdfBuffer::empty();
+        let status = unsafe { (self.plugin.api().close)(self.handle, &mut error) };
+        self.plugin.check(status, error).map(|_| ())
+    }
+}
+
+impl Drop for PluginOperator {
+    fn drop(&mut self) {
+        unsafe { (self.plugin.api().destroy)(self.handle) };
+    }
+}
+
+/// Load a plugin library and register its operators; returns the registered names
+/// Plugin operators may not replace operators that are already registered.
+pub fn load_plugin(path: &str, registry: &mut OperatorRegistry) -> Result<Vec<String>> {
+    // Loading runs the library's initializers: plugins are trusted code, like the spec itself
+    let library = unsafe { libloading::Library::new(path) }
+        .map_err(|e| anyhow::anyhow!("Failed to load plugin '{}': {}", path, e))?;
+    let api = unsafe {
+        let entry = library
+            .get::<extern "C" fn() -> *const FdfPluginV1>(ENTRY_SYMBOL.as_bytes())
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Plugin '{}' does not export '{}': {}",
+                    path,
+                    ENTRY_SYMBOL,
+                    e
+                )
+            })?;
+        entry()
+    };
+    if api.is_null() {
+        return Err(anyhow::anyhow!(
+            "Plugin '{}' returned no function table",
+            path
+        ));
+    }
+    let abi_version = unsafe { (*api).abi_version };
+    if abi_version != ABI_VERSION {
+        return Err(anyhow::anyhow!(
+            "Plugin '{}' uses ABI version {}, expected {}",
+            path,
+            abi_version,
+            ABI_VERSION
+        ));
+    }
+
+    let plugin = Arc::new(LoadedPlugin {
+        path: path.to_string(),
+        api,
+        _library: library,
+    });
+
+    let mut out = FdfBuffer::empty();
+    let status = unsafe { (plugin.api().operators)(&mut out) };
+    let operators: Vec<PluginOperatorInfo> = serde_json::from_slice(
+        &plugin
+            .check(status, out)
+            .map_err(|e| anyhow::anyhow!("Plugin '{}' failed to list operators: {}", path, e))?,
+    )?;
+
+    let mut names = Vec::with_capacity(operators.len());
+    for info in operators {
+        if registry.metadata(&info.name).is_some() {
+            return Err(anyhow::anyhow!(
+                "Plugin '{}' provides operator '{}', which is already registered",
+                path,
+                info.name
+            ));
+        }
+
+        let plugin = plugin.clone();
+        let name = info.name.clone();
+        let mut registration = registry
+            .register(&info.name, move |config| {
+                Ok(Box::new(plugin.create(&name, config)?) as Box<dyn Operator>)
+            })
+            .description(&info.metadata.description)
+            .version(&info.metadata.version);
+        if let Some(category) = info.metadata.category {
+            registration = registration.category(category);
+        }
+        for param in info.metadata.params {
+            registration = registration.param(param);
+        }
+        for alias in &info.metadata.aliases {
+            registration = registration.alias(alias);
+        }
+        names.push(info.name);
+    }
+    Ok(names)
+}
@@ -1,3 +0,0 @@
-// V1 Record implementation - kept for backward compatibility
-// This will be replaced by op_v2::Record in the new architecture
-// TODO: Remove when migration is complete
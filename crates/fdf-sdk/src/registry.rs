@@ -1,12 +1,18 @@
+use crate::metadata::{OperatorMetadata, Registration};
 use crate::{Operator, OperatorFactory, Result};
+use serde::de::DeserializeOwned;
 use serde_yaml::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Registry for operators
+///
+/// Operator names are namespaced by modality with a dot: `common.*`, `text.*`, `image.*`,
+/// and e.g. `acme.*` for plugins. Renamed operators keep their old names as aliases.
 #[derive(Default)]
 pub struct OperatorRegistry {
     factories: HashMap<String, Arc<dyn OperatorFactory>>,
+    metadata: HashMap<String, OperatorMetadata>, // Description, category and params per operator
 }
 
 impl OperatorRegistry {
@@ -14,7 +20,8 @@ impl OperatorRegistry {
         Self::default()
     }
 
-    pub fn register<F>(&mut self, name: &str, factory: F)
+    /// Register an operator factory; chain calls on the returned handle to describe it
+    pub fn register<F>(&mut self, name: &str, factory: F) -> Registration<'_>
     where
         F: Fn(&Value) -> Result<Box<dyn Operator>> + Send + Sync + 'static,
     {
@@ -30,12 +37,80 @@ impl OperatorRegistry {
 
         self.factories
             .insert(name.to_string(), Arc::new(FactoryFn(factory)));
+        let metadata = self.metadata.entry(name.to_string()).or_default();
+        *metadata = OperatorMetadata::default();
+        Registration::new(metadata)
     }
 
+    /// Register an operator whose YAML config deserializes into `C` (see config::parse_config)
+    pub fn register_typed<C, F>(&mut self, name: &str, factory: F) -> Registration<'_>
+    where
+        C: DeserializeOwned,
+        F: Fn(C) -> Result<Box<dyn Operator>> + Send + Sync + 'static,
+    {
+        let operator = name.to_string();
+        self.register(name, move |config: &Value| {
+            factory(crate::config::parse_config(&operator, config)?)
+        })
+    }
+
+    /// Metadata of a registered operator, by name or alias
+    pub fn metadata(&self, name: &str) -> Option<&OperatorMetadata> {
+        self.metadata.get(self.resolve(name)?)
+    }
+
+    /// Registered name of an operator given its name or one of its aliases
+    pub fn resolve<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.factories.contains_key(name) {
+            return Some(name);
+        }
+        self.metadata
+            .iter()
+            .find(|(_, metadata)| metadata.aliases.iter().any(|alias| alias == name))
+            .map(|(registered, _)| registered.as_str())
+    }
+
+    /// Registered operators with their metadata, sorted by name (aliases are in the metadata)
+    pub fn list(&self) -> Vec<(&str, &OperatorMetadata)> {
+        let mut entries: Vec<_> = self
+            .metadata
+            .iter()
+            .map(|(name, metadata)| (name.as_str(), metadata))
+            .collect();
+        entries.sort_by_key(|(name, _)| *name);
+        entries
+    }
+
+    /// Operators of a namespace, e.g. `list_namespace("text")` for `text.*`, sorted by name
+    pub fn list_namespace(&self, namespace: &str) -> Vec<(&str, &OperatorMetadata)> {
+        self.list()
+            .into_iter()
+            .filter(|(name, _)| OperatorRegistry::namespace(name) == Some(namespace))
+            .collect()
+    }
+
+    /// Namespaces with at least one operator, sorted
+    pub fn namespaces(&self) -> Vec<&str> {
+        let mut namespaces: Vec<&str> = self
+            .metadata
+            .keys()
+            .filter_map(|name| OperatorRegistry::namespace(name))
+            .collect();
+        namespaces.sort();
+        namespaces.dedup();
+        namespaces
+    }
+
+    /// Namespace of an operator name: the part before the first dot (`text` for `text.len_filter`)
+    pub fn namespace(name: &str) -> Option<&str> {
+        name.split_once('.').map(|(namespace, _)| namespace)
+    }
+
+    /// Create an operator by name or alias
     pub fn build(&self, name: &str, config: &Value) -> Result<Box<dyn Operator>> {
         let factory = self
-            .factories
-            .get(name)
+            .resolve(name)
+            .and_then(|name| self.factories.get(name))
             .ok_or_else(|| anyhow::anyhow!("Unknown operator: {}", name))?;
         factory.create(config)
     }
@@ -1,26 +1,34 @@
 use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Sample is a wrapper around serde_json::Value
 /// It represents a JSON object (one row of data)
+/// Binary fields (image bytes, audio buffers) are kept next to the JSON object as raw bytes,
+/// since JSON has no bytes type. A field name is either a JSON field or a binary field.
 #[derive(Clone, Debug)]
-pub struct Sample(pub Value);
+pub struct Sample(pub Value, BTreeMap<String, Vec<u8>>);
 
 impl Sample {
     /// Create a new empty JSON object
     pub fn new() -> Self {
-        Self(Value::Object(serde_json::Map::new()))
+        Self::from_map(serde_json::Map::new())
     }
 
     /// Create from a JSON Value
     pub fn from_value(value: Value) -> Option<Self> {
         if value.is_object() {
-            Some(Sample(value))
+            Some(Sample(value, BTreeMap::new()))
         } else {
             None
         }
     }
 
-    /// Convert to JSON Value
+    /// Create from a JSON object
+    pub fn from_map(map: serde_json::Map<String, Value>) -> Self {
+        Self(Value::Object(map), BTreeMap::new())
+    }
+
+    /// Convert to JSON Value (binary fields are dropped)
     pub fn into_value(self) -> Value {
         self.0
     }
@@ -78,49 +86,60 @@ impl Sample {
         }
     }
 
+    /// Get the raw bytes of a binary field
+    pub fn get_bytes(&self, k: &str) -> Option<&[u8]> {
+        self.1.get(k).map(Vec::as_slice)
+    }
+
+    /// All binary fields, ordered by name
+    pub fn binary_fields(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.1
+    }
+
     // --- setters ---
     pub fn set_str(&mut self, k: impl Into<String>, v: impl Into<String>) {
-        if let Value::Object(ref mut map) = self.0 {
-            map.insert(k.into(), Value::String(v.into()));
-        }
+        self.set_value(k, Value::String(v.into()));
     }
 
     pub fn set_i64(&mut self, k: impl Into<String>, v: i64) {
-        if let Value::Object(ref mut map) = self.0 {
-            map.insert(k.into(), Value::Number(v.into()));
-        }
+        self.set_value(k, Value::Number(v.into()));
     }
 
     pub fn set_f64(&mut self, k: impl Into<String>, v: f64) {
-        if let Value::Object(ref mut map) = self.0 {
-            map.insert(
-                k.into(),
-                Value::Number(
-                    serde_json::Number::from_f64(v).unwrap_or(serde_json::Number::from(0)),
-                ),
-            );
-        }
+        self.set_value(
+            k,
+            Value::Number(serde_json::Number::from_f64(v).unwrap_or(serde_json::Number::from(0))),
+        );
     }
 
     pub fn set_bool(&mut self, k: impl Into<String>, v: bool) {
-        if let Value::Object(ref mut map) = self.0 {
-            map.insert(k.into(), Value::Bool(v));
-        }
+        self.set_value(k, Value::Bool(v));
     }
 
     pub fn set_null(&mut self, k: impl Into<String>) {
+        self.set_value(k, Value::Null);
+    }
+
+    pub fn set_value(&mut self, k: impl Into<String>, v: Value) {
         if let Value::Object(ref mut map) = self.0 {
-            map.insert(k.into(), Value::Null);
+            let k = k.into();
+            self.1.remove(&k);
+            map.insert(k, v);
         }
     }
 
-    pub fn set_value(&mut self, k: impl Into<String>, v: Value) {
+    /// Set a binary field (replaces a JSON field of the same name)
+    pub fn set_bytes(&mut self, k: impl Into<String>, v: impl Into<Vec<u8>>) {
+        let k = k.into();
         if let Value::Object(ref mut map) = self.0 {
-            map.insert(k.into(), v);
+            map.remove(&k);
         }
+        self.1.insert(k, v.into());
     }
 
+    /// Remove a field; a removed binary field is dropped (see remove_bytes)
     pub fn remove(&mut self, k: &str) -> Option<Value> {
+        self.1.remove(k);
         if let Value::Object(ref mut map) = self.0 {
             map.remove(k)
         } else {
@@ -128,15 +147,99 @@ impl Sample {
         }
     }
 
-    /// Deterministic "random" in [0,1)
-    pub fn rand01(&self, id_key: &str, seed: u64) -> f64 {
-        use xxhash_rust::xxh3::xxh3_64_with_seed;
+    pub fn remove_bytes(&mut self, k: &str) -> Option<Vec<u8>> {
+        self.1.remove(k)
+    }
 
-        let id = self.get_str(id_key).unwrap_or("");
-        let h = xxh3_64_with_seed(id.as_bytes(), seed);
-        // top 53 bits -> f64
-        let v = h >> 11;
-        (v as f64) * (1.0 / ((1u64 << 53) as f64))
+    // --- dot paths (nested fields) ---
+    /// Get a nested value by dot path, e.g. "meta.source.url" or "tags.0" (array index)
+    /// A top-level field whose name contains the dots takes precedence over traversal.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        if let Some(value) = self.0.get(path) {
+            return Some(value);
+        }
+        path.split('.')
+            .try_fold(&self.0, |value, segment| match value {
+                Value::Object(map) => map.get(segment),
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Mutable reference to a nested value by dot path (see get_path)
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        if self.0.get(path).is_some() {
+            return self.0.get_mut(path);
+        }
+        path.split('.')
+            .try_fold(&mut self.0, |value, segment| match value {
+                Value::Object(map) => map.get_mut(segment),
+                Value::Array(items) => items.get_mut(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Set a nested value by dot path, creating missing intermediate objects
+    /// Fails if an intermediate value exists but is not an object.
+    pub fn set_path(&mut self, path: &str, v: Value) -> crate::Result<()> {
+        let (parent_path, last) = match path.rsplit_once('.') {
+            Some(split) if self.0.get(path).is_none() => split,
+            _ => {
+                self.set_value(path, v);
+                return Ok(());
+            }
+        };
+
+        let mut current = &mut self.0;
+        for segment in parent_path.split('.') {
+            current = match current {
+                Value::Object(map) => map
+                    .entry(segment)
+                    .or_insert_with(|| Value::Object(serde_json::Map::new())),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot set '{}': '{}' is not an object",
+                        path,
+                        segment
+                    ))
+                }
+            };
+        }
+        match current {
+            Value::Object(map) => {
+                map.insert(last.to_string(), v);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!(
+                "Cannot set '{}': '{}' is not an object",
+                path,
+                parent_path
+            )),
+        }
+    }
+
+    /// Remove a nested value by dot path (see get_path)
+    pub fn remove_path(&mut self, path: &str) -> Option<Value> {
+        match path.rsplit_once('.') {
+            Some((parent_path, last)) if self.0.get(path).is_none() => {
+                match self.get_path_mut(parent_path)? {
+                    Value::Object(map) => map.remove(last),
+                    _ => None,
+                }
+            }
+            _ => self.remove(path),
+        }
+    }
+
+    /// Fields added, removed and changed from this sample to `other`
+    pub fn diff(&self, other: &Sample) -> crate::SampleDiff {
+        crate::SampleDiff::between(self, other)
+    }
+
+    /// Deterministic "random" in [0,1) from a string id field (missing or non-string ids hash
+    /// as ""); see `sampling::rand01` for other ids and id-less samples
+    pub fn rand01(&self, id_key: &str, seed: u64) -> f64 {
+        crate::sampling::hash01(self.get_str(id_key).unwrap_or("").as_bytes(), seed)
     }
 }
 
@@ -0,0 +1,154 @@
+//! Deterministic sampling helpers shared by sampling operators
+//!
+//! Decisions are derived from a hash of the sample (its id field, or its content) and a seed
+//! rather than from a random stream, so they do not depend on the order samples arrive in or
+//! how they are split across workers, and the same seed reproduces the same subset.
+
+use crate::{Result, Sample, Value};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Uniform value in [0, 1) derived from a key and a seed (xxh3)
+pub fn hash01(key: &[u8], seed: u64) -> f64 {
+    let h = xxhash_rust::xxh3::xxh3_64_with_seed(key, seed);
+    // top 53 bits -> f64
+    ((h >> 11) as f64) * (1.0 / ((1u64 << 53) as f64))
+}
+
+/// Uniform value in [0, 1) for a sample: hashes the `id_col` field (dot path; strings by their
+/// bytes, other values by their JSON text), or the whole sample when `id_col` is None
+/// Errors if the id field is missing or null.
+pub fn rand01(sample: &Sample, id_col: Option<&str>, seed: u64) -> Result<f64> {
+    match id_col {
+        Some(col) => match sample.get_path(col) {
+            Some(Value::String(id)) => Ok(hash01(id.as_bytes(), seed)),
+            Some(Value::Null) | None => Err(anyhow::anyhow!("Missing id field: {}", col)),
+            Some(other) => Ok(hash01(other.to_string().as_bytes(), seed)),
+        },
+        // Object keys are sorted, so equal samples hash equally
+        None => Ok(hash01(&serde_json::to_vec(sample.as_value())?, seed)),
+    }
+}
+
+/// Probability of keeping a sample with the given score: `score * scale`, clamped to [0, 1]
+/// (NaN gives 0)
+pub fn keep_probability(score: f64, scale: f64) -> f64 {
+    let p = score * scale;
+    if p.is_nan() {
+        0.0
+    } else {
+        p.clamp(0.0, 1.0)
+    }
+}
+
+/// Fixed-size sample of a stream
+///
+/// Each offered item comes with a uniform value `u` in [0, 1) (from `hash01` / `rand01`), and the
+/// reservoir keeps the `capacity` items with the smallest keys: `u` for `offer`, or
+/// `-ln(1 - u) / weight` for `offer_weighted` (weighted sampling without replacement,
+/// Efraimidis-Spirakis). The result only depends on the items and their `u`, not on the order
+/// they are offered in, so `merge` is associative and commutative: reservoirs filled from parts
+/// of a stream merge into the reservoir of the whole stream. Use one kind of offer per reservoir.
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+    capacity: usize,
+    seen: u64,
+    items: BinaryHeap<Entry<T>>, // Max-heap on key: the top is evicted first
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    key: f64,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key.total_cmp(&other.key) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.total_cmp(&other.key)
+    }
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
+    /// Offer an item with uniform value `u`; returns whether it is (currently) kept
+    pub fn offer(&mut self, u: f64, item: T) -> bool {
+        self.seen += 1;
+        self.insert(u, item)
+    }
+
+    /// Offer an item with a weight (items with larger weights are more likely to be kept);
+    /// items with a weight that is not positive are never kept
+    pub fn offer_weighted(&mut self, u: f64, weight: f64, item: T) -> bool {
+        self.seen += 1;
+        if weight.is_nan() || weight <= 0.0 {
+            return false;
+        }
+        self.insert(-(1.0 - u).ln() / weight, item)
+    }
+
+    fn insert(&mut self, key: f64, item: T) -> bool {
+        if self.items.len() < self.capacity {
+            self.items.push(Entry { key, item });
+            return true;
+        }
+        match self.items.peek() {
+            Some(largest) if key < largest.key => {
+                self.items.pop();
+                self.items.push(Entry { key, item });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add the items of another reservoir with the same capacity (e.g. another worker's)
+    pub fn merge(&mut self, other: Reservoir<T>) {
+        self.seen += other.seen;
+        for entry in other.items {
+            self.insert(entry.key, entry.item);
+        }
+    }
+
+    /// Number of items offered so far (including merged reservoirs)
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Kept items, by increasing key
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| entry.item)
+            .collect()
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{ColumnSpec, Context, OpError, Operator, Result, Sample};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Operator that keeps state across samples (running counters, dedup sets, vocabulary)
+///
+/// The state is kept outside the operator, in one `StateHandle` shared by every worker of the
+/// step: a sample sees what all the samples processed before it recorded, whichever worker
+/// processed them, so a parallel run drops the same duplicates as a sequential one. The state
+/// is built in `open_state` (e.g. loading an on-disk index), published in `finish_state` once
+/// the input is exhausted and released in `close_state`, also when the run fails. Every hook
+/// receives the run's Context, so it can record metrics.
+pub trait StatefulOperator: Send + Sync {
+    type State: Send + Sync;
+
+    /// State of a new run
+    fn open_state(&mut self, ctx: &Context) -> Result<Self::State>;
+
+    /// Process one sample with the shared state
+    /// Take the write lock for the whole check-and-update, so concurrent samples don't both
+    /// miss each other.
+    fn process_with_state(
+        &self,
+        state: &StateHandle<Self::State>,
+        sample: Sample,
+        ctx: &Context,
+    ) -> Result<Option<Sample>>;
+
+    /// See Operator::columns
+    fn columns(&self) -> Option<ColumnSpec> {
+        None
+    }
+
+    /// Called once every sample went through, before `close_state` (e.g. to save the state);
+    /// the samples returned run through the following steps
+    fn finish_state(
+        &self,
+        _state: &StateHandle<Self::State>,
+        _ctx: &Context,
+    ) -> Result<Vec<Sample>> {
+        Ok(Vec::new())
+    }
+
+    /// Called at the end of the run, also when it failed (e.g. to discard a partial index)
+    fn close_state(&mut self, _state: &mut Self::State) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// State of a StatefulOperator, shared by all workers of the run
+///
+/// Cloning is cheap and clones share the same state.
+pub struct StateHandle<S> {
+    state: Arc<RwLock<S>>,
+}
+
+impl<S> StateHandle<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    /// Shared access, for lookups that do not update the state
+    pub fn read(&self) -> RwLockReadGuard<'_, S> {
+        self.state.read().unwrap()
+    }
+
+    /// Exclusive access
+    pub fn write(&self) -> RwLockWriteGuard<'_, S> {
+        self.state.write().unwrap()
+    }
+}
+
+impl<S> Clone for StateHandle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Adapter running a StatefulOperator as a regular Operator
+///
+/// The state is created in `open`, so every run starts from `open_state`.
+pub struct Stateful<T: StatefulOperator> {
+    operator: T,
+    state: Option<StateHandle<T::State>>,
+}
+
+impl<T: StatefulOperator> Stateful<T> {
+    pub fn new(operator: T) -> Self {
+        Self {
+            operator,
+            state: None,
+        }
+    }
+
+    pub fn operator(&self) -> &T {
+        &self.operator
+    }
+
+    /// State of the current run, None before `open` and after `close`
+    pub fn state(&self) -> Option<&StateHandle<T::State>> {
+        self.state.as_ref()
+    }
+}
+
+impl<T: StatefulOperator> Operator for Stateful<T> {
+    fn open(&mut self, ctx: &Context) -> Result<()> {
+        self.state = Some(StateHandle::new(self.operator.open_state(ctx)?));
+        Ok(())
+    }
+
+    fn process_with_context(&self, sample: Sample, ctx: &Context) -> Result<Option<Sample>> {
+        let state = self
+            .state
+            .as_ref()
+            .ok_or_else(|| OpError::fatal("Stateful operator used before open"))?;
+        self.operator.process_with_state(state, sample, ctx)
+    }
+
+    fn columns(&self) -> Option<ColumnSpec> {
+        self.operator.columns()
+    }
+
+    fn finish(&self, ctx: &Context) -> Result<Vec<Sample>> {
+        match &self.state {
+            Some(state) => self.operator.finish_state(state, ctx),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn close(&mut self) -> Result<()> {
+        match self.state.take() {
+            Some(state) => self.operator.close_state(&mut state.write()),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Helpers for unit-testing operators
+//!
+//! ```ignore
+//! use fdf_sdk::testing::{build_operator, run_operator, sample};
+//! use serde_json::json;
+//!
+//! let mut op = build_operator(register, "text.len_filter", "text_col: text\nlower_bound: 3")?;
+//! let outputs = run_operator(op.as_mut(), vec![sample(json!({"text": "hi"})), sample(json!({"text": "hello"}))])?;
+//! assert_eq!(outputs.len(), 1);
+//! fdf_sdk::testing::assert_golden("tests/golden/text_len.jsonl", &outputs);
+//! ```
+
+use crate::{Context, MetricValue, Metrics, Operator, OperatorRegistry, Result, Sample, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Environment variable that makes `assert_golden` rewrite golden files instead of comparing
+pub const UPDATE_GOLDEN_ENV: &str = "FDF_UPDATE_GOLDEN";
+
+/// Sample from a JSON object, e.g. `sample(json!({"text": "hello"}))`
+/// Panics if `value` is not an object.
+pub fn sample(value: Value) -> Sample {
+    Sample::from_value(value).expect("test sample must be a JSON object")
+}
+
+/// Samples from JSON objects
+pub fn samples(values: impl IntoIterator<Item = Value>) -> Vec<Sample> {
+    values.into_iter().map(sample).collect()
+}
+
+/// Builder for samples with nested or binary fields
+#[derive(Default)]
+pub struct SampleBuilder {
+    sample: Sample,
+}
+
+impl SampleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.sample.set_value(key, value.into());
+        self
+    }
+
+    /// Set a dot path, creating intermediate objects
+    pub fn path(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.sample
+            .set_path(path, value.into())
+            .expect("test sample path must be settable");
+        self
+    }
+
+    pub fn bytes(mut self, key: &str, bytes: impl Into<Vec<u8>>) -> Self {
+        self.sample.set_bytes(key, bytes);
+        self
+    }
+
+    pub fn build(self) -> Sample {
+        self.sample
+    }
+}
+
+/// Build an operator from a YAML config, using a module's `register` function
+pub fn build_operator(
+    register: impl FnOnce(&mut OperatorRegistry),
+    name: &str,
+    yaml_config: &str,
+) -> Result<Box<dyn Operator>> {
+    let mut registry = OperatorRegistry::new();
+    register(&mut registry);
+    let config: serde_yaml::Value = serde_yaml::from_str(yaml_config)?;
+    registry.build(name, &config)
+}
+
+/// Outcome of `run_operator_with`
+#[derive(Debug)]
+pub struct OperatorRun {
+    pub outputs: Vec<Sample>,
+    pub dropped: usize, // Inputs for which the operator produced no sample
+    pub metrics: BTreeMap<String, MetricValue>,
+}
+
+/// Run an operator over samples the way the engine does (open, flat_map each sample, finish,
+/// close)
+/// and return the produced samples. The first processing error is returned.
+pub fn run_operator(
+    op: &mut dyn Operator,
+    samples: impl IntoIterator<Item = Sample>,
+) -> Result<Vec<Sample>> {
+    Ok(run_operator_with(op, samples, &Context::default())?.outputs)
+}
+
+/// `run_operator` with a given Context (seed, resources), also returning drop counts and metrics
+pub fn run_operator_with(
+    op: &mut dyn Operator,
+    samples: impl IntoIterator<Item = Sample>,
+    ctx: &Context,
+) -> Result<OperatorRun> {
+    let ctx = ctx.with_metrics(Metrics::default());
+    op.open(&ctx)?;
+
+    let mut run = OperatorRun {
+        outputs: Vec::new(),
+        dropped: 0,
+        metrics: BTreeMap::new(),
+    };
+    let mut result = Ok(());
+    for (idx, sample) in samples.into_iter().enumerate() {
+        match op.flat_map(sample, &ctx) {
+            Ok(outputs) if outputs.is_empty() => run.dropped += 1,
+            Ok(outputs) => run.outputs.extend(outputs),
+            Err(e) => {
+                result = Err(e.context(format!("Failed to process sample {}", idx)));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        match op.finish(&ctx) {
+            Ok(outputs) => run.outputs.extend(outputs),
+            Err(e) => result = Err(e.context("Failed to finish")),
+        }
+    }
+
+    // Close even after an error, like the engine
+    let closed = op.close();
+    result?;
+    closed?;
+    run.metrics = ctx.metrics().snapshot();
+    Ok(run)
+}
+
+/// Compare samples with a JSONL golden file (one sample per line, keys sorted, binary fields
+/// as `{"$bytes_hex": "..."}`). A difference or a missing file panics, naming the first
+/// differing line; set FDF_UPDATE_GOLDEN to write the file instead.
+pub fn assert_golden(path: impl AsRef<Path>, samples: &[Sample]) {
+    let path = path.as_ref();
+    let actual: Vec<String> = samples.iter().map(golden_line).collect();
+
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("failed to create golden file directory");
+        }
+        let mut content = actual.join("\n");
+        content.push('\n');
+        std::fs::write(path, content).expect("failed to write golden file");
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "Cannot read golden file {}: {} (set {}=1 to create it)",
+            path.display(),
+            e,
+            UPDATE_GOLDEN_ENV
+        ),
+    };
+    let expected: Vec<&str> = expected.lines().collect();
+    for (line, (expected, actual)) in expected.iter().zip(&actual).enumerate() {
+        assert!(
+            expected == actual,
+            "Golden file {} differs at line {}\n  expected: {}\n  actual:   {}\n(set {}=1 to update)",
+            path.display(),
+            line + 1,
+            expected,
+            actual,
+            UPDATE_GOLDEN_ENV
+        );
+    }
+    assert!(
+        expected.len() == actual.len(),
+        "Golden file {} has {} samples, got {} (set {}=1 to update)",
+        path.display(),
+        expected.len(),
+        actual.len(),
+        UPDATE_GOLDEN_ENV
+    );
+}
+
+fn golden_line(sample: &Sample) -> String {
+    let mut value = sample.as_value().clone();
+    if let Value::Object(map) = &mut value {
+        for (key, bytes) in sample.binary_fields() {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            map.insert(key.clone(), serde_json::json!({ "$bytes_hex": hex }));
+        }
+    }
+    sorted(value).to_string()
+}
+
+/// Value with object keys sorted at every level, so golden lines do not depend on key order
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let entries: BTreeMap<String, Value> =
+                map.into_iter().map(|(k, v)| (k, sorted(v))).collect();
+            Value::Object(entries.into_iter().collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sorted).collect()),
+        other => other,
+    }
+}
+
+/// Check that samples fit a single columnar schema: encode them into an Arrow batch (schema
+/// inferred from the values, as for new columns when writing) and decode them back,
+/// comparing the values. Nulls and missing fields are equivalent, as are 1 and 1.0.
+/// Binary fields are not checked.
+pub fn batch_roundtrip(samples: &[Sample]) -> Result<()> {
+    if samples.is_empty() {
+        return Ok(());
+    }
+    let values: Vec<Value> = samples.iter().map(|s| s.as_value().clone()).collect();
+
+    let schema = arrow::json::reader::infer_json_schema_from_iterator(
+        values.iter().map(Ok::<&Value, arrow::error::ArrowError>),
+    )
+    .map_err(|e| anyhow::anyhow!("Samples do not share a columnar schema: {}", e))?;
+    let mut decoder = arrow::json::ReaderBuilder::new(Arc::new(schema))
+        .with_batch_size(values.len())
+        .build_decoder()?;
+    decoder.serialize(&values)?;
+    let batch = decoder
+        .flush()?
+        .ok_or_else(|| anyhow::anyhow!("No rows decoded"))?;
+
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    writer.write(&batch)?;
+    writer.finish()?;
+    let decoded: Vec<Value> = serde_json::from_slice(&writer.into_inner())?;
+
+    for (idx, (original, decoded)) in values.iter().zip(&decoded).enumerate() {
+        if !equivalent(original, decoded) {
+            return Err(anyhow::anyhow!(
+                "Sample {} changed in the batch round trip: {} became {}",
+                idx,
+                original,
+                decoded
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Panicking form of `batch_roundtrip`
+pub fn assert_batch_roundtrip(samples: &[Sample]) {
+    if let Err(e) = batch_roundtrip(samples) {
+        panic!("{}", e);
+    }
+}
+
+fn equivalent(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equivalent(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => x.keys().chain(y.keys()).all(|k| {
+            equivalent(
+                x.get(k).unwrap_or(&Value::Null),
+                y.get(k).unwrap_or(&Value::Null),
+            )
+        }),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Keeps samples with a string `text` field, uppercased; fails on other types
+    struct Upper {
+        closed: Arc<AtomicBool>,
+    }
+
+    impl Operator for Upper {
+        fn process_with_context(
+            &self,
+            mut sample: Sample,
+            _ctx: &Context,
+        ) -> Result<Option<Sample>> {
+            match sample.get("text").cloned() {
+                None => Ok(None),
+                Some(Value::String(text)) => {
+                    sample.set_value("text", Value::from(text.to_uppercase()));
+                    Ok(Some(sample))
+                }
+                Some(other) => Err(anyhow::anyhow!("Not a string: {}", other)),
+            }
+        }
+
+        fn finish(&self, ctx: &Context) -> Result<Vec<Sample>> {
+            ctx.metrics().increment("finished", 1);
+            Ok(vec![sample(json!({"text": "END"}))])
+        }
+
+        fn close(&mut self) -> Result<()> {
+            self.closed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn golden_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("fdf-golden-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn run_operator_counts_drops_and_finishes() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let mut op = Upper {
+            closed: closed.clone(),
+        };
+        let inputs = samples([json!({"text": "a"}), json!({"id": 1}), json!({"text": "b"})]);
+        let run = run_operator_with(&mut op, inputs, &Context::default()).unwrap();
+
+        let texts: Vec<&Value> = run.outputs.iter().map(|s| &s.as_value()["text"]).collect();
+        assert_eq!(texts, [&json!("A"), &json!("B"), &json!("END")]);
+        assert_eq!(run.dropped, 1);
+        assert!(matches!(
+            run.metrics.get("finished"),
+            Some(MetricValue::Counter { value: 1 })
+        ));
+        assert!(closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn run_operator_closes_after_error() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let mut op = Upper {
+            closed: closed.clone(),
+        };
+        let inputs = samples([json!({"text": "a"}), json!({"text": 1})]);
+        let error = run_operator(&mut op, inputs).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("Failed to process sample 1"));
+        assert!(closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn golden_matches_regardless_of_key_order() {
+        let path = golden_path("match.jsonl");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            "{\"a\":1,\"b\":{\"c\":2,\"d\":3},\"data\":{\"$bytes_hex\":\"00ff\"}}\n",
+        )
+        .unwrap();
+
+        let sample = SampleBuilder::new()
+            .path("b.d", 3)
+            .path("b.c", 2)
+            .field("a", 1)
+            .bytes("data", [0x00, 0xff])
+            .build();
+        assert_golden(&path, &[sample]);
+    }
+
+    #[test]
+    #[should_panic(expected = "differs at line 1")]
+    fn golden_difference_panics() {
+        let path = golden_path("differ.jsonl");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{\"a\":1}\n").unwrap();
+        assert_golden(&path, &[sample(json!({"a": 2}))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot read golden file")]
+    fn missing_golden_panics() {
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            panic!(
+                "Cannot read golden file (skipped: {} is set)",
+                UPDATE_GOLDEN_ENV
+            );
+        }
+        assert_golden(golden_path("missing.jsonl"), &[sample(json!({"a": 1}))]);
+    }
+
+    #[test]
+    fn batch_roundtrip_rejects_mixed_types() {
+        assert!(batch_roundtrip(&samples([json!({"a": 1}), json!({"a": 2.5})])).is_ok());
+        assert!(batch_roundtrip(&samples([json!({"a": 1}), json!({"a": "x"})])).is_err());
+    }
+}